@@ -5,6 +5,7 @@ rm -rvf $SYSTEM_ROOT
 set -e
 mkdir -pv $SYSTEM_ROOT
 mkdir -pv $SYSTEM_ROOT/bin
+mkdir -pv $SYSTEM_ROOT/lib
 mkdir -pv $SYSTEM_ROOT/bin/wolf3D
 mkdir -pv $SYSTEM_ROOT/dev
 mkdir -pv $SYSTEM_ROOT/etc
@@ -15,6 +16,8 @@ mkdir -pv $SYSTEM_ROOT/home/$STANDARD_USER
 mkdir -pv $SYSTEM_ROOT/turbofish
 mkdir -pv $SYSTEM_ROOT/turbofish/mod
 mkdir -pv $SYSTEM_ROOT/root
+mkdir -pv $SYSTEM_ROOT/mnt
+mkdir -pv $SYSTEM_ROOT/mnt/cdrom
 cp -v files/shinit $SYSTEM_ROOT/root/.shinit
 cp -v files/shinit $SYSTEM_ROOT/home/$STANDARD_USER/.shinit
 cp -v files/pulp_fiction.txt $SYSTEM_ROOT/home/$STANDARD_USER
@@ -4,4 +4,5 @@ source ./env.sh
 set -e
 make -C libc clean
 make -C libc
+cp -v libc/ld.so libc/libc.so $SYSTEM_ROOT/lib
 exit 0
\ No newline at end of file
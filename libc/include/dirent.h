@@ -2,22 +2,54 @@
 # define __DIRENT_H__
 
 #include <sys/types.h>
+#include <stdint.h>
 #include <limits.h>
 //It shall also define the structure dirent which shall include the following members:
 
 struct dirent {
 	ino_t  d_ino;                // File serial number. (typedef of unsigned int) 
+	off_t  d_off;                // Position of the next entry, only usable by seekdir
+	unsigned short d_reclen;     // Size of this record
+	unsigned char  d_type;       // Type of the file, one of DT_*
 	char   d_name[NAME_MAX + 1]; // Filename string of entry. (NAME_MAX + '\0')
 };
 
+// The record given by the getdents64 syscall, of variable size:
+// d_name is followed by a '\0' then padded to a multiple of 8 bytes
+struct dirent64 {
+	uint64_t       d_ino;
+	int64_t        d_off;
+	unsigned short d_reclen;
+	unsigned char  d_type;
+	char           d_name[NAME_MAX + 1];
+};
+
+// The values of d_type
+#define DT_UNKNOWN 0
+#define DT_FIFO    1
+#define DT_CHR     2
+#define DT_DIR     4
+#define DT_BLK     6
+#define DT_REG     8
+#define DT_LNK     10
+#define DT_SOCK    12
+
 //The internal format of directories is unspecified.
 
 //The <dirent.h> header shall define the following type:
 
+// Size of the buffer filled by getdents64, it must hold at least the
+// record of a NAME_MAX filename
+#define DIR_BUFFER_SIZE 2048
+
 typedef struct _DIR {
-	size_t			current_offset;
-	size_t			length;
-	struct dirent	*array;
+	int				fd;
+	// The next record to read in buf
+	size_t			offset;
+	// The size of the records in buf
+	size_t			size;
+	struct dirent	entry;
+	char			buf[DIR_BUFFER_SIZE];
 } DIR;
 
 //    A type representing a directory stream. The DIR type may be an incomplete type.
@@ -30,8 +62,7 @@ typedef struct _DIR {
 
 int alphasort(const struct dirent **, const struct dirent **);
 int closedir(DIR *);
-//TODO: check that for coreutils
-/* int dirfd(DIR *); */
+int dirfd(DIR *);
 DIR *fdopendir(int);
 DIR *opendir(const char *);
 struct dirent *readdir(DIR *);
@@ -46,4 +77,7 @@ void seekdir(DIR *, long);
 long telldir(DIR *);
 //[Option End]
 
+// Read the linux_dirent64 records of the open directory fd
+ssize_t getdents64(int fd, void *buf, size_t count);
+
 #endif
@@ -95,6 +95,10 @@
 #define AT_REMOVEDIR		0x200
 //        Remove directory instead of file.
 
+// Linux extension, for fstatat():
+#define AT_EMPTY_PATH		0x1000
+//        An empty pathname designates the file referred to by the file descriptor itself.
+
 //    [ADV] [Option Start] The <fcntl.h> header shall define the following symbolic constants for the advice argument used by posix_fadvise():
 
 #define POSIX_FADV_DONTNEED 4
@@ -57,6 +57,9 @@
 
 # define HOST_NAME_MAX 255
 
+/* Maximum number of iovec structures of a readv() or writev(). */
+# define IOV_MAX 1024
+
  /*
  *     Minimum Acceptable Value: {_POSIX_HOST_NAME_MAX}
  * {IOV_MAX}
@@ -123,6 +126,10 @@
  *     Minimum Acceptable Value: {_POSIX_SYMLOOP_MAX} */
 # define SYMLOOP_MAX 32
 
+/* Number of real-time signals, and of signals queued for a process by sigqueue(). */
+# define RTSIG_MAX 32
+# define SIGQUEUE_MAX 128
+
 /*
  * {TIMER_MAX}
  *     Maximum number of timers per process supported by the implementation.
@@ -185,6 +192,9 @@
 #define PATH_MAX 4096
 
      /* Maximum number of bytes the implementation will store as a pathname in a user-supplied buffer of unspecified size, including the terminating null character. Minimum number the implementation will accept as the maximum number of bytes in a pathname. */
+
+#define PIPE_BUF 4096
+     /* Maximum number of bytes that is guaranteed to be atomic when writing to a pipe. */
 /*
  *     Minimum Acceptable Value: {_POSIX_PATH_MAX}
  *     [XSI] [Option Start] Minimum Acceptable Value: {_XOPEN_PATH_MAX} [Option End]
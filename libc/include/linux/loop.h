@@ -0,0 +1,27 @@
+#ifndef __LINUX_LOOP_H__
+# define __LINUX_LOOP_H__
+
+#include <sys/types.h>
+
+# define LOOP_SET_FD          0x4C00
+# define LOOP_CLR_FD          0x4C01
+# define LOOP_GET_STATUS      0x4C03
+
+# define LO_NAME_SIZE         64
+
+// lo_flags
+# define LO_FLAGS_READ_ONLY   1
+# define LO_FLAGS_PARTSCAN    8
+
+// Status of a loop device, filled by LOOP_GET_STATUS
+struct loop_info {
+	int lo_number;
+	dev_t lo_device;
+	unsigned long lo_inode;
+	dev_t lo_rdevice;
+	int lo_offset;
+	int lo_flags;
+	char lo_name[LO_NAME_SIZE];
+};
+
+#endif /* __LINUX_LOOP_H__ */
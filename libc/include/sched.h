@@ -28,6 +28,14 @@
 #define CLONE_NEWNET         0x40000000	/* New network namespace */
 #define CLONE_IO             0x80000000	/* Clone io context */
 
+/* Scheduling policies */
+#define SCHED_OTHER 0	/* time sharing, weighted by the nice value */
+#define SCHED_FIFO  1	/* real-time, runs until it blocks or yields */
+#define SCHED_RR    2	/* real-time, in turn with the same priority */
+
+/* Or'ed to a policy: the children start as SCHED_OTHER */
+#define SCHED_RESET_ON_FORK 0x40000000
+
 struct sched_param
 {
 	int sched_priority;
@@ -38,4 +46,10 @@ int	clone(int (*fn)(void *), void *child_stack,
 
 int	sched_setscheduler(pid_t pid, int policy,
 		       const struct sched_param *param);
+int	sched_getscheduler(pid_t pid);
+int	sched_setparam(pid_t pid, const struct sched_param *param);
+int	sched_getparam(pid_t pid, struct sched_param *param);
+int	sched_get_priority_max(int policy);
+int	sched_get_priority_min(int policy);
+int	sched_yield(void);
 #endif
@@ -39,6 +39,8 @@
 #define SIGPWR        30     /* Power failure restart (System V).  */
 #define SIGSYS        31     /* Bad system call.  */
 #define SIGUNUSED     31
+#define SIGRTMIN      32     /* First real-time signal.  */
+#define SIGRTMAX      63     /* Last real-time signal.  */
 
 typedef void (*sighandler_t)(int);
 
@@ -92,6 +94,12 @@ typedef void (*sighandler_t)(int);
 
 #define SA_RESTORER  0x04000000
 
+#define SS_ONSTACK   1
+#define SS_DISABLE   2
+
+#define MINSIGSTKSZ  2048
+#define SIGSTKSZ     8192
+
 //[CX] [Option Start] Some of the functionality described on this reference page extends the ISO C standard. Applications shall define the appropriate feature test macro (see XSH The Compilation Environment ) to enable the visibility of these symbols in this header. [Option End]
 
 //The <signal.h> header shall define the following macros, which shall expand to constant expressions with distinct values that have a type compatible with the second argument to, and the return value of, the signal() function, and whose values shall compare unequal to the address of any declarable function.
@@ -116,7 +124,7 @@ typedef void (*sighandler_t)(int);
 
 #define sig_atomic_t int
 //    Possibly volatile-qualified integer type of an object that can be accessed as an atomic entity, even in the presence of asynchronous interrupts.
-#define sigset_t unsigned int
+#define sigset_t unsigned long long
     //[CX] [Option Start] Integer or structure type of an object used to represent sets of signals. [Option End]
 //pid_t
     //[CX] [Option Start] As described in <sys/types.h>. [Option End]
@@ -170,7 +178,34 @@ struct sigevent {
 
 //The <signal.h> header shall define the stack_t type as a structure, which shall include at least the following members:
 
-typedef int mcontext_t;
+typedef int greg_t;
+
+/* Indexes of the registers in gregs */
+#define REG_GS     0
+#define REG_FS     1
+#define REG_ES     2
+#define REG_DS     3
+#define REG_EDI    4
+#define REG_ESI    5
+#define REG_EBP    6
+#define REG_ESP    7
+#define REG_EBX    8
+#define REG_EDX    9
+#define REG_ECX    10
+#define REG_EAX    11
+#define REG_TRAPNO 12
+#define REG_ERR    13
+#define REG_EIP    14
+#define REG_CS     15
+#define REG_EFL    16
+#define REG_UESP   17
+#define REG_SS     18
+#define NGREG      19
+
+/* The registers of the interrupted thread, restored when the handler returns */
+typedef struct mcontext {
+	greg_t gregs[NGREG];
+} mcontext_t;
 
 typedef struct stack {
 	void     *ss_sp       ;//Stack base or pointer. 
@@ -273,6 +308,29 @@ struct sigaction {
 
 //If si_code is equal to CLD_EXITED, then si_status holds the exit value of the process; otherwise, it is equal to the signal that caused the process to change state. The exit value in si_status shall be equal to the full exit value (that is, the value passed to _exit(), _Exit(), or exit(), or returned from main()); it shall not be limited to the least significant eight bits of the value.
 
+/* Non-signal-specific codes */
+#define SI_USER       0    /* Signal sent by kill().  */
+#define SI_KERNEL     0x80 /* Signal sent by the kernel.  */
+#define SI_QUEUE      -1   /* Signal sent by sigqueue().  */
+#define SI_TIMER      -2   /* Signal generated by expiration of a timer.  */
+#define SI_MESGQ      -3   /* Signal generated by arrival of a message.  */
+#define SI_ASYNCIO    -4   /* Signal generated by completion of an AIO.  */
+
+/* SIGFPE codes */
+#define FPE_INTDIV    1    /* Integer divide by zero.  */
+
+/* SIGSEGV codes */
+#define SEGV_MAPERR   1    /* Address not mapped to object.  */
+#define SEGV_ACCERR   2    /* Invalid permissions for mapped object.  */
+
+/* SIGCHLD codes */
+#define CLD_EXITED    1    /* Child has exited.  */
+#define CLD_KILLED    2    /* Child has terminated abnormally.  */
+#define CLD_DUMPED    3    /* Child has terminated abnormally and created a core file.  */
+#define CLD_TRAPPED   4    /* Traced child has trapped.  */
+#define CLD_STOPPED   5    /* Child has stopped.  */
+#define CLD_CONTINUED 6    /* Stopped child has continued.  */
+
 //Band event for POLL_IN, POLL_OUT, or POLL_MSG.
 
 //For some implementations, the value of si_addr may be inaccurate.
@@ -336,7 +394,7 @@ int    sigwaitinfo(const sigset_t *restrict, siginfo_t *restrict);
 
 //[CX] [Option Start] Inclusion of the <signal.h> header may make visible all symbols from the <time.h> header. [Option End]
 
-//TODO: check NON POSIX
-#define NSIG 42
+/* The number of signals, the null signal included */
+#define NSIG 64
 
 #endif
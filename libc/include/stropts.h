@@ -1,7 +1,10 @@
 #ifndef __STROPTS_H__
 # define __STROPTS_H__
 
+# define TIOCSCTTY            0x540E
 # define TIOCGWINSZ           0x5413
+# define FIONREAD             0x541B
+# define TIOCNOTTY            0x5422
 
 # define RAW_SCANCODE_MODE    0x1
 # define GET_FRAME_BUFFER_PTR 0x3
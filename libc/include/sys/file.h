@@ -0,0 +1,12 @@
+#ifndef __SYS_FILE_H__
+# define __SYS_FILE_H__
+
+/* Operations of flock() */
+# define LOCK_SH 1 /* shared lock */
+# define LOCK_EX 2 /* exclusive lock */
+# define LOCK_NB 4 /* don't block when locking */
+# define LOCK_UN 8 /* unlock */
+
+int flock(int fd, int operation);
+
+#endif
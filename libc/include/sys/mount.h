@@ -1,6 +1,14 @@
 #ifndef __SYS_MOUNT_H__
 #define __SYS_MOUNT_H__
 
+/* Mount flags */
+#define MS_RDONLY	1	/* Mount read-only */
+#define MS_NOSUID	2	/* Ignore the set-user-ID and set-group-ID bits */
+#define MS_NODEV	4	/* Disallow access to the device files */
+#define MS_NOEXEC	8	/* Disallow program execution */
+#define MS_REMOUNT	32	/* Alter the flags of a mounted filesystem */
+#define MS_BIND		4096	/* Make a file or a directory visible elsewhere */
+
 int mount(const char *source, const char *target,
 		  const char *filesystemtype, unsigned long mountflags,
 		  const void *data);
@@ -0,0 +1,18 @@
+#ifndef __SYS_PERSONALITY_H__
+# define __SYS_PERSONALITY_H__
+
+/* This file is directly taken (in parts) from GNU/linux. */
+
+/* Flags which can be or'ed in the persona */
+# define ADDR_NO_RANDOMIZE	0x0040000	/* disable the randomization of the address space layout */
+
+/* The only execution domain */
+# define PER_LINUX		0x0000
+
+/* Set the execution domain of the process to `persona`, or only
+ * return it when `persona` is 0xffffffff. Returns the previous
+ * persona. The persona is inherited by fork() and kept across
+ * execve(). */
+int personality(unsigned long persona);
+
+#endif /* __SYS_PERSONALITY_H__ */
@@ -14,7 +14,7 @@
 //
 //The <sys/resource.h> header shall define the following type through typedef:
 //
-typedef int rlim_t;
+typedef unsigned long rlim_t;
 //    Unsigned integer type used for limit values.
 //
 //The <sys/resource.h> header shall define the following symbolic constants, which shall have values suitable for use in #if preprocessing directives:
@@ -30,10 +30,12 @@ typedef int rlim_t;
 //
 //The <sys/resource.h> header shall define the following symbolic constants as possible values of the who parameter of getrusage():
 //
-#define RUSAGE_SELF 42
+#define RUSAGE_SELF 0
 //    Returns information about the current process.
-#define RUSAGE_CHILDREN 42
+#define RUSAGE_CHILDREN -1
 //    Returns information about children of the current process.
+#define RUSAGE_THREAD 1
+//    Returns information about the calling thread (non-POSIX).
 //
 //The <sys/resource.h> header shall define the rlimit structure, which shall include at least the following members:
 //
@@ -47,6 +49,20 @@ struct rlimit {
 struct rusage {
 	struct timeval ru_utime; //  User time used.
 	struct timeval ru_stime; //  System time used.
+	long ru_maxrss;          //  Maximum resident set size, in kilobytes.
+	long ru_ixrss;           //  Integral shared memory size (unused).
+	long ru_idrss;           //  Integral unshared data size (unused).
+	long ru_isrss;           //  Integral unshared stack size (unused).
+	long ru_minflt;          //  Page faults serviced without any I/O.
+	long ru_majflt;          //  Page faults serviced with I/O.
+	long ru_nswap;           //  Swaps (unused).
+	long ru_inblock;         //  Block input operations (unused).
+	long ru_oublock;         //  Block output operations (unused).
+	long ru_msgsnd;          //  IPC messages sent (unused).
+	long ru_msgrcv;          //  IPC messages received (unused).
+	long ru_nsignals;        //  Signals received (unused).
+	long ru_nvcsw;           //  Voluntary context switches.
+	long ru_nivcsw;          //  Involuntary context switches.
 };
 //
 //The <sys/resource.h> header shall define the timeval structure as described in <sys/time.h>.
@@ -67,6 +83,8 @@ struct rusage {
 //    Limit on stack size.
 #define RLIMIT_AS 6
 //    Limit on address space size.
+#define RLIM_NLIMITS 7
+//    Number of resources.
 //
 //The following shall be declared as functions and may also be defined as macros. Function prototypes shall be provided.
 
@@ -17,6 +17,7 @@
 #define __SENDTO      11
 #define __RECVFROM    12
 #define __SHUTDOWN    13
+#define __ACCEPT4     18
 
 /*
  * sun_family
@@ -38,6 +39,12 @@
 #define SOCK_DGRAM 2  // Connectionless
 #define SOCK_SEQPACKET 3  // Connection-oriented
 
+/*
+ * flags of type and of accept4()
+ */
+#define SOCK_NONBLOCK 00004000 // O_NONBLOCK on the new file descriptor
+#define SOCK_CLOEXEC  00100000 // FD_CLOEXEC on the new file descriptor
+
 struct sockaddr;      // Opaque pointer to avoid compilation errors or warnings
 
 typedef size_t socklen_t;
@@ -47,6 +54,7 @@ int bind(int sockfd, const struct sockaddr *addr, socklen_t addrlen);
 int connect(int sockfd, const struct sockaddr *addr, socklen_t addrlen);
 int listen(int sockfd, int backlog);
 int accept(int sockfd, struct sockaddr *addr, socklen_t *addrlen);
+int accept4(int sockfd, struct sockaddr *addr, socklen_t *addrlen, int flags);
 ssize_t send(int sockfd, const void *buf, size_t len, int flags);
 ssize_t recv(int sockfd, void *buf, size_t len, int flags);
 ssize_t sendto(int sockfd, const void *buf, size_t len, int flags, const struct sockaddr *dest_addr, socklen_t addrlen);
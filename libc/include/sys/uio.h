@@ -0,0 +1,24 @@
+#ifndef __UIO_H__
+# define __UIO_H__
+
+#include <sys/types.h>
+
+//The <sys/uio.h> header shall define the iovec structure, which shall include at least the following members:
+
+struct iovec {
+	void   *iov_base; // Base address of a memory region for input or output.
+	size_t  iov_len;  // The size of the memory pointed to by iov_base.
+};
+
+//The <sys/uio.h> header uses the iovec structure for scatter/gather I/O.
+
+//The following shall be declared as functions and may also be defined as macros. Function prototypes shall be provided.
+
+ssize_t readv(int, const struct iovec *, int);
+ssize_t writev(int, const struct iovec *, int);
+
+// Linux extensions, readv() and writev() at an offset which is not used nor moved
+ssize_t preadv(int, const struct iovec *, int, off_t);
+ssize_t pwritev(int, const struct iovec *, int, off_t);
+
+#endif
@@ -35,6 +35,11 @@
 #define WUNTRACED     0x2 /* tell about stopped, untraced children */
 #define WCONTINUED    0x4 /* tell me about continued */
 
+/* Option bits of waitid, WCONTINUED and WNOHANG are also accepted */
+#define WSTOPPED      0x2  /* report the stopped children, as WUNTRACED */
+#define WEXITED       0x8  /* report the terminated children */
+#define WNOWAIT       0x10 /* leave the child in a waitable state */
+
 /*
  * returns the exit status of the child.  This consists of the least
  * significant 8 bits of the status argument that the child specified
@@ -79,7 +84,7 @@
 /*
  * Since turbofish 0.5: returns true if the child process was resumed by delivery of SIGCONT
  */
-#define WIFCONTINUED(wstatus)   ((status) & CONTINUED_STATUS_BIT)
+#define WIFCONTINUED(status)    ((status) & CONTINUED_STATUS_BIT)
 
 // The <sys/wait.h> header shall define the following symbolic constants as possible values for the options argument to waitid():
 //
@@ -0,0 +1,20 @@
+#ifndef __SYS_XATTR_H__
+# define __SYS_XATTR_H__
+
+# include <sys/types.h>
+
+/* Flags of setxattr() */
+# define XATTR_CREATE  0x1 /* set value, fail if attr already exists */
+# define XATTR_REPLACE 0x2 /* set value, fail if attr does not exist */
+
+/* Maximum sizes of an extended attribute name, value and list */
+# define XATTR_NAME_MAX 255
+# define XATTR_SIZE_MAX 65536
+# define XATTR_LIST_MAX 65536
+
+ssize_t getxattr(const char *path, const char *name, void *value, size_t size);
+int     setxattr(const char *path, const char *name, const void *value, size_t size, int flags);
+ssize_t listxattr(const char *path, char *list, size_t size);
+int     removexattr(const char *path, const char *name);
+
+#endif
@@ -30,13 +30,17 @@ extern "C" {
 
 int          dup(int);
 int          dup2(int, int);
+int          dup3(int, int, int);
 int          execve(const char *, char *const *, char *const *);
 pid_t        fork(void);
+pid_t        vfork(void);
 int          pipe(int fd[2]);
+int          pipe2(int fd[2], int);
 
 int          access(const char *, int);
 unsigned     alarm(unsigned);
 int          chdir(const char *);
+int          chroot(const char *);
 int          chown(const char *, uid_t, gid_t);
 int          close(int);
 size_t       confstr(int, char *, size_t);
@@ -119,6 +123,11 @@ pid_t        setpgrp(void);
 int          setregid(gid_t, gid_t);
 int          setreuid(uid_t, uid_t);
 //[Option End]
+// Linux specific
+int          setresuid(uid_t, uid_t, uid_t);
+int          getresuid(uid_t *, uid_t *, uid_t *);
+int          setresgid(gid_t, gid_t, gid_t);
+int          getresgid(gid_t *, gid_t *, gid_t *);
 pid_t        setsid(void);
 int          setuid(uid_t);
 unsigned     sleep(unsigned);
@@ -130,6 +139,8 @@ int          symlinkat(const char *, int, const char *);
 //[XSI][Option Start]
 void         sync(void);
 //[Option End]
+// Linux specific
+int          syncfs(int);
 long	     sysconf(int name);
 pid_t        tcgetpgrp(int);
 int          tcsetpgrp(int, pid_t);
@@ -28,6 +28,8 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define FSTAT        28
 #define UTIME        30
 #define ACCESS       33
+#define NICE         34
+#define SYNC         36
 #define KILL         37
 #define RENAME       38
 #define MKDIR        39
@@ -45,12 +47,18 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define SIGNAL       48
 #define SETPGID      57
 #define UMASK	     60
+#define CHROOT       61
 #define DUP2         63
 #define GETPPID      64
 #define GETPGRP      65
+#define SETSID       66
 #define SIGACTION    67
 #define SIGSUSPEND   72
+#define SIGPENDING   73
 #define SETHOSTNAME  74
+#define SETRLIMIT    75
+#define GETRLIMIT    76
+#define GETRUSAGE    77
 #define GETGROUPS    80
 #define SETGROUPS    81
 #define SYMLINK      83
@@ -61,20 +69,69 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define MUNMAP       91
 #define FCHMOD	     94
 #define FCHOWN	     95
+#define SETPRIORITY  97
 #define GETTIMEOFDAY 96
 #define SOCKETCALL  102
 #define WAIT4       114
+#define FSYNC       118
 #define CLONE       120
 #define MPROTECT    125
 #define SIGPROCMASK 126
 #define GETPGID     132
+#define FCHDIR      133
+#define PERSONALITY 136
 #define STATFS	    137
 #define FSTATFS	    138
+#define FLOCK       143
+#define READV       145
+#define WRITEV      146
+#define GETSID      147
+#define FDATASYNC   148
+#define SCHED_SETPARAM 154
+#define SCHED_GETPARAM 155
+#define SCHED_SETSCHEDULER 156
+#define SCHED_GETSCHEDULER 157
+#define SCHED_YIELD 158
+#define SCHED_GET_PRIORITY_MAX 159
+#define SCHED_GET_PRIORITY_MIN 160
 #define NANOSLEEP   162
+#define SETRESUID   164
+#define GETRESUID   165
+#define SETRESGID   170
+#define GETRESGID   171
+#define RT_SIGRETURN 173
+#define SIGTIMEDWAIT 177
+#define SIGQUEUE    178
+#define PREAD64     180
+#define PWRITE64    181
 #define CHOWN       182
 #define GETCWD      183
+#define SIGALTSTACK 186
+#define VFORK       190
+#define TRUNCATE64  193
+#define FTRUNCATE64 194
+#define SETXATTR    226
+#define GETXATTR    229
+#define LISTXATTR   232
+#define REMOVEXATTR 235
 #define SIGRETURN   200
-#define SHUTDOWN    293
+#define GETDENTS64  220
+#define WAITID      284
+#define INOTIFY_INIT 291
+#define INOTIFY_ADD_WATCH 292
+#define INOTIFY_RM_WATCH 293
+#define OPENAT      295
+#define MKDIRAT     296
+#define FSTATAT     300
+#define UNLINKAT    301
+#define RENAMEAT    302
+#define READLINKAT  305
+#define DUP3        330
+#define PIPE2       331
+#define INOTIFY_INIT1 332
+#define PREADV      333
+#define PWRITEV     334
+#define SYNCFS      344
 
 #define TEST            0x80000000
 #define STACK_OVERFLOW  0x80000001
@@ -86,9 +143,11 @@ int _user_syscall(u32 syscall_number, u32 args_len, ...);
 #define SETEGID         0x80000007
 #define SETEUID         0x80000008
 #define ISATTY          0x80000009
-#define OPENDIR         0x80000010
 #define IS_STR_VALID    0x80000011
 #define GETHOSTNAME	0x80000012
+#define SHUTDOWN        0x80000013
+// 96 is gettimeofday
+#define GETPRIORITY     0x80000014
 
 /*
  * Module Kernel specific
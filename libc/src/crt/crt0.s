@@ -4,6 +4,9 @@
 
 .global _start
 _start:
+	# Save the auxiliary vector.
+	mov [__auxv], edx
+
 	# Set up end of the stack frame linked list.
 	push 0x0
 	push 0x0
@@ -16,7 +16,13 @@ _init:
 
 	call basic_constructor
 	add esp, 12
+
+	# The init array of the program, the ones of the shared libraries
+	# are run by ld.so.
+	push offset __init_array_end
+	push offset __init_array_start
 	call call_init_array_ctors
+	add esp, 8
 
 	# gcc will nicely put the contents of crtbegin.o\'s .init section here.
 
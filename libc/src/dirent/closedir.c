@@ -2,7 +2,7 @@
 #include <dirent.h>
 #include <errno.h>
 #include <stdlib.h>
-#include <sys/mman.h>
+#include <unistd.h>
 
 // The closedir() function shall close the directory stream referred
 // to by the argument dirp. Upon return, the value of dirp may no
@@ -13,9 +13,7 @@
 int closedir(DIR *dirp)
 {
 	TRACE
-	if (munmap(dirp->array, dirp->length * sizeof(struct dirent)) < 0) {
-		return -1;
-	}
+	int ret = close(dirp->fd);
 	free(dirp);
-	return 0;
+	return ret;
 }
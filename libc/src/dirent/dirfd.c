@@ -0,0 +1,12 @@
+#include <ltrace.h>
+#include <dirent.h>
+
+// The dirfd() function shall return a file descriptor referring to
+// the same directory as the dirp argument. This file descriptor shall
+// be closed by a call to closedir().
+
+int dirfd(DIR *dirp)
+{
+	TRACE
+	return dirp->fd;
+}
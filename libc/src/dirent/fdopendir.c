@@ -0,0 +1,39 @@
+#include <ltrace.h>
+#include <dirent.h>
+#include <errno.h>
+#include <stdlib.h>
+#include <sys/stat.h>
+
+// The fdopendir() function shall be equivalent to the opendir()
+// function except that the directory is specified by a file
+// descriptor rather than by a name. The file offset associated with
+// the file descriptor at the time of the call determines which
+// entries are returned.
+//
+// Upon successful return from fdopendir(), the file descriptor is
+// under the control of the system, and if any attempt is made to
+// close the file descriptor, or to modify the state of the associated
+// description, other than by means of closedir(), readdir(),
+// readdir_r(), rewinddir(), or seekdir(), the behavior is undefined.
+
+DIR *fdopendir(int fd)
+{
+	TRACE
+	struct stat st;
+
+	if (fstat(fd, &st) < 0) {
+		return NULL;
+	}
+	if (!S_ISDIR(st.st_mode)) {
+		errno = ENOTDIR;
+		return NULL;
+	}
+	DIR *dir = (DIR *)malloc(sizeof(DIR));
+	if (dir == NULL) {
+		return NULL;
+	}
+	dir->fd = fd;
+	dir->offset = 0;
+	dir->size = 0;
+	return dir;
+}
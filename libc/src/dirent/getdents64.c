@@ -0,0 +1,24 @@
+#include <ltrace.h>
+#include <dirent.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/// The getdents64() system call reads several linux_dirent64
+/// structures from the directory referred to by the open file
+/// descriptor fd into the buffer pointed to by buf. The argument
+/// count specifies the size of that buffer.
+///
+/// On success, the number of bytes read is returned. On end of
+/// directory, 0 is returned. On error, -1 is returned, and errno is
+/// set appropriately.
+/// [EINVAL]
+///     Result buffer is too small.
+/// [ENOTDIR]
+///     File descriptor does not refer to a directory.
+
+ssize_t getdents64(int fd, void *buf, size_t count)
+{
+	TRACE
+	int ret = _user_syscall(GETDENTS64, 3, fd, buf, count);
+	set_errno_and_return(ret);
+}
@@ -2,7 +2,8 @@
 #include <dirent.h>
 #include <errno.h>
 #include <stdlib.h>
-#include <user_syscall.h>
+#include <fcntl.h>
+#include <unistd.h>
 
 // The opendir() function shall open a directory stream corresponding
 // to the directory named by the dirname argument. The directory
@@ -13,20 +14,19 @@
 DIR *opendir(const char *dirname)
 {
 	TRACE
-	DIR *dir = (DIR *)malloc(sizeof(DIR));
-	if (dir == NULL) {
-		return NULL;
-	}
-	int ret = _user_syscall(OPENDIR, 2, dirname, dir);
+	int fd = open(dirname, O_RDONLY | O_DIRECTORY);
 	/*
 	 * The opendir() and fdopendir() functions return a pointer to the directory stream.
 	 * On error, NULL is returned, and errno is set appropriately.
 	 */
-	if (ret < 0) {
-		errno = -ret;
-		free(dir);
+	if (fd < 0) {
 		return NULL;
-	} else {
-		return dir;
 	}
+	DIR *dir = fdopendir(fd);
+	if (dir == NULL) {
+		int err = errno;
+		close(fd);
+		errno = err;
+	}
+	return dir;
 }
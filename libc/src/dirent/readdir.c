@@ -2,6 +2,7 @@
 #include <dirent.h>
 #include <errno.h>
 #include <stdlib.h>
+#include <string.h>
 
 // The readdir() function shall return a pointer to a structure
 // representing the directory entry at the current position in the
@@ -25,11 +26,21 @@ struct dirent *readdir(DIR *dirp)
 	 * If an error occurs, NULL is returned and errno is set appropriately. To distinguish end of stream and from
 	 * an error, set errno to zero before calling readdir() and then check the value of errno if NULL is returned.
 	 */
-	if (dirp->current_offset < dirp->length) {
-		struct dirent *dirent = &(dirp->array[dirp->current_offset]);
-		dirp->current_offset += 1;
-		return dirent;
-	} else {
-		return NULL;
+	if (dirp->offset >= dirp->size) {
+		ssize_t size = getdents64(dirp->fd, dirp->buf, sizeof(dirp->buf));
+		if (size <= 0) {
+			return NULL;
+		}
+		dirp->offset = 0;
+		dirp->size = (size_t)size;
 	}
+	struct dirent64 *record = (struct dirent64 *)&dirp->buf[dirp->offset];
+	dirp->offset += record->d_reclen;
+
+	dirp->entry.d_ino = (ino_t)record->d_ino;
+	dirp->entry.d_off = (off_t)record->d_off;
+	dirp->entry.d_reclen = sizeof(struct dirent);
+	dirp->entry.d_type = record->d_type;
+	strcpy(dirp->entry.d_name, record->d_name);
+	return &dirp->entry;
 }
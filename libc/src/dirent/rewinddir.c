@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <dirent.h>
+#include <unistd.h>
+
+// The rewinddir() function shall reset the position of the directory
+// stream to which dirp refers to the beginning of the directory. It
+// shall also cause the directory stream to refer to the current state
+// of the corresponding directory, as a call to opendir() would have
+// done.
+
+void rewinddir(DIR *dirp)
+{
+	TRACE
+	lseek(dirp->fd, 0, SEEK_SET);
+	dirp->offset = 0;
+	dirp->size = 0;
+}
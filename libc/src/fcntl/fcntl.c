@@ -11,16 +11,24 @@ int fcntl(int fildes, int cmd, ...)
 {
 	TRACE
 	va_list ap;
-	int arg = 0;
+	int ret;
 
 	va_start(ap, cmd);
 	switch (cmd) {
+		case F_GETLK:
+		case F_SETLK:
+		case F_SETLKW:
+			ret = _user_syscall(FCNTL, 3, fildes, cmd, va_arg(ap, struct flock *));
+			break;
 		case F_DUPFD:
+		case F_DUPFD_CLOEXEC:
 		case F_SETFD:
-			arg = va_arg(ap, int);
+			ret = _user_syscall(FCNTL, 3, fildes, cmd, va_arg(ap, int));
+			break;
+		default:
+			ret = _user_syscall(FCNTL, 3, fildes, cmd, 0);
 			break;
 	}
-	int ret = _user_syscall(FCNTL, 3, fildes, cmd, arg);
 	va_end(ap);
 	set_errno_and_return(ret);
 }
@@ -0,0 +1,32 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <fcntl.h>
+#include <errno.h>
+#include <stdarg.h>
+
+/*
+ * The openat() function shall be equivalent to the open() function
+ * except in the case where path specifies a relative path. In this
+ * case the file to be opened is determined relative to the directory
+ * associated with the file descriptor fd instead of the current
+ * working directory. If fd is the special value AT_FDCWD, the current
+ * working directory shall be used.
+ */
+int openat(int fd, const char *path, int oflag, ...)
+{
+	TRACE
+	va_list ap;
+	int arg;
+
+	va_start(ap, oflag);
+	// Get new file stats if found a new file
+	if (oflag & O_CREAT) {
+		arg = va_arg(ap, int);
+	} else {
+		arg = 0;
+	}
+	va_end(ap);
+
+	int ret = _user_syscall(OPENAT, 4, fd, path, oflag, arg);
+	set_errno_and_return(ret);
+}
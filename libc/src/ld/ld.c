@@ -0,0 +1,527 @@
+/*
+ * ld.so, the dynamic linker of the turbofish programs.
+ *
+ * The kernel maps the executable, maps this interpreter and jumps here
+ * (see ld_start.s). The shared objects needed by the program are loaded
+ * from /lib then /usr/lib, all their relocations are done at once (there
+ * is no lazy binding), their constructors are run and the program is
+ * entered at AT_ENTRY.
+ *
+ * Nothing here may use the libc: ld.so is what loads it.
+ */
+
+#include <elf.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <fcntl.h>
+#include <sys/mman.h>
+#include <user_syscall.h>
+
+#define DL_PAGE_SIZE 4096
+#define DL_PAGE_MASK (DL_PAGE_SIZE - 1)
+
+/* The maximum number of loaded objects, the program included */
+#define MAX_DSO 16
+/* The maximum number of program headers of a shared object */
+#define MAX_PHNUM 16
+
+static const char *const search_paths[] = { "/lib/", "/usr/lib/" };
+
+/* The registers given by the kernel to the process, see ld_start.s */
+struct dl_args {
+	int argc;
+	char **argv;
+	char **envp;
+	Elf32_auxv_t *auxv;
+};
+
+typedef void (*init_fn)(int argc, char **argv, char **envp);
+
+struct dso {
+	const char *name;
+	/* The difference between the loaded and the linked addresses */
+	Elf32_Addr base;
+	const Elf32_Phdr *phdr;
+	size_t phnum;
+	const Elf32_Dyn *dynamic;
+	const Elf32_Word *hash;
+	const Elf32_Sym *symtab;
+	const char *strtab;
+	const Elf32_Rel *rel;
+	size_t relsz;
+	const Elf32_Rel *jmprel;
+	size_t pltrelsz;
+	init_fn init;
+	const init_fn *init_array;
+	size_t init_arraysz;
+};
+
+static struct dso dsos[MAX_DSO];
+static size_t dso_count;
+/* The program headers of the shared objects, read from their files */
+static Elf32_Phdr phdrs[MAX_DSO][MAX_PHNUM];
+
+extern const Elf32_Dyn _DYNAMIC[] __attribute__((visibility("hidden")));
+
+int _dl_syscall(int num, ...);
+
+struct mmap_struct {
+	void *addr;
+	size_t length;
+	int prot;
+	int flags;
+	int fd;
+	off_t offset;
+};
+
+/* gcc may emit calls to these two for the struct copies */
+void *memcpy(void *dst, const void *src, size_t n)
+{
+	unsigned char *d = dst;
+	const unsigned char *s = src;
+
+	while (n--)
+		*d++ = *s++;
+	return dst;
+}
+
+void *memset(void *s, int c, size_t n)
+{
+	unsigned char *p = s;
+
+	while (n--)
+		*p++ = (unsigned char)c;
+	return s;
+}
+
+static size_t dl_strlen(const char *s)
+{
+	size_t len = 0;
+
+	while (s[len])
+		len++;
+	return len;
+}
+
+static bool dl_streq(const char *a, const char *b)
+{
+	while (*a && *a == *b) {
+		a++;
+		b++;
+	}
+	return *a == *b;
+}
+
+static bool dl_strchr(const char *s, char c)
+{
+	for (; *s; s++)
+		if (*s == c)
+			return true;
+	return false;
+}
+
+static void dl_puts(const char *s)
+{
+	_dl_syscall(WRITE, 2, s, dl_strlen(s));
+}
+
+static void __attribute__((noreturn)) dl_fatal(const char *what, const char *name)
+{
+	dl_puts("ld.so: ");
+	dl_puts(what);
+	if (name) {
+		dl_puts(": ");
+		dl_puts(name);
+	}
+	dl_puts("\n");
+	_dl_syscall(EXIT, 127);
+	__builtin_unreachable();
+}
+
+static Elf32_Addr page_down(Elf32_Addr addr)
+{
+	return addr & ~DL_PAGE_MASK;
+}
+
+static Elf32_Addr page_up(Elf32_Addr addr)
+{
+	return (addr + DL_PAGE_MASK) & ~DL_PAGE_MASK;
+}
+
+/*
+ * ld.so is linked at 0 and the kernel maps it at AT_BASE: its own
+ * pointers must be adjusted before anything uses them.
+ */
+static void relocate_self(Elf32_Addr base)
+{
+	const Elf32_Rel *rel = NULL;
+	size_t relsz = 0;
+
+	for (const Elf32_Dyn *d = _DYNAMIC; d->d_tag != DT_NULL; d++) {
+		if (d->d_tag == DT_REL)
+			rel = (const Elf32_Rel *)(base + d->d_un.d_ptr);
+		else if (d->d_tag == DT_RELSZ)
+			relsz = d->d_un.d_val;
+	}
+	for (size_t i = 0; i < relsz / sizeof(Elf32_Rel); i++) {
+		if (ELF32_R_TYPE(rel[i].r_info) == R_386_RELATIVE)
+			*(Elf32_Addr *)(base + rel[i].r_offset) += base;
+	}
+}
+
+static void parse_dynamic(struct dso *dso)
+{
+	Elf32_Addr base = dso->base;
+
+	for (const Elf32_Dyn *d = dso->dynamic; d->d_tag != DT_NULL; d++) {
+		switch (d->d_tag) {
+		case DT_HASH:
+			dso->hash = (const Elf32_Word *)(base + d->d_un.d_ptr);
+			break;
+		case DT_SYMTAB:
+			dso->symtab = (const Elf32_Sym *)(base + d->d_un.d_ptr);
+			break;
+		case DT_STRTAB:
+			dso->strtab = (const char *)(base + d->d_un.d_ptr);
+			break;
+		case DT_REL:
+			dso->rel = (const Elf32_Rel *)(base + d->d_un.d_ptr);
+			break;
+		case DT_RELSZ:
+			dso->relsz = d->d_un.d_val;
+			break;
+		case DT_JMPREL:
+			dso->jmprel = (const Elf32_Rel *)(base + d->d_un.d_ptr);
+			break;
+		case DT_PLTRELSZ:
+			dso->pltrelsz = d->d_un.d_val;
+			break;
+		case DT_PLTREL:
+			if (d->d_un.d_val != DT_REL)
+				dl_fatal("unsupported PLT relocations", dso->name);
+			break;
+		case DT_RELA:
+			dl_fatal("unsupported RELA relocations", dso->name);
+		case DT_INIT:
+			dso->init = (init_fn)(base + d->d_un.d_ptr);
+			break;
+		case DT_INIT_ARRAY:
+			dso->init_array = (const init_fn *)(base + d->d_un.d_ptr);
+			break;
+		case DT_INIT_ARRAYSZ:
+			dso->init_arraysz = d->d_un.d_val;
+			break;
+		default:
+			break;
+		}
+	}
+	if (!dso->hash || !dso->symtab || !dso->strtab)
+		dl_fatal("missing symbol tables", dso->name);
+}
+
+static void dl_pread(int fd, void *buf, size_t len, Elf32_Off offset, const char *name)
+{
+	unsigned char *p = buf;
+
+	while (len > 0) {
+		int ret = _dl_syscall(PREAD64, fd, p, len, offset, 0);
+
+		if (ret <= 0)
+			dl_fatal("cannot read", name);
+		p += ret;
+		len -= ret;
+		offset += ret;
+	}
+}
+
+static int open_library(const char *name)
+{
+	char path[PATH_MAX];
+
+	if (dl_strchr(name, '/'))
+		return _dl_syscall(OPEN, name, O_RDONLY | O_CLOEXEC, 0);
+
+	for (size_t i = 0; i < sizeof(search_paths) / sizeof(*search_paths); i++) {
+		size_t dir_len = dl_strlen(search_paths[i]);
+		size_t name_len = dl_strlen(name);
+
+		if (dir_len + name_len >= PATH_MAX)
+			continue;
+		memcpy(path, search_paths[i], dir_len);
+		memcpy(path + dir_len, name, name_len + 1);
+
+		int fd = _dl_syscall(OPEN, path, O_RDONLY | O_CLOEXEC, 0);
+		if (fd >= 0)
+			return fd;
+	}
+	return -1;
+}
+
+/*
+ * Map the shared object `name` in a single anonymous area holding all
+ * its segments, read them in and leave the bss zero-filled.
+ */
+static void load_library(struct dso *dso, Elf32_Phdr *ph, const char *name)
+{
+	Elf32_Ehdr ehdr;
+	int fd = open_library(name);
+
+	if (fd < 0)
+		dl_fatal("cannot find shared library", name);
+	dso->name = name;
+
+	dl_pread(fd, &ehdr, sizeof(ehdr), 0, name);
+	if (ehdr.e_ident[0] != ELFMAG[0] || ehdr.e_ident[1] != ELFMAG[1]
+	    || ehdr.e_ident[2] != ELFMAG[2] || ehdr.e_ident[3] != ELFMAG[3]
+	    || ehdr.e_type != ET_DYN || ehdr.e_machine != EM_386
+	    || ehdr.e_phentsize != sizeof(Elf32_Phdr) || ehdr.e_phnum > MAX_PHNUM)
+		dl_fatal("not a shared library", name);
+	dl_pread(fd, ph, ehdr.e_phnum * sizeof(Elf32_Phdr), ehdr.e_phoff, name);
+	dso->phdr = ph;
+	dso->phnum = ehdr.e_phnum;
+
+	Elf32_Addr low = (Elf32_Addr)-1;
+	Elf32_Addr high = 0;
+	for (size_t i = 0; i < dso->phnum; i++) {
+		if (ph[i].p_type != PT_LOAD)
+			continue;
+		if (page_down(ph[i].p_vaddr) < low)
+			low = page_down(ph[i].p_vaddr);
+		if (page_up(ph[i].p_vaddr + ph[i].p_memsz) > high)
+			high = page_up(ph[i].p_vaddr + ph[i].p_memsz);
+	}
+	if (low >= high)
+		dl_fatal("no loadable segment", name);
+
+	struct mmap_struct m = {
+		.addr = NULL,
+		.length = high - low,
+		.prot = PROT_READ | PROT_WRITE | PROT_EXEC,
+		.flags = MAP_PRIVATE | MAP_ANONYMOUS,
+		.fd = -1,
+		.offset = 0,
+	};
+	Elf32_Addr area = (Elf32_Addr)_dl_syscall(MMAP, &m);
+	if (area & DL_PAGE_MASK)
+		dl_fatal("cannot map", name);
+	dso->base = area - low;
+
+	for (size_t i = 0; i < dso->phnum; i++) {
+		if (ph[i].p_type == PT_LOAD && ph[i].p_filesz > 0)
+			dl_pread(fd, (void *)(dso->base + ph[i].p_vaddr), ph[i].p_filesz,
+				 ph[i].p_offset, name);
+		else if (ph[i].p_type == PT_DYNAMIC)
+			dso->dynamic = (const Elf32_Dyn *)(dso->base + ph[i].p_vaddr);
+	}
+	_dl_syscall(CLOSE, fd);
+
+	if (!dso->dynamic)
+		dl_fatal("no dynamic section", name);
+	parse_dynamic(dso);
+}
+
+static Elf32_Word elf_hash(const char *name)
+{
+	const unsigned char *p = (const unsigned char *)name;
+	Elf32_Word h = 0;
+
+	while (*p) {
+		h = (h << 4) + *p++;
+		Elf32_Word g = h & 0xf0000000;
+		if (g)
+			h ^= g >> 24;
+		h &= ~g;
+	}
+	return h;
+}
+
+static const Elf32_Sym *find_in(const struct dso *dso, const char *name, Elf32_Word hash)
+{
+	Elf32_Word nbucket = dso->hash[0];
+	const Elf32_Word *bucket = dso->hash + 2;
+	const Elf32_Word *chain = bucket + nbucket;
+
+	for (Elf32_Word i = bucket[hash % nbucket]; i != STN_UNDEF; i = chain[i]) {
+		const Elf32_Sym *sym = &dso->symtab[i];
+		unsigned char bind = ELF32_ST_BIND(sym->st_info);
+
+		if (sym->st_shndx != SHN_UNDEF && (bind == STB_GLOBAL || bind == STB_WEAK)
+		    && dl_streq(dso->strtab + sym->st_name, name))
+			return sym;
+	}
+	return NULL;
+}
+
+/*
+ * Look for the definition of `name` in the program then in the shared
+ * objects, in load order. A R_386_COPY relocation looks past the program.
+ */
+static const Elf32_Sym *lookup(const char *name, const struct dso *skip, const struct dso **found)
+{
+	Elf32_Word hash = elf_hash(name);
+
+	for (size_t i = 0; i < dso_count; i++) {
+		if (&dsos[i] == skip)
+			continue;
+		const Elf32_Sym *sym = find_in(&dsos[i], name, hash);
+		if (sym) {
+			*found = &dsos[i];
+			return sym;
+		}
+	}
+	return NULL;
+}
+
+static void relocate(struct dso *dso, const Elf32_Rel *rel, size_t size)
+{
+	for (size_t i = 0; i < size / sizeof(Elf32_Rel); i++) {
+		Elf32_Word type = ELF32_R_TYPE(rel[i].r_info);
+		Elf32_Word sym_index = ELF32_R_SYM(rel[i].r_info);
+		Elf32_Addr *where = (Elf32_Addr *)(dso->base + rel[i].r_offset);
+		const Elf32_Sym *def = NULL;
+		Elf32_Addr value = 0;
+
+		if (type == R_386_NONE)
+			continue;
+		if (sym_index != STN_UNDEF) {
+			const Elf32_Sym *sym = &dso->symtab[sym_index];
+			const char *name = dso->strtab + sym->st_name;
+			const struct dso *def_dso = dso;
+
+			if (ELF32_ST_BIND(sym->st_info) == STB_LOCAL)
+				def = sym;
+			else
+				def = lookup(name, type == R_386_COPY ? dso : NULL, &def_dso);
+			if (def)
+				value = def_dso->base + def->st_value;
+			else if (ELF32_ST_BIND(sym->st_info) != STB_WEAK)
+				dl_fatal("undefined symbol", name);
+		}
+
+		switch (type) {
+		case R_386_32:
+			*where += value;
+			break;
+		case R_386_PC32:
+			*where += value - (Elf32_Addr)where;
+			break;
+		case R_386_GLOB_DAT:
+		case R_386_JMP_SLOT:
+			*where = value;
+			break;
+		case R_386_RELATIVE:
+			*where += dso->base;
+			break;
+		case R_386_COPY:
+			if (def)
+				memcpy(where, (const void *)value, def->st_size);
+			break;
+		default:
+			dl_fatal("unsupported relocation type", dso->name);
+		}
+	}
+}
+
+/* Give back their permissions to the segments written by the relocations */
+static void protect_segments(const struct dso *dso)
+{
+	for (size_t i = 0; i < dso->phnum; i++) {
+		const Elf32_Phdr *ph = &dso->phdr[i];
+
+		if (ph->p_type != PT_LOAD || (ph->p_flags & PF_W))
+			continue;
+		Elf32_Addr start = page_down(dso->base + ph->p_vaddr);
+		Elf32_Addr end = page_up(dso->base + ph->p_vaddr + ph->p_memsz);
+		int prot = PROT_READ | ((ph->p_flags & PF_X) ? PROT_EXEC : 0);
+
+		_dl_syscall(MPROTECT, start, end - start, prot);
+	}
+}
+
+static bool is_loaded(const char *name)
+{
+	for (size_t i = 1; i < dso_count; i++)
+		if (dl_streq(dsos[i].name, name))
+			return true;
+	return false;
+}
+
+/* Called by _dl_start, returns the entry point of the program */
+Elf32_Addr _dl_main(struct dl_args *args)
+{
+	Elf32_Addr at_base = 0;
+	Elf32_Addr at_entry = 0;
+	const Elf32_Phdr *at_phdr = NULL;
+	size_t at_phnum = 0;
+
+	for (Elf32_auxv_t *aux = args->auxv; aux->a_type != AT_NULL; aux++) {
+		switch (aux->a_type) {
+		case AT_BASE:
+			at_base = aux->a_un.a_val;
+			break;
+		case AT_ENTRY:
+			at_entry = aux->a_un.a_val;
+			break;
+		case AT_PHDR:
+			at_phdr = (const Elf32_Phdr *)aux->a_un.a_val;
+			break;
+		case AT_PHNUM:
+			at_phnum = aux->a_un.a_val;
+			break;
+		}
+	}
+	relocate_self(at_base);
+
+	if (!at_phdr)
+		dl_fatal("no program headers", NULL);
+
+	/* The program itself comes first in the lookup order */
+	struct dso *program = &dsos[dso_count++];
+	program->name = args->argv[0];
+	program->phdr = at_phdr;
+	program->phnum = at_phnum;
+	for (size_t i = 0; i < at_phnum; i++)
+		if (at_phdr[i].p_type == PT_PHDR)
+			program->base = (Elf32_Addr)at_phdr - at_phdr[i].p_vaddr;
+	for (size_t i = 0; i < at_phnum; i++)
+		if (at_phdr[i].p_type == PT_DYNAMIC)
+			program->dynamic = (const Elf32_Dyn *)(program->base + at_phdr[i].p_vaddr);
+	if (!program->dynamic)
+		dl_fatal("not a dynamic program", program->name);
+	parse_dynamic(program);
+
+	/* Load the dependencies breadth first */
+	for (size_t i = 0; i < dso_count; i++) {
+		for (const Elf32_Dyn *d = dsos[i].dynamic; d->d_tag != DT_NULL; d++) {
+			if (d->d_tag != DT_NEEDED)
+				continue;
+			const char *name = dsos[i].strtab + d->d_un.d_val;
+			if (is_loaded(name))
+				continue;
+			if (dso_count == MAX_DSO)
+				dl_fatal("too many shared libraries", name);
+			load_library(&dsos[dso_count], phdrs[dso_count], name);
+			dso_count++;
+		}
+	}
+
+	/*
+	 * The libraries are relocated before the program, whose R_386_COPY
+	 * relocations take the relocated data of the libraries.
+	 */
+	for (size_t i = dso_count; i-- > 0;) {
+		relocate(&dsos[i], dsos[i].rel, dsos[i].relsz);
+		relocate(&dsos[i], dsos[i].jmprel, dsos[i].pltrelsz);
+		if (i != 0)
+			protect_segments(&dsos[i]);
+	}
+
+	/* The constructors of the program are run by its own _init */
+	for (size_t i = dso_count; i-- > 1;) {
+		if (dsos[i].init)
+			dsos[i].init(args->argc, args->argv, args->envp);
+		for (size_t j = 0; j < dsos[i].init_arraysz / sizeof(init_fn); j++)
+			dsos[i].init_array[j](args->argc, args->argv, args->envp);
+	}
+	return at_entry;
+}
@@ -0,0 +1,63 @@
+.intel_syntax noprefix
+
+.section .text
+
+# The kernel enters the interpreter with the registers of the new
+# process image: EAX argc, EBX argv, ECX envp and EDX the auxiliary
+# vector. They are given back untouched to the entry of the program.
+.global _dl_start
+.hidden _dl_start
+.type _dl_start, @function
+_dl_start:
+	push edx # auxv
+	push ecx # envp
+	push ebx # argv
+	push eax # argc
+
+	# Set up end of the stack frame linked list.
+	xor ebp, ebp
+
+	push esp
+	call _dl_main
+	add esp, 4
+	mov esi, eax
+
+	pop eax
+	pop ebx
+	pop ecx
+	pop edx
+
+	# Enter the program.
+	jmp esi
+
+.size _dl_start, . - _dl_start
+
+# The dynamic linker cannot use the system calls of the libc, which it
+# has still to load.
+# int _dl_syscall(int num, ...): up to 5 arguments, returns the raw
+# result of the kernel (-errno on failure).
+.global _dl_syscall
+.hidden _dl_syscall
+.type _dl_syscall, @function
+_dl_syscall:
+	push ebp
+	mov ebp, esp
+	push ebx
+	push esi
+	push edi
+
+	mov eax, [ebp + 8]
+	mov ebx, [ebp + 12]
+	mov ecx, [ebp + 16]
+	mov edx, [ebp + 20]
+	mov esi, [ebp + 24]
+	mov edi, [ebp + 28]
+	int 0x80
+
+	pop edi
+	pop esi
+	pop ebx
+	pop ebp
+	ret
+
+.size _dl_syscall, . - _dl_syscall
@@ -11,6 +11,8 @@
 
 char **environ;
 
+unsigned long *__auxv;
+
 int errno;
 
 struct kernel kernel;
@@ -60,15 +62,8 @@ void basic_destructor(void) {
 	/* puts("*** libc destructor called ***"); */
 }
 
-extern unsigned char __init_array_start;
-extern unsigned char __init_array_end;
-
-
-void	call_init_array_ctors(void)
+void	call_init_array_ctors(void (**current_ctor)(void), void (**current_ctor_end)(void))
 {
-	void   (**current_ctor)(void) = (void *)&__init_array_start;
-	void   (**current_ctor_end)(void) = (void *)&__init_array_end;
-
 	/// Probably no init_array.
 	if (!current_ctor) {
 		return ;
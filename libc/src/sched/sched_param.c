@@ -0,0 +1,61 @@
+#include <sched.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The sched_setparam() function shall set the scheduling parameters of
+// the process specified by pid, or of the calling process if pid is
+// zero, to the values specified by the sched_param structure pointed to
+// by param.
+// [EINVAL]
+//     One or more of the requested scheduling parameters is outside the
+//     range defined for the scheduling policy of the specified pid.
+// [EPERM]
+//     The requesting process does not have permission to set the
+//     scheduling parameters for the specified process.
+// [ESRCH]
+//     No process can be found corresponding to that specified by pid.
+
+int sched_setparam(pid_t pid, const struct sched_param *param)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_SETPARAM, 2, pid, param);
+
+	set_errno_and_return(ret);
+}
+
+// The sched_getparam() function shall return the scheduling parameters
+// of the process specified by pid, or of the calling process if pid is
+// zero, in the sched_param structure pointed to by param.
+// [ESRCH]
+//     No process can be found corresponding to that specified by pid.
+
+int sched_getparam(pid_t pid, struct sched_param *param)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GETPARAM, 2, pid, param);
+
+	set_errno_and_return(ret);
+}
+
+// The sched_get_priority_max() and sched_get_priority_min() functions
+// shall return the maximum and minimum priority values of policy.
+// [EINVAL]
+//     The value of the policy parameter does not represent a defined
+//     scheduling policy.
+
+int sched_get_priority_max(int policy)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GET_PRIORITY_MAX, 1, policy);
+
+	set_errno_and_return(ret);
+}
+
+int sched_get_priority_min(int policy)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GET_PRIORITY_MIN, 1, policy);
+
+	set_errno_and_return(ret);
+}
@@ -1,18 +1,42 @@
 #include <sched.h>
 #include <errno.h>
 #include <ltrace.h>
-#include <custom.h>
+#include <user_syscall.h>
 
-#warning DUMMY IMPLEMENTATION
+// The sched_setscheduler() function shall set the scheduling policy and
+// scheduling parameters of the process specified by pid to policy and
+// the parameters specified in the sched_param structure pointed to by
+// param. If pid is zero, the calling process is used.
+// [EINVAL]
+//     The value of the policy parameter is invalid, or one or more of
+//     the parameters contained in param is outside the valid range for
+//     the specified scheduling policy.
+// [EPERM]
+//     The requesting process does not have permission to set the
+//     scheduling parameters or the scheduling policy of the specified
+//     process.
+// [ESRCH]
+//     No process can be found corresponding to that specified by pid.
 
 int sched_setscheduler(pid_t pid, int policy,
 		       const struct sched_param *param)
 {
 	TRACE
-	DUMMY
-	(void)pid;
-	(void)policy;
-	(void)param;
-	errno = ENOSYS;
-	return -1;
+	int ret = _user_syscall(SCHED_SETSCHEDULER, 3, pid, policy, param);
+
+	set_errno_and_return(ret);
+}
+
+// The sched_getscheduler() function shall return the scheduling policy
+// of the process specified by pid, or of the calling process if pid is
+// zero.
+// [ESRCH]
+//     No process can be found corresponding to that specified by pid.
+
+int sched_getscheduler(pid_t pid)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_GETSCHEDULER, 1, pid);
+
+	set_errno_and_return(ret);
 }
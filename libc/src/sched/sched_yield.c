@@ -0,0 +1,16 @@
+#include <sched.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The sched_yield() function shall force the running thread to
+// relinquish the processor until it again becomes the head of its
+// thread list. It takes no arguments and always succeeds.
+
+int sched_yield(void)
+{
+	TRACE
+	int ret = _user_syscall(SCHED_YIELD, 0);
+
+	set_errno_and_return(ret);
+}
@@ -10,10 +10,10 @@
 int    sigaddset(sigset_t *set, int signo)
 {
 	TRACE
-	if (signo < 0 || signo > 31) {
+	if (signo < 0 || signo >= NSIG) {
 		errno = EINVAL;
 		return -1;
 	}
-	*set |= 1 << signo;
+	*set |= 1ULL << signo;
 	return 0;
 }
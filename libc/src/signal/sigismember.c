@@ -4,9 +4,9 @@
 #include <errno.h>
 
 int    sigismember(const sigset_t *set, int signo) {
-	if (signo < 0 || signo > 31) {
+	if (signo < 0 || signo >= NSIG) {
 		errno = EINVAL;
 		return -1;
 	}
-	return (*set & (1 << signo)) != 0;
+	return (*set & (1ULL << signo)) != 0;
 }
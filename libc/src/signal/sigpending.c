@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/*
+ * sigpending - examine pending signals
+ */
+int sigpending(sigset_t *set)
+{
+	TRACE
+	int ret = _user_syscall(SIGPENDING, 1, set);
+
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/*
+ * sigqueue - queue a signal and data to a process
+ */
+int sigqueue(pid_t pid, int signo, union sigval value)
+{
+	TRACE
+	int ret = _user_syscall(SIGQUEUE, 3, pid, signo, value.sival_int);
+
+	set_errno_and_return(ret);
+}
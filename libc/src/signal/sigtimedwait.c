@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <time.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+/*
+ * sigtimedwait - wait for queued signals
+ */
+int sigtimedwait(const sigset_t *restrict set, siginfo_t *restrict info,
+		 const struct timespec *restrict timeout)
+{
+	TRACE
+	int ret = _user_syscall(SIGTIMEDWAIT, 3, set, info, timeout);
+
+	set_errno_and_return(ret);
+}
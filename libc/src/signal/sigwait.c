@@ -0,0 +1,22 @@
+#include <ltrace.h>
+#include <signal.h>
+#include <errno.h>
+
+/*
+ * sigwait - wait for a signal
+ * return 0 on success and an error number on failure, errno is left untouched.
+ */
+int sigwait(const sigset_t *restrict set, int *restrict sig)
+{
+	TRACE
+	int saved_errno = errno;
+	int ret = sigwaitinfo(set, NULL);
+
+	if (ret == -1) {
+		ret = errno;
+		errno = saved_errno;
+		return ret;
+	}
+	*sig = ret;
+	return 0;
+}
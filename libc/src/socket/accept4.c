@@ -0,0 +1,34 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <sys/socket.h>
+#include <errno.h>
+
+struct s_accept4 {
+	int sockfd;
+	struct sockaddr *addr;
+	socklen_t *addrlen;
+	int flags;
+};
+
+/*
+ * Accept a connection on a socket
+ *
+ * Same as accept(), SOCK_NONBLOCK in flags sets O_NONBLOCK on the new
+ * file descriptor and SOCK_CLOEXEC its close-on-exec flag.
+ *
+ * [EINVAL]
+ *     An invalid value was specified in flags.
+ */
+int accept4(int sockfd, struct sockaddr *addr, socklen_t *addrlen, int flags)
+{
+	TRACE
+	struct s_accept4 s = {sockfd, addr, addrlen, flags};
+
+	int ret = _user_syscall(SOCKETCALL, 2, __ACCEPT4, &s);
+	/*
+	 * On success, these system calls return a nonnegative integer that is a file
+	 * descriptor for the accepted socket.  On error, -1 is returned,
+	 * and errno is set appropriately
+	 */
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,215 @@
+#include <ltrace.h>
+#include <spawn.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <limits.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#define __PRIVATE_USE_SPAWN_ACTION_H__
+# include "spawn_action.h"
+#undef __PRIVATE_USE_SPAWN_ACTION_H__
+
+extern char **environ;
+
+#define DEFAULT_PATH "/bin:/usr/bin"
+
+/* Execute `file` in each directory of PATH, as execvp() would */
+static void exec_path(const char *file, char *const argv[], char *const envp[])
+{
+	const char *path = getenv("PATH");
+	size_t file_len = strlen(file);
+	char buf[PATH_MAX];
+	int eacces = 0;
+
+	if (!path) {
+		path = DEFAULT_PATH;
+	}
+	for (const char *dir = path;; dir++) {
+		const char *end = strchrnul(dir, ':');
+		size_t dir_len = end - dir;
+
+		// An empty entry stands for the current directory
+		if (dir_len == 0) {
+			dir = ".";
+			dir_len = 1;
+		}
+		if (dir_len + 1 + file_len < sizeof(buf)) {
+			memcpy(buf, dir, dir_len);
+			buf[dir_len] = '/';
+			memcpy(buf + dir_len + 1, file, file_len + 1);
+			execve(buf, argv, envp);
+			if (errno == EACCES) {
+				eacces = 1;
+			} else if (errno != ENOENT && errno != ENOTDIR) {
+				return;
+			}
+		}
+		if (*end == '\0') {
+			break;
+		}
+		dir = end;
+	}
+	errno = eacces ? EACCES : ENOENT;
+}
+
+static int apply_file_actions(const posix_spawn_file_actions_t *file_actions)
+{
+	for (int i = 0; i < file_actions->__count; i++) {
+		const struct __spawn_action *action = &file_actions->__actions[i];
+		int fd;
+
+		switch (action->kind) {
+		case SPAWN_OPEN:
+			fd = open(action->path, action->oflag, action->mode);
+			if (fd == -1) {
+				return -1;
+			}
+			if (fd != action->fildes) {
+				if (dup2(fd, action->fildes) == -1) {
+					return -1;
+				}
+				close(fd);
+			}
+			break;
+		case SPAWN_CLOSE:
+			// Closing an already closed descriptor is not an error
+			close(action->fildes);
+			break;
+		case SPAWN_DUP2:
+			if (action->fildes == action->newfildes) {
+				int flags = fcntl(action->fildes, F_GETFD);
+
+				if (flags == -1
+				    || fcntl(action->fildes, F_SETFD, flags & ~FD_CLOEXEC) == -1) {
+					return -1;
+				}
+			} else if (dup2(action->fildes, action->newfildes) == -1) {
+				return -1;
+			}
+			break;
+		}
+	}
+	return 0;
+}
+
+/*
+ * Run in the vforked child, on the memory of the parent: set up the
+ * child as described by the attributes and the file actions and execute
+ * the new process image. Only returns on failure, with errno set.
+ */
+static void spawn_child(const char *path,
+			const posix_spawn_file_actions_t *file_actions,
+			const posix_spawnattr_t *attrp,
+			char *const argv[], char *const envp[],
+			int use_path, const sigset_t *parent_mask)
+{
+	short flags = attrp ? attrp->__flags : 0;
+
+	// The handlers of the parent are not to be run by the child
+	for (int signum = 1; signum < NSIG; signum++) {
+		struct sigaction act;
+
+		if (sigaction(signum, NULL, &act) == -1) {
+			continue;
+		}
+		if ((act.sa_handler != SIG_DFL && act.sa_handler != SIG_IGN)
+		    || ((flags & POSIX_SPAWN_SETSIGDEF)
+			&& sigismember(&attrp->__sigdefault, signum) == 1)) {
+			act.sa_handler = SIG_DFL;
+			sigaction(signum, &act, NULL);
+		}
+	}
+	if ((flags & POSIX_SPAWN_SETPGROUP) && setpgid(0, attrp->__pgroup) == -1) {
+		return;
+	}
+	if ((flags & POSIX_SPAWN_RESETIDS)
+	    && (setgid(getgid()) == -1 || setuid(getuid()) == -1)) {
+		return;
+	}
+	if (file_actions && apply_file_actions(file_actions) == -1) {
+		return;
+	}
+	if (sigprocmask(SIG_SETMASK,
+			(flags & POSIX_SPAWN_SETSIGMASK) ? &attrp->__sigmask : parent_mask,
+			NULL) == -1) {
+		return;
+	}
+	if (use_path && !strchr(path, '/')) {
+		exec_path(path, argv, envp);
+	} else {
+		execve(path, argv, envp);
+	}
+}
+
+static int spawn(pid_t *restrict pid, const char *restrict path,
+		 const posix_spawn_file_actions_t *file_actions,
+		 const posix_spawnattr_t *restrict attrp,
+		 char *const argv[restrict], char *const envp[restrict],
+		 int use_path)
+{
+	// Written by the child, which shares the memory of the parent
+	volatile int error = 0;
+	int saved_errno = errno;
+	sigset_t all;
+	sigset_t old;
+	pid_t child;
+	int ret;
+
+	// No signal handler may run in the child before its handlers are reset
+	sigfillset(&all);
+	sigprocmask(SIG_BLOCK, &all, &old);
+
+	child = vfork();
+	if (child == 0) {
+		spawn_child(path, file_actions, attrp, argv, envp, use_path, &old);
+		error = errno;
+		_exit(127);
+	}
+
+	// The child has either executed the new image or exited
+	if (child == -1) {
+		ret = errno;
+	} else if (error != 0) {
+		ret = error;
+		while (waitpid(child, NULL, 0) == -1 && errno == EINTR)
+			;
+	} else {
+		if (pid) {
+			*pid = child;
+		}
+		ret = 0;
+	}
+	sigprocmask(SIG_SETMASK, &old, NULL);
+	errno = saved_errno;
+	return ret;
+}
+
+// Create a child process executing `path`, see posix_spawn(3p).
+// Returns 0 and stores the pid of the child in `pid` on success, or
+// an error number if the child could not be set up or if the new
+// process image could not be executed.
+
+int posix_spawn(pid_t *restrict pid, const char *restrict path,
+		const posix_spawn_file_actions_t *file_actions,
+		const posix_spawnattr_t *restrict attrp,
+		char *const argv[restrict], char *const envp[restrict])
+{
+	TRACE
+	return spawn(pid, path, file_actions, attrp, argv, envp ? envp : environ, 0);
+}
+
+// Same as posix_spawn(), but `file` is searched in the PATH when it
+// contains no slash.
+
+int posix_spawnp(pid_t *restrict pid, const char *restrict file,
+		 const posix_spawn_file_actions_t *file_actions,
+		 const posix_spawnattr_t *restrict attrp,
+		 char *const argv[restrict], char *const envp[restrict])
+{
+	TRACE
+	return spawn(pid, file, file_actions, attrp, argv, envp ? envp : environ, 1);
+}
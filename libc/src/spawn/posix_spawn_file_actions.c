@@ -0,0 +1,130 @@
+#include <ltrace.h>
+#include <spawn.h>
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define __PRIVATE_USE_SPAWN_ACTION_H__
+# include "spawn_action.h"
+#undef __PRIVATE_USE_SPAWN_ACTION_H__
+
+// Initialize an empty list of file actions.
+
+int posix_spawn_file_actions_init(posix_spawn_file_actions_t *file_actions)
+{
+	TRACE
+	file_actions->__count = 0;
+	file_actions->__capacity = 0;
+	file_actions->__actions = NULL;
+	return 0;
+}
+
+// Free the file actions, they must be initialized again before any use.
+
+int posix_spawn_file_actions_destroy(posix_spawn_file_actions_t *file_actions)
+{
+	TRACE
+	for (int i = 0; i < file_actions->__count; i++) {
+		free(file_actions->__actions[i].path);
+	}
+	free(file_actions->__actions);
+	file_actions->__count = 0;
+	file_actions->__capacity = 0;
+	file_actions->__actions = NULL;
+	return 0;
+}
+
+/* Append an action, the storage is allocated here so that the child
+ * of posix_spawn never has to */
+static int push_action(posix_spawn_file_actions_t *file_actions,
+		       const struct __spawn_action *action)
+{
+	if (file_actions->__count == file_actions->__capacity) {
+		int capacity = file_actions->__capacity ? file_actions->__capacity * 2 : 4;
+		struct __spawn_action *actions = realloc(file_actions->__actions,
+							 capacity * sizeof(*actions));
+
+		if (!actions) {
+			return ENOMEM;
+		}
+		file_actions->__actions = actions;
+		file_actions->__capacity = capacity;
+	}
+	file_actions->__actions[file_actions->__count++] = *action;
+	return 0;
+}
+
+// Open `path` on `fildes` in the child, as open(path, oflag, mode).
+// [EBADF]
+//     fildes is negative.
+// [ENOMEM]
+//     Insufficient memory exists to add to the file actions.
+
+int posix_spawn_file_actions_addopen(posix_spawn_file_actions_t *restrict file_actions,
+				     int fildes, const char *restrict path, int oflag, mode_t mode)
+{
+	TRACE
+	struct __spawn_action action = {
+		.kind = SPAWN_OPEN,
+		.fildes = fildes,
+		.oflag = oflag,
+		.mode = mode,
+	};
+	int ret;
+
+	if (fildes < 0) {
+		return EBADF;
+	}
+	action.path = strdup(path);
+	if (!action.path) {
+		return ENOMEM;
+	}
+	ret = push_action(file_actions, &action);
+	if (ret != 0) {
+		free(action.path);
+	}
+	return ret;
+}
+
+// Close `fildes` in the child.
+// [EBADF]
+//     fildes is negative.
+// [ENOMEM]
+//     Insufficient memory exists to add to the file actions.
+
+int posix_spawn_file_actions_addclose(posix_spawn_file_actions_t *file_actions, int fildes)
+{
+	TRACE
+	struct __spawn_action action = {
+		.kind = SPAWN_CLOSE,
+		.fildes = fildes,
+	};
+
+	if (fildes < 0) {
+		return EBADF;
+	}
+	return push_action(file_actions, &action);
+}
+
+// Duplicate `fildes` on `newfildes` in the child, as dup2(). When both
+// are equal, only the FD_CLOEXEC flag of fildes is cleared.
+// [EBADF]
+//     fildes or newfildes is negative.
+// [ENOMEM]
+//     Insufficient memory exists to add to the file actions.
+
+int posix_spawn_file_actions_adddup2(posix_spawn_file_actions_t *file_actions,
+				     int fildes, int newfildes)
+{
+	TRACE
+	struct __spawn_action action = {
+		.kind = SPAWN_DUP2,
+		.fildes = fildes,
+		.newfildes = newfildes,
+	};
+
+	if (fildes < 0 || newfildes < 0) {
+		return EBADF;
+	}
+	return push_action(file_actions, &action);
+}
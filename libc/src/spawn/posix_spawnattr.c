@@ -0,0 +1,91 @@
+#include <ltrace.h>
+#include <spawn.h>
+#include <errno.h>
+
+#define POSIX_SPAWN_FLAGS (POSIX_SPAWN_RESETIDS | POSIX_SPAWN_SETPGROUP \
+			   | POSIX_SPAWN_SETSIGDEF | POSIX_SPAWN_SETSIGMASK)
+
+// Initialize the attributes with all the flags cleared.
+
+int posix_spawnattr_init(posix_spawnattr_t *attr)
+{
+	TRACE
+	attr->__flags = 0;
+	attr->__pgroup = 0;
+	sigemptyset(&attr->__sigdefault);
+	sigemptyset(&attr->__sigmask);
+	return 0;
+}
+
+int posix_spawnattr_destroy(posix_spawnattr_t *attr)
+{
+	TRACE
+	(void)attr;
+	return 0;
+}
+
+int posix_spawnattr_getflags(const posix_spawnattr_t *restrict attr, short *restrict flags)
+{
+	TRACE
+	*flags = attr->__flags;
+	return 0;
+}
+
+// Select which attributes are applied to the child.
+// [EINVAL]
+//     flags contains an unsupported flag.
+
+int posix_spawnattr_setflags(posix_spawnattr_t *attr, short flags)
+{
+	TRACE
+	if (flags & ~POSIX_SPAWN_FLAGS) {
+		return EINVAL;
+	}
+	attr->__flags = flags;
+	return 0;
+}
+
+int posix_spawnattr_getpgroup(const posix_spawnattr_t *restrict attr, pid_t *restrict pgroup)
+{
+	TRACE
+	*pgroup = attr->__pgroup;
+	return 0;
+}
+
+// The child joins `pgroup` with POSIX_SPAWN_SETPGROUP, or creates its
+// own process group when it is 0.
+
+int posix_spawnattr_setpgroup(posix_spawnattr_t *attr, pid_t pgroup)
+{
+	TRACE
+	attr->__pgroup = pgroup;
+	return 0;
+}
+
+int posix_spawnattr_getsigdefault(const posix_spawnattr_t *restrict attr, sigset_t *restrict sigdefault)
+{
+	TRACE
+	*sigdefault = attr->__sigdefault;
+	return 0;
+}
+
+int posix_spawnattr_setsigdefault(posix_spawnattr_t *restrict attr, const sigset_t *restrict sigdefault)
+{
+	TRACE
+	attr->__sigdefault = *sigdefault;
+	return 0;
+}
+
+int posix_spawnattr_getsigmask(const posix_spawnattr_t *restrict attr, sigset_t *restrict sigmask)
+{
+	TRACE
+	*sigmask = attr->__sigmask;
+	return 0;
+}
+
+int posix_spawnattr_setsigmask(posix_spawnattr_t *restrict attr, const sigset_t *restrict sigmask)
+{
+	TRACE
+	attr->__sigmask = *sigmask;
+	return 0;
+}
@@ -0,0 +1,26 @@
+#ifndef _SPAWN_ACTION_H_
+# define _SPAWN_ACTION_H_
+# include <sys/types.h>
+
+#ifndef __PRIVATE_USE_SPAWN_ACTION_H__
+# error "This is a private header of the libc, do not use directly"
+#endif
+
+enum spawn_action_kind {
+	SPAWN_OPEN,
+	SPAWN_CLOSE,
+	SPAWN_DUP2,
+};
+
+/* One operation of a posix_spawn_file_actions_t, the path of an open
+ * is a copy owned by the file actions */
+struct __spawn_action {
+	enum spawn_action_kind	kind;
+	int			fildes;
+	int			newfildes;
+	char			*path;
+	int			oflag;
+	mode_t			mode;
+};
+
+#endif /* _SPAWN_ACTION_H_ */
@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <stdio.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The renameat() function shall be equivalent to the rename()
+// function except that a relative old is resolved from the directory
+// associated with the file descriptor oldfd and a relative new from
+// the directory associated with newfd. If a file descriptor is the
+// special value AT_FDCWD, the current working directory shall be used.
+
+int renameat(int oldfd, const char *old, int newfd, const char *new)
+{
+	TRACE
+	int ret = _user_syscall(RENAMEAT, 4, oldfd, old, newfd, new);
+	set_errno_and_return(ret);
+}
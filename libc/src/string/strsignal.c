@@ -1,6 +1,7 @@
 #include <ltrace.h>
 #include <string.h>
 #include <errno.h>
+#include <signal.h>
 
 static const char *signal_str[] = {
 	"Unknown signal 0",
@@ -40,7 +41,10 @@ static const char *signal_str[] = {
 char *strsignal(int signum)
 {
 	TRACE
-	if (signum < 0 || signum > 31) {
+	if (signum >= SIGRTMIN && signum <= SIGRTMAX) {
+		return "Real-time signal";
+	}
+	if (signum < 0 || signum >= SIGRTMIN) {
 		errno = EINVAL;
 		return "Unknown signal";
 	}
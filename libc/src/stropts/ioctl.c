@@ -1,5 +1,6 @@
 #include <ltrace.h>
 #include <stropts.h>
+#include <linux/loop.h>
 #include <errno.h>
 #include <stdio.h>
 #include <custom.h>
@@ -47,6 +48,13 @@ int ioctl(int fildes, int request, ... /* arg */)
 		case REFRESH_SCREEN:
 			arg = va_arg(ap, struct local_buffer *);
 			break;
+		case LOOP_SET_FD:
+		case LOOP_CLR_FD:
+			arg = (void *)va_arg(ap, int);
+			break;
+		case LOOP_GET_STATUS:
+			arg = va_arg(ap, struct loop_info *);
+			break;
 	}
 	int ret = _user_syscall(IOCTL, 3, fildes, request, arg);
 	va_end(ap);
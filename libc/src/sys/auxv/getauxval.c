@@ -0,0 +1,26 @@
+#include <sys/auxv.h>
+#include <errno.h>
+#include <ltrace.h>
+
+/// The auxiliary vector given by the kernel, saved by crt0
+extern unsigned long *__auxv;
+
+// The getauxval() function retrieves values from the auxiliary
+// vector, a mechanism that the kernel uses to pass certain
+// information to the process when a program is executed.
+
+// [ENOENT]
+//     No entry corresponding to type could be found in the auxiliary
+//     vector.
+
+unsigned long getauxval(unsigned long type)
+{
+	TRACE
+	for (unsigned long *entry = __auxv; entry && entry[0] != AT_NULL; entry += 2) {
+		if (entry[0] == type) {
+			return entry[1];
+		}
+	}
+	errno = ENOENT;
+	return 0;
+}
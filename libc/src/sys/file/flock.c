@@ -0,0 +1,22 @@
+#include <ltrace.h>
+#include <sys/file.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Apply or remove an advisory lock on the whole open file specified
+// by fd. The lock belongs to the open file description, and is
+// released when its last file descriptor is closed.
+// [EWOULDBLOCK]
+//     The file is locked and the LOCK_NB flag was selected.
+// [EINTR]
+//     While blocked waiting to acquire a lock, the call was
+//     interrupted by delivery of a signal caught by a handler.
+// [EINVAL]
+//     operation is invalid.
+
+int flock(int fd, int operation)
+{
+	TRACE
+	int ret = _user_syscall(FLOCK, 2, fd, operation);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,28 @@
+#include <ltrace.h>
+#include <sys/inotify.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Add a new watch, or modify an existing watch, for the file whose
+// location is specified in pathname. Return the watch descriptor,
+// which is the same for all the watches of a file in an instance.
+// [EACCES]
+//     Read access to the given file is not permitted.
+// [EBADF]
+//     The given file descriptor is not valid.
+// [EINVAL]
+//     The given event mask contains no valid events; or fd is not an
+//     inotify file descriptor.
+// [ENOENT]
+//     A directory component in pathname does not exist.
+// [ENOSPC]
+//     The limit on the number of watches of the instance was reached.
+// [ENOTDIR]
+//     mask contains IN_ONLYDIR and pathname is not a directory.
+
+int inotify_add_watch(int fd, const char *pathname, uint32_t mask)
+{
+	TRACE
+	int ret = _user_syscall(INOTIFY_ADD_WATCH, 3, fd, pathname, mask);
+	set_errno_and_return(ret);
+}
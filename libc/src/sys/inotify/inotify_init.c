@@ -0,0 +1,19 @@
+#include <ltrace.h>
+#include <sys/inotify.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Initialize a new inotify instance and return a file descriptor
+// associated with a new inotify event queue.
+// [EMFILE]
+//     The per-process limit on the number of open file descriptors
+//     has been reached.
+// [ENOMEM]
+//     Insufficient kernel memory is available.
+
+int inotify_init(void)
+{
+	TRACE
+	int ret = _user_syscall(INOTIFY_INIT, 0);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <sys/inotify.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Same as inotify_init(), the IN_NONBLOCK and IN_CLOEXEC flags set
+// the O_NONBLOCK and the close-on-exec flags of the new file
+// descriptor.
+// [EINVAL]
+//     An invalid value was specified in flags.
+
+int inotify_init1(int flags)
+{
+	TRACE
+	int ret = _user_syscall(INOTIFY_INIT1, 1, flags);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,19 @@
+#include <ltrace.h>
+#include <sys/inotify.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Remove the watch associated with the watch descriptor wd from the
+// inotify instance fd. An IN_IGNORED event is queued for it.
+// [EBADF]
+//     fd is not a valid file descriptor.
+// [EINVAL]
+//     The watch descriptor wd is not valid; or fd is not an inotify
+//     file descriptor.
+
+int inotify_rm_watch(int fd, int wd)
+{
+	TRACE
+	int ret = _user_syscall(INOTIFY_RM_WATCH, 2, fd, wd);
+	set_errno_and_return(ret);
+}
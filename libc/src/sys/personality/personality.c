@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <sys/personality.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Set the process execution domain, return the previous one.
+// [EINVAL]
+//     The kernel does not know the execution domain of persona.
+
+int personality(unsigned long persona)
+{
+	TRACE
+	int ret = _user_syscall(PERSONALITY, 1, persona);
+	set_errno_and_return(ret);
+}
@@ -1,14 +1,25 @@
 #include <sys/resource.h>
 #include <errno.h>
 #include <ltrace.h>
-#include <custom.h>
+#include <user_syscall.h>
+
+// The getpriority() function shall obtain the nice value of a process,
+// process group, or user. The nice value returned is the lowest one of
+// the processes specified by which and who.
+// [ESRCH]
+//     No process could be located using the which and who values specified.
+// [EINVAL]
+//     which is not one of PRIO_PROCESS, PRIO_PGRP, or PRIO_USER.
 
 int getpriority(int which, id_t who)
 {
 	TRACE
-	DUMMY
-	(void)which;
-	(void)who;
-	errno = ENOSYS;
-	return -1;
+	// The kernel returns 20 - nice, so that it is never negative
+	int ret = _user_syscall(GETPRIORITY, 2, which, who);
+
+	if (ret < 0) {
+		errno = -ret;
+		return -1;
+	}
+	return 20 - ret;
 }
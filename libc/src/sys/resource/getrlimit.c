@@ -0,0 +1,20 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The getrlimit() function shall get limits on the consumption of a
+// variety of resources. Each call to getrlimit() specifies a
+// particular resource and a soft and hard limit on that resource,
+// stored in the rlimit structure pointed to by rlp.
+
+// [EINVAL]
+//     An invalid resource was specified.
+
+int getrlimit(int resource, struct rlimit *rlp)
+{
+	TRACE
+	int ret = _user_syscall(GETRLIMIT, 2, resource, rlp);
+
+	set_errno_and_return(ret);
+}
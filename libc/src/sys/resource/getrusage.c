@@ -0,0 +1,23 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The getrusage() function shall provide measures of the resources
+// used by the current process or its terminated and waited-for child
+// processes. If the value of the who argument is RUSAGE_SELF,
+// information shall be returned about resources used by the current
+// process. If the value of the who argument is RUSAGE_CHILDREN,
+// information shall be returned about resources used by the
+// terminated and waited-for children of the current process.
+
+// [EINVAL]
+//     The value of the who argument is not valid.
+
+int getrusage(int who, struct rusage *r_usage)
+{
+	TRACE
+	int ret = _user_syscall(GETRUSAGE, 2, who, r_usage);
+
+	set_errno_and_return(ret);
+}
@@ -1,16 +1,26 @@
 #include <sys/resource.h>
 #include <errno.h>
-#include <custom.h>
 #include <ltrace.h>
+#include <user_syscall.h>
+
+// The setpriority() function shall set the nice value of a process,
+// process group, or user to value, clamped to [-20, 19].
+// [ESRCH]
+//     No process could be located using the which and who values specified.
+// [EINVAL]
+//     which is not one of PRIO_PROCESS, PRIO_PGRP, or PRIO_USER.
+// [EPERM]
+//     A process was located, but neither the real nor effective user ID
+//     of the executing process match the effective user ID of the
+//     process whose nice value is being changed.
+// [EACCES]
+//     A request was made to change the priority to a lower numeric value
+//     and the current process does not have appropriate privileges.
 
 int setpriority(int which, id_t who, int value)
 {
 	TRACE
-	DUMMY
-	(void)which;
-	(void)who;
-	(void)value;
+	int ret = _user_syscall(SETPRIORITY, 3, which, who, value);
 
-	errno = ENOSYS;
-	return -1;
+	set_errno_and_return(ret);
 }
@@ -0,0 +1,24 @@
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The setrlimit() function shall set limits on the consumption of a
+// variety of resources. The soft limit is the value that the kernel
+// enforces, the hard limit acts as a ceiling for the soft limit. An
+// unprivileged process may only lower its hard limit.
+
+// [EINVAL]
+//     An invalid resource was specified, or the soft limit exceeds
+//     the hard limit.
+// [EPERM]
+//     The limit specified would have raised the maximum limit value,
+//     and the calling process does not have appropriate privileges.
+
+int setrlimit(int resource, const struct rlimit *rlp)
+{
+	TRACE
+	int ret = _user_syscall(SETRLIMIT, 2, resource, rlp);
+
+	set_errno_and_return(ret);
+}
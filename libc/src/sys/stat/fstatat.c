@@ -0,0 +1,28 @@
+#include <ltrace.h>
+#include <sys/stat.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The fstatat() function shall be equivalent to the stat() or lstat()
+// function, depending on the value of flag, except in the case where
+// path specifies a relative path. In this case the status shall be
+// retrieved from a file relative to the directory associated with the
+// file descriptor fd instead of the current working directory. If fd
+// is the special value AT_FDCWD, the current working directory shall
+// be used.
+//
+// Values for flag are constructed by a bitwise-inclusive OR of flags
+// from the following list, defined in <fcntl.h>:
+// AT_SYMLINK_NOFOLLOW
+//     If path names a symbolic link, the status of the symbolic link
+//     is returned.
+// AT_EMPTY_PATH
+//     If path is an empty string, the status of the file referred to
+//     by fd is returned.
+
+int fstatat(int fd, const char *restrict path, struct stat *restrict buf, int flag)
+{
+	TRACE
+	int ret = _user_syscall(FSTATAT, 4, fd, path, buf, flag);
+	set_errno_and_return(ret);
+}
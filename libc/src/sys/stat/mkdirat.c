@@ -0,0 +1,18 @@
+#include <ltrace.h>
+#include <sys/stat.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The mkdirat() function shall be equivalent to the mkdir() function
+// except in the case where path specifies a relative path. In this
+// case the newly created directory is created relative to the
+// directory associated with the file descriptor fd instead of the
+// current working directory. If fd is the special value AT_FDCWD, the
+// current working directory shall be used.
+
+int mkdirat(int fd, const char *path, mode_t mode)
+{
+	TRACE
+	int ret = _user_syscall(MKDIRAT, 3, fd, path, mode);
+	set_errno_and_return(ret);
+}
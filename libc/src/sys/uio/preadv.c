@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <sys/uio.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The preadv() function combines the functionality of readv() and
+// pread(): it reads into the iovcnt buffers of iov from the file at
+// offset, the file offset is not used nor changed.
+
+ssize_t preadv(int fildes, const struct iovec *iov, int iovcnt, off_t offset)
+{
+	TRACE
+	// 5 argument since off_t is a 8 bytes type
+	int ret = _user_syscall(PREADV, 5, fildes, iov, iovcnt, offset);
+	set_errno_and_return(ret);
+}
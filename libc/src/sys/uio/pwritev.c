@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <sys/uio.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The pwritev() function combines the functionality of writev() and
+// pwrite(): it writes the iovcnt buffers of iov into the file at
+// offset, the file offset is not used nor changed.
+
+ssize_t pwritev(int fildes, const struct iovec *iov, int iovcnt, off_t offset)
+{
+	TRACE
+	// 5 argument since off_t is a 8 bytes type
+	int ret = _user_syscall(PWRITEV, 5, fildes, iov, iovcnt, offset);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,21 @@
+#include <ltrace.h>
+#include <sys/uio.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The readv() function shall be equivalent to read(), except as
+// described below. The readv() function shall place the input data
+// into the iovcnt buffers specified by the members of the iov array:
+// iov[0], iov[1], ..., iov[iovcnt-1]. The iovcnt argument is valid if
+// greater than 0 and less than or equal to {IOV_MAX}.
+//
+// Each iovec entry specifies the base address and length of an area
+// in memory where data should be placed. The readv() function shall
+// always fill an area completely before proceeding to the next.
+
+ssize_t readv(int fildes, const struct iovec *iov, int iovcnt)
+{
+	TRACE
+	int ret = _user_syscall(READV, 3, fildes, iov, iovcnt);
+	set_errno_and_return(ret);
+}
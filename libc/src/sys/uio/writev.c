@@ -0,0 +1,25 @@
+#include <ltrace.h>
+#include <sys/uio.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+// The writev() function shall be equivalent to write(), except as
+// described below. The writev() function shall gather output data
+// from the iovcnt buffers specified by the members of the iov array:
+// iov[0], iov[1], ..., iov[iovcnt-1]. The iovcnt argument is valid if
+// greater than 0 and less than or equal to {IOV_MAX}, as defined in
+// <limits.h>.
+//
+// Each iovec entry specifies the base address and length of an area
+// in memory from which data should be written. The writev() function
+// shall always write a complete area before proceeding to the next.
+//
+// A write to a pipe of at most {PIPE_BUF} bytes in total is not
+// interleaved with the data of other writers.
+
+ssize_t writev(int fildes, const struct iovec *iov, int iovcnt)
+{
+	TRACE
+	int ret = _user_syscall(WRITEV, 3, fildes, iov, iovcnt);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,16 @@
+#include <sys/xattr.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// getxattr() retrieves the value of the extended attribute `name`
+/// of the file `path` in the buffer `value` of `size` bytes.
+///
+/// If `size` is zero, the size of the value is returned and `value`
+/// is left untouched.
+ssize_t getxattr(const char *path, const char *name, void *value, size_t size)
+{
+	TRACE
+	int ret = _user_syscall(GETXATTR, 4, path, name, value, size);
+	set_errno_and_return(ret);
+}
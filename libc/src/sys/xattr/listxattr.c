@@ -0,0 +1,15 @@
+#include <sys/xattr.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// listxattr() places the null-terminated names of the extended
+/// attributes of the file `path` in the buffer `list` of `size` bytes.
+///
+/// If `size` is zero, the size needed by the list is returned.
+ssize_t listxattr(const char *path, char *list, size_t size)
+{
+	TRACE
+	int ret = _user_syscall(LISTXATTR, 3, path, list, size);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,12 @@
+#include <sys/xattr.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// removexattr() removes the extended attribute `name` of the file `path`.
+int removexattr(const char *path, const char *name)
+{
+	TRACE
+	int ret = _user_syscall(REMOVEXATTR, 2, path, name);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,14 @@
+#include <sys/xattr.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// setxattr() sets the value of the extended attribute `name` of the
+/// file `path`. `flags` may be XATTR_CREATE to fail if the attribute
+/// already exists, or XATTR_REPLACE to fail if it does not.
+int setxattr(const char *path, const char *name, const void *value, size_t size, int flags)
+{
+	TRACE
+	int ret = _user_syscall(SETXATTR, 5, path, name, value, size, flags);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,17 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The chroot() function shall cause the directory named by the
+// pathname pointed to by the path argument to become the root
+// directory of the process; that is, the starting point for path
+// searches for pathnames beginning with '/'. The current working
+// directory is unchanged.
+
+int chroot(const char *path)
+{
+	TRACE
+	int ret = _user_syscall(CHROOT, 1, path);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,23 @@
+#include <ltrace.h>
+#include <user_syscall.h>
+#include <unistd.h>
+#include <errno.h>
+
+// Same as dup2(), except that the FD_CLOEXEC flag associated with
+// fildes2 is set if flags contains O_CLOEXEC.
+
+// [EINVAL]
+//     fildes is equal to fildes2, or flags contains an invalid value.
+
+int dup3(int oldfd, int newfd, int flags)
+{
+	TRACE
+	if (oldfd < 0 || newfd < 0) {
+		errno = EBADF;
+		return -1;
+	}
+
+	int ret = _user_syscall(DUP3, 3, oldfd, newfd, flags);
+
+	set_errno_and_return(ret);
+}
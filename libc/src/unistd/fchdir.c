@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The fchdir() function shall be equivalent to chdir() except that
+// the directory that is to be the new current working directory is
+// specified by the file descriptor fildes.
+
+int fchdir(int fildes)
+{
+	TRACE
+	int ret = _user_syscall(FCHDIR, 1, fildes);
+	set_errno_and_return(ret);
+}
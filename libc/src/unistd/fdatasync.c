@@ -0,0 +1,20 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The fdatasync() function shall force all currently queued I/O
+// operations associated with the file indicated by file descriptor
+// fildes to the synchronized I/O completion state.
+// [EBADF]
+//     The fildes argument is not a valid file descriptor.
+// [EINVAL]
+//     This implementation does not support synchronized I/O for this
+//     file.
+
+int fdatasync(int fildes)
+{
+	TRACE
+	int ret = _user_syscall(FDATASYNC, 1, fildes);
+	set_errno_and_return(ret);
+}
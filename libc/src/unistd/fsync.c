@@ -0,0 +1,23 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The fsync() function shall request that all data for the open file
+// descriptor named by fildes is to be transferred to the storage
+// device associated with the file described by fildes.
+// [EBADF]
+//     The fildes argument is not a valid descriptor.
+// [EINVAL]
+//     The fildes argument does not refer to a file on which this
+//     operation is possible.
+// [EIO]
+//     An I/O error occurred while reading from or writing to the file
+//     system.
+
+int fsync(int fildes)
+{
+	TRACE
+	int ret = _user_syscall(FSYNC, 1, fildes);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,22 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// If fildes is not a valid file descriptor open for writing, the
+// ftruncate() function shall fail.
+// If fildes refers to a regular file, the ftruncate() function shall
+// cause the size of the file to be truncated to length.
+// [EINVAL]
+//     The length argument was less than 0.
+// [EINVAL]
+//     The fildes argument references a file that was opened without
+//     write permission, or that is not a regular file.
+
+int ftruncate(int fildes, off_t length)
+{
+	TRACE
+	// 3 argument since off_t is a 8 bytes type
+	int ret = _user_syscall(FTRUNCATE64, 3, fildes, length);
+	set_errno_and_return(ret);
+}
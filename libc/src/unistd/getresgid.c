@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// getresgid() returns the real group ID, the effective group ID, and
+/// the saved set-group-ID of the calling process, in the arguments
+/// rgid, egid, and sgid, respectively.
+
+int getresgid(gid_t *rgid, gid_t *egid, gid_t *sgid)
+{
+	TRACE
+	int ret = _user_syscall(GETRESGID, 3, rgid, egid, sgid);
+	set_errno_and_return(ret);
+}
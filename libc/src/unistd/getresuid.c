@@ -0,0 +1,15 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// getresuid() returns the real user ID, the effective user ID, and
+/// the saved set-user-ID of the calling process, in the arguments
+/// ruid, euid, and suid, respectively.
+
+int getresuid(uid_t *ruid, uid_t *euid, uid_t *suid)
+{
+	TRACE
+	int ret = _user_syscall(GETRESUID, 3, ruid, euid, suid);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,16 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Return the process group ID of the session leader of the process
+// `pid`, or of the calling process when it is 0.
+// [ESRCH]
+//     There is no process with a process ID equal to pid.
+
+pid_t getsid(pid_t pid)
+{
+	TRACE
+	pid_t ret = _user_syscall(GETSID, 1, pid);
+	set_errno_and_return(ret);
+}
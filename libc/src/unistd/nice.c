@@ -0,0 +1,24 @@
+#include <unistd.h>
+#include <sys/resource.h>
+#include <errno.h>
+#include <ltrace.h>
+#include <user_syscall.h>
+
+// The nice() function shall add the value of incr to the nice value of
+// the calling process, and return the new nice value. As -1 is a valid
+// nice value, errno shall be cleared before the call to detect errors.
+// [EPERM]
+//     The incr argument is negative and the calling process does not
+//     have appropriate privileges.
+
+int nice(int incr)
+{
+	TRACE
+	int ret = _user_syscall(NICE, 1, incr);
+
+	if (ret < 0) {
+		errno = -ret;
+		return -1;
+	}
+	return getpriority(PRIO_PROCESS, 0);
+}
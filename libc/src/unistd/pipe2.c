@@ -0,0 +1,21 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Same as pipe(), O_NONBLOCK in flags sets O_NONBLOCK on both file
+// descriptors and O_CLOEXEC sets their FD_CLOEXEC flag.
+
+// [EINVAL]
+//     An invalid value was specified in flags.
+
+int pipe2(int fd[2], int flags)
+{
+	TRACE
+	int ret = _user_syscall(PIPE2, 2, fd, flags);
+	/*
+	 * On success, zero is returned.  On error, -1 is returned,
+	 * and errno is set appropriately.
+	 */
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,23 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The pread() function shall be equivalent to read(), except that it
+// shall read from a given position in the file without changing the
+// file offset. The first three arguments to pread() are the same as
+// read() with the addition of a fourth argument offset for the
+// desired position inside the file. An attempt to perform a pread()
+// on a file that is incapable of seeking shall result in an error.
+// [ESPIPE]
+//     The file is a pipe, FIFO, or socket.
+// [EINVAL]
+//     The offset argument is negative.
+
+ssize_t pread(int fildes, void *buf, size_t nbyte, off_t offset)
+{
+	TRACE
+	// 5 argument since off_t is a 8 bytes type
+	ssize_t ret = _user_syscall(PREAD64, 5, fildes, buf, nbyte, offset);
+	set_errno_and_return(ret);
+}
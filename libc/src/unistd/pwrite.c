@@ -0,0 +1,24 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The pwrite() function shall be equivalent to write(), except that
+// it writes into a given position and does not change the file
+// offset (regardless of whether O_APPEND is set). The first three
+// arguments to pwrite() are the same as write() with the addition of
+// a fourth argument offset for the desired position inside the
+// file. An attempt to perform a pwrite() on a file that is incapable
+// of seeking shall result in an error.
+// [ESPIPE]
+//     The file is a pipe, FIFO, or socket.
+// [EINVAL]
+//     The offset argument is negative.
+
+ssize_t pwrite(int fildes, const void *buf, size_t nbyte, off_t offset)
+{
+	TRACE
+	// 5 argument since off_t is a 8 bytes type
+	ssize_t ret = _user_syscall(PWRITE64, 5, fildes, buf, nbyte, offset);
+	set_errno_and_return(ret);
+}
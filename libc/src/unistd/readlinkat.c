@@ -0,0 +1,19 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The readlinkat() function shall be equivalent to the readlink()
+// function except in the case where path specifies a relative
+// path. In this case the symbolic link whose content is read is
+// relative to the directory associated with the file descriptor fd
+// instead of the current working directory. If fd is the special
+// value AT_FDCWD, the current working directory shall be used.
+
+ssize_t readlinkat(int fd, const char *restrict path, char *restrict buf, size_t bufsize)
+{
+	TRACE
+
+	ssize_t ret = _user_syscall(READLINKAT, 4, fd, path, buf, bufsize);
+	set_errno_and_return(ret);
+}
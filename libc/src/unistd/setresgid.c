@@ -0,0 +1,18 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// setresgid() sets the real group ID, the effective group ID, and
+/// the saved set-group-ID of the calling process. If one of the
+/// arguments equals -1, the corresponding value is not changed.
+///
+/// An unprivileged process may change each of these IDs only to the
+/// current real, effective or saved ID.
+
+int setresgid(gid_t rgid, gid_t egid, gid_t sgid)
+{
+	TRACE
+	int ret = _user_syscall(SETRESGID, 3, rgid, egid, sgid);
+	set_errno_and_return(ret);
+}
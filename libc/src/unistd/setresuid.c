@@ -0,0 +1,18 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/// setresuid() sets the real user ID, the effective user ID, and the
+/// saved set-user-ID of the calling process. If one of the arguments
+/// equals -1, the corresponding value is not changed.
+///
+/// An unprivileged process may change each of these IDs only to the
+/// current real, effective or saved ID.
+
+int setresuid(uid_t ruid, uid_t euid, uid_t suid)
+{
+	TRACE
+	int ret = _user_syscall(SETRESUID, 3, ruid, euid, suid);
+	set_errno_and_return(ret);
+}
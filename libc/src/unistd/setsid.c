@@ -0,0 +1,19 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// Create a new session, led by the calling process, which is also the
+// leader of a new process group and has no controlling terminal.
+// Returns the new session ID.
+// [EPERM]
+//     The calling process is already a process group leader, or the
+//     process group ID of a process other than the calling process
+//     matches the process ID of the calling process.
+
+pid_t setsid(void)
+{
+	TRACE
+	pid_t ret = _user_syscall(SETSID, 0);
+	set_errno_and_return(ret);
+}
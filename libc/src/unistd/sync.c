@@ -1,20 +1,15 @@
+#include <ltrace.h>
 #include <unistd.h>
+#include <user_syscall.h>
 
+// The sync() function shall cause all information in memory that
+// updates file systems to be scheduled for writing out to all file
+// systems.
+// The writing, although scheduled, is not necessarily complete upon
+// return from sync().
 
-/* DESCRIPTION */
-/* The sync() function shall cause all information in memory that updates file systems to be scheduled for writing out to all file systems. */
-
-/* The writing, although scheduled, is not necessarily complete upon return from sync(). */
-
-/* RETURN VALUE */
-/* The sync() function shall not return a value. */
-
-#warning DUMMY IMPLEMENTATION
-
-#include <custom.h>
-
-void         sync(void)
+void sync(void)
 {
-	DUMMY_KERNEL
-	DUMMY
+	TRACE
+	_user_syscall(SYNC, 0);
 }
@@ -0,0 +1,14 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// syncfs() is like sync(), but synchronizes just the filesystem
+// containing the file referred to by the open file descriptor fd.
+
+int syncfs(int fd)
+{
+	TRACE
+	int ret = _user_syscall(SYNCFS, 1, fd);
+	set_errno_and_return(ret);
+}
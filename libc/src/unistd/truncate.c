@@ -0,0 +1,25 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The truncate() function shall cause the regular file named by path
+// to have a size which shall be equal to length bytes.
+// If the file previously was larger than length, the extra data is
+// discarded. If the file was previously shorter than length, its size
+// is increased, and the extended area appears as if it were
+// zero-filled.
+// [EACCES]
+//     Write permission is denied for the file.
+// [EINVAL]
+//     The length argument was less than 0.
+// [EISDIR]
+//     The named file is a directory.
+
+int truncate(const char *path, off_t length)
+{
+	TRACE
+	// 3 argument since off_t is a 8 bytes type
+	int ret = _user_syscall(TRUNCATE64, 3, path, length);
+	set_errno_and_return(ret);
+}
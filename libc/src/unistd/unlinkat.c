@@ -0,0 +1,21 @@
+#include <ltrace.h>
+#include <unistd.h>
+#include <errno.h>
+#include <user_syscall.h>
+
+// The unlinkat() function shall be equivalent to the unlink() or
+// rmdir() function except in the case where path specifies a relative
+// path. In this case the directory entry to be removed is determined
+// relative to the directory associated with the file descriptor fd
+// instead of the current working directory. If fd is the special
+// value AT_FDCWD, the current working directory shall be used.
+//
+// If the AT_REMOVEDIR flag is set, the behavior shall be the same as
+// rmdir() except in the processing of relative paths.
+
+int unlinkat(int fd, const char *path, int flag)
+{
+	TRACE
+	int ret = _user_syscall(UNLINKAT, 3, fd, path, flag);
+	set_errno_and_return(ret);
+}
@@ -0,0 +1,24 @@
+.intel_syntax noprefix
+
+.set VFORK, 190
+
+# pid_t vfork(void)
+#
+# The child runs on the stack of its parent until it calls execve or
+# _exit, overwriting what is below the stack pointer. The return
+# address is kept in a register, so the parent still finds it.
+.global vfork
+vfork:
+	pop edx
+	mov eax, VFORK
+	int 0x80
+	push edx
+
+	cmp eax, 0
+	jge .done
+	neg eax
+	mov [errno], eax
+	mov eax, -1
+
+.done:
+	ret
@@ -0,0 +1,19 @@
+#include <ltrace.h>
+#include <sys/wait.h>
+#include <user_syscall.h>
+#include <errno.h>
+
+/*
+ * waitid - wait for a child process to change state
+ */
+int waitid(idtype_t idtype, id_t id, siginfo_t *infop, int options)
+{
+	TRACE
+	int ret = _user_syscall(WAITID, 4, idtype, id, infop, options);
+	/*
+	 * on success, or if WNOHANG was specified and no child specified
+	 * by id has yet changed state, returns 0; on error, -1 is returned.
+	 * In the WNOHANG case, the si_pid field of infop is set to 0.
+	 */
+	set_errno_and_return(ret);
+}
@@ -1,15 +1,148 @@
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+#include <fcntl.h>
+#include <errno.h>
+#include <sys/ioctl.h>
 #include <sys/mount.h>
+#include <linux/loop.h>
+
+static void usage(void) {
+	dprintf(2, "usage: mount [-t type] [-o options] source target\n       mount -o remount[,options] target\n   mount source charactere device of file on directory pointed by target\n   type is ext2 (default), vfat, msdos or iso9660\n   options is a comma separated list of:\n     loop      attach the image file source to a free /dev/loopN first\n     bind      mount the directory or file source again on target\n     remount   change the options of the mount on target\n     ro, rw, nosuid, nodev, noexec\n   example: mount -t iso9660 /dev/sr0 /mnt/cdrom\n   example: mount -o loop disk.img /mnt\n   example: mount -o bind,ro /bin /mnt\n");
+	exit(1);
+}
+
+static const struct {
+	const char *name;
+	unsigned long flag;
+} mount_options[] = {
+	{"ro", MS_RDONLY},
+	{"nosuid", MS_NOSUID},
+	{"nodev", MS_NODEV},
+	{"noexec", MS_NOEXEC},
+	{"bind", MS_BIND},
+	{"remount", MS_REMOUNT},
+};
+
+/*
+ * Add the flag of the mount option to flags, rw clears MS_RDONLY.
+ * Returns -1 if the option is unknown
+ */
+static int parse_option(const char *option, unsigned long *flags) {
+	if (strcmp(option, "rw") == 0) {
+		*flags &= ~MS_RDONLY;
+		return 0;
+	}
+	for (size_t i = 0; i < sizeof(mount_options) / sizeof(mount_options[0]); i++) {
+		if (strcmp(option, mount_options[i].name) == 0) {
+			*flags |= mount_options[i].flag;
+			return 0;
+		}
+	}
+	return -1;
+}
+
+/*
+ * Attach the image file to the first free loop device, an image
+ * which cannot be written is attached read only.
+ * Returns the loop device fd, its path is written in loop_path
+ */
+static int setup_loop(const char *image, char *loop_path, size_t len) {
+	int file = open(image, O_RDWR);
+	if (file == -1 && (errno == EACCES || errno == EROFS)) {
+		file = open(image, O_RDONLY);
+	}
+	if (file == -1) {
+		perror(image);
+		return -1;
+	}
+	for (int i = 0; ; i++) {
+		snprintf(loop_path, len, "/dev/loop%d", i);
+		int loop = open(loop_path, O_RDWR);
+		if (loop == -1) {
+			if (errno == ENOENT) {
+				dprintf(2, "mount: no free loop device\n");
+			} else {
+				perror(loop_path);
+			}
+			break;
+		}
+		if (ioctl(loop, LOOP_SET_FD, file) == 0) {
+			close(file);
+			return loop;
+		}
+		int err = errno;
+		close(loop);
+		if (err != EBUSY) {
+			errno = err;
+			perror(loop_path);
+			break;
+		}
+	}
+	close(file);
+	return -1;
+}
 
 int main(int ac, char **av) {
-	if (ac != 3) {
-		dprintf(2, "usage: mount source target\n   mount source charactere device of file on directory pointed by target\n");
-		exit(1);
+	const char *type = "ext2";
+	int use_loop = 0;
+	unsigned long flags = 0;
+	int opt;
+
+	while ((opt = getopt(ac, av, "t:o:")) != -1) {
+		switch (opt) {
+		case 't':
+			type = optarg;
+			break;
+		case 'o':
+			for (char *option = strtok(optarg, ","); option != NULL; option = strtok(NULL, ",")) {
+				if (strcmp(option, "loop") == 0) {
+					use_loop = 1;
+				} else if (parse_option(option, &flags) == -1) {
+					dprintf(2, "mount: unsupported option %s\n", option);
+					usage();
+				}
+			}
+			break;
+		default:
+			usage();
+		}
 	}
-	int ret = mount(av[1], av[2], "ext2", 0, NULL);
+	if (flags & MS_REMOUNT) {
+		// Only the target of a remount matters
+		if (ac - optind != 1 || use_loop) {
+			usage();
+		}
+		if (mount("none", av[optind], NULL, flags, NULL) == -1) {
+			perror("mount");
+			return 1;
+		}
+		return 0;
+	}
+	if (ac - optind != 2) {
+		usage();
+	}
+	const char *source = av[optind];
+	char loop_path[32];
+	int loop = -1;
+	if (use_loop) {
+		loop = setup_loop(source, loop_path, sizeof(loop_path));
+		if (loop == -1) {
+			return 1;
+		}
+		source = loop_path;
+	}
+	int ret = mount(source, av[optind + 1], type, flags, NULL);
 	if (ret == -1) {
 		perror("mount");
+		// Do not leave the image attached
+		if (loop != -1) {
+			ioctl(loop, LOOP_CLR_FD, 0);
+		}
+	}
+	if (loop != -1) {
+		close(loop);
 	}
 	return 0;
 }
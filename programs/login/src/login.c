@@ -3,11 +3,15 @@
 #include <stdlib.h>
 #include <stdint.h>
 #include <errno.h>
+#include <sched.h>
 #include <sys/wait.h>
 #include <string.h>
 
 # define BIN_NAME "login"
 
+/* The real-time priority of the tty consumers, as in session_manager */
+# define TTY_RT_PRIORITY 1
+
 # define err_errno(format, ...) do {						\
 		dprintf(2, BIN_NAME ": " format ": %s\n" __VA_OPT__(,) __VA_ARGS__, strerror(errno)); \
 		exit(EXIT_FAILURE);					\
@@ -28,6 +32,13 @@ int worker(char **envp)
 		printf("'%s'\n", login);
 	}
 
+	// The shell reads the tty in the real-time class of the session,
+	// which the fork of this worker reset
+	struct sched_param param = {.sched_priority = TTY_RT_PRIORITY};
+	if (-1 == sched_setscheduler(0, SCHED_RR | SCHED_RESET_ON_FORK, &param)) {
+		perror("Failed to set the scheduling policy");
+	}
+
 	char	*bin = "/bin/su";
 	execve(bin, (char*[]){bin, "-l", "-s", "/bin/dash", login, NULL}, envp);
 	perror("Failed to execute su");
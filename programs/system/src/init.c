@@ -4,9 +4,10 @@
 #include <stdlib.h>
 #include <fcntl.h>
 
+// init never acquires a controlling terminal
 int open_tty_device(const char *tty_device)
 {
-	int fd = open(tty_device, O_RDWR);
+	int fd = open(tty_device, O_RDWR | O_NOCTTY);
 	if (fd < 0) {
 		exit(1);
 	}
@@ -18,9 +19,7 @@ int open_tty_device(const char *tty_device)
 int insmod(const char *modname, char **envp) {
 	pid_t pid = fork();
 	if (pid < 0) {
-		/* #[allow(unused)] */
-		int _fd = open_tty_device("/dev/tty1");
-		(void)_fd;
+		open_tty_device("/dev/tty1");
 		perror("fork failed");
 		exit(1);
 	} else if (pid == 0) {
@@ -30,9 +29,7 @@ int insmod(const char *modname, char **envp) {
 		argv[2] = NULL;
 		int ret = execve("/bin/insmod", argv, envp);
 		if (ret < 0) {
-			/* #[allow(unused)] */
-			int _fd = open_tty_device("/dev/tty1");
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			perror("execve failed");
 			exit(1);
 		}
@@ -41,9 +38,7 @@ int insmod(const char *modname, char **envp) {
 
 		pid_t ret = wait(&status);
 		if (ret < 0) {
-			/* #[allow(unused)] */
-			int _fd = open_tty_device("/dev/tty1");
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			perror("init wait failed");
 			exit(1);
 		}
@@ -59,23 +54,18 @@ int main(int argc, char **argv, char **envp)
 
 	pid_t pid = fork();
 	if (pid < 0) {
-		/* #[allow(unused)] */
-		int _fd = open_tty_device("/dev/tty1");
-		(void)_fd;
+		open_tty_device("/dev/tty1");
 		perror("fork failed");
 		exit(1);
 	} else if (pid == 0) {
 		if (argc < 2) {
-			int _fd = open_tty_device("/dev/tty1");
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			dprintf(STDERR_FILENO, "ArgC must be at least 2\n");
 			exit(1);
 		}
 		int ret = execve(argv[1], argv + 1, envp);
 		if (ret < 0) {
-		/* #[allow(unused)] */
-			int _fd = open_tty_device("/dev/tty1");
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			perror("execve failed");
 			exit(1);
 		}
@@ -85,9 +75,7 @@ int main(int argc, char **argv, char **envp)
 
 			pid_t ret = wait(&status);
 			if (ret < 0) {
-				/* #[allow(unused)] */
-				int _fd = open_tty_device("/dev/tty1");
-				(void)_fd;
+				open_tty_device("/dev/tty1");
 				perror("init wait failed");
 				exit(1);
 			}
@@ -3,6 +3,7 @@
 #include <unistd.h>
 #include <stdlib.h>
 #include <fcntl.h>
+#include <sched.h>
 #include <stdbool.h>
 
 int open_tty_device(const char *tty_device)
@@ -16,30 +17,38 @@ int open_tty_device(const char *tty_device)
 	return fd;
 }
 
+/* The real-time priority of the tty consumers, the lowest one */
+#define TTY_RT_PRIORITY 1
+
 pid_t init_forker(const char *tty_device, int argc, char *argv[], char *envp[])
 {
 	pid_t pid = fork();
 	if (pid < 0) {
-		/* #[allow(unused)] */
-		int _fd = open_tty_device(tty_device);
-		(void)_fd;
+		open_tty_device(tty_device);
 		perror("fork failed");
 		return -1;
 	} else if (pid == 0) {
+		// The child leads a new session, which acquires the tty as
+		// controlling terminal with its process group in the foreground
+		if (setsid() < 0) {
+			open_tty_device(tty_device);
+			perror("setsid failed");
+			return -1;
+		}
 		int fd = open_tty_device(tty_device);
 
+		// The shell of the tty reads the keyboard in a real-time
+		// class, to stay responsive under load. The jobs it starts
+		// are time sharing ones again.
+		struct sched_param param = {.sched_priority = TTY_RT_PRIORITY};
+		if (sched_setscheduler(0, SCHED_RR | SCHED_RESET_ON_FORK, &param) < 0) {
+			perror("sched_setscheduler failed");
+		}
+
 		if (argc < 2) {
 			dprintf(fd, "bad argument number %i: should be at least 2\n", argc);
 			return -1;
 		}
-		if (setpgid(0, 0) < 0) {
-			perror("setpgid failed");
-			return -1;
-		}
-		if (tcsetpgrp(fd, getpgid(0)) < 0) {
-			perror("tcsetpgrp failed");
-			return -1;
-		}
 		printf("argc: %i -> self: %s to_execve: %s to_tty: %s\n", argc, argv[0], argv[1], tty_device);
 		char *shell = getenv("SHELL");
 		int ret;
@@ -83,9 +92,7 @@ int main(int argc, char **argv, char **envp)
 		pid_t ret = wait(&status);
 
 		if (ret < 0) {
-			int _fd = open_tty_device("/dev/tty1");
-
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			perror("session manager wait failed");
 			while (1) {}
 		}
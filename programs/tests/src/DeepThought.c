@@ -113,15 +113,20 @@ static struct program_test TEST_PROGRAMS[] = {
 	{.path = "/bin/DeepTests/dir/mkdir"},
 	{.path = "/bin/DeepTests/dir/mkdir_exist"},
 	{.path = "/bin/DeepTests/dir/rmdir_not_empty"},
+	{.path = "/bin/DeepTests/dir/at_family"},
 	{.path = "/bin/DeepTests/write/create_write_read"},
 	{.path = "/bin/DeepTests/access/access"},
 	{.path = "/bin/DeepTests/signal/SignalSimple"},
 	{.path = "/bin/DeepTests/signal/SignalSimpleDuo"},
+	{.path = "/bin/DeepTests/signal/sigaltstack"},
+	{.path = "/bin/DeepTests/signal/rt_signals"},
 	{.path = "/bin/DeepTests/ProcessGroup"},
 	{.path = "/bin/DeepTests/execve/argv"},
 	{.path = "/bin/DeepTests/execve/cannot_exec_directory"},
 	{.path = "/bin/DeepTests/wait/wait"},
 	{.path = "/bin/DeepTests/wait/wuntraced"},
+	{.path = "/bin/DeepTests/wait/waitid"},
+	{.path = "/bin/DeepTests/wait/rusage"},
 	{.path = "/bin/DeepTests/mprotect/mprotect"},
 	{.path = "/bin/DeepTests/mmap/mmap"},
 	{.path = "/bin/DeepTests/atexit/atexit"},
@@ -131,13 +136,37 @@ static struct program_test TEST_PROGRAMS[] = {
 	{.path = "/bin/DeepTests/pipe/pipe_fucker"},
 	{.path = "/bin/DeepTests/pipe/pipe_fister"},
 	{.path = "/bin/DeepTests/pipe/pipe_lorem_ipsum"},
+	{.path = "/bin/DeepTests/pipe/writev_atomic"},
 	{.path = "/bin/DeepTests/math/roundf"},
 	{.path = "/bin/DeepTests/math/pow"},
 	{.path = "/bin/DeepTests/ctype/longlong"},
 	{.path = "/bin/DeepTests/lseek/sda"},
 	{.path = "/bin/DeepTests/lseek/lseek_return"},
 	{.path = "/bin/DeepTests/dirent/dummy_root"},
+	{.path = "/bin/DeepTests/dirent/getdents_unlink"},
 	{.path = "/bin/DeepTests/read/read_pulp_fiction"},
+	{.path = "/bin/DeepTests/read/pread_pwrite"},
+	{.path = "/bin/DeepTests/truncate/truncate"},
+	{.path = "/bin/DeepTests/lock/flock"},
+	{.path = "/bin/DeepTests/lock/fcntl_lock"},
+	{.path = "/bin/DeepTests/chroot/chroot"},
+	{.path = "/bin/DeepTests/mount/bind"},
+	{.path = "/bin/DeepTests/inotify/inotify"},
+	{.path = "/bin/DeepTests/xattr/xattr"},
+	{.path = "/bin/DeepTests/fd/cloexec"},
+	{.path = "/bin/DeepTests/fd/table"},
+	{.path = "/bin/DeepTests/execve/setuid"},
+	{.path = "/bin/DeepTests/execve/lazy_load"},
+	{.path = "/bin/DeepTests/execve/rewrite"},
+	{.path = "/bin/DeepTests/execve/etxtbsy"},
+	{.path = "/bin/DeepTests/execve/dynamic"},
+	{.path = "/bin/DeepTests/execve/script"},
+	{.path = "/bin/DeepTests/execve/aslr"},
+	{.path = "/bin/DeepTests/execve/vfork"},
+	{.path = "/bin/DeepTests/execve/spawn"},
+	{.path = "/bin/DeepTests/sched/priority"},
+	{.path = "/bin/DeepTests/sched/interactivity"},
+	{.path = "/bin/DeepTests/session/session"},
 	{.path = "/bin/DeepTests/execl/execl"},
 	{.path = "/bin/DeepTests/is_str_bullshit/is_str_bullshit"},
 	{.path = "/bin/DeepTests/umask/umask"},
@@ -34,19 +34,20 @@ pid_t init_forker(const char *tty_device, int argc, char *argv[], char *envp[])
 {
 	pid_t pid = fork();
 	if (pid < 0) {
-		/* #[allow(unused)] */
-		int _fd = open_tty_device(tty_device);
-		(void)_fd;
+		open_tty_device(tty_device);
 		perror("fork failed");
 		exit(1);
 	} else if (pid == 0) {
-		int fd = open_tty_device(tty_device);
-		if (setpgid(0, 0) < 0) {
-			perror("setpgid failed");
+		// The child leads a new session, which acquires the tty as
+		// controlling terminal with its process group in the foreground
+		if (setsid() < 0) {
+			open_tty_device(tty_device);
+			perror("setsid failed");
 			exit(1);
 		}
-		if (tcsetpgrp(fd, getpgid(0)) < 0) {
-			perror("tcsetpgrp failed");
+		int fd = open_tty_device(tty_device);
+		if (tcgetpgrp(fd) != getpgid(0)) {
+			perror("tcgetpgrp failed");
 			exit(1);
 		}
 		printf("argc: %i -> self: %s to_execve: %s to_tty: %s\n", argc, argv[0], argv[1], tty_device);
@@ -78,15 +79,12 @@ int main(int argc, char **argv, char **envp)
 	for (int i = 0; i < 4; i++) {
 		pid_t ret = wait(&status);
 		if (ret < 0) {
-			int _fd = open_tty_device("/dev/tty1");
-
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			perror("init wait failed");
 			exit(1);
 		}
 		if (status != 0) {
-			int _fd = open_tty_device("/dev/tty1");
-			(void)_fd;
+			open_tty_device("/dev/tty1");
 			dprintf(STDERR_FILENO, "Instance %i failed !\n", i);
 			failure = true;
 		}
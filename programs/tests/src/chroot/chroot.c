@@ -0,0 +1,112 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+/*
+ * After chroot(), the absolute paths, the `..` and the absolute
+ * symbolic links are resolved inside the new root, which is inherited
+ * by the children and which follows the renames of the directory
+ */
+int main(void)
+{
+	char jail[64];
+	char moved[64];
+	char path[128];
+	char buf[128];
+	struct stat st;
+	struct stat root;
+	int to_parent[2];
+	int to_child[2];
+
+	snprintf(jail, sizeof(jail), "chroot_%d", getpid());
+	assert(mkdir(jail, 0755) == 0);
+	snprintf(path, sizeof(path), "%s/file", jail);
+	int fd = open(path, O_CREAT | O_WRONLY, 0644);
+	assert(fd >= 0);
+	assert(write(fd, "jail", 4) == 4);
+	close(fd);
+	snprintf(path, sizeof(path), "%s/abs", jail);
+	assert(symlink("/file", path) == 0);
+	snprintf(path, sizeof(path), "%s/file", jail);
+	assert(chroot(path) == -1 && errno == ENOTDIR);
+
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(chroot(jail) == 0);
+		// The working directory is outside of the new root
+		assert(getcwd(buf, sizeof(buf)) == NULL && errno == ENOENT);
+
+		assert(chdir("/") == 0);
+		assert(getcwd(buf, sizeof(buf)) != NULL && strcmp(buf, "/") == 0);
+		assert(chdir("..") == 0);
+		assert(getcwd(buf, sizeof(buf)) != NULL && strcmp(buf, "/") == 0);
+
+		fd = open("/../../file", O_RDONLY);
+		assert(fd >= 0);
+		assert(read(fd, buf, 4) == 4 && memcmp(buf, "jail", 4) == 0);
+		close(fd);
+		assert(stat("/abs", &st) == 0 && st.st_size == 4);
+		assert(access("/bin", F_OK) == -1 && errno == ENOENT);
+
+		pid_t grandchild = fork();
+		assert(grandchild >= 0);
+		if (grandchild == 0) {
+			assert(access("/file", F_OK) == 0);
+			assert(setuid(1000) == 0);
+			assert(chroot("/") == -1 && errno == EPERM);
+			exit(0);
+		}
+		wait_child(grandchild);
+		exit(0);
+	}
+	wait_child(pid);
+
+	// The jail is renamed while a child is inside
+	assert(pipe(to_parent) == 0 && pipe(to_child) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(chroot(jail) == 0);
+		assert(chdir("/") == 0);
+		assert(write(to_parent[1], "", 1) == 1);
+		assert(read(to_child[0], buf, 1) == 1);
+
+		fd = open("/file", O_RDONLY);
+		assert(fd >= 0);
+		assert(read(fd, buf, 4) == 4 && memcmp(buf, "jail", 4) == 0);
+		close(fd);
+		assert(access("file", F_OK) == 0);
+		assert(stat("/abs", &st) == 0 && st.st_size == 4);
+		assert(stat("/", &root) == 0);
+		assert(stat("/..", &st) == 0 && st.st_ino == root.st_ino);
+		assert(getcwd(buf, sizeof(buf)) != NULL && strcmp(buf, "/") == 0);
+		exit(0);
+	}
+	snprintf(moved, sizeof(moved), "%s_moved", jail);
+	assert(read(to_parent[0], buf, 1) == 1);
+	assert(rename(jail, moved) == 0);
+	assert(write(to_child[1], "", 1) == 1);
+	wait_child(pid);
+
+	snprintf(path, sizeof(path), "%s/abs", moved);
+	assert(unlink(path) == 0);
+	snprintf(path, sizeof(path), "%s/file", moved);
+	assert(unlink(path) == 0);
+	assert(rmdir(moved) == 0);
+	return 0;
+}
@@ -0,0 +1,84 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * The *at() syscalls resolve the relative paths from a directory file
+ * descriptor, which keeps designating the directory when it is renamed
+ */
+int main(void)
+{
+	char dirname[64];
+	char renamed[64];
+	char path[128];
+	char buf[64];
+	struct stat st;
+
+	snprintf(dirname, sizeof(dirname), "dir_at_family_%d", getpid());
+	snprintf(renamed, sizeof(renamed), "dir_at_family_renamed_%d", getpid());
+	assert(mkdirat(AT_FDCWD, dirname, 0755) == 0);
+
+	int dir = open(dirname, O_RDONLY | O_DIRECTORY);
+	assert(dir >= 0);
+
+	// The directory is found through the fd after its rename
+	assert(rename(dirname, renamed) == 0);
+
+	int fd = openat(dir, "file", O_CREAT | O_WRONLY, 0644);
+	assert(fd >= 0);
+	assert(write(fd, "42", 2) == 2);
+	assert(fstatat(fd, "", &st, AT_EMPTY_PATH) == 0);
+	assert(S_ISREG(st.st_mode) && st.st_size == 2);
+	// A regular file is not a directory
+	assert(openat(fd, "file", O_RDONLY) == -1 && errno == ENOTDIR);
+	close(fd);
+
+	snprintf(path, sizeof(path), "%s/file", renamed);
+	assert(access(path, F_OK) == 0);
+
+	assert(mkdirat(dir, "sub", 0755) == 0);
+	assert(fstatat(dir, "sub", &st, 0) == 0 && S_ISDIR(st.st_mode));
+
+	// AT_SYMLINK_NOFOLLOW stats the link itself
+	snprintf(path, sizeof(path), "%s/link", renamed);
+	assert(symlink("file", path) == 0);
+	assert(fstatat(dir, "link", &st, 0) == 0 && S_ISREG(st.st_mode));
+	assert(fstatat(dir, "link", &st, AT_SYMLINK_NOFOLLOW) == 0 && S_ISLNK(st.st_mode));
+	ssize_t len = readlinkat(dir, "link", buf, sizeof(buf));
+	assert(len == 4 && memcmp(buf, "file", 4) == 0);
+
+	// Rename between two directory fds
+	int sub = openat(dir, "sub", O_RDONLY | O_DIRECTORY);
+	assert(sub >= 0);
+	assert(renameat(dir, "file", sub, "moved") == 0);
+	assert(fstatat(sub, "moved", &st, 0) == 0 && st.st_size == 2);
+	assert(fstatat(dir, "file", &st, 0) == -1 && errno == ENOENT);
+
+	// fchdir, the relative paths now start from sub
+	char cwd[256];
+	assert(getcwd(cwd, sizeof(cwd)) != NULL);
+	assert(fchdir(sub) == 0);
+	fd = open("moved", O_RDONLY);
+	assert(fd >= 0);
+	assert(fchdir(fd) == -1 && errno == ENOTDIR);
+	close(fd);
+	assert(fchdir(-1) == -1 && errno == EBADF);
+	assert(chdir(cwd) == 0);
+
+	// unlinkat removes files, or directories with AT_REMOVEDIR
+	assert(unlinkat(dir, "sub", 0) == -1 && errno == EISDIR);
+	assert(unlinkat(sub, "moved", 0) == 0);
+	close(sub);
+	assert(unlinkat(dir, "link", 0) == 0);
+	assert(unlinkat(dir, "sub", AT_REMOVEDIR) == 0);
+	assert(unlinkat(dir, "sub", AT_REMOVEDIR) == -1 && errno == ENOENT);
+	close(dir);
+
+	assert(unlinkat(AT_FDCWD, renamed, AT_REMOVEDIR) == 0);
+	return EXIT_SUCCESS;
+}
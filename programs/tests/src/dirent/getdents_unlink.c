@@ -0,0 +1,111 @@
+#include <dirent.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define NB_FILE 32
+
+/*
+ * Read a directory with a small getdents64 buffer while files are
+ * created, renamed and removed in it: every file which stays in the
+ * directory must be seen exactly once
+ */
+int main(void)
+{
+	char dirname[64];
+	char filename[128];
+	int seen[NB_FILE] = {0};
+	int seen_dot = 0;
+	int seen_dotdot = 0;
+
+	snprintf(dirname, sizeof(dirname), "dir_getdents_unlink_%d", getpid());
+	assert(mkdir(dirname, 0755) == 0);
+	for (int i = 0; i < NB_FILE; i++) {
+		snprintf(filename, sizeof(filename), "%s/stable_%d", dirname, i);
+		int fd = open(filename, O_CREAT | O_WRONLY, 0644);
+		assert(fd >= 0);
+		close(fd);
+		snprintf(filename, sizeof(filename), "%s/doomed_%d", dirname, i);
+		fd = open(filename, O_CREAT | O_WRONLY, 0644);
+		assert(fd >= 0);
+		close(fd);
+	}
+
+	int dir = open(dirname, O_RDONLY | O_DIRECTORY);
+	assert(dir >= 0);
+
+	// Not even one record fits
+	char tiny[8];
+	assert(getdents64(dir, tiny, sizeof(tiny)) == -1 && errno == EINVAL);
+
+	char buf[96];
+	ssize_t size;
+	int round = 0;
+	while ((size = getdents64(dir, buf, sizeof(buf))) > 0) {
+		for (ssize_t offset = 0; offset < size; ) {
+			struct dirent64 *record = (struct dirent64 *)&buf[offset];
+			int i;
+
+			if (strcmp(record->d_name, ".") == 0) {
+				assert(record->d_type == DT_DIR);
+				seen_dot++;
+			} else if (strcmp(record->d_name, "..") == 0) {
+				assert(record->d_type == DT_DIR);
+				seen_dotdot++;
+			} else if (sscanf(record->d_name, "stable_%d", &i) == 1) {
+				assert(record->d_type == DT_REG);
+				seen[i]++;
+			}
+			offset += record->d_reclen;
+		}
+		// Shuffle the directory between two reads
+		if (round < NB_FILE) {
+			snprintf(filename, sizeof(filename), "%s/doomed_%d", dirname, round);
+			assert(unlink(filename) == 0);
+			snprintf(filename, sizeof(filename), "%s/new_%d", dirname, round);
+			int fd = open(filename, O_CREAT | O_WRONLY, 0644);
+			assert(fd >= 0);
+			close(fd);
+			// A renamed entry moves in the filename order
+			char newname[128];
+			snprintf(newname, sizeof(newname), "%s/a_renamed_%d", dirname, round);
+			assert(rename(filename, newname) == 0);
+			round++;
+		}
+	}
+	assert(size == 0);
+	assert(seen_dot == 1);
+	assert(seen_dotdot == 1);
+	for (int i = 0; i < NB_FILE; i++) {
+		assert(seen[i] == 1);
+	}
+
+	// A directory can neither be read nor written as a file
+	assert(read(dir, buf, sizeof(buf)) == -1 && errno == EISDIR);
+	assert(open(dirname, O_RDWR) == -1 && errno == EISDIR);
+
+	// The rewind restarts from the first entry
+	assert(lseek(dir, 0, SEEK_SET) == 0);
+	assert(getdents64(dir, buf, sizeof(buf)) > 0);
+	close(dir);
+
+	// Cleanup with readdir, removing the entries while reading them
+	DIR *dirp = opendir(dirname);
+	assert(dirp != NULL);
+	assert(chdir(dirname) == 0);
+	struct dirent *entry;
+	while ((entry = readdir(dirp)) != NULL) {
+		if (strcmp(entry->d_name, ".") && strcmp(entry->d_name, "..")) {
+			assert(unlink(entry->d_name) == 0);
+		}
+	}
+	assert(closedir(dirp) == 0);
+	assert(chdir("..") == 0);
+	assert(rmdir(dirname) == 0);
+	return EXIT_SUCCESS;
+}
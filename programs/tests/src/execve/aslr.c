@@ -0,0 +1,117 @@
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/personality.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/aslr"
+
+#define RUNS 4
+#define STATIC_RUNS 32
+
+/*
+ * Each new process image gets its stack and its mappings at random
+ * addresses, unless its persona contains ADDR_NO_RANDOMIZE. A static
+ * executable, loaded where it is linked, gets its mappings above it.
+ */
+
+/* The end of the image, defined by the linker */
+extern char _end;
+
+struct layout {
+	unsigned long stack;
+	unsigned long mmap;
+	int personality;
+};
+
+/*
+ * Run in the new process image: send its layout to the parent
+ */
+static int report(int fd)
+{
+	struct layout layout;
+	char local;
+	void *map = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+
+	assert(map != MAP_FAILED);
+	assert((unsigned long)map >= (unsigned long)&_end);
+	layout.stack = (unsigned long)&local;
+	layout.mmap = (unsigned long)map;
+	layout.personality = personality(0xffffffff);
+	assert(write(fd, &layout, sizeof(layout)) == sizeof(layout));
+	return 0;
+}
+
+/* Exec a child with `persona`, return the layout it reports */
+static struct layout exec_child(int persona)
+{
+	struct layout layout;
+	int fds[2];
+	int status;
+	pid_t pid;
+
+	assert(pipe(fds) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		char fd[16];
+		char *args[] = {SELF, "report", fd, NULL};
+		char *env[] = {NULL};
+
+		assert(close(fds[0]) == 0);
+		snprintf(fd, sizeof(fd), "%d", fds[1]);
+		assert(personality(persona) != -1);
+		execve(SELF, args, env);
+		perror("execve");
+		exit(1);
+	}
+	assert(close(fds[1]) == 0);
+	assert(read(fds[0], &layout, sizeof(layout)) == sizeof(layout));
+	assert(close(fds[0]) == 0);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	return layout;
+}
+
+int main(int argc, char **argv)
+{
+	struct layout layouts[RUNS];
+	int stack_moved = 0;
+	int mmap_moved = 0;
+
+	if (argc == 3 && strcmp(argv[1], "report") == 0) {
+		return report(atoi(argv[2]));
+	}
+
+	// Only ADDR_NO_RANDOMIZE is known
+	assert(personality(0xffffffff) == PER_LINUX);
+	assert(personality(0x42) == -1 && errno == EINVAL);
+	assert(personality(0xffffffff) == PER_LINUX);
+
+	for (int i = 0; i < RUNS; i++) {
+		layouts[i] = exec_child(PER_LINUX);
+		assert(layouts[i].personality == PER_LINUX);
+		if (layouts[i].stack != layouts[0].stack)
+			stack_moved = 1;
+		if (layouts[i].mmap != layouts[0].mmap)
+			mmap_moved = 1;
+	}
+	assert(stack_moved && mmap_moved);
+
+	// Whatever the random offsets, the static image is always loaded
+	for (int i = 0; i < STATIC_RUNS; i++)
+		exec_child(PER_LINUX);
+
+	// The persona is kept across execve, the layout no longer moves
+	for (int i = 0; i < RUNS; i++) {
+		layouts[i] = exec_child(ADDR_NO_RANDOMIZE);
+		assert(layouts[i].personality == ADDR_NO_RANDOMIZE);
+		assert(layouts[i].stack == layouts[0].stack);
+		assert(layouts[i].mmap == layouts[0].mmap);
+	}
+	return 0;
+}
@@ -0,0 +1,66 @@
+#include <elf.h>
+#include <errno.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * This program is linked against libc.so: the kernel loads its
+ * interpreter, ld.so, which loads and relocates the libc before
+ * entering the program.
+ */
+
+static bool constructed = false;
+
+__attribute__((constructor)) static void constructor(void)
+{
+	constructed = true;
+}
+
+static void check_auxv(void)
+{
+	const Elf32_Phdr *phdr = (const Elf32_Phdr *)getauxval(AT_PHDR);
+	bool has_interp = false;
+
+	assert(phdr != NULL);
+	assert(getauxval(AT_PHENT) == sizeof(Elf32_Phdr));
+	for (unsigned long i = 0; i < getauxval(AT_PHNUM); i++)
+		if (phdr[i].p_type == PT_INTERP)
+			has_interp = true;
+	assert(has_interp);
+
+	/* The interpreter is mapped and the program entered at its own entry */
+	assert(getauxval(AT_BASE) != 0);
+	assert(getauxval(AT_ENTRY) != 0);
+	assert(getauxval(AT_PAGESZ) == 4096);
+	assert(getauxval(AT_RANDOM) != 0);
+	assert(getauxval(AT_UID) == getuid());
+	assert(getauxval(AT_EUID) == geteuid());
+	assert(getauxval(AT_GID) == getgid());
+	assert(getauxval(AT_EGID) == getegid());
+	assert(getauxval(AT_SECURE) == 0);
+}
+
+int main(int argc, char **argv)
+{
+	assert(argc >= 1 && argv[0] != NULL);
+	assert(constructed);
+	check_auxv();
+
+	/* The program and libc.so share the same errno and environ */
+	errno = 0;
+	assert(close(-1) == -1);
+	assert(errno == EBADF);
+	assert(setenv("DYNAMIC", "yes", 1) == 0);
+	assert(strcmp(getenv("DYNAMIC"), "yes") == 0);
+
+	char *s = strdup("dynamic");
+	assert(s != NULL && strcmp(s, "dynamic") == 0);
+	free(s);
+	assert(printf("%s %d\n", "loaded by ld.so", 42) == 19);
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,102 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/etxtbsy"
+
+/*
+ * An executable which a process runs cannot be opened for writing nor
+ * truncated: ETXTBSY. It can be written again once no process maps it.
+ */
+
+static char *read_file(const char *path, size_t *len)
+{
+	struct stat st;
+	char *content;
+	int fd = open(path, O_RDONLY);
+
+	assert(fd >= 0);
+	assert(fstat(fd, &st) == 0);
+	content = malloc(st.st_size);
+	assert(content != NULL);
+	assert(read(fd, content, st.st_size) == st.st_size);
+	close(fd);
+	*len = st.st_size;
+	return content;
+}
+
+static void assert_busy(int ret)
+{
+	assert(ret == -1 && errno == ETXTBSY);
+}
+
+int main(int argc, char **argv)
+{
+	char copy[64];
+	char *content;
+	size_t len;
+	sigset_t set;
+	pid_t runner;
+	int sig;
+	int fd;
+
+	if (argc == 2 && strcmp(argv[1], "pause") == 0) {
+		kill(getppid(), SIGUSR1);
+		while (1)
+			pause();
+	}
+
+	// The image of this process
+	assert_busy(open(SELF, O_WRONLY));
+	assert_busy(open(SELF, O_RDWR));
+	assert_busy(open(SELF, O_RDONLY | O_TRUNC));
+	assert_busy(truncate(SELF, 0));
+	fd = open(SELF, O_RDONLY);
+	assert(fd >= 0);
+	close(fd);
+
+	// A copy, kept open for writing before it runs
+	snprintf(copy, sizeof(copy), "etxtbsy_%d", getpid());
+	content = read_file(SELF, &len);
+	fd = open(copy, O_WRONLY | O_CREAT | O_TRUNC | O_CLOEXEC, 0755);
+	assert(fd >= 0);
+	assert(write(fd, content, len) == (ssize_t)len);
+
+	sigemptyset(&set);
+	sigaddset(&set, SIGUSR1);
+	assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+	runner = fork();
+	assert(runner >= 0);
+	if (runner == 0) {
+		char *args[] = {copy, "pause", NULL};
+
+		execve(copy, args, NULL);
+		exit(127);
+	}
+	assert(sigwait(&set, &sig) == 0 && sig == SIGUSR1);
+
+	assert_busy(open(copy, O_WRONLY));
+	assert_busy(open(copy, O_RDWR | O_TRUNC));
+	assert_busy(truncate(copy, 0));
+	assert_busy(ftruncate(fd, 0));
+
+	// The last mapping is gone with the runner
+	assert(kill(runner, SIGKILL) == 0);
+	assert(waitpid(runner, NULL, 0) == runner);
+	assert(ftruncate(fd, 0) == 0);
+	close(fd);
+	fd = open(copy, O_RDWR | O_TRUNC);
+	assert(fd >= 0);
+	close(fd);
+
+	assert(unlink(copy) == 0);
+	free(content);
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,116 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define PAGE 4096
+
+/*
+ * The segments of a program are loaded on demand: every page of these
+ * objects is only read from the executable, or zero-filled, when it is
+ * first touched.
+ */
+
+static char bss[256 * PAGE];
+
+static unsigned char data[3 * PAGE] = {
+	[0] = 1,
+	[PAGE] = 2,
+	[3 * PAGE - 1] = 3,
+};
+
+static const char rodata[2 * PAGE] __attribute__((aligned(PAGE))) = {
+	[0] = 'r',
+	[PAGE] = 'o',
+	[PAGE + 1] = 'd',
+	[PAGE + 2] = 'a',
+	[PAGE + 3] = 't',
+	[PAGE + 4] = 'a',
+};
+
+static int wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	return status;
+}
+
+static void check_bss(void)
+{
+	for (size_t i = 0; i < sizeof(bss); i++)
+		assert(bss[i] == 0);
+	memset(bss, 42, sizeof(bss));
+	assert(bss[0] == 42 && bss[sizeof(bss) - 1] == 42);
+}
+
+static void check_data(void)
+{
+	assert(data[0] == 1);
+	assert(data[PAGE] == 2);
+	assert(data[3 * PAGE - 1] == 3);
+	assert(data[PAGE + 1] == 0);
+	data[PAGE + 1] = 4;
+	assert(data[PAGE + 1] == 4);
+}
+
+/*
+ * The kernel reads a page of the executable which the process never
+ * touched
+ */
+static void check_syscall_on_untouched_page(void)
+{
+	int fds[2];
+	char buf[5];
+
+	assert(pipe(fds) == 0);
+	assert(write(fds[1], rodata + PAGE + 1, 4) == 4);
+	assert(read(fds[0], buf, 4) == 4);
+	assert(memcmp(buf, "data", 4) == 0);
+	close(fds[0]);
+	close(fds[1]);
+}
+
+int main(void)
+{
+	pid_t pid;
+	int status;
+
+	check_bss();
+	check_data();
+	check_syscall_on_untouched_page();
+
+	/* The text is read-only */
+	pid = fork();
+	assert(pid != -1);
+	if (pid == 0) {
+		*(volatile char *)rodata = 'w';
+		exit(1);
+	}
+	status = wait_child(pid);
+	assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGSEGV);
+
+	/* A child sees the pages of its parent and its own private copy */
+	pid = fork();
+	assert(pid != -1);
+	if (pid == 0) {
+		assert(bss[PAGE] == 42);
+		assert(data[PAGE + 1] == 4);
+		assert(rodata[0] == 'r');
+		data[PAGE + 1] = 5;
+		assert(mprotect((void *)rodata, PAGE, PROT_READ | PROT_WRITE) == 0);
+		*(volatile char *)rodata = 'w';
+		assert(*(volatile const char *)rodata == 'w');
+		exit(0);
+	}
+	status = wait_child(pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(data[PAGE + 1] == 4);
+	assert(*(volatile const char *)rodata == 'r');
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,132 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/rewrite"
+#define PAGE 4096
+
+/*
+ * Replacing an executable which a process still runs, by renaming a
+ * new file over it, leaves the old image to that process: the next
+ * exec runs the new content. It cannot be rewritten in place.
+ */
+
+static const char marker[2 * PAGE] __attribute__((aligned(PAGE))) =
+	"rewrite marker A";
+
+static char *read_file(const char *path, size_t *len)
+{
+	struct stat st;
+	char *content;
+	int fd = open(path, O_RDONLY);
+
+	assert(fd >= 0);
+	assert(fstat(fd, &st) == 0);
+	content = malloc(st.st_size);
+	assert(content != NULL);
+	assert(read(fd, content, st.st_size) == st.st_size);
+	close(fd);
+	*len = st.st_size;
+	return content;
+}
+
+static void write_file(const char *path, const char *content, size_t len)
+{
+	int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0755);
+
+	assert(fd >= 0);
+	assert(write(fd, content, len) == (ssize_t)len);
+	close(fd);
+}
+
+/* The marker in the content of the executable */
+static char *find_marker(char *content, size_t len)
+{
+	size_t marker_len = strlen(marker);
+
+	for (size_t i = 0; i + marker_len <= len; i++) {
+		if (memcmp(content + i, marker, marker_len) == 0)
+			return content + i;
+	}
+	return NULL;
+}
+
+static pid_t run(const char *path, char *mode)
+{
+	char *argv[] = {(char *)path, mode, NULL};
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		execve(path, argv, NULL);
+		exit(127);
+	}
+	return pid;
+}
+
+static int exit_status(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status));
+	return WEXITSTATUS(status);
+}
+
+int main(int argc, char **argv)
+{
+	char copy[64];
+	char replacement[64];
+	char *content;
+	char *found;
+	size_t len;
+	sigset_t set;
+	pid_t runner;
+	int sig;
+
+	if (argc == 2) {
+		// Report the marker of the image which runs
+		int version = *(volatile const char *)(marker + 15);
+
+		if (strcmp(argv[1], "pause") == 0) {
+			kill(getppid(), SIGUSR1);
+			while (1)
+				pause();
+		}
+		return version;
+	}
+
+	snprintf(copy, sizeof(copy), "rewrite_%d", getpid());
+	content = read_file(SELF, &len);
+	write_file(copy, content, len);
+	assert(exit_status(run(copy, "print")) == 'A');
+
+	// Keep the first image mapped while the file changes
+	sigemptyset(&set);
+	sigaddset(&set, SIGUSR1);
+	assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+	runner = run(copy, "pause");
+	assert(sigwait(&set, &sig) == 0 && sig == SIGUSR1);
+
+	found = find_marker(content, len);
+	assert(found != NULL);
+	found[15] = 'B';
+	assert(open(copy, O_WRONLY | O_TRUNC) == -1 && errno == ETXTBSY);
+	snprintf(replacement, sizeof(replacement), "rewrite_%d.new", getpid());
+	write_file(replacement, content, len);
+	assert(rename(replacement, copy) == 0);
+	assert(exit_status(run(copy, "print")) == 'B');
+
+	assert(kill(runner, SIGKILL) == 0);
+	assert(waitpid(runner, NULL, 0) == runner);
+	assert(unlink(copy) == 0);
+	free(content);
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,143 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/script"
+
+#define OWNER 1000
+#define USER 2000
+
+/*
+ * execve() of a file starting with `#!interpreter [optional-arg]` runs
+ * the interpreter with the argument list `interpreter [optional-arg]
+ * script argv[1]...`. This program is its own interpreter: it checks
+ * its arguments against the EXPECTED environment variable.
+ */
+
+extern char **environ;
+
+static char scripts[5][64];
+
+/* Run in the interpreter: argv joined by '|' must be EXPECTED */
+static int check_interpreted(int argc, char **argv)
+{
+	char joined[512] = "";
+
+	for (int i = 0; i < argc; i++) {
+		if (i != 0)
+			strcat(joined, "|");
+		strcat(joined, argv[i]);
+	}
+	if (strcmp(joined, getenv("EXPECTED")) != 0) {
+		dprintf(2, "got '%s', expected '%s'\n", joined, getenv("EXPECTED"));
+		return 1;
+	}
+	if (getenv("EXPECTED_EUID") != NULL)
+		assert(geteuid() == (uid_t)atoi(getenv("EXPECTED_EUID")));
+	return 0;
+}
+
+static void write_script(const char *path, const char *content)
+{
+	int fd = open(path, O_WRONLY | O_CREAT | O_TRUNC, 0755);
+
+	assert(fd >= 0);
+	assert(write(fd, content, strlen(content)) == (ssize_t)strlen(content));
+	assert(close(fd) == 0);
+}
+
+/* Execute `path` in a child, which exits with the status of the interpreter */
+static void exec_script(const char *path, char **args, const char *expected)
+{
+	pid_t pid = fork();
+	int status;
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		setenv("EXPECTED", expected, 1);
+		execve(path, args, environ);
+		perror("execve");
+		exit(1);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void check_exec_fails(const char *path, int err)
+{
+	char *args[] = {(char *)path, NULL};
+
+	assert(execve(path, args, environ) == -1);
+	assert(errno == err);
+}
+
+int main(int argc, char **argv)
+{
+	char expected[512];
+	char content[128];
+
+	if (getenv("EXPECTED") != NULL) {
+		return check_interpreted(argc, argv);
+	}
+	for (int i = 0; i < 5; i++) {
+		snprintf(scripts[i], sizeof(scripts[i]), "./script_%d_%d", getpid(), i);
+	}
+
+	// The blanks around the optional argument are dropped, not the inner ones
+	write_script(scripts[0], "#!  " SELF "  an  argument \t\necho not read\n");
+	snprintf(expected, sizeof(expected), SELF "|an  argument|%s|one|two", scripts[0]);
+	exec_script(scripts[0], (char *[]){"name", "one", "two", NULL}, expected);
+
+	// Without optional argument, and with an empty argv
+	write_script(scripts[1], "#!" SELF);
+	snprintf(expected, sizeof(expected), SELF "|%s|one", scripts[1]);
+	exec_script(scripts[1], (char *[]){"name", "one", NULL}, expected);
+	snprintf(expected, sizeof(expected), SELF "|%s", scripts[1]);
+	exec_script(scripts[1], (char *[]){NULL}, expected);
+
+	// A script interpreted by a script
+	snprintf(content, sizeof(content), "#!%s\n", scripts[0]);
+	write_script(scripts[2], content);
+	snprintf(expected, sizeof(expected), SELF "|an  argument|%s|%s|one",
+		 scripts[0], scripts[2]);
+	exec_script(scripts[2], (char *[]){"name", "one", NULL}, expected);
+
+	// The set-ID bits of a script are ignored
+	assert(chown(scripts[1], OWNER, OWNER) == 0);
+	assert(chmod(scripts[1], S_ISUID | S_ISGID | 0755) == 0);
+	setenv("EXPECTED_EUID", "2000", 1);
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(setresuid(USER, USER, USER) == 0);
+		snprintf(expected, sizeof(expected), SELF "|%s", scripts[1]);
+		exec_script(scripts[1], (char *[]){"name", NULL}, expected);
+		exit(0);
+	}
+	int status;
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	unsetenv("EXPECTED_EUID");
+
+	// A script interpreting itself loops
+	snprintf(content, sizeof(content), "#!%s\n", scripts[3]);
+	write_script(scripts[3], content);
+	check_exec_fails(scripts[3], ELOOP);
+
+	// No interpreter
+	write_script(scripts[4], "#!/nonexistent/interpreter\n");
+	check_exec_fails(scripts[4], ENOENT);
+	write_script(scripts[4], "#!   \n");
+	check_exec_fails(scripts[4], ENOEXEC);
+
+	for (int i = 0; i < 5; i++) {
+		assert(unlink(scripts[i]) == 0);
+	}
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,162 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/auxv.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/setuid"
+
+#define OWNER 1000
+#define USER 2000
+#define GROUP 3000
+
+/*
+ * execve() of a set-user-ID and set-group-ID file changes the
+ * effective and saved IDs, and tells the new image with AT_SECURE that
+ * its environment was sanitized.
+ */
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void check_resuid(uid_t ruid, uid_t euid, uid_t suid)
+{
+	uid_t r, e, s;
+
+	assert(getresuid(&r, &e, &s) == 0);
+	assert(r == ruid && e == euid && s == suid);
+}
+
+static void check_resgid(gid_t rgid, gid_t egid, gid_t sgid)
+{
+	gid_t r, e, s;
+
+	assert(getresgid(&r, &e, &s) == 0);
+	assert(r == rgid && e == egid && s == sgid);
+}
+
+/*
+ * Run in the new process image
+ */
+static int check_after_exec(int secure)
+{
+	assert(getauxval(AT_SECURE) == (unsigned long)secure);
+	assert(getauxval(AT_UID) == USER);
+	assert(getauxval(AT_GID) == GROUP);
+	assert(getenv("HOME") != NULL);
+	if (secure) {
+		assert(getauxval(AT_EUID) == OWNER);
+		assert(getauxval(AT_EGID) == OWNER);
+		check_resuid(USER, OWNER, OWNER);
+		check_resgid(GROUP, OWNER, OWNER);
+		assert(getenv("LD_PRELOAD") == NULL);
+
+		// Drop the privileges for good
+		assert(setresuid(USER, USER, USER) == 0);
+		check_resuid(USER, USER, USER);
+		assert(setresuid(-1, OWNER, -1) == -1 && errno == EPERM);
+	} else {
+		assert(getauxval(AT_EUID) == USER);
+		assert(getauxval(AT_EGID) == GROUP);
+		check_resuid(USER, USER, USER);
+		check_resgid(GROUP, GROUP, GROUP);
+		assert(getenv("LD_PRELOAD") != NULL);
+	}
+	return 0;
+}
+
+static void test_setresuid(void)
+{
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		// A privileged process sets any ID
+		assert(setresgid(OWNER, USER, GROUP) == 0);
+		check_resgid(OWNER, USER, GROUP);
+		assert(setresuid(OWNER, USER, GROUP) == 0);
+		check_resuid(OWNER, USER, GROUP);
+
+		// An unprivileged one only picks among its current IDs
+		assert(setresuid(-1, OWNER, -1) == 0);
+		check_resuid(OWNER, OWNER, GROUP);
+		assert(setresuid(GROUP, -1, USER) == -1 && errno == EPERM);
+		assert(setresuid(GROUP, -1, OWNER) == 0);
+		check_resuid(GROUP, OWNER, OWNER);
+		assert(setresgid(-1, 42, -1) == -1 && errno == EPERM);
+		assert(setresgid(GROUP, GROUP, -1) == 0);
+		check_resgid(GROUP, GROUP, GROUP);
+		exit(0);
+	}
+	wait_child(pid);
+}
+
+static void copy_file(const char *src, const char *dst)
+{
+	char buf[4096];
+	ssize_t len;
+	int in = open(src, O_RDONLY);
+	int out = open(dst, O_WRONLY | O_CREAT | O_TRUNC, 0755);
+
+	assert(in >= 0 && out >= 0);
+	while ((len = read(in, buf, sizeof(buf))) > 0) {
+		assert(write(out, buf, len) == len);
+	}
+	assert(len == 0);
+	assert(close(in) == 0);
+	assert(close(out) == 0);
+}
+
+static void exec_as_user(const char *path, const char *secure)
+{
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		char *args[] = {(char *)path, "check", (char *)secure, NULL};
+		char *env[] = {"HOME=/", "LD_PRELOAD=/lib/evil.so", NULL};
+
+		assert(setresgid(GROUP, GROUP, GROUP) == 0);
+		assert(setresuid(USER, USER, USER) == 0);
+		execve(path, args, env);
+		perror("execve");
+		exit(1);
+	}
+	wait_child(pid);
+}
+
+int main(int argc, char **argv)
+{
+	char name[64];
+
+	if (argc == 3 && strcmp(argv[1], "check") == 0) {
+		return check_after_exec(atoi(argv[2]));
+	}
+	assert(getuid() == 0);
+	assert(getauxval(AT_SECURE) == 0);
+	assert(getauxval(AT_EUID) == 0);
+	assert(getauxval(AT_NOTELF) == 0 && errno == ENOENT);
+
+	test_setresuid();
+
+	// The set-ID bits are only honored on a copy owned by OWNER
+	exec_as_user(SELF, "0");
+
+	snprintf(name, sizeof(name), "./setuid_%d", getpid());
+	copy_file(SELF, name);
+	assert(chown(name, OWNER, OWNER) == 0);
+	assert(chmod(name, S_ISUID | S_ISGID | 0755) == 0);
+	exec_as_user(name, "1");
+	assert(unlink(name) == 0);
+	return 0;
+}
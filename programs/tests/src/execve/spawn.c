@@ -0,0 +1,100 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <spawn.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/spawn"
+
+#define MESSAGE "spawned"
+
+static void handler(int signum)
+{
+	(void)signum;
+}
+
+/* Run in the spawned image: check what posix_spawn set up */
+static int child(void)
+{
+	struct sigaction act;
+	sigset_t mask;
+
+	assert(write(STDOUT_FILENO, MESSAGE, strlen(MESSAGE)) == (ssize_t)strlen(MESSAGE));
+	assert(sigprocmask(SIG_SETMASK, NULL, &mask) == 0);
+	assert(sigismember(&mask, SIGUSR2) == 1);
+	assert(sigaction(SIGUSR1, NULL, &act) == 0);
+	assert(act.sa_handler == SIG_DFL);
+	assert(getpgid(0) == getpid());
+	return 0;
+}
+
+int main(int argc, char **argv)
+{
+	posix_spawn_file_actions_t actions;
+	posix_spawnattr_t attr;
+	struct sigaction act;
+	char buf[sizeof(MESSAGE)];
+	char *args[] = {SELF, "child", NULL};
+	char *env[] = {NULL};
+	sigset_t mask;
+	short flags;
+	int status;
+	int fds[2];
+	pid_t pid;
+
+	if (argc == 2 && strcmp(argv[1], "child") == 0) {
+		return child();
+	}
+
+	assert(posix_spawnattr_init(&attr) == 0);
+	assert(posix_spawnattr_setflags(&attr, 0x4000) == EINVAL);
+	assert(posix_spawnattr_setflags(&attr, POSIX_SPAWN_SETPGROUP | POSIX_SPAWN_SETSIGMASK) == 0);
+	assert(posix_spawnattr_getflags(&attr, &flags) == 0);
+	assert(flags == (POSIX_SPAWN_SETPGROUP | POSIX_SPAWN_SETSIGMASK));
+	assert(posix_spawnattr_setpgroup(&attr, 0) == 0);
+	sigemptyset(&mask);
+	sigaddset(&mask, SIGUSR2);
+	assert(posix_spawnattr_setsigmask(&attr, &mask) == 0);
+
+	// A caught signal goes back to its default action in the child
+	memset(&act, 0, sizeof(act));
+	act.sa_handler = handler;
+	assert(sigaction(SIGUSR1, &act, NULL) == 0);
+
+	// The standard output of the child is the pipe
+	assert(pipe(fds) == 0);
+	assert(posix_spawn_file_actions_init(&actions) == 0);
+	assert(posix_spawn_file_actions_adddup2(&actions, fds[1], STDOUT_FILENO) == 0);
+	assert(posix_spawn_file_actions_addclose(&actions, fds[0]) == 0);
+	assert(posix_spawn_file_actions_addclose(&actions, fds[1]) == 0);
+	assert(posix_spawn_file_actions_addclose(&actions, -1) == EBADF);
+
+	assert(posix_spawn(&pid, SELF, &actions, &attr, args, env) == 0);
+	assert(close(fds[1]) == 0);
+	memset(buf, 0, sizeof(buf));
+	assert(read(fds[0], buf, sizeof(buf)) == (ssize_t)strlen(MESSAGE));
+	assert(strcmp(buf, MESSAGE) == 0);
+	assert(close(fds[0]) == 0);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// Failures of the child are reported to the caller, the child is reaped
+	errno = 0;
+	assert(posix_spawn(&pid, "/does/not/exist", NULL, NULL, args, env) == ENOENT);
+	assert(errno == 0);
+	assert(posix_spawnp(&pid, "does_not_exist", NULL, NULL, args, env) == ENOENT);
+	assert(posix_spawn_file_actions_destroy(&actions) == 0);
+	assert(posix_spawn_file_actions_init(&actions) == 0);
+	assert(posix_spawn_file_actions_addopen(&actions, 3, "/does/not/exist", O_RDONLY, 0) == 0);
+	assert(posix_spawn(&pid, SELF, &actions, NULL, args, env) == ENOENT);
+	assert(waitpid(-1, NULL, WNOHANG) == -1 && errno == ECHILD);
+
+	assert(posix_spawn_file_actions_destroy(&actions) == 0);
+	assert(posix_spawnattr_destroy(&attr) == 0);
+	return 0;
+}
@@ -0,0 +1,96 @@
+#include <errno.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/execve/vfork"
+
+#define THREAD_FLAGS (CLONE_VM | CLONE_FS | CLONE_FILES | CLONE_SIGHAND | CLONE_THREAD)
+
+static volatile int shared;
+
+static char thread_stack[16384];
+
+/* Run in a thread: replace the whole thread group with a new image */
+static int exec_from_thread(void *fd)
+{
+	char *args[] = {SELF, "report", fd, NULL};
+	char *env[] = {NULL};
+
+	execve(SELF, args, env);
+	perror("execve");
+	exit(1);
+}
+
+int main(int argc, char **argv)
+{
+	int status;
+	pid_t pid;
+
+	if (argc == 3 && strcmp(argv[1], "report") == 0) {
+		pid_t self = getpid();
+
+		assert(write(atoi(argv[2]), &self, sizeof(self)) == sizeof(self));
+		return 0;
+	}
+	if (argc == 2 && strcmp(argv[1], "exit") == 0) {
+		return 0;
+	}
+
+	// The child borrows the memory of the parent until it exits
+	pid = vfork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		shared = 42;
+		_exit(3);
+	}
+	assert(shared == 42);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 3);
+
+	// ... or until it executes a new image
+	shared = 0;
+	pid = vfork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		char *args[] = {SELF, "exit", NULL};
+		char *env[] = {NULL};
+
+		shared = 1;
+		execve(args[0], args, env);
+		_exit(1);
+	}
+	assert(shared == 1);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// Any thread may execve, the new image keeps the pid of the process
+	int fds[2];
+	pid_t reported;
+
+	assert(pipe(fds) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		char fd[16];
+
+		assert(close(fds[0]) == 0);
+		snprintf(fd, sizeof(fd), "%d", fds[1]);
+		assert(clone(exec_from_thread, thread_stack + sizeof(thread_stack),
+			     THREAD_FLAGS, fd) != -1);
+		// The main thread is reaped by the execve of the other one
+		while (1)
+			pause();
+	}
+	assert(close(fds[1]) == 0);
+	assert(read(fds[0], &reported, sizeof(reported)) == sizeof(reported));
+	assert(reported == pid);
+	assert(close(fds[0]) == 0);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	return 0;
+}
@@ -0,0 +1,175 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/inotify.h>
+#include <sys/socket.h>
+#include <sys/un.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define SELF "/bin/DeepTests/fd/cloexec"
+#define MAX_CHECKS 32
+
+/*
+ * The file descriptors whose FD_CLOEXEC flag is set are closed by
+ * execve(), whatever their file type. The others stay open.
+ */
+
+struct check {
+	int fd;
+	int open_after_exec;
+};
+
+static struct check checks[MAX_CHECKS];
+static int nb_checks = 0;
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void expect(int fd, int cloexec)
+{
+	assert(fd >= 0);
+	assert(fcntl(fd, F_GETFD) == (cloexec ? FD_CLOEXEC : 0));
+	assert(nb_checks < MAX_CHECKS);
+	checks[nb_checks].fd = fd;
+	checks[nb_checks].open_after_exec = !cloexec;
+	nb_checks++;
+}
+
+/*
+ * Run in the new process image: argv holds pairs of a file descriptor
+ * and whether it shall still be open
+ */
+static int check_after_exec(int argc, char **argv)
+{
+	for (int i = 1; i + 1 < argc; i += 2) {
+		int fd = atoi(argv[i]);
+		int open_after_exec = atoi(argv[i + 1]);
+
+		if (open_after_exec) {
+			assert(fcntl(fd, F_GETFD) == 0);
+		} else {
+			assert(fcntl(fd, F_GETFD) == -1 && errno == EBADF);
+		}
+	}
+	return 0;
+}
+
+static int accepted_socket(const char *path, int flags)
+{
+	struct sockaddr_un addr;
+	int server;
+	int fd;
+	pid_t pid;
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sun_family = AF_UNIX;
+	strcpy((char *)addr.sun_path, path);
+	unlink(path);
+
+	server = socket(AF_UNIX, SOCK_STREAM, 0);
+	assert(server >= 0);
+	assert(bind(server, (struct sockaddr *)&addr, sizeof(addr)) == 0);
+	assert(listen(server, 1) == 0);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		int client = socket(AF_UNIX, SOCK_STREAM, 0);
+
+		assert(client >= 0);
+		assert(connect(client, (struct sockaddr *)&addr, sizeof(addr)) == 0);
+		exit(0);
+	}
+	fd = accept4(server, NULL, NULL, flags);
+	wait_child(pid);
+	assert(close(server) == 0);
+	return fd;
+}
+
+int main(int argc, char **argv)
+{
+	char name[64];
+	char sock_name[64];
+	int fds[2];
+	int file;
+
+	if (argc > 1) {
+		return check_after_exec(argc, argv);
+	}
+	snprintf(name, sizeof(name), "cloexec_%d", getpid());
+	snprintf(sock_name, sizeof(sock_name), "cloexec_sock_%d", getpid());
+
+	// Regular files and directories
+	file = open(name, O_CREAT | O_RDWR | O_CLOEXEC, 0644);
+	expect(file, 1);
+	expect(open(name, O_RDONLY), 0);
+	expect(open(".", O_RDONLY | O_DIRECTORY | O_CLOEXEC), 1);
+
+	// Pipes
+	assert(pipe2(fds, O_CLOEXEC) == 0);
+	expect(fds[0], 1);
+	expect(fds[1], 1);
+	assert(pipe2(fds, 0) == 0);
+	expect(fds[0], 0);
+	expect(fds[1], 0);
+	assert(pipe2(fds, O_APPEND) == -1 && errno == EINVAL);
+
+	// Sockets
+	expect(socket(AF_UNIX, SOCK_DGRAM | SOCK_CLOEXEC, 0), 1);
+	expect(socket(AF_UNIX, SOCK_STREAM, 0), 0);
+	expect(accepted_socket(sock_name, SOCK_CLOEXEC), 1);
+	expect(accepted_socket(sock_name, 0), 0);
+	assert(unlink(sock_name) == 0);
+
+	// Inotify instances
+	expect(inotify_init1(IN_CLOEXEC), 1);
+	expect(inotify_init(), 0);
+
+	// A duplicate has its own FD_CLOEXEC flag
+	expect(dup(file), 0);
+	expect(fcntl(file, F_DUPFD, 20), 0);
+	expect(fcntl(file, F_DUPFD_CLOEXEC, 20), 1);
+	expect(dup3(file, 40, O_CLOEXEC), 1);
+	assert(dup3(file, 40, 0) == 40);
+	assert(fcntl(40, F_GETFD) == 0);
+	assert(dup3(file, file, O_CLOEXEC) == -1 && errno == EINVAL);
+	assert(dup2(file, 41) == 41);
+	expect(41, 0);
+	assert(fcntl(41, F_SETFD, FD_CLOEXEC) == 0);
+	checks[nb_checks - 1].open_after_exec = 0;
+	// dup2() on the same file descriptor keeps its flag
+	assert(dup2(file, file) == file);
+	assert(fcntl(file, F_GETFD) == FD_CLOEXEC);
+
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		char *args[2 * MAX_CHECKS + 2];
+		static char numbers[2 * MAX_CHECKS][16];
+		int n = 0;
+
+		args[n++] = SELF;
+		for (int i = 0; i < nb_checks; i++) {
+			snprintf(numbers[2 * i], 16, "%d", checks[i].fd);
+			snprintf(numbers[2 * i + 1], 16, "%d", checks[i].open_after_exec);
+			args[n++] = numbers[2 * i];
+			args[n++] = numbers[2 * i + 1];
+		}
+		args[n] = NULL;
+		execv(SELF, args);
+		perror("execv");
+		exit(1);
+	}
+	wait_child(pid);
+	assert(unlink(name) == 0);
+	return 0;
+}
@@ -0,0 +1,126 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/inotify.h>
+#include <sys/resource.h>
+#include <sys/socket.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define NB_FDS 600
+
+/*
+ * The file descriptor table grows up to the RLIMIT_NOFILE soft limit
+ * and gives the lowest available file descriptor. /proc/[pid]/fd
+ * shows the targets of the file descriptors.
+ */
+
+static int fds[NB_FDS];
+
+/*
+ * Check that the /proc/[pid]/fd/`fd` link of the process contains
+ * `target`
+ */
+static void check_link(int fd, const char *target)
+{
+	char path[64];
+	char buf[256];
+	ssize_t len;
+
+	snprintf(path, sizeof(path), "/proc/%d/fd/%d", getpid(), fd);
+	len = readlink(path, buf, sizeof(buf) - 1);
+	assert(len > 0);
+	buf[len] = '\0';
+	assert(strstr(buf, target) != NULL);
+}
+
+int main(void)
+{
+	struct rlimit limit;
+	struct rlimit old_limit;
+	char name[64];
+	char link_name[64];
+	int pipe_fds[2];
+	int fd;
+
+	snprintf(name, sizeof(name), "table_%d", getpid());
+	snprintf(link_name, sizeof(link_name), "/table_%d", getpid());
+
+	assert(getrlimit(RLIMIT_NOFILE, &old_limit) == 0);
+	assert(old_limit.rlim_cur <= old_limit.rlim_max);
+	assert(getrlimit(RLIM_NLIMITS, &limit) == -1 && errno == EINVAL);
+
+	// More file descriptors than the limit allows
+	limit.rlim_cur = 64;
+	limit.rlim_max = old_limit.rlim_max;
+	assert(setrlimit(RLIMIT_NOFILE, &limit) == 0);
+	fd = open(name, O_CREAT | O_RDWR, 0644);
+	assert(fd >= 0);
+	int nb_dup = 0;
+	while ((fds[nb_dup] = dup(fd)) >= 0) {
+		assert(fds[nb_dup] < 64);
+		nb_dup++;
+	}
+	assert(errno == EMFILE);
+	assert(dup2(fd, 64) == -1 && errno == EBADF);
+	assert(fcntl(fd, F_DUPFD, 64) == -1 && errno == EINVAL);
+	for (int i = 0; i < nb_dup; i++) {
+		assert(close(fds[i]) == 0);
+	}
+
+	// The soft limit cannot exceed the hard limit
+	limit.rlim_cur = limit.rlim_max + 1;
+	assert(setrlimit(RLIMIT_NOFILE, &limit) == -1 && errno == EINVAL);
+
+	// The table grows beyond its former size
+	limit.rlim_cur = NB_FDS + 64;
+	assert(setrlimit(RLIMIT_NOFILE, &limit) == 0);
+	for (int i = 0; i < NB_FDS; i++) {
+		fds[i] = dup(fd);
+		assert(fds[i] >= 0);
+		if (i > 0 && fds[i - 1] > fd) {
+			assert(fds[i] == fds[i - 1] + 1);
+		}
+	}
+
+	// The lowest available file descriptor is reused
+	assert(close(fds[300]) == 0);
+	assert(close(fds[100]) == 0);
+	assert(close(fds[200]) == 0);
+	assert(dup(fd) == fds[100]);
+	assert(fcntl(fd, F_DUPFD, fds[150]) == fds[200]);
+	assert(dup(fd) == fds[300]);
+	assert(dup(fd) == fds[NB_FDS - 1] + 1);
+	assert(close(fds[NB_FDS - 1] + 1) == 0);
+
+	// A file descriptor far beyond the end of the table
+	assert(dup2(fd, NB_FDS + 60) == NB_FDS + 60);
+	assert(dup(fd) == fds[NB_FDS - 1] + 1);
+	assert(close(NB_FDS + 60) == 0);
+	assert(close(fds[NB_FDS - 1] + 1) == 0);
+
+	// The targets of the file descriptors
+	check_link(fd, link_name);
+	check_link(fds[0], link_name);
+	assert(pipe(pipe_fds) == 0);
+	check_link(pipe_fds[0], "pipe:[");
+	check_link(pipe_fds[1], "pipe:[");
+	check_link(socket(AF_UNIX, SOCK_DGRAM, 0), "socket:[");
+	check_link(inotify_init(), "anon_inode:inotify");
+
+	for (int i = 0; i < NB_FDS; i++) {
+		assert(close(fds[i]) == 0);
+	}
+	assert(close(fd) == 0);
+	assert(unlink(name) == 0);
+
+	// Only a privileged process may raise its hard limit
+	if (geteuid() != 0) {
+		limit.rlim_max = old_limit.rlim_max + 1;
+		assert(setrlimit(RLIMIT_NOFILE, &limit) == -1 && errno == EPERM);
+	}
+	assert(setrlimit(RLIMIT_NOFILE, &old_limit) == 0);
+	return 0;
+}
@@ -0,0 +1,179 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/inotify.h>
+#include <sys/ioctl.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+static char events[4096];
+static ssize_t events_len = 0;
+static ssize_t events_pos = 0;
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+/*
+ * Get the next event of the inotify instance fd, a read gives as many
+ * events as the buffer can hold
+ */
+static struct inotify_event *next_event(int fd)
+{
+	struct inotify_event *event;
+
+	if (events_pos >= events_len) {
+		events_len = read(fd, events, sizeof(events));
+		assert(events_len > 0);
+		events_pos = 0;
+	}
+	event = (struct inotify_event *)(events + events_pos);
+	events_pos += sizeof(struct inotify_event) + event->len;
+	assert(events_pos <= events_len);
+	return event;
+}
+
+static struct inotify_event *expect_event(int fd, int wd, uint32_t mask, const char *name)
+{
+	struct inotify_event *event = next_event(fd);
+
+	assert(event->wd == wd);
+	assert(event->mask == mask);
+	if (name) {
+		assert(event->len > strlen(name));
+		assert(strcmp(event->name, name) == 0);
+	} else {
+		assert(event->len == 0);
+	}
+	return event;
+}
+
+static void expect_no_event(int fd)
+{
+	assert(events_pos >= events_len);
+	assert(read(fd, events, sizeof(events)) == -1 && errno == EAGAIN);
+}
+
+int main(void)
+{
+	char dir[64];
+	char path[128];
+	char other[128];
+	int pending;
+
+	snprintf(dir, sizeof(dir), "inotify_%d", getpid());
+	snprintf(path, sizeof(path), "%s/file", dir);
+	snprintf(other, sizeof(other), "%s/other", dir);
+	assert(mkdir(dir, 0755) == 0);
+
+	assert(inotify_init1(42) == -1 && errno == EINVAL);
+	int fd = inotify_init1(IN_NONBLOCK);
+	assert(fd >= 0);
+	assert(inotify_add_watch(fd, dir, 0) == -1 && errno == EINVAL);
+	assert(inotify_add_watch(42, dir, IN_ALL_EVENTS) == -1 && errno == EBADF);
+	assert(inotify_add_watch(0, dir, IN_ALL_EVENTS) == -1 && errno == EINVAL);
+	int wd = inotify_add_watch(fd, dir, IN_ALL_EVENTS);
+	assert(wd >= 0);
+	// The same file gives the same watch descriptor
+	assert(inotify_add_watch(fd, dir, IN_ALL_EVENTS) == wd);
+	expect_no_event(fd);
+
+	// The changes of the files of a watched directory
+	int file = open(path, O_CREAT | O_WRONLY, 0644);
+	assert(file >= 0);
+	expect_event(fd, wd, IN_CREATE, "file");
+	assert(write(file, "hello", 5) == 5);
+	assert(write(file, "hello", 5) == 5);
+	// The identical events in a row are merged
+	expect_event(fd, wd, IN_MODIFY, "file");
+	assert(close(file) == 0);
+	expect_event(fd, wd, IN_CLOSE_WRITE, "file");
+	assert(chmod(path, 0600) == 0);
+	expect_event(fd, wd, IN_ATTRIB, "file");
+
+	assert(ioctl(fd, FIONREAD, &pending) == 0);
+	assert(pending == 0);
+	assert(mkdir(other, 0755) == 0);
+	assert(ioctl(fd, FIONREAD, &pending) == 0);
+	assert(pending == sizeof(struct inotify_event) + 16);
+	// A buffer too small for the next event
+	assert(read(fd, events, sizeof(struct inotify_event)) == -1 && errno == EINVAL);
+	expect_event(fd, wd, IN_CREATE | IN_ISDIR, "other");
+	assert(rmdir(other) == 0);
+	expect_event(fd, wd, IN_DELETE | IN_ISDIR, "other");
+
+	// A rename links its two events with a cookie
+	assert(rename(path, other) == 0);
+	uint32_t cookie = expect_event(fd, wd, IN_MOVED_FROM, "file")->cookie;
+	assert(cookie != 0);
+	assert(expect_event(fd, wd, IN_MOVED_TO, "other")->cookie == cookie);
+
+	// A watch on the file itself, removed with the file
+	int file_wd = inotify_add_watch(fd, other, IN_ATTRIB | IN_DELETE_SELF | IN_ONLYDIR);
+	assert(file_wd == -1 && errno == ENOTDIR);
+	file_wd = inotify_add_watch(fd, other, IN_DELETE_SELF);
+	assert(file_wd >= 0 && file_wd != wd);
+	assert(unlink(other) == 0);
+	expect_event(fd, wd, IN_DELETE, "other");
+	expect_event(fd, file_wd, IN_DELETE_SELF, NULL);
+	expect_event(fd, file_wd, IN_IGNORED, NULL);
+	expect_no_event(fd);
+
+	// Only the first event of an IN_ONESHOT watch
+	assert(inotify_add_watch(fd, dir, IN_CREATE | IN_ONESHOT) == wd);
+	assert(mkdir(other, 0755) == 0);
+	assert(rmdir(other) == 0);
+	expect_event(fd, wd, IN_CREATE | IN_ISDIR, "other");
+	expect_event(fd, wd, IN_IGNORED, NULL);
+	expect_no_event(fd);
+	assert(inotify_rm_watch(fd, wd) == -1 && errno == EINVAL);
+
+	// The events lost when the queue is full
+	wd = inotify_add_watch(fd, dir, IN_CREATE | IN_DELETE);
+	assert(wd >= 0);
+	for (int i = 0; i < 1024; i++) {
+		file = open(path, O_CREAT | O_WRONLY, 0644);
+		assert(file >= 0);
+		assert(close(file) == 0);
+		assert(unlink(path) == 0);
+	}
+	for (int i = 0; i < 1024; i++) {
+		expect_event(fd, wd, i % 2 ? IN_DELETE : IN_CREATE, "file");
+	}
+	expect_event(fd, -1, IN_Q_OVERFLOW, NULL);
+	expect_no_event(fd);
+
+	assert(inotify_rm_watch(fd, wd) == 0);
+	expect_event(fd, wd, IN_IGNORED, NULL);
+	assert(inotify_rm_watch(fd, wd) == -1 && errno == EINVAL);
+	assert(close(fd) == 0);
+
+	// A read blocks until an event comes
+	fd = inotify_init();
+	assert(fd >= 0);
+	wd = inotify_add_watch(fd, dir, IN_CREATE);
+	assert(wd >= 0);
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		sleep(1);
+		file = open(path, O_CREAT | O_WRONLY, 0644);
+		assert(file >= 0);
+		exit(0);
+	}
+	expect_event(fd, wd, IN_CREATE, "file");
+	wait_child(pid);
+	assert(close(fd) == 0);
+
+	assert(unlink(path) == 0);
+	assert(rmdir(dir) == 0);
+	return 0;
+}
@@ -0,0 +1,141 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+static int set_lock(int fd, int cmd, short type, off_t start, off_t len)
+{
+	struct flock fl;
+
+	memset(&fl, 0, sizeof(fl));
+	fl.l_type = type;
+	fl.l_whence = SEEK_SET;
+	fl.l_start = start;
+	fl.l_len = len;
+	return fcntl(fd, cmd, &fl);
+}
+
+static void handler(int signum)
+{
+	(void)signum;
+}
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+/*
+ * The record locks belong to the process: they conflict with the locks
+ * of the other processes, are lost on any close of the file, and the
+ * sleeping requests detect deadlocks and are interrupted by signals
+ */
+int main(void)
+{
+	char filename[64];
+	char c;
+	int p[2];
+
+	snprintf(filename, sizeof(filename), "fcntl_lock_%d", getpid());
+	int fd = open(filename, O_CREAT | O_RDWR | O_TRUNC, 0644);
+	assert(fd >= 0);
+	pid_t parent = getpid();
+
+	// Unknown commands are rejected
+	assert(fcntl(fd, 4242) == -1 && errno == EINVAL);
+
+	assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 10) == 0);
+	// Unlocking the middle splits the lock
+	assert(set_lock(fd, F_SETLK, F_UNLCK, 4, 2) == 0);
+
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		struct flock fl;
+
+		memset(&fl, 0, sizeof(fl));
+		fl.l_type = F_RDLCK;
+		fl.l_whence = SEEK_SET;
+		fl.l_start = 5;
+		fl.l_len = 10;
+		assert(fcntl(fd, F_GETLK, &fl) == 0);
+		assert(fl.l_type == F_WRLCK && fl.l_start == 6 && fl.l_len == 4);
+		assert(fl.l_pid == parent);
+
+		assert(set_lock(fd, F_SETLK, F_RDLCK, 4, 2) == 0);
+		assert(set_lock(fd, F_SETLK, F_RDLCK, 0, 1) == -1 && errno == EAGAIN);
+		assert(set_lock(fd, F_SETLK, F_WRLCK, 10, 0) == 0);
+		exit(0);
+	}
+	wait_child(pid);
+
+	// The locks of the child were released at its exit
+	assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 0) == 0);
+
+	// A close of another descriptor of the file releases the locks
+	int other = open(filename, O_RDONLY);
+	assert(other >= 0);
+	close(other);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 0) == 0);
+		exit(0);
+	}
+	wait_child(pid);
+
+	// Each process waits for the other one: deadlock
+	assert(pipe(p) == 0);
+	assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 1) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(set_lock(fd, F_SETLK, F_WRLCK, 1, 1) == 0);
+		assert(write(p[1], "x", 1) == 1);
+		assert(set_lock(fd, F_SETLKW, F_WRLCK, 0, 1) == 0);
+		exit(0);
+	}
+	assert(read(p[0], &c, 1) == 1);
+	sleep(1);
+	assert(set_lock(fd, F_SETLKW, F_WRLCK, 1, 1) == -1 && errno == EDEADLK);
+	assert(set_lock(fd, F_SETLK, F_UNLCK, 0, 1) == 0);
+	wait_child(pid);
+
+	// A signal interrupts the sleep
+	assert(set_lock(fd, F_SETLK, F_WRLCK, 0, 1) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		struct sigaction sa;
+
+		memset(&sa, 0, sizeof(sa));
+		sa.sa_handler = handler;
+		assert(sigaction(SIGUSR1, &sa, NULL) == 0);
+		assert(write(p[1], "x", 1) == 1);
+		assert(set_lock(fd, F_SETLKW, F_WRLCK, 0, 1) == -1 && errno == EINTR);
+		exit(0);
+	}
+	assert(read(p[0], &c, 1) == 1);
+	sleep(1);
+	assert(kill(pid, SIGUSR1) == 0);
+	wait_child(pid);
+
+	// The lock still held is listed in /proc/locks
+	char buf[256];
+	int proc = open("/proc/locks", O_RDONLY);
+	assert(proc >= 0);
+	assert(read(proc, buf, sizeof(buf)) > 0);
+	close(proc);
+
+	close(fd);
+	assert(unlink(filename) == 0);
+	return 0;
+}
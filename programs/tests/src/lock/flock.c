@@ -0,0 +1,58 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/file.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * A flock() lock belongs to the open file description: it is shared
+ * with the inherited descriptors, conflicts with the other opens of
+ * the file, and is released when the last descriptor is closed
+ */
+int main(void)
+{
+	char filename[64];
+	int status;
+
+	snprintf(filename, sizeof(filename), "flock_%d", getpid());
+	int fd = open(filename, O_CREAT | O_RDWR | O_TRUNC, 0644);
+	assert(fd >= 0);
+
+	assert(flock(fd, LOCK_EX) == 0);
+	// A conversion of its own lock never blocks
+	assert(flock(fd, LOCK_SH) == 0);
+	assert(flock(fd, LOCK_EX | LOCK_NB) == 0);
+	assert(flock(fd, 42) == -1 && errno == EINVAL);
+
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		// The inherited descriptor shares the lock
+		assert(flock(fd, LOCK_EX | LOCK_NB) == 0);
+
+		int other = open(filename, O_RDONLY);
+		assert(other >= 0);
+		assert(flock(other, LOCK_SH | LOCK_NB) == -1 && errno == EWOULDBLOCK);
+		close(fd);
+		// Sleep until the parent closes its descriptor too
+		assert(flock(other, LOCK_SH) == 0);
+		exit(0);
+	}
+	sleep(1);
+	close(fd);
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// The lock of the child was released at its exit
+	fd = open(filename, O_RDWR);
+	assert(fd >= 0);
+	assert(flock(fd, LOCK_EX | LOCK_NB) == 0);
+	assert(flock(fd, LOCK_UN) == 0);
+	close(fd);
+
+	assert(unlink(filename) == 0);
+	return 0;
+}
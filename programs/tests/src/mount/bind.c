@@ -0,0 +1,173 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+static void wait_child(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+/*
+ * Tell if /proc/mounts has a line for the mount on target with the
+ * options opts
+ */
+static int is_in_proc_mounts(const char *target, const char *opts)
+{
+	char line[512];
+	char expected[256];
+	char cwd[128];
+	int found = 0;
+
+	assert(getcwd(cwd, sizeof(cwd)) != NULL);
+	snprintf(expected, sizeof(expected), " %s/%s ext2 %s ", strcmp(cwd, "/") == 0 ? "" : cwd, target, opts);
+	FILE *mounts = fopen("/proc/mounts", "r");
+	assert(mounts != NULL);
+	while (fgets(line, sizeof(line), mounts) != NULL) {
+		if (strstr(line, expected) != NULL) {
+			found = 1;
+		}
+	}
+	fclose(mounts);
+	return found;
+}
+
+static void write_file(const char *path, const char *content)
+{
+	int fd = open(path, O_CREAT | O_WRONLY | O_TRUNC, 0755);
+	assert(fd >= 0);
+	assert(write(fd, content, strlen(content)) == (ssize_t)strlen(content));
+	close(fd);
+}
+
+/*
+ * A bind mount shows a directory or a file elsewhere, possibly read
+ * only, and its flags change with a remount. A mount cannot be made
+ * read-only while files are open for writing through it
+ */
+int main(int argc, char **argv)
+{
+	char src[64];
+	char dst[64];
+	char path[128];
+	char buf[64];
+	struct stat st;
+
+	// Executed from a noexec mount
+	if (argc > 1) {
+		return 1;
+	}
+	(void)argv;
+	snprintf(src, sizeof(src), "bind_src_%d", getpid());
+	snprintf(dst, sizeof(dst), "bind_dst_%d", getpid());
+	assert(mkdir(src, 0755) == 0);
+	assert(mkdir(dst, 0755) == 0);
+	snprintf(path, sizeof(path), "%s/file", src);
+	write_file(path, "bind");
+
+	assert(mount(src, dst, NULL, MS_BIND | MS_RDONLY, NULL) == 0);
+	assert(is_in_proc_mounts(dst, "ro"));
+	snprintf(path, sizeof(path), "%s/file", dst);
+	int fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	assert(read(fd, buf, 4) == 4 && memcmp(buf, "bind", 4) == 0);
+	assert(fchmod(fd, 0600) == -1 && errno == EROFS);
+	assert(fchown(fd, 0, 0) == -1 && errno == EROFS);
+	close(fd);
+	// The same file through the writable mount
+	snprintf(path, sizeof(path), "%s/file", src);
+	fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	assert(fchmod(fd, 0755) == 0);
+	close(fd);
+	snprintf(path, sizeof(path), "%s/file", dst);
+	assert(open(path, O_WRONLY) == -1 && errno == EROFS);
+	assert(truncate(path, 0) == -1 && errno == EROFS);
+	assert(chmod(path, 0600) == -1 && errno == EROFS);
+	assert(unlink(path) == -1 && errno == EROFS);
+	snprintf(path, sizeof(path), "%s/new", dst);
+	assert(open(path, O_CREAT | O_WRONLY, 0644) == -1 && errno == EROFS);
+	assert(mkdir(path, 0755) == -1 && errno == EROFS);
+	assert(umount(src) == -1 && errno == EINVAL);
+
+	// Both mounts show the same files
+	snprintf(path, sizeof(path), "%s/other", src);
+	write_file(path, "other");
+	snprintf(path, sizeof(path), "%s/other", dst);
+	assert(stat(path, &st) == 0 && st.st_size == 5);
+	snprintf(path, sizeof(path), "%s/other", src);
+	assert(unlink(path) == 0);
+	snprintf(path, sizeof(path), "%s/other", dst);
+	assert(stat(path, &st) == -1 && errno == ENOENT);
+
+	// A file cannot be moved from one mount to the other
+	snprintf(path, sizeof(path), "%s/file", src);
+	snprintf(buf, sizeof(buf), "%s/moved", dst);
+	assert(rename(path, buf) == -1 && (errno == EXDEV || errno == EROFS));
+
+	assert(mount("none", dst, NULL, MS_REMOUNT | MS_BIND, NULL) == 0);
+	assert(is_in_proc_mounts(dst, "rw"));
+	snprintf(path, sizeof(path), "%s/new", dst);
+	write_file(path, "new");
+
+	// A mount with files open for writing cannot be made read-only
+	fd = open(path, O_WRONLY);
+	assert(fd >= 0);
+	assert(mount("none", dst, NULL, MS_REMOUNT | MS_BIND | MS_RDONLY, NULL) == -1 && errno == EBUSY);
+	close(fd);
+	assert(mount("none", dst, NULL, MS_REMOUNT | MS_BIND | MS_RDONLY, NULL) == 0);
+	assert(mount("none", dst, NULL, MS_REMOUNT | MS_BIND, NULL) == 0);
+	snprintf(path, sizeof(path), "%s/new", src);
+	assert(stat(path, &st) == 0 && st.st_size == 3);
+	assert(unlink(path) == 0);
+
+	// The programs of a noexec mount cannot be executed
+	assert(mount("none", dst, NULL, MS_REMOUNT | MS_BIND | MS_NOEXEC, NULL) == 0);
+	assert(is_in_proc_mounts(dst, "rw,noexec"));
+	snprintf(path, sizeof(path), "%s/file", dst);
+	pid_t pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		execl(path, path, "exec", NULL);
+		exit(errno == EACCES ? 0 : 1);
+	}
+	wait_child(pid);
+
+	assert(umount(dst) == 0);
+	assert(!is_in_proc_mounts(dst, "rw,noexec"));
+	snprintf(path, sizeof(path), "%s/file", dst);
+	assert(stat(path, &st) == -1 && errno == ENOENT);
+
+	// A single file may be bind mounted on another one
+	char file[128];
+	snprintf(file, sizeof(file), "%s/file", src);
+	snprintf(path, sizeof(path), "%s/target", src);
+	write_file(path, "target");
+	assert(mount(file, dst, NULL, MS_BIND, NULL) == -1 && errno == ENOTDIR);
+	assert(mount(file, path, NULL, MS_BIND, NULL) == 0);
+	fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	assert(read(fd, buf, sizeof(buf)) == 4 && memcmp(buf, "bind", 4) == 0);
+	close(fd);
+	assert(unlink(file) == -1 && errno == EBUSY);
+	assert(umount(path) == 0);
+	fd = open(path, O_RDONLY);
+	assert(fd >= 0);
+	assert(read(fd, buf, sizeof(buf)) == 6 && memcmp(buf, "target", 6) == 0);
+	close(fd);
+
+	assert(unlink(path) == 0);
+	assert(unlink(file) == 0);
+	assert(rmdir(src) == 0);
+	assert(rmdir(dst) == 0);
+	return 0;
+}
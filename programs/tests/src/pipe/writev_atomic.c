@@ -0,0 +1,64 @@
+#include <limits.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define WRITERS 4
+#define WRITES 16
+#define CHUNK (PIPE_BUF / 2)
+
+/*
+ * Several writers send a record of PIPE_BUF bytes made of two iovecs
+ * into the same pipe: the records must never be interleaved
+ */
+int main(void)
+{
+	static char record[PIPE_BUF];
+	int p[2];
+
+	assert(pipe(p) == 0);
+	for (int i = 0; i < WRITERS; i++) {
+		pid_t pid = fork();
+		assert(pid >= 0);
+		if (pid == 0) {
+			close(p[0]);
+			char head[CHUNK];
+			char tail[CHUNK];
+
+			memset(head, 'a' + i, CHUNK);
+			memset(tail, 'a' + i, CHUNK);
+			struct iovec iov[2] = {
+				{.iov_base = head, .iov_len = CHUNK},
+				{.iov_base = tail, .iov_len = CHUNK},
+			};
+			for (int j = 0; j < WRITES; j++) {
+				assert(writev(p[1], iov, 2) == PIPE_BUF);
+			}
+			exit(0);
+		}
+	}
+	close(p[1]);
+
+	for (int n = 0; n < WRITERS * WRITES; n++) {
+		size_t len = 0;
+		while (len < PIPE_BUF) {
+			ssize_t ret = read(p[0], record + len, PIPE_BUF - len);
+			assert(ret > 0);
+			len += ret;
+		}
+		for (int i = 1; i < PIPE_BUF; i++) {
+			assert(record[i] == record[0]);
+		}
+	}
+	assert(read(p[0], record, 1) == 0);
+
+	int status;
+	for (int i = 0; i < WRITERS; i++) {
+		assert(wait(&status) > 0);
+		assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	}
+	return 0;
+}
@@ -0,0 +1,76 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/uio.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * The positional and vectored I/O never use nor move the file offset
+ * for p* variants, and move it as read() and write() for the others
+ */
+int main(void)
+{
+	char filename[64];
+	char buf[32];
+	char a[4];
+	char b[8];
+
+	snprintf(filename, sizeof(filename), "pread_pwrite_%d", getpid());
+	int fd = open(filename, O_CREAT | O_RDWR | O_TRUNC, 0644);
+	assert(fd >= 0);
+
+	struct iovec out[3] = {
+		{.iov_base = "Hello", .iov_len = 5},
+		{.iov_base = "", .iov_len = 0},
+		{.iov_base = " World", .iov_len = 6},
+	};
+	assert(writev(fd, out, 3) == 11);
+	assert(lseek(fd, 0, SEEK_CUR) == 11);
+
+	// pwrite() and pread() leave the offset at 11
+	assert(pwrite(fd, "J", 1, 0) == 1);
+	assert(lseek(fd, 0, SEEK_CUR) == 11);
+	memset(buf, 0, sizeof(buf));
+	assert(pread(fd, buf, 5, 0) == 5);
+	assert(strcmp(buf, "Jello") == 0);
+	assert(lseek(fd, 0, SEEK_CUR) == 11);
+	assert(pread(fd, buf, 5, -1) == -1 && errno == EINVAL);
+
+	// preadv() fills each buffer completely before the next one
+	struct iovec in[2] = {
+		{.iov_base = a, .iov_len = sizeof(a)},
+		{.iov_base = b, .iov_len = sizeof(b)},
+	};
+	memset(b, 0, sizeof(b));
+	assert(preadv(fd, in, 2, 1) == 10);
+	assert(memcmp(a, "ello", 4) == 0);
+	assert(memcmp(b, " World", 6) == 0);
+	assert(lseek(fd, 0, SEEK_CUR) == 11);
+
+	assert(pwritev(fd, out, 3, 11) == 11);
+	assert(lseek(fd, 0, SEEK_CUR) == 11);
+
+	// readv() moves the offset
+	assert(readv(fd, in, 2) == sizeof(a) + sizeof(b));
+	assert(memcmp(a, "Hell", 4) == 0);
+	assert(memcmp(b, "o World", 7) == 0);
+	assert(lseek(fd, 0, SEEK_CUR) == 23);
+
+	assert(readv(fd, in, -1) == -1 && errno == EINVAL);
+	assert(readv(fd, (struct iovec *)0x42, 1) == -1 && errno == EFAULT);
+	close(fd);
+	assert(unlink(filename) == 0);
+
+	// No positional I/O on pipes
+	int p[2];
+	assert(pipe(p) == 0);
+	assert(pwrite(p[1], "x", 1, 0) == -1 && errno == ESPIPE);
+	assert(write(p[1], "x", 1) == 1);
+	assert(pread(p[0], buf, 1, 0) == -1 && errno == ESPIPE);
+	assert(preadv(p[0], in, 2, 0) == -1 && errno == ESPIPE);
+	close(p[0]);
+	close(p[1]);
+	return 0;
+}
@@ -0,0 +1,123 @@
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * CPU-bound load test: the CPU is shared in proportion of the nice
+ * values, and a process which sleeps most of the time is scheduled as
+ * soon as it wakes up, even when the CPU-bound processes are runnable.
+ */
+
+#define HOGS 3
+#define LOAD_SECONDS 2
+#define NAPS 10
+#define NAP_US 20000
+
+/* The scheduler runs at 20Hz, a woken process must not wait for the time slices of all the hogs */
+#define MAX_MEAN_LATENCY_US 100000
+
+static long long now_us(void)
+{
+	struct timeval tv;
+
+	assert(gettimeofday(&tv, NULL) == 0);
+	return (long long)tv.tv_sec * 1000000 + tv.tv_usec;
+}
+
+/* Count loop iterations for `seconds`, report the count on `fd` */
+static void hog(int fd, int nice_value, int seconds)
+{
+	long long end;
+	unsigned long count = 0;
+
+	assert(setpriority(PRIO_PROCESS, 0, nice_value) == 0);
+	end = now_us() + seconds * 1000000LL;
+	while (now_us() < end)
+		count++;
+	assert(write(fd, &count, sizeof(count)) == sizeof(count));
+	exit(0);
+}
+
+static pid_t spawn_hog(int fd, int nice_value, int seconds)
+{
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0)
+		hog(fd, nice_value, seconds);
+	return pid;
+}
+
+static void reap(pid_t pid)
+{
+	int status;
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+/* A nice 0 hog gets far more CPU than a nice 19 one */
+static void fair_share(void)
+{
+	static const int nice_values[2] = {0, 19};
+	unsigned long counts[2];
+	int fds[2][2];
+	pid_t pids[2];
+
+	for (int i = 0; i < 2; i++) {
+		assert(pipe(fds[i]) == 0);
+		pids[i] = spawn_hog(fds[i][1], nice_values[i], LOAD_SECONDS);
+	}
+	for (int i = 0; i < 2; i++) {
+		reap(pids[i]);
+		assert(read(fds[i][0], &counts[i], sizeof(counts[i])) == sizeof(counts[i]));
+		assert(close(fds[i][0]) == 0);
+		assert(close(fds[i][1]) == 0);
+	}
+	printf("nice 0: %lu iterations, nice 19: %lu iterations\n", counts[0], counts[1]);
+	assert(counts[0] > 4 * counts[1]);
+}
+
+/* Measure how late the naps of the calling process end under load */
+static void latency(void)
+{
+	long long total = 0;
+	long long worst = 0;
+	pid_t pids[HOGS];
+	int fds[2];
+
+	assert(pipe(fds) == 0);
+	for (int i = 0; i < HOGS; i++) {
+		pids[i] = spawn_hog(fds[1], 0, LOAD_SECONDS);
+	}
+	for (int i = 0; i < NAPS; i++) {
+		long long start = now_us();
+		long long late;
+
+		assert(usleep(NAP_US) == 0);
+		late = now_us() - start - NAP_US;
+		total += late;
+		if (late > worst)
+			worst = late;
+	}
+	printf("wake up latency under load: mean %lldus, worst %lldus\n",
+	       total / NAPS, worst);
+	for (int i = 0; i < HOGS; i++) {
+		reap(pids[i]);
+	}
+	assert(close(fds[0]) == 0);
+	assert(close(fds[1]) == 0);
+	assert(total / NAPS < MAX_MEAN_LATENCY_US);
+}
+
+int main(void)
+{
+	fair_share();
+	latency();
+	return 0;
+}
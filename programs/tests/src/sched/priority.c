@@ -0,0 +1,209 @@
+#include <errno.h>
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define NOBODY 1000
+
+/* The fields of /proc/self/stat after the comm one, the first is the state */
+struct stat_fields {
+	int priority;
+	int nice;
+	int num_threads;
+	int rt_priority;
+	int policy;
+};
+
+static struct stat_fields read_stat(void)
+{
+	struct stat_fields fields;
+	char buf[1024];
+	char *p;
+	int fd = open("/proc/self/stat", O_RDONLY);
+	ssize_t len;
+
+	assert(fd >= 0);
+	len = read(fd, buf, sizeof(buf) - 1);
+	assert(len > 0);
+	buf[len] = '\0';
+	assert(close(fd) == 0);
+
+	// Skip the pid and the comm, the first field here is the state (3)
+	p = strrchr(buf, ')');
+	assert(p != NULL);
+	p += 2;
+	for (int field = 3; field <= 41; field++) {
+		switch (field) {
+		case 18: fields.priority = atoi(p); break;
+		case 19: fields.nice = atoi(p); break;
+		case 20: fields.num_threads = atoi(p); break;
+		case 40: fields.rt_priority = atoi(p); break;
+		case 41: fields.policy = atoi(p); break;
+		}
+		p = strchr(p, ' ');
+		assert(p != NULL);
+		p++;
+	}
+	return fields;
+}
+
+/* Run `f` in a child and check it succeeded */
+static void in_child(void (*f)(void))
+{
+	int status;
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		f();
+		exit(0);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+}
+
+static void nice_values(void)
+{
+	struct stat_fields fields;
+	int status;
+	pid_t pid;
+
+	assert(getpriority(PRIO_PROCESS, 0) == 0);
+	assert(nice(5) == 5);
+	assert(getpriority(PRIO_PROCESS, 0) == 5);
+	assert(getpriority(PRIO_PROCESS, getpid()) == 5);
+	assert(getpriority(PRIO_PGRP, 0) <= 5);
+
+	// The nice value is clamped
+	assert(setpriority(PRIO_PROCESS, 0, 42) == 0);
+	assert(getpriority(PRIO_PROCESS, 0) == 19);
+	assert(nice(1) == 19);
+
+	fields = read_stat();
+	assert(fields.nice == 19);
+	assert(fields.priority == 39);
+	assert(fields.num_threads == 1);
+	assert(fields.policy == SCHED_OTHER);
+
+	// Root can lower it
+	assert(setpriority(PRIO_PROCESS, 0, -20) == 0);
+	assert(getpriority(PRIO_PROCESS, 0) == -20);
+	assert(setpriority(PRIO_PROCESS, 0, 3) == 0);
+
+	// It is inherited by the children
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		exit(getpriority(PRIO_PROCESS, 0) == 3 ? 0 : 1);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	errno = 0;
+	assert(getpriority(42, 0) == -1 && errno == EINVAL);
+	assert(getpriority(PRIO_PROCESS, 0x7fffffff) == -1 && errno == ESRCH);
+	assert(setpriority(PRIO_PROCESS, 0x7fffffff, 0) == -1 && errno == ESRCH);
+}
+
+static void unprivileged(void)
+{
+	struct sched_param param = {.sched_priority = 0};
+
+	// Only root lets the children inherit the policy again
+	assert(sched_setscheduler(0, SCHED_OTHER | SCHED_RESET_ON_FORK, &param) == 0);
+	assert(setuid(NOBODY) == 0);
+	assert(sched_setscheduler(0, SCHED_OTHER, &param) == -1 && errno == EPERM);
+	assert(sched_setscheduler(0, SCHED_OTHER | SCHED_RESET_ON_FORK, &param) == 0);
+	assert(nice(2) == 2);
+	assert(nice(-1) == -1 && errno == EPERM);
+	assert(setpriority(PRIO_PROCESS, 0, 1) == -1 && errno == EACCES);
+	assert(setpriority(PRIO_PROCESS, 0, 4) == 0);
+	// init belongs to root
+	assert(setpriority(PRIO_PROCESS, 1, 19) == -1 && errno == EPERM);
+
+	param.sched_priority = 1;
+	assert(sched_setscheduler(0, SCHED_FIFO, &param) == -1 && errno == EPERM);
+}
+
+static void realtime(void)
+{
+	struct sched_param param = {.sched_priority = 50};
+	struct stat_fields fields;
+	int status;
+	pid_t pid;
+
+	assert(sched_getscheduler(0) == SCHED_OTHER);
+	assert(sched_getparam(0, &param) == 0 && param.sched_priority == 0);
+
+	assert(sched_get_priority_min(SCHED_FIFO) == 1);
+	assert(sched_get_priority_max(SCHED_FIFO) == 99);
+	assert(sched_get_priority_min(SCHED_RR) == 1);
+	assert(sched_get_priority_max(SCHED_RR) == 99);
+	assert(sched_get_priority_min(SCHED_OTHER) == 0);
+	assert(sched_get_priority_max(SCHED_OTHER) == 0);
+	assert(sched_get_priority_max(42) == -1 && errno == EINVAL);
+
+	param.sched_priority = 0;
+	assert(sched_setscheduler(0, SCHED_FIFO, &param) == -1 && errno == EINVAL);
+	param.sched_priority = 100;
+	assert(sched_setscheduler(0, SCHED_RR, &param) == -1 && errno == EINVAL);
+	assert(sched_setscheduler(0, 42, &param) == -1 && errno == EINVAL);
+	assert(sched_setscheduler(0x7fffffff, SCHED_OTHER, &param) == -1 && errno == ESRCH);
+
+	param.sched_priority = 50;
+	assert(sched_setscheduler(0, SCHED_FIFO, &param) == 0);
+	assert(sched_getscheduler(0) == SCHED_FIFO);
+	assert(sched_getscheduler(getpid()) == SCHED_FIFO);
+	param.sched_priority = 0;
+	assert(sched_getparam(0, &param) == 0 && param.sched_priority == 50);
+
+	fields = read_stat();
+	assert(fields.policy == SCHED_FIFO);
+	assert(fields.rt_priority == 50);
+	assert(fields.priority == -51);
+
+	// The policy is kept, only the priority changes
+	param.sched_priority = 10;
+	assert(sched_setparam(0, &param) == 0);
+	assert(sched_getscheduler(0) == SCHED_FIFO);
+	assert(sched_getparam(0, &param) == 0 && param.sched_priority == 10);
+	assert(sched_yield() == 0);
+
+	// The children of a SCHED_RESET_ON_FORK process are time sharing
+	// ones, with a nice value of at least 0
+	assert(sched_setscheduler(0, SCHED_RR | SCHED_RESET_ON_FORK, &param) == 0);
+	assert(sched_getscheduler(0) == (SCHED_RR | SCHED_RESET_ON_FORK));
+	assert(nice(-5) == -5);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(sched_getscheduler(0) == SCHED_OTHER);
+		assert(sched_getparam(0, &param) == 0 && param.sched_priority == 0);
+		assert(getpriority(PRIO_PROCESS, 0) == 0);
+		exit(0);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	// Changing the priority only keeps the flag
+	assert(sched_setparam(0, &param) == 0);
+	assert(sched_getscheduler(0) == (SCHED_RR | SCHED_RESET_ON_FORK));
+
+	param.sched_priority = 0;
+	assert(sched_setscheduler(0, SCHED_OTHER, &param) == 0);
+	assert(sched_getscheduler(0) == SCHED_OTHER);
+}
+
+int main(void)
+{
+	in_child(nice_values);
+	in_child(unprivileged);
+	in_child(realtime);
+	assert(sched_yield() == 0);
+	return 0;
+}
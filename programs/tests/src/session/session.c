@@ -0,0 +1,106 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * Sessions: a process group leader cannot create a session, a new
+ * session has no controlling terminal, processes cannot move across
+ * sessions, and a stopped process group orphaned by the exit of its
+ * parent is sent SIGHUP followed by SIGCONT.
+ */
+
+static int hangup_pipe[2];
+
+static void hangup_handler(int signum)
+{
+	char c = (char)signum;
+
+	assert(write(hangup_pipe[1], &c, 1) == 1);
+}
+
+static void new_session_child(pid_t parent_pgid)
+{
+	pid_t pid = getpid();
+
+	assert(setsid() == pid);
+	assert(getsid(0) == pid);
+	assert(getpgrp() == pid);
+	assert(getsid(getppid()) != pid);
+
+	// A session leader cannot create a new session, nor change its group
+	assert(setsid() == -1 && errno == EPERM);
+	assert(setpgid(0, parent_pgid) == -1 && errno == EPERM);
+
+	// The new session has no controlling terminal
+	for (int fd = 0; fd < 3; fd++) {
+		assert(tcgetpgrp(fd) == -1);
+		assert(errno == ENOTTY || errno == EBADF);
+	}
+	exit(0);
+}
+
+static void orphan_parent(void)
+{
+	pid_t pid = fork();
+	int status;
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(signal(SIGHUP, hangup_handler) != SIG_ERR);
+		assert(setpgid(0, 0) == 0);
+		raise(SIGSTOP);
+		exit(0);
+	}
+	// The parent keeps the stopped group from being orphaned until it exits
+	assert(waitpid(pid, &status, WUNTRACED) == pid);
+	assert(WIFSTOPPED(status));
+	exit(0);
+}
+
+int main(void)
+{
+	pid_t pid;
+	int status;
+	char c;
+
+	assert(setpgid(0, 0) == 0);
+	assert(setsid() == -1 && errno == EPERM);
+	assert(getsid(0) == getsid(getpid()));
+	assert(getsid(-1) == -1 && errno == ESRCH);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		new_session_child(getpgrp());
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// A child cannot be moved into a group of another session
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		assert(setsid() == getpid());
+		pause();
+		exit(0);
+	}
+	while (getsid(pid) != pid)
+		usleep(1000);
+	assert(setpgid(pid, getpgrp()) == -1 && errno == EPERM);
+	assert(kill(pid, SIGKILL) == 0);
+	assert(waitpid(pid, &status, 0) == pid);
+
+	assert(pipe(hangup_pipe) == 0);
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		orphan_parent();
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(read(hangup_pipe[0], &c, 1) == 1);
+	assert(c == SIGHUP);
+	return 0;
+}
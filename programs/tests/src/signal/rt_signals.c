@@ -0,0 +1,156 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * Real-time signals are queued with their value and delivered lowest
+ * number first, in the order they were sent. SA_SIGINFO handlers and
+ * sigwaitinfo() describe the origin of the signal.
+ */
+
+#define FAULT_ADDR ((volatile int *)16)
+#define HANDLED_EXIT 42
+
+struct delivery {
+	int signo;
+	int value;
+};
+
+static struct delivery deliveries[8];
+static volatile int nb_deliveries;
+
+static void rt_handler(int signum, siginfo_t *info, void *ucontext)
+{
+	assert(info != NULL && ucontext != NULL);
+	assert(info->si_signo == signum);
+	assert(info->si_code == SI_QUEUE);
+	assert(info->si_pid == getpid());
+	deliveries[nb_deliveries].signo = signum;
+	deliveries[nb_deliveries].value = info->si_value.sival_int;
+	nb_deliveries++;
+}
+
+static void segv_handler(int signum, siginfo_t *info, void *ucontext)
+{
+	(void)ucontext;
+	assert(signum == SIGSEGV);
+	assert(info->si_code == SEGV_MAPERR);
+	assert(info->si_addr == (void *)FAULT_ADDR);
+	_exit(HANDLED_EXIT);
+}
+
+static void queue(int signo, int value)
+{
+	union sigval sv = {.sival_int = value};
+
+	assert(sigqueue(getpid(), signo, sv) == 0);
+}
+
+static void test_queued_delivery(void)
+{
+	struct sigaction act = {.sa_sigaction = rt_handler, .sa_flags = SA_SIGINFO};
+	sigset_t set, pending;
+
+	sigemptyset(&act.sa_mask);
+	assert(sigaction(SIGRTMIN + 1, &act, NULL) == 0);
+	assert(sigaction(SIGRTMIN + 2, &act, NULL) == 0);
+
+	sigemptyset(&set);
+	assert(sigaddset(&set, SIGRTMIN + 1) == 0);
+	assert(sigaddset(&set, SIGRTMIN + 2) == 0);
+	assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+
+	queue(SIGRTMIN + 2, 20);
+	queue(SIGRTMIN + 1, 10);
+	queue(SIGRTMIN + 1, 11);
+	assert(nb_deliveries == 0);
+
+	assert(sigpending(&pending) == 0);
+	assert(sigismember(&pending, SIGRTMIN + 1) == 1);
+	assert(sigismember(&pending, SIGRTMIN + 2) == 1);
+	assert(sigismember(&pending, SIGRTMIN) == 0);
+
+	assert(sigprocmask(SIG_UNBLOCK, &set, NULL) == 0);
+	assert(nb_deliveries == 3);
+	assert(deliveries[0].signo == SIGRTMIN + 1 && deliveries[0].value == 10);
+	assert(deliveries[1].signo == SIGRTMIN + 1 && deliveries[1].value == 11);
+	assert(deliveries[2].signo == SIGRTMIN + 2 && deliveries[2].value == 20);
+}
+
+static void test_wait(void)
+{
+	struct timespec zero = {.tv_sec = 0, .tv_nsec = 0};
+	siginfo_t info;
+	sigset_t set;
+	int sig;
+
+	sigemptyset(&set);
+	assert(sigaddset(&set, SIGRTMIN + 3) == 0);
+	assert(sigaddset(&set, SIGUSR1) == 0);
+	assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+
+	assert(sigtimedwait(&set, &info, &zero) == -1 && errno == EAGAIN);
+
+	queue(SIGRTMIN + 3, 33);
+	assert(sigwaitinfo(&set, &info) == SIGRTMIN + 3);
+	assert(info.si_signo == SIGRTMIN + 3);
+	assert(info.si_code == SI_QUEUE);
+	assert(info.si_pid == getpid());
+	assert(info.si_value.sival_int == 33);
+
+	assert(kill(getpid(), SIGUSR1) == 0);
+	assert(sigwait(&set, &sig) == 0 && sig == SIGUSR1);
+
+	assert(sigprocmask(SIG_UNBLOCK, &set, NULL) == 0);
+}
+
+static void test_fault_info(void)
+{
+	siginfo_t info;
+	sigset_t set;
+	pid_t pid;
+	int status;
+
+	sigemptyset(&set);
+	assert(sigaddset(&set, SIGCHLD) == 0);
+	assert(sigprocmask(SIG_BLOCK, &set, NULL) == 0);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0) {
+		struct sigaction act = {.sa_sigaction = segv_handler, .sa_flags = SA_SIGINFO};
+
+		sigemptyset(&act.sa_mask);
+		assert(sigaction(SIGSEGV, &act, NULL) == 0);
+		*FAULT_ADDR = 0;
+		exit(0);
+	}
+	assert(sigwaitinfo(&set, &info) == SIGCHLD);
+	assert(info.si_code == CLD_EXITED);
+	assert(info.si_pid == pid);
+	assert(info.si_status == HANDLED_EXIT);
+
+	assert(waitpid(pid, &status, 0) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == HANDLED_EXIT);
+	assert(sigprocmask(SIG_UNBLOCK, &set, NULL) == 0);
+}
+
+int main(void)
+{
+	union sigval sv = {.sival_int = 0};
+	sigset_t set;
+
+	sigemptyset(&set);
+	assert(sigaddset(&set, SIGRTMAX) == 0);
+	assert(sigaddset(&set, SIGRTMAX + 1) == -1 && errno == EINVAL);
+	assert(sigqueue(getpid(), SIGRTMAX + 1, sv) == -1 && errno == EINVAL);
+
+	test_queued_delivery();
+	test_wait();
+	test_fault_info();
+	return 0;
+}
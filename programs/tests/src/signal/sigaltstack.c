@@ -0,0 +1,108 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * The stack grows on demand up to RLIMIT_STACK. Beyond, the overflow
+ * raises SIGSEGV, whose handler can only run on an alternate stack.
+ */
+
+#define HANDLED_EXIT 42
+
+static char alt_stack[SIGSTKSZ];
+
+/* Use about `max` KB of stack, never returns if max is negative */
+static int recurse(int depth, int max)
+{
+	volatile char buf[1024];
+
+	buf[0] = depth;
+	if (depth == max)
+		return buf[0];
+	return recurse(depth + 1, max) + buf[0];
+}
+
+static void segv_handler(int signum)
+{
+	char local;
+	stack_t current;
+	stack_t other = {.ss_sp = alt_stack, .ss_size = SIGSTKSZ, .ss_flags = 0};
+
+	assert(signum == SIGSEGV);
+	assert(&local >= alt_stack && &local < alt_stack + SIGSTKSZ);
+	assert(sigaltstack(NULL, &current) == 0);
+	assert(current.ss_flags == SS_ONSTACK);
+	assert(current.ss_sp == alt_stack && current.ss_size == SIGSTKSZ);
+
+	/* The stack in use cannot be changed */
+	assert(sigaltstack(&other, NULL) == -1 && errno == EPERM);
+	_exit(HANDLED_EXIT);
+}
+
+/* Overflow a stack limited to 256K in a child, return its status */
+static int overflow(int on_alt_stack)
+{
+	pid_t pid = fork();
+	int status;
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		struct rlimit limit = {.rlim_cur = 256 * 1024, .rlim_max = RLIM_INFINITY};
+		struct sigaction act;
+		stack_t ss = {.ss_sp = alt_stack, .ss_size = SIGSTKSZ, .ss_flags = 0};
+
+		assert(setrlimit(RLIMIT_STACK, &limit) == 0);
+		if (on_alt_stack)
+			assert(sigaltstack(&ss, NULL) == 0);
+		memset(&act, 0, sizeof(act));
+		act.sa_handler = &segv_handler;
+		act.sa_flags = SA_ONSTACK;
+		assert(sigaction(SIGSEGV, &act, NULL) == 0);
+		recurse(0, -1);
+		exit(EXIT_FAILURE);
+	}
+	assert(waitpid(pid, &status, 0) == pid);
+	return status;
+}
+
+int main(void)
+{
+	stack_t ss;
+
+	/* Far more than the 64K the stack used to be */
+	assert(recurse(0, 2048) != 0);
+
+	/* No alternate stack until one is set */
+	assert(sigaltstack(NULL, &ss) == 0);
+	assert(ss.ss_flags == SS_DISABLE);
+
+	ss.ss_sp = alt_stack;
+	ss.ss_size = MINSIGSTKSZ - 1;
+	ss.ss_flags = 0;
+	assert(sigaltstack(&ss, NULL) == -1 && errno == ENOMEM);
+	ss.ss_size = SIGSTKSZ;
+	ss.ss_flags = SS_ONSTACK;
+	assert(sigaltstack(&ss, NULL) == -1 && errno == EINVAL);
+	ss.ss_flags = 0;
+	assert(sigaltstack(&ss, NULL) == 0);
+	memset(&ss, 0, sizeof(ss));
+	assert(sigaltstack(NULL, &ss) == 0);
+	assert(ss.ss_sp == alt_stack && ss.ss_size == SIGSTKSZ && ss.ss_flags == 0);
+	ss.ss_flags = SS_DISABLE;
+	assert(sigaltstack(&ss, NULL) == 0);
+
+	/* The overflow is handled on the alternate stack */
+	int status = overflow(1);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == HANDLED_EXIT);
+
+	/* Without it, there is no room for the handler */
+	status = overflow(0);
+	assert(WIFSIGNALED(status) && WTERMSIG(status) == SIGSEGV);
+	return EXIT_SUCCESS;
+}
@@ -0,0 +1,66 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * A truncated file keeps its first bytes, and a file grown by
+ * truncate() reads as zeroes after its old end
+ */
+int main(void)
+{
+	char filename[64];
+	char buf[3000];
+	struct stat st;
+	int pipefd[2];
+
+	snprintf(filename, sizeof(filename), "truncate_%d", getpid());
+	int fd = open(filename, O_CREAT | O_RDWR | O_TRUNC, 0644);
+	assert(fd >= 0);
+
+	memset(buf, 'a', sizeof(buf));
+	assert(write(fd, buf, sizeof(buf)) == sizeof(buf));
+
+	// Shrink in the middle of a block, then grow over two blocks
+	assert(ftruncate(fd, 1500) == 0);
+	assert(fstat(fd, &st) == 0 && st.st_size == 1500);
+	assert(truncate(filename, 4200) == 0);
+	assert(fstat(fd, &st) == 0 && st.st_size == 4200);
+
+	memset(buf, 0xff, sizeof(buf));
+	assert(pread(fd, buf, 1500, 0) == 1500);
+	for (int i = 0; i < 1500; i++)
+		assert(buf[i] == 'a');
+	assert(pread(fd, buf, sizeof(buf), 1500) == 2700);
+	for (int i = 0; i < 2700; i++)
+		assert(buf[i] == 0);
+
+	assert(ftruncate(fd, -1) == -1 && errno == EINVAL);
+	assert(fsync(fd) == 0);
+	assert(fdatasync(fd) == 0);
+	assert(syncfs(fd) == 0);
+	sync();
+	close(fd);
+
+	// The file must be open for writing
+	fd = open(filename, O_RDONLY);
+	assert(fd >= 0);
+	assert(ftruncate(fd, 0) == -1 && errno == EINVAL);
+	close(fd);
+
+	assert(truncate(".", 0) == -1 && errno == EISDIR);
+	assert(truncate(filename, 0) == 0);
+	assert(stat(filename, &st) == 0 && st.st_size == 0);
+
+	// A pipe cannot be synchronized
+	assert(pipe(pipefd) == 0);
+	assert(fsync(pipefd[0]) == -1 && errno == EINVAL);
+	close(pipefd[0]);
+	close(pipefd[1]);
+
+	assert(unlink(filename) == 0);
+	return 0;
+}
@@ -0,0 +1,85 @@
+#include <errno.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/resource.h>
+#include <sys/time.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * The resources used by a process, a thread and the reaped children:
+ * CPU times, stack growth faults, context switches and peak resident
+ * set size.
+ */
+
+#define STACK_PAGES 16
+
+static long cpu_time_ms(struct rusage *usage)
+{
+	return (usage->ru_utime.tv_sec + usage->ru_stime.tv_sec) * 1000
+		+ (usage->ru_utime.tv_usec + usage->ru_stime.tv_usec) / 1000;
+}
+
+/* Touch pages of stack never used before */
+static __attribute__((noinline)) int grow_stack(void)
+{
+	volatile char buf[STACK_PAGES * 4096];
+
+	memset((char *)buf, 1, sizeof(buf));
+	return buf[0];
+}
+
+static void child(void)
+{
+	struct rusage usage;
+
+	grow_stack();
+	do {
+		assert(getrusage(RUSAGE_SELF, &usage) == 0);
+	} while (cpu_time_ms(&usage) < 20);
+	exit(0);
+}
+
+int main(void)
+{
+	struct rusage before, after, thread, children;
+	int status;
+	pid_t pid;
+
+	assert(getrusage(42, &before) == -1 && errno == EINVAL);
+
+	assert(getrusage(RUSAGE_CHILDREN, &children) == 0);
+	assert(children.ru_minflt == 0 && cpu_time_ms(&children) == 0);
+
+	assert(getrusage(RUSAGE_SELF, &before) == 0);
+	grow_stack();
+	usleep(10000);
+	assert(getrusage(RUSAGE_SELF, &after) == 0);
+	assert(after.ru_minflt >= before.ru_minflt + STACK_PAGES);
+	assert(after.ru_nvcsw > before.ru_nvcsw);
+	assert(after.ru_maxrss >= STACK_PAGES * 4);
+
+	assert(getrusage(RUSAGE_THREAD, &thread) == 0);
+	assert(thread.ru_minflt >= STACK_PAGES);
+	assert(thread.ru_maxrss == after.ru_maxrss);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		child();
+
+	// The usage of the child is reported by wait4 and kept on reap
+	assert(wait4(pid, &status, 0, &after) == pid);
+	assert(WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	assert(cpu_time_ms(&after) >= 20);
+	assert(after.ru_minflt >= STACK_PAGES);
+	assert(after.ru_maxrss > 0);
+
+	assert(getrusage(RUSAGE_CHILDREN, &children) == 0);
+	assert(cpu_time_ms(&children) >= 20);
+	assert(children.ru_minflt == after.ru_minflt);
+	assert(children.ru_nvcsw == after.ru_nvcsw);
+	assert(children.ru_nivcsw == after.ru_nivcsw);
+	return 0;
+}
@@ -0,0 +1,76 @@
+#include <errno.h>
+#include <signal.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+#include <assert.h>
+
+/*
+ * waitid reports the children of a set as siginfo, and leaves them
+ * waitable with WNOWAIT.
+ */
+
+static pid_t spawn_stopped(void)
+{
+	pid_t pid = fork();
+
+	assert(pid >= 0);
+	if (pid == 0) {
+		raise(SIGSTOP);
+		while (1)
+			pause();
+	}
+	return pid;
+}
+
+static void check_info(siginfo_t *info, pid_t pid, int code, int status)
+{
+	assert(info->si_signo == SIGCHLD);
+	assert(info->si_pid == pid);
+	assert(info->si_uid == getuid());
+	assert(info->si_code == code);
+	assert(info->si_status == status);
+}
+
+int main(void)
+{
+	siginfo_t info;
+	pid_t pid;
+
+	// At least one kind of state change must be waited for
+	assert(waitid(P_ALL, 0, &info, WNOHANG) == -1 && errno == EINVAL);
+	assert(waitid(P_PID, 0, &info, WEXITED) == -1 && errno == EINVAL);
+	assert(waitid(P_ALL, 0, &info, WEXITED) == -1 && errno == ECHILD);
+
+	pid = fork();
+	assert(pid >= 0);
+	if (pid == 0)
+		exit(7);
+
+	// WNOWAIT leaves the zombie to be waited for again
+	assert(waitid(P_PID, pid, &info, WEXITED | WNOWAIT) == 0);
+	check_info(&info, pid, CLD_EXITED, 7);
+	memset(&info, 0, sizeof(info));
+	assert(waitid(P_ALL, 0, &info, WEXITED) == 0);
+	check_info(&info, pid, CLD_EXITED, 7);
+	assert(waitid(P_PID, pid, &info, WEXITED) == -1 && errno == ECHILD);
+
+	pid = spawn_stopped();
+	assert(waitid(P_PID, pid, &info, WSTOPPED) == 0);
+	check_info(&info, pid, CLD_STOPPED, SIGSTOP);
+
+	// Nothing more to report: si_pid is zeroed
+	memset(&info, 0xff, sizeof(info));
+	assert(waitid(P_PID, pid, &info, WSTOPPED | WEXITED | WNOHANG) == 0);
+	assert(info.si_pid == 0 && info.si_signo == 0);
+
+	assert(kill(pid, SIGCONT) == 0);
+	assert(waitid(P_PGID, 0, &info, WCONTINUED) == 0);
+	check_info(&info, pid, CLD_CONTINUED, SIGCONT);
+
+	assert(kill(pid, SIGKILL) == 0);
+	assert(waitid(P_PGID, getpgrp(), &info, WEXITED) == 0);
+	check_info(&info, pid, CLD_KILLED, SIGKILL);
+	return 0;
+}
@@ -0,0 +1,152 @@
+#include <fcntl.h>
+#include <errno.h>
+#include <stdio.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/xattr.h>
+#include <unistd.h>
+#include <assert.h>
+
+#define ACL_ACCESS  "system.posix_acl_access"
+#define ACL_DEFAULT "system.posix_acl_default"
+
+#define ACL_USER_OBJ  0x01
+#define ACL_USER      0x02
+#define ACL_GROUP_OBJ 0x04
+#define ACL_MASK      0x10
+#define ACL_OTHER     0x20
+#define ACL_UNDEFINED 0xffffffff
+
+/* xattr format of an ACL entry, after a 32 bits version number */
+struct acl_entry {
+	uint16_t tag;
+	uint16_t perm;
+	uint32_t id;
+};
+
+static size_t make_acl(char *buf, const struct acl_entry *entries, size_t n)
+{
+	uint32_t version = 2;
+
+	memcpy(buf, &version, sizeof(version));
+	memcpy(buf + sizeof(version), entries, n * sizeof(*entries));
+	return sizeof(version) + n * sizeof(*entries);
+}
+
+static int list_contains(const char *list, ssize_t size, const char *name)
+{
+	for (ssize_t i = 0; i < size; i += strlen(list + i) + 1) {
+		if (strcmp(list + i, name) == 0) {
+			return 1;
+		}
+	}
+	return 0;
+}
+
+static mode_t perms(const char *path)
+{
+	struct stat st;
+
+	assert(stat(path, &st) == 0);
+	return st.st_mode & 0777;
+}
+
+/*
+ * Set, get, list and remove an user extended attribute, then check that
+ * the files created in a directory with a default ACL inherit it and
+ * ignore the umask, unlike the ones of a directory without one
+ */
+int main(void)
+{
+	char dir[64];
+	char path[128];
+	char buf[256];
+	ssize_t size;
+
+	snprintf(dir, sizeof(dir), "xattr_%d", getpid());
+	assert(mkdir(dir, 0755) == 0);
+	snprintf(path, sizeof(path), "%s/file", dir);
+	int fd = open(path, O_CREAT | O_WRONLY, 0644);
+	assert(fd >= 0);
+	close(fd);
+
+	assert(setxattr(path, "user.color", "blue", 4, XATTR_CREATE) == 0);
+	assert(setxattr(path, "user.color", "red", 3, XATTR_CREATE) == -1 && errno == EEXIST);
+	assert(setxattr(path, "user.shape", "round", 5, XATTR_REPLACE) == -1 && errno == ENODATA);
+	assert(setxattr(path, "user.color", "green", 5, XATTR_REPLACE) == 0);
+	assert(setxattr(path, "bogus.color", "blue", 4, 0) == -1 && errno == EOPNOTSUPP);
+
+	assert(getxattr(path, "user.color", NULL, 0) == 5);
+	assert(getxattr(path, "user.color", buf, 2) == -1 && errno == ERANGE);
+	assert(getxattr(path, "user.color", buf, sizeof(buf)) == 5);
+	assert(memcmp(buf, "green", 5) == 0);
+	assert(getxattr(path, "user.shape", buf, sizeof(buf)) == -1 && errno == ENODATA);
+
+	assert(setxattr(path, "user.shape", "round", 5, 0) == 0);
+	size = listxattr(path, NULL, 0);
+	assert(size == (ssize_t)(sizeof("user.color") + sizeof("user.shape")));
+	assert(listxattr(path, buf, 1) == -1 && errno == ERANGE);
+	assert(listxattr(path, buf, sizeof(buf)) == size);
+	assert(list_contains(buf, size, "user.color"));
+	assert(list_contains(buf, size, "user.shape"));
+
+	assert(removexattr(path, "user.color") == 0);
+	assert(removexattr(path, "user.color") == -1 && errno == ENODATA);
+	assert(getxattr(path, "user.color", buf, sizeof(buf)) == -1 && errno == ENODATA);
+	size = listxattr(path, buf, sizeof(buf));
+	assert(size == sizeof("user.shape") && !list_contains(buf, size, "user.color"));
+	assert(unlink(path) == 0);
+
+	umask(077);
+
+	// Without a default ACL, the umask applies
+	snprintf(path, sizeof(path), "%s/plain", dir);
+	fd = open(path, O_CREAT | O_WRONLY, 0666);
+	assert(fd >= 0);
+	close(fd);
+	assert(perms(path) == 0600);
+	assert(getxattr(path, ACL_ACCESS, buf, sizeof(buf)) == -1 && errno == ENODATA);
+	assert(unlink(path) == 0);
+
+	// A default ACL giving rw- to another user
+	struct acl_entry entries[] = {
+		{ACL_USER_OBJ, 07, ACL_UNDEFINED},
+		{ACL_USER, 06, 1000},
+		{ACL_GROUP_OBJ, 05, ACL_UNDEFINED},
+		{ACL_MASK, 07, ACL_UNDEFINED},
+		{ACL_OTHER, 05, ACL_UNDEFINED},
+	};
+	size_t len = make_acl(buf, entries, sizeof(entries) / sizeof(*entries));
+	assert(setxattr(dir, ACL_DEFAULT, buf, len, 0) == 0);
+	assert(getxattr(dir, ACL_DEFAULT, NULL, 0) == (ssize_t)len);
+
+	// The mode only comes from the ACL and the mode argument
+	snprintf(path, sizeof(path), "%s/inherited", dir);
+	fd = open(path, O_CREAT | O_WRONLY, 0666);
+	assert(fd >= 0);
+	close(fd);
+	assert(perms(path) == 0664);
+	assert(getxattr(path, ACL_ACCESS, NULL, 0) == (ssize_t)len);
+	assert(getxattr(path, ACL_DEFAULT, buf, sizeof(buf)) == -1 && errno == ENODATA);
+	assert(unlink(path) == 0);
+
+	// A subdirectory also inherits the default ACL itself
+	snprintf(path, sizeof(path), "%s/subdir", dir);
+	assert(mkdir(path, 0777) == 0);
+	assert(perms(path) == 0775);
+	assert(getxattr(path, ACL_ACCESS, NULL, 0) == (ssize_t)len);
+	assert(getxattr(path, ACL_DEFAULT, NULL, 0) == (ssize_t)len);
+	assert(rmdir(path) == 0);
+
+	// Removing the default ACL brings the umask back
+	assert(removexattr(dir, ACL_DEFAULT) == 0);
+	snprintf(path, sizeof(path), "%s/masked", dir);
+	assert(mkdir(path, 0777) == 0);
+	assert(perms(path) == 0700);
+	assert(rmdir(path) == 0);
+
+	assert(rmdir(dir) == 0);
+	return 0;
+}
@@ -224,7 +224,7 @@ pub struct ElfHeader {
     abi_version: u8,
 
     /// The object type of file.
-    object_type: ObjectType,
+    pub object_type: ObjectType,
 
     /// The target architecture of this object file.
     machine: Architecture,
@@ -248,7 +248,7 @@ pub struct ElfHeader {
     self_size: u16,
 
     /// Contains the size of a program header table entry.
-    program_header_table_size: u16,
+    pub program_header_table_size: u16,
 
     /// Contains the number of entries in the program header table.
     pub nbr_program_header: u16,
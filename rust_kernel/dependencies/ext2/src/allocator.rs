@@ -0,0 +1,794 @@
+//! This module contains the block and inode allocators of the ext2 driver
+//!
+//! Allocation is locality aware: a file inode is placed in the block
+//! group of its parent, a directory inode is spread across the block
+//! groups as the Orlov allocator does, and a data block is placed
+//! right after the previous block of its file. Sequential writers get
+//! a small window of blocks reserved ahead of them so that files
+//! written concurrently do not interleave.
+//!
+//! The allocation bitmaps are read once from the disk and then kept in
+//! memory, summarised as sorted lists of free extents. Every change is
+//! written through to the disk.
+
+use super::{Block, Ext2Filesystem, Inode, InodeAddr, InodeNbr, IoResult};
+use crate::header::BlockGroupDescriptor;
+use alloc::vec::Vec;
+use bit_field::BitArray;
+use core::cmp::{max, min};
+use libc_binding::Errno;
+
+/// Number of blocks reserved ahead of a sequential writer when the
+/// superblock does not give any hint
+const DEFAULT_PREALLOCATION: u32 = 8;
+
+/// The inode number of the root directory
+const ROOT_INODE_NBR: InodeNbr = 2;
+
+/// A run of consecutive free bits in an allocation bitmap
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Extent {
+    start: u32,
+    len: u32,
+}
+
+impl Extent {
+    fn end(&self) -> u32 {
+        self.start + self.len
+    }
+}
+
+/// In-memory copy of an allocation bitmap, summarised as a sorted list
+/// of free extents
+#[derive(Debug)]
+pub struct BitmapSummary {
+    bitmap: Vec<u8>,
+    free_extents: Vec<Extent>,
+}
+
+impl BitmapSummary {
+    /// Summarise the `nbr_bits` first bits of `bitmap`
+    pub fn new(bitmap: Vec<u8>, nbr_bits: u32) -> IoResult<Self> {
+        let mut free_extents: Vec<Extent> = Vec::new();
+        let mut current: Option<Extent> = None;
+
+        for index in 0..nbr_bits {
+            if bitmap.get_bit(index as usize) {
+                if let Some(extent) = current.take() {
+                    free_extents.try_reserve(1)?;
+                    free_extents.push(extent);
+                }
+            } else {
+                match current.as_mut() {
+                    Some(extent) => extent.len += 1,
+                    None => current = Some(Extent { start: index, len: 1 }),
+                }
+            }
+        }
+        if let Some(extent) = current {
+            free_extents.try_reserve(1)?;
+            free_extents.push(extent);
+        }
+        Ok(Self {
+            bitmap,
+            free_extents,
+        })
+    }
+
+    /// Position in `free_extents` of the first extent which ends after `index`
+    fn extent_position(&self, index: u32) -> usize {
+        self.free_extents.partition_point(|e| e.end() <= index)
+    }
+
+    /// Get the first free bit at or after `from`
+    pub fn next_free(&self, from: u32) -> Option<u32> {
+        self.free_extents
+            .get(self.extent_position(from))
+            .map(|extent| max(extent.start, from))
+    }
+
+    /// Get the end (exclusive) of the free extent which contains the free bit `index`
+    pub fn free_extent_end(&self, index: u32) -> u32 {
+        let extent = self.free_extents[self.extent_position(index)];
+        debug_assert!(extent.start <= index);
+        extent.end()
+    }
+
+    pub fn is_free(&self, index: u32) -> bool {
+        !self.bitmap.get_bit(index as usize)
+    }
+
+    /// Get the byte of the bitmap which contains the bit `index`, as it must be written on the disk
+    pub fn byte(&self, index: u32) -> u8 {
+        self.bitmap[(index / 8) as usize]
+    }
+
+    /// Mark the free bit `index` as used
+    pub fn set_used(&mut self, index: u32) -> IoResult<()> {
+        self.free_extents.try_reserve(1)?;
+        let position = self.extent_position(index);
+        let Extent { start, len } = *self.free_extents.get(position).ok_or(Errno::EIO)?;
+        if start > index {
+            // the bit is not free, the bitmap is corrupted
+            return Err(Errno::EIO);
+        }
+        let end = start + len;
+
+        if len == 1 {
+            self.free_extents.remove(position);
+        } else if index == start {
+            self.free_extents[position] = Extent {
+                start: start + 1,
+                len: len - 1,
+            };
+        } else if index == end - 1 {
+            self.free_extents[position].len -= 1;
+        } else {
+            self.free_extents[position].len = index - start;
+            self.free_extents.insert(
+                position + 1,
+                Extent {
+                    start: index + 1,
+                    len: end - index - 1,
+                },
+            );
+        }
+        self.bitmap.set_bit(index as usize, true);
+        Ok(())
+    }
+
+    /// Mark the used bit `index` as free
+    pub fn set_free(&mut self, index: u32) -> IoResult<()> {
+        if self.is_free(index) {
+            // double free, the bitmap is corrupted
+            return Err(Errno::EIO);
+        }
+        self.free_extents.try_reserve(1)?;
+        let position = self.extent_position(index);
+        let merge_prev = position > 0 && self.free_extents[position - 1].end() == index;
+        let merge_next = position < self.free_extents.len()
+            && self.free_extents[position].start == index + 1;
+
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let next_len = self.free_extents[position].len;
+                self.free_extents[position - 1].len += 1 + next_len;
+                self.free_extents.remove(position);
+            }
+            (true, false) => self.free_extents[position - 1].len += 1,
+            (false, true) => {
+                self.free_extents[position].start -= 1;
+                self.free_extents[position].len += 1;
+            }
+            (false, false) => self
+                .free_extents
+                .insert(position, Extent { start: index, len: 1 }),
+        }
+        self.bitmap.set_bit(index as usize, false);
+        Ok(())
+    }
+}
+
+/// In-memory state of a block group
+#[derive(Debug)]
+pub struct BlockGroup {
+    /// Write-through copy of the on-disk descriptor
+    pub descriptor: BlockGroupDescriptor,
+    /// Lazily loaded summary of the block usage bitmap
+    block_bitmap: Option<BitmapSummary>,
+    /// Lazily loaded summary of the inode usage bitmap
+    inode_bitmap: Option<BitmapSummary>,
+}
+
+impl BlockGroup {
+    pub fn new(descriptor: BlockGroupDescriptor) -> Self {
+        Self {
+            descriptor,
+            block_bitmap: None,
+            inode_bitmap: None,
+        }
+    }
+}
+
+/// A window of free blocks reserved ahead of a sequential writer. The
+/// blocks stay free on the disk, the other inodes just avoid them
+#[derive(Debug, Copy, Clone)]
+pub struct Reservation {
+    start: Block,
+    /// exclusive
+    end: Block,
+}
+
+impl Reservation {
+    fn contains(&self, block: Block) -> bool {
+        block >= self.start && block < self.end
+    }
+}
+
+/// How a data block missing from an inode must be handled
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Alloc {
+    /// Do not allocate, fail on a hole
+    No,
+    /// Allocate the block and fill it with zeroes
+    Zeroed,
+    /// Allocate the block without zeroing it: the caller overwrites it entirely
+    Overwrite,
+}
+
+impl Alloc {
+    /// The policy to apply to the indirect blocks leading to a data
+    /// block: they are pointer tables and must always be zeroed
+    pub fn indirect(self) -> Self {
+        match self {
+            Alloc::No => Alloc::No,
+            _ => Alloc::Zeroed,
+        }
+    }
+}
+
+impl Ext2Filesystem {
+    /// Read the whole block group descriptor table in memory
+    pub(crate) fn load_block_groups(&mut self) -> IoResult<()> {
+        self.groups.try_reserve_exact(self.nbr_block_grp as usize)?;
+        for n in 0..self.nbr_block_grp {
+            let descriptor: BlockGroupDescriptor =
+                self.disk.read_struct(self.block_grp_descriptor_addr(n))?;
+            self.groups.push(BlockGroup::new(descriptor));
+        }
+        Ok(())
+    }
+
+    /// Get the block group `n`, which may come from a corrupted disk
+    fn group(&self, n: u32) -> IoResult<&BlockGroup> {
+        self.groups.get(n as usize).ok_or(Errno::EIO)
+    }
+
+    /// Get the block group `n` mutably, which may come from a corrupted disk
+    fn group_mut(&mut self, n: u32) -> IoResult<&mut BlockGroup> {
+        self.groups.get_mut(n as usize).ok_or(Errno::EIO)
+    }
+
+    /// Update the descriptor of the block group `n`, in memory and on the disk
+    pub(crate) fn set_block_grp_descriptor(
+        &mut self,
+        n: u32,
+        descriptor: BlockGroupDescriptor,
+    ) -> IoResult<()> {
+        self.group_mut(n)?.descriptor = descriptor;
+        self.disk
+            .write_struct(self.block_grp_descriptor_addr(n), &descriptor)
+    }
+
+    /// Read a whole block, such as an allocation bitmap
+    pub(crate) fn read_block(&mut self, block: Block) -> IoResult<Vec<u8>> {
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(self.block_size as usize)?;
+        buf.resize(self.block_size as usize, 0);
+        let count = self.disk.read_buffer(self.to_addr(block), &mut buf)?;
+        if count != self.block_size as u64 {
+            return Err(Errno::EIO);
+        }
+        Ok(buf)
+    }
+
+    /// Get the summary of the block bitmap of the group `n`, reading it if necessary
+    fn block_bitmap(&mut self, n: u32) -> IoResult<&mut BitmapSummary> {
+        if self.group(n)?.block_bitmap.is_none() {
+            let bitmap = self.read_block(self.group(n)?.descriptor.block_usage_bitmap)?;
+            let summary = BitmapSummary::new(bitmap, self.nbr_blocks_in_grp(n))?;
+            self.group_mut(n)?.block_bitmap = Some(summary);
+        }
+        Ok(self
+            .group_mut(n)?
+            .block_bitmap
+            .as_mut()
+            .expect("block bitmap should be loaded"))
+    }
+
+    /// Get the summary of the inode bitmap of the group `n`, reading it if necessary
+    fn inode_bitmap(&mut self, n: u32) -> IoResult<&mut BitmapSummary> {
+        if self.group(n)?.inode_bitmap.is_none() {
+            let bitmap = self.read_block(self.group(n)?.descriptor.inode_usage_bitmap)?;
+            let summary = BitmapSummary::new(bitmap, self.superblock.inodes_per_block_grp)?;
+            self.group_mut(n)?.inode_bitmap = Some(summary);
+        }
+        Ok(self
+            .group_mut(n)?
+            .inode_bitmap
+            .as_mut()
+            .expect("inode bitmap should be loaded"))
+    }
+
+    /// Get the number of blocks of the group `n`, the last group may be truncated
+    fn nbr_blocks_in_grp(&self, n: u32) -> u32 {
+        let block_per_grp = self.superblock.get_block_per_block_grp().0;
+        let first_data_block = self.superblock.get_first_data_block().0;
+        min(
+            block_per_grp,
+            self.superblock.nbr_blocks - first_data_block - n * block_per_grp,
+        )
+    }
+
+    /// Convert a block number to its block group and its index in the group bitmap
+    fn block_to_grp(&self, block: Block) -> (u32, u32) {
+        let block_per_grp = self.superblock.get_block_per_block_grp().0;
+        let relative = block.0 - self.superblock.get_first_data_block().0;
+        (relative / block_per_grp, relative % block_per_grp)
+    }
+
+    /// Convert a block number read from the disk to its block group and
+    /// its index in the bitmap of the group, it must be a data block
+    fn locate_block(&self, block: Block) -> IoResult<(u32, u32)> {
+        if block < self.superblock.get_first_data_block() || block.0 >= self.superblock.nbr_blocks {
+            return Err(Errno::EIO);
+        }
+        Ok(self.block_to_grp(block))
+    }
+
+    /// Convert a block group and an index in its bitmap to a block number
+    pub(crate) fn grp_to_block(&self, n: u32, index: u32) -> Block {
+        self.superblock.get_first_data_block()
+            + self.superblock.get_block_per_block_grp() * n
+            + Block(index)
+    }
+
+    /// Get the block group whose inode table contains the inode at `inode_addr`
+    pub(crate) fn inode_addr_grp(&self, inode_addr: InodeAddr) -> u32 {
+        let inode_table_size =
+            self.superblock.inodes_per_block_grp as u64 * self.superblock.get_size_inode() as u64;
+        self.groups
+            .iter()
+            .position(|group| {
+                let start = self.to_addr(group.descriptor.inode_table);
+                inode_addr >= start && inode_addr < start + inode_table_size
+            })
+            .unwrap_or(0) as u32
+    }
+
+    /// Get the number of blocks to reserve ahead of a writer of `inode`
+    fn preallocation_size(&self, inode: &Inode) -> u32 {
+        if inode.is_a_regular_file() {
+            match self.superblock.get_nbr_blocks_to_preallocate_for_files() {
+                0 => DEFAULT_PREALLOCATION,
+                n => n as u32,
+            }
+        } else if inode.is_a_directory() {
+            self.superblock
+                .get_nbr_blocks_to_preallocate_for_directories() as u32
+        } else {
+            0
+        }
+    }
+
+    /// Get the block the allocator should try first for the block
+    /// `block_off` of an inode: the one following the previous block of
+    /// the file, or the beginning of the block group of the inode
+    fn block_goal(&mut self, (inode, inode_addr): (&Inode, InodeAddr), block_off: u64) -> Block {
+        if block_off > 0 {
+            if let Ok(addr) = self.inode_data(inode, (block_off - 1) << self.block_shift) {
+                return self.to_block_addr(addr) + Block(1);
+            }
+        }
+        if let Some(reservation) = self.reservations.get(&inode_addr) {
+            return reservation.start;
+        }
+        self.grp_to_block(self.inode_addr_grp(inode_addr), 0)
+    }
+
+    /// Allocate a new block for the block `block_off` of an inode. The
+    /// first allocation of a lookup targets the block following the
+    /// previous one of the file, the next ones (the data block after
+    /// its indirect blocks) are chained right after it
+    pub(crate) fn alloc_inode_block(
+        &mut self,
+        (inode, inode_addr): (&Inode, InodeAddr),
+        block_off: u64,
+        goal: &mut Option<Block>,
+        alloc: Alloc,
+    ) -> IoResult<Block> {
+        debug_assert!(alloc != Alloc::No);
+        let target = match *goal {
+            Some(target) => target,
+            None => self.block_goal((inode, inode_addr), block_off),
+        };
+        let block = self.alloc_block(inode_addr, target, self.preallocation_size(inode))?;
+        if alloc == Alloc::Zeroed {
+            self.zero_block(block)?;
+        }
+        *goal = Some(block + Block(1));
+        Ok(block)
+    }
+
+    /// Fill a block with zeroes
+    fn zero_block(&mut self, block: Block) -> IoResult<()> {
+        const ZEROES: [u8; 1024] = [0; 1024];
+        let addr = self.to_addr(block);
+        for chunk in 0..(self.block_size as u64 / ZEROES.len() as u64) {
+            self.disk
+                .write_all(addr + chunk * ZEROES.len() as u64, &ZEROES)?;
+        }
+        Ok(())
+    }
+
+    /// Allocate a block for the inode at `inode_addr`, as close as
+    /// possible to `goal`. When the inode has no reservation, up to
+    /// `prealloc` free blocks following the new one are reserved for its
+    /// next allocations
+    pub(crate) fn alloc_block(
+        &mut self,
+        inode_addr: InodeAddr,
+        goal: Block,
+        prealloc: u32,
+    ) -> IoResult<Block> {
+        // A sequential writer takes the next block of its window
+        if let Some(reservation) = self.reservations.get_mut(&inode_addr) {
+            if reservation.start == goal {
+                let block = reservation.start;
+                reservation.start = reservation.start + Block(1);
+                if reservation.start == reservation.end {
+                    self.reservations.remove(&inode_addr);
+                }
+                let (n, index) = self.block_to_grp(block);
+                return self.take_block(n, index);
+            }
+            // Not sequential anymore, give the window back
+            self.reservations.remove(&inode_addr);
+        }
+
+        let goal = if goal >= self.superblock.get_first_data_block()
+            && goal.0 < self.superblock.nbr_blocks
+        {
+            goal
+        } else {
+            self.superblock.get_first_data_block()
+        };
+        let found = match self.find_free_block(inode_addr, goal, prealloc)? {
+            Some(found) => Some(found),
+            None if !self.reservations.is_empty() => {
+                // The only free blocks left are reserved: cancel all the windows
+                self.reservations.clear();
+                self.find_free_block(inode_addr, goal, prealloc)?
+            }
+            None => None,
+        };
+        let (n, index, window) = found.ok_or(Errno::ENOSPC)?;
+        let block = self.take_block(n, index)?;
+        if window > 0 {
+            self.reservations.try_insert(
+                inode_addr,
+                Reservation {
+                    start: block + Block(1),
+                    end: block + Block(1 + window),
+                },
+            )?;
+        }
+        Ok(block)
+    }
+
+    /// Find the first free block at or after `goal` which is not
+    /// reserved by another inode, wrapping around the filesystem.
+    /// Return its group, its index in the group and the size of the
+    /// window which can be reserved after it
+    fn find_free_block(
+        &mut self,
+        inode_addr: InodeAddr,
+        goal: Block,
+        prealloc: u32,
+    ) -> IoResult<Option<(u32, u32, u32)>> {
+        // The goal comes from the pointers of the file, start from the
+        // beginning of the filesystem if it is corrupted
+        let (goal_grp, goal_index) = self.locate_block(goal).unwrap_or((0, 0));
+
+        // The last iteration scans the beginning of the goal group
+        for i in 0..=self.nbr_block_grp {
+            let n = (goal_grp + i) % self.nbr_block_grp;
+            if self.groups[n as usize].descriptor.nbr_free_blocks == 0 {
+                continue;
+            }
+            let from = if i == 0 { goal_index } else { 0 };
+            self.block_bitmap(n)?;
+
+            let bitmap = self.groups[n as usize]
+                .block_bitmap
+                .as_ref()
+                .expect("block bitmap should be loaded");
+            let reserved_by_other = |block: Block| {
+                self.reservations
+                    .iter()
+                    .find(|(addr, reservation)| **addr != inode_addr && reservation.contains(block))
+                    .map(|(_, reservation)| *reservation)
+            };
+
+            let mut candidate = bitmap.next_free(from);
+            while let Some(index) = candidate {
+                let block = self.grp_to_block(n, index);
+                if let Some(reservation) = reserved_by_other(block) {
+                    let (end_grp, end_index) = self.block_to_grp(reservation.end);
+                    candidate = if end_grp == n {
+                        bitmap.next_free(end_index)
+                    } else {
+                        None
+                    };
+                    continue;
+                }
+                let mut window_end = min(bitmap.free_extent_end(index), index + 1 + prealloc);
+                for (_, reservation) in self
+                    .reservations
+                    .iter()
+                    .filter(|(addr, _)| **addr != inode_addr)
+                {
+                    if reservation.start > block && reservation.start < self.grp_to_block(n, window_end)
+                    {
+                        window_end = self.block_to_grp(reservation.start).1;
+                    }
+                }
+                return Ok(Some((n, index, window_end - index - 1)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Mark the block `index` of the group `n` as used
+    fn take_block(&mut self, n: u32, index: u32) -> IoResult<Block> {
+        let bitmap_addr = self.to_addr(self.group(n)?.descriptor.block_usage_bitmap);
+        let byte = {
+            let bitmap = self.block_bitmap(n)?;
+            bitmap.set_used(index)?;
+            bitmap.byte(index)
+        };
+        self.disk.write_struct(bitmap_addr + index as u64 / 8, &byte)?;
+
+        let mut descriptor = self.group(n)?.descriptor;
+        descriptor.nbr_free_blocks -= 1;
+        self.set_block_grp_descriptor(n, descriptor)?;
+        self.superblock.nbr_free_blocks -= 1;
+        self.disk
+            .write_struct(self.superblock_addr, &self.superblock)?;
+        Ok(self.grp_to_block(n, index))
+    }
+
+    /// Free the block `block_nbr`
+    pub(crate) fn free_block(&mut self, block_nbr: Block) -> IoResult<()> {
+        let (n, index) = self.locate_block(block_nbr)?;
+        let bitmap_addr = self.to_addr(self.group(n)?.descriptor.block_usage_bitmap);
+        let byte = {
+            let bitmap = self.block_bitmap(n)?;
+            bitmap.set_free(index)?;
+            bitmap.byte(index)
+        };
+        self.disk.write_struct(bitmap_addr + index as u64 / 8, &byte)?;
+        // The block may have held pointers
+        self.cache.forget(self.to_addr(block_nbr));
+
+        let mut descriptor = self.group(n)?.descriptor;
+        descriptor.nbr_free_blocks += 1;
+        self.set_block_grp_descriptor(n, descriptor)?;
+        self.superblock.nbr_free_blocks += 1;
+        self.disk
+            .write_struct(self.superblock_addr, &self.superblock)?;
+        Ok(())
+    }
+
+    /// Give back the blocks reserved ahead of the inode `inode_nbr`,
+    /// must be called when the last writer closes the file
+    pub fn discard_preallocation(&mut self, inode_nbr: InodeNbr) -> IoResult<()> {
+        let inode_addr = self.inode_addr(inode_nbr)?;
+        self.reservations.remove(&inode_addr);
+        Ok(())
+    }
+
+    /// Check in the inode bitmap whether the inode `inode_nbr` is allocated
+    pub(crate) fn is_inode_allocated(&mut self, inode_nbr: InodeNbr) -> IoResult<bool> {
+        assert!(inode_nbr >= 1);
+        let n = (inode_nbr - 1) / self.superblock.inodes_per_block_grp;
+        let index = (inode_nbr - 1) % self.superblock.inodes_per_block_grp;
+        if n >= self.nbr_block_grp {
+            return Err(Errno::EINVAL);
+        }
+        Ok(!self.inode_bitmap(n)?.is_free(index))
+    }
+
+    /// Allocate an inode for a new entry of the directory `parent_inode_nbr`
+    pub(crate) fn alloc_inode(
+        &mut self,
+        parent_inode_nbr: InodeNbr,
+        is_directory: bool,
+    ) -> IoResult<InodeNbr> {
+        let parent_grp = (parent_inode_nbr - 1) / self.superblock.inodes_per_block_grp;
+        if parent_grp >= self.nbr_block_grp {
+            return Err(Errno::EINVAL);
+        }
+        let n = if is_directory {
+            self.find_group_orlov(parent_inode_nbr, parent_grp)
+        } else {
+            self.find_group_other(parent_grp)
+        }
+        .ok_or(Errno::ENOSPC)?;
+
+        let bitmap_addr = self.to_addr(self.group(n)?.descriptor.inode_usage_bitmap);
+        let (index, byte) = {
+            let bitmap = self.inode_bitmap(n)?;
+            // The descriptor said there was a free inode
+            let index = bitmap.next_free(0).ok_or(Errno::EIO)?;
+            bitmap.set_used(index)?;
+            (index, bitmap.byte(index))
+        };
+        self.disk.write_struct(bitmap_addr + index as u64 / 8, &byte)?;
+
+        let mut descriptor = self.group(n)?.descriptor;
+        descriptor.nbr_free_inodes -= 1;
+        if is_directory {
+            descriptor.nbr_directories += 1;
+        }
+        self.set_block_grp_descriptor(n, descriptor)?;
+        self.superblock.nbr_free_inodes -= 1;
+        self.disk
+            .write_struct(self.superblock_addr, &self.superblock)?;
+        Ok(self.superblock.inodes_per_block_grp * n + index + 1)
+    }
+
+    /// Free the inode `inode_nbr` in its bitmap
+    pub(crate) fn free_inode_nbr(&mut self, inode_nbr: InodeNbr, is_directory: bool) -> IoResult<()> {
+        let relative = inode_nbr.checked_sub(1).ok_or(Errno::EINVAL)?;
+        let n = relative / self.superblock.inodes_per_block_grp;
+        let index = relative % self.superblock.inodes_per_block_grp;
+
+        let bitmap_addr = self.to_addr(self.group(n)?.descriptor.inode_usage_bitmap);
+        let byte = {
+            let bitmap = self.inode_bitmap(n)?;
+            bitmap.set_free(index)?;
+            bitmap.byte(index)
+        };
+        self.disk.write_struct(bitmap_addr + index as u64 / 8, &byte)?;
+
+        let mut descriptor = self.group(n)?.descriptor;
+        descriptor.nbr_free_inodes += 1;
+        if is_directory {
+            descriptor.nbr_directories = descriptor.nbr_directories.saturating_sub(1);
+        }
+        self.set_block_grp_descriptor(n, descriptor)?;
+        self.superblock.nbr_free_inodes += 1;
+        self.disk
+            .write_struct(self.superblock_addr, &self.superblock)?;
+        Ok(())
+    }
+
+    /// Get the average number of free inodes, free blocks and directories per group
+    fn group_averages(&self) -> (u32, u32, u32) {
+        let nbr_directories: u32 = self
+            .groups
+            .iter()
+            .map(|group| group.descriptor.nbr_directories as u32)
+            .sum();
+        (
+            self.superblock.nbr_free_inodes / self.nbr_block_grp,
+            self.superblock.nbr_free_blocks / self.nbr_block_grp,
+            nbr_directories / self.nbr_block_grp,
+        )
+    }
+
+    /// Choose the block group of a new directory, after the Orlov
+    /// allocator: top level directories are spread across the groups
+    /// which have more free space than average, the others stay near
+    /// their parent unless its group is already crowded
+    fn find_group_orlov(&mut self, parent_inode_nbr: InodeNbr, parent_grp: u32) -> Option<u32> {
+        let nbr_grp = self.nbr_block_grp;
+        let (avg_free_inodes, avg_free_blocks, avg_directories) = self.group_averages();
+
+        if parent_inode_nbr == ROOT_INODE_NBR {
+            let start = (self.last_orlov_group + 1) % nbr_grp;
+            let best = (0..nbr_grp)
+                .map(|i| (start + i) % nbr_grp)
+                .filter(|n| {
+                    let descriptor = self.groups[*n as usize].descriptor;
+                    descriptor.nbr_free_inodes as u32 >= max(avg_free_inodes, 1)
+                        && descriptor.nbr_free_blocks as u32 >= avg_free_blocks
+                })
+                .min_by_key(|n| self.groups[*n as usize].descriptor.nbr_directories);
+            if let Some(n) = best {
+                self.last_orlov_group = n;
+                return Some(n);
+            }
+        } else {
+            let inodes_per_grp = self.superblock.inodes_per_block_grp;
+            let block_per_grp = self.superblock.get_block_per_block_grp().0;
+            let max_directories = avg_directories + inodes_per_grp / 16;
+            let min_inodes = max(avg_free_inodes.saturating_sub(inodes_per_grp / 4), 1);
+            let min_blocks = avg_free_blocks.saturating_sub(block_per_grp / 4);
+
+            let found = (0..nbr_grp).map(|i| (parent_grp + i) % nbr_grp).find(|n| {
+                let descriptor = self.groups[*n as usize].descriptor;
+                (descriptor.nbr_directories as u32) < max_directories
+                    && descriptor.nbr_free_inodes as u32 >= min_inodes
+                    && descriptor.nbr_free_blocks as u32 >= min_blocks
+            });
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        // Fallback: the first group with an average number of free inodes, then any
+        (0..nbr_grp)
+            .map(|i| (parent_grp + i) % nbr_grp)
+            .find(|n| {
+                self.groups[*n as usize].descriptor.nbr_free_inodes as u32
+                    >= max(avg_free_inodes, 1)
+            })
+            .or_else(|| self.find_any_group_with_free_inodes(parent_grp))
+    }
+
+    /// Choose the block group of a new file: its parent's one if it
+    /// has room, else a group chosen by quadratic hashing from it
+    fn find_group_other(&self, parent_grp: u32) -> Option<u32> {
+        let nbr_grp = self.nbr_block_grp;
+        let has_room = |n: u32| {
+            let descriptor = self.groups[n as usize].descriptor;
+            descriptor.nbr_free_inodes != 0 && descriptor.nbr_free_blocks != 0
+        };
+        if has_room(parent_grp) {
+            return Some(parent_grp);
+        }
+        let mut n = parent_grp;
+        let mut step = 1;
+        while step < nbr_grp {
+            n = (n + step) % nbr_grp;
+            if has_room(n) {
+                return Some(n);
+            }
+            step <<= 1;
+        }
+        self.find_any_group_with_free_inodes(parent_grp)
+    }
+
+    fn find_any_group_with_free_inodes(&self, from: u32) -> Option<u32> {
+        (0..self.nbr_block_grp)
+            .map(|i| (from + i) % self.nbr_block_grp)
+            .find(|n| self.groups[*n as usize].descriptor.nbr_free_inodes != 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BitmapSummary;
+
+    fn summary(bits: &[bool]) -> BitmapSummary {
+        let mut bitmap = vec![0xff; (bits.len() + 7) / 8];
+        for (i, used) in bits.iter().enumerate() {
+            if !used {
+                bitmap[i / 8] &= !(1 << (i % 8));
+            }
+        }
+        BitmapSummary::new(bitmap, bits.len() as u32).unwrap()
+    }
+
+    #[test]
+    fn next_free() {
+        let s = summary(&[true, false, false, true, true, false, true, true]);
+        assert_eq!(s.next_free(0), Some(1));
+        assert_eq!(s.next_free(2), Some(2));
+        assert_eq!(s.next_free(3), Some(5));
+        assert_eq!(s.next_free(6), None);
+        assert_eq!(s.free_extent_end(1), 3);
+    }
+
+    #[test]
+    fn set_used_splits_extents() {
+        let mut s = summary(&[false; 8]);
+        s.set_used(3).unwrap();
+        assert_eq!(s.next_free(3), Some(4));
+        assert_eq!(s.free_extent_end(0), 3);
+        assert_eq!(s.byte(3), 0b0000_1000);
+        assert!(s.set_used(3).is_err());
+    }
+
+    #[test]
+    fn set_free_merges_extents() {
+        let mut s = summary(&[false, true, false, true, false]);
+        s.set_free(1).unwrap();
+        assert_eq!(s.free_extent_end(0), 3);
+        s.set_free(3).unwrap();
+        assert_eq!(s.free_extent_end(0), 5);
+        assert!(s.set_free(3).is_err());
+    }
+}
@@ -211,7 +211,26 @@ impl Inode {
             }
             block_data
         };
-        self.nbr_disk_sectors = block_data as u32;
+        let xattr_data = if self.extended_attribute_block != 0 { multiplier } else { 0 };
+        self.nbr_disk_sectors = (block_data + xattr_data) as u32;
+    }
+
+    /// Get the block holding the extended attributes of the inode, 0 if none.
+    pub fn get_extended_attribute_block(&self) -> Block {
+        Block(self.extended_attribute_block)
+    }
+
+    /// Set the block holding the extended attributes of the inode,
+    /// keeping the count of disk sectors in use consistent.
+    pub fn set_extended_attribute_block(&mut self, block: Block, block_size: u32) {
+        let multiplier = block_size / 512;
+        if self.extended_attribute_block != 0 {
+            self.nbr_disk_sectors -= multiplier;
+        }
+        if block.0 != 0 {
+            self.nbr_disk_sectors += multiplier;
+        }
+        self.extended_attribute_block = block.0;
     }
     pub fn unlink(&mut self) -> IoResult<()> {
         unimplemented!()
@@ -24,7 +24,7 @@ pub struct BlockGroupDescriptor {
     pub nbr_free_inodes: u16,
     /// Number of directories in group
     /*16 	17 	2*/
-    nbr_directories: u16,
+    pub nbr_directories: u16,
     pad: u16,
     reserved: [u8; 12],
 }
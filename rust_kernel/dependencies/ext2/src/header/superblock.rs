@@ -169,6 +169,41 @@ impl SuperBlock {
     pub fn get_size_inode(&self) -> u16 {
         self.size_inode
     }
+
+    /// Get the block number of the block containing the superblock, which is the first data block
+    pub fn get_first_data_block(&self) -> Block {
+        self.block_containing_superblock
+    }
+
+    /// Get the number of blocks to preallocate for files
+    pub fn get_nbr_blocks_to_preallocate_for_files(&self) -> u8 {
+        self.number_of_blocks_to_preallocate_for_files
+    }
+
+    /// Get the number of blocks to preallocate for directories, 0 if the feature is not enabled
+    pub fn get_nbr_blocks_to_preallocate_for_directories(&self) -> u8 {
+        let optional_features_flag = self.optional_features_flag;
+        if optional_features_flag.contains(
+            OptionalFeaturesFlag::PREALLOCATE_SOME_NUMBER_OF_BLOCKS_A_DIRECTORY_WHEN_CREATING_A_NEW_ONE,
+        ) {
+            self.number_of_blocks_to_preallocate_for_directories
+        } else {
+            0
+        }
+    }
+
+    /// Check whether some inodes may have extended attributes
+    pub fn has_extended_attributes(&self) -> bool {
+        let optional_features_flag = self.optional_features_flag;
+        optional_features_flag.contains(OptionalFeaturesFlag::INODES_HAVE_EXTENDED_ATTRIBUTES)
+    }
+
+    /// Record that some inodes have extended attributes
+    pub fn set_extended_attributes(&mut self) {
+        let mut optional_features_flag = self.optional_features_flag;
+        optional_features_flag.insert(OptionalFeaturesFlag::INODES_HAVE_EXTENDED_ATTRIBUTES);
+        self.optional_features_flag = optional_features_flag;
+    }
 }
 
 /// SuperBlock contains the file System state
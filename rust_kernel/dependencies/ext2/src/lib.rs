@@ -17,13 +17,18 @@ pub use tools::{
     IoResult,
 };
 
-use bit_field::BitField;
 mod header;
 use header::{BlockGroupDescriptor, SuperBlock};
 
 mod body;
 pub use body::{DirectoryEntry, DirectoryEntryType, Inode};
 
+mod allocator;
+use allocator::{Alloc, BlockGroup, Reservation};
+
+mod xattr;
+pub use xattr::{POSIX_ACL_ACCESS, POSIX_ACL_DEFAULT, XATTR_NAME_MAX};
+
 #[cfg(not(feature = "std-print"))]
 #[allow(unused_imports)]
 #[macro_use]
@@ -32,8 +37,9 @@ extern crate terminal;
 extern crate alloc;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
-use bit_field::BitArray;
+use fallible_collections::btree::BTreeMap;
 
+use core::cmp::min;
 use core::mem::{size_of, MaybeUninit};
 
 /// Global structure of ext2Filesystem, such as disk partition.
@@ -47,6 +53,14 @@ pub struct Ext2Filesystem {
     block_mask: u32,
     block_shift: u32,
     cache: Cache<u64, Block>,
+    /// In-memory state of the block groups
+    groups: Vec<BlockGroup>,
+    /// Blocks reserved ahead of the sequential writers
+    reservations: BTreeMap<InodeAddr, Reservation>,
+    /// Block group of the last top level directory
+    last_orlov_group: u32,
+    /// Hashes of the known extended attribute blocks, to share them
+    xattr_blocks: BTreeMap<u32, u32>,
 }
 
 /// Used to help confirm the presence of Ext2 on a volume
@@ -126,7 +140,7 @@ impl Ext2Filesystem {
         let block_mask = block_size - 1;
         let block_shift = u32::trailing_zeros(block_size);
 
-        Ok(Self {
+        let mut ext2 = Self {
             block_size,
             block_mask,
             block_shift,
@@ -135,7 +149,13 @@ impl Ext2Filesystem {
             nbr_block_grp,
             disk,
             cache: Cache::new(block_size as usize / size_of::<Block>()),
-        })
+            groups: Vec::new(),
+            reservations: BTreeMap::new(),
+            last_orlov_group: 0,
+            xattr_blocks: BTreeMap::new(),
+        };
+        ext2.load_block_groups()?;
+        Ok(ext2)
     }
 
     /// go through all filesystem to find the Parent Inode and the entry of path
@@ -192,11 +212,13 @@ impl Ext2Filesystem {
     ) -> IoResult<()> {
         let size = inode.get_size();
         assert!(new_size <= size);
+        // The reserved window no longer follows the end of the file
+        self.reservations.remove(&inode_addr);
         if size == 0 {
             return Ok(());
         }
-        let new_size_block = self.to_block_addr(new_size);
-        // let curr_size = self.to_block_addr(align_next(size, self.block_size as u64));
+        // The block containing the new end of file is still partially used
+        let new_size_block = self.to_block(new_size);
         // size - 1 to get the previous block addr
         let curr_size = self.to_block_addr(size - 1);
 
@@ -209,6 +231,57 @@ impl Ext2Filesystem {
         Ok(())
     }
 
+    /// extend inode to the size `new_size`, the new bytes read as zeros.
+    /// As there is no hole, the new data blocks are allocated zeroed
+    pub fn extend_inode(
+        &mut self,
+        (inode, inode_addr): (&mut Inode, InodeAddr),
+        new_size: u64,
+    ) -> IoResult<()> {
+        const ZEROES: [u8; 1024] = [0; 1024];
+        let size = inode.get_size();
+        assert!(new_size >= size);
+        let block_size = self.block_size as u64;
+
+        // The end of the last block may still contain truncated data
+        let tail_end = min(align_next(size, block_size), new_size);
+        if size < tail_end {
+            let addr = self.inode_data(inode, size)?;
+            let mut offset = 0;
+            while size + offset < tail_end {
+                let len = min(tail_end - size - offset, ZEROES.len() as u64);
+                self.disk
+                    .write_all(addr + offset, &ZEROES[..len as usize])?;
+                offset += len;
+            }
+        }
+
+        let mut offset = align_next(size, block_size);
+        let mut res = Ok(());
+        while offset < new_size {
+            if let Err(e) = self.inode_data_alloc((inode, inode_addr), offset) {
+                res = Err(e);
+                break;
+            }
+            offset += block_size;
+        }
+        inode.update_size(min(offset, new_size), self.block_size);
+        self.disk.write_struct(inode_addr, inode)?;
+        if res.is_err() {
+            // Give back the blocks of a partial extension
+            self.truncate_inode((inode, inode_addr), size)?;
+        }
+        res
+    }
+
+    /// Write back the filesystem: all the metadata are written through
+    /// to the disk, so only the pointer cache is dropped and the disk
+    /// is asked to flush its own cache
+    pub fn sync(&mut self) -> IoResult<()> {
+        self.cache.invalidate();
+        self.disk.0.flush()
+    }
+
     /// delete inode `inode_nbr`
     pub fn free_inode(
         &mut self,
@@ -225,24 +298,19 @@ impl Ext2Filesystem {
             self.truncate_inode((inode, inode_addr), 0).unwrap();
         }
 
-        /* Unset Inode bitmap */
-        let block_grp = (inode_nbr - 1) / self.superblock.inodes_per_block_grp;
-        let index = (inode_nbr as u64 - 1) % self.superblock.inodes_per_block_grp as u64;
-        let (mut block_dtr, block_dtr_addr) = self.get_block_grp_descriptor(block_grp)?;
-        let bitmap_addr = self.to_addr(block_dtr.inode_usage_bitmap);
-        let mut bitmap: u8 = self.disk.read_struct(bitmap_addr + index / 8)?;
-        assert!(bitmap.get_bit((index % 8) as usize));
-        bitmap.set_bit((index % 8) as usize, false);
-        self.disk.write_struct(bitmap_addr + index / 8, &bitmap)?;
+        self.reservations.remove(&inode_addr);
 
+        /* Drop the extended attributes */
+        let xattr_block = inode.get_extended_attribute_block();
+        if xattr_block.0 != 0 {
+            self.release_xattr_block(xattr_block)?;
+            inode.set_extended_attribute_block(Block(0), self.block_size);
+            self.disk.write_struct(inode_addr, inode)?;
+        }
+
+        /* Unset Inode bitmap */
+        self.free_inode_nbr(inode_nbr, inode.is_a_directory())?;
         debug_assert!(self.get_inode(inode_nbr).is_err());
-        // TODO: check that with fsck
-        block_dtr.nbr_free_inodes += 1;
-        self.superblock.nbr_free_inodes += 1;
-        block_dtr.nbr_free_inodes;
-        self.disk
-            .write_struct(self.superblock_addr, &self.superblock)?;
-        self.disk.write_struct(block_dtr_addr, &block_dtr)?;
         Ok(())
     }
 
@@ -288,7 +356,7 @@ impl Ext2Filesystem {
         else {
             let next_entry_off = curr_offset as u64 + entry.get_size() as u64;
             let previous_entry_addr = self
-                .inode_data_may_alloc((&mut inode, inode_addr), previous_offset as u64, false)
+                .inode_data_may_alloc((&mut inode, inode_addr), previous_offset as u64, Alloc::No)
                 .unwrap();
             previous.set_size((next_entry_off - previous_offset as u64) as u16);
             previous.write_on_disk(previous_entry_addr, &mut self.disk)?;
@@ -313,64 +381,25 @@ impl Ext2Filesystem {
         )
     }
 
-    /// get inode nbr inode and return the Inode and it's address
-    pub fn get_inode(&mut self, inode: u32) -> IoResult<(Inode, InodeAddr)> {
+    /// get the address of the inode `inode` in the inode table, allocated or not
+    fn inode_addr(&mut self, inode: u32) -> IoResult<InodeAddr> {
         assert!(inode >= 1);
         let block_grp = (inode - 1) / self.superblock.inodes_per_block_grp;
         let index = (inode as u64 - 1) % self.superblock.inodes_per_block_grp as u64;
         let inode_offset = index as u64 * self.superblock.get_size_inode() as u64;
 
         let (block_dtr, _) = self.get_block_grp_descriptor(block_grp)?;
-        let bitmap_addr = self.to_addr(block_dtr.inode_usage_bitmap);
-        let bitmap: u8 = self.disk.read_struct(bitmap_addr + index / 8)?;
-        if !bitmap.get_bit((index % 8) as usize) {
-            return Err(Errno::ENOENT);
-        }
-
-        let inode_addr = self.to_addr(block_dtr.inode_table) + inode_offset;
-
-        Ok((self.disk.read_struct(inode_addr)?, inode_addr))
+        Ok(self.to_addr(block_dtr.inode_table) + inode_offset)
     }
 
-    //TODO: better handle disk error
-    /// try to allocate a new inode on block group n and return the inode number
-    fn alloc_inode_on_grp(&mut self, n: u32) -> Option<InodeNbr> {
-        let (mut block_dtr, block_dtr_addr) = self.get_block_grp_descriptor(n).ok()?;
-        if block_dtr.nbr_free_inodes == 0 {
-            return None;
-        }
-
-        // TODO: dynamic alloc ?
-        let bitmap_addr = self.to_addr(block_dtr.inode_usage_bitmap);
-        let mut bitmap: [u8; 1024] = self.disk.read_struct(bitmap_addr).ok()?;
-        for i in 0..self.superblock.inodes_per_block_grp {
-            if !bitmap.get_bit(i as usize) {
-                bitmap.set_bit(i as usize, true);
-                self.disk
-                    .write_struct(bitmap_addr + i as u64 / 8, &bitmap[(i / 8) as usize])
-                    .ok()?;
-                block_dtr.nbr_free_inodes -= 1;
-                self.superblock.nbr_free_inodes -= 1;
-                block_dtr.nbr_free_inodes;
-                self.disk
-                    .write_struct(self.superblock_addr, &self.superblock)
-                    .ok()?;
-                self.disk.write_struct(block_dtr_addr, &block_dtr).ok()?;
-                // TODO: Check the + 1
-                return Some(self.superblock.inodes_per_block_grp * n + i + 1);
-            }
+    /// get inode nbr inode and return the Inode and it's address
+    pub fn get_inode(&mut self, inode: u32) -> IoResult<(Inode, InodeAddr)> {
+        if !self.is_inode_allocated(inode)? {
+            return Err(Errno::ENOENT);
         }
-        None
-    }
+        let inode_addr = self.inode_addr(inode)?;
 
-    /// try to allocate a new inode anywhere on the filesystem and return the inode number
-    fn alloc_inode(&mut self) -> Option<InodeNbr> {
-        for n in 0..self.nbr_block_grp {
-            if let Some(n) = self.alloc_inode_on_grp(n) {
-                return Some(n);
-            }
-        }
-        None
+        Ok((self.disk.read_struct(inode_addr)?, inode_addr))
     }
 
     /// the the entry at offset entry_offset the last entry of the directory
@@ -409,7 +438,7 @@ impl Ext2Filesystem {
                 let offset = offset as u64;
 
                 let entry_addr = self
-                    .inode_data_may_alloc((&mut inode, inode_addr), offset, false)
+                    .inode_data_may_alloc((&mut inode, inode_addr), offset, Alloc::No)
                     .unwrap();
                 // debug_assert_eq!(self.disk.read_struct::<DirectoryEntry>(entry_addr), entry)?;
                 let entry_size = entry.size() as u64;
@@ -419,7 +448,7 @@ impl Ext2Filesystem {
                     // if we do not cross a Block
                     if self.to_block(new_offset) == self.to_block(new_offset + new_entry.size() as u64)
                     // and the block is already allocated
-                        && self.inode_data_may_alloc((&mut inode, inode_addr), new_offset, false).is_ok()
+                        && self.inode_data_may_alloc((&mut inode, inode_addr), new_offset, Alloc::No).is_ok()
                     //self.to_block( as u32) == self.to_block(offset)
                     {
                         new_offset
@@ -442,7 +471,7 @@ impl Ext2Filesystem {
         if offset >= inode.0.get_size() {
             return None;
         }
-        let base_addr = self.inode_data_may_alloc(inode, offset, false).ok()? as u64;
+        let base_addr = self.inode_data_may_alloc(inode, offset, Alloc::No).ok()? as u64;
         let dir_header: DirectoryEntry = self.disk.read_struct(base_addr).ok()?;
         Some(dir_header)
     }
@@ -475,85 +504,33 @@ impl Ext2Filesystem {
         self.to_addr(Block(offset)) + n as u64 * size_of::<BlockGroupDescriptor>() as u64
     }
 
-    /// read the block group descriptor from the block group number starting at 0
+    /// get the block group descriptor from the block group number starting at 0
     pub fn get_block_grp_descriptor(&mut self, n: u32) -> IoResult<(BlockGroupDescriptor, u64)> {
-        let block_grp_addr = self.block_grp_descriptor_addr(n);
-        let block_grp: BlockGroupDescriptor = self.disk.read_struct(block_grp_addr)?;
-        Ok((block_grp, block_grp_addr))
-    }
-
-    /// try to allocate a new block on block grp number `n`
-    fn alloc_block_on_grp(&mut self, n: u32) -> Option<Block> {
-        let (mut block_dtr, block_dtr_addr) = self.get_block_grp_descriptor(n).ok()?;
-        if block_dtr.nbr_free_blocks == 0 {
-            return None;
-        }
-        // TODO: dynamic alloc ?
-        let bitmap_addr = self.to_addr(block_dtr.block_usage_bitmap);
-        let mut bitmap: [u8; 1024] = self.disk.read_struct(bitmap_addr).ok()?;
-        for i in 0..self.superblock.get_block_per_block_grp().0 {
-            if !bitmap.get_bit(i as usize) {
-                bitmap.set_bit(i as usize, true);
-                self.disk
-                    .write_struct(bitmap_addr + i as u64 / 8, &bitmap[(i / 8) as usize])
-                    .ok()?;
-
-                block_dtr.nbr_free_blocks -= 1;
-                self.disk.write_struct(block_dtr_addr, &block_dtr).ok()?;
-                self.superblock.nbr_free_blocks -= 1;
-                self.disk
-                    .write_struct(self.superblock_addr, &self.superblock)
-                    .ok()?;
-                // TODO: Check the + 1
-                return Some(self.superblock.get_block_per_block_grp() * n + Block(i + 1));
-            }
-        }
-        None
-    }
-
-    /// try to allocate a new block anywhere on the filesystem
-    fn alloc_block(&mut self) -> Option<Block> {
-        for n in 0..self.nbr_block_grp {
-            if let Some(addr) = self.alloc_block_on_grp(n) {
-                // TODO: dynamic alloc ?
-                let _res = self.disk.write_buffer(self.to_addr(addr), &[0; 1024]);
-                return Some(addr);
-            }
-        }
-        None
-    }
-
-    /// try to free the block block_nbr
-    fn free_block(&mut self, block_nbr: Block) -> IoResult<()> {
-        let block_grp = (block_nbr.0 - 1) / self.superblock.get_block_per_block_grp().0;
-        let index = (block_nbr.0 as u64 - 1) % self.superblock.get_block_per_block_grp().0 as u64;
-
-        let (mut block_dtr, block_dtr_addr) = self.get_block_grp_descriptor(block_grp)?;
-        let bitmap_addr = self.to_addr(block_dtr.block_usage_bitmap);
-        let mut bitmap: u8 = self.disk.read_struct(bitmap_addr + index / 8)?;
-        assert!(bitmap.get_bit((index % 8) as usize));
-        bitmap.set_bit((index % 8) as usize, false);
-        self.disk.write_struct(bitmap_addr + index / 8, &bitmap)?;
-        block_dtr.nbr_free_blocks += 1;
-        self.disk.write_struct(block_dtr_addr, &block_dtr)?;
-        self.superblock.nbr_free_blocks += 1;
-        self.disk
-            .write_struct(self.superblock_addr, &self.superblock)?;
-        Ok(())
+        let block_grp = self.groups.get(n as usize).ok_or(Errno::EINVAL)?.descriptor;
+        Ok((block_grp, self.block_grp_descriptor_addr(n)))
     }
 
-    /// get the data of inode at offset `offset`, and allocate the data block if necessary
+    /// get the data of inode at offset `offset`, and allocate a zeroed data block if necessary
     fn inode_data_alloc(&mut self, inode: (&mut Inode, u64), offset: u64) -> IoResult<u64> {
-        self.inode_data_may_alloc(inode, offset, true)
+        self.inode_data_may_alloc(inode, offset, Alloc::Zeroed)
     }
 
     /// alloc a pointer (used by the function inode_data_alloc)
-    fn alloc_pointer(&mut self, pointer_addr: u64, alloc: bool) -> IoResult<Block> {
+    fn alloc_pointer(
+        &mut self,
+        (inode, inode_addr): (&Inode, InodeAddr),
+        pointer_addr: u64,
+        block_off: u64,
+        goal: &mut Option<Block>,
+        alloc: Alloc,
+    ) -> IoResult<Block> {
         err_if_zero({
             let pointer = self.disk.read_struct(pointer_addr)?;
-            if alloc && pointer == Block(0) {
-                let new_block = self.alloc_block().ok_or(Errno::ENOSPC)?;
+            if alloc != Alloc::No && pointer == Block(0) {
+                let new_block =
+                    self.alloc_inode_block((inode, inode_addr), block_off, goal, alloc)?;
                 self.disk.write_struct(pointer_addr, &new_block)?;
+                self.cache_pointer(pointer_addr, new_block);
                 new_block
             } else {
                 pointer
@@ -568,6 +545,7 @@ impl Ext2Filesystem {
             panic!("free pointer null");
         } else {
             self.disk.write_struct(pointer_addr, &Block(0))?;
+            self.cache_pointer(pointer_addr, Block(0));
             self.free_block(pointer)
         }
     }
@@ -695,18 +673,20 @@ impl Ext2Filesystem {
         &mut self,
         (inode, inode_addr): (&mut Inode, InodeAddr),
         offset: u64,
-        alloc: bool,
+        alloc: Alloc,
     ) -> IoResult<u64> {
         let block_off = offset / self.block_size as u64;
         let blocknumber_per_block = self.block_size as usize / size_of::<Block>();
+        // The indirect blocks and the data block are allocated next to each other
+        let mut goal = None;
 
         // SIMPLE ADDRESSING
         let mut offset_start = 0;
         let mut offset_end = 12;
         if block_off >= offset_start && block_off < offset_end {
-            if alloc && inode.direct_block_pointers[block_off as usize] == Block(0) {
+            if alloc != Alloc::No && inode.direct_block_pointers[block_off as usize] == Block(0) {
                 inode.direct_block_pointers[block_off as usize] =
-                    self.alloc_block().ok_or(Errno::ENOSPC)?;
+                    self.alloc_inode_block((&*inode, inode_addr), block_off, &mut goal, alloc)?;
                 self.disk.write_struct(inode_addr, inode)?;
             }
             return Ok(self.to_addr(err_if_zero(
@@ -722,16 +702,23 @@ impl Ext2Filesystem {
             let off = block_off - offset_start;
 
             let singly_indirect = err_if_zero({
-                if alloc && inode.singly_indirect_block_pointers == Block(0) {
-                    inode.singly_indirect_block_pointers =
-                        self.alloc_block().ok_or(Errno::ENOSPC)?;
+                if alloc != Alloc::No && inode.singly_indirect_block_pointers == Block(0) {
+                    inode.singly_indirect_block_pointers = self.alloc_inode_block(
+                        (&*inode, inode_addr),
+                        block_off,
+                        &mut goal,
+                        alloc.indirect(),
+                    )?;
                     self.disk.write_struct(inode_addr, inode)?;
                 }
                 inode.singly_indirect_block_pointers
             })?;
 
             let pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(singly_indirect) + off * size_of::<Block>() as u64,
+                block_off,
+                &mut goal,
                 alloc,
             )?;
 
@@ -744,20 +731,30 @@ impl Ext2Filesystem {
         if block_off >= offset_start && block_off < offset_end {
             let off = (block_off - offset_start) / blocknumber_per_block as u64;
             let doubly_indirect = err_if_zero({
-                if alloc && inode.doubly_indirect_block_pointers == Block(0) {
-                    inode.doubly_indirect_block_pointers =
-                        self.alloc_block().ok_or(Errno::ENOSPC)?;
+                if alloc != Alloc::No && inode.doubly_indirect_block_pointers == Block(0) {
+                    inode.doubly_indirect_block_pointers = self.alloc_inode_block(
+                        (&*inode, inode_addr),
+                        block_off,
+                        &mut goal,
+                        alloc.indirect(),
+                    )?;
                     self.disk.write_struct(inode_addr, inode)?;
                 }
                 inode.doubly_indirect_block_pointers
             })?;
             let pointer_to_pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(doubly_indirect) + off * size_of::<Block>() as u64,
-                alloc,
+                block_off,
+                &mut goal,
+                alloc.indirect(),
             )?;
             let off = (block_off - offset_start) % blocknumber_per_block as u64;
             let pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(pointer_to_pointer) + off * size_of::<Block>() as u64,
+                block_off,
+                &mut goal,
                 alloc,
             )?;
             return Ok(self.to_addr(pointer) + offset % self.block_size as u64);
@@ -772,31 +769,44 @@ impl Ext2Filesystem {
                 (block_off - offset_start) / (blocknumber_per_block * blocknumber_per_block) as u64;
 
             let tripply_indirect = err_if_zero({
-                if alloc && inode.triply_indirect_block_pointers == Block(0) {
-                    inode.triply_indirect_block_pointers =
-                        self.alloc_block().ok_or(Errno::ENOSPC)?;
+                if alloc != Alloc::No && inode.triply_indirect_block_pointers == Block(0) {
+                    inode.triply_indirect_block_pointers = self.alloc_inode_block(
+                        (&*inode, inode_addr),
+                        block_off,
+                        &mut goal,
+                        alloc.indirect(),
+                    )?;
                     self.disk.write_struct(inode_addr, inode)?;
                 }
                 inode.triply_indirect_block_pointers
             })?;
             let pointer_to_pointer_to_pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(tripply_indirect) + off * size_of::<Block>() as u64,
-                alloc,
+                block_off,
+                &mut goal,
+                alloc.indirect(),
             )?;
 
             let off = (((block_off - offset_start)
                 % (blocknumber_per_block * blocknumber_per_block) as u64)
                 / blocknumber_per_block as u64) as u64;
             let pointer_to_pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(pointer_to_pointer_to_pointer) + off * size_of::<Block>() as u64,
-                alloc,
+                block_off,
+                &mut goal,
+                alloc.indirect(),
             )?;
 
             let off = (((block_off - offset_start)
                 % (blocknumber_per_block * blocknumber_per_block) as u64)
                 % blocknumber_per_block as u64) as u64;
             let pointer: Block = self.alloc_pointer(
+                (&*inode, inode_addr),
                 self.to_addr(pointer_to_pointer) + off * size_of::<Block>() as u64,
+                block_off,
+                &mut goal,
                 alloc,
             )?;
 
@@ -885,6 +895,15 @@ impl Ext2Filesystem {
         Err(Errno::EFBIG)
     }
 
+    /// Update the cached copy of the pointer written at `pointer_addr`,
+    /// if its pointer block is cached
+    fn cache_pointer(&mut self, pointer_addr: u64, pointer: Block) {
+        let block_addr = pointer_addr & !(self.block_mask as u64);
+        let off = (pointer_addr - block_addr) as usize / size_of::<Block>();
+
+        self.cache.update(block_addr, off, pointer);
+    }
+
     /// Get a inode pointer
     #[inline(always)]
     fn get_pointer(&mut self, addr: u64, off: u64, level: Level) -> IoResult<Block> {
@@ -936,6 +955,24 @@ impl<K: Eq + PartialEq + Copy, T: Clone + Default> Cache<K, T> {
         }
     }
 
+    /// Update the data at `offset` in the layers holding `idx`
+    fn update(&mut self, idx: K, offset: usize, value: T) {
+        for entry in self.entries.iter_mut() {
+            if let Some(data) = entry.get_mut(idx) {
+                data[offset] = value.clone();
+            }
+        }
+    }
+
+    /// Drop the layers holding `idx`
+    fn forget(&mut self, idx: K) {
+        for entry in self.entries.iter_mut() {
+            if entry.get(idx).is_some() {
+                entry.invalidate();
+            }
+        }
+    }
+
     /// Try to get a layer borrowed data
     fn get(&self, idx: K, offset: usize, level: Level) -> Option<&T> {
         self.entries[get_index(level)].get(idx).map(|v| &v[offset])
@@ -990,6 +1027,13 @@ impl<K: Eq + PartialEq + Copy, T: Clone + Default> CacheEntry<K, T> {
         None
     }
 
+    fn get_mut(&mut self, idx: K) -> Option<&mut Vec<T>> {
+        match self.idx {
+            Some(stored_idx) if stored_idx == idx => Some(&mut self.data),
+            _ => None,
+        }
+    }
+
     fn update_layer(&mut self, idx: K) -> &mut Vec<T> {
         self.idx = Some(idx);
         &mut self.data
@@ -3,12 +3,12 @@
 use super::DirectoryEntryType;
 use super::{DirectoryEntry, Inode};
 use crate::tools::IoResult;
-use crate::Ext2Filesystem;
+use crate::{xattr, Alloc, Ext2Filesystem};
 use alloc::vec::Vec;
 use core::cmp::min;
 use core::convert::TryFrom;
 use fallible_collections::TryCollect;
-use libc_binding::{gid_t, uid_t, utimbuf, Errno, FileType};
+use libc_binding::{gid_t, uid_t, utimbuf, Errno, FileType, XattrFlags};
 
 impl Ext2Filesystem {
     /// The utime() function shall set the access and modification
@@ -83,7 +83,107 @@ impl Ext2Filesystem {
         if !inode.is_a_regular_file() {
             return Err(Errno::EISDIR);
         }
-        self.truncate_inode((&mut inode, inode_addr), new_size)
+        if new_size > inode.get_size() {
+            self.extend_inode((&mut inode, inode_addr), new_size)
+        } else {
+            self.truncate_inode((&mut inode, inode_addr), new_size)
+        }
+    }
+
+    /// The getxattr() function shall retrieve in `buf` the value of
+    /// the extended attribute `name`, and return its size. If `buf`
+    /// is empty, only the size of the value is returned
+    pub fn getxattr(&mut self, inode_nbr: u32, name: &str, buf: &mut [u8]) -> IoResult<usize> {
+        let (index, name) = xattr::split_name(name)?;
+        let (inode, _) = self.get_inode(inode_nbr)?;
+        let xattrs = self.read_xattrs(&inode)?;
+        let xattr = xattrs
+            .iter()
+            .find(|xattr| xattr.index == index && xattr.name == name)
+            .ok_or(Errno::ENODATA)?;
+        let acl;
+        let value = if xattr.is_acl() {
+            acl = xattr::acl_from_disk(&xattr.value)?;
+            &acl
+        } else {
+            &xattr.value
+        };
+        if buf.is_empty() {
+            return Ok(value.len());
+        }
+        if buf.len() < value.len() {
+            return Err(Errno::ERANGE);
+        }
+        buf[..value.len()].copy_from_slice(value);
+        Ok(value.len())
+    }
+
+    /// The setxattr() function shall set the value of the extended
+    /// attribute `name`. With XATTR_CREATE it fails if the attribute
+    /// already exists, with XATTR_REPLACE if it does not
+    pub fn setxattr(
+        &mut self,
+        inode_nbr: u32,
+        name: &str,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> IoResult<()> {
+        let (index, name) = xattr::split_name(name)?;
+        let (mut inode, inode_addr) = self.get_inode(inode_nbr)?;
+        let mut xattrs = self.read_xattrs(&inode)?;
+        let exists = xattrs
+            .iter()
+            .any(|xattr| xattr.index == index && xattr.name == name);
+        if exists && flags.contains(XattrFlags::XATTR_CREATE) {
+            return Err(Errno::EEXIST);
+        }
+        if !exists && flags.contains(XattrFlags::XATTR_REPLACE) {
+            return Err(Errno::ENODATA);
+        }
+        let acl;
+        let value = if xattr::is_acl_index(index) {
+            acl = xattr::acl_to_disk(value)?;
+            &acl
+        } else {
+            value
+        };
+        xattr::update_list(&mut xattrs, (index, name), Some(value))?;
+        self.write_xattrs((&mut inode, inode_addr), &xattrs)
+    }
+
+    /// The listxattr() function shall retrieve in `buf` the list of
+    /// the null terminated names of the extended attributes, and
+    /// return its size. If `buf` is empty, only the size is returned
+    pub fn listxattr(&mut self, inode_nbr: u32, buf: &mut [u8]) -> IoResult<usize> {
+        let (inode, _) = self.get_inode(inode_nbr)?;
+        let xattrs = self.read_xattrs(&inode)?;
+        let mut size = 0;
+        for xattr in xattrs.iter() {
+            let prefix = match xattr.prefix() {
+                Some(prefix) => prefix,
+                None => continue,
+            };
+            let len = prefix.len() + xattr.name.len() + 1;
+            if !buf.is_empty() {
+                let name = buf.get_mut(size..size + len).ok_or(Errno::ERANGE)?;
+                name[..prefix.len()].copy_from_slice(prefix.as_bytes());
+                name[prefix.len()..len - 1].copy_from_slice(&xattr.name);
+                name[len - 1] = 0;
+            }
+            size += len;
+        }
+        Ok(size)
+    }
+
+    /// The removexattr() function shall remove the extended attribute `name`
+    pub fn removexattr(&mut self, inode_nbr: u32, name: &str) -> IoResult<()> {
+        let (index, name) = xattr::split_name(name)?;
+        let (mut inode, inode_addr) = self.get_inode(inode_nbr)?;
+        let mut xattrs = self.read_xattrs(&inode)?;
+        if !xattr::update_list(&mut xattrs, (index, name), None)? {
+            return Err(Errno::ENODATA);
+        }
+        self.write_xattrs((&mut inode, inode_addr), &xattrs)
     }
 
     pub fn create(
@@ -96,7 +196,7 @@ impl Ext2Filesystem {
     ) -> IoResult<(DirectoryEntry, Inode)> {
         let direntry_type = DirectoryEntryType::try_from(file_type).expect("bad file type");
         //TODO: remove expect
-        let inode_nbr = self.alloc_inode().ok_or(Errno::ENOSPC)?;
+        let inode_nbr = self.alloc_inode(parent_inode_nbr, false)?;
         let (_, inode_addr) = self.get_inode(inode_nbr)?;
         let mut inode = Inode::new(file_type);
 
@@ -164,7 +264,7 @@ impl Ext2Filesystem {
         mode: FileType,
         (owner, group): (uid_t, gid_t),
     ) -> IoResult<(DirectoryEntry, Inode)> {
-        let inode_nbr = self.alloc_inode().ok_or(Errno::ENOSPC)?;
+        let inode_nbr = self.alloc_inode(parent_inode_nbr, true)?;
         let (_, inode_addr) = self.get_inode(inode_nbr)?;
         let mut inode = Inode::new((mode & FileType::PERMISSIONS_MASK) | FileType::DIRECTORY);
         inode.nbr_hard_links = 2;
@@ -218,11 +318,15 @@ impl Ext2Filesystem {
         if buf.len() == 0 {
             return Ok((0, inode));
         }
-        let data_address = self.inode_data_alloc((&mut inode, inode_addr), *file_offset)?;
         let offset = min(
             self.block_size as u64 - *file_offset % self.block_size as u64,
             buf.len() as u64,
         );
+        let data_address = self.inode_data_may_alloc(
+            (&mut inode, inode_addr),
+            *file_offset,
+            self.alloc_policy(offset),
+        )?;
         let data_write = self
             .disk
             .write_buffer(data_address, &buf[0..offset as usize])?;
@@ -236,7 +340,11 @@ impl Ext2Filesystem {
         }
 
         for chunk in buf[offset as usize..].chunks(self.block_size as usize) {
-            let data_address = self.inode_data_alloc((&mut inode, inode_addr), *file_offset)?;
+            let data_address = self.inode_data_may_alloc(
+                (&mut inode, inode_addr),
+                *file_offset,
+                self.alloc_policy(chunk.len() as u64),
+            )?;
             let data_write = self.disk.write_buffer(data_address, &chunk)?;
             *file_offset += data_write as u64;
             if inode.get_size() < *file_offset {
@@ -250,6 +358,16 @@ impl Ext2Filesystem {
         Ok((*file_offset - file_curr_offset_start, inode))
     }
 
+    /// a new block which is entirely overwritten by a write of `len`
+    /// bytes does not need to be zeroed first
+    fn alloc_policy(&self, len: u64) -> Alloc {
+        if len == self.block_size as u64 {
+            Alloc::Overwrite
+        } else {
+            Alloc::Zeroed
+        }
+    }
+
     /// return all the (directory, inode) conainted in inode_nbr
     pub fn lookup_directory(&mut self, inode_nbr: u32) -> IoResult<Vec<(DirectoryEntry, Inode)>> {
         let entries: Vec<DirectoryEntry> = self
@@ -354,7 +472,7 @@ impl Ext2Filesystem {
         timestamp: u32,
     ) -> IoResult<(DirectoryEntry, Inode)> {
         let direntry_type = DirectoryEntryType::SymbolicLink;
-        let inode_nbr = self.alloc_inode().ok_or(Errno::ENOSPC)?;
+        let inode_nbr = self.alloc_inode(parent_inode_nbr, false)?;
         let (_, inode_addr) = self.get_inode(inode_nbr)?;
         let access_mode =
             FileType::SYMBOLIC_LINK | FileType::S_IRWXO | FileType::S_IRWXG | FileType::S_IRWXU;
@@ -0,0 +1,501 @@
+//! This module contains the extended attributes of the ext2 driver
+//!
+//! All the extended attributes of an inode are stored in a single
+//! block pointed by the inode. The block starts with a header followed
+//! by the entries sorted by name, while their values are packed from
+//! the end of the block.
+//!
+//! Identical attribute blocks are shared between inodes and reference
+//! counted, so that a whole tree of files with the same ACL uses a
+//! single block. Shared blocks are never modified in place: a copy is
+//! written instead.
+//!
+//! POSIX ACLs are stored in a compact disk format and are converted
+//! from and to the format used by the xattr syscalls.
+
+use super::{Block, Ext2Filesystem, Inode, InodeAddr, IoResult};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use libc_binding::Errno;
+
+/// Magic number of an attribute block
+const XATTR_MAGIC: u32 = 0xEA02_0000;
+
+/// Maximum number of inodes sharing an attribute block
+const XATTR_REFCOUNT_MAX: u32 = 1024;
+
+/// Size of the header of an attribute block
+const HEADER_SIZE: usize = 32;
+
+/// Size of an entry, without its name
+const ENTRY_HEADER_SIZE: usize = 16;
+
+/// Names and values are padded to 4 bytes
+const XATTR_PAD: usize = 4;
+
+/// Maximum length of an attribute name
+pub const XATTR_NAME_MAX: usize = 255;
+
+/// Name of the ACL of a file
+pub const POSIX_ACL_ACCESS: &str = "system.posix_acl_access";
+
+/// Name of the ACL a directory gives to its new entries
+pub const POSIX_ACL_DEFAULT: &str = "system.posix_acl_default";
+
+/// On-disk name indexes of the ACLs, their name suffix is empty
+const INDEX_ACL_ACCESS: u8 = 2;
+const INDEX_ACL_DEFAULT: u8 = 3;
+
+/// Check whether the name index is the one of an ACL
+pub fn is_acl_index(index: u8) -> bool {
+    index == INDEX_ACL_ACCESS || index == INDEX_ACL_DEFAULT
+}
+
+/// The supported name prefixes and their on-disk name index
+const NAME_INDEXES: [(&str, u8); 5] = [
+    ("user.", 1),
+    (POSIX_ACL_ACCESS, INDEX_ACL_ACCESS),
+    (POSIX_ACL_DEFAULT, INDEX_ACL_DEFAULT),
+    ("trusted.", 4),
+    ("security.", 6),
+];
+
+/// ACL versions of the xattr syscalls format and of the disk format
+const ACL_XATTR_VERSION: u32 = 2;
+const ACL_DISK_VERSION: u32 = 1;
+
+/// ACL entry tags, only the named entries carry an id on disk
+const ACL_USER_OBJ: u16 = 0x01;
+const ACL_USER: u16 = 0x02;
+const ACL_GROUP_OBJ: u16 = 0x04;
+const ACL_GROUP: u16 = 0x08;
+const ACL_MASK: u16 = 0x10;
+const ACL_OTHER: u16 = 0x20;
+
+/// The id of the entries which are not named
+const ACL_UNDEFINED_ID: u32 = 0xffff_ffff;
+
+fn pad(len: usize) -> usize {
+    (len + XATTR_PAD - 1) & !(XATTR_PAD - 1)
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn write_u16(buf: &mut [u8], offset: usize, value: u16) {
+    buf[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn write_u32(buf: &mut [u8], offset: usize, value: u32) {
+    buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn try_vec_from(slice: &[u8]) -> IoResult<Vec<u8>> {
+    let mut v = Vec::new();
+    v.try_reserve_exact(slice.len())?;
+    v.extend_from_slice(slice);
+    Ok(v)
+}
+
+/// Split an attribute name into its on-disk name index and suffix
+pub fn split_name(name: &str) -> IoResult<(u8, &[u8])> {
+    if name.len() > XATTR_NAME_MAX {
+        return Err(Errno::ERANGE);
+    }
+    let (prefix, index) = NAME_INDEXES
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .ok_or(Errno::EOPNOTSUPP)?;
+    let suffix = &name.as_bytes()[prefix.len()..];
+    match *index {
+        INDEX_ACL_ACCESS | INDEX_ACL_DEFAULT if !suffix.is_empty() => Err(Errno::EOPNOTSUPP),
+        INDEX_ACL_ACCESS | INDEX_ACL_DEFAULT => Ok((*index, suffix)),
+        _ if suffix.is_empty() => Err(Errno::EINVAL),
+        _ => Ok((*index, suffix)),
+    }
+}
+
+/// An extended attribute of an inode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Xattr {
+    pub index: u8,
+    pub name: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl Xattr {
+    /// Get the prefix of the name of the attribute, None for the
+    /// namespaces which are not supported
+    pub fn prefix(&self) -> Option<&'static str> {
+        NAME_INDEXES
+            .iter()
+            .find(|(_, index)| *index == self.index)
+            .map(|(prefix, _)| *prefix)
+    }
+
+    /// Check whether the attribute is an ACL stored in the disk format
+    pub fn is_acl(&self) -> bool {
+        is_acl_index(self.index)
+    }
+
+    /// Get the size of the entry describing the attribute
+    fn entry_size(&self) -> usize {
+        ENTRY_HEADER_SIZE + pad(self.name.len())
+    }
+
+    /// Get the sort key of the entries in the block
+    fn key(&self) -> (u8, usize, &[u8]) {
+        (self.index, self.name.len(), &self.name)
+    }
+
+    /// Compute the hash of the entry: on the name, then on the
+    /// value as padded little endian words
+    fn hash(&self) -> u32 {
+        let mut hash: u32 = 0;
+        for c in self.name.iter() {
+            // The name is hashed as signed chars
+            hash = (hash << 5) ^ (hash >> 27) ^ (*c as i8 as i32 as u32);
+        }
+        for word in self.value.chunks(4) {
+            let mut bytes = [0; 4];
+            bytes[..word.len()].copy_from_slice(word);
+            hash = (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(bytes);
+        }
+        hash
+    }
+}
+
+/// Parse an attribute block
+fn parse_block(buf: &[u8]) -> IoResult<Vec<Xattr>> {
+    if read_u32(buf, 0) != XATTR_MAGIC || read_u32(buf, 8) != 1 {
+        return Err(Errno::EIO);
+    }
+    let mut xattrs = Vec::new();
+    let mut offset = HEADER_SIZE;
+    loop {
+        if offset + 4 > buf.len() {
+            return Err(Errno::EIO);
+        }
+        if read_u32(buf, offset) == 0 {
+            return Ok(xattrs);
+        }
+        if offset + ENTRY_HEADER_SIZE > buf.len() {
+            return Err(Errno::EIO);
+        }
+        let name_len = buf[offset] as usize;
+        let index = buf[offset + 1];
+        let value_offs = read_u16(buf, offset + 2) as usize;
+        let value_block = read_u32(buf, offset + 4);
+        let value_size = read_u32(buf, offset + 8) as usize;
+
+        let name_start = offset + ENTRY_HEADER_SIZE;
+        if value_block != 0
+            || name_start + name_len > buf.len()
+            || value_offs + value_size > buf.len()
+        {
+            return Err(Errno::EIO);
+        }
+        let xattr = Xattr {
+            index,
+            name: try_vec_from(&buf[name_start..name_start + name_len])?,
+            value: try_vec_from(&buf[value_offs..value_offs + value_size])?,
+        };
+        offset += xattr.entry_size();
+        xattrs.try_reserve(1)?;
+        xattrs.push(xattr);
+    }
+}
+
+/// Build an attribute block holding `xattrs`, which must be sorted.
+/// Fail with ENOSPC if they do not fit in a block
+fn build_block(xattrs: &[Xattr], block_size: usize) -> IoResult<Vec<u8>> {
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(block_size)?;
+    buf.resize(block_size, 0);
+
+    write_u32(&mut buf, 0, XATTR_MAGIC);
+    write_u32(&mut buf, 4, 1);
+    write_u32(&mut buf, 8, 1);
+
+    let mut offset = HEADER_SIZE;
+    let mut value_end = block_size;
+    let mut block_hash: u32 = 0;
+    for xattr in xattrs {
+        let value_size = pad(xattr.value.len());
+        // Keep room for the terminating null word
+        if offset + xattr.entry_size() + 4 + value_size > value_end {
+            return Err(Errno::ENOSPC);
+        }
+        let value_offs = if xattr.value.is_empty() {
+            0
+        } else {
+            value_end -= value_size;
+            buf[value_end..value_end + xattr.value.len()].copy_from_slice(&xattr.value);
+            value_end
+        };
+        let hash = xattr.hash();
+        buf[offset] = xattr.name.len() as u8;
+        buf[offset + 1] = xattr.index;
+        write_u16(&mut buf, offset + 2, value_offs as u16);
+        write_u32(&mut buf, offset + 8, xattr.value.len() as u32);
+        write_u32(&mut buf, offset + 12, hash);
+        let name_start = offset + ENTRY_HEADER_SIZE;
+        buf[name_start..name_start + xattr.name.len()].copy_from_slice(&xattr.name);
+        offset += xattr.entry_size();
+
+        block_hash = (block_hash << 16) ^ (block_hash >> 16) ^ hash;
+    }
+    // A null entry hash prevents the block from being shared
+    if xattrs.iter().any(|xattr| xattr.hash() == 0) {
+        block_hash = 0;
+    }
+    write_u32(&mut buf, 12, block_hash);
+    Ok(buf)
+}
+
+/// Check whether two attribute blocks hold the same attributes,
+/// whatever their reference count
+fn same_content(a: &[u8], b: &[u8]) -> bool {
+    a[..4] == b[..4] && a[8..] == b[8..]
+}
+
+/// Insert, replace or remove (`value` is None) the attribute `index`
+/// `name` in the sorted list `xattrs`. Return whether it existed
+pub fn update_list(
+    xattrs: &mut Vec<Xattr>,
+    (index, name): (u8, &[u8]),
+    value: Option<&[u8]>,
+) -> IoResult<bool> {
+    let key = (index, name.len(), name);
+    let position = xattrs.binary_search_by(|xattr| xattr.key().cmp(&key));
+    match (position, value) {
+        (Ok(i), Some(value)) => {
+            xattrs[i].value = try_vec_from(value)?;
+            Ok(true)
+        }
+        (Ok(i), None) => {
+            xattrs.remove(i);
+            Ok(true)
+        }
+        (Err(i), Some(value)) => {
+            let xattr = Xattr {
+                index,
+                name: try_vec_from(name)?,
+                value: try_vec_from(value)?,
+            };
+            xattrs.try_reserve(1)?;
+            xattrs.insert(i, xattr);
+            Ok(false)
+        }
+        (Err(_), None) => Ok(false),
+    }
+}
+
+/// Convert an ACL from the disk format to the xattr syscalls format
+pub fn acl_from_disk(value: &[u8]) -> IoResult<Vec<u8>> {
+    if value.len() < 4 || read_u32(value, 0) != ACL_DISK_VERSION {
+        return Err(Errno::EIO);
+    }
+    let mut acl = Vec::new();
+    acl.try_reserve_exact(value.len() * 2)?;
+    acl.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+    let mut offset = 4;
+    while offset < value.len() {
+        if offset + 4 > value.len() {
+            return Err(Errno::EIO);
+        }
+        let tag = read_u16(value, offset);
+        let (id, entry_size) = match tag {
+            ACL_USER | ACL_GROUP if offset + 8 <= value.len() => (read_u32(value, offset + 4), 8),
+            ACL_USER_OBJ | ACL_GROUP_OBJ | ACL_MASK | ACL_OTHER => (ACL_UNDEFINED_ID, 4),
+            _ => return Err(Errno::EIO),
+        };
+        acl.extend_from_slice(&value[offset..offset + 4]);
+        acl.extend_from_slice(&id.to_le_bytes());
+        offset += entry_size;
+    }
+    Ok(acl)
+}
+
+/// Convert an ACL from the xattr syscalls format to the disk format
+pub fn acl_to_disk(value: &[u8]) -> IoResult<Vec<u8>> {
+    if value.len() < 4 || (value.len() - 4) % 8 != 0 || read_u32(value, 0) != ACL_XATTR_VERSION {
+        return Err(Errno::EINVAL);
+    }
+    let mut acl = Vec::new();
+    acl.try_reserve_exact(value.len())?;
+    acl.extend_from_slice(&ACL_DISK_VERSION.to_le_bytes());
+    for entry in value[4..].chunks(8) {
+        match read_u16(entry, 0) {
+            ACL_USER | ACL_GROUP => acl.extend_from_slice(entry),
+            ACL_USER_OBJ | ACL_GROUP_OBJ | ACL_MASK | ACL_OTHER => {
+                acl.extend_from_slice(&entry[..4])
+            }
+            _ => return Err(Errno::EINVAL),
+        }
+    }
+    Ok(acl)
+}
+
+impl Ext2Filesystem {
+    /// Read the extended attributes of `inode`
+    pub(crate) fn read_xattrs(&mut self, inode: &Inode) -> IoResult<Vec<Xattr>> {
+        let block = inode.get_extended_attribute_block();
+        if block.0 == 0 {
+            return Ok(Vec::new());
+        }
+        let buf = self.read_block(block)?;
+        let xattrs = parse_block(&buf)?;
+        self.xattr_blocks.try_insert(block.0, read_u32(&buf, 12))?;
+        Ok(xattrs)
+    }
+
+    /// Replace the extended attributes of `inode` by `xattrs`, which
+    /// must be sorted, and write the inode on the disk
+    pub(crate) fn write_xattrs(
+        &mut self,
+        (inode, inode_addr): (&mut Inode, InodeAddr),
+        xattrs: &[Xattr],
+    ) -> IoResult<()> {
+        let old = inode.get_extended_attribute_block();
+        let new = if xattrs.is_empty() {
+            Block(0)
+        } else {
+            let buf = build_block(xattrs, self.block_size as usize)?;
+            match self.find_shared_xattr_block(&buf, old)? {
+                Some(block) => block,
+                None => {
+                    let block = if old.0 != 0 && self.xattr_refcount(old)? == 1 {
+                        old
+                    } else {
+                        let goal = self.grp_to_block(self.inode_addr_grp(inode_addr), 0);
+                        self.alloc_block(inode_addr, goal, 0)?
+                    };
+                    self.disk.write_all(self.to_addr(block), &buf)?;
+                    let hash = read_u32(&buf, 12);
+                    self.xattr_blocks.try_insert(block.0, hash)?;
+                    block
+                }
+            }
+        };
+        if old.0 != 0 && old != new {
+            self.release_xattr_block(old)?;
+        }
+        if new.0 != 0 && !self.superblock.has_extended_attributes() {
+            self.superblock.set_extended_attributes();
+            self.disk
+                .write_struct(self.superblock_addr, &self.superblock)?;
+        }
+        inode.set_extended_attribute_block(new, self.block_size);
+        self.disk.write_struct(inode_addr, inode)?;
+        Ok(())
+    }
+
+    /// Find an attribute block with the same content as `buf` which
+    /// can still be shared, and take a reference on it
+    fn find_shared_xattr_block(&mut self, buf: &[u8], current: Block) -> IoResult<Option<Block>> {
+        let hash = read_u32(buf, 12);
+        if hash == 0 {
+            return Ok(None);
+        }
+        let mut candidates = Vec::new();
+        for (block, _) in self.xattr_blocks.iter().filter(|(_, h)| **h == hash) {
+            candidates.try_reserve(1)?;
+            candidates.push(Block(*block));
+        }
+        for block in candidates {
+            let mut other = self.read_block(block)?;
+            if !same_content(buf, &other) {
+                continue;
+            }
+            let refcount = read_u32(&other, 4);
+            if block == current {
+                return Ok(Some(block));
+            }
+            if refcount < XATTR_REFCOUNT_MAX {
+                write_u32(&mut other, 4, refcount + 1);
+                self.disk.write_all(self.to_addr(block), &other[..8])?;
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the number of inodes sharing an attribute block
+    fn xattr_refcount(&mut self, block: Block) -> IoResult<u32> {
+        self.disk.read_struct::<u32>(self.to_addr(block) + 4)
+    }
+
+    /// Drop a reference on an attribute block, freeing it when it was the last one
+    pub(crate) fn release_xattr_block(&mut self, block: Block) -> IoResult<()> {
+        let refcount = self.xattr_refcount(block)?;
+        if refcount <= 1 {
+            self.xattr_blocks.remove(&block.0);
+            self.free_block(block)
+        } else {
+            self.disk
+                .write_struct(self.to_addr(block) + 4, &(refcount - 1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn xattr(index: u8, name: &str, value: &[u8]) -> Xattr {
+        Xattr {
+            index,
+            name: name.as_bytes().to_vec(),
+            value: value.to_vec(),
+        }
+    }
+
+    #[test]
+    fn block_round_trip() {
+        let mut xattrs = Vec::new();
+        update_list(&mut xattrs, (1, b"zeta"), Some(b"value")).unwrap();
+        update_list(&mut xattrs, (1, b"alpha"), Some(b"")).unwrap();
+        update_list(&mut xattrs, (6, b"a"), Some(b"selinux")).unwrap();
+        assert_eq!(xattrs[0], xattr(1, "zeta", b"value"));
+        assert_eq!(xattrs[1], xattr(1, "alpha", b""));
+
+        let buf = build_block(&xattrs, 1024).unwrap();
+        assert_eq!(parse_block(&buf).unwrap(), xattrs);
+        assert!(update_list(&mut xattrs, (1, b"zeta"), None).unwrap());
+        assert!(!update_list(&mut xattrs, (1, b"zeta"), None).unwrap());
+    }
+
+    #[test]
+    fn block_full() {
+        let xattrs = [xattr(1, "big", &[0; 1000])];
+        assert_eq!(build_block(&xattrs, 1024), Err(Errno::ENOSPC));
+    }
+
+    #[test]
+    fn acl_conversion() {
+        let mut acl = Vec::new();
+        acl.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+        for (tag, perm, id) in [
+            (ACL_USER_OBJ, 6, ACL_UNDEFINED_ID),
+            (ACL_USER, 4, 1000),
+            (ACL_GROUP_OBJ, 4, ACL_UNDEFINED_ID),
+            (ACL_MASK, 4, ACL_UNDEFINED_ID),
+            (ACL_OTHER, 0, ACL_UNDEFINED_ID),
+        ]
+        .iter()
+        {
+            acl.extend_from_slice(&tag.to_le_bytes());
+            acl.extend_from_slice(&(*perm as u16).to_le_bytes());
+            acl.extend_from_slice(&id.to_le_bytes());
+        }
+        let disk = acl_to_disk(&acl).unwrap();
+        assert_eq!(disk.len(), 4 + 4 * 4 + 8);
+        assert_eq!(acl_from_disk(&disk).unwrap(), acl);
+    }
+}
@@ -0,0 +1,206 @@
+//! Check the block placement of the allocator on a real image, then let
+//! e2fsck check that the image is still consistent. Needs mkfs.ext2 and
+//! e2fsck in the PATH, but no mount: the image is only a regular file
+use ext2::{DiskIo, Ext2Filesystem, Inode, IoResult};
+use libc_binding::{Errno, FileType};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::process::Command;
+
+const ROOT_INODE: u32 = 2;
+const BLOCK_SIZE: usize = 1024;
+
+#[derive(Debug)]
+struct StdDiskIo {
+    f: File,
+}
+
+impl DiskIo for StdDiskIo {
+    fn flush(&mut self) -> IoResult<()> {
+        self.f.flush().map_err(|_| Errno::EIO)
+    }
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.write(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.read(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+}
+
+/// A fresh 8MiB image with 1KiB blocks, removed on drop
+struct Image {
+    path: String,
+}
+
+impl Image {
+    fn new(name: &str) -> Self {
+        let path = format!(
+            "{}/ext2_{}_{}.img",
+            std::env::temp_dir().display(),
+            name,
+            std::process::id()
+        );
+        let output = Command::new("mkfs.ext2")
+            .args(["-q", "-F", "-b", "1024", &path, "8192"])
+            .output()
+            .expect("mkfs.ext2 not found");
+        assert!(output.status.success(), "mkfs.ext2 failed");
+        Self { path }
+    }
+
+    fn mount(&self) -> Ext2Filesystem {
+        let f = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)
+            .expect("open image failed");
+        Ext2Filesystem::new(Box::new(StdDiskIo { f })).expect("bad image")
+    }
+
+    /// Run a read-only forced check of the image
+    fn fsck(&self) {
+        let output = Command::new("e2fsck")
+            .args(["-f", "-n", &self.path])
+            .output()
+            .expect("e2fsck not found");
+        assert!(
+            output.status.success(),
+            "e2fsck found errors:\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn create(ext2: &mut Ext2Filesystem, filename: &str) -> u32 {
+    let mode = FileType::REGULAR_FILE | FileType::from_bits_truncate(0o644);
+    let (entry, _) = ext2
+        .create(filename, ROOT_INODE, 0, mode, (0, 0))
+        .expect("create failed");
+    entry.get_inode()
+}
+
+/// Append one full block to the file
+fn append_block(ext2: &mut Ext2Filesystem, inode_nbr: u32, n: usize) {
+    let mut offset = (n * BLOCK_SIZE) as u64;
+    let buf = [n as u8; BLOCK_SIZE];
+    let (count, _) = ext2
+        .write(inode_nbr, &mut offset, &buf)
+        .expect("write failed");
+    assert_eq!(count as usize, BLOCK_SIZE);
+}
+
+fn direct_blocks(ext2: &mut Ext2Filesystem, inode_nbr: u32) -> Vec<u32> {
+    let inode: Inode = ext2.read_inode(inode_nbr).expect("read inode failed");
+    let pointers = inode.direct_block_pointers;
+    pointers.iter().map(|b| b.0).filter(|&b| b != 0).collect()
+}
+
+fn assert_contiguous(blocks: &[u32]) {
+    for pair in blocks.windows(2) {
+        assert_eq!(pair[0] + 1, pair[1], "fragmented file: {:?}", blocks);
+    }
+}
+
+#[test]
+fn interleaved_sequential_writes_stay_contiguous() {
+    let image = Image::new("interleaved");
+    {
+        let mut ext2 = image.mount();
+        let a = create(&mut ext2, "a");
+        let b = create(&mut ext2, "b");
+
+        for n in 0..12 {
+            append_block(&mut ext2, a, n);
+            append_block(&mut ext2, b, n);
+        }
+        let blocks_a = direct_blocks(&mut ext2, a);
+        let blocks_b = direct_blocks(&mut ext2, b);
+        assert_eq!(blocks_a.len(), 12);
+        assert_eq!(blocks_b.len(), 12);
+        // The windows are refilled as the writers go, so each file is
+        // made of a few runs of the size of a window at worst
+        let runs = |blocks: &[u32]| blocks.windows(2).filter(|p| p[0] + 1 != p[1]).count() + 1;
+        assert!(runs(&blocks_a) <= 2, "file a fragmented: {:?}", blocks_a);
+        assert!(runs(&blocks_b) <= 2, "file b fragmented: {:?}", blocks_b);
+
+        ext2.discard_preallocation(a).unwrap();
+        ext2.discard_preallocation(b).unwrap();
+        ext2.sync().unwrap();
+    }
+    image.fsck();
+}
+
+#[test]
+fn single_writer_is_contiguous() {
+    let image = Image::new("single");
+    {
+        let mut ext2 = image.mount();
+        let a = create(&mut ext2, "a");
+
+        for n in 0..12 {
+            append_block(&mut ext2, a, n);
+        }
+        assert_contiguous(&direct_blocks(&mut ext2, a));
+
+        ext2.discard_preallocation(a).unwrap();
+        ext2.sync().unwrap();
+    }
+    image.fsck();
+}
+
+#[test]
+fn preallocation_is_released_on_close() {
+    let image = Image::new("prealloc");
+    {
+        let mut ext2 = image.mount();
+        let a = create(&mut ext2, "a");
+        let b = create(&mut ext2, "b");
+
+        append_block(&mut ext2, a, 0);
+        let first_a = direct_blocks(&mut ext2, a)[0];
+        // Closing a gives back the window reserved after its block
+        ext2.discard_preallocation(a).unwrap();
+
+        append_block(&mut ext2, b, 0);
+        let first_b = direct_blocks(&mut ext2, b)[0];
+        assert_eq!(first_b, first_a + 1);
+
+        ext2.discard_preallocation(b).unwrap();
+        ext2.sync().unwrap();
+    }
+    image.fsck();
+}
+
+#[test]
+fn preallocation_is_kept_while_open() {
+    let image = Image::new("open");
+    {
+        let mut ext2 = image.mount();
+        let a = create(&mut ext2, "a");
+        let b = create(&mut ext2, "b");
+
+        append_block(&mut ext2, a, 0);
+        let first_a = direct_blocks(&mut ext2, a)[0];
+
+        append_block(&mut ext2, b, 0);
+        let first_b = direct_blocks(&mut ext2, b)[0];
+        assert!(first_b > first_a + 1, "b took a block reserved for a");
+
+        ext2.discard_preallocation(a).unwrap();
+        ext2.discard_preallocation(b).unwrap();
+        ext2.sync().unwrap();
+    }
+    image.fsck();
+}
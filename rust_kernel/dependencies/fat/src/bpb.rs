@@ -0,0 +1,180 @@
+//! This file describe the boot sector of a FAT volume
+
+use crate::IoResult;
+use libc_binding::Errno;
+
+use core::mem::size_of;
+
+/// Common part of the BIOS Parameter Block, at the start of the boot sector
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+#[repr(packed)]
+pub struct BiosParameterBlock {
+    /// Jump instruction to the boot code
+    /*0 	2 	3*/
+    jump_boot: [u8; 3],
+    /// Name of the formatting tool
+    /*3 	10 	8*/
+    oem_name: [u8; 8],
+    /// Number of bytes per sector: 512, 1024, 2048 or 4096
+    /*11 	12 	2*/
+    pub bytes_per_sector: u16,
+    /// Number of sectors per cluster, a power of two
+    /*13 	13 	1*/
+    pub sectors_per_cluster: u8,
+    /// Number of sectors before the first FAT, boot sector included
+    /*14 	15 	2*/
+    pub reserved_sectors: u16,
+    /// Number of copies of the FAT, usually 2
+    /*16 	16 	1*/
+    pub nbr_fats: u8,
+    /// Number of 32 bytes entries in the root directory, 0 on FAT32
+    /*17 	18 	2*/
+    pub root_entries: u16,
+    /// Total number of sectors if it fits in 16 bits, else 0
+    /*19 	20 	2*/
+    total_sectors_16: u16,
+    /// Media descriptor, also stored in the low byte of FAT[0]
+    /*21 	21 	1*/
+    media: u8,
+    /// Number of sectors of a FAT, 0 on FAT32
+    /*22 	23 	2*/
+    fat_size_16: u16,
+    /// Geometry for the BIOS int 13h
+    /*24 	25 	2*/
+    sectors_per_track: u16,
+    /*26 	27 	2*/
+    nbr_heads: u16,
+    /// Number of sectors before the volume on the disk
+    /*28 	31 	4*/
+    hidden_sectors: u32,
+    /// Total number of sectors if it does not fit in 16 bits
+    /*32 	35 	4*/
+    total_sectors_32: u32,
+}
+
+/// Extended BIOS Parameter Block of FAT12 and FAT16, right after the common part
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+#[repr(packed)]
+pub struct Fat16Extension {
+    /*36 	36 	1*/
+    drive_number: u8,
+    /*37 	37 	1*/
+    reserved: u8,
+    /// 0x29 when the three next fields are present
+    /*38 	38 	1*/
+    boot_signature: u8,
+    /*39 	42 	4*/
+    pub volume_id: u32,
+    /*43 	53 	11*/
+    volume_label: [u8; 11],
+    /// Informative only, never use it to find the FAT type
+    /*54 	61 	8*/
+    file_system_type: [u8; 8],
+}
+
+/// Extended BIOS Parameter Block of FAT32, right after the common part
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+#[repr(packed)]
+pub struct Fat32Extension {
+    /// Number of sectors of a FAT
+    /*36 	39 	4*/
+    pub fat_size_32: u32,
+    /// Bit 7 set when only the FAT pointed by the bits 0-3 is active
+    /*40 	41 	2*/
+    pub ext_flags: u16,
+    /*42 	43 	2*/
+    fs_version: u16,
+    /// First cluster of the root directory
+    /*44 	47 	4*/
+    pub root_cluster: u32,
+    /// Sector of the FSInfo structure in the reserved area
+    /*48 	49 	2*/
+    pub fs_info: u16,
+    /// Sector of the copy of the boot sector in the reserved area
+    /*50 	51 	2*/
+    backup_boot_sector: u16,
+    /*52 	63 	12*/
+    reserved: [u8; 12],
+    /*64 	64 	1*/
+    drive_number: u8,
+    /*65 	65 	1*/
+    reserved1: u8,
+    /*66 	66 	1*/
+    boot_signature: u8,
+    /*67 	70 	4*/
+    pub volume_id: u32,
+    /*71 	81 	11*/
+    volume_label: [u8; 11],
+    /*82 	89 	8*/
+    file_system_type: [u8; 8],
+}
+
+/// Signatures of the FSInfo sector of FAT32
+pub const FSINFO_LEAD_SIGNATURE: u32 = 0x41615252;
+pub const FSINFO_STRUCT_SIGNATURE: u32 = 0x61417272;
+/// Offsets of the FSInfo fields
+pub const FSINFO_STRUCT_SIGNATURE_OFFSET: u64 = 484;
+pub const FSINFO_FREE_COUNT_OFFSET: u64 = 488;
+/// Value of the free count and next free fields when unknown
+pub const FSINFO_UNKNOWN: u32 = 0xffffffff;
+
+/// Read a packed structure at `offset` in the boot sector
+fn read_at<T: Copy>(sector: &[u8], offset: usize) -> T {
+    assert!(offset + size_of::<T>() <= sector.len());
+    unsafe { core::ptr::read_unaligned(sector[offset..].as_ptr() as *const T) }
+}
+
+impl BiosParameterBlock {
+    /// Parse the boot sector, check it is a FAT one
+    pub fn new(boot_sector: &[u8; 512]) -> IoResult<Self> {
+        if boot_sector[510] != 0x55 || boot_sector[511] != 0xaa {
+            return Err(Errno::EINVAL);
+        }
+        let bpb: Self = read_at(boot_sector, 0);
+        let bytes_per_sector = bpb.bytes_per_sector;
+        if !bytes_per_sector.is_power_of_two()
+            || !(512..=4096).contains(&bytes_per_sector)
+            || !bpb.sectors_per_cluster.is_power_of_two()
+            || bpb.reserved_sectors == 0
+            || bpb.nbr_fats == 0
+            || bpb.get_fat_size(boot_sector) == 0
+            || bpb.get_total_sectors() == 0
+        {
+            return Err(Errno::EINVAL);
+        }
+        Ok(bpb)
+    }
+
+    /// Get the total number of sectors of the volume
+    pub fn get_total_sectors(&self) -> u32 {
+        match self.total_sectors_16 {
+            0 => self.total_sectors_32,
+            n => n as u32,
+        }
+    }
+
+    /// Get the number of sectors of a FAT
+    pub fn get_fat_size(&self, boot_sector: &[u8; 512]) -> u32 {
+        match self.fat_size_16 {
+            0 => self.get_fat32_extension(boot_sector).fat_size_32,
+            n => n as u32,
+        }
+    }
+
+    /// Get the number of sectors of the FAT12/16 root directory
+    pub fn get_root_dir_sectors(&self) -> u32 {
+        let bytes_per_sector = self.bytes_per_sector as u32;
+        (self.root_entries as u32 * 32 + bytes_per_sector - 1) / bytes_per_sector
+    }
+
+    pub fn get_fat16_extension(&self, boot_sector: &[u8; 512]) -> Fat16Extension {
+        read_at(boot_sector, size_of::<Self>())
+    }
+
+    pub fn get_fat32_extension(&self, boot_sector: &[u8; 512]) -> Fat32Extension {
+        read_at(boot_sector, size_of::<Self>())
+    }
+}
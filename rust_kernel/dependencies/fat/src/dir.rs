@@ -0,0 +1,560 @@
+//! This file describe the directory entries, short names and VFAT long names
+
+use crate::IoResult;
+use libc_binding::Errno;
+
+use alloc::string::String;
+use bitflags::bitflags;
+
+/// Size of a directory slot, short or long
+pub const SLOT_SIZE: u32 = 32;
+/// First byte of a free slot
+pub const SLOT_FREE: u8 = 0xe5;
+/// First byte of the slot after the last one in use
+pub const SLOT_END: u8 = 0x00;
+/// A short name beginning with 0xe5 is stored with 0x05
+const SLOT_KANJI_E5: u8 = 0x05;
+
+/// Maximum number of UCS-2 characters of a long name
+pub const LONG_NAME_MAX: usize = 255;
+/// Number of UCS-2 characters in a long name slot
+const LFN_CHARS_PER_SLOT: usize = 13;
+/// Byte offsets of the characters in a long name slot
+const LFN_CHAR_OFFSETS: [usize; LFN_CHARS_PER_SLOT] =
+    [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+/// Order flag of the last (physically first) long name slot
+const LFN_LAST_SLOT: u8 = 0x40;
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Attributes: u8 {
+        const READ_ONLY = 0x01;
+        const HIDDEN = 0x02;
+        const SYSTEM = 0x04;
+        const VOLUME_ID = 0x08;
+        const DIRECTORY = 0x10;
+        const ARCHIVE = 0x20;
+        /// The combination marking a long name slot
+        const LONG_NAME = 0x0f;
+    }
+}
+
+bitflags! {
+    /// Case of the short names, as stored by Windows NT
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct CaseFlags: u8 {
+        const LOWER_BASE = 0x08;
+        const LOWER_EXTENSION = 0x10;
+    }
+}
+
+/// A 8.3 name, padded with spaces
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct ShortName(pub [u8; 11]);
+
+impl ShortName {
+    pub const DOT: Self = Self(*b".          ");
+    pub const DOT_DOT: Self = Self(*b"..         ");
+
+    /// The checksum stored in the long name slots of the entry
+    pub fn checksum(&self) -> u8 {
+        self.0
+            .iter()
+            .fold(0u8, |sum, &c| sum.rotate_right(1).wrapping_add(c))
+    }
+
+    /// Get the name as displayed when there is no long name
+    pub fn to_string(self, case: CaseFlags) -> IoResult<String> {
+        let mut name = String::new();
+        name.try_reserve(12)?;
+        push_short_name_part(
+            &mut name,
+            &self.0[..8],
+            case.contains(CaseFlags::LOWER_BASE),
+        );
+        if self.0[8..].iter().any(|&c| c != b' ') {
+            name.push('.');
+            push_short_name_part(
+                &mut name,
+                &self.0[8..],
+                case.contains(CaseFlags::LOWER_EXTENSION),
+            );
+        }
+        Ok(name)
+    }
+
+    /// Build the short name with a numeric tail `~n` from a basis name
+    pub fn with_tail(&self, n: u32) -> Self {
+        let mut tail = [0; 7];
+        let mut len = 0;
+        let mut n = n;
+        while n > 0 {
+            tail[len] = b'0' + (n % 10) as u8;
+            n /= 10;
+            len += 1;
+        }
+        tail[len] = b'~';
+        len += 1;
+        tail[..len].reverse();
+
+        let base_len = self.0[..8]
+            .iter()
+            .position(|&c| c == b' ')
+            .unwrap_or(8)
+            .min(8 - len);
+        let mut name = *self;
+        name.0[base_len..base_len + len].copy_from_slice(&tail[..len]);
+        name.0[base_len + len..8].fill(b' ');
+        name
+    }
+}
+
+/// Append the base or the extension of a short name to `name`
+fn push_short_name_part(name: &mut String, bytes: &[u8], lower: bool) {
+    let len = bytes.iter().rposition(|&c| c != b' ').map_or(0, |i| i + 1);
+    for (i, &c) in bytes[..len].iter().enumerate() {
+        let c = match c {
+            SLOT_KANJI_E5 if i == 0 => SLOT_FREE,
+            c if lower => c.to_ascii_lowercase(),
+            c => c,
+        };
+        // Bytes above 127 depend on the OEM code page, assume latin-1
+        name.push(c as char);
+    }
+}
+
+/// Check if `c` can be stored in a short name without conversion
+fn is_short_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "$%'-_@~`!(){}^#&".contains(c)
+}
+
+/// Check if the long name `name` can be stored on the disk
+pub fn check_long_name(name: &str) -> IoResult<()> {
+    if name.encode_utf16().count() > LONG_NAME_MAX {
+        return Err(Errno::ENAMETOOLONG);
+    }
+    // Windows ignores the trailing dots and spaces, so two names
+    // could differ only by them
+    if name.is_empty()
+        || name.ends_with('.')
+        || name.ends_with(' ')
+        || name
+            .chars()
+            .any(|c| (c as u32) < 0x20 || "\"*/:<>?\\|".contains(c))
+    {
+        return Err(Errno::EINVAL);
+    }
+    Ok(())
+}
+
+/// Get the short name if `name` is a valid 8.3 name in a single case
+/// per part, the case is kept by the case flags
+pub fn exact_short_name(name: &str) -> Option<(ShortName, CaseFlags)> {
+    let (base, extension) = match name.rfind('.') {
+        Some(i) => (&name[..i], &name[i + 1..]),
+        None => (name, ""),
+    };
+    if base.is_empty()
+        || base.len() > 8
+        || extension.len() > 3
+        || !base
+            .chars()
+            .chain(extension.chars())
+            .all(is_short_name_char)
+    {
+        return None;
+    }
+    let mut case = CaseFlags::empty();
+    for (part, flag) in [
+        (base, CaseFlags::LOWER_BASE),
+        (extension, CaseFlags::LOWER_EXTENSION),
+    ] {
+        let lower = part.chars().any(|c| c.is_ascii_lowercase());
+        let upper = part.chars().any(|c| c.is_ascii_uppercase());
+        match (lower, upper) {
+            (true, true) => return None,
+            (true, false) => case.insert(flag),
+            _ => {}
+        }
+    }
+    let mut short = ShortName([b' '; 11]);
+    short.0[..base.len()].copy_from_slice(base.as_bytes());
+    short.0[8..8 + extension.len()].copy_from_slice(extension.as_bytes());
+    short.0.make_ascii_uppercase();
+    Some((short, case))
+}
+
+/// Get the basis name of the long name `name`, as in the Microsoft
+/// specification, and whether some characters were lost
+pub fn basis_name(name: &str) -> (ShortName, bool) {
+    let trimmed = name.trim_start_matches('.');
+    let (base, extension) = match trimmed.rfind('.') {
+        Some(i) => (&trimmed[..i], &trimmed[i + 1..]),
+        None => (trimmed, ""),
+    };
+    let mut short = ShortName([b' '; 11]);
+    let (base_part, extension_part) = short.0.split_at_mut(8);
+    let (base_len, base_lossy) = convert_name_part(base, base_part);
+    let (_, extension_lossy) = convert_name_part(extension, extension_part);
+    if base_len == 0 {
+        base_part[0] = b'_';
+    }
+    let lossy = trimmed.len() != name.len() || base_len == 0 || base_lossy || extension_lossy;
+    (short, lossy)
+}
+
+/// Convert a part of a long name to the short name characters in
+/// `dest`, return the length and whether some characters were lost
+fn convert_name_part(part: &str, dest: &mut [u8]) -> (usize, bool) {
+    let mut len = 0;
+    let mut lossy = false;
+    for c in part.chars() {
+        if c == ' ' || c == '.' {
+            lossy = true;
+            continue;
+        }
+        if len == dest.len() {
+            lossy = true;
+            break;
+        }
+        dest[len] = if is_short_name_char(c) {
+            c.to_ascii_uppercase() as u8
+        } else {
+            lossy = true;
+            b'_'
+        };
+        len += 1;
+    }
+    (len, lossy)
+}
+
+/// Decode the UCS-2 long name, a null character ends it
+pub fn decode_long_name(chars: &[u16]) -> IoResult<String> {
+    let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+    let mut name = String::new();
+    name.try_reserve(len)?;
+    for c in char::decode_utf16(chars[..len].iter().cloned()) {
+        name.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+    Ok(name)
+}
+
+/// Build the long name slot number `order` (from 1) of `name`, the
+/// slots are stored in the reverse order before the short entry
+pub fn long_name_slot(name: &[u16], order: usize, checksum: u8) -> [u8; SLOT_SIZE as usize] {
+    let mut slot = [0; SLOT_SIZE as usize];
+    let nbr_slots = (name.len() + LFN_CHARS_PER_SLOT - 1) / LFN_CHARS_PER_SLOT;
+    slot[0] = order as u8;
+    if order == nbr_slots {
+        slot[0] |= LFN_LAST_SLOT;
+    }
+    slot[11] = Attributes::LONG_NAME.bits();
+    slot[13] = checksum;
+    let start = (order - 1) * LFN_CHARS_PER_SLOT;
+    for (i, &offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+        // A null character ends the name, the rest is padded with 0xffff
+        let c = match start + i {
+            n if n < name.len() => name[n],
+            n if n == name.len() => 0,
+            _ => 0xffff,
+        };
+        slot[offset..offset + 2].copy_from_slice(&c.to_le_bytes());
+    }
+    slot
+}
+
+/// Number of slots needed to store the long name `name`
+pub fn nbr_long_name_slots(name: &[u16]) -> usize {
+    (name.len() + LFN_CHARS_PER_SLOT - 1) / LFN_CHARS_PER_SLOT
+}
+
+/// Accumulate the long name slots preceding a short entry
+#[derive(Debug)]
+pub struct LongNameParser {
+    chars: [u16; LFN_CHARS_PER_SLOT * 20],
+    /// Order of the next expected slot, 0 when no long name is pending
+    next_order: u8,
+    checksum: u8,
+    /// First slot of the pending long name
+    pub first_slot: u32,
+}
+
+impl LongNameParser {
+    pub fn new() -> Self {
+        Self {
+            chars: [0; LFN_CHARS_PER_SLOT * 20],
+            next_order: 0,
+            checksum: 0,
+            first_slot: 0,
+        }
+    }
+
+    /// Feed a long name slot
+    pub fn push(&mut self, slot: &[u8], slot_nbr: u32) {
+        let order = slot[0] & !LFN_LAST_SLOT;
+        if slot[0] & LFN_LAST_SLOT != 0 {
+            if order == 0 || order > 20 {
+                self.next_order = 0;
+                return;
+            }
+            self.chars = [0; LFN_CHARS_PER_SLOT * 20];
+            self.checksum = slot[13];
+            self.first_slot = slot_nbr;
+        } else if order == 0 || order != self.next_order || slot[13] != self.checksum {
+            // An orphan long name slot, the long name is lost
+            self.next_order = 0;
+            return;
+        }
+        let start = (order as usize - 1) * LFN_CHARS_PER_SLOT;
+        for (i, &offset) in LFN_CHAR_OFFSETS.iter().enumerate() {
+            self.chars[start + i] = u16::from_le_bytes([slot[offset], slot[offset + 1]]);
+        }
+        self.next_order = order - 1;
+        if self.next_order == 0 {
+            // Mark the long name complete
+            self.next_order = u8::MAX;
+        }
+    }
+
+    /// Get the long name of the short entry `short`, if any
+    pub fn take(&mut self, short: &ShortName) -> Option<&[u16]> {
+        let complete = self.next_order == u8::MAX && self.checksum == short.checksum();
+        self.next_order = 0;
+        match complete {
+            true => Some(&self.chars),
+            false => None,
+        }
+    }
+
+    /// Forget the pending long name
+    pub fn reset(&mut self) {
+        self.next_order = 0;
+    }
+}
+
+/// A short directory entry
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone)]
+#[repr(packed)]
+pub struct DirEntry {
+    /*0 	10 	11*/
+    pub name: ShortName,
+    /*11 	11 	1*/
+    attributes: u8,
+    /// Case of the short name, see CaseFlags
+    /*12 	12 	1*/
+    nt_reserved: u8,
+    /// Tenths of seconds of the creation time, from 0 to 199
+    /*13 	13 	1*/
+    creation_time_tenth: u8,
+    /*14 	15 	2*/
+    creation_time: u16,
+    /*16 	17 	2*/
+    creation_date: u16,
+    /*18 	19 	2*/
+    last_access_date: u16,
+    /// High word of the first cluster, 0 on FAT12/16
+    /*20 	21 	2*/
+    first_cluster_hi: u16,
+    /*22 	23 	2*/
+    write_time: u16,
+    /*24 	25 	2*/
+    write_date: u16,
+    /*26 	27 	2*/
+    first_cluster_lo: u16,
+    /// Size of a file in bytes, 0 for the directories
+    /*28 	31 	4*/
+    file_size: u32,
+}
+
+impl DirEntry {
+    /// Create a new entry, with all the times set to `timestamp`
+    pub fn new(name: ShortName, attributes: Attributes, timestamp: u32) -> Self {
+        let (date, time) = unix_to_fat(timestamp);
+        Self {
+            name,
+            attributes: attributes.bits(),
+            nt_reserved: 0,
+            creation_time_tenth: (timestamp % 2 * 100) as u8,
+            creation_time: time,
+            creation_date: date,
+            last_access_date: date,
+            first_cluster_hi: 0,
+            write_time: time,
+            write_date: date,
+            first_cluster_lo: 0,
+            file_size: 0,
+        }
+    }
+
+    pub fn from_slot(slot: &[u8]) -> Self {
+        assert!(slot.len() >= SLOT_SIZE as usize);
+        unsafe { core::ptr::read_unaligned(slot.as_ptr() as *const Self) }
+    }
+
+    pub fn as_slot(&self) -> &[u8; SLOT_SIZE as usize] {
+        unsafe { &*(self as *const Self as *const [u8; SLOT_SIZE as usize]) }
+    }
+
+    pub fn get_attributes(&self) -> Attributes {
+        Attributes::from_bits_retain(self.attributes)
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes.bits();
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.get_attributes().contains(Attributes::DIRECTORY)
+    }
+
+    pub fn get_case(&self) -> CaseFlags {
+        CaseFlags::from_bits_truncate(self.nt_reserved)
+    }
+
+    pub fn set_case(&mut self, case: CaseFlags) {
+        self.nt_reserved = case.bits();
+    }
+
+    pub fn get_first_cluster(&self) -> u32 {
+        (self.first_cluster_hi as u32) << 16 | self.first_cluster_lo as u32
+    }
+
+    pub fn set_first_cluster(&mut self, cluster: u32) {
+        self.first_cluster_hi = (cluster >> 16) as u16;
+        self.first_cluster_lo = cluster as u16;
+    }
+
+    pub fn get_size(&self) -> u32 {
+        self.file_size
+    }
+
+    pub fn set_size(&mut self, size: u32) {
+        self.file_size = size;
+    }
+
+    pub fn get_modification_time(&self) -> u32 {
+        fat_to_unix(self.write_date, self.write_time)
+    }
+
+    pub fn set_modification_time(&mut self, timestamp: u32) {
+        let (date, time) = unix_to_fat(timestamp);
+        self.write_date = date;
+        self.write_time = time;
+    }
+
+    /// Only the date of the last access is stored
+    pub fn get_access_time(&self) -> u32 {
+        fat_to_unix(self.last_access_date, 0)
+    }
+
+    pub fn set_access_time(&mut self, timestamp: u32) {
+        self.last_access_date = unix_to_fat(timestamp).0;
+    }
+}
+
+/// Seconds between 1970-01-01 and 1980-01-01, the FAT epoch
+const FAT_EPOCH: u32 = 315532800;
+const SECONDS_PER_DAY: u32 = 86400;
+
+/// Convert a POSIX time to a FAT (date, time), the FAT times have a
+/// two seconds granularity
+pub fn unix_to_fat(timestamp: u32) -> (u16, u16) {
+    let timestamp = timestamp.max(FAT_EPOCH);
+    let (days, seconds) = (timestamp / SECONDS_PER_DAY, timestamp % SECONDS_PER_DAY);
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    let date = ((year - 1980).min(127) as u16) << 9 | (month as u16) << 5 | day as u16;
+    let time = ((seconds / 3600) as u16) << 11
+        | ((seconds / 60 % 60) as u16) << 5
+        | (seconds % 60 / 2) as u16;
+    (date, time)
+}
+
+/// Convert a FAT (date, time) to a POSIX time
+pub fn fat_to_unix(date: u16, time: u16) -> u32 {
+    let year = 1980 + (date >> 9) as i64;
+    let month = ((date >> 5) & 0xf).clamp(1, 12) as i64;
+    let day = (date & 0x1f).max(1) as i64;
+
+    // Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let seconds =
+        (time >> 11) as i64 * 3600 + ((time >> 5) & 0x3f) as i64 * 60 + (time & 0x1f) as i64 * 2;
+    (days * SECONDS_PER_DAY as i64 + seconds) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn short_name_checksum() {
+        // Computed by mkfs.vfat and Linux for "README  TXT"
+        assert_eq!(ShortName(*b"README  TXT").checksum(), 0x73);
+    }
+
+    #[test]
+    fn short_name_generation() {
+        let (name, case) = exact_short_name("readme.txt").unwrap();
+        assert_eq!(&name.0, b"README  TXT");
+        assert_eq!(case, CaseFlags::LOWER_BASE | CaseFlags::LOWER_EXTENSION);
+        assert_eq!(name.to_string(case).unwrap(), "readme.txt");
+        assert!(exact_short_name("ReadMe.txt").is_none());
+        assert!(exact_short_name("a long name.txt").is_none());
+
+        let (basis, lossy) = basis_name("a long name.text");
+        assert_eq!(&basis.0, b"ALONGNAMTEX");
+        assert!(lossy);
+        assert_eq!(&basis.with_tail(1).0, b"ALONGN~1TEX");
+        assert_eq!(&basis.with_tail(12345).0, b"AL~12345TEX");
+        assert_eq!(&basis_name(".bashrc").0 .0, b"BASHRC     ");
+    }
+
+    #[test]
+    fn long_name_round_trip() {
+        let name: alloc::vec::Vec<u16> = "Un nom très long.txt".encode_utf16().collect();
+        let short = basis_name("Un nom très long.txt").0.with_tail(1);
+        let mut parser = LongNameParser::new();
+        let nbr_slots = nbr_long_name_slots(&name);
+        assert_eq!(nbr_slots, 2);
+        for order in (1..=nbr_slots).rev() {
+            parser.push(&long_name_slot(&name, order, short.checksum()), 0);
+        }
+        let decoded = decode_long_name(parser.take(&short).unwrap()).unwrap();
+        assert_eq!(decoded, "Un nom très long.txt");
+        // A short entry with another checksum does not own the long name
+        parser.push(&long_name_slot(&name, 2, short.checksum()), 0);
+        parser.push(&long_name_slot(&name, 1, short.checksum()), 0);
+        assert!(parser.take(&ShortName::DOT).is_none());
+    }
+
+    #[test]
+    fn timestamps() {
+        // 2019-07-04 13:37:42
+        let timestamp = 1562247462;
+        let (date, time) = unix_to_fat(timestamp);
+        assert_eq!(date, (39 << 9) | (7 << 5) | 4);
+        assert_eq!(time, (13 << 11) | (37 << 5) | 21);
+        assert_eq!(fat_to_unix(date, time), timestamp);
+        assert_eq!(fat_to_unix(unix_to_fat(0).0, 0), FAT_EPOCH);
+    }
+}
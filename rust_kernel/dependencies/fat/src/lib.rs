@@ -0,0 +1,978 @@
+//! this module contains a FAT12/16/32 driver, with the VFAT long names
+//! see [osdev](https://wiki.osdev.org/FAT) and the Microsoft FAT specification
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod disk;
+use disk::Disk;
+pub use disk::DiskIo;
+
+mod bpb;
+use bpb::{
+    BiosParameterBlock, FSINFO_FREE_COUNT_OFFSET, FSINFO_LEAD_SIGNATURE, FSINFO_STRUCT_SIGNATURE,
+    FSINFO_STRUCT_SIGNATURE_OFFSET, FSINFO_UNKNOWN,
+};
+
+mod table;
+pub use table::FatType;
+use table::{FatCache, FIRST_CLUSTER};
+
+mod dir;
+use dir::{
+    basis_name, check_long_name, decode_long_name, exact_short_name, long_name_slot,
+    nbr_long_name_slots, Attributes, CaseFlags, DirEntry, LongNameParser, ShortName, LONG_NAME_MAX,
+    SLOT_END, SLOT_FREE, SLOT_SIZE,
+};
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use fallible_collections::btree::BTreeMap;
+use libc_binding::Errno;
+
+pub type IoResult<T> = core::result::Result<T, Errno>;
+
+type InodeNbr = u32;
+
+/// The inode number of the root directory. FAT has no inodes, the
+/// other numbers are given when the entries are looked up
+pub const ROOT_INODE: InodeNbr = 1;
+
+/// Files sizes are stored on 32 bits
+const FILE_SIZE_MAX: u64 = u32::MAX as u64;
+/// A directory cannot have more slots
+const DIRECTORY_SLOTS_MAX: u32 = 65536;
+
+/// What is known about a file, FAT has no owner nor permissions
+#[derive(Debug, Copy, Clone)]
+pub struct Stat {
+    pub inode_nbr: u32,
+    pub is_directory: bool,
+    pub read_only: bool,
+    pub size: u64,
+    /// Number of 512 bytes sectors allocated to the file
+    pub nbr_disk_sectors: u32,
+    pub access_time: u32,
+    pub modification_time: u32,
+}
+
+/// Where the short entry of a file lives
+#[derive(Debug, Copy, Clone)]
+struct Location {
+    parent: InodeNbr,
+    /// First slot of the long name, or the short entry without long name
+    first_slot: u32,
+    /// Slot of the short entry
+    slot: u32,
+    /// Byte offset of the short entry on the disk
+    offset: u64,
+}
+
+/// An in memory inode
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    /// None for the root and the unlinked files still opened
+    location: Option<Location>,
+    entry: DirEntry,
+    /// Last (cluster index, cluster) reached in the chain, it speeds
+    /// up the sequential accesses
+    cursor: (u32, u32),
+}
+
+/// A directory entry read from the disk
+#[derive(Debug)]
+struct RawEntry {
+    name: String,
+    entry: DirEntry,
+    first_slot: u32,
+    slot: u32,
+}
+
+/// Global structure of a FAT filesystem
+#[derive(Debug)]
+pub struct FatFilesystem {
+    disk: Disk,
+    fat_type: FatType,
+    cluster_size: u32,
+    /// Byte offset of the first FAT
+    fat_offset: u64,
+    /// Size of a FAT in bytes
+    fat_size: u64,
+    nbr_fats: u32,
+    fat_cache: FatCache,
+    /// Byte offset and number of slots of the FAT12/16 root directory
+    root_dir_offset: u64,
+    root_dir_slots: u32,
+    /// Byte offset of the cluster 2
+    data_offset: u64,
+    nbr_clusters: u32,
+    free_clusters: u32,
+    /// Where to look for a free cluster first
+    next_free: u32,
+    /// Byte offset of the FAT32 FSInfo sector
+    fs_info: Option<u64>,
+    volume_id: u32,
+    nodes: BTreeMap<InodeNbr, Node>,
+    /// Inode numbers of the loaded entries by (directory, slot)
+    positions: BTreeMap<(InodeNbr, u32), InodeNbr>,
+    next_inode_nbr: InodeNbr,
+}
+
+impl FatFilesystem {
+    /// Invocation of a new FileSystem instance: take a FD and his reader as parameter
+    pub fn new(disk: Box<dyn DiskIo>) -> IoResult<Self> {
+        let mut disk = Disk(disk);
+        let mut boot_sector = [0; 512];
+        disk.read_exact(0, &mut boot_sector)?;
+        let bpb = BiosParameterBlock::new(&boot_sector)?;
+
+        let bytes_per_sector = bpb.bytes_per_sector as u64;
+        let fat_sectors = bpb.get_fat_size(&boot_sector) as u64;
+        let root_dir_sector = bpb.reserved_sectors as u64 + bpb.nbr_fats as u64 * fat_sectors;
+        let data_sector = root_dir_sector + bpb.get_root_dir_sectors() as u64;
+        let total_sectors = bpb.get_total_sectors() as u64;
+        if data_sector >= total_sectors {
+            return Err(Errno::EINVAL);
+        }
+        let nbr_clusters = (total_sectors - data_sector) / bpb.sectors_per_cluster as u64;
+        let fat_type = FatType::from_nbr_clusters(nbr_clusters as u32);
+
+        // Some formatters make the FAT a bit too short for the last clusters
+        let entry_bits = match fat_type {
+            FatType::Fat12 => 12,
+            FatType::Fat16 => 16,
+            FatType::Fat32 => 32,
+        };
+        let fat_entries = fat_sectors * bytes_per_sector * 8 / entry_bits;
+        let nbr_clusters = min(nbr_clusters, fat_entries - FIRST_CLUSTER as u64) as u32;
+
+        let mut fat_offset = bpb.reserved_sectors as u64 * bytes_per_sector;
+        let fat_size = fat_sectors * bytes_per_sector;
+        let mut nbr_fats = bpb.nbr_fats as u32;
+        let (root_cluster, volume_id, mut fs_info) = match fat_type {
+            FatType::Fat32 => {
+                let extension = bpb.get_fat32_extension(&boot_sector);
+                let root_cluster = extension.root_cluster;
+                if bpb.root_entries != 0
+                    || root_cluster < FIRST_CLUSTER
+                    || root_cluster >= FIRST_CLUSTER + nbr_clusters
+                {
+                    return Err(Errno::EINVAL);
+                }
+                // Only one FAT is active when the mirroring is disabled
+                let ext_flags = extension.ext_flags;
+                if ext_flags & 0x80 != 0 {
+                    fat_offset += (ext_flags & 0xf) as u64 * fat_size;
+                    nbr_fats = 1;
+                }
+                let fs_info = match extension.fs_info {
+                    0 | 0xffff => None,
+                    sector => Some(sector as u64 * bytes_per_sector),
+                };
+                (root_cluster, extension.volume_id, fs_info)
+            }
+            _ => {
+                if bpb.root_entries == 0 {
+                    return Err(Errno::EINVAL);
+                }
+                (0, bpb.get_fat16_extension(&boot_sector).volume_id, None)
+            }
+        };
+
+        // Trust the free count of the FSInfo sector if it looks sane
+        let mut free_clusters = None;
+        let mut next_free = FIRST_CLUSTER;
+        if let Some(offset) = fs_info {
+            let mut sector = [0; 512];
+            disk.read_exact(offset, &mut sector)?;
+            let read_u32 = |offset: u64| {
+                let o = offset as usize;
+                u32::from_le_bytes([sector[o], sector[o + 1], sector[o + 2], sector[o + 3]])
+            };
+            if read_u32(0) != FSINFO_LEAD_SIGNATURE
+                || read_u32(FSINFO_STRUCT_SIGNATURE_OFFSET) != FSINFO_STRUCT_SIGNATURE
+            {
+                fs_info = None;
+            } else {
+                let free_count = read_u32(FSINFO_FREE_COUNT_OFFSET);
+                if free_count != FSINFO_UNKNOWN && free_count <= nbr_clusters {
+                    free_clusters = Some(free_count);
+                }
+                let hint = read_u32(FSINFO_FREE_COUNT_OFFSET + 4);
+                if hint != FSINFO_UNKNOWN {
+                    next_free = hint;
+                }
+            }
+        }
+
+        let mut root_entry = DirEntry::new(ShortName([b' '; 11]), Attributes::DIRECTORY, 0);
+        root_entry.set_first_cluster(root_cluster);
+        let mut nodes = BTreeMap::new();
+        nodes.try_insert(
+            ROOT_INODE,
+            Node {
+                location: None,
+                entry: root_entry,
+                cursor: (0, 0),
+            },
+        )?;
+
+        let mut fat = Self {
+            disk,
+            fat_type,
+            cluster_size: bpb.sectors_per_cluster as u32 * bpb.bytes_per_sector as u32,
+            fat_offset,
+            fat_size,
+            nbr_fats,
+            fat_cache: FatCache::new(bpb.bytes_per_sector as u32)?,
+            root_dir_offset: root_dir_sector * bytes_per_sector,
+            root_dir_slots: bpb.root_entries as u32,
+            data_offset: data_sector * bytes_per_sector,
+            nbr_clusters,
+            free_clusters: 0,
+            next_free,
+            fs_info,
+            volume_id,
+            nodes,
+            positions: BTreeMap::new(),
+            next_inode_nbr: ROOT_INODE + 1,
+        };
+        fat.free_clusters = match free_clusters {
+            Some(free_clusters) => free_clusters,
+            None => fat.count_free_clusters()?,
+        };
+        Ok(fat)
+    }
+
+    pub fn get_fat_type(&self) -> FatType {
+        self.fat_type
+    }
+
+    pub fn get_cluster_size(&self) -> u32 {
+        self.cluster_size
+    }
+
+    pub fn get_nbr_clusters(&self) -> u32 {
+        self.nbr_clusters
+    }
+
+    pub fn get_free_clusters(&self) -> u32 {
+        self.free_clusters
+    }
+
+    pub fn get_volume_id(&self) -> u32 {
+        self.volume_id
+    }
+
+    fn node(&self, inode_nbr: InodeNbr) -> IoResult<Node> {
+        self.nodes.get(&inode_nbr).copied().ok_or(Errno::ENOENT)
+    }
+
+    fn set_node(&mut self, inode_nbr: InodeNbr, node: Node) {
+        *self.nodes.get_mut(&inode_nbr).expect("no such node") = node;
+    }
+
+    /// The FAT12/16 root directory is not made of clusters
+    fn is_fixed_root(&self, inode_nbr: InodeNbr) -> bool {
+        inode_nbr == ROOT_INODE && self.fat_type != FatType::Fat32
+    }
+
+    /// Get the cluster of index `index` in the chain of `node`,
+    /// allocating the missing ones if `alloc`
+    fn seek_cluster(&mut self, node: &mut Node, index: u32, alloc: bool) -> IoResult<Option<u32>> {
+        let mut first = node.entry.get_first_cluster();
+        if first == 0 {
+            if !alloc {
+                return Ok(None);
+            }
+            first = self.alloc_cluster(None)?;
+            node.entry.set_first_cluster(first);
+            node.cursor = (0, first);
+        }
+        let (mut i, mut cluster) = match node.cursor {
+            (i, cluster) if cluster != 0 && i <= index => (i, cluster),
+            _ => (0, first),
+        };
+        while i < index {
+            cluster = match self.next_cluster(cluster)? {
+                Some(next) => next,
+                None if alloc => self.alloc_cluster(Some(cluster))?,
+                None => return Ok(None),
+            };
+            i += 1;
+        }
+        node.cursor = (i, cluster);
+        Ok(Some(cluster))
+    }
+
+    /// Read the content of `node` from `offset`, up to the end of its clusters
+    fn read_data(
+        &mut self,
+        inode_nbr: InodeNbr,
+        node: &mut Node,
+        mut offset: u64,
+        buf: &mut [u8],
+    ) -> IoResult<usize> {
+        if self.is_fixed_root(inode_nbr) {
+            let size = self.root_dir_slots as u64 * SLOT_SIZE as u64;
+            if offset >= size {
+                return Ok(0);
+            }
+            let len = min(buf.len() as u64, size - offset) as usize;
+            self.disk
+                .read_exact(self.root_dir_offset + offset, &mut buf[..len])?;
+            return Ok(len);
+        }
+        let cluster_size = self.cluster_size as u64;
+        let mut done = 0;
+        while done < buf.len() {
+            let cluster = match self.seek_cluster(node, (offset / cluster_size) as u32, false)? {
+                Some(cluster) => cluster,
+                None => break,
+            };
+            let in_cluster = offset % cluster_size;
+            let len = min((cluster_size - in_cluster) as usize, buf.len() - done);
+            self.disk.read_exact(
+                self.cluster_offset(cluster) + in_cluster,
+                &mut buf[done..done + len],
+            )?;
+            done += len;
+            offset += len as u64;
+        }
+        Ok(done)
+    }
+
+    /// Write `buf` in the content of the file `node` at `offset`,
+    /// allocating the clusters as needed
+    fn write_data(&mut self, node: &mut Node, mut offset: u64, buf: &[u8]) -> IoResult<usize> {
+        let cluster_size = self.cluster_size as u64;
+        let mut done = 0;
+        while done < buf.len() {
+            let cluster = match self.seek_cluster(node, (offset / cluster_size) as u32, true) {
+                Ok(cluster) => cluster.expect("allocation must give a cluster"),
+                // Report what was written before the disk was full
+                Err(Errno::ENOSPC) if done > 0 => break,
+                Err(e) => return Err(e),
+            };
+            let in_cluster = offset % cluster_size;
+            let len = min((cluster_size - in_cluster) as usize, buf.len() - done);
+            self.disk.write_all(
+                self.cluster_offset(cluster) + in_cluster,
+                &buf[done..done + len],
+            )?;
+            done += len;
+            offset += len as u64;
+        }
+        Ok(done)
+    }
+
+    /// Fill the file `node` with zeros from `offset` to `end`, FAT
+    /// files cannot have holes
+    fn write_zeros(&mut self, node: &mut Node, mut offset: u64, end: u64) -> IoResult<()> {
+        let zeros = [0; 512];
+        while offset < end {
+            let len = min(end - offset, zeros.len() as u64) as usize;
+            offset += self.write_data(node, offset, &zeros[..len])? as u64;
+        }
+        Ok(())
+    }
+
+    /// Read all the slots of the directory `inode_nbr`
+    fn read_dir_content(&mut self, inode_nbr: InodeNbr) -> IoResult<Vec<u8>> {
+        let mut node = self.node(inode_nbr)?;
+        if !node.entry.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+        let cluster_size = self.cluster_size as usize;
+        let mut content = Vec::new();
+        loop {
+            let len = content.len();
+            content.try_reserve(cluster_size)?;
+            content.resize(len + cluster_size, 0);
+            let count = self.read_data(inode_nbr, &mut node, len as u64, &mut content[len..])?;
+            content.truncate(len + count);
+            if count < cluster_size {
+                break;
+            }
+        }
+        self.set_node(inode_nbr, node);
+        Ok(content)
+    }
+
+    /// Get the byte offset on the disk of the slot `slot` of the directory `inode_nbr`
+    fn slot_offset(&mut self, inode_nbr: InodeNbr, node: &mut Node, slot: u32) -> IoResult<u64> {
+        let offset = slot as u64 * SLOT_SIZE as u64;
+        if self.is_fixed_root(inode_nbr) {
+            return Ok(self.root_dir_offset + offset);
+        }
+        let cluster_size = self.cluster_size as u64;
+        let cluster = self
+            .seek_cluster(node, (offset / cluster_size) as u32, false)?
+            .ok_or(Errno::EIO)?;
+        Ok(self.cluster_offset(cluster) + offset % cluster_size)
+    }
+
+    /// Write the short entry of `node` back on the disk
+    fn write_entry(&mut self, node: &Node) -> IoResult<()> {
+        match node.location {
+            Some(location) => self.disk.write_all(location.offset, node.entry.as_slot()),
+            None => Ok(()),
+        }
+    }
+
+    /// The first cluster stored in the '..' entries of the children of `inode_nbr`
+    fn dot_dot_cluster(&self, inode_nbr: InodeNbr) -> IoResult<u32> {
+        match inode_nbr {
+            ROOT_INODE => Ok(0),
+            _ => Ok(self.node(inode_nbr)?.entry.get_first_cluster()),
+        }
+    }
+
+    /// Register a new node, return its inode number
+    fn insert_node(&mut self, node: Node) -> IoResult<InodeNbr> {
+        let inode_nbr = self.next_inode_nbr;
+        self.nodes.try_insert(inode_nbr, node)?;
+        if let Some(location) = node.location {
+            if let Err(e) = self
+                .positions
+                .try_insert((location.parent, location.slot), inode_nbr)
+            {
+                self.nodes.remove(&inode_nbr);
+                return Err(e.into());
+            }
+        }
+        self.next_inode_nbr += 1;
+        Ok(inode_nbr)
+    }
+
+    /// Get the inode number of the entry `raw` of the directory
+    /// `parent`, loading it if needed
+    fn load_node(&mut self, parent: InodeNbr, raw: &RawEntry) -> IoResult<InodeNbr> {
+        if let Some(&inode_nbr) = self.positions.get(&(parent, raw.slot)) {
+            return Ok(inode_nbr);
+        }
+        let mut parent_node = self.node(parent)?;
+        let offset = self.slot_offset(parent, &mut parent_node, raw.slot)?;
+        self.set_node(parent, parent_node);
+        self.insert_node(Node {
+            location: Some(Location {
+                parent,
+                first_slot: raw.first_slot,
+                slot: raw.slot,
+                offset,
+            }),
+            entry: raw.entry,
+            cursor: (0, 0),
+        })
+    }
+
+    /// Free the clusters of a node which is no longer in a directory
+    fn release_node(&mut self, inode_nbr: InodeNbr) -> IoResult<()> {
+        let node = self.nodes.remove(&inode_nbr).ok_or(Errno::ENOENT)?;
+        match node.entry.get_first_cluster() {
+            0 => Ok(()),
+            cluster => self.free_chain(cluster),
+        }
+    }
+
+    /// Find the entry named `name` in the directory `parent`, the
+    /// exact name is prefered to a name only differing by the case
+    fn find_entry(&mut self, parent: InodeNbr, name: &str) -> IoResult<RawEntry> {
+        let mut entries = parse_dir(&self.read_dir_content(parent)?)?;
+        let i = entries
+            .iter()
+            .position(|e| e.name == name)
+            .or_else(|| entries.iter().position(|e| names_match(&e.name, name)))
+            .ok_or(Errno::ENOENT)?;
+        Ok(entries.swap_remove(i))
+    }
+
+    /// Store the entry `entry` named `name` in the directory
+    /// `parent`, with a long name if needed. The entry at slot
+    /// `exclude` is ignored, for a rename in the same directory
+    fn add_entry(
+        &mut self,
+        parent: InodeNbr,
+        name: &str,
+        mut entry: DirEntry,
+        exclude: Option<u32>,
+    ) -> IoResult<(Location, DirEntry)> {
+        check_long_name(name)?;
+        let content = self.read_dir_content(parent)?;
+        let entries = parse_dir(&content)?;
+        for e in entries.iter().filter(|e| Some(e.slot) != exclude) {
+            let short = e.entry.name.to_string(e.entry.get_case())?;
+            if names_match(&e.name, name) || names_match(&short, name) {
+                return Err(Errno::EEXIST);
+            }
+        }
+
+        // Store a short name alone when it keeps the name
+        let taken = |short: &ShortName| {
+            entries
+                .iter()
+                .any(|e| Some(e.slot) != exclude && e.entry.name == *short)
+        };
+        let mut long_name = Vec::new();
+        let (short, case) = match exact_short_name(name) {
+            Some((short, case)) if !taken(&short) => (short, case),
+            _ => {
+                long_name.try_reserve(LONG_NAME_MAX)?;
+                long_name.extend(name.encode_utf16());
+                let (basis, lossy) = basis_name(name);
+                let short = match !lossy && !taken(&basis) {
+                    true => basis,
+                    false => (1..1000000)
+                        .map(|n| basis.with_tail(n))
+                        .find(|short| !taken(short))
+                        .ok_or(Errno::EEXIST)?,
+                };
+                (short, CaseFlags::empty())
+            }
+        };
+        drop(entries);
+
+        // Find enough consecutive free slots, the free slots at the
+        // end of the directory can be extended
+        let nbr_slots = nbr_long_name_slots(&long_name) as u32 + 1;
+        let mut run = 0;
+        let mut first_slot = None;
+        for (i, slot) in content.chunks_exact(SLOT_SIZE as usize).enumerate() {
+            match slot[0] {
+                SLOT_FREE | SLOT_END => run += 1,
+                _ => run = 0,
+            }
+            if run == nbr_slots {
+                first_slot = Some(i as u32 + 1 - nbr_slots);
+                break;
+            }
+        }
+        let capacity = content.len() as u32 / SLOT_SIZE;
+        let first_slot = first_slot.unwrap_or(capacity - run);
+
+        let mut parent_node = self.node(parent)?;
+        if first_slot + nbr_slots > capacity {
+            if self.is_fixed_root(parent) || first_slot + nbr_slots > DIRECTORY_SLOTS_MAX {
+                return Err(Errno::ENOSPC);
+            }
+            let slots_per_cluster = self.cluster_size / SLOT_SIZE;
+            let mut index = capacity / slots_per_cluster;
+            while index * slots_per_cluster < first_slot + nbr_slots {
+                let cluster = self
+                    .seek_cluster(&mut parent_node, index, true)?
+                    .expect("allocation must give a cluster");
+                let offset = self.cluster_offset(cluster);
+                self.disk.write_zeros(offset, self.cluster_size as u64)?;
+                index += 1;
+            }
+        }
+
+        entry.name = short;
+        entry.set_case(case);
+        let checksum = short.checksum();
+        let nbr_long_slots = nbr_slots - 1;
+        for i in 0..nbr_long_slots {
+            let slot = long_name_slot(&long_name, (nbr_long_slots - i) as usize, checksum);
+            let offset = self.slot_offset(parent, &mut parent_node, first_slot + i)?;
+            self.disk.write_all(offset, &slot)?;
+        }
+        let slot = first_slot + nbr_long_slots;
+        let offset = self.slot_offset(parent, &mut parent_node, slot)?;
+        self.disk.write_all(offset, entry.as_slot())?;
+        self.set_node(parent, parent_node);
+        Ok((
+            Location {
+                parent,
+                first_slot,
+                slot,
+                offset,
+            },
+            entry,
+        ))
+    }
+
+    /// Free the slots of the entry at `location`
+    fn clear_slots(&mut self, location: &Location) -> IoResult<()> {
+        let mut parent_node = self.node(location.parent)?;
+        for slot in location.first_slot..=location.slot {
+            let offset = self.slot_offset(location.parent, &mut parent_node, slot)?;
+            self.disk.write_all(offset, &[SLOT_FREE])?;
+        }
+        self.set_node(location.parent, parent_node);
+        self.positions.remove(&(location.parent, location.slot));
+        Ok(())
+    }
+
+    /// Get the informations about the file `inode_nbr`
+    pub fn stat(&mut self, inode_nbr: u32) -> IoResult<Stat> {
+        let node = self.node(inode_nbr)?;
+        let cluster_size = self.cluster_size as u64;
+        let is_directory = node.entry.is_directory();
+        let allocated = if self.is_fixed_root(inode_nbr) {
+            self.root_dir_slots as u64 * SLOT_SIZE as u64
+        } else if is_directory {
+            match node.entry.get_first_cluster() {
+                0 => 0,
+                cluster => self.chain_len(cluster)? as u64 * cluster_size,
+            }
+        } else {
+            (node.entry.get_size() as u64 + cluster_size - 1) / cluster_size * cluster_size
+        };
+        Ok(Stat {
+            inode_nbr,
+            is_directory,
+            read_only: node.entry.get_attributes().contains(Attributes::READ_ONLY),
+            size: match is_directory {
+                true => allocated,
+                false => node.entry.get_size() as u64,
+            },
+            nbr_disk_sectors: (allocated / 512) as u32,
+            access_time: node.entry.get_access_time(),
+            modification_time: node.entry.get_modification_time(),
+        })
+    }
+
+    /// Get the informations about the root directory
+    pub fn root_inode(&mut self) -> IoResult<Stat> {
+        self.stat(ROOT_INODE)
+    }
+
+    /// Get the names and the informations of the files in the directory `inode_nbr`
+    pub fn lookup_directory(&mut self, inode_nbr: u32) -> IoResult<Vec<(String, Stat)>> {
+        let entries = parse_dir(&self.read_dir_content(inode_nbr)?)?;
+        let mut res = Vec::new();
+        res.try_reserve(entries.len())?;
+        for raw in entries {
+            let child = self.load_node(inode_nbr, &raw)?;
+            res.push((raw.name, self.stat(child)?));
+        }
+        Ok(res)
+    }
+
+    /// Read `buf.len()` bytes of the file `inode_nbr` from `offset`
+    pub fn read(&mut self, inode_nbr: u32, offset: &mut u64, buf: &mut [u8]) -> IoResult<usize> {
+        let mut node = self.node(inode_nbr)?;
+        if node.entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        let size = node.entry.get_size() as u64;
+        if *offset >= size {
+            return Ok(0);
+        }
+        let len = min(buf.len() as u64, size - *offset) as usize;
+        let count = self.read_data(inode_nbr, &mut node, *offset, &mut buf[..len])?;
+        self.set_node(inode_nbr, node);
+        *offset += count as u64;
+        Ok(count)
+    }
+
+    /// Write `buf` in the file `inode_nbr` at `offset`
+    pub fn write(
+        &mut self,
+        inode_nbr: u32,
+        offset: &mut u64,
+        buf: &[u8],
+        timestamp: u32,
+    ) -> IoResult<(usize, Stat)> {
+        let mut node = self.node(inode_nbr)?;
+        if node.entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        if buf.is_empty() {
+            return Ok((0, self.stat(inode_nbr)?));
+        }
+        offset
+            .checked_add(buf.len() as u64)
+            .filter(|&end| end <= FILE_SIZE_MAX)
+            .ok_or(Errno::EFBIG)?;
+
+        let size = node.entry.get_size() as u64;
+        let res = match *offset > size {
+            true => self.write_zeros(&mut node, size, *offset),
+            false => Ok(()),
+        }
+        .and_then(|_| self.write_data(&mut node, *offset, buf));
+        // The chain may have grown even on error
+        self.set_node(inode_nbr, node);
+        let count = res?;
+
+        *offset += count as u64;
+        node.entry.set_size(max(size, *offset) as u32);
+        node.entry.set_modification_time(timestamp);
+        node.entry
+            .set_attributes(node.entry.get_attributes() | Attributes::ARCHIVE);
+        self.set_node(inode_nbr, node);
+        self.write_entry(&node)?;
+        Ok((count, self.stat(inode_nbr)?))
+    }
+
+    /// Write back the filesystem: the FAT, the FSInfo sector and the
+    /// directory entries are written through, only the disk has to
+    /// flush its own cache
+    pub fn sync(&mut self) -> IoResult<()> {
+        self.disk.0.flush()
+    }
+
+    /// Change the size of the file `inode_nbr`, an extension is filled with zeros
+    pub fn truncate(&mut self, inode_nbr: u32, new_size: u64, timestamp: u32) -> IoResult<()> {
+        let mut node = self.node(inode_nbr)?;
+        if node.entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        if new_size > FILE_SIZE_MAX {
+            return Err(Errno::EFBIG);
+        }
+        let size = node.entry.get_size() as u64;
+        if new_size > size {
+            let res = self.write_zeros(&mut node, size, new_size);
+            self.set_node(inode_nbr, node);
+            res?;
+        } else if new_size < size {
+            match (node.entry.get_first_cluster(), new_size) {
+                (0, _) => {}
+                (first, 0) => {
+                    node.entry.set_first_cluster(0);
+                    node.entry.set_size(0);
+                    self.write_entry(&node)?;
+                    self.free_chain(first)?;
+                }
+                _ => {
+                    let last_index = ((new_size - 1) / self.cluster_size as u64) as u32;
+                    let last = self
+                        .seek_cluster(&mut node, last_index, false)?
+                        .ok_or(Errno::EIO)?;
+                    self.cut_chain(last)?;
+                }
+            }
+            node.cursor = (0, 0);
+        }
+        node.entry.set_size(new_size as u32);
+        node.entry.set_modification_time(timestamp);
+        self.set_node(inode_nbr, node);
+        self.write_entry(&node)
+    }
+
+    /// Create the file `name` in the directory `parent`
+    pub fn create(
+        &mut self,
+        parent: u32,
+        name: &str,
+        timestamp: u32,
+        read_only: bool,
+    ) -> IoResult<Stat> {
+        let mut attributes = Attributes::ARCHIVE;
+        if read_only {
+            attributes |= Attributes::READ_ONLY;
+        }
+        let entry = DirEntry::new(ShortName([b' '; 11]), attributes, timestamp);
+        let (location, entry) = self.add_entry(parent, name, entry, None)?;
+        let inode_nbr = self.insert_node(Node {
+            location: Some(location),
+            entry,
+            cursor: (0, 0),
+        })?;
+        self.stat(inode_nbr)
+    }
+
+    /// Create the directory `name` in the directory `parent`
+    pub fn create_dir(&mut self, parent: u32, name: &str, timestamp: u32) -> IoResult<Stat> {
+        let dot_dot_cluster = self.dot_dot_cluster(parent)?;
+        let cluster = self.alloc_cluster(None)?;
+        match self.init_dir(cluster, (parent, dot_dot_cluster), name, timestamp) {
+            Ok(inode_nbr) => self.stat(inode_nbr),
+            Err(e) => {
+                self.free_chain(cluster)?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the '.' and '..' entries in the new directory
+    /// `cluster`, then store it in `parent`
+    fn init_dir(
+        &mut self,
+        cluster: u32,
+        (parent, dot_dot_cluster): (InodeNbr, u32),
+        name: &str,
+        timestamp: u32,
+    ) -> IoResult<InodeNbr> {
+        let offset = self.cluster_offset(cluster);
+        self.disk.write_zeros(offset, self.cluster_size as u64)?;
+        let mut dot = DirEntry::new(ShortName::DOT, Attributes::DIRECTORY, timestamp);
+        dot.set_first_cluster(cluster);
+        self.disk.write_all(offset, dot.as_slot())?;
+        let mut dot_dot = DirEntry::new(ShortName::DOT_DOT, Attributes::DIRECTORY, timestamp);
+        dot_dot.set_first_cluster(dot_dot_cluster);
+        self.disk
+            .write_all(offset + SLOT_SIZE as u64, dot_dot.as_slot())?;
+
+        let mut entry = DirEntry::new(ShortName([b' '; 11]), Attributes::DIRECTORY, timestamp);
+        entry.set_first_cluster(cluster);
+        let (location, entry) = self.add_entry(parent, name, entry, None)?;
+        self.insert_node(Node {
+            location: Some(location),
+            entry,
+            cursor: (0, 0),
+        })
+    }
+
+    /// Remove the file `name` from the directory `parent`, its
+    /// clusters are kept until remove_inode if not `free_inode_data`
+    pub fn unlink(&mut self, parent: u32, name: &str, free_inode_data: bool) -> IoResult<()> {
+        let raw = self.find_entry(parent, name)?;
+        if raw.entry.is_directory() {
+            return Err(Errno::EISDIR);
+        }
+        let inode_nbr = self.load_node(parent, &raw)?;
+        let mut node = self.node(inode_nbr)?;
+        self.clear_slots(&node.location.ok_or(Errno::ENOENT)?)?;
+        node.location = None;
+        self.set_node(inode_nbr, node);
+        match free_inode_data {
+            true => self.release_node(inode_nbr),
+            false => Ok(()),
+        }
+    }
+
+    /// Free the clusters of an unlinked file
+    pub fn remove_inode(&mut self, inode_nbr: u32) -> IoResult<()> {
+        self.release_node(inode_nbr)
+    }
+
+    /// Remove the empty directory `name` from the directory `parent`
+    pub fn rmdir(&mut self, parent: u32, name: &str) -> IoResult<()> {
+        let raw = self.find_entry(parent, name)?;
+        if !raw.entry.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+        let inode_nbr = self.load_node(parent, &raw)?;
+        if !parse_dir(&self.read_dir_content(inode_nbr)?)?.is_empty() {
+            return Err(Errno::ENOTEMPTY);
+        }
+        let node = self.node(inode_nbr)?;
+        self.clear_slots(&node.location.ok_or(Errno::ENOENT)?)?;
+        self.release_node(inode_nbr)
+    }
+
+    /// Move the entry `name` of `parent` to `new_name` in `new_parent`
+    pub fn rename(
+        &mut self,
+        parent: u32,
+        name: &str,
+        new_parent: u32,
+        new_name: &str,
+    ) -> IoResult<()> {
+        let raw = self.find_entry(parent, name)?;
+        let inode_nbr = self.load_node(parent, &raw)?;
+        let mut node = self.node(inode_nbr)?;
+        let old_location = node.location.ok_or(Errno::ENOENT)?;
+        let is_directory = node.entry.is_directory();
+
+        // A directory cannot be moved inside itself
+        if is_directory && new_parent != parent {
+            let mut ancestor = new_parent;
+            while let Some(location) = self.node(ancestor)?.location {
+                if ancestor == inode_nbr {
+                    return Err(Errno::EINVAL);
+                }
+                ancestor = location.parent;
+            }
+        }
+
+        let exclude = match new_parent == parent {
+            true => Some(raw.slot),
+            false => None,
+        };
+        let (location, entry) = self.add_entry(new_parent, new_name, node.entry, exclude)?;
+        self.clear_slots(&old_location)?;
+        node.location = Some(location);
+        node.entry = entry;
+        self.set_node(inode_nbr, node);
+        self.positions
+            .try_insert((new_parent, location.slot), inode_nbr)?;
+
+        if is_directory && new_parent != parent {
+            let offset = self.cluster_offset(node.entry.get_first_cluster()) + SLOT_SIZE as u64;
+            let mut slot = [0; SLOT_SIZE as usize];
+            self.disk.read_exact(offset, &mut slot)?;
+            let mut dot_dot = DirEntry::from_slot(&slot);
+            dot_dot.set_first_cluster(self.dot_dot_cluster(new_parent)?);
+            self.disk.write_all(offset, dot_dot.as_slot())?;
+        }
+        Ok(())
+    }
+
+    /// Set the access and modification times of `inode_nbr`
+    pub fn utime(
+        &mut self,
+        inode_nbr: u32,
+        access_time: u32,
+        modification_time: u32,
+    ) -> IoResult<()> {
+        let mut node = self.node(inode_nbr)?;
+        node.entry.set_access_time(access_time);
+        node.entry.set_modification_time(modification_time);
+        self.set_node(inode_nbr, node);
+        self.write_entry(&node)
+    }
+
+    /// The only permission FAT knows: the read only attribute
+    pub fn set_read_only(&mut self, inode_nbr: u32, read_only: bool) -> IoResult<()> {
+        let mut node = self.node(inode_nbr)?;
+        let mut attributes = node.entry.get_attributes();
+        attributes.set(Attributes::READ_ONLY, read_only);
+        node.entry.set_attributes(attributes);
+        self.set_node(inode_nbr, node);
+        self.write_entry(&node)
+    }
+}
+
+/// Compare two names ignoring the case, as FAT does
+fn names_match(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Parse the slots of a directory, '.' and '..' excluded
+fn parse_dir(content: &[u8]) -> IoResult<Vec<RawEntry>> {
+    let mut entries = Vec::new();
+    let mut long_name = LongNameParser::new();
+    for (slot_nbr, slot) in content.chunks_exact(SLOT_SIZE as usize).enumerate() {
+        let slot_nbr = slot_nbr as u32;
+        match slot[0] {
+            SLOT_END => break,
+            SLOT_FREE => {
+                long_name.reset();
+                continue;
+            }
+            _ => {}
+        }
+        if slot[11] & 0x3f == Attributes::LONG_NAME.bits() {
+            long_name.push(slot, slot_nbr);
+            continue;
+        }
+        let entry = DirEntry::from_slot(slot);
+        let first_slot = long_name.first_slot;
+        let (name, first_slot) = match long_name.take(&entry.name) {
+            Some(chars) => (decode_long_name(chars)?, first_slot),
+            None => (entry.name.to_string(entry.get_case())?, slot_nbr),
+        };
+        if entry.get_attributes().contains(Attributes::VOLUME_ID)
+            || entry.name == ShortName::DOT
+            || entry.name == ShortName::DOT_DOT
+        {
+            continue;
+        }
+        entries.try_reserve(1)?;
+        entries.push(RawEntry {
+            name,
+            entry,
+            first_slot,
+            slot: slot_nbr,
+        });
+    }
+    Ok(entries)
+}
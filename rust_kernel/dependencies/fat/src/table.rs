@@ -0,0 +1,271 @@
+//! This file contains the access to the File Allocation Table and the cluster chains
+
+use crate::bpb::{FSINFO_FREE_COUNT_OFFSET, FSINFO_UNKNOWN};
+use crate::{FatFilesystem, IoResult};
+use libc_binding::Errno;
+
+use alloc::vec::Vec;
+
+/// The width of the FAT entries, deduced from the number of clusters
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+impl FatType {
+    /// The type is only determined by the number of data clusters
+    pub fn from_nbr_clusters(nbr_clusters: u32) -> Self {
+        match nbr_clusters {
+            n if n < 4085 => FatType::Fat12,
+            n if n < 65525 => FatType::Fat16,
+            _ => FatType::Fat32,
+        }
+    }
+
+    /// The smallest value marking the end of a cluster chain
+    fn end_of_chain(&self) -> u32 {
+        match self {
+            FatType::Fat12 => 0xff8,
+            FatType::Fat16 => 0xfff8,
+            FatType::Fat32 => 0x0ffffff8,
+        }
+    }
+}
+
+/// Value of a free FAT entry
+const FREE_CLUSTER: u32 = 0;
+/// Number of the first data cluster
+pub const FIRST_CLUSTER: u32 = 2;
+
+/// A sector of the first FAT kept in memory
+#[derive(Debug)]
+pub struct FatCache {
+    /// Offset of the sector in the FAT, u64::MAX if none
+    offset: u64,
+    buf: Vec<u8>,
+}
+
+impl FatCache {
+    pub fn new(bytes_per_sector: u32) -> IoResult<Self> {
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(bytes_per_sector as usize)?;
+        buf.resize(bytes_per_sector as usize, 0);
+        Ok(Self {
+            offset: u64::MAX,
+            buf,
+        })
+    }
+}
+
+impl FatFilesystem {
+    /// Read the bytes at `offset` in the first FAT
+    fn read_fat(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<()> {
+        let sector_size = self.fat_cache.buf.len() as u64;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            let offset = offset + i as u64;
+            let sector = offset - offset % sector_size;
+            if self.fat_cache.offset != sector {
+                self.fat_cache.offset = u64::MAX;
+                self.disk
+                    .read_exact(self.fat_offset + sector, &mut self.fat_cache.buf)?;
+                self.fat_cache.offset = sector;
+            }
+            *byte = self.fat_cache.buf[(offset - sector) as usize];
+        }
+        Ok(())
+    }
+
+    /// Write the bytes at `offset` in all the FATs
+    fn write_fat(&mut self, offset: u64, buf: &[u8]) -> IoResult<()> {
+        for i in 0..self.nbr_fats as u64 {
+            self.disk
+                .write_all(self.fat_offset + i * self.fat_size + offset, buf)?;
+        }
+        let sector_size = self.fat_cache.buf.len() as u64;
+        for (i, &byte) in buf.iter().enumerate() {
+            let offset = offset + i as u64;
+            if self.fat_cache.offset == offset - offset % sector_size {
+                self.fat_cache.buf[(offset % sector_size) as usize] = byte;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the FAT entry of `cluster`
+    pub(crate) fn fat_entry(&mut self, cluster: u32) -> IoResult<u32> {
+        let mut buf = [0; 4];
+        Ok(match self.fat_type {
+            FatType::Fat12 => {
+                self.read_fat((cluster + cluster / 2) as u64, &mut buf[..2])?;
+                let v = u16::from_le_bytes([buf[0], buf[1]]) as u32;
+                if cluster & 1 == 1 {
+                    v >> 4
+                } else {
+                    v & 0xfff
+                }
+            }
+            FatType::Fat16 => {
+                self.read_fat(cluster as u64 * 2, &mut buf[..2])?;
+                u16::from_le_bytes([buf[0], buf[1]]) as u32
+            }
+            FatType::Fat32 => {
+                self.read_fat(cluster as u64 * 4, &mut buf)?;
+                // The 4 high bits are reserved
+                u32::from_le_bytes(buf) & 0x0fffffff
+            }
+        })
+    }
+
+    /// Set the FAT entry of `cluster` to `value`
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) -> IoResult<()> {
+        let mut buf = [0; 4];
+        match self.fat_type {
+            FatType::Fat12 => {
+                let offset = (cluster + cluster / 2) as u64;
+                self.read_fat(offset, &mut buf[..2])?;
+                let v = u16::from_le_bytes([buf[0], buf[1]]);
+                let v = if cluster & 1 == 1 {
+                    (v & 0x000f) | (value as u16) << 4
+                } else {
+                    (v & 0xf000) | (value as u16 & 0xfff)
+                };
+                self.write_fat(offset, &v.to_le_bytes())
+            }
+            FatType::Fat16 => self.write_fat(cluster as u64 * 2, &(value as u16).to_le_bytes()),
+            FatType::Fat32 => {
+                let offset = cluster as u64 * 4;
+                self.read_fat(offset, &mut buf)?;
+                let v = (u32::from_le_bytes(buf) & 0xf0000000) | (value & 0x0fffffff);
+                self.write_fat(offset, &v.to_le_bytes())
+            }
+        }
+    }
+
+    /// Check whether `cluster` is a data cluster of the volume
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        cluster >= FIRST_CLUSTER && cluster < FIRST_CLUSTER + self.nbr_clusters
+    }
+
+    /// Get the cluster following `cluster` in its chain, None at the end
+    pub(crate) fn next_cluster(&mut self, cluster: u32) -> IoResult<Option<u32>> {
+        match self.fat_entry(cluster)? {
+            n if n >= self.fat_type.end_of_chain() => Ok(None),
+            n if self.is_valid_cluster(n) => Ok(Some(n)),
+            // A free or bad cluster in a chain, the volume is corrupted
+            _ => Err(Errno::EIO),
+        }
+    }
+
+    /// Get the byte offset of `cluster` on the disk
+    pub(crate) fn cluster_offset(&self, cluster: u32) -> u64 {
+        debug_assert!(self.is_valid_cluster(cluster));
+        self.data_offset + (cluster - FIRST_CLUSTER) as u64 * self.cluster_size as u64
+    }
+
+    /// Allocate a new cluster at the end of the chain ending by
+    /// `last`, as close as possible to it
+    pub(crate) fn alloc_cluster(&mut self, last: Option<u32>) -> IoResult<u32> {
+        if self.free_clusters == 0 {
+            return Err(Errno::ENOSPC);
+        }
+        let start = match last {
+            Some(last) => last + 1,
+            None => self.next_free,
+        };
+        let end = FIRST_CLUSTER + self.nbr_clusters;
+        let start = if self.is_valid_cluster(start) {
+            start
+        } else {
+            FIRST_CLUSTER
+        };
+        let mut cluster = start;
+        loop {
+            if self.fat_entry(cluster)? == FREE_CLUSTER {
+                break;
+            }
+            cluster = if cluster + 1 == end {
+                FIRST_CLUSTER
+            } else {
+                cluster + 1
+            };
+            if cluster == start {
+                // The free count was wrong
+                self.free_clusters = 0;
+                return Err(Errno::ENOSPC);
+            }
+        }
+        self.set_fat_entry(cluster, self.fat_type.end_of_chain() | 0x7)?;
+        if let Some(last) = last {
+            self.set_fat_entry(last, cluster)?;
+        }
+        self.free_clusters -= 1;
+        self.next_free = cluster + 1;
+        self.write_fsinfo()?;
+        Ok(cluster)
+    }
+
+    /// Free the chain of clusters starting at `cluster`
+    pub(crate) fn free_chain(&mut self, cluster: u32) -> IoResult<()> {
+        let mut next = Some(cluster);
+        while let Some(cluster) = next {
+            next = self.next_cluster(cluster)?;
+            self.set_fat_entry(cluster, FREE_CLUSTER)?;
+            self.free_clusters += 1;
+            self.next_free = self.next_free.min(cluster);
+        }
+        self.write_fsinfo()
+    }
+
+    /// Cut the chain of clusters after `cluster`
+    pub(crate) fn cut_chain(&mut self, cluster: u32) -> IoResult<()> {
+        if let Some(next) = self.next_cluster(cluster)? {
+            self.set_fat_entry(cluster, self.fat_type.end_of_chain() | 0x7)?;
+            self.free_chain(next)?;
+        }
+        Ok(())
+    }
+
+    /// Count the clusters of the chain starting at `cluster`
+    pub(crate) fn chain_len(&mut self, cluster: u32) -> IoResult<u32> {
+        let mut len = 0;
+        let mut next = Some(cluster);
+        while let Some(cluster) = next {
+            len += 1;
+            if len > self.nbr_clusters {
+                // A loop in the chain
+                return Err(Errno::EIO);
+            }
+            next = self.next_cluster(cluster)?;
+        }
+        Ok(len)
+    }
+
+    /// Count the free clusters of the volume
+    pub(crate) fn count_free_clusters(&mut self) -> IoResult<u32> {
+        let mut free = 0;
+        for cluster in FIRST_CLUSTER..FIRST_CLUSTER + self.nbr_clusters {
+            if self.fat_entry(cluster)? == FREE_CLUSTER {
+                free += 1;
+            }
+        }
+        Ok(free)
+    }
+
+    /// Keep the free count of the FAT32 FSInfo sector up to date
+    fn write_fsinfo(&mut self) -> IoResult<()> {
+        if let Some(fs_info) = self.fs_info {
+            let mut buf = [0; 8];
+            buf[..4].copy_from_slice(&self.free_clusters.to_le_bytes());
+            let next_free = match self.is_valid_cluster(self.next_free) {
+                true => self.next_free,
+                false => FSINFO_UNKNOWN,
+            };
+            buf[4..].copy_from_slice(&next_free.to_le_bytes());
+            self.disk
+                .write_all(fs_info + FSINFO_FREE_COUNT_OFFSET, &buf)?;
+        }
+        Ok(())
+    }
+}
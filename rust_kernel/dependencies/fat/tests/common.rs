@@ -0,0 +1,136 @@
+#![allow(dead_code)]
+//! The tests need mkfs.vfat, fsck.vfat (dosfstools) and mtools
+//! (mcopy, mmd, mdir) to prepare and check the images, no mount needed
+
+use fat::{DiskIo, FatFilesystem, IoResult, Stat, ROOT_INODE};
+use libc_binding::Errno;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug)]
+pub struct StdDiskIo {
+    pub f: File,
+}
+
+impl DiskIo for StdDiskIo {
+    fn flush(&mut self) -> IoResult<()> {
+        self.f.flush().map_err(|_| Errno::EIO)
+    }
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.write(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.read(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+}
+
+pub fn exec_shell(cmd: &str) -> bool {
+    let exit_code = Command::new("bash")
+        .args(&["-c"])
+        .args(&[cmd])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    if !exit_code.success() {
+        eprintln!("command failed: {}", cmd);
+    }
+    exit_code.success()
+}
+
+/// Every test works on its own image so they can run in parallel
+pub fn create_disk(name: &str, size: usize, fat_bits: u32) {
+    exec_shell(&format!("rm -f {}", name));
+    exec_shell(&format!(
+        "dd if=/dev/zero of={} bs=1024 count={}",
+        name,
+        size / 1024
+    ));
+    assert!(exec_shell(&format!("mkfs.vfat -F {} {}", fat_bits, name)));
+}
+
+/// Copy `content` in the image at `path` with mcopy
+pub fn mcopy_to(name: &str, path: &str, content: &[u8]) {
+    let tmp = format!("{}.tmp", name);
+    std::fs::write(&tmp, content).unwrap();
+    assert!(exec_shell(&format!(
+        "mcopy -i {} '{}' '::{}'",
+        name, tmp, path
+    )));
+    std::fs::remove_file(&tmp).unwrap();
+}
+
+/// Get the content of the file at `path` in the image with mcopy
+pub fn mcopy_from(name: &str, path: &str) -> Option<Vec<u8>> {
+    let tmp = format!("{}.tmp", name);
+    let _ = std::fs::remove_file(&tmp);
+    if !exec_shell(&format!("mcopy -i {} '::{}' '{}'", name, path, tmp)) {
+        return None;
+    }
+    let content = std::fs::read(&tmp).unwrap();
+    std::fs::remove_file(&tmp).unwrap();
+    Some(content)
+}
+
+pub fn mmd(name: &str, path: &str) {
+    assert!(exec_shell(&format!("mmd -i {} '::{}'", name, path)));
+}
+
+/// Check the image is consistent, without repairing it
+pub fn fsck(name: &str) -> bool {
+    exec_shell(&format!("fsck.vfat -n {}", name))
+}
+
+pub fn new_fat(name: &str) -> FatFilesystem {
+    let f = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(name)
+        .expect("open filesystem failed");
+    FatFilesystem::new(Box::new(StdDiskIo { f })).expect("init fat filesystem failed")
+}
+
+/// Walk `path` from the root directory
+pub fn find(fat: &mut FatFilesystem, path: &str) -> IoResult<Stat> {
+    let mut stat = fat.stat(ROOT_INODE)?;
+    for component in path.split('/').filter(|s| !s.is_empty()) {
+        stat = fat
+            .lookup_directory(stat.inode_nbr)?
+            .into_iter()
+            .find(|(name, _)| name == component)
+            .ok_or(Errno::ENOENT)?
+            .1;
+    }
+    Ok(stat)
+}
+
+pub fn read_all(fat: &mut FatFilesystem, inode_nbr: u32) -> Vec<u8> {
+    let mut content = vec![0; fat.stat(inode_nbr).unwrap().size as usize];
+    let mut offset = 0;
+    let mut total = 0;
+    while total < content.len() {
+        let n = fat
+            .read(inode_nbr, &mut offset, &mut content[total..])
+            .expect("read on filesystem failed");
+        assert_ne!(n, 0);
+        total += n;
+    }
+    content
+}
+
+pub fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+pub const FAT_TYPES: [(u32, usize); 3] = [
+    (12, 1024 * 1024),
+    (16, 16 * 1024 * 1024),
+    (32, 64 * 1024 * 1024),
+];
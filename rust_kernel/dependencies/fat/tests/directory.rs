@@ -0,0 +1,94 @@
+use fat::ROOT_INODE;
+use libc_binding::Errno;
+mod common;
+use common::*;
+
+#[test]
+fn mkdir() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_mkdir_{}", bits);
+        create_disk(&disk, *size, *bits);
+        {
+            let mut fat = new_fat(&disk);
+            let dir = fat.create_dir(ROOT_INODE, "Some Directory", 0).unwrap();
+            let sub = fat.create_dir(dir.inode_nbr, "sub", 0).unwrap();
+            let file = fat.create(sub.inode_nbr, "file.txt", 0, false).unwrap();
+            let mut offset = 0;
+            fat.write(file.inode_nbr, &mut offset, b"nested", 0)
+                .unwrap();
+        }
+        assert_eq!(
+            mcopy_from(&disk, "Some Directory/sub/file.txt").unwrap(),
+            b"nested"
+        );
+        assert!(fsck(&disk));
+    }
+}
+
+#[test]
+fn rmdir() {
+    let disk = "disk_rmdir";
+    create_disk(disk, 1024 * 1024, 12);
+    mmd(disk, "empty");
+    mmd(disk, "full");
+    mcopy_to(disk, "full/file", b"");
+    {
+        let mut fat = new_fat(disk);
+        fat.rmdir(ROOT_INODE, "empty").expect("rmdir failed");
+        assert_eq!(fat.rmdir(ROOT_INODE, "full").unwrap_err(), Errno::ENOTEMPTY);
+        assert_eq!(find(&mut fat, "empty").unwrap_err(), Errno::ENOENT);
+    }
+    assert!(fsck(disk));
+}
+
+#[test]
+fn unlink() {
+    let disk = "disk_unlink";
+    create_disk(disk, 16 * 1024 * 1024, 16);
+    let free = new_fat(disk).get_free_clusters();
+    mcopy_to(disk, "a file with a long name", &pattern(50 * 1024));
+    {
+        let mut fat = new_fat(disk);
+        fat.unlink(ROOT_INODE, "a file with a long name", true)
+            .expect("unlink failed");
+        assert_eq!(fat.get_free_clusters(), free);
+    }
+    assert_eq!(mcopy_from(disk, "a file with a long name"), None);
+    assert!(fsck(disk));
+}
+
+#[test]
+fn rename() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_rename_{}", bits);
+        create_disk(&disk, *size, *bits);
+        mmd(&disk, "src");
+        mmd(&disk, "dst");
+        mmd(&disk, "src/moved dir");
+        mcopy_to(&disk, "src/file", b"content");
+        mcopy_to(&disk, "src/moved dir/inner", b"inner");
+        {
+            let mut fat = new_fat(&disk);
+            let src = find(&mut fat, "src").unwrap();
+            let dst = find(&mut fat, "dst").unwrap();
+            fat.rename(src.inode_nbr, "file", dst.inode_nbr, "Renamed File.txt")
+                .expect("rename failed");
+            fat.rename(src.inode_nbr, "moved dir", dst.inode_nbr, "moved dir")
+                .expect("rename failed");
+            let moved = find(&mut fat, "dst/moved dir").unwrap();
+            assert_eq!(
+                fat.rename(dst.inode_nbr, "moved dir", moved.inode_nbr, "loop")
+                    .unwrap_err(),
+                Errno::EINVAL
+            );
+        }
+        assert_eq!(mcopy_from(&disk, "src/file"), None);
+        assert_eq!(
+            mcopy_from(&disk, "dst/Renamed File.txt").unwrap(),
+            b"content"
+        );
+        assert_eq!(mcopy_from(&disk, "dst/moved dir/inner").unwrap(), b"inner");
+        // fsck checks the '..' entry of the moved directory
+        assert!(fsck(&disk));
+    }
+}
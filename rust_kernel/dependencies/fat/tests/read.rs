@@ -0,0 +1,46 @@
+mod common;
+use common::*;
+
+#[test]
+fn read_short_name() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_read_short_name_{}", bits);
+        create_disk(&disk, *size, *bits);
+        mcopy_to(&disk, "README.TXT", b"hello");
+        let mut fat = new_fat(&disk);
+        let stat = find(&mut fat, "README.TXT").expect("lookup failed");
+        assert_eq!(read_all(&mut fat, stat.inode_nbr), b"hello");
+    }
+}
+
+#[test]
+fn read_long_name() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_read_long_name_{}", bits);
+        create_disk(&disk, *size, *bits);
+        let content = pattern(300 * 1024);
+        mmd(&disk, "A directory");
+        mcopy_to(&disk, "A directory/a long file name.data", &content);
+        let mut fat = new_fat(&disk);
+        let stat = find(&mut fat, "A directory/a long file name.data").expect("lookup failed");
+        assert_eq!(read_all(&mut fat, stat.inode_nbr), content);
+    }
+}
+
+#[test]
+fn read_at_offset() {
+    let disk = "disk_read_at_offset";
+    create_disk(disk, 16 * 1024 * 1024, 16);
+    let content = pattern(100 * 1024);
+    mcopy_to(disk, "file", &content);
+    let mut fat = new_fat(disk);
+    let stat = find(&mut fat, "file").expect("lookup failed");
+    let mut buf = vec![0; 1000];
+    for &start in [0, 511, 4096, 70000, 102000].iter() {
+        let mut offset = start as u64;
+        let n = fat.read(stat.inode_nbr, &mut offset, &mut buf).unwrap();
+        let end = (start + 1000).min(content.len());
+        assert_eq!(&buf[..n], &content[start..end]);
+        assert_eq!(offset, end as u64);
+    }
+}
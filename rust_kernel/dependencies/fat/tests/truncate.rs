@@ -0,0 +1,42 @@
+mod common;
+use common::*;
+
+#[test]
+fn truncate_shrink() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_truncate_shrink_{}", bits);
+        create_disk(&disk, *size, *bits);
+        let content = pattern(100 * 1024);
+        mcopy_to(&disk, "file", &content);
+        let free = {
+            let mut fat = new_fat(&disk);
+            let free = fat.get_free_clusters();
+            let stat = find(&mut fat, "file").unwrap();
+            fat.truncate(stat.inode_nbr, 1000, 0)
+                .expect("truncate failed");
+            assert!(fat.get_free_clusters() > free);
+            fat.get_free_clusters()
+        };
+        assert_eq!(mcopy_from(&disk, "file").unwrap(), &content[..1000]);
+        assert_eq!(new_fat(&disk).get_free_clusters(), free);
+        assert!(fsck(&disk));
+    }
+}
+
+#[test]
+fn truncate_grow() {
+    let disk = "disk_truncate_grow";
+    create_disk(disk, 1024 * 1024, 12);
+    mcopy_to(disk, "file", b"data");
+    {
+        let mut fat = new_fat(disk);
+        let stat = find(&mut fat, "file").unwrap();
+        fat.truncate(stat.inode_nbr, 10000, 0)
+            .expect("truncate failed");
+    }
+    let content = mcopy_from(disk, "file").unwrap();
+    assert_eq!(content.len(), 10000);
+    assert_eq!(&content[..4], b"data");
+    assert!(content[4..].iter().all(|&b| b == 0));
+    assert!(fsck(disk));
+}
@@ -0,0 +1,82 @@
+use fat::ROOT_INODE;
+use libc_binding::Errno;
+mod common;
+use common::*;
+
+#[test]
+fn write_new_file() {
+    for (bits, size) in FAT_TYPES.iter() {
+        let disk = format!("disk_write_new_file_{}", bits);
+        create_disk(&disk, *size, *bits);
+        let content = pattern(200 * 1024);
+        {
+            let mut fat = new_fat(&disk);
+            let stat = fat
+                .create(ROOT_INODE, "A new file.txt", 0, false)
+                .expect("create failed");
+            let mut offset = 0;
+            for chunk in content.chunks(3000) {
+                fat.write(stat.inode_nbr, &mut offset, chunk, 0)
+                    .expect("write failed");
+            }
+        }
+        assert_eq!(mcopy_from(&disk, "A new file.txt").unwrap(), content);
+        assert!(fsck(&disk));
+    }
+}
+
+#[test]
+fn write_after_end() {
+    let disk = "disk_write_after_end";
+    create_disk(disk, 1024 * 1024, 12);
+    mcopy_to(disk, "file", b"start");
+    {
+        let mut fat = new_fat(disk);
+        let stat = find(&mut fat, "file").unwrap();
+        let mut offset = 10;
+        fat.write(stat.inode_nbr, &mut offset, b"end", 0).unwrap();
+    }
+    assert_eq!(mcopy_from(disk, "file").unwrap(), b"start\0\0\0\0\0end");
+    assert!(fsck(disk));
+}
+
+#[test]
+fn create_existing() {
+    let disk = "disk_create_existing";
+    create_disk(disk, 1024 * 1024, 12);
+    mcopy_to(disk, "Mixed Case.txt", b"");
+    let mut fat = new_fat(disk);
+    assert_eq!(
+        fat.create(ROOT_INODE, "MIXED case.TXT", 0, false)
+            .unwrap_err(),
+        Errno::EEXIST
+    );
+    assert_eq!(
+        fat.create(ROOT_INODE, "what?", 0, false).unwrap_err(),
+        Errno::EINVAL
+    );
+}
+
+#[test]
+fn fill_directory() {
+    let disk = "disk_fill_directory";
+    create_disk(disk, 16 * 1024 * 1024, 16);
+    {
+        let mut fat = new_fat(disk);
+        let dir = fat.create_dir(ROOT_INODE, "dir", 0).unwrap();
+        for i in 0..300 {
+            fat.create(
+                dir.inode_nbr,
+                &format!("file with a long name {}", i),
+                0,
+                false,
+            )
+            .expect("create failed");
+        }
+    }
+    assert!(fsck(disk));
+    assert_eq!(
+        mcopy_from(disk, "dir/file with a long name 299").unwrap(),
+        b""
+    );
+}
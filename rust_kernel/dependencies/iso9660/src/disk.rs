@@ -0,0 +1,33 @@
+use crate::IoResult;
+use core::fmt::Debug;
+use libc_binding::Errno;
+extern crate alloc;
+use alloc::boxed::Box;
+
+/// trait to read / write on a disk, an ISO9660 volume is only read
+pub trait DiskIo: Debug + Send {
+    /// flush
+    fn flush(&mut self) -> IoResult<()>;
+    /// write at offset
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64>;
+    /// read at offset
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64>;
+}
+
+#[derive(Debug)]
+pub struct Disk(pub Box<dyn DiskIo>);
+
+impl Disk {
+    pub fn read_exact(&mut self, mut offset: u64, mut buf: &mut [u8]) -> IoResult<()> {
+        while !buf.is_empty() {
+            match self.0.read_buffer(offset, buf)? {
+                0 => return Err(Errno::EIO),
+                n => {
+                    offset += n;
+                    buf = &mut buf[n as usize..]
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+//! this module contains a read only ISO9660 driver, with the Rock Ridge
+//! and Joliet extensions
+//! see [osdev](https://wiki.osdev.org/ISO_9660) and ECMA-119
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod disk;
+use disk::Disk;
+pub use disk::DiskIo;
+
+mod volume;
+use volume::{DescriptorType, VolumeDescriptor, FIRST_DESCRIPTOR_SECTOR, SECTOR_SIZE};
+
+mod record;
+use record::{decode_joliet_name, decode_plain_name, DirRecord, FileFlags};
+
+mod rock_ridge;
+use rock_ridge::{decode_bytes, find_sharing_protocol, RockRidge};
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::min;
+use fallible_collections::btree::BTreeMap;
+use fallible_collections::vec::FallibleVec;
+use libc_binding::Errno;
+
+pub type IoResult<T> = core::result::Result<T, Errno>;
+
+type InodeNbr = u32;
+
+/// The inode number of the root directory. ISO9660 has no inodes, the
+/// other numbers are given when the entries are looked up
+pub const ROOT_INODE: InodeNbr = 1;
+
+/// The volume descriptor set ends before this sector on any sane volume
+const LAST_DESCRIPTOR_SECTOR: u64 = FIRST_DESCRIPTOR_SECTOR + 64;
+/// Maximum number of CE entries followed for one record
+const CONTINUATIONS_MAX: usize = 16;
+
+/// Permissions of the files of a volume without Rock Ridge
+const DEFAULT_PERMISSIONS: u32 = 0o555;
+
+/// The format bits of a Rock Ridge mode
+const S_IFMT: u32 = 0o170000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileKind {
+    Regular,
+    Directory,
+    Symlink,
+    Fifo,
+    Socket,
+    CharDevice,
+    BlockDevice,
+}
+
+impl FileKind {
+    fn from_mode(mode: u32) -> Self {
+        use FileKind::*;
+        match mode & S_IFMT {
+            0o140000 => Socket,
+            0o120000 => Symlink,
+            0o060000 => BlockDevice,
+            0o040000 => Directory,
+            0o020000 => CharDevice,
+            0o010000 => Fifo,
+            _ => Regular,
+        }
+    }
+}
+
+/// What is known about a file
+#[derive(Debug, Copy, Clone)]
+pub struct Stat {
+    pub inode_nbr: u32,
+    pub kind: FileKind,
+    /// The permissions bits, with the set-user-id, set-group-id and sticky bits
+    pub mode: u16,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+    /// The (major, minor) of a device file
+    pub device: (u32, u32),
+    pub size: u64,
+    /// Number of 512 bytes sectors used by the file
+    pub nbr_disk_sectors: u32,
+    pub access_time: u32,
+    pub modification_time: u32,
+    pub change_time: u32,
+}
+
+/// A contiguous part of a file
+#[derive(Debug, Copy, Clone)]
+struct Extent {
+    block: u32,
+    size: u32,
+}
+
+/// An in memory inode
+#[derive(Debug)]
+struct Node {
+    stat: Stat,
+    /// The data, several extents for the multi-extent files
+    extents: Vec<Extent>,
+    /// The target of a symbolic link
+    symlink: Option<String>,
+}
+
+/// Global structure of an ISO9660 filesystem
+#[derive(Debug)]
+pub struct Iso9660Filesystem {
+    disk: Disk,
+    block_size: u32,
+    nbr_blocks: u32,
+    volume_identifier: [u8; 32],
+    /// Whether the names are read in the Joliet hierarchy
+    joliet: bool,
+    /// The bytes to skip at the beginning of the system use areas, when
+    /// Rock Ridge is used
+    susp_skip: Option<usize>,
+    nodes: BTreeMap<InodeNbr, Node>,
+    /// Inode numbers of the loaded entries by the disk offset of their record
+    positions: BTreeMap<u64, InodeNbr>,
+    next_inode_nbr: InodeNbr,
+}
+
+impl Iso9660Filesystem {
+    /// Invocation of a new FileSystem instance: take a FD and his reader as parameter
+    pub fn new(disk: Box<dyn DiskIo>) -> IoResult<Self> {
+        let mut disk = Disk(disk);
+        let mut primary = None;
+        let mut joliet = None;
+        let mut sector = [0; SECTOR_SIZE as usize];
+        for sector_nbr in FIRST_DESCRIPTOR_SECTOR..LAST_DESCRIPTOR_SECTOR {
+            disk.read_exact(sector_nbr * SECTOR_SIZE, &mut sector)?;
+            let descriptor = VolumeDescriptor::new(&sector).ok_or(Errno::EINVAL)?;
+            match descriptor.descriptor_type {
+                DescriptorType::Terminator => break,
+                DescriptorType::Primary if primary.is_none() => primary = Some(descriptor),
+                DescriptorType::Supplementary if descriptor.joliet && joliet.is_none() => {
+                    joliet = Some(descriptor)
+                }
+                _ => {}
+            }
+        }
+        let primary = primary.ok_or(Errno::EINVAL)?;
+        if !primary.is_valid() {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut fs = Self {
+            disk,
+            block_size: primary.logical_block_size as u32,
+            nbr_blocks: primary.volume_space_size,
+            volume_identifier: primary.volume_identifier,
+            joliet: false,
+            susp_skip: None,
+            nodes: BTreeMap::new(),
+            positions: BTreeMap::new(),
+            next_inode_nbr: ROOT_INODE + 1,
+        };
+
+        // Rock Ridge is announced by a SP entry in the "." of the root,
+        // it is found in the primary hierarchy only
+        let root_record = DirRecord::new(&primary.root_record)?.ok_or(Errno::EINVAL)?;
+        let root_extent = root_record.get_extent();
+        let mut block = fs.read_blocks(root_extent, 1)?;
+        let current = DirRecord::new(&block)?.ok_or(Errno::EINVAL)?;
+        fs.susp_skip = find_sharing_protocol(current.get_system_use());
+
+        let root = match (fs.susp_skip, joliet) {
+            (None, Some(joliet)) if joliet.is_valid() => {
+                fs.joliet = true;
+                block = fs.read_blocks(
+                    DirRecord::new(&joliet.root_record)?
+                        .ok_or(Errno::EINVAL)?
+                        .get_extent(),
+                    1,
+                )?;
+                DirRecord::new(&block)?.ok_or(Errno::EINVAL)?
+            }
+            _ => DirRecord::new(&block)?.ok_or(Errno::EINVAL)?,
+        };
+        // The attributes of a directory are in its "." record
+        let rock_ridge = fs.rock_ridge(&root, 0)?;
+        let node = fs.make_node(ROOT_INODE, &root, &rock_ridge, None)?;
+        fs.nodes.try_insert(ROOT_INODE, node)?;
+        Ok(fs)
+    }
+
+    pub fn get_block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    pub fn get_nbr_blocks(&self) -> u32 {
+        self.nbr_blocks
+    }
+
+    /// A cheap identifier for the volume, made of its name
+    pub fn get_volume_id(&self) -> u32 {
+        self.volume_identifier.iter().fold(0u32, |hash, &c| {
+            hash.wrapping_mul(31).wrapping_add(c as u32)
+        })
+    }
+
+    /// The name of the volume, without its padding
+    pub fn get_volume_identifier(&self) -> &[u8] {
+        let len = self
+            .volume_identifier
+            .iter()
+            .rposition(|&c| c != b' ' && c != 0)
+            .map_or(0, |last| last + 1);
+        &self.volume_identifier[..len]
+    }
+
+    pub fn has_rock_ridge(&self) -> bool {
+        self.susp_skip.is_some()
+    }
+
+    pub fn has_joliet(&self) -> bool {
+        self.joliet
+    }
+
+    /// Read `nbr_blocks` logical blocks
+    fn read_blocks(&mut self, block: u32, nbr_blocks: u32) -> IoResult<Vec<u8>> {
+        if block as u64 + nbr_blocks as u64 > self.nbr_blocks as u64 {
+            return Err(Errno::EIO);
+        }
+        let len = nbr_blocks as usize * self.block_size as usize;
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(len)?;
+        buf.resize(len, 0);
+        self.disk
+            .read_exact(block as u64 * self.block_size as u64, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Collect the Rock Ridge entries of a record, following the continuations
+    fn rock_ridge(&mut self, record: &DirRecord, skip: usize) -> IoResult<RockRidge> {
+        let mut rock_ridge = RockRidge::default();
+        if self.susp_skip.is_none() {
+            return Ok(rock_ridge);
+        }
+        let area = record.get_system_use().get(skip..).unwrap_or(&[]);
+        let mut continuation = rock_ridge.parse(area)?;
+        for _ in 0..CONTINUATIONS_MAX {
+            let ce = match continuation {
+                Some(ce) => ce,
+                None => break,
+            };
+            if ce.offset as u64 + ce.len as u64 > self.block_size as u64 {
+                return Err(Errno::EIO);
+            }
+            let block = self.read_blocks(ce.block, 1)?;
+            let (start, end) = (ce.offset as usize, (ce.offset + ce.len) as usize);
+            continuation = rock_ridge.parse(&block[start..end])?;
+        }
+        Ok(rock_ridge)
+    }
+
+    /// Build the in memory inode of a file, `extents` being the data of
+    /// a multi-extent file
+    fn make_node(
+        &self,
+        inode_nbr: InodeNbr,
+        record: &DirRecord,
+        rock_ridge: &RockRidge,
+        extents: Option<Vec<Extent>>,
+    ) -> IoResult<Node> {
+        let extents = match extents {
+            Some(extents) => extents,
+            None => {
+                let mut extents = Vec::new();
+                extents.try_push(Extent {
+                    block: record.get_extent(),
+                    size: record.get_size(),
+                })?;
+                extents
+            }
+        };
+        let (kind, mode, nlink, uid, gid) = match rock_ridge.attributes {
+            Some(px) => (
+                FileKind::from_mode(px.mode),
+                (px.mode & 0o7777) as u16,
+                px.nlink,
+                px.uid,
+                px.gid,
+            ),
+            None => match record.is_directory() {
+                true => (FileKind::Directory, DEFAULT_PERMISSIONS as u16, 2, 0, 0),
+                false => (FileKind::Regular, DEFAULT_PERMISSIONS as u16, 1, 0, 0),
+            },
+        };
+        let symlink = match (kind, &rock_ridge.symlink) {
+            (FileKind::Symlink, Some(target)) => Some(decode_bytes(target)?),
+            (FileKind::Symlink, None) => return Err(Errno::EIO),
+            _ => None,
+        };
+        let data_size: u64 = extents.iter().map(|e| e.size as u64).sum();
+        let block_size = self.block_size as u64;
+        let allocated: u64 = extents
+            .iter()
+            .map(|e| (e.size as u64 + block_size - 1) / block_size * block_size)
+            .sum();
+        let recording_time = record.get_recording_time();
+        let modification_time = rock_ridge.modification_time.unwrap_or(recording_time);
+        Ok(Node {
+            stat: Stat {
+                inode_nbr,
+                kind,
+                mode,
+                nlink,
+                uid,
+                gid,
+                device: rock_ridge.device.unwrap_or((0, 0)),
+                size: match &symlink {
+                    Some(target) => target.len() as u64,
+                    None => data_size,
+                },
+                nbr_disk_sectors: (allocated / 512) as u32,
+                access_time: rock_ridge.access_time.unwrap_or(modification_time),
+                modification_time,
+                change_time: rock_ridge.attributes_time.unwrap_or(modification_time),
+            },
+            extents,
+            symlink,
+        })
+    }
+
+    fn node(&self, inode_nbr: InodeNbr) -> IoResult<&Node> {
+        self.nodes.get(&inode_nbr).ok_or(Errno::ENOENT)
+    }
+
+    /// Get the name of a record in the hierarchy in use
+    fn name(&self, record: &DirRecord, rock_ridge: &RockRidge) -> IoResult<String> {
+        match &rock_ridge.name {
+            Some(name) => decode_bytes(name),
+            None if self.joliet => decode_joliet_name(record.get_identifier()),
+            None => decode_plain_name(record.get_identifier()),
+        }
+    }
+
+    /// Load (or reload) the inode of the record at `offset` on the disk
+    fn load_node(
+        &mut self,
+        offset: u64,
+        record: &DirRecord,
+        rock_ridge: &RockRidge,
+        extents: Vec<Extent>,
+    ) -> IoResult<(String, InodeNbr)> {
+        let name = self.name(record, rock_ridge)?;
+        let inode_nbr = match self.positions.get(&offset) {
+            Some(&inode_nbr) => inode_nbr,
+            None => {
+                let inode_nbr = self.next_inode_nbr;
+                self.next_inode_nbr = self.next_inode_nbr.checked_add(1).ok_or(Errno::ENOSPC)?;
+                self.positions.try_insert(offset, inode_nbr)?;
+                inode_nbr
+            }
+        };
+        let node = match rock_ridge.child_link {
+            // A deep directory relocated by Rock Ridge, its attributes
+            // are in its own "." record
+            Some(block) => {
+                let content = self.read_blocks(block, 1)?;
+                let current = DirRecord::new(&content)?.ok_or(Errno::EIO)?;
+                let current_rock_ridge = self.rock_ridge(&current, self.susp_skip.unwrap_or(0))?;
+                self.make_node(inode_nbr, &current, &current_rock_ridge, None)?
+            }
+            None => self.make_node(inode_nbr, record, rock_ridge, Some(extents))?,
+        };
+        self.nodes.try_insert(inode_nbr, node)?;
+        Ok((name, inode_nbr))
+    }
+
+    /// Read the whole content of a directory
+    fn read_dir_content(&mut self, inode_nbr: InodeNbr) -> IoResult<(u32, Vec<u8>)> {
+        let node = self.node(inode_nbr)?;
+        if node.stat.kind != FileKind::Directory {
+            return Err(Errno::ENOTDIR);
+        }
+        let extent = node.extents[0];
+        let nbr_blocks = (extent.size + self.block_size - 1) / self.block_size;
+        Ok((extent.block, self.read_blocks(extent.block, nbr_blocks)?))
+    }
+
+    /// Get the informations about the file `inode_nbr`
+    pub fn stat(&self, inode_nbr: u32) -> IoResult<Stat> {
+        Ok(self.node(inode_nbr)?.stat)
+    }
+
+    /// Get the informations about the root directory
+    pub fn root_inode(&self) -> IoResult<Stat> {
+        self.stat(ROOT_INODE)
+    }
+
+    /// Get the names and the informations of the files in the directory `inode_nbr`
+    pub fn lookup_directory(&mut self, inode_nbr: u32) -> IoResult<Vec<(String, Stat)>> {
+        let (first_block, content) = self.read_dir_content(inode_nbr)?;
+        let block_size = self.block_size as usize;
+        let mut res = Vec::new();
+        let mut extents: Vec<Extent> = Vec::new();
+        let mut first_offset = None;
+        let mut pos = 0;
+        while pos < content.len() {
+            // The records do not cross the block boundaries, the end
+            // of a block is padded with zeros
+            let record = match DirRecord::new(&content[pos..(pos / block_size + 1) * block_size])? {
+                Some(record) => record,
+                None => {
+                    pos = (pos / block_size + 1) * block_size;
+                    continue;
+                }
+            };
+            let offset = first_block as u64 * block_size as u64 + pos as u64;
+            pos += record.len();
+            if record.is_current() || record.is_parent() {
+                continue;
+            }
+            extents.try_push(Extent {
+                block: record.get_extent(),
+                size: record.get_size(),
+            })?;
+            // The multi-extent file is named by the first record
+            let offset = *first_offset.get_or_insert(offset);
+            if record.get_flags().contains(FileFlags::MULTI_EXTENT) {
+                continue;
+            }
+            first_offset = None;
+            let extents = core::mem::take(&mut extents);
+            if record.get_flags().contains(FileFlags::ASSOCIATED) {
+                continue;
+            }
+            let rock_ridge = self.rock_ridge(&record, self.susp_skip.unwrap_or(0))?;
+            if rock_ridge.relocated {
+                continue;
+            }
+            let (name, child) = self.load_node(offset, &record, &rock_ridge, extents)?;
+            res.try_push((name, self.stat(child)?))?;
+        }
+        Ok(res)
+    }
+
+    /// Read `buf.len()` bytes of the file `inode_nbr` from `offset`
+    pub fn read(&mut self, inode_nbr: u32, offset: &mut u64, buf: &mut [u8]) -> IoResult<usize> {
+        // Borrow the nodes apart from the disk
+        let node = self.nodes.get(&inode_nbr).ok_or(Errno::ENOENT)?;
+        match node.stat.kind {
+            FileKind::Directory => return Err(Errno::EISDIR),
+            FileKind::Regular => {}
+            _ => return Err(Errno::EINVAL),
+        }
+        let block_size = self.block_size as u64;
+        let mut count = 0;
+        // The position of the current extent in the file
+        let mut extent_start = 0;
+        for extent in node.extents.iter() {
+            let extent_end = extent_start + extent.size as u64;
+            let position = *offset + count as u64;
+            if count == buf.len() {
+                break;
+            }
+            if position < extent_end {
+                let len = min((extent_end - position) as usize, buf.len() - count);
+                let disk_offset = extent.block as u64 * block_size + (position - extent_start);
+                self.disk
+                    .read_exact(disk_offset, &mut buf[count..count + len])?;
+                count += len;
+            }
+            extent_start = extent_end;
+        }
+        *offset += count as u64;
+        Ok(count)
+    }
+
+    /// Get the target of the symbolic link `inode_nbr`
+    pub fn read_link(&self, inode_nbr: u32) -> IoResult<String> {
+        let target = self
+            .node(inode_nbr)?
+            .symlink
+            .as_ref()
+            .ok_or(Errno::EINVAL)?;
+        let mut res = String::new();
+        res.try_reserve(target.len())?;
+        res.push_str(target);
+        Ok(res)
+    }
+}
@@ -0,0 +1,274 @@
+//! This file describe the directory records, their names and dates
+
+use crate::IoResult;
+use libc_binding::Errno;
+
+use alloc::string::String;
+use bitflags::bitflags;
+
+/// Size of the fixed part of a directory record
+pub const RECORD_HEADER_SIZE: usize = 33;
+
+/// Identifiers of the "." and ".." records
+const CURRENT_DIRECTORY_ID: u8 = 0x00;
+const PARENT_DIRECTORY_ID: u8 = 0x01;
+
+bitflags! {
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct FileFlags: u8 {
+        const HIDDEN = 0x01;
+        const DIRECTORY = 0x02;
+        const ASSOCIATED = 0x04;
+        const RECORD = 0x08;
+        const PROTECTION = 0x10;
+        /// The file continues in the next record
+        const MULTI_EXTENT = 0x80;
+    }
+}
+
+/// Read a little endian u16, the both endian fields are read from their LE half
+pub fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Read a little endian u32
+pub fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        buf[offset],
+        buf[offset + 1],
+        buf[offset + 2],
+        buf[offset + 3],
+    ])
+}
+
+/// A directory record borrowed from a directory extent
+#[derive(Debug, Copy, Clone)]
+pub struct DirRecord<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> DirRecord<'a> {
+    /// Parse the record at the beginning of `buf`, None when the
+    /// remaining of the sector is padding
+    pub fn new(buf: &'a [u8]) -> IoResult<Option<Self>> {
+        let len = match buf.first() {
+            None | Some(0) => return Ok(None),
+            Some(&len) => len as usize,
+        };
+        if len < RECORD_HEADER_SIZE
+            || len > buf.len()
+            || RECORD_HEADER_SIZE + buf[32] as usize > len
+        {
+            return Err(Errno::EIO);
+        }
+        Ok(Some(Self { raw: &buf[..len] }))
+    }
+
+    /// Length of the record on the disk
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// First logical block of the data, after the extended attribute record
+    pub fn get_extent(&self) -> u32 {
+        read_u32(self.raw, 2) + self.raw[1] as u32
+    }
+
+    pub fn get_size(&self) -> u32 {
+        read_u32(self.raw, 10)
+    }
+
+    pub fn get_recording_time(&self) -> u32 {
+        short_date_to_unix(&self.raw[18..25])
+    }
+
+    pub fn get_flags(&self) -> FileFlags {
+        FileFlags::from_bits_truncate(self.raw[25])
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.get_flags().contains(FileFlags::DIRECTORY)
+    }
+
+    pub fn get_identifier(&self) -> &'a [u8] {
+        &self.raw[RECORD_HEADER_SIZE..RECORD_HEADER_SIZE + self.raw[32] as usize]
+    }
+
+    /// "." and ".." are found at the beginning of every directory
+    pub fn is_current(&self) -> bool {
+        self.get_identifier() == [CURRENT_DIRECTORY_ID]
+    }
+
+    pub fn is_parent(&self) -> bool {
+        self.get_identifier() == [PARENT_DIRECTORY_ID]
+    }
+
+    /// The system use area, where the SUSP entries are, it follows
+    /// the identifier and its padding byte
+    pub fn get_system_use(&self) -> &'a [u8] {
+        let identifier_len = self.raw[32] as usize;
+        let start = RECORD_HEADER_SIZE + identifier_len + (identifier_len + 1) % 2;
+        self.raw.get(start..).unwrap_or(&[])
+    }
+}
+
+/// Convert an ISO9660 file identifier "NAME.EXT;1" to a lowercase name
+pub fn decode_plain_name(identifier: &[u8]) -> IoResult<String> {
+    let end = identifier
+        .iter()
+        .position(|&c| c == b';')
+        .unwrap_or(identifier.len());
+    let mut identifier = &identifier[..end];
+    if let Some((b'.', head)) = identifier.split_last() {
+        identifier = head;
+    }
+    let mut name = String::new();
+    name.try_reserve(identifier.len())?;
+    for &c in identifier {
+        // The d-characters are ASCII, keep the others lossless enough
+        name.push(match c.is_ascii() {
+            true => c.to_ascii_lowercase() as char,
+            false => char::REPLACEMENT_CHARACTER,
+        });
+    }
+    Ok(name)
+}
+
+/// Convert a Joliet UCS-2 big endian identifier, without its version
+pub fn decode_joliet_name(identifier: &[u8]) -> IoResult<String> {
+    let units = identifier
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]));
+    let mut name = String::new();
+    for c in char::decode_utf16(units) {
+        let c = c.unwrap_or(char::REPLACEMENT_CHARACTER);
+        if c == ';' {
+            break;
+        }
+        name.try_reserve(c.len_utf8())?;
+        name.push(c);
+    }
+    Ok(name)
+}
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Days from civil, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Convert a date and a GMT offset in 15 minutes intervals to a POSIX
+/// time, the dates before 1970 are clamped to the epoch
+fn to_unix(date: [i64; 6], gmt_offset: u8) -> u32 {
+    let [year, month, day, hour, minute, second] = date;
+    let days = days_from_civil(year, month.clamp(1, 12), day.clamp(1, 31));
+    let local = days * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second;
+    let timestamp = local - (gmt_offset as i8) as i64 * 15 * 60;
+    timestamp.clamp(0, u32::MAX as i64) as u32
+}
+
+/// Convert the 7 bytes date of the directory records
+pub fn short_date_to_unix(date: &[u8]) -> u32 {
+    if date[..6].iter().all(|&b| b == 0) {
+        return 0;
+    }
+    to_unix(
+        [
+            1900 + date[0] as i64,
+            date[1] as i64,
+            date[2] as i64,
+            date[3] as i64,
+            date[4] as i64,
+            date[5] as i64,
+        ],
+        date[6],
+    )
+}
+
+/// Convert the 17 bytes "YYYYMMDDHHMMSScc" date of the volume
+/// descriptors, also used by Rock Ridge
+pub fn long_date_to_unix(date: &[u8]) -> u32 {
+    let number = |digits: &[u8]| {
+        digits
+            .iter()
+            .fold(0, |acc, &d| acc * 10 + d.wrapping_sub(b'0').min(9) as i64)
+    };
+    if date[..16].iter().all(|&b| b == b'0' || b == 0) {
+        return 0;
+    }
+    to_unix(
+        [
+            number(&date[0..4]),
+            number(&date[4..6]),
+            number(&date[6..8]),
+            number(&date[8..10]),
+            number(&date[10..12]),
+            number(&date[12..14]),
+        ],
+        date[16],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        // 2019-07-04 13:37:42 UTC
+        let timestamp = 1562247462;
+        assert_eq!(short_date_to_unix(&[119, 7, 4, 13, 37, 42, 0]), timestamp);
+        // The same time at GMT+2
+        assert_eq!(short_date_to_unix(&[119, 7, 4, 15, 37, 42, 8]), timestamp);
+        // The same time at GMT-1
+        assert_eq!(
+            short_date_to_unix(&[119, 7, 4, 12, 37, 42, (-4i8) as u8]),
+            timestamp
+        );
+        assert_eq!(long_date_to_unix(b"2019070413374200\x00"), timestamp);
+        assert_eq!(long_date_to_unix(b"0000000000000000\x00"), 0);
+        assert_eq!(short_date_to_unix(&[0; 7]), 0);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(decode_plain_name(b"README.TXT;1").unwrap(), "readme.txt");
+        assert_eq!(decode_plain_name(b"MAKEFILE.;1").unwrap(), "makefile");
+        assert_eq!(decode_plain_name(b"DIR").unwrap(), "dir");
+        let joliet: alloc::vec::Vec<u8> = "Un nom très long.txt;1"
+            .encode_utf16()
+            .flat_map(|c| c.to_be_bytes())
+            .collect();
+        assert_eq!(decode_joliet_name(&joliet).unwrap(), "Un nom très long.txt");
+    }
+
+    #[test]
+    fn records() {
+        let mut raw = [0u8; 40];
+        raw[0] = 38;
+        raw[2..6].copy_from_slice(&20u32.to_le_bytes());
+        raw[10..14].copy_from_slice(&4242u32.to_le_bytes());
+        raw[25] = FileFlags::DIRECTORY.bits();
+        raw[32] = 4;
+        raw[33..37].copy_from_slice(b"ABCD");
+        raw[37] = 0xaa;
+        let record = DirRecord::new(&raw).unwrap().unwrap();
+        assert_eq!(record.len(), 38);
+        assert_eq!(record.get_extent(), 20);
+        assert_eq!(record.get_size(), 4242);
+        assert!(record.is_directory());
+        assert_eq!(record.get_identifier(), b"ABCD");
+        // The padding byte follows the even length identifier
+        assert_eq!(record.get_system_use(), &[]);
+        assert!(DirRecord::new(&[0; 10]).unwrap().is_none());
+        raw[0] = 20;
+        assert_eq!(DirRecord::new(&raw).unwrap_err(), Errno::EIO);
+    }
+}
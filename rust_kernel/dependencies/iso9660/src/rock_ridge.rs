@@ -0,0 +1,342 @@
+//! This file describe the System Use Sharing Protocol entries and the
+//! Rock Ridge extensions carried by them, see IEEE P1281 and P1282
+
+use crate::record::read_u32;
+use crate::IoResult;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use fallible_collections::vec::FallibleVec;
+
+/// Size of the header of every SUSP entry: signature, length and version
+const ENTRY_HEADER_SIZE: usize = 4;
+/// Check bytes of the SP entry
+const SP_CHECK_BYTES: [u8; 2] = [0xbe, 0xef];
+
+/// Sizes of the Rock Ridge timestamps
+const SHORT_DATE_SIZE: usize = 7;
+const LONG_DATE_SIZE: usize = 17;
+
+bitflags! {
+    /// Flags of the NM entries
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct NameFlags: u8 {
+        const CONTINUE = 0x01;
+        const CURRENT = 0x02;
+        const PARENT = 0x04;
+    }
+
+    /// Flags of the components of the SL entries
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct ComponentFlags: u8 {
+        const CONTINUE = 0x01;
+        const CURRENT = 0x02;
+        const PARENT = 0x04;
+        const ROOT = 0x08;
+    }
+
+    /// Flags of the TF entries, the timestamps are recorded in this order
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct TimeFlags: u8 {
+        const CREATION = 0x01;
+        const MODIFY = 0x02;
+        const ACCESS = 0x04;
+        const ATTRIBUTES = 0x08;
+        const BACKUP = 0x10;
+        const EXPIRATION = 0x20;
+        const EFFECTIVE = 0x40;
+        const LONG_FORM = 0x80;
+    }
+}
+
+/// A CE entry: the system use area continues at this place
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    pub block: u32,
+    pub offset: u32,
+    pub len: u32,
+}
+
+/// The POSIX attributes of a PX entry
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PosixAttributes {
+    pub mode: u32,
+    pub nlink: u32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// What the Rock Ridge entries of a directory record say about the file
+#[derive(Debug, Default)]
+pub struct RockRidge {
+    pub attributes: Option<PosixAttributes>,
+    /// The NM entries, concatenated
+    pub name: Option<Vec<u8>>,
+    /// The SL entries, as a path
+    pub symlink: Option<Vec<u8>>,
+    /// Whether the last symlink component continues in the next SL entry
+    component_continues: bool,
+    /// The (major, minor) of a PN entry
+    pub device: Option<(u32, u32)>,
+    pub modification_time: Option<u32>,
+    pub access_time: Option<u32>,
+    pub attributes_time: Option<u32>,
+    /// The CL entry: the directory was relocated at this block
+    pub child_link: Option<u32>,
+    /// The RE entry: this is a relocated directory, hidden from its
+    /// fake parent
+    pub relocated: bool,
+}
+
+/// Find the SP entry which begins the system use area of the root
+/// ".", it gives the number of bytes to skip in the other areas
+pub fn find_sharing_protocol(area: &[u8]) -> Option<usize> {
+    match area {
+        [b'S', b'P', 7, _, check0, check1, len_skp, ..] if [*check0, *check1] == SP_CHECK_BYTES => {
+            Some(*len_skp as usize)
+        }
+        _ => None,
+    }
+}
+
+impl RockRidge {
+    /// Parse a system use area, return where it continues if it does
+    pub fn parse(&mut self, area: &[u8]) -> IoResult<Option<Continuation>> {
+        let mut continuation = None;
+        let mut pos = 0;
+        while pos + ENTRY_HEADER_SIZE <= area.len() {
+            let len = area[pos + 2] as usize;
+            if len < ENTRY_HEADER_SIZE || pos + len > area.len() {
+                break;
+            }
+            let entry = &area[pos..pos + len];
+            match &entry[..2] {
+                b"ST" => break,
+                b"CE" if len >= 28 => {
+                    continuation = Some(Continuation {
+                        block: read_u32(entry, 4),
+                        offset: read_u32(entry, 12),
+                        len: read_u32(entry, 20),
+                    })
+                }
+                b"PX" if len >= 36 => {
+                    self.attributes = Some(PosixAttributes {
+                        mode: read_u32(entry, 4),
+                        nlink: read_u32(entry, 12),
+                        uid: read_u32(entry, 20),
+                        gid: read_u32(entry, 28),
+                    })
+                }
+                b"PN" if len >= 20 => {
+                    let (high, low) = (read_u32(entry, 4), read_u32(entry, 12));
+                    // Some writers put an old style dev_t in the low part
+                    self.device = Some(match high {
+                        0 => (low >> 8, low & 0xff),
+                        high => (high, low),
+                    })
+                }
+                b"NM" if len >= 5 => self.parse_name(entry)?,
+                b"SL" if len >= 5 => self.parse_symlink(&entry[5..])?,
+                b"TF" if len >= 5 => self.parse_timestamps(entry),
+                b"CL" if len >= 12 => self.child_link = Some(read_u32(entry, 4)),
+                b"RE" => self.relocated = true,
+                _ => {}
+            }
+            pos += len;
+        }
+        Ok(continuation)
+    }
+
+    fn parse_name(&mut self, entry: &[u8]) -> IoResult<()> {
+        let flags = NameFlags::from_bits_truncate(entry[4]);
+        // "." and ".." are not named by NM
+        if flags.intersects(NameFlags::CURRENT | NameFlags::PARENT) {
+            return Ok(());
+        }
+        let name = self.name.get_or_insert_with(Vec::new);
+        name.try_extend_from_slice(&entry[5..])?;
+        Ok(())
+    }
+
+    fn parse_symlink(&mut self, mut components: &[u8]) -> IoResult<()> {
+        let path = self.symlink.get_or_insert_with(Vec::new);
+        while let [flags, len, content @ ..] = components {
+            let len = (*len as usize).min(content.len());
+            let flags = ComponentFlags::from_bits_truncate(*flags);
+            if !self.component_continues && !path.is_empty() && path.last() != Some(&b'/') {
+                path.try_push(b'/')?;
+            }
+            if flags.contains(ComponentFlags::ROOT) {
+                path.try_push(b'/')?;
+            } else if flags.contains(ComponentFlags::CURRENT) {
+                path.try_push(b'.')?;
+            } else if flags.contains(ComponentFlags::PARENT) {
+                path.try_extend_from_slice(b"..")?;
+            } else {
+                path.try_extend_from_slice(&content[..len])?;
+            }
+            self.component_continues = flags.contains(ComponentFlags::CONTINUE);
+            components = &content[len..];
+        }
+        Ok(())
+    }
+
+    fn parse_timestamps(&mut self, entry: &[u8]) {
+        let flags = TimeFlags::from_bits_truncate(entry[4]);
+        let (date_size, to_unix): (usize, fn(&[u8]) -> u32) =
+            match flags.contains(TimeFlags::LONG_FORM) {
+                true => (LONG_DATE_SIZE, crate::record::long_date_to_unix),
+                false => (SHORT_DATE_SIZE, crate::record::short_date_to_unix),
+            };
+        let mut dates = entry[5..].chunks_exact(date_size);
+        for flag in [
+            TimeFlags::CREATION,
+            TimeFlags::MODIFY,
+            TimeFlags::ACCESS,
+            TimeFlags::ATTRIBUTES,
+        ] {
+            if !flags.contains(flag) {
+                continue;
+            }
+            let date = match dates.next() {
+                Some(date) => to_unix(date),
+                None => return,
+            };
+            match flag {
+                TimeFlags::MODIFY => self.modification_time = Some(date),
+                TimeFlags::ACCESS => self.access_time = Some(date),
+                TimeFlags::ATTRIBUTES => self.attributes_time = Some(date),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Decode the bytes of a Rock Ridge name, which has no defined
+/// encoding, the invalid UTF-8 sequences are replaced
+pub fn decode_bytes(mut bytes: &[u8]) -> IoResult<String> {
+    let mut s = String::new();
+    s.try_reserve(bytes.len())?;
+    loop {
+        match core::str::from_utf8(bytes) {
+            Ok(valid) => {
+                s.try_reserve(valid.len())?;
+                s.push_str(valid);
+                return Ok(s);
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                s.try_reserve(valid.len() + char::REPLACEMENT_CHARACTER.len_utf8())?;
+                // valid_up_to() guarantees this part is UTF-8
+                s.push_str(core::str::from_utf8(valid).unwrap_or_default());
+                s.push(char::REPLACEMENT_CHARACTER);
+                bytes = &rest[e.error_len().unwrap_or(rest.len())..];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(signature: &[u8; 2], data: &[u8]) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.extend_from_slice(signature);
+        entry.push((data.len() + ENTRY_HEADER_SIZE) as u8);
+        entry.push(1);
+        entry.extend_from_slice(data);
+        entry
+    }
+
+    fn both_endian(n: u32) -> [u8; 8] {
+        let mut b = [0; 8];
+        b[..4].copy_from_slice(&n.to_le_bytes());
+        b[4..].copy_from_slice(&n.to_be_bytes());
+        b
+    }
+
+    #[test]
+    fn sharing_protocol() {
+        assert_eq!(
+            find_sharing_protocol(&entry(b"SP", &[0xbe, 0xef, 0])),
+            Some(0)
+        );
+        assert_eq!(find_sharing_protocol(&entry(b"SP", &[0xbe, 0xee, 0])), None);
+        assert_eq!(find_sharing_protocol(&[]), None);
+    }
+
+    #[test]
+    fn attributes_and_names() {
+        let mut px = Vec::new();
+        for n in [0o100755, 1, 1000, 100, 42] {
+            px.extend_from_slice(&both_endian(n));
+        }
+        let mut area = entry(b"PX", &px);
+        area.extend(entry(b"NM", b"\x01a very "));
+        area.extend(entry(b"NM", b"\x00long name"));
+        area.extend(entry(b"NM", b"\x02"));
+        let mut ce = Vec::new();
+        for n in [20, 100, 50] {
+            ce.extend_from_slice(&both_endian(n));
+        }
+        area.extend(entry(b"CE", &ce));
+        let mut rr = RockRidge::default();
+        let continuation = rr.parse(&area).unwrap();
+        assert_eq!(
+            continuation,
+            Some(Continuation {
+                block: 20,
+                offset: 100,
+                len: 50
+            })
+        );
+        assert_eq!(
+            rr.attributes,
+            Some(PosixAttributes {
+                mode: 0o100755,
+                nlink: 1,
+                uid: 1000,
+                gid: 100
+            })
+        );
+        assert_eq!(rr.name.as_deref(), Some(&b"a very long name"[..]));
+    }
+
+    #[test]
+    fn symlinks() {
+        let mut rr = RockRidge::default();
+        // "/usr/lib/../share" split in two SL entries, "share" in two components
+        rr.parse(&entry(b"SL", b"\x01\x08\x00\x00\x03usr\x00\x03lib\x04\x00"))
+            .unwrap();
+        rr.parse(&entry(b"SL", b"\x00\x01\x02sh\x00\x03are"))
+            .unwrap();
+        assert_eq!(rr.symlink.as_deref(), Some(&b"/usr/lib/../share"[..]));
+
+        let mut rr = RockRidge::default();
+        rr.parse(&entry(b"SL", b"\x00\x02\x00\x00\x04file"))
+            .unwrap();
+        assert_eq!(rr.symlink.as_deref(), Some(&b"./file"[..]));
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut rr = RockRidge::default();
+        let mut tf = Vec::new();
+        tf.push((TimeFlags::MODIFY | TimeFlags::ACCESS | TimeFlags::ATTRIBUTES).bits());
+        tf.extend_from_slice(&[119, 7, 4, 13, 37, 42, 0]);
+        tf.extend_from_slice(&[119, 7, 4, 13, 37, 43, 0]);
+        tf.extend_from_slice(&[119, 7, 4, 13, 37, 44, 0]);
+        rr.parse(&entry(b"TF", &tf)).unwrap();
+        assert_eq!(rr.modification_time, Some(1562247462));
+        assert_eq!(rr.access_time, Some(1562247463));
+        assert_eq!(rr.attributes_time, Some(1562247464));
+    }
+
+    #[test]
+    fn lossy_names() {
+        assert_eq!(decode_bytes(b"caf\xc3\xa9").unwrap(), "café");
+        assert_eq!(decode_bytes(b"caf\xe9!").unwrap(), "caf\u{fffd}!");
+    }
+}
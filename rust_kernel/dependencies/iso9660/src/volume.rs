@@ -0,0 +1,92 @@
+//! This file describe the volume descriptors, the first of them is
+//! at the sector 16 of the volume
+
+use crate::record::{read_u16, read_u32};
+
+/// Size of a sector, and of a volume descriptor
+pub const SECTOR_SIZE: u64 = 2048;
+/// The 16 first sectors are the system area, unused by ISO9660
+pub const FIRST_DESCRIPTOR_SECTOR: u64 = 16;
+
+const STANDARD_IDENTIFIER: &[u8; 5] = b"CD001";
+
+/// Escape sequences of the UCS-2 levels 1, 2 and 3 of Joliet
+const JOLIET_ESCAPE_SEQUENCES: [&[u8; 3]; 3] = [b"%/@", b"%/C", b"%/E"];
+
+/// Offsets of the fields of the primary and supplementary descriptors
+const VOLUME_IDENTIFIER_OFFSET: usize = 40;
+const VOLUME_SPACE_SIZE_OFFSET: usize = 80;
+const ESCAPE_SEQUENCES_OFFSET: usize = 88;
+const LOGICAL_BLOCK_SIZE_OFFSET: usize = 128;
+const ROOT_RECORD_OFFSET: usize = 156;
+pub const ROOT_RECORD_SIZE: usize = 34;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DescriptorType {
+    BootRecord,
+    Primary,
+    Supplementary,
+    Partition,
+    Terminator,
+    Unknown,
+}
+
+impl From<u8> for DescriptorType {
+    fn from(t: u8) -> Self {
+        use DescriptorType::*;
+        match t {
+            0 => BootRecord,
+            1 => Primary,
+            2 => Supplementary,
+            3 => Partition,
+            255 => Terminator,
+            _ => Unknown,
+        }
+    }
+}
+
+/// The fields we need from a primary or supplementary volume descriptor
+#[derive(Debug, Copy, Clone)]
+pub struct VolumeDescriptor {
+    pub descriptor_type: DescriptorType,
+    /// Number of logical blocks of the volume
+    pub volume_space_size: u32,
+    /// Size of a logical block, 2048 in practice
+    pub logical_block_size: u16,
+    /// Whether the names of this hierarchy are Joliet UCS-2 ones
+    pub joliet: bool,
+    pub volume_identifier: [u8; 32],
+    pub root_record: [u8; ROOT_RECORD_SIZE],
+}
+
+impl VolumeDescriptor {
+    /// Parse a volume descriptor sector, None if it is not an ISO9660 one
+    pub fn new(sector: &[u8]) -> Option<Self> {
+        if &sector[1..6] != STANDARD_IDENTIFIER {
+            return None;
+        }
+        let descriptor_type = DescriptorType::from(sector[0]);
+        let mut volume_identifier = [0; 32];
+        volume_identifier.copy_from_slice(&sector[VOLUME_IDENTIFIER_OFFSET..][..32]);
+        let mut root_record = [0; ROOT_RECORD_SIZE];
+        root_record.copy_from_slice(&sector[ROOT_RECORD_OFFSET..][..ROOT_RECORD_SIZE]);
+        let escape_sequences = &sector[ESCAPE_SEQUENCES_OFFSET..][..32];
+        Some(Self {
+            descriptor_type,
+            volume_space_size: read_u32(sector, VOLUME_SPACE_SIZE_OFFSET),
+            logical_block_size: read_u16(sector, LOGICAL_BLOCK_SIZE_OFFSET),
+            joliet: descriptor_type == DescriptorType::Supplementary
+                && JOLIET_ESCAPE_SEQUENCES
+                    .iter()
+                    .any(|seq| escape_sequences.starts_with(&seq[..])),
+            volume_identifier,
+            root_record,
+        })
+    }
+
+    /// Check the fields we rely on
+    pub fn is_valid(&self) -> bool {
+        self.logical_block_size.is_power_of_two()
+            && (512..=SECTOR_SIZE as u16).contains(&self.logical_block_size)
+    }
+}
@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+//! The tests need genisoimage to prepare the images, no mount needed
+
+use iso9660::{DiskIo, IoResult, Iso9660Filesystem, Stat, ROOT_INODE};
+use libc_binding::Errno;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::process::{Command, Stdio};
+
+#[derive(Debug)]
+pub struct StdDiskIo {
+    pub f: File,
+}
+
+impl DiskIo for StdDiskIo {
+    fn flush(&mut self) -> IoResult<()> {
+        self.f.flush().map_err(|_| Errno::EIO)
+    }
+    fn write_buffer(&mut self, _offset: u64, _buf: &[u8]) -> IoResult<u64> {
+        Err(Errno::EROFS)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        self.f
+            .seek(SeekFrom::Start(offset))
+            .map_err(|_| Errno::EIO)?;
+        self.f.read(buf).map_err(|_| Errno::EIO).map(|x| x as u64)
+    }
+}
+
+pub fn exec_shell(cmd: &str) -> bool {
+    let exit_code = Command::new("bash")
+        .args(&["-c"])
+        .args(&[cmd])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+    if !exit_code.success() {
+        eprintln!("command failed: {}", cmd);
+    }
+    exit_code.success()
+}
+
+/// Every test works on its own tree and image so they can run in
+/// parallel, the tree is `name`.d
+pub fn create_tree(name: &str) -> String {
+    let tree = format!("{}.d", name);
+    exec_shell(&format!("rm -rf {}", tree));
+    std::fs::create_dir(&tree).unwrap();
+    tree
+}
+
+/// Make the image `name` of the tree with the genisoimage `options`
+/// (-R for Rock Ridge, -J for Joliet)
+pub fn create_iso(name: &str, options: &str) {
+    exec_shell(&format!("rm -f {}", name));
+    assert!(exec_shell(&format!(
+        "genisoimage -quiet {} -V TESTVOLUME -o {} {}.d",
+        options, name, name
+    )));
+}
+
+pub fn new_iso(name: &str) -> Iso9660Filesystem {
+    let f = File::open(name).expect("open filesystem failed");
+    Iso9660Filesystem::new(Box::new(StdDiskIo { f })).expect("init iso9660 filesystem failed")
+}
+
+/// Walk `path` from the root directory
+pub fn find(iso: &mut Iso9660Filesystem, path: &str) -> IoResult<Stat> {
+    let mut stat = iso.stat(ROOT_INODE)?;
+    for component in path.split('/').filter(|s| !s.is_empty()) {
+        stat = iso
+            .lookup_directory(stat.inode_nbr)?
+            .into_iter()
+            .find(|(name, _)| name == component)
+            .ok_or(Errno::ENOENT)?
+            .1;
+    }
+    Ok(stat)
+}
+
+pub fn read_all(iso: &mut Iso9660Filesystem, inode_nbr: u32) -> Vec<u8> {
+    let mut content = vec![0; iso.stat(inode_nbr).unwrap().size as usize];
+    let mut offset = 0;
+    let mut total = 0;
+    while total < content.len() {
+        let n = iso
+            .read(inode_nbr, &mut offset, &mut content[total..])
+            .expect("read on filesystem failed");
+        assert_ne!(n, 0);
+        total += n;
+    }
+    content
+}
+
+pub fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
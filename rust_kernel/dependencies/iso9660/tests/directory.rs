@@ -0,0 +1,71 @@
+mod common;
+use common::*;
+use iso9660::FileKind;
+use libc_binding::Errno;
+
+#[test]
+fn large_directory() {
+    let disk = "disk_large_directory";
+    let tree = create_tree(disk);
+    // Enough records to spread the directory over several blocks
+    for i in 0..300 {
+        std::fs::write(format!("{}/file number {}", tree, i), format!("{}", i)).unwrap();
+    }
+    create_iso(disk, "-R");
+    let mut iso = new_iso(disk);
+    let root = iso.root_inode().unwrap();
+    assert!(root.size > 2048);
+    let entries = iso.lookup_directory(root.inode_nbr).unwrap();
+    assert_eq!(entries.len(), 300);
+    for i in 0..300 {
+        let stat = find(&mut iso, &format!("file number {}", i)).expect("lookup failed");
+        assert_eq!(
+            read_all(&mut iso, stat.inode_nbr),
+            format!("{}", i).as_bytes()
+        );
+    }
+}
+
+#[test]
+fn deep_directories() {
+    let disk = "disk_deep_directories";
+    let tree = create_tree(disk);
+    // ISO9660 limits the depth to 8, Rock Ridge relocates the deeper ones
+    let path = "a/b/c/d/e/f/g/h/i/j";
+    std::fs::create_dir_all(format!("{}/{}", tree, path)).unwrap();
+    std::fs::write(format!("{}/{}/deep", tree, path), b"deep").unwrap();
+    create_iso(disk, "-R");
+    let mut iso = new_iso(disk);
+    let stat = find(&mut iso, &format!("{}/deep", path)).expect("lookup failed");
+    assert_eq!(read_all(&mut iso, stat.inode_nbr), b"deep");
+    let stat = find(&mut iso, "a/b/c/d/e/f/g/h/i").expect("lookup failed");
+    assert_eq!(stat.kind, FileKind::Directory);
+    // The relocated directories are hidden where they really are
+    let root = iso.root_inode().unwrap();
+    for (name, stat) in iso.lookup_directory(root.inode_nbr).unwrap() {
+        if name != "a" {
+            assert!(iso.lookup_directory(stat.inode_nbr).unwrap().is_empty());
+        }
+    }
+}
+
+#[test]
+fn stable_inode_numbers() {
+    let disk = "disk_stable_inode_numbers";
+    let tree = create_tree(disk);
+    std::fs::create_dir(format!("{}/dir", tree)).unwrap();
+    std::fs::write(format!("{}/dir/file", tree), b"x").unwrap();
+    create_iso(disk, "-R");
+    let mut iso = new_iso(disk);
+    let first = find(&mut iso, "dir/file").unwrap();
+    let second = find(&mut iso, "dir/file").unwrap();
+    assert_eq!(first.inode_nbr, second.inode_nbr);
+    assert_eq!(find(&mut iso, "dir/nothing").unwrap_err(), Errno::ENOENT);
+    let dir = find(&mut iso, "dir").unwrap();
+    let mut offset = 0;
+    assert_eq!(
+        iso.read(dir.inode_nbr, &mut offset, &mut [0; 10])
+            .unwrap_err(),
+        Errno::EISDIR
+    );
+}
@@ -0,0 +1,97 @@
+mod common;
+use common::*;
+use iso9660::FileKind;
+use std::os::unix::fs::{symlink, PermissionsExt};
+
+#[test]
+fn read_plain() {
+    let disk = "disk_read_plain";
+    let tree = create_tree(disk);
+    std::fs::write(format!("{}/README.TXT", tree), b"hello").unwrap();
+    create_iso(disk, "");
+    let mut iso = new_iso(disk);
+    assert!(!iso.has_rock_ridge() && !iso.has_joliet());
+    assert_eq!(iso.get_volume_identifier(), b"TESTVOLUME");
+    let stat = find(&mut iso, "readme.txt").expect("lookup failed");
+    assert_eq!(stat.kind, FileKind::Regular);
+    assert_eq!(stat.mode, 0o555);
+    assert_eq!(read_all(&mut iso, stat.inode_nbr), b"hello");
+}
+
+#[test]
+fn read_joliet() {
+    let disk = "disk_read_joliet";
+    let tree = create_tree(disk);
+    std::fs::create_dir(format!("{}/A directory", tree)).unwrap();
+    let content = pattern(300 * 1024);
+    std::fs::write(
+        format!("{}/A directory/Un fichier très long.data", tree),
+        &content,
+    )
+    .unwrap();
+    create_iso(disk, "-J");
+    let mut iso = new_iso(disk);
+    assert!(!iso.has_rock_ridge() && iso.has_joliet());
+    let stat = find(&mut iso, "A directory/Un fichier très long.data").expect("lookup failed");
+    assert_eq!(read_all(&mut iso, stat.inode_nbr), content);
+}
+
+#[test]
+fn read_rock_ridge() {
+    let disk = "disk_read_rock_ridge";
+    let tree = create_tree(disk);
+    std::fs::create_dir(format!("{}/bin", tree)).unwrap();
+    let content = pattern(100 * 1024);
+    let program = format!("{}/bin/a_rather_long_program_name", tree);
+    std::fs::write(&program, &content).unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o4751)).unwrap();
+    symlink(
+        "../bin/a_rather_long_program_name",
+        format!("{}/link", tree),
+    )
+    .unwrap();
+    symlink("/usr/lib/./libc.so", format!("{}/absolute", tree)).unwrap();
+    create_iso(disk, "-R -J");
+    let mut iso = new_iso(disk);
+    // Rock Ridge wins over Joliet
+    assert!(iso.has_rock_ridge() && !iso.has_joliet());
+
+    let stat = find(&mut iso, "bin/a_rather_long_program_name").expect("lookup failed");
+    assert_eq!(stat.kind, FileKind::Regular);
+    assert_eq!(stat.mode, 0o4751);
+    assert_eq!(read_all(&mut iso, stat.inode_nbr), content);
+
+    let stat = find(&mut iso, "link").expect("lookup failed");
+    assert_eq!(stat.kind, FileKind::Symlink);
+    assert_eq!(
+        iso.read_link(stat.inode_nbr).unwrap(),
+        "../bin/a_rather_long_program_name"
+    );
+    assert_eq!(stat.size, "../bin/a_rather_long_program_name".len() as u64);
+    let stat = find(&mut iso, "absolute").expect("lookup failed");
+    assert_eq!(iso.read_link(stat.inode_nbr).unwrap(), "/usr/lib/./libc.so");
+
+    let stat = find(&mut iso, "bin").expect("lookup failed");
+    assert_eq!(stat.kind, FileKind::Directory);
+}
+
+#[test]
+fn read_at_offset() {
+    let disk = "disk_read_at_offset";
+    let tree = create_tree(disk);
+    let content = pattern(100 * 1024);
+    std::fs::write(format!("{}/file", tree), &content).unwrap();
+    create_iso(disk, "-R");
+    let mut iso = new_iso(disk);
+    let stat = find(&mut iso, "file").expect("lookup failed");
+    let mut buf = vec![0; 1000];
+    for &start in [0, 511, 2047, 70000, 102000].iter() {
+        let mut offset = start as u64;
+        let n = iso.read(stat.inode_nbr, &mut offset, &mut buf).unwrap();
+        let end = (start + 1000).min(content.len());
+        assert_eq!(&buf[..n], &content[start..end]);
+        assert_eq!(offset, end as u64);
+    }
+    let mut offset = 200000;
+    assert_eq!(iso.read(stat.inode_nbr, &mut offset, &mut buf).unwrap(), 0);
+}
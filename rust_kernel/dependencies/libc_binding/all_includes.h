@@ -1,8 +1,13 @@
 #include <assert.h>
-/* #include <sys/file.h> */
+#include <sys/auxv.h>
+#include <sys/personality.h>
+#include <sys/file.h>
+#include <sys/inotify.h>
 
 #include <sys/ioctl.h>
+#include <linux/loop.h>
 #include <sys/mman.h>
+#include <sys/mount.h>
 #include <sys/param.h>
 #include <sys/resource.h>
 #include <sys/select.h>
@@ -13,6 +18,8 @@
 #include <sys/types.h>
 #include <sys/wait.h>
 #include <sys/statfs.h>
+#include <sys/xattr.h>
+#include <sys/uio.h>
 
 #include <assert.h>
 #include <ctype.h>
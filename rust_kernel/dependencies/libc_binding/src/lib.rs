@@ -27,7 +27,7 @@ use core::mem::transmute;
 impl TryFrom<u32> for Signum {
     type Error = InvalidSignum;
     fn try_from(n: u32) -> Result<Self, Self::Error> {
-        if n >= 32 {
+        if n > SIGRTMAX {
             return Err(InvalidSignum);
         } else {
             Ok(unsafe { transmute(n) })
@@ -209,6 +209,15 @@ impl Signum {
 impl Signum {
     pub const SIGUNUSED: Signum = Signum::SIGSYS;
 }
+impl Signum {
+    pub const SIGRTMIN: Signum = Signum::SIGRT0;
+    pub const SIGRTMAX: Signum = Signum::SIGRT31;
+
+    /// Is it a real-time signal, which is queued with its value
+    pub fn is_realtime(self) -> bool {
+        self as u32 >= SIGRTMIN
+    }
+}
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -245,6 +254,38 @@ pub enum Signum {
     SIGIO = SIGIO,
     SIGPWR = SIGPWR,
     SIGSYS = SIGSYS,
+    SIGRT0 = SIGRTMIN,
+    SIGRT1 = SIGRTMIN + 1,
+    SIGRT2 = SIGRTMIN + 2,
+    SIGRT3 = SIGRTMIN + 3,
+    SIGRT4 = SIGRTMIN + 4,
+    SIGRT5 = SIGRTMIN + 5,
+    SIGRT6 = SIGRTMIN + 6,
+    SIGRT7 = SIGRTMIN + 7,
+    SIGRT8 = SIGRTMIN + 8,
+    SIGRT9 = SIGRTMIN + 9,
+    SIGRT10 = SIGRTMIN + 10,
+    SIGRT11 = SIGRTMIN + 11,
+    SIGRT12 = SIGRTMIN + 12,
+    SIGRT13 = SIGRTMIN + 13,
+    SIGRT14 = SIGRTMIN + 14,
+    SIGRT15 = SIGRTMIN + 15,
+    SIGRT16 = SIGRTMIN + 16,
+    SIGRT17 = SIGRTMIN + 17,
+    SIGRT18 = SIGRTMIN + 18,
+    SIGRT19 = SIGRTMIN + 19,
+    SIGRT20 = SIGRTMIN + 20,
+    SIGRT21 = SIGRTMIN + 21,
+    SIGRT22 = SIGRTMIN + 22,
+    SIGRT23 = SIGRTMIN + 23,
+    SIGRT24 = SIGRTMIN + 24,
+    SIGRT25 = SIGRTMIN + 25,
+    SIGRT26 = SIGRTMIN + 26,
+    SIGRT27 = SIGRTMIN + 27,
+    SIGRT28 = SIGRTMIN + 28,
+    SIGRT29 = SIGRTMIN + 29,
+    SIGRT30 = SIGRTMIN + 30,
+    SIGRT31 = SIGRTMIN + 31,
 }
 
 #[repr(u32)]
@@ -263,6 +304,26 @@ pub enum FcntlCmd {
     F_SETOWN = F_SETOWN,
 }
 
+impl TryFrom<u32> for FcntlCmd {
+    type Error = Errno;
+    fn try_from(n: u32) -> Result<Self, Self::Error> {
+        Ok(match n {
+            F_DUPFD => FcntlCmd::F_DUPFD,
+            F_DUPFD_CLOEXEC => FcntlCmd::F_DUPFD_CLOEXEC,
+            F_GETFD => FcntlCmd::F_GETFD,
+            F_SETFD => FcntlCmd::F_SETFD,
+            F_GETFL => FcntlCmd::F_GETFL,
+            F_SETFL => FcntlCmd::F_SETFL,
+            F_GETLK => FcntlCmd::F_GETLK,
+            F_SETLK => FcntlCmd::F_SETLK,
+            F_SETLKW => FcntlCmd::F_SETLKW,
+            F_GETOWN => FcntlCmd::F_GETOWN,
+            F_SETOWN => FcntlCmd::F_SETOWN,
+            _ => Err(Errno::EINVAL)?,
+        })
+    }
+}
+
 //// The number of I/O operations that can be specified in a list I/O call.
 // pub const _POSIX_AIO_LISTIO_MAX: usize = 2;
 // const_assert!(AIO_LISTIO_MAX >= _POSIX_AIO_LISTIO_MAX);
@@ -697,6 +758,10 @@ impl FileType {
         *self & Self::S_IFMT == Self::CHARACTER_DEVICE
     }
 
+    pub fn is_block_device(&self) -> bool {
+        *self & Self::S_IFMT == Self::BLOCK_DEVICE
+    }
+
     pub fn is_fifo(&self) -> bool {
         *self & Self::S_IFMT == Self::FIFO
     }
@@ -804,6 +869,121 @@ impl From<OpenFlags> for Amode {
     }
 }
 
+bitflags! {
+    #[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct XattrFlags: u32 {
+        /// Fail with EEXIST if the extended attribute already exists.
+        const XATTR_CREATE = XATTR_CREATE;
+
+        /// Fail with ENODATA if the extended attribute does not exist.
+        const XATTR_REPLACE = XATTR_REPLACE;
+    }
+}
+
+bitflags! {
+    #[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct MountFlags: u32 {
+        /// Mount read-only.
+        const MS_RDONLY = MS_RDONLY;
+
+        /// Ignore the set-user-ID and set-group-ID bits on execve.
+        const MS_NOSUID = MS_NOSUID;
+
+        /// Disallow access to the device files.
+        const MS_NODEV = MS_NODEV;
+
+        /// Disallow program execution.
+        const MS_NOEXEC = MS_NOEXEC;
+
+        /// Alter the flags of an existing mount.
+        const MS_REMOUNT = MS_REMOUNT;
+
+        /// Make a file or a directory subtree visible elsewhere.
+        const MS_BIND = MS_BIND;
+    }
+}
+
+impl MountFlags {
+    /// The flags which are kept by a mount, the other ones are only
+    /// requests to mount()
+    pub const PER_MOUNT: Self =
+        Self::from_bits_truncate(MS_RDONLY | MS_NOSUID | MS_NODEV | MS_NOEXEC);
+}
+
+bitflags! {
+    #[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
+    pub struct InotifyMask: u32 {
+        /// File was accessed.
+        const IN_ACCESS = IN_ACCESS;
+
+        /// File was modified.
+        const IN_MODIFY = IN_MODIFY;
+
+        /// Metadata changed: permissions, timestamps, extended
+        /// attributes, link count or ownership.
+        const IN_ATTRIB = IN_ATTRIB;
+
+        /// File opened for writing was closed.
+        const IN_CLOSE_WRITE = IN_CLOSE_WRITE;
+
+        /// File not opened for writing was closed.
+        const IN_CLOSE_NOWRITE = IN_CLOSE_NOWRITE;
+
+        /// File was opened.
+        const IN_OPEN = IN_OPEN;
+
+        /// Generated for the directory containing the old filename
+        /// when a file is renamed.
+        const IN_MOVED_FROM = IN_MOVED_FROM;
+
+        /// Generated for the directory containing the new filename
+        /// when a file is renamed.
+        const IN_MOVED_TO = IN_MOVED_TO;
+
+        /// File or directory created in the watched directory.
+        const IN_CREATE = IN_CREATE;
+
+        /// File or directory deleted from the watched directory.
+        const IN_DELETE = IN_DELETE;
+
+        /// Watched file or directory was itself deleted.
+        const IN_DELETE_SELF = IN_DELETE_SELF;
+
+        /// Watched file or directory was itself moved.
+        const IN_MOVE_SELF = IN_MOVE_SELF;
+
+        /// Filesystem containing the watched object was unmounted.
+        const IN_UNMOUNT = IN_UNMOUNT;
+
+        /// Event queue overflowed.
+        const IN_Q_OVERFLOW = IN_Q_OVERFLOW;
+
+        /// Watch was removed.
+        const IN_IGNORED = IN_IGNORED;
+
+        /// Only watch the pathname if it is a directory.
+        const IN_ONLYDIR = IN_ONLYDIR;
+
+        /// Do not dereference the pathname if it is a symbolic link.
+        const IN_DONT_FOLLOW = IN_DONT_FOLLOW;
+
+        /// Add the events to the mask of an existing watch instead of
+        /// replacing it.
+        const IN_MASK_ADD = IN_MASK_ADD;
+
+        /// Subject of this event is a directory.
+        const IN_ISDIR = IN_ISDIR;
+
+        /// Remove the watch after its first event.
+        const IN_ONESHOT = IN_ONESHOT;
+    }
+}
+
+impl InotifyMask {
+    /// The events which can be watched
+    pub const ALL_EVENTS: Self = Self::from_bits_truncate(IN_ALL_EVENTS);
+}
+
 /// Also known as File Classes in POSIX-2018.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PermissionClass {
@@ -891,20 +1071,32 @@ impl TryFrom<u32> for ShutDownOption {
 #[repr(u32)]
 #[derive(Debug, PartialEq)]
 pub enum IoctlCmd {
+    TIOCSCTTY = TIOCSCTTY,
     TIOCGWINSZ = TIOCGWINSZ,
+    FIONREAD = FIONREAD,
+    TIOCNOTTY = TIOCNOTTY,
     RAW_SCANCODE_MODE = RAW_SCANCODE_MODE,
     REFRESH_SCREEN = REFRESH_SCREEN,
     GET_FRAME_BUFFER_PTR = GET_FRAME_BUFFER_PTR,
+    LOOP_SET_FD = LOOP_SET_FD,
+    LOOP_CLR_FD = LOOP_CLR_FD,
+    LOOP_GET_STATUS = LOOP_GET_STATUS,
 }
 
 impl TryFrom<u32> for IoctlCmd {
     type Error = Errno;
     fn try_from(n: u32) -> Result<Self, Self::Error> {
         Ok(match n {
+            TIOCSCTTY => IoctlCmd::TIOCSCTTY,
             TIOCGWINSZ => IoctlCmd::TIOCGWINSZ,
+            FIONREAD => IoctlCmd::FIONREAD,
+            TIOCNOTTY => IoctlCmd::TIOCNOTTY,
             RAW_SCANCODE_MODE => IoctlCmd::RAW_SCANCODE_MODE,
             REFRESH_SCREEN => IoctlCmd::REFRESH_SCREEN,
             GET_FRAME_BUFFER_PTR => IoctlCmd::GET_FRAME_BUFFER_PTR,
+            LOOP_SET_FD => IoctlCmd::LOOP_SET_FD,
+            LOOP_CLR_FD => IoctlCmd::LOOP_CLR_FD,
+            LOOP_GET_STATUS => IoctlCmd::LOOP_GET_STATUS,
             _ => Err(Errno::EINVAL)?,
         })
     }
@@ -40,6 +40,8 @@ impl Partition {
 enum PartitionType {
     LinuxExtendedPartition,
     Dos12bitsFat,
+    Dos16bitsFat,
+    Fat32,
     Empty,
     Unknown,
 }
@@ -137,6 +139,10 @@ impl From<u8> for PartitionType {
         match part_number {
             0x83 => LinuxExtendedPartition,
             0x01 => Dos12bitsFat,
+            // Small FAT16, FAT16B and FAT16 with LBA addressing
+            0x04 | 0x06 | 0x0e => Dos16bitsFat,
+            // CHS and LBA addressing
+            0x0b | 0x0c => Fat32,
             0x00 => Empty,
             _ => Unknown,
         }
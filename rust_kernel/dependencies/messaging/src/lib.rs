@@ -69,6 +69,10 @@ pub enum MessageTo {
     Connecter {
         uid_file_op: usize,
     },
+    /// VFS: Adressed to the threads waiting for a lock of a file
+    Locker {
+        uid: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -24,8 +24,7 @@ mod tty;
 pub use tty::{BufferedTty, Scroll, Tty, WriteMode};
 
 mod line_discipline;
-use line_discipline::LineDiscipline;
-pub use line_discipline::ReadResult;
+pub use line_discipline::{JobControl, LineDiscipline, ReadResult, TtyAccess};
 
 pub mod log;
 
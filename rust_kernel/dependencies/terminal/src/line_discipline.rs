@@ -9,9 +9,6 @@ use libc_binding::{termios, Pid, Signum, ECHO, ICANON, ISIG, TOSTOP};
 use libc_binding::{VEOF, VERASE, VINTR, VKILL, VQUIT, VSUSP};
 use messaging::{MessageTo, ProcessGroupMessage};
 
-// extern "C" {
-//     fn get_current_pgid() -> Pid;
-// }
 // use libc_binding::{VEOL, VMIN, VSTART, VSTOP, VTIME};
 
 #[derive(Debug, Clone)]
@@ -20,6 +17,8 @@ pub struct LineDiscipline {
     termios: termios,
     read_buffer: ArrayVec<u8, 4096>,
     foreground_process_group: Pid,
+    /// The session of which the tty is the controlling terminal
+    session: Option<Pid>,
     end_of_file_set: bool,
     /// raw mode doesn't transform scancode in utf8
     is_raw_mode: bool,
@@ -34,6 +33,40 @@ pub enum ReadResult {
     NonBlocking(usize),
 }
 
+/// An access of a process to its controlling terminal, which is
+/// subject to job control when the process is not in the foreground
+/// process group
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TtyAccess {
+    Read,
+    Write,
+    /// tcsetpgrp(), tcsetattr() and the other requests which change
+    /// the terminal
+    Control,
+}
+
+impl TtyAccess {
+    /// The signal sent to a background process group for this access
+    pub fn signal(self) -> Signum {
+        match self {
+            TtyAccess::Read => Signum::SIGTTIN,
+            TtyAccess::Write | TtyAccess::Control => Signum::SIGTTOU,
+        }
+    }
+}
+
+/// The outcome of the job control checks of an access
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JobControl {
+    /// The access is done
+    Granted,
+    /// The process group of the process must be sent the signal, the
+    /// access is done once it is in the foreground
+    Signal(Signum),
+    /// The access fails with EIO
+    Denied,
+}
+
 impl LineDiscipline {
     pub fn new(tty: BufferedTty) -> Self {
         Self {
@@ -41,7 +74,7 @@ impl LineDiscipline {
                 c_iflag: 0,
                 c_oflag: 0,
                 c_cflag: 0,
-                c_lflag: (ECHO | ICANON | ISIG | TOSTOP),
+                c_lflag: (ECHO | ICANON | ISIG),
                 c_cc: [
                     /*VEOF  */ KeySymb::Control_d as u32,
                     /*VEOL  */ KeySymb::Return as u32,
@@ -59,6 +92,7 @@ impl LineDiscipline {
             tty,
             read_buffer: ArrayVec::new(),
             foreground_process_group: 0,
+            session: None,
             end_of_file_set: false,
             is_raw_mode: false,
         }
@@ -220,28 +254,6 @@ impl LineDiscipline {
     /// return the number of bytes readen
     pub fn read(&mut self, output: &mut [u8]) -> ReadResult {
         use ReadResult::*;
-        // print!("read buffer: ");
-        // for c in &self.read_buffer {
-        //     print!("{}", *c as char);
-        // }
-        // print!("\n");
-        // Any attempts by a process in a background process group to
-        // read from its controlling terminal cause its process group
-        // to be sent a SIGTTIN signal unless one of the following
-        // special cases applies: if the reading process is ignoring
-        // the SIGTTIN signal or the reading thread is blocking the
-        // SIGTTIN signal, or if the process group of the reading
-        // process is orphaned, the read() shall return -1, with errno
-        // set to [EIO] and no signal shall be sent. The default
-        // action of the SIGTTIN signal shall be to stop the process
-        // to which it is sent. See <signal.h>.
-        // let current_pgid = unsafe { get_current_pgid() };
-        // if self.foreground_process_group != current_pgid && (self.termios.c_lflag & TOSTOP != 0) {
-        //     messaging::send_message(MessageTo::ProcessGroup {
-        //         pgid: self.foreground_process_group,
-        //         content: ProcessGroupMessage::Signal(Signum::SIGTTIN),
-        //     });
-        // }
         if self.termios.c_lflag & ICANON != 0 {
             // dbg!("canonical");
             // if VEOF was pressed, read all
@@ -263,27 +275,7 @@ impl LineDiscipline {
 
     /// write on the tty
     pub fn write(&mut self, s: &[u8]) -> usize {
-        //Attempts by a process in a background process group to write
-        // to its controlling terminal shall cause the process group
-        // to be sent a SIGTTOU signal unless one of the following
-        // special cases applies: if TOSTOP is not set, or if TOSTOP
-        // is set and the process is ignoring the SIGTTOU signal or
-        // the writing thread is blocking the SIGTTOU signal, the
-        // process is allowed to write to the terminal and the SIGTTOU
-        // signal is not sent. If TOSTOP is set, the process group of
-        // the writing process is orphaned, the writing process is not
-        // ignoring the SIGTTOU signal, and the writing thread is not
-        // blocking the SIGTTOU signal, the write() shall return -1,
-        // with errno set to [EIO] and no signal shall be sent.
         let s = core::str::from_utf8(s).expect("bad utf8");
-        // let current_pgid = unsafe { get_current_pgid() };
-        // if self.foreground_process_group != current_pgid && (self.termios.c_lflag & TOSTOP != 0) {
-        //     messaging::send_message(MessageTo::ProcessGroup {
-        //         pgid: self.foreground_process_group,
-        //         content: ProcessGroupMessage::Signal(Signum::SIGTTOU),
-        //     });
-        // }
-
         self.tty.write_str(s).expect("write failed");
         s.len()
     }
@@ -318,6 +310,67 @@ impl LineDiscipline {
     pub fn tcgetpgrp(&mut self) -> Pid {
         self.foreground_process_group
     }
+
+    /// The session of which the tty is the controlling terminal
+    pub fn session(&self) -> Option<Pid> {
+        self.session
+    }
+
+    /// Make the tty the controlling terminal of `session`, with
+    /// `pgid` as foreground process group, or disassociate it from its
+    /// session when `session` is None
+    pub fn set_session(&mut self, session: Option<Pid>, pgid: Pid) {
+        self.session = session;
+        self.foreground_process_group = pgid;
+    }
+
+    /// Apply the job control rules to an access of a process of the
+    /// session of the tty. `orphaned` tells whether its process group
+    /// is orphaned, `signal_refused` whether it ignores or blocks the
+    /// signal of the access.
+    ///
+    /// Any attempts by a process in a background process group to
+    /// read from its controlling terminal cause its process group to
+    /// be sent a SIGTTIN signal unless one of the following special
+    /// cases applies: if the reading process is ignoring the SIGTTIN
+    /// signal or the reading thread is blocking the SIGTTIN signal, or
+    /// if the process group of the reading process is orphaned, the
+    /// read() shall return -1, with errno set to [EIO] and no signal
+    /// shall be sent.
+    ///
+    /// Attempts by a process in a background process group to write
+    /// to its controlling terminal shall cause the process group to
+    /// be sent a SIGTTOU signal unless one of the following special
+    /// cases applies: if TOSTOP is not set, or if TOSTOP is set and
+    /// the process is ignoring the SIGTTOU signal or the writing
+    /// thread is blocking the SIGTTOU signal, the process is allowed
+    /// to write to the terminal and the SIGTTOU signal is not sent. If
+    /// TOSTOP is set, the process group of the writing process is
+    /// orphaned, the writing process is not ignoring the SIGTTOU
+    /// signal, and the writing thread is not blocking the SIGTTOU
+    /// signal, the write() shall return -1, with errno set to [EIO]
+    /// and no signal shall be sent.
+    ///
+    /// The requests which change the terminal follow the rules of
+    /// write() as if TOSTOP was set.
+    pub fn job_control(
+        &self,
+        access: TtyAccess,
+        pgid: Pid,
+        orphaned: bool,
+        signal_refused: bool,
+    ) -> JobControl {
+        if pgid == self.foreground_process_group {
+            return JobControl::Granted;
+        }
+        match access {
+            TtyAccess::Read if signal_refused || orphaned => JobControl::Denied,
+            TtyAccess::Write if self.termios.c_lflag & TOSTOP == 0 => JobControl::Granted,
+            TtyAccess::Write | TtyAccess::Control if signal_refused => JobControl::Granted,
+            TtyAccess::Write | TtyAccess::Control if orphaned => JobControl::Denied,
+            _ => JobControl::Signal(access.signal()),
+        }
+    }
 }
 
 pub fn encode_utf8(keysymb: KeySymb, dst: &mut [u8]) -> &[u8] {
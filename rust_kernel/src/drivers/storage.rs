@@ -73,6 +73,10 @@ pub trait BlockIo: Send {
         buf: *const u8,
     ) -> DiskResult<NbrSectors>;
     fn disk_size(&self) -> u64;
+    /// Write back the volatile write cache of the device, if any
+    fn flush(&mut self) -> DiskResult<()> {
+        Ok(())
+    }
 }
 
 pub fn init(multiboot_info: &MultibootInfo) {
@@ -1,11 +1,14 @@
 //! This module contains the turbo fish's ATA/IDE drivers, See https://wiki.osdev.org/PCI_IDE_Controller
 
+pub mod atapi;
 pub mod pci_udma;
 pub mod pio_polling;
 
 mod udma;
 pub use udma::{Channel, DmaCommand, DmaStatus, Udma};
 
+use atapi::ATAPI_SIGNATURES;
+
 use super::{
     BlockIo, DiskResult, IdeControllerProgIf, MassStorageControllerSubClass, NbrSectors,
     PciCommand, PciDeviceClass, PciType0, Sector, PCI, SECTOR_SIZE,
@@ -256,6 +259,9 @@ impl IdeAtaController {
 
     /// Select the drive we would like to read or write
     pub fn select_drive(&mut self, rank: Rank) -> AtaResult<()> {
+        if self.selected_drive == Some(rank) {
+            return Ok(());
+        }
         self.selected_drive = match rank {
             Rank::Primary(Hierarchy::Master) if self.primary_master.is_some() => Some(rank),
             Rank::Primary(Hierarchy::Slave) if self.primary_slave.is_some() => Some(rank),
@@ -268,6 +274,28 @@ impl IdeAtaController {
             .select_drive();
         Ok(())
     }
+    /// Get the number of sectors of the drive pointed by Rank, None if it is absent
+    pub fn drive_capacity(&self, rank: Rank) -> Option<NbrSectors> {
+        match rank {
+            Rank::Primary(Hierarchy::Master) => self.primary_master.as_ref(),
+            Rank::Primary(Hierarchy::Slave) => self.primary_slave.as_ref(),
+            Rank::Secondary(Hierarchy::Master) => self.secondary_master.as_ref(),
+            Rank::Secondary(Hierarchy::Slave) => self.secondary_slave.as_ref(),
+        }
+        .map(|drive| drive.sector_capacity)
+    }
+
+    /// Tell if the drive pointed by Rank is an ATAPI one, like a CD-ROM drive
+    pub fn is_atapi(&self, rank: Rank) -> bool {
+        match rank {
+            Rank::Primary(Hierarchy::Master) => self.primary_master.as_ref(),
+            Rank::Primary(Hierarchy::Slave) => self.primary_slave.as_ref(),
+            Rank::Secondary(Hierarchy::Master) => self.secondary_master.as_ref(),
+            Rank::Secondary(Hierarchy::Slave) => self.secondary_slave.as_ref(),
+        }
+        .map_or(false, |drive| drive.is_atapi())
+    }
+
     /// Get the drive pointed by Rank, or else return None
     fn get_selected_drive(&self) -> Option<&Drive> {
         match self.selected_drive? {
@@ -280,8 +308,23 @@ impl IdeAtaController {
 }
 
 impl BlockIo for IdeAtaController {
+    /// Write back the volatile write cache of the selected drive
+    fn flush(&mut self) -> DiskResult<()> {
+        let d = self.get_selected_drive().ok_or(AtaError::DeviceNotFound)?;
+        // The CD-ROM are read only
+        if d.is_atapi() {
+            return Ok(());
+        }
+        // The command is polled, its interrupt is not the end of a DMA transfert
+        d.disable_interrupt();
+        Ok(d.fflush_write_cache()?)
+    }
+
+    /// return the size of the selected drive
     fn disk_size(&self) -> u64 {
-        unimplemented!()
+        self.get_selected_drive()
+            .map(|drive| drive.sector_capacity.0 as u64 * SECTOR_SIZE as u64)
+            .unwrap_or(0)
     }
     /// Read nbr_sectors after start_sector location and write it into the buf
     fn read(
@@ -309,6 +352,11 @@ impl BlockIo for IdeAtaController {
         };
         let d = drive.ok_or(AtaError::DeviceNotFound)?;
         Ok(match (self.operating_mode, udma, self.udma_capable) {
+            // The packet commands are only sent in PIO mode
+            _ if d.is_atapi() => {
+                d.disable_interrupt();
+                PioIo::read(d, start_sector, nbr_sectors, buf)?
+            }
             (OperatingMode::UdmaTransfert, Some(udma), true) => {
                 d.enable_interrupt();
                 DmaIo::read(d, start_sector, nbr_sectors, buf, udma)?
@@ -349,6 +397,8 @@ impl BlockIo for IdeAtaController {
         };
         let d = drive.ok_or(AtaError::DeviceNotFound)?;
         Ok(match (self.operating_mode, udma, self.udma_capable) {
+            // The CD-ROM are read only
+            _ if d.is_atapi() => return Err(AtaError::NotSupported.into()),
             (OperatingMode::UdmaTransfert, Some(udma), true) => {
                 d.enable_interrupt();
                 DmaIo::write(d, start_sector, nbr_sectors, buf, udma)?
@@ -412,6 +462,8 @@ enum Capabilities {
     Lba48,
     Lba28,
     Chs,
+    /// A packet device, its sectors are 2048 bytes blocks
+    Atapi,
 }
 
 // Necessary to set some advanced features
@@ -497,6 +549,15 @@ impl Drive {
         .contains(StatusRegister::BSY)
         {}
 
+        // Check the LBAmid and LBAhi ports, the packet devices abort IDENTIFY and put their signature there
+        let signature = (
+            Pio::<u8>::new(command_register + Self::L2_CYLINDER).read(),
+            Pio::<u8>::new(command_register + Self::L3_CYLINDER).read(),
+        );
+        if ATAPI_SIGNATURES.contains(&signature) {
+            return Self::identify_packet(rank, command_register, control_register);
+        }
+
         // Continue polling one of the Status ports until bit 3 (DRQ, value = 8) sets, or until bit 0 (ERR, value = 1) sets.
         while !(StatusRegister::from_bits_truncate(
             Pio::<u8>::new(command_register + Self::STATUS).read(),
@@ -606,6 +667,11 @@ impl Drive {
         {}
     }
 
+    /// Is it a packet device ?
+    fn is_atapi(&self) -> bool {
+        matches!(self.capabilities, Capabilities::Atapi)
+    }
+
     /// Extract the sub tag hierarchy from rank
     fn get_hierarchy(&self) -> Hierarchy {
         match self.rank {
@@ -0,0 +1,230 @@
+//! This files contains the code related to the ATAPI packet interface, used by the CD-ROM drives. See https://wiki.osdev.org/ATAPI
+
+use super::{check_bounds, AtaError, AtaResult, Capabilities, Drive, Rank};
+use super::{AtaCommand, ErrorRegister, StatusRegister};
+use super::{NbrSectors, Sector, SECTOR_SIZE};
+
+use io::{Io, Pio};
+
+use core::cmp::min;
+
+/// Signatures left by a packet device in LBAmid and LBAhi after an aborted IDENTIFY (PATAPI and SATAPI)
+pub const ATAPI_SIGNATURES: [(u8, u8); 2] = [(0x14, 0xEB), (0x69, 0x96)];
+
+/// Size of a CD-ROM block
+pub const ATAPI_BLOCK_SIZE: usize = 2048;
+const SECTORS_PER_BLOCK: usize = ATAPI_BLOCK_SIZE / SECTOR_SIZE;
+
+/// Size of a SCSI command packet
+const PACKET_SIZE: usize = 12;
+
+/// Maximum number of bytes the device may send for each DRQ, it must be even
+const BYTE_COUNT_LIMIT: u16 = 0xF800;
+
+/// Maximum number of blocks asked by a single READ (10)
+const MAX_BLOCKS_PER_READ: usize = 32;
+
+/// A just inserted medium may answer UNIT ATTENTION to the first commands
+const READ_CAPACITY_RETRIES: usize = 3;
+
+/// The SCSI commands we use
+#[repr(u8)]
+enum ScsiCommand {
+    ReadCapacity10 = 0x25,
+    Read10 = 0x28,
+}
+
+impl Drive {
+    /// Identify a packet device and get the capacity of its medium, a drive without medium has no sector
+    pub fn identify_packet(
+        rank: Rank,
+        command_register: u16,
+        control_register: u16,
+    ) -> Option<Drive> {
+        // send the IDENTIFY PACKET DEVICE command (0xA1) to the Command IO port
+        Pio::<u8>::new(command_register + Self::COMMAND)
+            .write(AtaCommand::AtaCmdIdentifyPacket as u8);
+
+        // Poll until DRQ or ERR sets, after BSY clears
+        let status = loop {
+            let status = StatusRegister::from_bits_truncate(
+                Pio::<u8>::new(command_register + Self::STATUS).read(),
+            );
+            if !status.contains(StatusRegister::BSY)
+                && status.intersects(StatusRegister::ERR | StatusRegister::DRQ)
+            {
+                break status;
+            }
+        };
+        if status.contains(StatusRegister::ERR) {
+            log::warn!("IDENTIFY PACKET DEVICE failed on {:?}", rank);
+            return None;
+        }
+
+        // Read the 256 16-bit values
+        let mut v = [0; 256];
+        for word in v.iter_mut() {
+            *word = Pio::<u16>::new(command_register + Self::DATA).read();
+        }
+
+        let mut drive = Drive {
+            capabilities: Capabilities::Atapi,
+            sector_capacity: NbrSectors(0),
+            udma_support: v[88],
+            command_register,
+            control_register,
+            rank,
+        };
+        // The packet commands are polled
+        drive.disable_interrupt();
+        for _ in 0..READ_CAPACITY_RETRIES {
+            if let Ok(nbr_blocks) = drive.read_capacity() {
+                drive.sector_capacity = NbrSectors(nbr_blocks as usize * SECTORS_PER_BLOCK);
+                break;
+            }
+        }
+        log::info!(
+            "ATAPI {:?} medium of {:?} sectors",
+            rank,
+            drive.sector_capacity
+        );
+        Some(drive)
+    }
+
+    /// Get the number of blocks of the medium with READ CAPACITY (10)
+    fn read_capacity(&self) -> AtaResult<u32> {
+        let mut packet = [0; PACKET_SIZE];
+        packet[0] = ScsiCommand::ReadCapacity10 as u8;
+
+        // The last LBA and the block length, in big endian
+        let mut answer = [0; 8];
+        if self.send_packet(&packet, &mut answer)? != answer.len() {
+            return Err(AtaError::IoError);
+        }
+        let last_lba = u32::from_be_bytes([answer[0], answer[1], answer[2], answer[3]]);
+        let block_size = u32::from_be_bytes([answer[4], answer[5], answer[6], answer[7]]);
+        if block_size as usize != ATAPI_BLOCK_SIZE {
+            log::warn!("unsupported ATAPI block size: {}", block_size);
+            return Err(AtaError::NotSupported);
+        }
+        Ok(last_lba + 1)
+    }
+
+    /// Read the 512 bytes sectors from start_sector into buf, the partial blocks go through a bounce buffer
+    pub fn atapi_read(&self, start_sector: Sector, buf: &mut [u8]) -> AtaResult<()> {
+        check_bounds(
+            start_sector,
+            NbrSectors(buf.len() / SECTOR_SIZE),
+            self.sector_capacity,
+        )?;
+
+        let mut sector = start_sector.0;
+        let mut done = 0;
+        while done < buf.len() {
+            let block = (sector / SECTORS_PER_BLOCK) as u32;
+            let skip = (sector % SECTORS_PER_BLOCK) * SECTOR_SIZE;
+            let remaining = buf.len() - done;
+
+            let len = if skip == 0 && remaining >= ATAPI_BLOCK_SIZE {
+                let nbr_blocks = min(remaining / ATAPI_BLOCK_SIZE, MAX_BLOCKS_PER_READ);
+                let len = nbr_blocks * ATAPI_BLOCK_SIZE;
+                self.read_blocks(block, &mut buf[done..done + len])?;
+                len
+            } else {
+                let mut bounce = [0; ATAPI_BLOCK_SIZE];
+                self.read_blocks(block, &mut bounce)?;
+                let len = min(ATAPI_BLOCK_SIZE - skip, remaining);
+                buf[done..done + len].copy_from_slice(&bounce[skip..skip + len]);
+                len
+            };
+            done += len;
+            sector += len / SECTOR_SIZE;
+        }
+        Ok(())
+    }
+
+    /// Read whole blocks with READ (10)
+    fn read_blocks(&self, block: u32, buf: &mut [u8]) -> AtaResult<()> {
+        let nbr_blocks = (buf.len() / ATAPI_BLOCK_SIZE) as u16;
+        let mut packet = [0; PACKET_SIZE];
+        packet[0] = ScsiCommand::Read10 as u8;
+        packet[2..6].copy_from_slice(&block.to_be_bytes());
+        packet[7..9].copy_from_slice(&nbr_blocks.to_be_bytes());
+
+        match self.send_packet(&packet, buf)? {
+            len if len == buf.len() => Ok(()),
+            _ => Err(AtaError::IoError),
+        }
+    }
+
+    /// Send a packet command and read its answer into buf, return the number of bytes stored
+    fn send_packet(&self, packet: &[u8; PACKET_SIZE], buf: &mut [u8]) -> AtaResult<usize> {
+        self.select_drive();
+
+        // PIO transfers, the byte count limit goes into LBAmid and LBAhi
+        Pio::<u8>::new(self.command_register + Self::FEATURES).write(0);
+        Pio::<u8>::new(self.command_register + Self::L2_CYLINDER).write(BYTE_COUNT_LIMIT as u8);
+        Pio::<u8>::new(self.command_register + Self::L3_CYLINDER)
+            .write((BYTE_COUNT_LIMIT >> 8) as u8);
+
+        // Send the PACKET command (0xA0), then the packet itself as 6 words when the device asks for it
+        Pio::<u8>::new(self.command_register + Self::COMMAND).write(AtaCommand::AtaCmdPacket as u8);
+        if !self.packet_wait()?.contains(StatusRegister::DRQ) {
+            return Err(AtaError::IoError);
+        }
+        for word in packet.chunks(2) {
+            Pio::<u16>::new(self.command_register + Self::DATA)
+                .write(u16::from_le_bytes([word[0], word[1]]));
+        }
+
+        // The device sends the data by chunks of the byte count it announces, until DRQ stays clear
+        let mut received = 0;
+        while self.packet_wait()?.contains(StatusRegister::DRQ) {
+            let byte_count = Pio::<u8>::new(self.command_register + Self::L2_CYLINDER).read()
+                as usize
+                | (Pio::<u8>::new(self.command_register + Self::L3_CYLINDER).read() as usize) << 8;
+            for _ in 0..(byte_count + 1) / 2 {
+                let word = Pio::<u16>::new(self.command_register + Self::DATA)
+                    .read()
+                    .to_le_bytes();
+                // What does not fit is dropped
+                for byte in word.iter() {
+                    if let Some(b) = buf.get_mut(received) {
+                        *b = *byte;
+                    }
+                    received += 1;
+                }
+            }
+        }
+        Ok(min(received, buf.len()))
+    }
+
+    /// Wait for the end of the Busy state, return the status
+    fn packet_wait(&self) -> AtaResult<StatusRegister> {
+        // Give 400ns to the device to update its status
+        for _ in 0..4 {
+            Pio::<u8>::new(self.control_register + Self::ALTERNATE_STATUS).read();
+        }
+        loop {
+            let status = StatusRegister::from_bits_truncate(
+                Pio::<u8>::new(self.command_register + Self::STATUS).read(),
+            );
+            if status.contains(StatusRegister::BSY) {
+                continue;
+            }
+            if status.contains(StatusRegister::ERR) {
+                // The high nibble is the SCSI sense key
+                log::warn!(
+                    "ATAPI error on {:?}: {:?}, sense key {:#X}",
+                    self.rank,
+                    ErrorRegister::from_bits_truncate(
+                        Pio::<u8>::new(self.command_register + Self::ERROR).read()
+                    ),
+                    Pio::<u8>::new(self.command_register + Self::ERROR).read() >> 4
+                );
+                return Err(AtaError::IoError);
+            }
+            return Ok(status);
+        }
+    }
+}
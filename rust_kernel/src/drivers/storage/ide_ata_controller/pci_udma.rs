@@ -51,7 +51,8 @@ impl DmaIo for Drive {
                     .write(AtaCommand::AtaCmdReadDma as u8);
             }
             // I experiment a lack of documentation about this mode
-            Capabilities::Chs => {
+            // and the packet devices are only used in PIO mode
+            Capabilities::Chs | Capabilities::Atapi => {
                 return Err(AtaError::NotSupported);
             }
         }
@@ -105,7 +106,8 @@ impl DmaIo for Drive {
                     .write(AtaCommand::AtaCmdWriteDma as u8);
             }
             // I experiment a lack of documentation about this mode
-            Capabilities::Chs => {
+            // and the packet devices are only used in PIO mode
+            Capabilities::Chs | Capabilities::Atapi => {
                 return Err(AtaError::NotSupported);
             }
         }
@@ -62,6 +62,10 @@ impl PioIo for Drive {
                 }
                 Ok(nbr_sectors)
             }
+            Capabilities::Atapi => {
+                self.atapi_read(start_sector, s)?;
+                Ok(nbr_sectors)
+            }
             // I experiment a lack of documentation about this mode
             Capabilities::Chs => Err(AtaError::NotSupported),
         }
@@ -97,7 +101,7 @@ impl PioIo for Drive {
                     self.write_sectors(sectors_to_write, chunk.as_ptr())?;
 
                     // Fflush write cache
-                    self.fflush_write_cache();
+                    self.fflush_write_cache()?;
                 }
                 Ok(nbr_sectors)
             }
@@ -119,12 +123,12 @@ impl PioIo for Drive {
                     self.write_sectors(sectors_to_write, chunk.as_ptr())?;
 
                     // Fflush write cache
-                    self.fflush_write_cache();
+                    self.fflush_write_cache()?;
                 }
                 Ok(nbr_sectors)
             }
-            // I experiment a lack of documentation about this mode
-            Capabilities::Chs => Err(AtaError::NotSupported),
+            // I experiment a lack of documentation about this mode, and the CD-ROM are read only
+            Capabilities::Chs | Capabilities::Atapi => Err(AtaError::NotSupported),
         }
     }
 }
@@ -191,7 +195,8 @@ impl Drive {
     /// This is done by sending the 0xE7 command to the Command Register (then waiting for BSY to clear).
     /// If a driver does not do this, then subsequent write commands can fail invisibly,
     /// or "temporary bad sectors" can be created on your disk.
-    fn fflush_write_cache(&self) {
+    pub(super) fn fflush_write_cache(&self) -> AtaResult<()> {
+        self.wait_available();
         match self.capabilities {
             Capabilities::Lba28 => Pio::<u8>::new(self.command_register + Self::COMMAND)
                 .write(AtaCommand::AtaCmdCacheFlush as u8),
@@ -202,5 +207,9 @@ impl Drive {
 
         let p = Pio::<u8>::new(self.command_register + Self::STATUS);
         while StatusRegister::from_bits_truncate(p.read()).contains(StatusRegister::BSY) {}
+        if StatusRegister::from_bits_truncate(p.read()).contains(StatusRegister::ERR) {
+            return Err(AtaError::IoError);
+        }
+        Ok(())
     }
 }
@@ -11,26 +11,42 @@ pub struct Elf {
     pub program_header_table: Vec<ProgramHeader>,
 }
 
+/// The size of the 32-bit ELF header in the file
+const ELF_HEADER_SIZE: usize = 52;
+
 /// Parse a ELF file from a slice
 pub fn load_elf(content: &[u8]) -> Result<Elf, Errno> {
-    let header = ElfHeader::from_bytes(&content).or(Err(Errno::ENOEXEC))?;
+    load_elf_with(|buf, offset| {
+        let src = content
+            .get(offset as usize..)
+            .and_then(|content| content.get(..buf.len()))
+            .ok_or(Errno::ENOEXEC)?;
+        buf.copy_from_slice(src);
+        Ok(())
+    })
+}
+
+/// Parse a ELF file without holding its content: `read_at` fills its
+/// buffer with the bytes of the file at the given offset
+pub fn load_elf_with<F>(mut read_at: F) -> Result<Elf, Errno>
+where
+    F: FnMut(&mut [u8], u64) -> Result<(), Errno>,
+{
+    let mut header = [0; ELF_HEADER_SIZE];
+    read_at(&mut header, 0)?;
+    let header = ElfHeader::from_bytes(&header).or(Err(Errno::ENOEXEC))?;
 
     let program_header_table = {
         let mut ph_table = Vec::new();
 
-        // println!("{:#X?}", &header);
-        use core::slice;
-        let program_header_table: &[[u8; mem::size_of::<ProgramHeader>()]] = unsafe {
-            slice::from_raw_parts(
-                &content[header.program_header_table_offset as usize] as *const u8 as *const _,
-                header.nbr_program_header as usize,
-            )
-        };
-        // println!("\nProgram header table:");
-        for (_index, program_header) in program_header_table.iter().enumerate() {
-            let pheader =
-                ProgramHeader::from_bytes(program_header as &[u8]).or(Err(Errno::ENOEXEC))?;
-            // println!("{}: {:#X?}", index, pheader);
+        for index in 0..header.nbr_program_header as u64 {
+            let mut program_header = [0; mem::size_of::<ProgramHeader>()];
+            read_at(
+                &mut program_header,
+                header.program_header_table_offset as u64
+                    + index * mem::size_of::<ProgramHeader>() as u64,
+            )?;
+            let pheader = ProgramHeader::from_bytes(&program_header).or(Err(Errno::ENOEXEC))?;
             ph_table.try_push(pheader)?;
         }
         ph_table
@@ -13,6 +13,8 @@ pub use allocator::{VirtualPageAllocator, HIGH_KERNEL_MEMORY, KERNEL_VIRTUAL_PAG
 pub mod address_space;
 pub use address_space::AddressSpace;
 
+pub mod file_mapping;
+
 pub mod init;
 pub use init::init_memory_system;
 
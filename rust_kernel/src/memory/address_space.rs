@@ -1,18 +1,64 @@
-use super::allocator::{BuddyAllocator, VirtualPageAllocator};
+use super::allocator::{BuddyAllocator, VirtualPageAllocator, PHYSICAL_ALLOCATOR};
+use super::file_mapping::{FileMapping, PAGE_CACHE};
 use crate::memory::mmu::{Entry, PageDirectory};
 use crate::memory::tools::*;
 pub use crate::taskmaster::{CString, CStringArray};
 use alloc::vec::Vec;
 use core::convert::Into;
 use core::mem::size_of;
-use fallible_collections::{try_vec, FallibleVec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use fallible_collections::{try_vec, FallibleVec, TryClone};
 use libc_binding::c_char;
 
 #[derive(Debug)]
 /// Virtual Allocator Specialized for processus
-pub struct AddressSpace(VirtualPageAllocator);
+pub struct AddressSpace {
+    allocator: VirtualPageAllocator,
+    /// The areas loaded on demand from a file
+    mappings: Vec<FileMapping>,
+    /// How far below its top the user stack may grow, None if the
+    /// stack is not reserved
+    stack_limit: Option<usize>,
+    /// The end of the user stack area, at most STACK_TOP
+    stack_top: Virt,
+    /// The number of pages in memory. The faults load pages through a
+    /// shared borrow, hence the atomic.
+    resident_pages: AtomicUsize,
+    /// The highest number of pages the address space had in memory
+    peak_resident_pages: AtomicUsize,
+}
+
+/// What is left to service a page fault, see
+/// `AddressSpace::prepare_page_in`
+#[derive(Debug)]
+pub enum PageIn {
+    /// The page is in memory
+    Done(FaultKind),
+    /// The page must be read from the file of the mapping first
+    Read(FileMapping),
+}
+
+/// How a page fault was serviced
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FaultKind {
+    /// Without any I/O: the page was zeroed or found in the page cache
+    Minor,
+    /// The page was read from its file
+    Major,
+}
 
 impl AddressSpace {
+    /// The highest end of the user stack, at the top of the user space
+    pub const STACK_TOP: Virt = Virt(0xC000_0000);
+    /// The area reserved below the top of the user stack, its pages
+    /// are allocated on their first access
+    const STACK_RESERVED: NbrPages = NbrPages::_16MB;
+    /// The bottom of the reserved area is never allocated: an overflow
+    /// faults there instead of running into the mapping below
+    const STACK_GUARD_GAP: NbrPages = NbrPages::_1MB;
+    /// Where the mappings without a fixed address are searched from
+    pub const MMAP_BASE: Virt = Virt(0x40_0000);
+
     pub unsafe fn try_new() -> Result<Self> {
         let mut buddy = BuddyAllocator::new(Page::new(0x0), NbrPages::_3GB)?;
         buddy
@@ -21,12 +67,353 @@ impl AddressSpace {
 
         let pd = PageDirectory::new_for_process()?;
 
-        Ok(Self(VirtualPageAllocator::new(buddy, pd)))
+        Ok(Self {
+            allocator: VirtualPageAllocator::new(buddy, pd),
+            mappings: Vec::new(),
+            stack_limit: None,
+            stack_top: Self::STACK_TOP,
+            resident_pages: AtomicUsize::new(0),
+            peak_resident_pages: AtomicUsize::new(0),
+        })
     }
 
     /// the process forker must be the current cr3
     pub fn fork(&self) -> Result<Self> {
-        Ok(Self(self.0.fork()?))
+        let mut allocator = self.allocator.fork()?;
+
+        // Only the allocated pages of the stack are copied
+        if self.stack_limit.is_some() {
+            let (bottom, size) = self.stack_growth_area();
+            unsafe {
+                allocator.context_switch();
+                let res = allocator.mark_valloc(bottom, size, AllocFlags::USER_MEMORY);
+                self.allocator.context_switch();
+                res?;
+            }
+        }
+        // The copy starts with all the pages of its parent in memory
+        let resident_pages = self.resident_pages.load(Ordering::Relaxed);
+        Ok(Self {
+            allocator,
+            mappings: self.mappings.try_clone()?,
+            stack_limit: self.stack_limit,
+            stack_top: self.stack_top,
+            resident_pages: AtomicUsize::new(resident_pages),
+            peak_resident_pages: AtomicUsize::new(resident_pages),
+        })
+    }
+
+    /// The highest resident set size the address space reached, in
+    /// kilobytes
+    pub fn peak_rss(&self) -> usize {
+        NbrPages(self.peak_resident_pages.load(Ordering::Relaxed)).to_bytes() / 1024
+    }
+
+    /// Count `nbr_pages` more pages in memory
+    fn charge(&self, nbr_pages: NbrPages) {
+        let resident = self
+            .resident_pages
+            .fetch_add(nbr_pages.0, Ordering::Relaxed)
+            + nbr_pages.0;
+        self.peak_resident_pages
+            .fetch_max(resident, Ordering::Relaxed);
+    }
+
+    /// Count `nbr_pages` less pages in memory
+    fn uncharge(&self, nbr_pages: NbrPages) {
+        let _ =
+            self.resident_pages
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |resident| {
+                    Some(resident.saturating_sub(nbr_pages.0))
+                });
+    }
+
+    /// The part of the stack area above the guard gap
+    fn stack_growth_area(&self) -> (Page<Virt>, NbrPages) {
+        let size = Self::STACK_RESERVED - Self::STACK_GUARD_GAP;
+        (Page::containing(self.stack_top - size.to_bytes()), size)
+    }
+
+    /// The bottom of the stack area, its guard gap included
+    fn stack_bottom(&self) -> Page<Virt> {
+        Page::containing(self.stack_top - Self::STACK_RESERVED.to_bytes())
+    }
+
+    /// Reserve the user stack `offset` below STACK_TOP, its top is
+    /// returned. Its pages are allocated on their first access, as
+    /// long as the stack stays within its limit. The address space
+    /// must be the current one.
+    pub fn reserve_stack(&mut self, offset: NbrPages) -> Result<Virt> {
+        self.stack_top = Self::STACK_TOP - offset.to_bytes();
+        let bottom = self.stack_bottom();
+        let (growth_bottom, growth_size) = self.stack_growth_area();
+
+        self.allocator
+            .reserve_unmapped(bottom, Self::STACK_RESERVED)?;
+        unsafe {
+            self.allocator
+                .mark_valloc(growth_bottom, growth_size, AllocFlags::USER_MEMORY)?;
+        }
+        self.stack_limit = Some(growth_size.to_bytes());
+        Ok(self.stack_top)
+    }
+
+    /// Leave the `size` pages from `start` unused, so the mappings
+    /// without a fixed address start at a random place. Must be called
+    /// before anything is mapped there.
+    pub fn reserve_mmap_gap(&mut self, start: Virt, size: NbrPages) -> Result<()> {
+        if size == NbrPages(0) {
+            return Ok(());
+        }
+        self.allocator
+            .reserve_unmapped(Page::containing(start), size)
+    }
+
+    /// Set how far the user stack may grow, from the RLIMIT_STACK soft
+    /// limit. It never grows into the guard gap.
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        let growth_size = self.stack_growth_area().1.to_bytes();
+        if let Some(stack_limit) = self.stack_limit.as_mut() {
+            *stack_limit = limit.min(growth_size);
+        }
+    }
+
+    /// Map the area described by `mapping`, its pages are loaded on
+    /// their first access
+    pub fn map_file(&mut self, mapping: FileMapping) -> Result<()> {
+        self.mappings.try_reserve(1)?;
+        self.allocator.reserve_lazy(
+            mapping.start,
+            mapping.nbr_pages,
+            mapping.flags | AllocFlags::USER_MEMORY,
+        )?;
+        self.mappings.push(mapping);
+        Ok(())
+    }
+
+    /// Load `page` of a file mapping on its first access. The
+    /// read-only pages which hold a whole page of the file are taken
+    /// from the page cache, the others are private.
+    ///
+    /// Only the address space borrow is needed, so the user pointer
+    /// checks load the pages they are asked for.
+    pub fn page_in(&self, page: Page<Virt>) -> Result<FaultKind> {
+        let kind = self.load_lazy_page(page)?;
+        self.charge(NbrPages(1));
+        Ok(kind)
+    }
+
+    /// Service a fault on `page` as far as it goes without reading a
+    /// file: the stack pages and the pages found in the page cache are
+    /// mapped. The other pages are read from the returned mapping by
+    /// the caller, without holding the scheduler, then installed by
+    /// `finish_page_in`.
+    pub fn prepare_page_in(&self, page: Page<Virt>) -> Result<PageIn> {
+        let entry = self
+            .allocator
+            .get_entry(page)
+            .ok_or(MemoryError::PageFault)?;
+        if entry.contains(Entry::VALLOC) {
+            self.grow_stack(page, entry)?;
+            self.charge(NbrPages(1));
+            return Ok(PageIn::Done(FaultKind::Minor));
+        }
+        if !entry.contains(Entry::LAZY) {
+            return Err(MemoryError::PageFault);
+        }
+        let mapping = self.find_mapping(page)?;
+        if mapping.is_shared(page) && !entry.contains(Entry::READ_WRITE) {
+            let cached = PAGE_CACHE.lock().get(mapping.cache_key(page));
+            if let Some(paddr) = cached {
+                let loaded_entry = (entry | Entry::PRESENT) & !Entry::LAZY;
+                let res = unsafe {
+                    self.allocator
+                        .set_lazy_entry(page, paddr, loaded_entry | Entry::SHARED)
+                };
+                if let Err(e) = res {
+                    PAGE_CACHE.lock().release(paddr);
+                    return Err(e);
+                }
+                self.charge(NbrPages(1));
+                return Ok(PageIn::Done(FaultKind::Minor));
+            }
+        }
+        Ok(PageIn::Read(mapping.try_clone()?))
+    }
+
+    /// Install `page` of `mapping`, whose `content` was read by the
+    /// caller after `prepare_page_in`. The page may have been loaded
+    /// by another thread or unmapped in the meantime.
+    pub fn finish_page_in(
+        &self,
+        page: Page<Virt>,
+        mapping: &FileMapping,
+        content: &[u8],
+    ) -> Result<FaultKind> {
+        let entry = self
+            .allocator
+            .get_entry(page)
+            .ok_or(MemoryError::PageFault)?;
+        if !entry.contains(Entry::LAZY) {
+            return match entry.contains(Entry::PRESENT) {
+                true => Ok(FaultKind::Minor),
+                false => Err(MemoryError::PageFault),
+            };
+        }
+        if !self.find_mapping(page)?.same_area(mapping) {
+            return Err(MemoryError::PageFault);
+        }
+        let kind = unsafe {
+            self.map_file_page(page, entry, mapping, || {
+                (page.to_addr().0 as *mut u8)
+                    .copy_from_nonoverlapping(content[..PAGE_SIZE].as_ptr(), PAGE_SIZE);
+                Ok(())
+            })?
+        };
+        self.charge(NbrPages(1));
+        Ok(kind)
+    }
+
+    /// The file mapping holding `page`
+    fn find_mapping(&self, page: Page<Virt>) -> Result<&FileMapping> {
+        self.mappings
+            .iter()
+            .find(|mapping| mapping.contains(page))
+            .ok_or(MemoryError::PageFault)
+    }
+
+    fn load_lazy_page(&self, page: Page<Virt>) -> Result<FaultKind> {
+        let entry = self
+            .allocator
+            .get_entry(page)
+            .ok_or(MemoryError::PageFault)?;
+        if entry.contains(Entry::VALLOC) {
+            return self.grow_stack(page, entry).map(|()| FaultKind::Minor);
+        }
+        if !entry.contains(Entry::LAZY) {
+            return Err(MemoryError::PageFault);
+        }
+        let mapping = self.find_mapping(page)?;
+        unsafe { self.map_file_page(page, entry, mapping, || mapping.load(page)) }
+    }
+
+    /// Map `page` of `mapping` in place of its lazy `entry`, from the
+    /// page cache or filled by `fill`
+    unsafe fn map_file_page<F>(
+        &self,
+        page: Page<Virt>,
+        entry: Entry,
+        mapping: &FileMapping,
+        fill: F,
+    ) -> Result<FaultKind>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let loaded_entry = (entry | Entry::PRESENT) & !Entry::LAZY;
+
+        if !mapping.is_shared(page) || entry.contains(Entry::READ_WRITE) {
+            let paddr = self.load_page(page, entry, fill)?;
+            return self
+                .allocator
+                .set_lazy_entry(page, paddr, loaded_entry)
+                .map(|()| FaultKind::Major);
+        }
+        let mut page_cache = PAGE_CACHE.lock();
+        let key = mapping.cache_key(page);
+        let (paddr, kind) = match page_cache.get(key) {
+            Some(paddr) => (paddr, FaultKind::Minor),
+            None => {
+                let paddr = self.load_page(page, entry, fill)?;
+                if let Err(e) = page_cache.insert(key, paddr) {
+                    self.allocator
+                        .set_lazy_entry(page, Page::new(0), entry)
+                        .expect("page table vanished");
+                    PHYSICAL_ALLOCATOR
+                        .as_mut()
+                        .unwrap()
+                        .free(paddr)
+                        .expect("page was never allocated");
+                    return Err(e);
+                }
+                (paddr, FaultKind::Major)
+            }
+        };
+        self.allocator
+            .set_lazy_entry(page, paddr, loaded_entry | Entry::SHARED)
+            .map(|()| kind)
+    }
+
+    /// Allocate the page `page` of the user stack on its first access,
+    /// if the stack stays within its limit. The page is zeroed before
+    /// the process can reach it.
+    fn grow_stack(&self, page: Page<Virt>, valloc_entry: Entry) -> Result<()> {
+        let limit = self.stack_limit.ok_or(MemoryError::PageFault)?;
+        match self.stack_top.0.checked_sub(page.to_addr().0) {
+            Some(size) if size <= limit => {}
+            _ => return Err(MemoryError::PageFault),
+        }
+        unsafe {
+            let paddr = PHYSICAL_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .alloc(NbrPages(1), AllocFlags::USER_MEMORY)?;
+
+            self.allocator
+                .set_lazy_entry(page, paddr, Entry::PRESENT | Entry::READ_WRITE)
+                .expect("page table vanished");
+            *(page.to_addr().0 as *mut [u8; PAGE_SIZE]) = [0; PAGE_SIZE];
+            self.allocator.set_lazy_entry(
+                page,
+                paddr,
+                (valloc_entry | Entry::PRESENT) & !Entry::VALLOC,
+            )
+        }
+    }
+
+    /// Allocate a physical page for `page` and fill it with `fill`.
+    /// The page is left mapped writable for the kernel only, or
+    /// restored to `lazy_entry` on failure.
+    unsafe fn load_page<F>(
+        &self,
+        page: Page<Virt>,
+        lazy_entry: Entry,
+        fill: F,
+    ) -> Result<Page<Phys>>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let physical_allocator = PHYSICAL_ALLOCATOR.as_mut().unwrap();
+        let paddr = physical_allocator.alloc(NbrPages(1), AllocFlags::USER_MEMORY)?;
+
+        let res = self
+            .allocator
+            .set_lazy_entry(page, paddr, Entry::PRESENT | Entry::READ_WRITE)
+            .and_then(|()| fill());
+        if let Err(e) = res {
+            self.allocator
+                .set_lazy_entry(page, Page::new(0), lazy_entry)
+                .expect("page table vanished");
+            physical_allocator
+                .free(paddr)
+                .expect("page was never allocated");
+            return Err(e);
+        }
+        Ok(paddr)
+    }
+
+    /// Load the pages of the file mappings and of the stack between
+    /// `start_ptr` and `end_ptr` which were never accessed, before a
+    /// user pointer check
+    pub fn page_in_range(&self, start_ptr: Virt, end_ptr: Virt) -> Result<()> {
+        for page in (Page::containing(start_ptr)..=Page::containing(end_ptr)).iter() {
+            match self.allocator.get_entry(page) {
+                Some(entry) if entry.intersects(Entry::LAZY | Entry::VALLOC) => {
+                    self.page_in(page)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
     }
 
     /// Check if a pointer given by user process is not bullshit
@@ -41,8 +428,10 @@ impl AddressSpace {
                 .ok_or(MemoryError::BadAddr)?,
         );
 
+        self.page_in_range(start_ptr, end_ptr)
+            .map_err(|_| MemoryError::BadAddr)?;
         Ok(self
-            .0
+            .allocator
             .check_page_range(start_ptr.into(), end_ptr.into(), predicate)
             .map_err(|_| MemoryError::BadAddr)?)
     }
@@ -68,8 +457,10 @@ impl AddressSpace {
                 .ok_or(MemoryError::BadAddr)?,
         );
 
+        self.page_in_range(start_ptr, end_ptr)
+            .map_err(|_| MemoryError::BadAddr)?;
         Ok(self
-            .0
+            .allocator
             .check_page_range(start_ptr.into(), end_ptr.into(), predicate)
             .map_err(|_| MemoryError::BadAddr)?)
     }
@@ -220,15 +611,18 @@ impl AddressSpace {
     where
         N: Into<NbrPages>,
     {
-        Ok(self
-            .0
-            .alloc(length.into(), alloc_flags | AllocFlags::USER_MEMORY)?
+        let length = length.into();
+        let addr = self
+            .allocator
+            .alloc(length, alloc_flags | AllocFlags::USER_MEMORY)?
             .to_addr()
-            .0 as *mut u8)
+            .0 as *mut u8;
+        self.charge(length);
+        Ok(addr)
     }
 
     pub unsafe fn context_switch(&self) {
-        self.0.context_switch()
+        self.allocator.context_switch()
     }
 
     pub fn change_range_page_entry<U>(
@@ -240,28 +634,61 @@ impl AddressSpace {
     where
         U: FnMut(&mut Entry),
     {
-        self.0
+        self.allocator
             .change_range_page_entry(start_page, nbr_pages, update)
     }
 
+    /// The pages of the page cache made writable are replaced by a
+    /// private copy first
     pub fn change_flags_range_page_entry(
         &mut self,
         start_page: Page<Virt>,
         nbr_pages: NbrPages,
         flags: AllocFlags,
-    ) {
+    ) -> Result<()> {
         // URGENT TODO: check if range is in user_memory
-        self.0.change_flags_range_page_entry(
+        if !flags.contains(AllocFlags::READ_ONLY) {
+            for i in 0..nbr_pages.0 {
+                self.unshare_page(start_page + NbrPages(i))?;
+            }
+        }
+        self.allocator.change_flags_range_page_entry(
             start_page,
             nbr_pages,
             flags | AllocFlags::USER_MEMORY,
         );
+        Ok(())
+    }
+
+    /// Replace `page`, if it comes from the page cache, by a private
+    /// copy. The address space must be the current one.
+    fn unshare_page(&mut self, page: Page<Virt>) -> Result<()> {
+        let entry = match self.allocator.get_entry(page) {
+            Some(entry) if entry.contains(Entry::SHARED) => entry,
+            _ => return Ok(()),
+        };
+        unsafe {
+            let paddr = PHYSICAL_ALLOCATOR
+                .as_mut()
+                .unwrap()
+                .alloc(NbrPages(1), AllocFlags::USER_MEMORY)?;
+            let content = *(page.to_addr().0 as *const [u8; PAGE_SIZE]);
+
+            self.allocator.set_lazy_entry(
+                page,
+                paddr,
+                (entry | Entry::READ_WRITE) & !Entry::SHARED,
+            )?;
+            *(page.to_addr().0 as *mut [u8; PAGE_SIZE]) = content;
+        }
+        PAGE_CACHE.lock().release(entry.entry_page());
+        Ok(())
     }
 
     #[inline(always)]
     pub fn change_flags_page_entry(&mut self, page: Page<Virt>, flags: AllocFlags) {
         // URGENT TODO: check if range is in user_memory
-        self.0
+        self.allocator
             .change_flags_page_entry(page, flags | AllocFlags::USER_MEMORY);
     }
 
@@ -270,14 +697,30 @@ impl AddressSpace {
         let size =
             NbrPages::from((vaddr + size).align_next(PAGE_SIZE) - vaddr.align_prev(PAGE_SIZE));
         let page = Page::from(vaddr);
-        Ok(self
-            .0
+        let addr = self
+            .allocator
             .alloc_on(page, size, flags | AllocFlags::USER_MEMORY)?
             .to_addr()
-            .0 as *mut u8)
+            .0 as *mut u8;
+        self.charge(size);
+        Ok(addr)
     }
     pub fn unmap_addr(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
-        self.0.unmap_addr(vaddr, size)
+        // The file mappings and the stack are not made of buddy blocks
+        if self.mappings.iter().any(|mapping| {
+            vaddr < mapping.start + mapping.nbr_pages && mapping.start < vaddr + size
+        }) {
+            return Err(MemoryError::NotAllocated);
+        }
+        if self.stack_limit.is_some()
+            && vaddr < Page::containing(self.stack_top)
+            && self.stack_bottom() < vaddr + size
+        {
+            return Err(MemoryError::NotAllocated);
+        }
+        self.allocator.unmap_addr(vaddr, size)?;
+        self.uncharge(size);
+        Ok(())
     }
 }
 
@@ -120,6 +120,22 @@ impl VirtualPageAllocator {
         Ok(())
     }
 
+    /// get the page table entry of `page`
+    pub fn get_entry(&self, page: Page<Virt>) -> Option<Entry> {
+        self.mmu.get_entry(page)
+    }
+
+    /// Set the entry of `page`, which must belong to an area reserved
+    /// by reserve_lazy, see PageDirectory::set_lazy_entry
+    pub unsafe fn set_lazy_entry(
+        &self,
+        page: Page<Virt>,
+        paddr: Page<Phys>,
+        entry: Entry,
+    ) -> Result<()> {
+        self.mmu.set_lazy_entry(page, paddr, entry)
+    }
+
     /// get the physical mapping of virtual address `v`
     pub unsafe fn get_physical_addr(&self, v: Virt) -> Option<Phys> {
         let offset = v.offset();
@@ -295,6 +311,57 @@ impl VirtualPageAllocator {
         Ok(vaddr.into())
     }
 
+    /// Reserve the virtual area at `vaddr` without allocating it: its
+    /// pages are marked LAZY and loaded on their first access
+    pub fn reserve_lazy(
+        &mut self,
+        vaddr: Page<Virt>,
+        size: NbrPages,
+        flags: AllocFlags,
+    ) -> Result<()> {
+        let entry = (Entry::from(flags) | Entry::LAZY) & !Entry::PRESENT;
+
+        self.virt.reserve_exact(vaddr, size)?;
+        unsafe {
+            self.mmu
+                .map_range_page(vaddr, Page::new(0), size, entry)
+                .map_err(|e| {
+                    self.virt
+                        .free_reserve(vaddr, size)
+                        .expect("Could not free memory reserved on VirtualPageAllocator");
+                    e
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Reserve the virtual area at `vaddr` without mapping anything on it
+    pub fn reserve_unmapped(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
+        self.virt.reserve_exact(vaddr, size)
+    }
+
+    /// Mark VALLOC the pages of the area at `vaddr` which are not
+    /// mapped: they are allocated on their first access. The page
+    /// tables are created now, so the pages can be allocated while the
+    /// page directory is only borrowed. The allocator must be the
+    /// current one.
+    pub unsafe fn mark_valloc(
+        &mut self,
+        vaddr: Page<Virt>,
+        size: NbrPages,
+        flags: AllocFlags,
+    ) -> Result<()> {
+        let entry = (Entry::from(flags) | Entry::VALLOC) & !Entry::PRESENT;
+
+        for page in (vaddr..vaddr + size).iter() {
+            match self.mmu.get_entry(page) {
+                Some(current) if current.contains(Entry::PRESENT) => {}
+                _ => self.mmu.map_page(page, Page::new(0), entry)?,
+            }
+        }
+        Ok(())
+    }
+
     pub fn dealloc_on(&mut self, vaddr: Page<Virt>, size: NbrPages) -> Result<()> {
         let order = size.into();
 
@@ -397,7 +464,9 @@ impl VirtualPageAllocator {
         let physical_allocator = unsafe { PHYSICAL_ALLOCATOR.as_mut().unwrap() };
         let entry = self.mmu.get_entry_mut(p).ok_or(MemoryError::PageFault)?;
 
-        if entry.contains(Entry::VALLOC) {
+        // The user stacks are grown by their address space, which
+        // checks the stack limit and zeroes the pages
+        if entry.contains(Entry::VALLOC) && !entry.contains(Entry::USER) {
             // KERNEL_MEMORY flags is currently not used.
             let paddr = physical_allocator
                 .alloc(NbrPages(1), AllocFlags::KERNEL_MEMORY)
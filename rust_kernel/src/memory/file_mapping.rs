@@ -0,0 +1,276 @@
+//! Memory areas backed by a file and loaded on demand: the pages of an
+//! area are only read from the file on their first access. The pages
+//! of the read-only areas are kept in a page cache, shared between the
+//! processes mapping the same file. The cached pages of a file are
+//! detached from the cache when it is written, truncated or freed.
+
+use crate::memory::allocator::PHYSICAL_ALLOCATOR;
+use crate::memory::tools::*;
+use crate::taskmaster::vfs::{InodeId, VFS};
+use crate::taskmaster::{FileOperation, SysResult};
+use alloc::sync::Arc;
+use fallible_collections::{btree::BTreeMap, TryClone};
+use sync::DeadMutex;
+
+use lazy_static::lazy_static;
+
+/// A memory area mapping a part of a file
+#[derive(Debug)]
+pub struct FileMapping {
+    /// The first page of the area
+    pub start: Page<Virt>,
+    /// The size of the area
+    pub nbr_pages: NbrPages,
+    /// The file the pages are read from
+    file: Arc<DeadMutex<dyn FileOperation>>,
+    /// The identity of the file, which keys its pages in the page
+    /// cache. The files without one are never shared.
+    inode_id: Option<InodeId>,
+    /// The offset in the file of the first byte of the area
+    offset: u64,
+    /// The number of bytes of the area read from the file, the
+    /// remaining bytes are zero-filled
+    file_len: usize,
+    /// The permissions of the pages
+    pub flags: AllocFlags,
+    /// Keeps the file from being written while it is mapped as a
+    /// process image
+    write_denial: Option<Arc<WriteDenial>>,
+}
+
+impl FileMapping {
+    pub fn new(
+        start: Page<Virt>,
+        nbr_pages: NbrPages,
+        file: Arc<DeadMutex<dyn FileOperation>>,
+        inode_id: Option<InodeId>,
+        offset: u64,
+        file_len: usize,
+        flags: AllocFlags,
+    ) -> Self {
+        Self {
+            start,
+            nbr_pages,
+            file,
+            inode_id,
+            offset,
+            file_len,
+            flags,
+            write_denial: None,
+        }
+    }
+
+    /// Forbid the writes to the file while the area is mapped
+    pub fn with_write_denial(mut self, write_denial: Arc<WriteDenial>) -> Self {
+        self.write_denial = Some(write_denial);
+        self
+    }
+
+    /// Checks if `page` belongs to the area
+    pub fn contains(&self, page: Page<Virt>) -> bool {
+        page >= self.start && page < self.start + self.nbr_pages
+    }
+
+    /// Checks if `page` is taken from the page cache: it is read-only
+    /// and entirely read from a page of the file
+    pub fn is_shared(&self, page: Page<Virt>) -> bool {
+        let area_offset = (page - self.start).to_bytes();
+
+        self.inode_id.is_some()
+            && self.flags.contains(AllocFlags::READ_ONLY)
+            && self.offset as usize & PAGE_SIZE_MASK == 0
+            && area_offset + PAGE_SIZE <= self.file_len
+    }
+
+    /// The key of `page` in the page cache
+    pub fn cache_key(&self, page: Page<Virt>) -> (InodeId, usize) {
+        let area_offset = (page - self.start).to_bytes();
+
+        (
+            self.inode_id.expect("file mapping is not shared"),
+            (self.offset as usize + area_offset) / PAGE_SIZE,
+        )
+    }
+
+    /// Checks if `other` maps the same part of the same file at the
+    /// same place
+    pub fn same_area(&self, other: &FileMapping) -> bool {
+        self.start == other.start
+            && self.nbr_pages == other.nbr_pages
+            && Arc::as_ptr(&self.file) as *const u8 == Arc::as_ptr(&other.file) as *const u8
+            && self.offset == other.offset
+            && self.file_len == other.file_len
+            && self.flags.bits() == other.flags.bits()
+    }
+
+    /// Fill `page`, which must be mapped writable in the current
+    /// address space, with its content
+    pub unsafe fn load(&self, page: Page<Virt>) -> Result<()> {
+        let content = core::slice::from_raw_parts_mut(page.to_addr().0 as *mut u8, PAGE_SIZE);
+
+        self.read(page, content)
+    }
+
+    /// Read the content of `page` into `content`, a page long
+    pub fn read(&self, page: Page<Virt>, content: &mut [u8]) -> Result<()> {
+        let area_offset = (page - self.start).to_bytes();
+
+        let file_bytes = if area_offset < self.file_len {
+            (self.file_len - area_offset).min(PAGE_SIZE)
+        } else {
+            0
+        };
+        let mut read = 0;
+        while read < file_bytes {
+            let count = self
+                .file
+                .lock()
+                .pread(
+                    &mut content[read..file_bytes],
+                    (self.offset + (area_offset + read) as u64) as i64,
+                )
+                .map_err(|_| MemoryError::PageFault)? as usize;
+            if count == 0 {
+                // The file was truncated under our feet
+                return Err(MemoryError::PageFault);
+            }
+            read += count;
+        }
+        content[file_bytes..PAGE_SIZE]
+            .iter_mut()
+            .for_each(|byte| *byte = 0);
+        Ok(())
+    }
+}
+
+impl TryClone for FileMapping {
+    fn try_clone(&self) -> core::result::Result<Self, alloc::collections::TryReserveError> {
+        Ok(Self {
+            start: self.start,
+            nbr_pages: self.nbr_pages,
+            file: self.file.clone(),
+            inode_id: self.inode_id,
+            offset: self.offset,
+            file_len: self.file_len,
+            flags: self.flags,
+            write_denial: self.write_denial.clone(),
+        })
+    }
+}
+
+/// A file which cannot be written nor truncated, with ETXTBSY, because
+/// it is executed. The writes are allowed again when the last mapping
+/// of the process images holding it is dropped.
+#[derive(Debug)]
+pub struct WriteDenial {
+    inode_id: InodeId,
+}
+
+impl WriteDenial {
+    pub fn new(inode_id: InodeId) -> SysResult<Self> {
+        VFS.lock().get_inode(inode_id)?.deny_write();
+        Ok(Self { inode_id })
+    }
+}
+
+impl Drop for WriteDenial {
+    fn drop(&mut self) {
+        // The inode may already be freed
+        if let Ok(inode) = VFS.lock().get_inode(self.inode_id) {
+            inode.allow_write();
+        }
+    }
+}
+
+/// A physical page of the page cache
+#[derive(Debug)]
+struct CachedPage {
+    /// The page of file held, which may no longer lead to this page
+    /// once the file is invalidated
+    key: (InodeId, usize),
+    /// The number of page table entries mapping the page
+    count: usize,
+}
+
+/// The page cache holds the pages of the read-only file mappings, so
+/// every process running the same binary shares its text. A page is
+/// released when no page table maps it anymore.
+#[derive(Debug, Default)]
+pub struct PageCache {
+    /// The physical page holding each page of file
+    pages: BTreeMap<(InodeId, usize), Page<Phys>>,
+    /// The users of each physical page
+    entries: BTreeMap<Page<Phys>, CachedPage>,
+}
+
+impl PageCache {
+    /// Take a reference on the cached page `key`, if there is one
+    pub fn get(&mut self, key: (InodeId, usize)) -> Option<Page<Phys>> {
+        let page = *self.pages.get(&key)?;
+
+        self.entries
+            .get_mut(&page)
+            .expect("unreferenced cached page")
+            .count += 1;
+        Some(page)
+    }
+
+    /// Register `page` holding the page of file `key`, with one reference
+    pub fn insert(&mut self, key: (InodeId, usize), page: Page<Phys>) -> Result<()> {
+        self.pages.try_insert(key, page)?;
+        if let Err(e) = self.entries.try_insert(page, CachedPage { key, count: 1 }) {
+            self.pages.remove(&key);
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Take one more reference on the cached `page`
+    pub fn acquire(&mut self, page: Page<Phys>) {
+        self.entries
+            .get_mut(&page)
+            .expect("not a cached page")
+            .count += 1;
+    }
+
+    /// Drop a reference on the cached `page`, freeing it with the last one
+    pub fn release(&mut self, page: Page<Phys>) {
+        let entry = self.entries.get_mut(&page).expect("not a cached page");
+
+        entry.count -= 1;
+        if entry.count == 0 {
+            let key = entry.key;
+            self.entries.remove(&page);
+            // The key may already lead to a newer page of the file
+            if self.pages.get(&key) == Some(&page) {
+                self.pages.remove(&key);
+            }
+            unsafe {
+                PHYSICAL_ALLOCATOR
+                    .as_mut()
+                    .unwrap()
+                    .free(page)
+                    .expect("cached page was never allocated");
+            }
+        }
+    }
+
+    /// Detach the pages of the file `inode_id` from the cache, because
+    /// its content changed or its inode is freed and may be reused. The
+    /// processes mapping them keep them until they unmap them, the next
+    /// mappings read the file again.
+    pub fn invalidate(&mut self, inode_id: InodeId) {
+        while let Some(key) = self
+            .pages
+            .range((inode_id, 0)..=(inode_id, usize::MAX))
+            .next()
+            .map(|(key, _)| *key)
+        {
+            self.pages.remove(&key);
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref PAGE_CACHE: DeadMutex<PageCache> = DeadMutex::new(PageCache::default());
+}
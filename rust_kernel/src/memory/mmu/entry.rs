@@ -53,6 +53,14 @@ bitflags! {
         /// if set, prevents the TLB from updating the address in its cache if CR3 is reset. Note, that the page global enable bit in CR4 must be set to enable this feature.
         const GLOBAL = 1 << 8;
         const VALLOC = 1 << 9;
+
+        /// The page belongs to a file mapping and is loaded from the
+        /// file on its first access, see memory::file_mapping.
+        const LAZY = 1 << 10;
+
+        /// The physical page is owned by the page cache and shared
+        /// with the other processes mapping the same file.
+        const SHARED = 1 << 11;
     }
 }
 
@@ -1,9 +1,11 @@
 //! This module contains the code related to the page directory and its page directory entries, which are the highest abstraction paging-related data structures (for the cpu)
 //! See https://wiki.osdev.org/Paging for relevant documentation.
 use super::_read_cr3;
+use super::invalidate_page;
 use super::page_table::PageTable;
 use super::{Entry, _enable_paging, BIOS_PAGE_TABLE, PAGE_TABLES};
 use crate::memory::allocator::{HIGH_KERNEL_MEMORY, PHYSICAL_ALLOCATOR};
+use crate::memory::file_mapping::PAGE_CACHE;
 use crate::memory::tools::*;
 use alloc::boxed::Box;
 use core::mem::size_of;
@@ -62,6 +64,7 @@ impl PageDirectory {
     }
 
     // Very very dummy fork ( no copy on write, a lot of context switch and a page per page approach )
+    // Only the pages of the page cache are shared, and the pages not loaded yet stay so.
     pub unsafe fn fork(&self) -> Result<Box<Self>> {
         #[allow(unused_assignments)]
         let mut mem_tmp = [0; PAGE_SIZE];
@@ -76,9 +79,18 @@ impl PageDirectory {
                 // parcour the user page table
                 for j in 0..1024 {
                     let entry = page_table[j];
-                    if entry.contains(Entry::PRESENT) {
+                    let virt = page + NbrPages(j);
+                    if entry.contains(Entry::SHARED) {
+                        PAGE_CACHE.lock().acquire(entry.entry_page());
+                        child.as_ref().context_switch();
+                        let res = child.map_page(virt, entry.entry_page(), entry);
+                        self.context_switch();
+                        if let Err(e) = res {
+                            PAGE_CACHE.lock().release(entry.entry_page());
+                            return Err(e);
+                        }
+                    } else if entry.contains(Entry::PRESENT) {
                         // get the memory
-                        let virt = page + NbrPages(j);
                         let mem = virt.to_addr().0 as *mut [u8; PAGE_SIZE];
                         mem_tmp = *mem;
 
@@ -87,14 +99,18 @@ impl PageDirectory {
                             .as_mut()
                             .unwrap()
                             .alloc(PAGE_SIZE.into(), AllocFlags::USER_MEMORY)?;
-                        // TODO: Respect the origin layout
                         child.map_page(
                             virt,
                             phys,
-                            Entry::PRESENT | Entry::READ_WRITE | Entry::USER,
+                            entry & (Entry::PRESENT | Entry::READ_WRITE | Entry::USER),
                         )?;
                         *(virt.to_addr().0 as *mut [u8; PAGE_SIZE]) = mem_tmp;
                         self.context_switch();
+                    } else if entry.contains(Entry::LAZY) {
+                        child.as_ref().context_switch();
+                        let res = child.map_page(virt, Page::new(0), entry);
+                        self.context_switch();
+                        res?;
                     }
                 }
             }
@@ -115,7 +131,11 @@ impl PageDirectory {
             if self[i].contains(Entry::PRESENT) {
                 let page_table = self.get_page_table_trick(page).expect("can't happen");
                 for j in 0..1024 {
-                    if page_table[j].contains(Entry::PRESENT) {
+                    if page_table[j].contains(Entry::SHARED) {
+                        // The page cache owns the page
+                        PAGE_CACHE.lock().release(page_table[j].entry_page());
+                        remaining_pages = NbrPages(0);
+                    } else if page_table[j].contains(Entry::PRESENT) {
                         if page_table[j].entry_addr() != temporary_addr
                             || remaining_pages == NbrPages(0)
                         {
@@ -154,10 +174,32 @@ impl PageDirectory {
 
         // Be careful, reseting the flags of a page_table[pt_index] remove automaticely its physical entry addr (seems to be a dev error)
         let entry_addr = page_table[pt_index].entry_addr();
-        page_table[pt_index] = entry | Entry::PRESENT;
+        // The page cache keeps owning its pages
+        let shared = page_table[pt_index] & Entry::SHARED;
+        page_table[pt_index] = entry | shared | Entry::PRESENT;
         page_table[pt_index].set_entry_addr(entry_addr);
     }
 
+    /// Set the entry of `page` in its already allocated page table. It
+    /// only borrows the page directory since the page table is reached
+    /// through the self-mapping trick: the pages of a file mapping are
+    /// loaded while the user pointers are checked.
+    pub unsafe fn set_lazy_entry(
+        &self,
+        page: Page<Virt>,
+        physp: Page<Phys>,
+        entry: Entry,
+    ) -> Result<()> {
+        let page_table = self
+            .get_page_table_trick(page)
+            .ok_or(MemoryError::PageTableNotPresent)?;
+
+        page_table[page.pt_index()] = entry;
+        page_table[page.pt_index()].set_entry_page(physp);
+        invalidate_page(page);
+        Ok(())
+    }
+
     /// This is a trick that ensures that the page tables are mapped into virtual memory at address 0xFFC00000 .
     /// The idea is that the last Entry points to self, viewed as a Page Table.
     /// See [Osdev](https://wiki.osdev.org/Memory_Management_Unit)
@@ -23,10 +23,11 @@ pub use vfs::VFS;
 
 mod global_time;
 use global_time::{GlobalTime, GLOBAL_TIME};
+mod rusage;
 
-use core::convert::{TryFrom, TryInto};
+use core::convert::TryInto;
 use thread_group::Credentials;
-use vfs::Path;
+use vfs::Cwd;
 
 mod sync;
 
@@ -52,7 +53,7 @@ impl<T> IpcResult<T> {
 }
 
 pub use process::{
-    get_file_content, KernelProcess, Process, ProcessArguments, ProcessOrigin, UserProcess,
+    ExecutableFile, KernelProcess, Process, ProcessArguments, ProcessOrigin, UserProcess,
 };
 pub use safe_ffi::{CString, CStringArray};
 
@@ -113,8 +114,7 @@ pub fn start(filename: &str, argv: &[&str], envp: &[&str]) -> ! {
     let path = filename
         .try_into()
         .expect("The path of the init program is not valid");
-    let file = get_file_content(&Path::try_from("/").unwrap(), &Credentials::ROOT, path)
-        .expect("Cannot syncing");
+    let file = ExecutableFile::open(&Cwd::new(), &Credentials::ROOT, path).expect("Cannot syncing");
     SCHEDULER
         .lock()
         .add_user_process(
@@ -122,10 +122,15 @@ pub fn start(filename: &str, argv: &[&str], envp: &[&str]) -> ! {
             unsafe {
                 UserProcess::new(
                     ProcessOrigin::Elf(&file),
-                    Some(ProcessArguments::new(
-                        argv.try_into().expect("argv creation failed"),
-                        envp.try_into().expect("envp creation failed"),
-                    )),
+                    Some(
+                        ProcessArguments::new(
+                            argv.try_into().expect("argv creation failed"),
+                            envp.try_into().expect("envp creation failed"),
+                            &Credentials::ROOT,
+                            0,
+                        )
+                        .expect("arguments creation failed"),
+                    ),
                 )
             }
             .expect("Unexpected error when parsing ELF file"),
@@ -3,13 +3,18 @@
 use super::global_time::{TimeSession, GLOBAL_TIME};
 use super::process::CpuState;
 use super::scheduler::{Scheduler, SCHEDULER};
+use super::signal_interface::SigInfo;
 use super::syscall::sys_kill;
 use libc_binding::Signum;
+use libc_binding::{FPE_INTDIV, SEGV_ACCERR, SEGV_MAPERR};
 
 use core::ffi::c_void;
 use elf_loader::SymbolTable;
+use fallible_collections::try_vec;
 use interrupts::idt::{GateType, IdtGateEntry, InterruptTable};
 
+use crate::memory::address_space::PageIn;
+use crate::memory::tools::{MemoryError, Page, Virt, PAGE_SIZE};
 use crate::memory::{AddressSpace, KERNEL_VIRTUAL_PAGE_ALLOCATOR};
 use crate::panic::{get_page_fault_origin, qemu_check, trace_back};
 
@@ -207,6 +212,40 @@ fn trace_process(
     Ok(())
 }
 
+/// Load `page` of the current process on its first access. The pages
+/// read from a file are read like in a syscall: with the scheduler
+/// unlocked and the interruptions enabled, since the read takes the
+/// VFS and may wait for the disk.
+unsafe fn page_in(page: Page<Virt>) -> Result<(), MemoryError> {
+    let prepared = SCHEDULER
+        .lock()
+        .current_thread()
+        .unwrap_process()
+        .get_virtual_allocator()
+        .prepare_page_in(page)?;
+    let kind = match prepared {
+        PageIn::Done(kind) => kind,
+        PageIn::Read(mapping) => {
+            let mut content = try_vec![0; PAGE_SIZE]?;
+
+            interrupts::enable();
+            let read = crate::unpreemptible_context!({ mapping.read(page, &mut content) });
+            interrupts::disable();
+            read?;
+            SCHEDULER
+                .lock()
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator()
+                .finish_page_in(page, &mapping, &content)?
+        }
+    };
+    let mut scheduler = SCHEDULER.lock();
+    scheduler.current_thread_mut().usage.count_fault(kind);
+    scheduler.current_thread_group_mut().usage.count_fault(kind);
+    Ok(())
+}
+
 /// Global Cpu Isr Interrupt Handler
 /// This function returns a pointer on a process stack to follow
 #[no_mangle]
@@ -243,6 +282,18 @@ unsafe extern "C" fn cpu_isr_interrupt_handler(cpu_state: *mut CpuState) -> u32
             .as_mut()
             .unwrap()
             .update_global_time(TimeSession::User);
+        // The pages of the file mappings are loaded on their first access
+        if (*cpu_state).cpu_isr_reserved == 14 {
+            let page = Page::containing(Virt(_read_cr2() as usize));
+            let paged_in = page_in(page).is_ok();
+            if paged_in {
+                GLOBAL_TIME
+                    .as_mut()
+                    .unwrap()
+                    .update_global_time(TimeSession::System);
+                return cpu_state as u32;
+            }
+        }
         // Temporaly display a debug
         let page_fault_cause = get_page_fault_origin((*cpu_state).err_code_reserved);
         log::warn!("{}     address: {:#X?}", page_fault_cause, _read_cr2());
@@ -278,10 +329,29 @@ unsafe extern "C" fn cpu_isr_interrupt_handler(cpu_state: *mut CpuState) -> u32
 
         // Send a kill signum to the current process: kernel-sodo mode
         let current_thread_pid = SCHEDULER.lock().current_task_id().0;
-        let _res = match (*cpu_state).cpu_isr_reserved {
-            0 => sys_kill(current_thread_pid as i32, Signum::SIGFPE as u32),
-            14 => sys_kill(current_thread_pid as i32, Signum::SIGSEGV as u32),
-            _ => {
+        let fault = match (*cpu_state).cpu_isr_reserved {
+            0 => Some((
+                Signum::SIGFPE,
+                SigInfo::fault(FPE_INTDIV as i32, (*cpu_state).eip),
+            )),
+            14 => {
+                // The present bit of the error code is set on a protection violation
+                let si_code = if (*cpu_state).err_code_reserved & 1 != 0 {
+                    SEGV_ACCERR
+                } else {
+                    SEGV_MAPERR
+                };
+                Some((Signum::SIGSEGV, SigInfo::fault(si_code as i32, _read_cr2())))
+            }
+            _ => None,
+        };
+        let _res = match fault {
+            Some((signum, info)) => SCHEDULER
+                .lock()
+                .current_thread_mut()
+                .signal
+                .generate_signal_info(signum, info),
+            None => {
                 log::warn!(
                     "{}",
                     CPU_EXCEPTIONS[(*cpu_state).cpu_isr_reserved as usize].1
@@ -4,8 +4,8 @@ use super::SysResult;
 
 use super::scheduler::Scheduler;
 use super::vfs;
-use super::vfs::Path;
-use super::vfs::{InodeId, VFS};
+use super::vfs::{Cwd, Path};
+use super::vfs::{DirectoryEntryId, InodeId, VFS};
 use super::Credentials;
 use super::IpcResult;
 
@@ -14,8 +14,8 @@ pub use ipc::{socket::Whom, ConnectedSocket, FifoDriver, FifoFileOperation, Pipe
 
 use alloc::sync::Arc;
 use libc_binding::{
-    gid_t, off_t, stat, statfs, termios, uid_t, Errno, FileType, IoctlCmd, OpenFlags, Pid,
-    ShutDownOption, Whence,
+    gid_t, off_t, stat, statfs, termios, uid_t, Errno, FileType, InotifyMask, IoctlCmd, OpenFlags,
+    Pid, ShutDownOption, Whence,
 };
 use sync::dead_mutex::DeadMutex;
 
@@ -34,6 +34,13 @@ pub trait FileOperation: core::fmt::Debug + Send {
 
     fn set_file_offset(&mut self, _offset: u64) {}
 
+    /// Keep the root of the mount which the file was opened through,
+    /// whose flags apply to it. Return false if it is not kept, else
+    /// a file open for writing gives it back to VFS::close_write
+    fn set_mount_root(&mut self, _mount_root: DirectoryEntryId) -> bool {
+        false
+    }
+
     fn lseek(&mut self, _offset: off_t, _whence: Whence) -> SysResult<off_t> {
         Err(Errno::EINVAL)
     }
@@ -48,6 +55,30 @@ pub trait FileOperation: core::fmt::Debug + Send {
         Err(Errno::ENOSYS)
     }
 
+    /// Read at `offset` without moving the file offset, only the
+    /// seekable files support it and never block
+    fn pread(&mut self, buf: &mut [u8], offset: off_t) -> SysResult<u32> {
+        let saved_offset = self.lseek(0, Whence::SeekCur).map_err(|_| Errno::ESPIPE)?;
+        self.lseek(offset, Whence::SeekSet)?;
+        let res = self.read(buf);
+        self.lseek(saved_offset, Whence::SeekSet)?;
+        match res? {
+            IpcResult::Done(count) | IpcResult::Wait(count, _) => Ok(count),
+        }
+    }
+
+    /// Write at `offset` without moving the file offset, only the
+    /// seekable files support it and never block
+    fn pwrite(&mut self, buf: &[u8], offset: off_t) -> SysResult<u32> {
+        let saved_offset = self.lseek(0, Whence::SeekCur).map_err(|_| Errno::ESPIPE)?;
+        self.lseek(offset, Whence::SeekSet)?;
+        let res = self.write(buf);
+        self.lseek(saved_offset, Whence::SeekSet)?;
+        match res? {
+            IpcResult::Done(count) | IpcResult::Wait(count, _) => Ok(count),
+        }
+    }
+
     fn fstat(&mut self) -> SysResult<stat> {
         let inode_id = self.get_inode_id()?;
         VFS.lock()
@@ -88,17 +119,38 @@ pub trait FileOperation: core::fmt::Debug + Send {
         Err(Errno::ENOSYS)
     }
 
+    /// Write back the file to its storage device
+    fn fsync(&mut self) -> SysResult<u32> {
+        Err(Errno::EINVAL)
+    }
+
+    /// Set the size of the file to `length`
+    fn ftruncate(&mut self, _length: off_t) -> SysResult<u32> {
+        Err(Errno::EINVAL)
+    }
+
+    /// Fill `buf` with the next linux_dirent64 of an open directory
+    fn getdents(&mut self, _buf: &mut [u8]) -> SysResult<u32> {
+        Err(Errno::ENOTDIR)
+    }
+
+    /// Get the current directory entry of an open directory, the start
+    /// of the relative paths of the *at() syscalls and of fchdir
+    fn get_directory_entry(&mut self) -> SysResult<DirectoryEntryId> {
+        Err(Errno::ENOTDIR)
+    }
+
     fn ioctl(&mut self, _scheduler: &Scheduler, _cmd: IoctlCmd, _arg: u32) -> SysResult<u32> {
         Err(Errno::ENOSYS)
     }
 
-    fn bind(&mut self, _cwd: &Path, _creds: &Credentials, _sockaddr: Path) -> SysResult<u32> {
+    fn bind(&mut self, _cwd: &Cwd, _creds: &Credentials, _sockaddr: Path) -> SysResult<u32> {
         Err(Errno::ENOTSOCK)
     }
 
     fn connect(
         &mut self,
-        _cwd: &Path,
+        _cwd: &Cwd,
         _creds: &Credentials,
         _sockaddr: Path,
     ) -> SysResult<IpcResult<()>> {
@@ -134,6 +186,23 @@ pub trait FileOperation: core::fmt::Debug + Send {
     fn shutdown(&mut self, _option: ShutDownOption) -> SysResult<()> {
         Err(Errno::ENOTSOCK)
     }
+
+    /// Watch the events `mask` on the file `path`, only for the
+    /// inotify instances
+    fn add_watch(
+        &mut self,
+        _cwd: &Cwd,
+        _creds: &Credentials,
+        _path: Path,
+        _mask: InotifyMask,
+    ) -> SysResult<u32> {
+        Err(Errno::EINVAL)
+    }
+
+    /// Remove the watch `wd` of an inotify instance
+    fn rm_watch(&mut self, _wd: i32) -> SysResult<u32> {
+        Err(Errno::EINVAL)
+    }
 }
 
 #[derive(Debug)]
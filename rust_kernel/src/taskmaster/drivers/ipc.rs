@@ -1,5 +1,6 @@
 use super::get_file_op_uid;
 use super::Credentials;
+use super::Cwd;
 use super::InodeId;
 use super::IpcResult;
 use super::Path;
@@ -9,6 +10,10 @@ use super::{Driver, FileOperation};
 
 use super::vfs;
 
+use const_assert::const_assert;
+use core::cmp;
+use libc_binding::PIPE_BUF;
+
 pub mod pipe;
 pub use pipe::Pipe;
 
@@ -54,5 +59,19 @@ impl core::fmt::Debug for Buf {
 
 /// Buf implementation
 impl Buf {
-    pub const BUF_SIZE: usize = 128;
+    pub const BUF_SIZE: usize = 8192;
+
+    /// Get how many of `len` bytes can be written when `used` bytes are
+    /// already in the buffer: the writes of at most PIPE_BUF bytes are
+    /// never split, so that they are not interleaved with others
+    pub fn writable_len(used: usize, len: usize) -> usize {
+        let free = Self::BUF_SIZE - used;
+        if len <= PIPE_BUF as usize && len > free {
+            0
+        } else {
+            cmp::min(len, free)
+        }
+    }
 }
+
+const_assert!(PIPE_BUF as usize <= Buf::BUF_SIZE);
@@ -212,7 +212,7 @@ impl FileOperation for FifoFileOperationData {
             return Err(Errno::EPIPE);
         }
 
-        let min = cmp::min(buf.len(), Buf::BUF_SIZE - self.current_index);
+        let min = Buf::writable_len(self.current_index, buf.len());
 
         self.buf[self.current_index..self.current_index + min].copy_from_slice(&buf[..min]);
         self.current_index += min;
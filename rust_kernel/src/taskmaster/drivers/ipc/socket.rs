@@ -6,6 +6,7 @@ use crate::taskmaster::syscall::socket;
 use super::get_file_op_uid;
 use super::Buf;
 use super::Credentials;
+use super::Cwd;
 use super::Driver;
 use super::FileOperation;
 use super::InodeId;
@@ -4,6 +4,7 @@ use crate::taskmaster::syscall::socket;
 use super::get_file_op_uid;
 use super::Buf;
 use super::Credentials;
+use super::Cwd;
 use super::FileOperation;
 use super::InodeId;
 use super::IpcResult;
@@ -411,7 +412,7 @@ impl FileOperation for ConnectedSocket {
         self.send_to_without_creds(buf, 0)
     }
 
-    fn bind(&mut self, cwd: &Path, creds: &Credentials, sockaddr: Path) -> SysResult<u32> {
+    fn bind(&mut self, cwd: &Cwd, creds: &Credentials, sockaddr: Path) -> SysResult<u32> {
         let mut vfs = VFS.lock();
         let inode_id = vfs.mknod(cwd, creds, sockaddr.try_clone()?, FileType::UNIX_SOCKET)?;
 
@@ -435,7 +436,7 @@ impl FileOperation for ConnectedSocket {
 
     fn connect(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         sockaddr: Path,
     ) -> SysResult<IpcResult<()>> {
@@ -3,6 +3,7 @@ use crate::taskmaster::syscall::socket;
 
 use super::get_file_op_uid;
 use super::Credentials;
+use super::Cwd;
 use super::FileOperation;
 use super::InodeId;
 use super::IpcResult;
@@ -138,7 +139,7 @@ impl FileOperation for SocketDgram {
         unimplemented!();
     }
 
-    fn bind(&mut self, cwd: &Path, creds: &Credentials, sockaddr: Path) -> SysResult<u32> {
+    fn bind(&mut self, cwd: &Cwd, creds: &Credentials, sockaddr: Path) -> SysResult<u32> {
         let mut vfs = VFS.lock();
         let inode_id = vfs.mknod(cwd, creds, sockaddr.try_clone()?, FileType::UNIX_SOCKET)?;
 
@@ -162,7 +163,7 @@ impl FileOperation for SocketDgram {
 
     fn connect(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         sockaddr: Path,
     ) -> SysResult<IpcResult<()>> {
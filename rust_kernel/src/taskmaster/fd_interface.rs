@@ -1,70 +1,126 @@
-use super::drivers::FileOperation;
+use super::drivers::{get_file_op_uid, FileOperation};
+use super::scheduler::Pid;
 use super::syscall::socket;
 use super::thread_group::Credentials;
-use super::vfs::Path;
+use super::vfs::{Cwd, InotifyFileOperation, LockOwner, Path};
 use super::IpcResult;
-/// The User File Descriptor are sorted into a Binary Tree
-/// Key is the user number and value the structure FileDescriptor
 use super::SysResult;
 use super::VFS;
 
+use core::cmp;
 use core::convert::TryFrom;
 
-use libc_binding::{Errno, FileType, OpenFlags};
+use libc_binding::{off_t, Errno, FileType, OpenFlags};
 
 use super::drivers::ipc::{ConnectedSocket, Pipe, SocketDgram};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
-use fallible_collections::btree::BTreeMap;
+use fallible_collections::btree::BTreeSet;
 use fallible_collections::TryClone;
 
-use try_clone_derive::TryClone;
-
 use sync::{DeadMutex, DeadMutexGuard};
 
 pub type Fd = u32;
 
-#[derive(Debug, TryClone)]
+/// The User File Descriptors are stored in a table indexed by their
+/// number. The table grows as file descriptors are allocated, up to
+/// the RLIMIT_NOFILE limit of the process.
+#[derive(Debug)]
 pub struct FileDescriptorInterface {
-    user_fd_list: BTreeMap<Fd, FileDescriptor>,
+    user_fd_list: Vec<Option<FileDescriptor>>,
+    /// The free slots of `user_fd_list`: the lowest available file
+    /// descriptor is the first of them, or the end of the table.
+    free_fds: BTreeSet<Fd>,
+    /// The file descriptors shall be lower than this limit
+    max_fds: Fd,
 }
 
 /// Main implementation
 impl FileDescriptorInterface {
-    const MAX_FD: Fd = 128;
+    /// Default soft limit on the number of file descriptors
+    pub const OPEN_MAX: Fd = 1024;
+    /// Default hard limit on the number of file descriptors
+    pub const OPEN_MAX_HARD: Fd = 4096;
+    /// No process can have more file descriptors than this
+    pub const NR_OPEN: Fd = 1 << 20;
 
     /// Global constructor
     pub fn new() -> Self {
         Self {
-            // New BTreeMap does not allocate memory
-            user_fd_list: BTreeMap::new(),
+            // New Vec and BTreeSet do not allocate memory
+            user_fd_list: Vec::new(),
+            free_fds: BTreeSet::new(),
+            max_fds: Self::OPEN_MAX,
         }
     }
 
     /// Clear all the owned content into the File Descriptor Interface
     pub fn delete(&mut self) {
         self.user_fd_list.clear();
+        self.free_fds.clear();
+    }
+
+    /// Get the limit on the file descriptor numbers
+    pub fn get_max_fds(&self) -> Fd {
+        self.max_fds
+    }
+
+    /// Set the limit on the file descriptor numbers. The file
+    /// descriptors above the new limit stay open.
+    pub fn set_max_fds(&mut self, max_fds: Fd) {
+        self.max_fds = cmp::min(max_fds, Self::NR_OPEN);
+    }
+
+    fn get(&self, fd: Fd) -> SysResult<&FileDescriptor> {
+        self.user_fd_list
+            .get(fd as usize)
+            .and_then(|slot| slot.as_ref())
+            .ok_or(Errno::EBADF)
+    }
+
+    fn get_mut(&mut self, fd: Fd) -> SysResult<&mut FileDescriptor> {
+        self.user_fd_list
+            .get_mut(fd as usize)
+            .and_then(|slot| slot.as_mut())
+            .ok_or(Errno::EBADF)
     }
 
     pub fn get_file_operation(&self, fd: Fd) -> SysResult<DeadMutexGuard<dyn FileOperation>> {
-        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
-        Ok(elem.file_operation.lock())
+        Ok(self.get(fd)?.file_operation.lock())
+    }
+
+    /// Get the file descriptor `fd`, to share its open file
+    pub fn get_file_descriptor(&self, fd: Fd) -> SysResult<&FileDescriptor> {
+        self.get(fd)
+    }
+
+    /// Get the close-on-exec flag of `fd`
+    pub fn get_cloexec(&self, fd: Fd) -> SysResult<bool> {
+        Ok(self.get(fd)?.cloexec)
+    }
+
+    /// Set or clear the close-on-exec flag of `fd`
+    pub fn set_cloexec(&mut self, fd: Fd, cloexec: bool) -> SysResult<()> {
+        self.get_mut(fd)?.cloexec = cloexec;
+        Ok(())
     }
 
     /// Open a file and give a file descriptor
     pub fn open(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         filename: &str,
         flags: OpenFlags,
         mode: FileType,
+        umask: FileType,
     ) -> SysResult<IpcResult<Fd>> {
         let path = super::vfs::Path::try_from(filename)?;
 
         let file_operator = VFS
             .lock()
-            .open(cwd, creds, path.try_clone()?, flags, mode)?;
+            .open(cwd, creds, path.try_clone()?, flags, mode, umask)?;
         let path = VFS.lock().resolve_path(cwd, creds, &path)?;
         match file_operator {
             IpcResult::Done(file_operator) => {
@@ -78,25 +134,48 @@ impl FileDescriptorInterface {
         }
     }
 
-    /// Clone one file descriptor
+    /// Close one file descriptor
     pub fn close_fd(&mut self, fd: Fd) -> SysResult<()> {
-        self.user_fd_list.remove(&fd).ok_or::<Errno>(Errno::EBADF)?;
+        self.take_fd(fd)?;
         Ok(())
     }
 
+    /// Close the file descriptors whose close-on-exec flag is set,
+    /// the record locks of the process `pid` on their files are
+    /// removed as by close()
+    pub fn close_on_exec(&mut self, pid: Pid) {
+        for fd in 0..self.user_fd_list.len() as Fd {
+            match self.get(fd) {
+                Ok(descriptor) if descriptor.cloexec => {}
+                _ => continue,
+            }
+            let inode_id = self
+                .get_file_operation(fd)
+                .ok()
+                .and_then(|file_operation| file_operation.get_inode_id().ok());
+            let _r = self.take_fd(fd);
+            if let Some(inode_id) = inode_id {
+                VFS.lock().release_locks(inode_id, LockOwner::Process(pid));
+            }
+        }
+    }
+
     /// Read something from the File Descriptor: Can block
     /// Important ! When in blocked syscall, the slice must be verified before read op and
     /// we have fo find a solution to avoid the DeadLock when multiple access to fd occured
     pub fn read(&mut self, fd: Fd, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
-        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+        let elem = self.get(fd)?;
 
         if !elem.flags.is_open_for_read() {
             return Err(Errno::EBADF);
         }
         let res = elem.file_operation.lock().read(buf);
         if elem.flags.contains(OpenFlags::O_NONBLOCK) {
-            if let Ok(IpcResult::Wait(r, _)) = res {
-                return Ok(IpcResult::Done(r));
+            match res {
+                // Nothing to read yet
+                Ok(IpcResult::Wait(0, _)) if !buf.is_empty() => return Err(Errno::EAGAIN),
+                Ok(IpcResult::Wait(r, _)) => return Ok(IpcResult::Done(r)),
+                _ => {}
             }
         }
         res
@@ -106,7 +185,7 @@ impl FileDescriptorInterface {
     /// Important ! When in blocked syscall, the slice must be verified before write op and
     /// we have fo find a solution to avoid the DeadLock when multiple access to fd occured
     pub fn write(&mut self, fd: Fd, buf: &[u8]) -> SysResult<IpcResult<u32>> {
-        let elem = self.user_fd_list.get(&fd).ok_or::<Errno>(Errno::EBADF)?;
+        let elem = self.get(fd)?;
 
         if !elem.flags.is_open_for_write() {
             return Err(Errno::EBADF);
@@ -114,52 +193,101 @@ impl FileDescriptorInterface {
         elem.file_operation.lock().write(buf)
     }
 
-    /// Made two File Descriptors connected with a Pipe
-    pub fn new_pipe(&mut self) -> SysResult<(Fd, Fd)> {
+    /// Read from the File Descriptor at `offset`, without using nor moving its offset
+    pub fn pread(&mut self, fd: Fd, buf: &mut [u8], offset: off_t) -> SysResult<u32> {
+        let elem = self.get(fd)?;
+
+        if !elem.flags.is_open_for_read() {
+            return Err(Errno::EBADF);
+        }
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        elem.file_operation.lock().pread(buf, offset)
+    }
+
+    /// Write into the File Descriptor at `offset`, without using nor moving its offset
+    pub fn pwrite(&mut self, fd: Fd, buf: &[u8], offset: off_t) -> SysResult<u32> {
+        let elem = self.get(fd)?;
+
+        if !elem.flags.is_open_for_write() {
+            return Err(Errno::EBADF);
+        }
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        elem.file_operation.lock().pwrite(buf, offset)
+    }
+
+    /// Set the size of the file of the File Descriptor, which must be open for writing
+    pub fn ftruncate(&mut self, fd: Fd, length: off_t) -> SysResult<u32> {
+        let elem = self.get(fd)?;
+
+        if !elem.flags.is_open_for_write() {
+            return Err(Errno::EINVAL);
+        }
+        elem.file_operation.lock().ftruncate(length)
+    }
+
+    /// Made two File Descriptors connected with a Pipe, `flags` may
+    /// contain O_NONBLOCK and O_CLOEXEC
+    pub fn new_pipe(&mut self, flags: OpenFlags) -> SysResult<(Fd, Fd)> {
         let pipe = Arc::try_new(DeadMutex::new(Pipe::new()))?;
         let cloned_pipe = pipe.clone();
-        let pipe_path = Path::try_from(":pipe")?;
+        let pipe_path = anonymous_path("pipe")?;
 
-        let input_fd = self.insert_user_fd(OpenFlags::O_RDONLY, pipe, pipe_path.try_clone()?)?;
+        let input_fd =
+            self.insert_user_fd(OpenFlags::O_RDONLY | flags, pipe, pipe_path.try_clone()?)?;
         let output_fd = self
-            .insert_user_fd(OpenFlags::O_WRONLY, cloned_pipe, pipe_path)
+            .insert_user_fd(OpenFlags::O_WRONLY | flags, cloned_pipe, pipe_path)
             .map_err(|e| {
-                let _r = self.user_fd_list.remove(&input_fd);
+                let _r = self.take_fd(input_fd);
                 e
             })?;
 
         Ok((input_fd, output_fd))
     }
 
-    /// Duplicate one File Descriptor
-    pub fn dup(&mut self, oldfd: Fd, minimum: Option<Fd>) -> SysResult<Fd> {
-        if let Some(elem) = self.user_fd_list.get(&oldfd) {
-            let new_elem = elem.try_clone()?;
-            let newfd = self
-                .get_lower_fd_value(minimum.unwrap_or(0))
-                .ok_or::<Errno>(Errno::EMFILE)?;
+    /// Give a File Descriptor to a new inotify instance, `flags` may
+    /// contain O_NONBLOCK and O_CLOEXEC
+    pub fn new_inotify(&mut self, flags: OpenFlags) -> SysResult<Fd> {
+        let inotify = Arc::try_new(DeadMutex::new(InotifyFileOperation::new()?))?;
+        let inotify_path = Path::try_from("anon_inode:inotify")?;
+
+        self.insert_user_fd(OpenFlags::O_RDONLY | flags, inotify, inotify_path)
+    }
 
-            self.user_fd_list.try_insert(newfd, new_elem)?;
-            return Ok(newfd);
+    /// Duplicate one File Descriptor on the lowest available file
+    /// descriptor greater than or equal to `minimum`, the close-on-exec
+    /// flag of the new one is set to `cloexec`
+    pub fn dup(&mut self, oldfd: Fd, minimum: Option<Fd>, cloexec: bool) -> SysResult<Fd> {
+        if minimum.map_or(false, |minimum| minimum >= self.max_fds) {
+            return Err(Errno::EINVAL);
         }
-        Err(Errno::EBADF)
+        let mut new_elem = self.get(oldfd)?.try_clone()?;
+        new_elem.cloexec = cloexec;
+
+        let newfd = self
+            .get_lower_fd_value(minimum.unwrap_or(0))
+            .ok_or::<Errno>(Errno::EMFILE)?;
+        self.install_fd(newfd, new_elem)?;
+        Ok(newfd)
     }
 
-    /// Duplicate one file descriptor with possible override
-    pub fn dup2(&mut self, oldfd: Fd, newfd: Fd) -> SysResult<Fd> {
-        if newfd > Self::MAX_FD {
+    /// Duplicate one file descriptor with possible override, the
+    /// close-on-exec flag of `newfd` is set to `cloexec`
+    pub fn dup2(&mut self, oldfd: Fd, newfd: Fd, cloexec: bool) -> SysResult<Fd> {
+        if newfd >= self.max_fds {
             return Err(Errno::EBADF);
         }
 
         // If oldfd is not a valid file descriptor, then the call fails, and newfd is not closed.
-        if let Some(elem) = self.user_fd_list.get(&oldfd) {
-            let new_elem = elem.try_clone()?;
-            let _r = self.close_fd(newfd);
+        let mut new_elem = self.get(oldfd)?.try_clone()?;
+        new_elem.cloexec = cloexec;
 
-            self.user_fd_list.try_insert(newfd, new_elem)?;
-            return Ok(newfd);
-        }
-        Err(Errno::EBADF)
+        let _r = self.close_fd(newfd);
+        self.install_fd(newfd, new_elem)?;
+        Ok(newfd)
     }
 
     /// Insert a new User File Descriptor atached to a Kernel File Descriptor:
@@ -171,34 +299,74 @@ impl FileDescriptorInterface {
         path: Path,
     ) -> SysResult<Fd> {
         let user_fd = self.get_lower_fd_value(0).ok_or::<Errno>(Errno::EMFILE)?;
-        self.user_fd_list
-            .try_insert(user_fd, FileDescriptor::new(flags, file_operation, path))?;
+        self.install_fd(user_fd, FileDescriptor::new(flags, file_operation, path))?;
         Ok(user_fd)
     }
 
     /// Get the first available File Descriptor number that is superior to `minimum`
     fn get_lower_fd_value(&self, minimum: Fd) -> Option<Fd> {
-        let mut lower_fd = minimum;
+        let lower_fd = match self.free_fds.range(minimum..).next() {
+            Some(&fd) => fd,
+            None => cmp::max(minimum, self.user_fd_list.len() as Fd),
+        };
+        if lower_fd >= self.max_fds {
+            None
+        } else {
+            Some(lower_fd)
+        }
+    }
 
-        for &key in self.user_fd_list.keys().skip_while(|&key| *key < minimum) {
-            if lower_fd < key {
-                break;
-            } else {
-                lower_fd += 1;
+    /// Put `descriptor` in the free slot `fd`, the table is grown if
+    /// `fd` is beyond its end
+    fn install_fd(&mut self, fd: Fd, descriptor: FileDescriptor) -> SysResult<()> {
+        let len = self.user_fd_list.len() as Fd;
+        if fd < len {
+            self.free_fds.remove(&fd);
+        } else {
+            self.user_fd_list.try_reserve((fd - len + 1) as usize)?;
+            for free_fd in len..fd {
+                if let Err(e) = self.free_fds.try_insert(free_fd) {
+                    for free_fd in len..free_fd {
+                        self.free_fds.remove(&free_fd);
+                    }
+                    return Err(e.into());
+                }
             }
+            self.user_fd_list.resize_with(fd as usize + 1, || None);
         }
-        if lower_fd > Self::MAX_FD {
-            None
+        self.user_fd_list[fd as usize] = Some(descriptor);
+        Ok(())
+    }
+
+    /// Take the file descriptor `fd` out of the table, its slot
+    /// becomes free. The free slots at the end of the table are
+    /// released.
+    fn take_fd(&mut self, fd: Fd) -> SysResult<FileDescriptor> {
+        let descriptor = self
+            .user_fd_list
+            .get_mut(fd as usize)
+            .and_then(|slot| slot.take())
+            .ok_or(Errno::EBADF)?;
+
+        if fd as usize + 1 == self.user_fd_list.len() {
+            while let Some(None) = self.user_fd_list.last() {
+                self.user_fd_list.pop();
+                self.free_fds.remove(&(self.user_fd_list.len() as Fd));
+            }
         } else {
-            Some(lower_fd)
+            // Without memory, the slot is only lost for the lowest
+            // available file descriptor allocation
+            let _r = self.free_fds.try_insert(fd);
         }
+        Ok(descriptor)
     }
 
-    /// Open a Socket
+    /// Open a Socket, `flags` may contain O_NONBLOCK and O_CLOEXEC
     pub fn open_socket(
         &mut self,
         domain: socket::Domain,
         socket_type: socket::SocketType,
+        flags: OpenFlags,
     ) -> SysResult<Fd> {
         let file_operator: Arc<DeadMutex<dyn FileOperation>> = match socket_type {
             socket::SocketType::SockDgram => {
@@ -208,12 +376,18 @@ impl FileDescriptorInterface {
                 Arc::try_new(DeadMutex::new(ConnectedSocket::new(domain, socket_type)?))?
             }
         };
-        let socket_path = Path::try_from(":socket")?;
+        let socket_path = anonymous_path("socket")?;
 
-        self.insert_user_fd(OpenFlags::O_RDWR, file_operator, socket_path)
+        self.insert_user_fd(OpenFlags::O_RDWR | flags, file_operator, socket_path)
     }
 
-    pub fn accept_socket(&mut self, socket_fd: u32) -> SysResult<IpcResult<(u32, Option<Path>)>> {
+    /// Accept a connection on `socket_fd`, `flags` may contain
+    /// O_NONBLOCK and O_CLOEXEC for the new file descriptor
+    pub fn accept_socket(
+        &mut self,
+        socket_fd: u32,
+        flags: OpenFlags,
+    ) -> SysResult<IpcResult<(u32, Option<Path>)>> {
         let mut file_operation = self.get_file_operation(socket_fd)?;
         let res = file_operation.accept()?;
         drop(file_operation);
@@ -223,9 +397,9 @@ impl FileDescriptorInterface {
             IpcResult::Done(socket_stream) => {
                 let socket_stream = socket_stream.expect("socket stream should be there");
                 let sender_path = socket_stream.path.try_clone()?;
-                let socket_path = Path::try_from(":socket")?;
+                let socket_path = anonymous_path("socket")?;
                 let new_fd = self.insert_user_fd(
-                    OpenFlags::O_RDWR,
+                    OpenFlags::O_RDWR | flags,
                     Arc::new(DeadMutex::new(socket_stream)) as Arc<DeadMutex<dyn FileOperation>>,
                     socket_path,
                 )?;
@@ -234,16 +408,44 @@ impl FileDescriptorInterface {
         })
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (&Fd, &FileDescriptor)> {
-        self.user_fd_list.iter()
+    pub fn iter(&self) -> impl Iterator<Item = (Fd, &FileDescriptor)> {
+        self.user_fd_list
+            .iter()
+            .enumerate()
+            .filter_map(|(fd, slot)| slot.as_ref().map(|descriptor| (fd as Fd, descriptor)))
+    }
+}
+
+/// TryClone Boilerplate. The free slots are the same in the clone
+impl TryClone for FileDescriptorInterface {
+    fn try_clone(&self) -> Result<Self, TryReserveError> {
+        let mut free_fds = BTreeSet::new();
+        for &fd in self.free_fds.iter() {
+            free_fds.try_insert(fd)?;
+        }
+        Ok(Self {
+            user_fd_list: self.user_fd_list.try_clone()?,
+            free_fds,
+            max_fds: self.max_fds,
+        })
     }
 }
 
+/// The path shown for the files which are not in the filesystem, as
+/// `pipe:[4]` in /proc/[pid]/fd
+fn anonymous_path(kind: &str) -> SysResult<Path> {
+    Ok(Path::try_from(
+        tryformat!(32, "{}:[{}]", kind, get_file_op_uid())?.as_str(),
+    )?)
+}
+
 /// This structure design a User File Descriptor
 /// We can normally clone the Arc
 #[derive(Debug)]
 pub struct FileDescriptor {
     flags: OpenFlags,
+    /// The FD_CLOEXEC flag: the file descriptor is closed by execve()
+    cloexec: bool,
     file_operation: Arc<DeadMutex<dyn FileOperation>>,
 
     /// The resolved open path of the corresponding file.
@@ -258,6 +460,7 @@ impl TryClone for FileDescriptor {
         self.file_operation.lock().register(self.flags);
         Ok(Self {
             flags: self.flags.clone(),
+            cloexec: self.cloexec,
             file_operation: self.file_operation.clone(),
             path: self.path.try_clone()?,
         })
@@ -272,9 +475,13 @@ impl FileDescriptor {
         file_operation: Arc<DeadMutex<dyn FileOperation>>,
         path: Path,
     ) -> Self {
+        let cloexec = flags.contains(OpenFlags::O_CLOEXEC);
+        let flags = flags - OpenFlags::O_CLOEXEC;
+
         file_operation.lock().register(flags);
         Self {
             flags,
+            cloexec,
             file_operation,
             path,
         }
@@ -283,11 +490,27 @@ impl FileDescriptor {
     pub fn get_open_path(&self) -> &Path {
         &self.path
     }
+
+    pub fn get_flags(&self) -> OpenFlags {
+        self.flags
+    }
+
+    pub fn get_file_operation(&self) -> Arc<DeadMutex<dyn FileOperation>> {
+        self.file_operation.clone()
+    }
 }
 
 /// Drop boilerplate for an FileDescriptor structure. Decremente reference
 impl Drop for FileDescriptor {
     fn drop(&mut self) {
         self.file_operation.lock().unregister(self.flags);
+        // The flock() locks of the open file description are released
+        // with its last file descriptor
+        if Arc::strong_count(&self.file_operation) == 1 {
+            if let Ok(inode_id) = self.file_operation.lock().get_inode_id() {
+                VFS.lock()
+                    .release_locks(inode_id, LockOwner::open_file(&self.file_operation));
+            }
+        }
     }
 }
@@ -2,7 +2,6 @@
 
 use core::ops::AddAssign;
 use core::time::Duration;
-use libc_binding::{rusage, timeval};
 
 use crate::drivers::PIT0;
 
@@ -79,22 +78,6 @@ impl AddAssign for ProcessDuration {
     }
 }
 
-/// From boilerplate from ProcessDuration to rusage
-impl From<ProcessDuration> for rusage {
-    fn from(process_duration: ProcessDuration) -> Self {
-        Self {
-            ru_utime: timeval {
-                tv_sec: process_duration.user_time.as_secs() as i32,
-                tv_usec: process_duration.user_time.subsec_micros(),
-            },
-            ru_stime: timeval {
-                tv_sec: process_duration.system_time.as_secs() as i32,
-                tv_usec: process_duration.system_time.subsec_micros(),
-            },
-        }
-    }
-}
-
 /// Main Global Time implementation
 impl GlobalTime {
     const TEST_PERIOD_DIVISOR: u64 = 10;
@@ -3,7 +3,7 @@ use super::message::push_message;
 use super::process::get_file_content;
 use super::scheduler::Scheduler;
 use super::thread_group::Credentials;
-use super::vfs::{Path, VFS};
+use super::vfs::{Cwd, Path, VFS};
 use super::{IpcResult, SysResult};
 
 use alloc::boxed::Box;
@@ -305,13 +305,16 @@ pub fn symbol_list_test() {
 #[no_mangle]
 #[link_section = ".kernel_exported_functions"]
 pub fn add_syslog_entry(entry: &str) -> Result<(), Errno> {
-    let cwd = Path::try_from("/")?;
+    let cwd = Cwd::new();
     let path = Path::try_from("/var/syslog")?;
     let mode = FileType::from_bits(0o600).expect("Cannot set FileType");
     let flags = OpenFlags::O_WRONLY | OpenFlags::O_CREAT | OpenFlags::O_APPEND;
     let creds = &Credentials::ROOT;
     VFS.force_unlock(); /* just in case of. This mutex could become very problematic */
-    let file_operator = match VFS.lock().open(&cwd, creds, path, flags, mode)? {
+    let file_operator = match VFS
+        .lock()
+        .open(&cwd, creds, path, flags, mode, FileType::empty())?
+    {
         IpcResult::Done(file_operator) => file_operator,
         IpcResult::Wait(file_operator, _) => file_operator,
     };
@@ -411,9 +414,5 @@ fn load_module(content: &[u8]) -> SysResult<(u32, Option<Box<SymbolTable>>, Allo
 /// Get Data of a module
 fn get_module_raw_content(mod_pathname: &str) -> SysResult<Vec<u8>> {
     let path = mod_pathname.try_into()?;
-    get_file_content(
-        &Path::try_from("/").expect("no root"),
-        &Credentials::ROOT,
-        path,
-    )
+    get_file_content(&Cwd::new(), &Credentials::ROOT, path)
 }
@@ -5,6 +5,7 @@ use tss::TSS;
 
 use super::safe_ffi::CStringArray;
 use super::syscall::clone::CloneFlags;
+use super::thread_group::Credentials;
 use super::SysResult;
 use sync::{DeadMutex, DeadMutexGuard};
 
@@ -14,16 +15,23 @@ use alloc::vec::Vec;
 use core::ffi::c_void;
 use core::slice;
 
-use elf_loader::{SegmentType, SymbolTable};
-use fallible_collections::try_vec;
+use elf_loader::{ObjectType, ProgramHeader, SegmentType, SymbolTable};
+use fallible_collections::{try_vec, FallibleVec};
 use i386::{BaseRegisters, Eflags, PrivilegeLevel};
-use libc_binding::{c_char, Errno};
-
-use crate::elf_loader::load_elf;
+use libc_binding::{
+    c_char, Errno, ADDR_NO_RANDOMIZE, AT_BASE, AT_EGID, AT_ENTRY, AT_EUID, AT_FLAGS, AT_GID,
+    AT_NULL, AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_RANDOM, AT_SECURE, AT_UID,
+};
+
+use crate::elf_loader::{load_elf_with, Elf};
+use crate::math::random::srand;
+use crate::memory::file_mapping::{FileMapping, WriteDenial};
 use crate::memory::mmu::{_enable_paging, _read_cr3};
-use crate::memory::tools::{AllocFlags, NbrPages, Page, Phys, Virt};
+use crate::memory::tools::{
+    Address, AllocFlags, MemoryError, NbrPages, Page, Phys, Virt, PAGE_SIZE, PAGE_SIZE_MASK,
+};
+use crate::memory::AddressSpace;
 use crate::memory::KERNEL_VIRTUAL_PAGE_ALLOCATOR;
-use crate::memory::{mmu::Entry, AddressSpace};
 
 extern "C" {
     fn _start_process(kernel_esp: u32) -> !;
@@ -33,11 +41,109 @@ extern "C" {
 pub struct ProcessArguments {
     argv: CStringArray,
     envp: CStringArray,
+    auxv: Vec<AuxEntry>,
+    /// Whether the areas of the new process image are placed at random
+    randomize: bool,
 }
 
 impl ProcessArguments {
-    pub fn new(argv: CStringArray, envp: CStringArray) -> Self {
-        Self { argv, envp }
+    /// The auxiliary vector tells the new process image about the
+    /// `credentials` it runs with. Its layout is randomized, unless
+    /// `personality` contains ADDR_NO_RANDOMIZE.
+    pub fn new(
+        argv: CStringArray,
+        envp: CStringArray,
+        credentials: &Credentials,
+        personality: u32,
+    ) -> SysResult<Self> {
+        let mut auxv = Vec::new();
+        for entry in &[
+            AuxEntry::new(AT_UID, credentials.uid),
+            AuxEntry::new(AT_EUID, credentials.euid),
+            AuxEntry::new(AT_GID, credentials.gid),
+            AuxEntry::new(AT_EGID, credentials.egid),
+            AuxEntry::new(AT_SECURE, credentials.is_secure() as u32),
+        ] {
+            auxv.try_push(*entry)?;
+        }
+        Ok(Self {
+            argv,
+            envp,
+            auxv,
+            randomize: personality & ADDR_NO_RANDOMIZE == 0,
+        })
+    }
+
+    /// Add an entry to the auxiliary vector
+    pub fn push_aux(&mut self, a_type: u32, a_val: u32) -> SysResult<()> {
+        Ok(self.auxv.try_push(AuxEntry::new(a_type, a_val))?)
+    }
+}
+
+/// The offsets the areas of a new process image are shifted by,
+/// downwards for the stack and upwards for the others
+#[derive(Debug)]
+struct Layout {
+    /// Between the top of the user space and the top of the stack
+    stack: NbrPages,
+    /// Between the top of the stack and the first argument, a multiple
+    /// of 16 below PAGE_SIZE
+    stack_bytes: usize,
+    /// The gap the mappings without a fixed address start after
+    mmap: NbrPages,
+    /// Between the mmap gap and a position independent executable
+    image: NbrPages,
+    /// Added to the base of the program interpreter
+    interpreter: NbrPages,
+}
+
+impl Layout {
+    /// The largest random offsets of the stack, the mmap gap and the
+    /// position independent images, in pages
+    const STACK_RANDOM: NbrPages = NbrPages::_8MB;
+    const MMAP_RANDOM: NbrPages = NbrPages::_16MB;
+    const IMAGE_RANDOM: NbrPages = NbrPages::_16MB;
+
+    /// Every area at its lowest address
+    fn fixed() -> Self {
+        Self {
+            stack: NbrPages(0),
+            stack_bytes: 0,
+            mmap: NbrPages(0),
+            image: NbrPages(0),
+            interpreter: NbrPages(0),
+        }
+    }
+
+    /// Every area shifted by a random offset
+    fn random() -> Self {
+        Self {
+            stack: Self::random_pages(Self::STACK_RANDOM),
+            stack_bytes: srand::<usize>(PAGE_SIZE / 16 - 1) * 16,
+            mmap: Self::random_pages(Self::MMAP_RANDOM),
+            image: Self::random_pages(Self::IMAGE_RANDOM),
+            interpreter: Self::random_pages(Self::IMAGE_RANDOM),
+        }
+    }
+
+    /// A random number of pages, less than `max`
+    fn random_pages(max: NbrPages) -> NbrPages {
+        NbrPages(srand::<usize>(max.0 - 1))
+    }
+}
+
+/// An entry of the auxiliary vector given to a new process image, the
+/// AT_* types are described in <sys/auxv.h>
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AuxEntry {
+    pub a_type: u32,
+    pub a_val: u32,
+}
+
+impl AuxEntry {
+    pub fn new(a_type: u32, a_val: u32) -> Self {
+        Self { a_type, a_val }
     }
 }
 
@@ -135,8 +241,6 @@ pub struct UserProcess {
     pub virtual_allocator: Arc<DeadMutex<AddressSpace>>,
     /// Symbol Table of a user process
     pub symbol_table: Option<Arc<SymbolTable>>,
-    /// User Stack location
-    pub user_stack_range: (u32, u32),
 }
 
 /// This structure represents an entire kernel process
@@ -173,7 +277,7 @@ impl core::fmt::Debug for KernelProcess {
 #[allow(unused)]
 pub enum ProcessOrigin<'a> {
     /// ELF file
-    Elf(&'a [u8]),
+    Elf(&'a ExecutableFile),
     /// Just a dummy function
     Raw(*const u8, usize),
     /// A regular Kernel Process With his entry point inside the kernel
@@ -190,9 +294,14 @@ impl UserProcess {
     const RING3_DPL: u32 = 0b11;
 
     const RING3_RAW_PROCESS_MAX_SIZE: NbrPages = NbrPages::_64K;
-    const RING3_PROCESS_STACK_SIZE: NbrPages = NbrPages::_64K;
     const RING3_PROCESS_KERNEL_STACK_SIZE: NbrPages = NbrPages::_128K;
 
+    /// Where the ET_DYN executables are loaded, like the ET_EXEC ones,
+    /// above the gap left at the start of the mmap area
+    const ET_DYN_BASE: u32 = AddressSpace::MMAP_BASE.0 as u32;
+    /// Where the program interpreters are loaded
+    const INTERPRETER_BASE: u32 = 0x40000000;
+
     pub fn sys_clone(
         &self,
         kernel_esp: u32,
@@ -233,7 +342,6 @@ impl UserProcess {
                 Arc::try_new(DeadMutex::new(self.virtual_allocator.lock().fork()?))?
             },
             symbol_table: self.symbol_table.as_ref().map(|elem| elem.clone()),
-            user_stack_range: self.user_stack_range,
         })?)
     }
     pub fn get_virtual_allocator(&self) -> DeadMutexGuard<AddressSpace> {
@@ -278,7 +386,7 @@ impl Drop for ContextSwitchGuard {
 impl Process for UserProcess {
     unsafe fn new(
         origin: ProcessOrigin,
-        arguments: Option<ProcessArguments>,
+        mut arguments: Option<ProcessArguments>,
     ) -> SysResult<Box<Self>> {
         // Store kernel CR3
         // Create the process Page directory
@@ -286,50 +394,64 @@ impl Process for UserProcess {
         // This will switch to this process Page Directory
         let _context_switch_guard = ContextSwitchGuard::new(&mut virtual_allocator);
 
+        // Choose where the areas are placed, at random unless the
+        // persona of the process disables it
+        let layout = match arguments.as_ref() {
+            Some(arguments) if arguments.randomize => Layout::random(),
+            _ => Layout::fixed(),
+        };
+
+        // Reserve the user stack at the top of the user space before
+        // anything else is mapped
+        let stack_top = virtual_allocator.reserve_stack(layout.stack)?;
+
         let (eip, symbol_table) = match origin {
-            ProcessOrigin::Elf(content) => {
-                // Parse Elf and generate stuff
-                let elf = load_elf(content)?;
-                for h in &elf.program_header_table {
-                    if h.segment_type == SegmentType::Load {
-                        let segment = {
-                            virtual_allocator.alloc_on(
-                                h.vaddr as *mut u8,
-                                h.memsz as usize,
-                                AllocFlags::USER_MEMORY,
-                            )?;
-                            slice::from_raw_parts_mut(h.vaddr as usize as *mut u8, h.memsz as usize)
-                        };
-                        segment[0..h.filez as usize].copy_from_slice(
-                            &content[h.offset as usize..h.offset as usize + h.filez as usize],
-                        );
-                        // With BSS (so a NOBITS section), the memsz value exceed the filesz. Setting next bytes as 0
-                        segment[h.filez as usize..h.memsz as usize]
-                            .as_mut_ptr()
-                            .write_bytes(0, h.memsz as usize - h.filez as usize);
-                        // Modify the rights on pages by following the ELF specific restrictions
-                        virtual_allocator
-                            .change_range_page_entry(
-                                Page::containing(Virt(h.vaddr as usize)),
-                                (h.memsz as usize).into(),
-                                &mut |entry: &mut Entry| {
-                                    *entry |= Entry::from(
-                                        Into::<AllocFlags>::into(h.flags) | AllocFlags::USER_MEMORY,
-                                    )
-                                },
-                            )
-                            .expect("page must have been alloc by alloc on");
+            ProcessOrigin::Elf(file) => {
+                // Map the segments, they are read from the file on demand
+                let bias = file.load_bias(
+                    Self::ET_DYN_BASE + (layout.mmap + layout.image).to_bytes() as u32,
+                )?;
+                file.map_segments(&mut virtual_allocator, bias)?;
+
+                // A position independent image is loaded above the mmap
+                // gap, the others are linked at the start of the mmap
+                // area: their gap follows their image
+                let mmap_gap = if file.is_position_independent() {
+                    AddressSpace::MMAP_BASE
+                } else {
+                    file.image_end(bias)?
+                };
+                virtual_allocator.reserve_mmap_gap(mmap_gap, layout.mmap)?;
+
+                // A dynamically linked executable starts in its
+                // interpreter, which finds the executable through the
+                // auxiliary vector
+                let (eip, interpreter_bias) = match file.interpreter() {
+                    Some(interpreter) => {
+                        let interpreter_bias = interpreter.load_bias(
+                            Self::INTERPRETER_BASE + layout.interpreter.to_bytes() as u32,
+                        )?;
+                        interpreter.map_segments(&mut virtual_allocator, interpreter_bias)?;
+                        (interpreter.entry_point(interpreter_bias), interpreter_bias)
+                    }
+                    None => (file.entry_point(bias), 0),
+                };
+                if let Some(arguments) = arguments.as_mut() {
+                    if let Some(phdr) = file.program_headers_addr(bias) {
+                        arguments.push_aux(AT_PHDR, phdr)?;
                     }
+                    arguments.push_aux(AT_PHENT, file.program_header_size())?;
+                    arguments.push_aux(AT_PHNUM, file.program_headers_len() as u32)?;
+                    arguments.push_aux(AT_PAGESZ, PAGE_SIZE as u32)?;
+                    arguments.push_aux(AT_BASE, interpreter_bias)?;
+                    arguments.push_aux(AT_FLAGS, 0)?;
+                    arguments.push_aux(AT_ENTRY, file.entry_point(bias))?;
                 }
-                (
-                    elf.header.entry_point as u32,
-                    match SymbolTable::try_new(content).ok() {
-                        Some(elem) => Some(Arc::try_new(elem)?),
-                        None => None,
-                    },
-                )
+                // The symbols are not loaded, the file is never read entirely
+                (eip, None)
             }
             ProcessOrigin::Raw(code, code_len) => {
+                virtual_allocator.reserve_mmap_gap(AddressSpace::MMAP_BASE, layout.mmap)?;
                 // Allocate one page for code segment of the Dummy process
                 let base_addr = virtual_allocator
                     .alloc(Self::RING3_RAW_PROCESS_MAX_SIZE, AllocFlags::USER_MEMORY)?;
@@ -359,25 +481,36 @@ impl Process for UserProcess {
                 - core::mem::size_of::<CpuState>(),
         ) as u32;
 
-        // Allocate one page for stack segment of the process
-        let stack_addr =
-            virtual_allocator.alloc(Self::RING3_PROCESS_STACK_SIZE, AllocFlags::USER_MEMORY)?;
-
-        // Mark the first entry of the user stack as read-only, this prevent user stack overflow
-        virtual_allocator.change_flags_page_entry(
-            Virt(stack_addr as usize).into(),
-            AllocFlags::READ_ONLY | AllocFlags::USER_MEMORY,
-        );
-
-        // stack go downwards set esp to the end of the allocation
-        let mut esp = stack_addr.add(Self::RING3_PROCESS_STACK_SIZE.into()) as u32;
+        // stack go downwards set esp to the top of the stack
+        let mut esp = (stack_top.0 - layout.stack_bytes) as u32;
 
-        let (mut eax, mut ebx, mut ecx) = (0, 0, 0);
+        let (mut eax, mut ebx, mut ecx, mut edx) = (0, 0, 0, 0);
 
         // Assign arguments of the main function
-        if let Some(arguments) = arguments {
+        if let Some(mut arguments) = arguments {
             let align = 4;
 
+            // Allocate the stack pages the arguments are written on,
+            // they must fit in the stack
+            let auxv_size = (arguments.auxv.len() + 2) * core::mem::size_of::<AuxEntry>();
+            let arguments_size = 16
+                + arguments.argv.get_serialized_len(align).expect("WTF")
+                + arguments.envp.get_serialized_len(align).expect("WTF")
+                + auxv_size;
+            virtual_allocator
+                .page_in_range(Virt(esp as usize) - arguments_size, stack_top - 1)
+                .map_err(|e| match e {
+                    MemoryError::PageFault => Errno::E2BIG,
+                    e => e.into(),
+                })?;
+
+            // Some random bytes for the libc, pointed by AT_RANDOM
+            esp -= 16;
+            for byte in slice::from_raw_parts_mut(esp as *mut u8, 16) {
+                *byte = srand::<u8>(core::u8::MAX);
+            }
+            arguments.push_aux(AT_RANDOM, esp)?;
+
             // Set the argc argument: EAX
             eax = arguments.argv.len() as u32;
 
@@ -394,6 +527,14 @@ impl Process for UserProcess {
                 .envp
                 .serialize(align, esp as *mut c_char)
                 .expect("WTF") as u32;
+
+            // Set the auxiliary vector, terminated by an AT_NULL entry: EDX
+            let auxv_len = arguments.auxv.len() + 1;
+            esp -= (auxv_len * core::mem::size_of::<AuxEntry>()) as u32;
+            let auxv = slice::from_raw_parts_mut(esp as *mut AuxEntry, auxv_len);
+            auxv[..auxv_len - 1].copy_from_slice(&arguments.auxv);
+            auxv[auxv_len - 1] = AuxEntry::new(AT_NULL, 0);
+            edx = esp;
         }
 
         // Create the process identity
@@ -406,6 +547,7 @@ impl Process for UserProcess {
                 eax,
                 ebx,
                 ecx,
+                edx,
                 ..Default::default()
             }, // Be carefull, never trust ESP
             ds: Self::RING3_DATA_SEGMENT + Self::RING3_DPL,
@@ -429,10 +571,6 @@ impl Process for UserProcess {
             kernel_esp,
             virtual_allocator: Arc::try_new(DeadMutex::new(virtual_allocator))?,
             symbol_table,
-            user_stack_range: (
-                stack_addr as u32,
-                stack_addr.add(Self::RING3_PROCESS_STACK_SIZE.into()) as u32,
-            ),
         })?)
     }
 
@@ -473,7 +611,7 @@ impl Process for KernelProcess {
             panic!("Giving process arguments to a kernel process is a non-implemented feature !");
         }
         let eip = match origin {
-            ProcessOrigin::Elf(_content) => {
+            ProcessOrigin::Elf(_file) => {
                 unimplemented!();
             }
             ProcessOrigin::Raw(code, code_len) => {
@@ -567,18 +705,264 @@ pub unsafe fn get_ring(context_ptr: u32) -> PrivilegeLevel {
     (((*cpu_state).cs & 0b11) as u8).into()
 }
 
+use super::vfs::posix_consts::PATH_MAX;
+use super::FileOperation;
 use super::IpcResult;
-use super::{thread_group::Credentials, vfs::Path};
-use libc_binding::FileType;
+use super::{thread_group::Credentials, vfs::Cwd, vfs::InodeId, vfs::Path};
+use core::convert::TryFrom;
+use libc_binding::{off_t, FileType};
+
+/// An executable file opened by execve, the process image is mapped
+/// from it
+pub struct ExecutableFile {
+    file: Arc<DeadMutex<dyn FileOperation>>,
+    inode_id: Option<InodeId>,
+    /// Held by the mappings of the file, so it is not written while
+    /// executed
+    write_denial: Option<Arc<WriteDenial>>,
+    size: u64,
+    elf: Elf,
+    /// The program interpreter (PT_INTERP) of a dynamically linked
+    /// executable, which loads its shared objects
+    interpreter: Option<Box<ExecutableFile>>,
+}
+
+impl ExecutableFile {
+    /// Open the executable `path` and its interpreter
+    pub fn open(cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<Self> {
+        let mut executable = Self::open_elf(cwd, creds, path)?;
+
+        if let Some(interpreter_path) = executable.interpreter_path()? {
+            let interpreter = Self::open_elf(cwd, creds, interpreter_path)?;
+            // An interpreter cannot need an interpreter itself
+            if interpreter.interpreter_path()?.is_some() {
+                return Err(Errno::ELIBBAD);
+            }
+            executable.interpreter = Some(Box::try_new(interpreter)?);
+        }
+        Ok(executable)
+    }
+
+    fn open_elf(cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<Self> {
+        let mode = FileType::from_bits(0).expect("file permission creation failed");
+        let flags = libc_binding::OpenFlags::empty();
+        let file =
+            match super::vfs::VFS
+                .lock()
+                .open(cwd, creds, path, flags, mode, FileType::empty())?
+            {
+                IpcResult::Done(file_operator) => file_operator,
+                IpcResult::Wait(file_operator, _) => file_operator,
+            };
+        let (inode_id, size) = {
+            let mut file = file.lock();
+            (file.get_inode_id().ok(), file.fstat()?.st_size as u64)
+        };
+        let write_denial = match inode_id {
+            Some(inode_id) => Some(Arc::try_new(WriteDenial::new(inode_id)?)?),
+            None => None,
+        };
+        let elf = load_elf_with(|buf, offset| read_file_at(&file, buf, offset))?;
+        Ok(Self {
+            file,
+            inode_id,
+            write_denial,
+            size,
+            elf,
+            interpreter: None,
+        })
+    }
+
+    /// Fill `buf` with the content of the file at `offset`
+    pub fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> SysResult<()> {
+        read_file_at(&self.file, buf, offset)
+    }
+
+    /// The path of the interpreter named by the PT_INTERP segment
+    fn interpreter_path(&self) -> SysResult<Option<Path>> {
+        let h = match self
+            .elf
+            .program_header_table
+            .iter()
+            .find(|h| h.segment_type == SegmentType::Interp)
+        {
+            Some(h) => h,
+            None => return Ok(None),
+        };
+        if h.filez == 0 || h.filez as usize > PATH_MAX {
+            return Err(Errno::ENOEXEC);
+        }
+        let mut path = try_vec![0; h.filez as usize]?;
+        self.read_exact_at(&mut path, h.offset as u64)?;
+
+        // The path is null-terminated
+        let path = path.split(|c| *c == 0).next().unwrap_or(&[]);
+        let path = core::str::from_utf8(path).map_err(|_| Errno::ENOEXEC)?;
+        Ok(Some(Path::try_from(path)?))
+    }
+
+    /// The interpreter of the executable, if it is dynamically linked
+    pub fn interpreter(&self) -> Option<&ExecutableFile> {
+        self.interpreter
+            .as_ref()
+            .map(|interpreter| interpreter.as_ref())
+    }
+
+    /// The distance between the addresses of the file and the ones of
+    /// its image: ET_EXEC files are loaded where they are linked, the
+    /// position independent ET_DYN ones from `base`
+    pub fn load_bias(&self, base: u32) -> SysResult<u32> {
+        let object_type = self.elf.header.object_type;
+        match object_type {
+            ObjectType::Exec => Ok(0),
+            ObjectType::Dyn => {
+                let first_vaddr = self
+                    .elf
+                    .program_header_table
+                    .iter()
+                    .filter(|h| h.segment_type == SegmentType::Load)
+                    .map(|h| h.vaddr & !(PAGE_SIZE_MASK as u32))
+                    .min()
+                    .ok_or(Errno::ENOEXEC)?;
+                Ok(base.wrapping_sub(first_vaddr))
+            }
+            _ => Err(Errno::ENOEXEC),
+        }
+    }
+
+    /// Checks if the executable is an ET_DYN one, which can be loaded
+    /// anywhere
+    pub fn is_position_independent(&self) -> bool {
+        match self.elf.header.object_type {
+            ObjectType::Dyn => true,
+            _ => false,
+        }
+    }
+
+    /// The end of the last PT_LOAD segment of the image loaded with
+    /// `bias`, rounded up to a page
+    pub fn image_end(&self, bias: u32) -> SysResult<Virt> {
+        let end = self
+            .elf
+            .program_header_table
+            .iter()
+            .filter(|h| h.segment_type == SegmentType::Load)
+            .map(|h| h.vaddr.wrapping_add(bias).checked_add(h.memsz))
+            .max()
+            .ok_or(Errno::ENOEXEC)?
+            .ok_or(Errno::ENOEXEC)?;
+        Ok(Virt(end as usize).align_next(PAGE_SIZE))
+    }
+
+    /// The entry point of the image loaded with `bias`
+    pub fn entry_point(&self, bias: u32) -> u32 {
+        self.elf.header.entry_point.wrapping_add(bias)
+    }
+
+    /// The address of the program headers in the image loaded with
+    /// `bias`, which the interpreter reads through AT_PHDR
+    pub fn program_headers_addr(&self, bias: u32) -> Option<u32> {
+        let table = &self.elf.program_header_table;
+        let phoff = self.elf.header.program_header_table_offset;
+
+        table
+            .iter()
+            .find(|h| h.segment_type == SegmentType::Phdr)
+            .map(|h| h.vaddr)
+            .or_else(|| {
+                table
+                    .iter()
+                    .find(|h| {
+                        h.segment_type == SegmentType::Load
+                            && h.offset <= phoff
+                            && phoff - h.offset < h.filez
+                    })
+                    .map(|h| h.vaddr + (phoff - h.offset))
+            })
+            .map(|addr| addr.wrapping_add(bias))
+    }
+
+    /// The number of program headers, for AT_PHNUM
+    pub fn program_headers_len(&self) -> usize {
+        self.elf.program_header_table.len()
+    }
+
+    /// The size of a program header, for AT_PHENT
+    pub fn program_header_size(&self) -> u32 {
+        self.elf.header.program_header_table_size as u32
+    }
+
+    /// Map the PT_LOAD segments in `address_space`, moved by `bias`
+    pub fn map_segments(&self, address_space: &mut AddressSpace, bias: u32) -> SysResult<()> {
+        for h in &self.elf.program_header_table {
+            if h.segment_type == SegmentType::Load && h.memsz != 0 {
+                address_space.map_file(self.segment_mapping(h, bias)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The mapping of the PT_LOAD segment `h`: its file image is read
+    /// on the first access of each page and the remaining of the
+    /// segment (.bss) is zero-filled on demand. The pages are
+    /// read-only unless the segment is writable, i386 paging cannot
+    /// forbid the execution of a readable page.
+    fn segment_mapping(&self, h: &ProgramHeader, bias: u32) -> SysResult<FileMapping> {
+        let vaddr = h.vaddr.wrapping_add(bias) as usize;
+        let page_offset = vaddr & PAGE_SIZE_MASK;
+
+        if h.filez > h.memsz
+            || h.offset as usize & PAGE_SIZE_MASK != page_offset
+            || h.offset as u64 + h.filez as u64 > self.size
+        {
+            return Err(Errno::ENOEXEC);
+        }
+        let size = page_offset
+            .checked_add(h.memsz as usize)
+            .ok_or(Errno::ENOEXEC)?;
+        let mapping = FileMapping::new(
+            Page::containing(Virt(vaddr)),
+            size.into(),
+            self.file.clone(),
+            self.inode_id,
+            (h.offset as usize - page_offset) as u64,
+            page_offset + h.filez as usize,
+            h.flags.into(),
+        );
+        Ok(match &self.write_denial {
+            Some(write_denial) => mapping.with_write_denial(write_denial.clone()),
+            None => mapping,
+        })
+    }
+}
+
+/// Fill `buf` with the content of `file` at `offset`
+fn read_file_at(file: &DeadMutex<dyn FileOperation>, buf: &mut [u8], offset: u64) -> SysResult<()> {
+    let mut file = file.lock();
+    let mut read = 0;
+
+    while read < buf.len() {
+        let count = file.pread(&mut buf[read..], (offset + read as u64) as off_t)?;
+        if count == 0 {
+            return Err(Errno::ENOEXEC);
+        }
+        read += count as usize;
+    }
+    Ok(())
+}
 
 /// Return a file content using raw ext2 methods
-pub fn get_file_content(cwd: &Path, creds: &Credentials, path: Path) -> SysResult<Vec<u8>> {
+pub fn get_file_content(cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<Vec<u8>> {
     let mode = FileType::from_bits(0).expect("file permission creation failed");
     let flags = libc_binding::OpenFlags::empty();
-    let file_operator = match super::vfs::VFS.lock().open(cwd, creds, path, flags, mode)? {
-        IpcResult::Done(file_operator) => file_operator,
-        IpcResult::Wait(file_operator, _) => file_operator,
-    };
+    let file_operator =
+        match super::vfs::VFS
+            .lock()
+            .open(cwd, creds, path, flags, mode, FileType::empty())?
+        {
+            IpcResult::Done(file_operator) => file_operator,
+            IpcResult::Wait(file_operator, _) => file_operator,
+        };
     let mut file = file_operator.lock();
     let stat = file.fstat()?;
 
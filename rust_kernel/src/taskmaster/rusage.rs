@@ -0,0 +1,86 @@
+//! This module accounts the resources used by the threads and the
+//! processes, as reported by getrusage() and wait4()
+
+use super::global_time::ProcessDuration;
+use crate::memory::address_space::FaultKind;
+
+use core::ops::AddAssign;
+use libc_binding::{rusage, timeval};
+
+#[derive(Copy, Clone, Debug, Default)]
+/// The resources used by a thread, a process or its reaped children
+pub struct ResourceUsage {
+    /// Time spent in user space and in kernel space
+    pub duration: ProcessDuration,
+    /// Highest resident set size, in kilobytes
+    pub max_rss: usize,
+    /// Page faults serviced without any I/O
+    pub minor_faults: u32,
+    /// Page faults which read the page from its file
+    pub major_faults: u32,
+    /// Times the CPU was given up because the thread blocked
+    pub voluntary_switches: u32,
+    /// Times the thread was preempted while it could still run
+    pub involuntary_switches: u32,
+}
+
+impl ResourceUsage {
+    /// Count a page fault serviced as `kind`
+    pub fn count_fault(&mut self, kind: FaultKind) {
+        match kind {
+            FaultKind::Minor => self.minor_faults += 1,
+            FaultKind::Major => self.major_faults += 1,
+        }
+    }
+
+    /// Count a context switch away from the thread
+    pub fn count_switch(&mut self, voluntary: bool) {
+        if voluntary {
+            self.voluntary_switches += 1;
+        } else {
+            self.involuntary_switches += 1;
+        }
+    }
+
+    /// Take a new resident set size into account
+    pub fn update_max_rss(&mut self, rss: usize) {
+        self.max_rss = self.max_rss.max(rss);
+    }
+}
+
+/// The usages of several processes add up, except for the resident
+/// set size: the largest one is kept
+impl AddAssign for ResourceUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.duration += other.duration;
+        self.update_max_rss(other.max_rss);
+        self.minor_faults += other.minor_faults;
+        self.major_faults += other.major_faults;
+        self.voluntary_switches += other.voluntary_switches;
+        self.involuntary_switches += other.involuntary_switches;
+    }
+}
+
+/// From boilerplate from ResourceUsage to rusage
+impl From<ResourceUsage> for rusage {
+    fn from(usage: ResourceUsage) -> Self {
+        let user_time = usage.duration.user_time();
+        let system_time = usage.duration.system_time();
+        Self {
+            ru_utime: timeval {
+                tv_sec: user_time.as_secs() as i32,
+                tv_usec: user_time.subsec_micros(),
+            },
+            ru_stime: timeval {
+                tv_sec: system_time.as_secs() as i32,
+                tv_usec: system_time.subsec_micros(),
+            },
+            ru_maxrss: usage.max_rss as i32,
+            ru_minflt: usage.minor_faults as i32,
+            ru_majflt: usage.major_faults as i32,
+            ru_nvcsw: usage.voluntary_switches as i32,
+            ru_nivcsw: usage.involuntary_switches as i32,
+            ..Default::default()
+        }
+    }
+}
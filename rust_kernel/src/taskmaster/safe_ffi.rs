@@ -33,6 +33,11 @@ impl CString {
         self.0.len() - 1
     }
 
+    /// Return the content of the string, without the terminating nul byte
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.0.as_ptr() as *const u8, self.len()) }
+    }
+
     /// Get the len of data if the are serialized into a raw buffer
     pub fn get_serialized_len(&self, align: usize) -> Option<usize> {
         if align == 0 {
@@ -114,6 +119,40 @@ impl CStringArray {
         self.owned_content.len()
     }
 
+    /// Keep only the strings for which `f` returns true
+    pub fn retain<F: FnMut(&CString) -> bool>(&mut self, f: F) {
+        self.owned_content.retain(f);
+        // The strings are not moved by the retain, and there are less
+        // pointers than before: no reallocation
+        self.c_pointer.clear();
+        for string in self.owned_content.iter() {
+            self.c_pointer.push(string.as_ptr());
+        }
+        self.c_pointer.push(0x0 as *const c_char);
+    }
+
+    /// Replace the `count` first strings by `strings`
+    pub fn try_replace_front(
+        &mut self,
+        count: usize,
+        strings: Vec<CString>,
+    ) -> Result<(), TryReserveError> {
+        let count = count.min(self.len());
+        let len = strings.len() + self.len() - count;
+        let mut owned_content = Vec::try_with_capacity(len)?;
+        let mut c_pointer = Vec::try_with_capacity(len + 1)?;
+
+        // Nothing can fail anymore, the vectors are big enough
+        for string in strings.into_iter().chain(self.owned_content.drain(count..)) {
+            c_pointer.push(string.as_ptr());
+            owned_content.push(string);
+        }
+        c_pointer.push(0x0 as *const c_char);
+        self.owned_content = owned_content;
+        self.c_pointer = c_pointer;
+        Ok(())
+    }
+
     /// Get the len of data if the are serialized into a raw buffer
     pub fn get_serialized_len(&self, align: usize) -> Option<usize> {
         if align == 0 {
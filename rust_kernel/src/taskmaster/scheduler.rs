@@ -2,19 +2,24 @@
 use super::global_time::{TimeSession, GLOBAL_TIME};
 use super::kmodules::KernelModules;
 use super::process::{get_ring, CpuState, KernelProcess, Process, ProcessOrigin, UserProcess};
-use super::signal_interface::JobAction;
+use super::signal_interface::{JobAction, SigInfo};
 use super::sync::SmartMutex;
 use super::syscall::clone::CloneFlags;
 use super::thread::{AutoPreemptReturnValue, ProcessState, Thread, WaitingState};
 pub use super::thread_group::{
     Credentials, RunningThreadGroup, Status, ThreadGroup, ThreadGroupState,
 };
+use super::vfs::VFS;
 use super::{SysResult, TaskMode};
 
 mod dustman;
 use dustman::{dustman_handler, DUSTMAN_TRIGGER};
 mod second_callback;
 use second_callback::{second_callback_handler, SECOND_CALLBACK_TRIGGER};
+mod policy;
+use policy::Rank;
+pub use policy::{SchedEntity, SchedPolicy, NICE_MAX, NICE_MIN};
+mod session;
 
 use alloc::boxed::Box;
 use alloc::vec::Vec;
@@ -93,6 +98,8 @@ pub struct Scheduler {
     current_task_id: (Pid, Tid),
     /// current process index in the running_process vector
     current_task_index: usize,
+    /// virtual runtime of the last elected SCHED_OTHER thread
+    min_vruntime: u64,
     /// time interval in PIT tics between two schedules + microseconds between two PIT tics
     time_interval: Option<(u32, u32)>,
     /// The scheduler must have an idle kernel proces if all the user process are waiting
@@ -107,6 +114,9 @@ pub struct Scheduler {
     mode: Mode,
     /// Indicate if scheduler is on exit routine
     pub on_exit_routine: Option<(Pid, Status)>,
+    /// The thread which last left the CPU and whether it blocked,
+    /// until a context switch is accounted to it
+    switched_out: Option<((Pid, Tid), bool)>,
 }
 
 /// The pit handler (cpu_state represents a pointer to esp)
@@ -179,6 +189,7 @@ impl Scheduler {
             next_pid: AtomicI32::new(1),
             current_task_index: 0,
             current_task_id: (1, 0),
+            min_vruntime: 0,
             time_interval: None,
             kernel_idle_process: unsafe {
                 KernelProcess::new(
@@ -198,6 +209,7 @@ impl Scheduler {
             last_second_callback_pit_time: unsafe { _get_pit_time() },
             mode: Mode::Normal,
             on_exit_routine: None,
+            switched_out: None,
         }
     }
 
@@ -208,6 +220,7 @@ impl Scheduler {
         self.dispatch_messages();
         // Switch between processes
         let action = self.advance_next_process(next_process);
+        self.account_context_switch();
         // Set all the context of the illigible process
         self.load_new_context(action)
     }
@@ -258,9 +271,17 @@ impl Scheduler {
         use Mode::*;
         match self.mode {
             Normal => {
-                self.current_thread_group_mut().process_duration +=
-                    unsafe { GLOBAL_TIME.as_mut().unwrap().get_process_time() };
-                self.current_thread_mut().unwrap_process_mut().kernel_esp = kernel_esp;
+                let duration = unsafe { GLOBAL_TIME.as_mut().unwrap().get_process_time() };
+                self.current_thread_group_mut().usage.duration += duration;
+                let thread = self.current_thread_mut();
+                thread.usage.duration += duration;
+                thread
+                    .sched
+                    .account(duration.user_time() + duration.system_time());
+                thread.unwrap_process_mut().kernel_esp = kernel_esp;
+                // A thread which leaves the CPU while waiting blocked
+                let voluntary = thread.get_waiting_state().is_some();
+                self.switched_out = Some((self.current_task_id, voluntary));
             }
             Idle => {
                 self.kernel_idle_process.kernel_esp = kernel_esp;
@@ -275,65 +296,120 @@ impl Scheduler {
         self.mode = Normal;
     }
 
-    /// Advance until a next elligible process was found, modify
-    /// self.current_task_index and self.current_task_id
+    /// Count a context switch for the thread which left the CPU, unless
+    /// it was elected again
+    fn account_context_switch(&mut self) {
+        if let Some((task_id, voluntary)) = self.switched_out.take() {
+            if self.mode == Mode::Idle || self.current_task_id != task_id {
+                if let Some(thread) = self.get_thread_mut(task_id) {
+                    thread.usage.count_switch(voluntary);
+                }
+                if let Some(thread_group) = self.get_thread_group_mut(task_id.0) {
+                    thread_group.usage.count_switch(voluntary);
+                }
+            }
+        }
+    }
+
+    /// Elect the next thread to run among the elligible ones: the one
+    /// with the best rank, the first found from `offset` after the
+    /// current one on a tie. Modify self.current_task_index and
+    /// self.current_task_id
     fn advance_next_process(&mut self, offset: usize) -> JobAction {
+        let previous_task_id = self.current_task_id;
         let next_process_index = (self.current_task_index + offset) % self.running_process.len();
+        let mut elected: Option<(usize, JobAction, Rank)> = None;
         // dbg!(&self.running_process);
 
         for idx in 0..self.running_process.len() {
             self.current_task_index = (next_process_index + idx) % self.running_process.len();
             self.current_task_id = self.running_process[self.current_task_index];
 
-            // Check if pending signal: Signal Can interrupt all except zombie
-            // some signals may be marked as IGNORED, Remove signal and dont DO anything in this case
-            // else create a signal var with option<SignalStatus>
-            let action = self.current_thread_get_job_action();
-
-            // Job control: STOP lock thread, CONTINUE (witch erase STOP) or TERMINATE unlock it
-            if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
-                continue;
+            if let Some(action) = self.current_thread_elligibility() {
+                let min_vruntime = self.min_vruntime;
+                let is_previous = self.current_task_id == previous_task_id;
+                let sched = &mut self.current_thread_mut().sched;
+                let rank = sched.rank(min_vruntime);
+                // A SCHED_FIFO thread is not preempted by the threads of its priority
+                let keeps_cpu = is_previous && sched.keeps_cpu();
+
+                let better = match &elected {
+                    None => true,
+                    Some((_, _, best)) => rank < *best || (rank == *best && keeps_cpu),
+                };
+                if better {
+                    elected = Some((self.current_task_index, action, rank));
+                }
             }
-            match &self.current_thread().process_state {
-                ProcessState::Running(_) => return action,
-                ProcessState::Waiting(_, waiting_state) => {
-                    if action.intersects(JobAction::TERMINATE) {
-                        // Immediately resume blocking syscall if TERMINATE action.
-                        // Ce n'est pas tout a fait logique mais on va faire la meme
-                        // chose que pour un signal tout con. Le fait de mettre Errno
-                        // a EINTR sert juste a retourner une erreur pour debloquer
-                        // auto-preempt(). Le deliver_pending_signal machin au fond de syscall
-                        // fera le reste du boulot pour achever le process.
-                        // Il y a toujours un truc qui me chaffouine profondement la-dedans...
-                        self.current_thread_mut().set_running();
-                        self.current_thread_mut()
-                            .set_return_value_autopreempt(Err(Errno::EINTR));
-                        return action;
-                    } else if action.intersects(JobAction::INTERRUPT) {
-                        // Check if signal var contains something, set return value as
-                        // Err(Errno::EINTR), set process as running then return
-                        self.current_thread_mut().set_running();
-                        self.current_thread_mut()
-                            .set_return_value_autopreempt(Err(Errno::EINTR));
-                        return action;
-                    }
-                    match waiting_state {
-                        WaitingState::Sleeping(time) => {
-                            let now = unsafe { _get_pit_time() };
-                            if now >= *time {
-                                self.current_thread_mut().set_running();
-                                self.current_thread_mut()
-                                    .set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
-                                return action;
-                            }
+        }
+        match elected {
+            Some((index, action, _)) => {
+                self.current_task_index = index;
+                self.current_task_id = self.running_process[index];
+                if let Some(vruntime) = self.current_thread_mut().sched.elect() {
+                    self.min_vruntime = core::cmp::max(self.min_vruntime, vruntime);
+                }
+                action
+            }
+            None => {
+                self.mode = Mode::Idle;
+                JobAction::default()
+            }
+        }
+    }
+
+    /// Return the job action of the current thread if it can run,
+    /// resuming it from a blocking syscall if needed
+    fn current_thread_elligibility(&mut self) -> Option<JobAction> {
+        // Check if pending signal: Signal Can interrupt all except zombie
+        // some signals may be marked as IGNORED, Remove signal and dont DO anything in this case
+        // else create a signal var with option<SignalStatus>
+        let action = self.current_thread_get_job_action();
+
+        // Job control: STOP lock thread, CONTINUE (witch erase STOP) or TERMINATE unlock it
+        if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
+            return None;
+        }
+        match &self.current_thread().process_state {
+            ProcessState::Running(_) => Some(action),
+            ProcessState::Waiting(_, waiting_state) => {
+                if action.intersects(JobAction::TERMINATE) {
+                    // Immediately resume blocking syscall if TERMINATE action.
+                    // Ce n'est pas tout a fait logique mais on va faire la meme
+                    // chose que pour un signal tout con. Le fait de mettre Errno
+                    // a EINTR sert juste a retourner une erreur pour debloquer
+                    // auto-preempt(). Le deliver_pending_signal machin au fond de syscall
+                    // fera le reste du boulot pour achever le process.
+                    // Il y a toujours un truc qui me chaffouine profondement la-dedans...
+                    self.current_thread_mut().set_running();
+                    self.current_thread_mut()
+                        .set_return_value_autopreempt(Err(Errno::EINTR));
+                    return Some(action);
+                } else if action.intersects(JobAction::INTERRUPT)
+                    && waiting_state.is_interruptible()
+                {
+                    // Check if signal var contains something, set return value as
+                    // Err(Errno::EINTR), set process as running then return
+                    self.current_thread_mut().set_running();
+                    self.current_thread_mut()
+                        .set_return_value_autopreempt(Err(Errno::EINTR));
+                    return Some(action);
+                }
+                match waiting_state {
+                    WaitingState::Sleeping(time) => {
+                        let now = unsafe { _get_pit_time() };
+                        if now >= *time {
+                            self.current_thread_mut().set_running();
+                            self.current_thread_mut()
+                                .set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                            return Some(action);
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
-            };
+                None
+            }
         }
-        self.mode = Mode::Idle;
-        JobAction::default()
     }
 
     /// Prepare the context for the new illigible process
@@ -454,6 +530,77 @@ impl Scheduler {
         Ok(child_pid)
     }
 
+    /// Make the current thread the only one of its thread group, as
+    /// execve requires: the other threads are terminated without
+    /// running anything more, and the current one becomes the main
+    /// thread. The signals pending for the process, which are queued
+    /// on the main thread, are kept.
+    pub fn current_thread_group_exec(&mut self) -> SysResult<()> {
+        let (pid, tid) = self.current_task_id;
+        let all_thread = self
+            .current_thread_group_mut()
+            .get_all_thread_mut()
+            .expect("the thread group is running");
+
+        if tid != 0 {
+            let pending = all_thread
+                .get(&0)
+                .expect("no main thread")
+                .signal
+                .pending_signals_len();
+            all_thread
+                .get_mut(&tid)
+                .expect("no current thread")
+                .signal
+                .reserve_pending_signals(pending)?;
+        }
+
+        // Nothing can fail from here
+        while let Some(other_tid) = all_thread
+            .keys()
+            .find(|&&other_tid| other_tid != 0 && other_tid != tid)
+            .copied()
+        {
+            all_thread.remove(&other_tid);
+        }
+        if tid != 0 {
+            let mut current = all_thread.remove(&tid).expect("no current thread");
+            let main = all_thread.get_mut(&0).expect("no main thread");
+            current.signal.adopt_pending_signals(&main.signal);
+            // The old main thread is dropped with its kernel stack
+            core::mem::swap(main, &mut current);
+        }
+
+        self.running_process
+            .retain(|&(running_pid, running_tid)| running_pid != pid || running_tid == tid);
+        self.current_task_index = self
+            .running_process
+            .iter()
+            .position(|&id| id == (pid, tid))
+            .expect("the current thread is not running");
+        self.running_process[self.current_task_index] = (pid, 0);
+        self.current_task_id = (pid, 0);
+        Ok(())
+    }
+
+    /// Resume the parent of `pid` if it is suspended by vfork() until
+    /// its child calls execve or exits
+    pub fn release_vfork_parent(&mut self, pid: Pid) {
+        let parent_pid = match self.get_thread_group(pid) {
+            Some(thread_group) => thread_group.parent,
+            None => return,
+        };
+        if let Some(thread) = self
+            .get_thread_group_mut(parent_pid)
+            .into_iter()
+            .flat_map(|thread_group| thread_group.iter_thread_mut())
+            .find(|thread| thread.get_waiting_state() == Some(&WaitingState::Vfork(pid)))
+        {
+            thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+            thread.set_running();
+        }
+    }
+
     const REAPER_PID: Pid = 1;
 
     /// Start the exit() routine: Return informations about the process to destroy
@@ -471,6 +618,18 @@ impl Scheduler {
             _ => {}
         }
 
+        self.current_thread_group_leave_session();
+
+        // The peak resident set size is kept for the wait of the parent
+        let max_rss = self
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator()
+            .peak_rss();
+        self.current_thread_group_mut()
+            .usage
+            .update_max_rss(max_rss);
+
         while let Some(child_pid) = self.current_thread_group_running_mut().child.pop() {
             let thread_group = self
                 .get_thread_group_mut(child_pid)
@@ -500,6 +659,7 @@ impl Scheduler {
 
         let (pid, _) = self.current_task_id;
 
+        self.release_vfork_parent(pid);
         self.remove_thread_group_running(pid);
 
         DUSTMAN_TRIGGER.store(true, Ordering::Relaxed);
@@ -518,11 +678,12 @@ impl Scheduler {
 
         // Send a sig child signal to the father
         let parent_pid = dead_process.parent;
+        let info = SigInfo::child(process_to_free_pid, dead_process.credentials.uid, status);
         let parent = self
             .get_thread_mut((parent_pid, 0))
             .expect("WTF: Parent not alive");
 
-        let res = parent.signal.generate_signal(Signum::SIGCHLD);
+        let res = parent.signal.generate_signal_info(Signum::SIGCHLD, info);
         if let Err(e) = res {
             log::error!("generate sigchidld failed {:?}", e);
         }
@@ -539,6 +700,9 @@ impl Scheduler {
             .unwrap_running_mut()
             .file_descriptor_interface
             .delete();
+        VFS.lock().release_process_locks(process_to_free_pid);
+        // Its root and working directories may be removed now
+        VFS.lock().unpin_cwd(&dead_process.cwd);
 
         dead_process.set_zombie(status);
 
@@ -575,8 +739,11 @@ impl Scheduler {
     /// a system process shall not have a process ID of 1.
     fn get_available_pid(&self) -> Pid {
         // this check if the candidate does't pid match any active process group
-        let posix_constraits =
-            |pid: Pid| -> bool { !self.iter_thread_groups().any(|pg| pg.pgid == pid) };
+        let posix_constraits = |pid: Pid| -> bool {
+            !self
+                .iter_thread_groups()
+                .any(|pg| pg.pgid == pid || pg.sid == pid)
+        };
 
         let pred = |pid| pid > 0 && !self.all_process.contains_key(&pid) && posix_constraits(pid);
         let mut pid = self.next_pid.fetch_add(1, Ordering::Relaxed);
@@ -602,11 +769,15 @@ impl Scheduler {
             PrivilegeLevel::Ring3,
             "Cannot apply signal from ring0 process"
         );
-        // Get the user_stack location to ensure that are large available space
-        let user_stack_range = self.current_thread().unwrap_process().user_stack_range;
+        // The signal frames are written on the stacks of the address space
+        let address_space = self
+            .current_thread()
+            .unwrap_process()
+            .virtual_allocator
+            .clone();
         let signum: Option<Signum> = self.current_thread_mut().signal.exec_signal_handler(
             cpu_state,
-            user_stack_range,
+            &address_space.lock(),
             in_blocked_syscall,
         );
         if let Some(signum) = signum {
@@ -730,6 +901,13 @@ impl Scheduler {
         self.all_process.values_mut()
     }
 
+    /// iter on all the thread group mutably
+    pub fn iter_thread_groups_with_pid_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&Pid, &mut ThreadGroup)> {
+        self.all_process.iter_mut()
+    }
+
     /// iter on all the thread mutably
     #[allow(dead_code)]
     pub fn iter_thread_mut(&mut self) -> impl Iterator<Item = &mut Thread> {
@@ -791,6 +969,17 @@ impl Scheduler {
                         thread.set_running();
                     });
             }
+            MessageTo::Locker { uid } => {
+                // All the waiters check again if their lock can be set
+                self.iter_thread_mut()
+                    .filter(|thread| {
+                        thread.get_waiting_state() == Some(&WaitingState::FileLock(uid))
+                    })
+                    .for_each(|thread| {
+                        thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::None));
+                        thread.set_running();
+                    });
+            }
             MessageTo::Process { pid, content } => match content {
                 ProcessMessage::ProcessUpdated {
                     pid: dead_process_pid,
@@ -799,8 +988,9 @@ impl Scheduler {
                 } => {
                     // to avoid the borrow checker we declare a bool
                     // which comes to true if we find the father Waiting
-                    // with the right options
-                    let mut finded = false;
+                    // with the right options, and did not ask to leave
+                    // the child in a waitable state
+                    let mut consume = false;
                     let s: Status = status.into();
                     if let Some(thread) = self
                         .get_thread_group_mut(pid)
@@ -808,28 +998,27 @@ impl Scheduler {
                         .flat_map(|thread| thread.iter_thread_mut())
                         .find(|thread| {
                             /* Wake Condition of the Waitpid */
-                            if let Some(WaitingState::Waitpid {
-                                pid: wake_pid,
-                                pgid,
-                                options,
-                            }) = thread.get_waiting_state()
+                            if let Some(WaitingState::Waitpid { set, options }) =
+                                thread.get_waiting_state()
                             {
                                 ((options.contains(WaitOption::WUNTRACED) && s == Status::Stopped)
                                     || (options.contains(WaitOption::WCONTINUED)
                                         && s == Status::Continued)
-                                    || s.is_exited()
-                                    || s.is_signaled())
-                                    && (*wake_pid == -1
-                                        || *wake_pid == 0 && dead_process_pgid == *pgid
-                                        || *wake_pid == dead_process_pid
-                                        || -*wake_pid == dead_process_pgid)
+                                    || (options.contains(WaitOption::WEXITED)
+                                        && (s.is_exited() || s.is_signaled())))
+                                    && set.contains(dead_process_pid, dead_process_pgid)
                             } else {
                                 false
                             }
                             /* end Wake Condition of the Waitpid */
                         })
                     {
-                        finded = true;
+                        consume = match thread.get_waiting_state() {
+                            Some(WaitingState::Waitpid { options, .. }) => {
+                                !options.contains(WaitOption::WNOWAIT)
+                            }
+                            _ => false,
+                        };
                         thread.set_running();
                         thread.set_return_value_autopreempt(Ok(AutoPreemptReturnValue::Wait {
                             dead_process_pid,
@@ -837,7 +1026,7 @@ impl Scheduler {
                         }));
                     }
 
-                    if finded && (s == Status::Stopped || s == Status::Continued) {
+                    if consume && (s == Status::Stopped || s == Status::Continued) {
                         // consume the state, because at the return of
                         // auto_preempt after scheduling, the state
                         // can change and it maybe too late to consume
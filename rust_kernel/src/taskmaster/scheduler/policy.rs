@@ -0,0 +1,239 @@
+//! Scheduling policies of the threads. The real-time SCHED_FIFO and
+//! SCHED_RR threads always run before the SCHED_OTHER ones, which
+//! share the CPU in proportion of a weight given by their nice value.
+
+use super::SysResult;
+
+use core::cmp::{max, Reverse};
+use core::convert::TryFrom;
+use core::time::Duration;
+use libc_binding::{Errno, SCHED_FIFO, SCHED_OTHER, SCHED_RR};
+
+/// Highest priority nice value
+pub const NICE_MIN: i32 = -20;
+/// Lowest priority nice value
+pub const NICE_MAX: i32 = 19;
+
+/// Lowest static priority of the real-time policies
+pub const RT_PRIORITY_MIN: u32 = 1;
+/// Highest static priority of the real-time policies
+pub const RT_PRIORITY_MAX: u32 = 99;
+
+/// Weight of the nice values from -20 to 19, as in Linux: each step
+/// changes the share of CPU of a thread by about 10%
+const NICE_TO_WEIGHT: [u64; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+    3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+    70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+/// Weight of the nice value 0
+const NICE_0_WEIGHT: u64 = 1024;
+
+/// How far, in microseconds of virtual runtime, a thread which slept
+/// can be behind the others. It is elected as soon as it wakes up,
+/// which makes the interactive processes responsive, without being
+/// owed all the time it slept.
+const SLEEPER_CREDIT: u64 = 20_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// Time sharing weighted by the nice value
+    Other,
+    /// Real-time, runs until it blocks, yields or a thread of higher
+    /// priority is runnable
+    Fifo,
+    /// Real-time, as SCHED_FIFO, but shares the CPU in turn with the
+    /// threads of the same priority
+    RoundRobin,
+}
+
+impl TryFrom<u32> for SchedPolicy {
+    type Error = Errno;
+
+    fn try_from(policy: u32) -> SysResult<Self> {
+        match policy {
+            SCHED_OTHER => Ok(SchedPolicy::Other),
+            SCHED_FIFO => Ok(SchedPolicy::Fifo),
+            SCHED_RR => Ok(SchedPolicy::RoundRobin),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+impl From<SchedPolicy> for u32 {
+    fn from(policy: SchedPolicy) -> Self {
+        match policy {
+            SchedPolicy::Other => SCHED_OTHER,
+            SchedPolicy::Fifo => SCHED_FIFO,
+            SchedPolicy::RoundRobin => SCHED_RR,
+        }
+    }
+}
+
+impl SchedPolicy {
+    pub fn is_realtime(self) -> bool {
+        self != SchedPolicy::Other
+    }
+
+    /// The range of the static priority of the threads of this policy
+    pub fn priority_range(self) -> (u32, u32) {
+        if self.is_realtime() {
+            (RT_PRIORITY_MIN, RT_PRIORITY_MAX)
+        } else {
+            (0, 0)
+        }
+    }
+}
+
+/// Order of election of the runnable threads, the lowest runs first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    /// The highest static priority first
+    RealTime(Reverse<u32>),
+    /// The thread which had the least CPU time first
+    Fair { yielded: bool, vruntime: u64 },
+}
+
+/// The scheduling state of a thread
+#[derive(Debug, Clone)]
+pub struct SchedEntity {
+    policy: SchedPolicy,
+    /// Static priority, 0 for SCHED_OTHER
+    rt_priority: u32,
+    nice: i32,
+    /// CPU time used, weighted by the nice value, in microseconds
+    vruntime: u64,
+    /// The thread called sched_yield() since its last election
+    yielded: bool,
+    /// The threads it creates do not inherit its policy and its
+    /// negative nice value
+    reset_on_fork: bool,
+}
+
+impl SchedEntity {
+    pub fn new() -> Self {
+        Self {
+            policy: SchedPolicy::Other,
+            rt_priority: 0,
+            nice: 0,
+            vruntime: 0,
+            yielded: false,
+            reset_on_fork: false,
+        }
+    }
+
+    /// The scheduling state of a new thread, it inherits the policy,
+    /// priority and nice value of its creator, unless its creator
+    /// asked for SCHED_RESET_ON_FORK: it is then a SCHED_OTHER thread
+    /// with a nice value of at least 0
+    pub fn fork(&self) -> Self {
+        if self.reset_on_fork {
+            Self {
+                policy: SchedPolicy::Other,
+                rt_priority: 0,
+                nice: max(self.nice, 0),
+                yielded: false,
+                reset_on_fork: false,
+                ..self.clone()
+            }
+        } else {
+            Self {
+                yielded: false,
+                ..self.clone()
+            }
+        }
+    }
+
+    pub fn policy(&self) -> SchedPolicy {
+        self.policy
+    }
+
+    pub fn rt_priority(&self) -> u32 {
+        self.rt_priority
+    }
+
+    pub fn nice(&self) -> i32 {
+        self.nice
+    }
+
+    pub fn reset_on_fork(&self) -> bool {
+        self.reset_on_fork
+    }
+
+    pub fn set_reset_on_fork(&mut self, reset_on_fork: bool) {
+        self.reset_on_fork = reset_on_fork;
+    }
+
+    /// The nice value is clamped to [NICE_MIN, NICE_MAX]
+    pub fn set_nice(&mut self, nice: i32) {
+        self.nice = max(NICE_MIN, nice.min(NICE_MAX));
+    }
+
+    /// Set the policy and its static priority
+    ///
+    /// [EINVAL]
+    ///     priority is out of the range of policy.
+    pub fn set_policy(&mut self, policy: SchedPolicy, priority: u32) -> SysResult<()> {
+        let (min, max) = policy.priority_range();
+
+        if priority < min || priority > max {
+            return Err(Errno::EINVAL);
+        }
+        self.policy = policy;
+        self.rt_priority = priority;
+        Ok(())
+    }
+
+    /// The priority shown in /proc/[pid]/stat: 0 to 39 for the nice
+    /// values, -2 to -100 for the real-time priorities
+    pub fn priority(&self) -> i32 {
+        if self.policy.is_realtime() {
+            -1 - self.rt_priority as i32
+        } else {
+            20 + self.nice
+        }
+    }
+
+    /// Account `runtime` of CPU time to the thread
+    pub fn account(&mut self, runtime: Duration) {
+        let weight = NICE_TO_WEIGHT[(self.nice - NICE_MIN) as usize];
+
+        self.vruntime += runtime.as_micros() as u64 * NICE_0_WEIGHT / weight;
+    }
+
+    /// The rank of the thread in the next election. `min_vruntime` is
+    /// the virtual runtime of the last elected SCHED_OTHER thread.
+    pub fn rank(&mut self, min_vruntime: u64) -> Rank {
+        if self.policy.is_realtime() {
+            Rank::RealTime(Reverse(self.rt_priority))
+        } else {
+            self.vruntime = max(self.vruntime, min_vruntime.saturating_sub(SLEEPER_CREDIT));
+            Rank::Fair {
+                yielded: self.yielded,
+                vruntime: self.vruntime,
+            }
+        }
+    }
+
+    /// Does the thread keep the CPU against the threads of the same rank
+    pub fn keeps_cpu(&self) -> bool {
+        self.policy == SchedPolicy::Fifo && !self.yielded
+    }
+
+    /// The thread is elected, return its virtual runtime if it is a
+    /// SCHED_OTHER one
+    pub fn elect(&mut self) -> Option<u64> {
+        self.yielded = false;
+        if self.policy.is_realtime() {
+            None
+        } else {
+            Some(self.vruntime)
+        }
+    }
+
+    /// Let the other threads of the same rank run first
+    pub fn yield_cpu(&mut self) {
+        self.yielded = true;
+    }
+}
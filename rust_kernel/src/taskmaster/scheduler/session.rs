@@ -0,0 +1,276 @@
+//! Sessions and controlling terminals. The leader of a session may
+//! acquire a terminal which becomes the controlling terminal of the
+//! session: only its foreground process group may freely read from
+//! it, and it is hung up when the leader exits.
+
+use super::{JobAction, Pid, Scheduler, SysResult, ThreadGroup, VFS};
+use crate::taskmaster::fd_interface::Fd;
+
+use libc_binding::{dev_t, Errno, Signum};
+use messaging::{MessageTo, ProcessGroupMessage};
+use terminal::{JobControl, LineDiscipline, TtyAccess, TERMINAL};
+
+fn line_discipline(tty: dev_t) -> &'static mut LineDiscipline {
+    unsafe { TERMINAL.as_mut().unwrap().get_line_discipline(tty as usize) }
+}
+
+/// Does the parent `parent` keep the process group of `child` from
+/// being orphaned: it is in another process group of the same session
+fn is_link(parent: &ThreadGroup, child: &ThreadGroup) -> bool {
+    parent.pgid != child.pgid && parent.sid == child.sid
+}
+
+impl Scheduler {
+    /// Is the process group `pgid` orphaned: the parent of each of its
+    /// members is either a member of the group or is not a member of
+    /// its session. The process `exiting`, if any, is not counted and
+    /// its children are counted as children of the reaper.
+    pub fn is_orphaned_process_group(&self, pgid: Pid, exiting: Option<Pid>) -> bool {
+        !self
+            .iter_thread_groups_with_pid()
+            .filter(|(&pid, thread_group)| {
+                Some(pid) != exiting && thread_group.pgid == pgid && !thread_group.is_zombie()
+            })
+            .any(|(_, thread_group)| {
+                let parent_pid = if Some(thread_group.parent) == exiting {
+                    Self::REAPER_PID
+                } else {
+                    thread_group.parent
+                };
+                self.get_thread_group(parent_pid).map_or(false, |parent| {
+                    !parent.is_zombie() && is_link(parent, thread_group)
+                })
+            })
+    }
+
+    fn signal_process_group(&mut self, pgid: Pid, signum: Signum) {
+        self.send_message(MessageTo::ProcessGroup {
+            pgid,
+            content: ProcessGroupMessage::Signal(signum),
+        });
+    }
+
+    /// Send SIGHUP followed by SIGCONT to the process group `pgid`
+    fn hang_up(&mut self, pgid: Pid) {
+        self.signal_process_group(pgid, Signum::SIGHUP);
+        self.signal_process_group(pgid, Signum::SIGCONT);
+    }
+
+    /// Hang up the process group `pgid` if the exit of `exiting`, which
+    /// kept it from being orphaned, makes it orphaned while one of its
+    /// members is stopped
+    fn hang_up_if_orphaned(&mut self, pgid: Pid, exiting: Pid) {
+        let has_stopped_member = self
+            .iter_thread_groups()
+            .any(|thread_group| thread_group.pgid == pgid && thread_group.job.is_stopped());
+
+        if has_stopped_member && self.is_orphaned_process_group(pgid, Some(exiting)) {
+            self.hang_up(pgid);
+        }
+    }
+
+    /// Disassociate the terminal `tty` from the session `sid` it
+    /// controls, its foreground process group is hung up
+    fn disassociate_controlling_terminal(&mut self, sid: Pid, tty: dev_t) {
+        let line_discipline = line_discipline(tty);
+
+        if line_discipline.session() != Some(sid) {
+            return;
+        }
+        let foreground_process_group = line_discipline.tcgetpgrp();
+        line_discipline.set_session(None, 0);
+        if foreground_process_group != 0 {
+            self.hang_up(foreground_process_group);
+        }
+        for thread_group in self
+            .iter_thread_groups_mut()
+            .filter(|thread_group| thread_group.sid == sid)
+        {
+            if thread_group.controlling_terminal == Some(tty) {
+                thread_group.controlling_terminal = None;
+            }
+        }
+    }
+
+    /// Apply the consequences of the exit of the current process on
+    /// its session: a controlling process hangs up its terminal, and
+    /// the process groups orphaned by its exit which have a stopped
+    /// member are sent SIGHUP followed by SIGCONT
+    pub fn current_thread_group_leave_session(&mut self) {
+        let pid = self.current_task_id.0;
+        let thread_group = self.current_thread_group();
+        let (pgid, sid, parent_pid) = (thread_group.pgid, thread_group.sid, thread_group.parent);
+
+        if sid == pid {
+            if let Some(tty) = thread_group.controlling_terminal {
+                self.disassociate_controlling_terminal(sid, tty);
+            }
+        }
+        let is_link_of_own_group = self
+            .get_thread_group(parent_pid)
+            .map_or(false, |parent| is_link(parent, self.current_thread_group()));
+        if is_link_of_own_group {
+            self.hang_up_if_orphaned(pgid, pid);
+        }
+        for i in 0..self.current_thread_group_running().child.len() {
+            let child_pid = self.current_thread_group_running().child[i];
+            let child_pgid = match self.get_thread_group(child_pid) {
+                Some(child)
+                    if !child.is_zombie() && is_link(self.current_thread_group(), child) =>
+                {
+                    child.pgid
+                }
+                _ => continue,
+            };
+            self.hang_up_if_orphaned(child_pgid, pid);
+        }
+    }
+
+    /// Create a new session led by the current process, which has no
+    /// controlling terminal, return its ID
+    ///
+    /// [EPERM]
+    ///     The calling process is already a process group leader, or
+    ///     the process group ID of a process other than the calling
+    ///     process matches the process ID of the calling process.
+    pub fn current_thread_group_new_session(&mut self) -> SysResult<Pid> {
+        let pid = self.current_task_id.0;
+
+        if self
+            .iter_thread_groups()
+            .any(|thread_group| thread_group.pgid == pid)
+        {
+            return Err(Errno::EPERM);
+        }
+        let thread_group = self.current_thread_group_mut();
+        thread_group.sid = pid;
+        thread_group.pgid = pid;
+        thread_group.controlling_terminal = None;
+        Ok(pid)
+    }
+
+    /// The minor of the terminal opened as `fd` by the current
+    /// process, if it is one
+    pub fn current_thread_group_tty(&self, fd: Fd) -> Option<dev_t> {
+        let mut file_operation = self
+            .current_thread_group_running()
+            .file_descriptor_interface
+            .get_file_operation(fd)
+            .ok()?;
+
+        if file_operation.isatty() != Ok(1) {
+            return None;
+        }
+        let inode_id = file_operation.get_inode_id().ok()?;
+        let minor = VFS.lock().get_inode(inode_id).ok()?.minor;
+        Some(minor)
+    }
+
+    /// The controlling terminal of the current process, if it is the
+    /// terminal opened as `fd`
+    ///
+    /// [ENOTTY]
+    ///     fd is not the controlling terminal of the calling process.
+    pub fn current_thread_group_controlling_terminal(&self, fd: Fd) -> SysResult<dev_t> {
+        let thread_group = self.current_thread_group();
+
+        match (
+            thread_group.controlling_terminal,
+            self.current_thread_group_tty(fd),
+        ) {
+            (Some(ctty), Some(tty))
+                if ctty == tty && line_discipline(tty).session() == Some(thread_group.sid) =>
+            {
+                Ok(tty)
+            }
+            _ => Err(Errno::ENOTTY),
+        }
+    }
+
+    /// Make `tty` the controlling terminal of the session led by the
+    /// current process, with its process group in the foreground. A
+    /// privileged process may `steal` it from another session.
+    ///
+    /// [EPERM]
+    ///     The calling process is not a session leader, already has a
+    ///     controlling terminal, or the terminal is the controlling
+    ///     terminal of another session.
+    pub fn current_thread_group_acquire_terminal(
+        &mut self,
+        tty: dev_t,
+        steal: bool,
+    ) -> SysResult<()> {
+        let pid = self.current_task_id.0;
+        let thread_group = self.current_thread_group();
+        let (pgid, sid) = (thread_group.pgid, thread_group.sid);
+        let session = line_discipline(tty).session();
+
+        if thread_group.controlling_terminal == Some(tty) && session == Some(sid) {
+            return Ok(());
+        }
+        if sid != pid || thread_group.controlling_terminal.is_some() {
+            return Err(Errno::EPERM);
+        }
+        match session {
+            None => {}
+            Some(other) if steal && thread_group.credentials.is_root() => {
+                self.disassociate_controlling_terminal(other, tty);
+            }
+            Some(_) => return Err(Errno::EPERM),
+        }
+        line_discipline(tty).set_session(Some(sid), pgid);
+        self.current_thread_group_mut().controlling_terminal = Some(tty);
+        Ok(())
+    }
+
+    /// Give up the controlling terminal `tty` of the current process.
+    /// When it is the session leader, the terminal is disassociated
+    /// from the session.
+    pub fn current_thread_group_release_terminal(&mut self, tty: dev_t) {
+        let pid = self.current_task_id.0;
+        let sid = self.current_thread_group().sid;
+
+        if sid == pid {
+            self.disassociate_controlling_terminal(sid, tty);
+        } else {
+            self.current_thread_group_mut().controlling_terminal = None;
+        }
+    }
+
+    /// Apply the job control rules to an access of the current process
+    /// to the terminal opened as `fd`. Return true when its process
+    /// group was sent SIGTTIN or SIGTTOU to be stopped: the access must
+    /// be tried again once the process is continued.
+    ///
+    /// [EIO]
+    ///     The process is in a background process group which is
+    ///     orphaned, or it ignores or blocks SIGTTIN for a read.
+    /// [EINTR]
+    ///     The signal sent to the process group is caught.
+    pub fn current_thread_tty_job_control(&mut self, fd: Fd, access: TtyAccess) -> SysResult<bool> {
+        let tty = match self.current_thread_group_controlling_terminal(fd) {
+            Ok(tty) => tty,
+            Err(_) => return Ok(false),
+        };
+        let pgid = self.current_thread_group().pgid;
+        let signal_refused = self
+            .current_thread()
+            .signal
+            .is_ignored_or_blocked(access.signal());
+        let orphaned = self.is_orphaned_process_group(pgid, None);
+
+        match line_discipline(tty).job_control(access, pgid, orphaned, signal_refused) {
+            JobControl::Granted => Ok(false),
+            JobControl::Denied => Err(Errno::EIO),
+            JobControl::Signal(signum) => {
+                self.signal_process_group(pgid, signum);
+                let action = self.current_thread_get_job_action();
+                if action.intersects(JobAction::INTERRUPT | JobAction::TERMINATE) {
+                    Err(Errno::EINTR)
+                } else {
+                    Ok(true)
+                }
+            }
+        }
+    }
+}
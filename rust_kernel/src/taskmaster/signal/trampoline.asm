@@ -9,3 +9,11 @@ _trampoline:
 	mov eax, 200
 	int 0x80
 _trampoline_len:    dd $-_trampoline
+
+; trampoline code of the SA_SIGINFO handlers, just do a rt_sigreturn syscall
+global _rt_trampoline
+global _rt_trampoline_len
+_rt_trampoline:
+	mov eax, 173
+	int 0x80
+_rt_trampoline_len:    dd $-_rt_trampoline
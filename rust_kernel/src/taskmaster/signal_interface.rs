@@ -1,6 +1,7 @@
 //! This file contains signal interface
 
 use super::process::CpuState;
+use super::thread_group::Status;
 use super::SysResult;
 
 use alloc::collections::vec_deque::VecDeque;
@@ -10,16 +11,19 @@ use core::mem;
 use core::ops::{BitAnd, BitOr, BitOrAssign, Index, IndexMut, Not};
 use libc_binding::Errno;
 use libc_binding::Signum;
+use libc_binding::{stack_t, MINSIGSTKSZ, SS_DISABLE, SS_ONSTACK};
+use libc_binding::{uid_t, Pid, NSIG, SIGQUEUE_MAX, SI_KERNEL};
+use libc_binding::{CLD_CONTINUED, CLD_EXITED, CLD_KILLED, CLD_STOPPED};
 use libc_binding::{
     SA_NOCLDSTOP, SA_NOCLDWAIT, SA_NODEFER, SA_ONSTACK, SA_RESETHAND, SA_RESTART, SA_RESTORER,
     SA_SIGINFO,
 };
 use libc_binding::{SIG_BLOCK, SIG_SETMASK, SIG_UNBLOCK};
 
-use crate::memory::tools::PAGE_SIZE;
+use crate::memory::AddressSpace;
 
 #[allow(non_camel_case_types)]
-pub type sigset_t = u32;
+pub type sigset_t = u64;
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -54,18 +58,21 @@ impl core::convert::From<Signum> for DefaultAction {
 
             // SIGSTOP cannot be handled or ignore. For others, they can STOP process execution, or execute handler ot be ignored.
             SIGSTOP | SIGTSTP | SIGTTIN | SIGTTOU => DefaultAction::Stop,
+
+            // The real-time signals are deadly by default.
+            _ => DefaultAction::Terminate,
         }
     }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
 #[repr(transparent)]
-pub struct SaMask(u32);
+pub struct SaMask(u64);
 
 impl SaMask {
     /// Check is sa_mask contains a specified signum
     fn contains(&self, s: Signum) -> bool {
-        self.0.get_bit(s as u32 as usize)
+        self.0.get_bit(s as usize)
     }
 
     /// Check if the current Signum is marked as masked. Ignore it if SIGSTOP, SIGKILL and SIGCONT for job control
@@ -89,13 +96,13 @@ impl From<Signum> for SaMask {
     }
 }
 
-impl From<u32> for SaMask {
-    fn from(n: u32) -> Self {
+impl From<sigset_t> for SaMask {
+    fn from(n: sigset_t) -> Self {
         // make sure we don't create a mask with syskill or Sigstop as
         // syskill and sigstop cannot be blocked
-        SaMask(
-            n & !(Signum::SIGKILL as u32) & !(Signum::SIGSTOP as u32) & !(Signum::SIGCONT as u32),
-        )
+        let unblockable =
+            Self::from(Signum::SIGKILL) | Self::from(Signum::SIGSTOP) | Self::from(Signum::SIGCONT);
+        SaMask(n & !unblockable.0)
     }
 }
 
@@ -175,7 +182,7 @@ impl StructSigaction {
 }
 
 #[derive(Copy, Clone, Debug)]
-struct SignalActions(pub [StructSigaction; 32]);
+struct SignalActions(pub [StructSigaction; NSIG as usize]);
 
 impl IndexMut<Signum> for SignalActions {
     fn index_mut(&mut self, index: Signum) -> &mut StructSigaction {
@@ -190,15 +197,189 @@ impl Index<Signum> for SignalActions {
     }
 }
 
+/// The siginfo_t structure describing why a signal was generated,
+/// given to the SA_SIGINFO handlers
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct SigInfo {
+    pub si_signo: i32,
+    pub si_errno: i32,
+    pub si_code: i32,
+    pub si_pid: Pid,
+    pub si_uid: uid_t,
+    pub si_status: i32,
+    pub si_utime: i32,
+    pub si_stime: i32,
+    /// union sigval
+    pub si_value: u32,
+    pub si_int: i32,
+    pub si_ptr: u32,
+    pub si_addr: u32,
+    pub si_band: i32,
+    pub si_fd: i32,
+}
+
+impl SigInfo {
+    /// A signal generated for the reason `si_code`
+    pub fn new(si_code: i32) -> Self {
+        Self {
+            si_code,
+            ..Default::default()
+        }
+    }
+
+    /// A signal sent by the process `pid` of real user ID `uid`
+    pub fn sender(si_code: i32, pid: Pid, uid: uid_t) -> Self {
+        Self {
+            si_pid: pid,
+            si_uid: uid,
+            ..Self::new(si_code)
+        }
+    }
+
+    /// A signal generated by a fault at the address `addr`
+    pub fn fault(si_code: i32, addr: u32) -> Self {
+        Self {
+            si_addr: addr,
+            ..Self::new(si_code)
+        }
+    }
+
+    /// The SIGCHLD sent when the child `pid` of real user ID `uid`
+    /// changes to `status`
+    pub fn child(pid: Pid, uid: uid_t, status: Status) -> Self {
+        let (si_code, si_status) = match status {
+            Status::Exited(code) => (CLD_EXITED, code),
+            Status::Signaled(signum) => (CLD_KILLED, signum as i32),
+            Status::Stopped => (CLD_STOPPED, Signum::SIGSTOP as i32),
+            Status::Continued => (CLD_CONTINUED, Signum::SIGCONT as i32),
+        };
+        Self {
+            si_status,
+            ..Self::sender(si_code as i32, pid, uid)
+        }
+    }
+
+    /// Attach the value given to sigqueue()
+    pub fn with_value(self, value: u32) -> Self {
+        Self {
+            si_value: value,
+            si_int: value as i32,
+            si_ptr: value,
+            ..self
+        }
+    }
+}
+
+/// The registers of the interrupted thread, in the order of the REG_*
+/// indexes of the gregs of the mcontext_t structure
+#[derive(Debug, Copy, Clone, Default)]
+#[repr(C)]
+pub struct MContext {
+    pub gs: u32,
+    pub fs: u32,
+    pub es: u32,
+    pub ds: u32,
+    pub edi: u32,
+    pub esi: u32,
+    pub ebp: u32,
+    pub esp: u32,
+    pub ebx: u32,
+    pub edx: u32,
+    pub ecx: u32,
+    pub eax: u32,
+    pub trapno: u32,
+    pub err: u32,
+    pub eip: u32,
+    pub cs: u32,
+    pub eflags: u32,
+    pub uesp: u32,
+    pub ss: u32,
+}
+
+impl From<&CpuState> for MContext {
+    fn from(cpu_state: &CpuState) -> Self {
+        let registers = &cpu_state.registers;
+        Self {
+            gs: cpu_state.gs,
+            fs: cpu_state.fs,
+            es: cpu_state.es,
+            ds: cpu_state.ds,
+            edi: registers.edi,
+            esi: registers.esi,
+            ebp: registers.ebp,
+            esp: registers.esp,
+            ebx: registers.ebx,
+            edx: registers.edx,
+            ecx: registers.ecx,
+            eax: registers.eax,
+            trapno: cpu_state.cpu_isr_reserved,
+            err: cpu_state.err_code_reserved,
+            eip: cpu_state.eip,
+            cs: cpu_state.cs,
+            eflags: cpu_state.eflags.inner(),
+            uesp: cpu_state.esp,
+            ss: cpu_state.ss,
+        }
+    }
+}
+
+/// The ucontext_t structure given to the SA_SIGINFO handlers, the
+/// context restored when they return
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct UContext {
+    pub uc_link: u32,
+    pub uc_sigmask: sigset_t,
+    pub uc_stack: stack_t,
+    pub uc_mcontext: MContext,
+}
+
+impl UContext {
+    /// The address of the ucontext of the frame of a SA_SIGINFO
+    /// handler, when its trampoline code runs with the stack pointer
+    /// `user_esp`
+    pub fn from_frame(user_esp: u32) -> *const Self {
+        context_builder::rt_ucontext(user_esp)
+    }
+}
+
+/// A signal waiting to be delivered
+#[derive(Debug, Copy, Clone)]
+struct PendingSignal {
+    signum: Signum,
+    info: SigInfo,
+}
+
 /// Main Signal Inteface structure
 #[derive(Debug)]
 pub struct SignalInterface {
     /// This array contains all the actions which must be applied for each signums
     signal_actions: SignalActions,
-    /// This is the queue of all pending signals
-    signal_queue: VecDeque<Signum>,
+    /// This is the queue of all pending signals: the classic signals,
+    /// followed by the real-time signals by increasing number
+    signal_queue: VecDeque<PendingSignal>,
     /// The SaMask specifies a mask of signals which should be blocked
     current_sa_mask: SaMask,
+    /// The stack of the SA_ONSTACK handlers, set by sigaltstack()
+    alt_stack: Option<AltStack>,
+    /// The signals awaited by sigtimedwait(), which resume the thread
+    /// even if they are blocked
+    waited_signals: SaMask,
+}
+
+/// An alternate signal stack, from `sp` to `sp + size`
+#[derive(Debug, Copy, Clone)]
+struct AltStack {
+    sp: u32,
+    size: u32,
+}
+
+impl AltStack {
+    /// Is the stack pointer `esp` on this stack
+    fn contains(&self, esp: u32) -> bool {
+        esp > self.sp && esp - self.sp <= self.size
+    }
 }
 
 bitflags! {
@@ -218,9 +399,11 @@ impl SignalInterface {
     /// Create a new signal Inteface
     pub fn new() -> Self {
         Self {
-            signal_actions: SignalActions([Default::default(); 32]),
+            signal_actions: SignalActions([Default::default(); NSIG as usize]),
             signal_queue: VecDeque::new(),
             current_sa_mask: Default::default(),
+            alt_stack: None,
+            waited_signals: Default::default(),
         }
     }
 
@@ -247,6 +430,7 @@ impl SignalInterface {
             // SA_ONSTACK flag shall be cleared for all signals.
             sigaction.sa_flags.remove(SaFlags::SA_ONSTACK);
         }
+        self.alt_stack = None;
 
         // The initial thread of the new process shall inherit at
         // least the following attributes from the calling thread:
@@ -266,6 +450,40 @@ impl SignalInterface {
             // parent process if the thread was created as the result of a call to
             // fork()
             current_sa_mask: self.current_sa_mask,
+            // The alternate stack is inherited, the creator of a thread
+            // sharing the address space must drop it
+            alt_stack: self.alt_stack,
+            waited_signals: Default::default(),
+        }
+    }
+
+    /// Forget the alternate signal stack, for a new thread which cannot
+    /// use the one of its creator
+    pub fn drop_alt_stack(&mut self) {
+        self.alt_stack = None;
+    }
+
+    /// The number of pending signals
+    pub fn pending_signals_len(&self) -> usize {
+        self.signal_queue.len()
+    }
+
+    /// Would `signum` be discarded or left pending if it was generated
+    pub fn is_ignored_or_blocked(&self, signum: Signum) -> bool {
+        self.signal_actions[signum].sa_handler == SIG_IGN || self.current_sa_mask.is_masked(signum)
+    }
+
+    /// Make room for `additional` pending signals to be adopted
+    pub fn reserve_pending_signals(&mut self, additional: usize) -> SysResult<()> {
+        Ok(self.signal_queue.try_reserve(additional)?)
+    }
+
+    /// Queue the signals pending for `other`, a thread terminated by
+    /// execve. The room for them must have been reserved.
+    pub fn adopt_pending_signals(&mut self, other: &Self) {
+        for pending in &other.signal_queue {
+            self.generate_signal_info(pending.signum, pending.info)
+                .expect("no room reserved for the pending signals");
         }
     }
 
@@ -275,7 +493,12 @@ impl SignalInterface {
         let mut action: JobAction = JobAction::default();
         let mut sa_mask = self.current_sa_mask;
 
-        for &signum in self.signal_queue.iter() {
+        for &PendingSignal { signum, .. } in self.signal_queue.iter() {
+            // The thread waiting for it in sigtimedwait() takes it
+            if self.waited_signals.contains(signum) {
+                action |= JobAction::INTERRUPT;
+                continue;
+            }
             if sa_mask.is_masked(signum) {
                 continue;
             }
@@ -300,16 +523,20 @@ impl SignalInterface {
     }
 
     /// Create handler contexts and pop the signal queue. Return Some(signum) in case of Deadly signal
+    ///
+    /// A single handler context is created at a time: the next pending
+    /// signal is delivered when the handler returns, so that the
+    /// signals are handled in the order of the queue.
     pub fn exec_signal_handler(
         &mut self,
         cpu_state: *mut CpuState,
-        user_stack_range: (u32, u32),
+        address_space: &AddressSpace,
         in_blocked_syscall: bool,
     ) -> Option<Signum> {
         let mut i = 0;
-        let mut frame_build = 0;
+        let mut frame_built = false;
 
-        while let Some(&signum) = self.signal_queue.get(i) {
+        while let Some(&PendingSignal { signum, info }) = self.signal_queue.get(i) {
             if self.current_sa_mask.is_masked(signum) {
                 i += 1;
             } else {
@@ -321,29 +548,36 @@ impl SignalInterface {
                         _ => {}
                     },
                     _ => {
+                        // The handler runs on the alternate stack if it
+                        // asks for it and does not already run on it
                         let process_esp = unsafe { (*cpu_state).esp };
-                        if process_esp >= user_stack_range.1 {
-                            log::error!("ESP range of the current process is bullshit !");
-                            log::error!(
-                                "proc esp: {:#X?} > stack end: {:#X?}",
-                                process_esp,
-                                user_stack_range.1
-                            );
-                            return Some(Signum::SIGKILL);
-                        }
-                        // It There are not enough space in user stack (neg | x < PAGE_SIZE)
-                        else if process_esp < user_stack_range.0
-                            || process_esp - user_stack_range.0 < PAGE_SIZE as u32
-                        {
-                            log::warn!("ESP range underflow detected !");
-                            log::warn!(
-                                "proc esp: {:#X?}, stack start: {:#X?}",
-                                process_esp,
-                                user_stack_range.0
-                            );
-                            return Some(Signum::SIGKILL);
+                        let esp = match self.alt_stack {
+                            Some(alt_stack)
+                                if sigaction.sa_flags.contains(SaFlags::SA_ONSTACK)
+                                    && !alt_stack.contains(process_esp) =>
+                            {
+                                alt_stack.sp.wrapping_add(alt_stack.size)
+                            }
+                            _ => process_esp,
+                        };
+                        // The frame is written by the kernel: its pages
+                        // must be allocated and writable
+                        let with_siginfo = sigaction.sa_flags.contains(SaFlags::SA_SIGINFO);
+                        let frame_size = if with_siginfo {
+                            context_builder::rt_frame_size()
+                        } else {
+                            context_builder::frame_size()
+                        };
+                        let frame_fits = esp.checked_sub(frame_size).map_or(false, |frame| {
+                            address_space
+                                .check_user_mut_ptr_with_len(frame as *mut u8, frame_size as usize)
+                                .is_ok()
+                        });
+                        if !frame_fits {
+                            log::warn!("No room for a signal frame below esp: {:#X?}", esp);
+                            return Some(Signum::SIGSEGV);
                         }
-                        if frame_build == 0 && in_blocked_syscall {
+                        if in_blocked_syscall {
                             if sigaction.sa_flags.intersects(SaFlags::SA_RESTART) {
                                 // Back 2 instruction to reput eip on `int 80h` and restart the syscall
                                 unsafe { (*cpu_state).eip -= 2 }
@@ -354,19 +588,35 @@ impl SignalInterface {
                                 };
                             }
                         }
-                        frame_build += 1;
+                        frame_built = true;
                         unsafe {
-                            context_builder::push(
-                                cpu_state,
-                                self.current_sa_mask,
-                                signum,
-                                sigaction.sa_handler as u32,
-                            );
+                            if with_siginfo {
+                                context_builder::push_rt(
+                                    cpu_state,
+                                    esp,
+                                    self.current_sa_mask,
+                                    signum,
+                                    info,
+                                    self.alt_stack_state(process_esp),
+                                    sigaction.sa_handler as u32,
+                                );
+                            } else {
+                                context_builder::push(
+                                    cpu_state,
+                                    esp,
+                                    self.current_sa_mask,
+                                    signum,
+                                    sigaction.sa_handler as u32,
+                                );
+                            }
                         }
                         self.current_sa_mask.update(signum, &sigaction);
                     }
                 };
                 self.signal_queue.remove(i);
+                if frame_built {
+                    break;
+                }
             }
         }
         None
@@ -379,6 +629,14 @@ impl SignalInterface {
         }
     }
 
+    /// Acknowledge the end of a SA_SIGINFO handler, restore the
+    /// context saved in `ucontext`, which the handler may have changed
+    pub fn terminate_rt_signal(&mut self, cpu_state: *mut CpuState, ucontext: &UContext) {
+        unsafe {
+            self.current_sa_mask = context_builder::pop_rt(cpu_state, ucontext);
+        }
+    }
+
     /// Register a new handler for a specified Signum
     pub fn new_handler(
         &mut self,
@@ -405,10 +663,32 @@ impl SignalInterface {
         }
     }
 
-    /// Register a new signal
+    /// Register a new signal generated by the kernel
     pub fn generate_signal(&mut self, signum: Signum) -> SysResult<u32> {
-        // If the same signal already exists in signal queue, ignore it
-        if self.signal_queue.iter().any(|&s| s == signum) {
+        self.generate_signal_info(signum, SigInfo::new(SI_KERNEL as i32))
+    }
+
+    /// Register a new signal with the informations about its generation
+    ///
+    /// [EAGAIN]
+    ///     SIGQUEUE_MAX real-time signals are already pending.
+    pub fn generate_signal_info(&mut self, signum: Signum, info: SigInfo) -> SysResult<u32> {
+        if signum.is_realtime() {
+            // Each real-time signal is queued with its value
+            let queued = self
+                .signal_queue
+                .iter()
+                .filter(|pending| pending.signum.is_realtime())
+                .count();
+            if queued >= SIGQUEUE_MAX as usize {
+                return Err(Errno::EAGAIN);
+            }
+        } else if self
+            .signal_queue
+            .iter()
+            .any(|pending| pending.signum == signum)
+        {
+            // If the same signal already exists in signal queue, ignore it
             return Ok(0);
         }
 
@@ -419,22 +699,67 @@ impl SignalInterface {
         // or any of the threads within that process shall be
         // discarded.
         if default_action == DefaultAction::Stop {
-            self.signal_queue.retain(|&s| s != Signum::SIGCONT);
+            self.signal_queue
+                .retain(|pending| pending.signum != Signum::SIGCONT);
         }
 
         // Conversely, when SIGCONT is generated for a process or
         // thread, all pending stop signals for that process or any of the
         // threads within that process shall be discarded
         if default_action == DefaultAction::Continue {
-            self.signal_queue
-                .retain(|&s| Into::<DefaultAction>::into(s) != DefaultAction::Stop);
+            self.signal_queue.retain(|pending| {
+                Into::<DefaultAction>::into(pending.signum) != DefaultAction::Stop
+            });
         }
 
         self.signal_queue.try_reserve(1)?;
-        self.signal_queue.push_back(signum);
+        // The lowest real-time signals are delivered first, and a signal
+        // after the ones of the same number
+        let pending = PendingSignal {
+            signum,
+            info: SigInfo {
+                si_signo: signum as i32,
+                ..info
+            },
+        };
+        let position = self
+            .signal_queue
+            .iter()
+            .position(|other| other.signum.is_realtime() && other.signum as u32 > signum as u32);
+        match position {
+            Some(index) => self.signal_queue.insert(index, pending),
+            None => self.signal_queue.push_back(pending),
+        }
         Ok(0)
     }
 
+    /// The signals which are pending and blocked
+    pub fn pending_signals(&self) -> sigset_t {
+        self.signal_queue
+            .iter()
+            .filter(|pending| self.current_sa_mask.is_masked(pending.signum))
+            .fold(0, |set, pending| set | SaMask::from(pending.signum).0)
+    }
+
+    /// Remove the first pending signal of `set` from the queue, even if
+    /// it is blocked, and get it with its informations
+    pub fn take_pending_signal(&mut self, set: sigset_t) -> Option<(Signum, SigInfo)> {
+        let set = SaMask::from(set);
+        let index = self
+            .signal_queue
+            .iter()
+            .position(|pending| set.contains(pending.signum))?;
+        self.signal_queue
+            .remove(index)
+            .map(|pending| (pending.signum, pending.info))
+    }
+
+    /// Set the signals awaited by sigtimedwait(), an empty set when it
+    /// returns
+    pub fn set_waited_signals(&mut self, set: sigset_t) {
+        self.waited_signals = SaMask::from(set);
+    }
+
     pub fn change_signal_mask(
         &mut self,
         how: u32,
@@ -462,23 +787,92 @@ impl SignalInterface {
         }
         Ok(0)
     }
+
+    /// The alternate stack as seen by a thread whose stack pointer is
+    /// `esp`
+    fn alt_stack_state(&self, esp: u32) -> stack_t {
+        match self.alt_stack {
+            Some(alt_stack) => stack_t {
+                ss_sp: alt_stack.sp as *mut _,
+                ss_size: alt_stack.size,
+                ss_flags: if alt_stack.contains(esp) {
+                    SS_ONSTACK as i32
+                } else {
+                    0
+                },
+            },
+            None => stack_t {
+                ss_sp: core::ptr::null_mut(),
+                ss_size: 0,
+                ss_flags: SS_DISABLE as i32,
+            },
+        }
+    }
+
+    /// Set the alternate stack to `ss` and get the previous one in
+    /// `old_ss`. `esp` is the stack pointer of the thread, which cannot
+    /// change the stack it runs on.
+    pub fn change_alt_stack(
+        &mut self,
+        ss: Option<stack_t>,
+        old_ss: Option<&mut stack_t>,
+        esp: u32,
+    ) -> SysResult<u32> {
+        let on_alt_stack = self.alt_stack.map_or(false, |alt| alt.contains(esp));
+        let old = self.alt_stack_state(esp);
+        if let Some(ss) = ss {
+            if on_alt_stack {
+                return Err(Errno::EPERM);
+            }
+            if ss.ss_flags == SS_DISABLE as i32 {
+                self.alt_stack = None;
+            } else if ss.ss_flags != 0 {
+                return Err(Errno::EINVAL);
+            } else if ss.ss_size < MINSIGSTKSZ {
+                return Err(Errno::ENOMEM);
+            } else {
+                self.alt_stack = Some(AltStack {
+                    sp: ss.ss_sp as u32,
+                    size: ss.ss_size,
+                });
+            }
+        }
+        if let Some(old_ss) = old_ss {
+            *old_ss = old;
+        }
+        Ok(0)
+    }
 }
 
 /// This module allow to create contexts for handlers and to get back from them
 mod context_builder {
-    use super::{CpuState, SaMask, Signum};
+    use super::{CpuState, MContext, SaMask, SigInfo, Signum, UContext};
 
     use core::mem::size_of;
+    use i386::Eflags;
+    use libc_binding::stack_t;
+
+    /// The flags of EFLAGS a handler may change in its ucontext: CF,
+    /// PF, AF, ZF, SF, TF, DF, OF, RF, AC and ID
+    const USER_EFLAGS: u32 = 0x0025_0DD5;
+
+    /// The size of the frame built by `push`
+    pub fn frame_size() -> u32 {
+        (size_of::<CpuState>()
+            + size_of::<SaMask>()
+            + align_on(unsafe { _trampoline_len } as usize, 4)
+            + 2 * size_of::<u32>()) as u32
+    }
 
-    /// Create a new context witch will execute a signal handler
+    /// Create a new context witch will execute a signal handler, on the
+    /// stack ending at `user_esp`
     pub unsafe fn push(
         cpu_state: *mut CpuState,
+        mut user_esp: u32,
         sa_mask: SaMask,
         signum: Signum,
         handler_address: u32,
     ) {
-        let mut user_esp = (*cpu_state).esp;
-
         /* PUSH DATA SECTION */
 
         // push the current cpu_state on the user stack
@@ -503,6 +897,95 @@ mod context_builder {
         (*cpu_state).esp = user_esp;
     }
 
+    /// The size of the frame built by `push_rt`
+    pub fn rt_frame_size() -> u32 {
+        (size_of::<UContext>()
+            + size_of::<SigInfo>()
+            + align_on(unsafe { _rt_trampoline_len } as usize, 4)
+            + 4 * size_of::<u32>()) as u32
+    }
+
+    /// Create a new context witch will execute the SA_SIGINFO handler
+    /// `handler_address(signum, &siginfo, &ucontext)`, on the stack
+    /// ending at `user_esp`
+    pub unsafe fn push_rt(
+        cpu_state: *mut CpuState,
+        mut user_esp: u32,
+        sa_mask: SaMask,
+        signum: Signum,
+        info: SigInfo,
+        uc_stack: stack_t,
+        handler_address: u32,
+    ) {
+        /* PUSH DATA SECTION */
+
+        // push the context to restore on the user stack
+        push_esp(
+            &mut user_esp,
+            UContext {
+                uc_link: 0,
+                uc_sigmask: sa_mask.0,
+                uc_stack,
+                uc_mcontext: MContext::from(&*cpu_state),
+            },
+        );
+        let ucontext = user_esp;
+
+        // push the siginfo
+        push_esp(&mut user_esp, info);
+        let siginfo = user_esp;
+
+        // push the trampoline code on the user stack
+        push_buff_esp(
+            &mut user_esp,
+            symbol_addr!(_rt_trampoline) as *mut u8,
+            _rt_trampoline_len as usize,
+        );
+
+        // push the arguments of the handler and the address of the trampoline code
+        let eip_trampoline = user_esp;
+        push_esp(&mut user_esp, ucontext);
+        push_esp(&mut user_esp, siginfo);
+        push_esp(&mut user_esp, signum as u32);
+        push_esp(&mut user_esp, eip_trampoline);
+
+        (*cpu_state).eip = handler_address;
+        (*cpu_state).esp = user_esp;
+    }
+
+    /// The address of the ucontext of the frame built by `push_rt`
+    pub fn rt_ucontext(user_esp: u32) -> *const UContext {
+        // skip the arguments, the trampoline code and the siginfo
+        (user_esp as usize
+            + 3 * size_of::<u32>()
+            + align_on(unsafe { _rt_trampoline_len } as usize, 4)
+            + size_of::<SigInfo>()) as *const UContext
+    }
+
+    /// Set the execution on the context of `ucontext`. Return the stored SA_MASK
+    pub unsafe fn pop_rt(cpu_state: *mut CpuState, ucontext: &UContext) -> SaMask {
+        let mcontext = &ucontext.uc_mcontext;
+
+        // secure restore stored registers (GDT selectors are exclude)
+        let registers = &mut (*cpu_state).registers;
+        registers.edi = mcontext.edi;
+        registers.esi = mcontext.esi;
+        registers.ebp = mcontext.ebp;
+        registers.esp = mcontext.esp;
+        registers.ebx = mcontext.ebx;
+        registers.edx = mcontext.edx;
+        registers.ecx = mcontext.ecx;
+        registers.eax = mcontext.eax;
+        (*cpu_state).eip = mcontext.eip;
+        (*cpu_state).esp = mcontext.uesp;
+        (*cpu_state).eflags = Eflags::new(
+            ((*cpu_state).eflags.inner() & !USER_EFLAGS) | (mcontext.eflags & USER_EFLAGS),
+        );
+
+        // return stored sa_mask
+        SaMask::from(ucontext.uc_sigmask)
+    }
+
     /// Destroy a context and set execution pointer on the previous context. Return the stored SA_MASK
     pub unsafe fn pop(cpu_state: *mut CpuState) -> SaMask {
         // skip the trampoline code
@@ -577,5 +1060,7 @@ mod context_builder {
     extern "C" {
         static _trampoline: u8;
         static _trampoline_len: u32;
+        static _rt_trampoline: u8;
+        static _rt_trampoline_len: u32;
     }
 }
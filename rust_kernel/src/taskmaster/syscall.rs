@@ -1,30 +1,38 @@
 //! all kernel syscall start by sys_ and userspace syscall (which will be in libc anyway) start by user_
 
-use super::fd_interface::Fd;
+use super::fd_interface::{self, Fd};
 use super::global_time::{TimeSession, GLOBAL_TIME};
 use super::kmodules;
 use super::process;
 use super::process::CpuState;
+use super::rusage;
 use super::safe_ffi;
 use super::scheduler;
 use super::scheduler::{Pid, PreemptionGuard, SCHEDULER};
 use super::signal_interface;
-use super::signal_interface::{sigset_t, StructSigaction};
+use super::signal_interface::{sigset_t, SigInfo, StructSigaction};
 use super::thread;
 use super::thread_group;
 use super::vfs;
 use super::IpcResult;
 use super::{IntoRawResult, SysResult};
 use libc_binding::{
-    ACCESS, CHDIR, CHMOD, CHOWN, CLONE, CLOSE, DUP, DUP2, EXECVE, EXIT, EXIT_QEMU, FCHMOD, FCHOWN,
-    FCNTL, FORK, FSTAT, FSTATFS, GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID,
-    GETPGRP, GETPID, GETPPID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES, INSMOD, IOCTL, ISATTY,
-    IS_STR_VALID, KILL, LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP, MOUNT, MPROTECT, MUNMAP,
-    NANOSLEEP, OPEN, OPENDIR, PAUSE, PIPE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD, SETEGID,
-    SETEUID, SETGID, SETGROUPS, SETHOSTNAME, SETPGID, SETUID, SHUTDOWN, SIGACTION, SIGNAL,
-    SIGPROCMASK, SIGRETURN, SIGSUSPEND, SOCKETCALL, STACK_OVERFLOW, STAT, STATFS, SYMLINK,
-    TCGETATTR, TCGETPGRP, TCSETATTR, TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4,
-    WAITPID, WRITE,
+    ACCESS, CHDIR, CHMOD, CHOWN, CHROOT, CLONE, CLOSE, DUP, DUP2, DUP3, EXECVE, EXIT, EXIT_QEMU,
+    FCHDIR, FCHMOD, FCHOWN, FCNTL, FDATASYNC, FLOCK, FORK, FSTAT, FSTATAT, FSTATFS, FSYNC,
+    FTRUNCATE64, GETCWD, GETDENTS64, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID,
+    GETPGRP, GETPID, GETPPID, GETPRIORITY, GETRESGID, GETRESUID, GETRLIMIT, GETRUSAGE, GETSID,
+    GETTIMEOFDAY, GETUID, GETXATTR, GET_KERNEL_PROPERTIES, INOTIFY_ADD_WATCH, INOTIFY_INIT,
+    INOTIFY_INIT1, INOTIFY_RM_WATCH, INSMOD, IOCTL, ISATTY, IS_STR_VALID, KILL, LINK, LISTXATTR,
+    LSEEK, LSMOD, LSTAT, MKDIR, MKDIRAT, MKNOD, MMAP, MOUNT, MPROTECT, MUNMAP, NANOSLEEP, NICE,
+    OPEN, OPENAT, PAUSE, PERSONALITY, PIPE, PIPE2, PREAD64, PREADV, PWRITE64, PWRITEV, READ,
+    READLINK, READLINKAT, READV, REBOOT, REMOVEXATTR, RENAME, RENAMEAT, RMDIR, RMMOD, RT_SIGRETURN,
+    SCHED_GETPARAM, SCHED_GETSCHEDULER, SCHED_GET_PRIORITY_MAX, SCHED_GET_PRIORITY_MIN,
+    SCHED_SETPARAM, SCHED_SETSCHEDULER, SCHED_YIELD, SETEGID, SETEUID, SETGID, SETGROUPS,
+    SETHOSTNAME, SETPGID, SETPRIORITY, SETRESGID, SETRESUID, SETRLIMIT, SETSID, SETUID, SETXATTR,
+    SHUTDOWN, SIGACTION, SIGALTSTACK, SIGNAL, SIGPENDING, SIGPROCMASK, SIGQUEUE, SIGRETURN,
+    SIGSUSPEND, SIGTIMEDWAIT, SOCKETCALL, STACK_OVERFLOW, STAT, STATFS, SYMLINK, SYNC, SYNCFS,
+    TCGETATTR, TCGETPGRP, TCSETATTR, TCSETPGRP, TEST, TIMES, TRUNCATE64, UMASK, UMOUNT, UNLINK,
+    UNLINKAT, UTIME, VFORK, WAIT4, WAITID, WAITPID, WRITE, WRITEV,
 };
 
 use core::ffi::c_void;
@@ -32,8 +40,8 @@ use i386::BaseRegisters;
 use interrupts::idt::{GateType, IdtGateEntry, InterruptTable};
 use libc_binding::Errno;
 use libc_binding::{
-    c_char, dev_t, gid_t, kernel, mode_t, off_t, rusage, termios, timeval, timezone, tms, uid_t,
-    utimbuf, DIR,
+    c_char, dev_t, gid_t, iovec, kernel, mode_t, off_t, rlimit, rusage, sched_param, stack_t,
+    termios, timeval, timezone, tms, uid_t, utimbuf,
 };
 
 mod mmap;
@@ -46,11 +54,14 @@ mod gettimeofday;
 use gettimeofday::sys_gettimeofday;
 
 mod wait4;
-pub use wait4::WaitOption;
+pub use wait4::{WaitOption, WaitSet};
 use wait4::{sys_wait4, sys_waitpid};
 
+mod waitid;
+use waitid::sys_waitid;
+
 mod unlink;
-use unlink::sys_unlink;
+use unlink::{sys_unlink, sys_unlinkat};
 
 mod execve;
 use execve::sys_execve;
@@ -94,6 +105,15 @@ use seteuid::sys_seteuid;
 mod sigsuspend;
 use sigsuspend::sys_sigsuspend;
 
+mod sigpending;
+use sigpending::sys_sigpending;
+
+mod sigtimedwait;
+use sigtimedwait::sys_sigtimedwait;
+
+mod sigaltstack;
+use sigaltstack::sys_sigaltstack;
+
 mod signal;
 use signal::sys_signal;
 
@@ -104,13 +124,14 @@ mod sigaction;
 use sigaction::sys_sigaction;
 
 mod sigreturn;
-use sigreturn::sys_sigreturn;
+use sigreturn::{sys_rt_sigreturn, sys_sigreturn};
 
 mod pause;
 use pause::sys_pause;
 
 mod kill;
 pub use kill::sys_kill;
+use kill::sys_sigqueue;
 
 mod mprotect;
 use mprotect::{sys_mprotect, MmapProt};
@@ -136,6 +157,9 @@ use test::sys_test;
 mod fork;
 use fork::sys_fork;
 
+mod vfork;
+use vfork::sys_vfork;
+
 mod getpgrp;
 use getpgrp::sys_getpgrp;
 
@@ -145,6 +169,12 @@ use getpgid::sys_getpgid;
 mod setpgid;
 use setpgid::sys_setpgid;
 
+mod setsid;
+use setsid::sys_setsid;
+
+mod getsid;
+use getsid::sys_getsid;
+
 mod getuid;
 use getuid::sys_getuid;
 
@@ -169,11 +199,51 @@ use lseek::sys_lseek;
 mod fcntl;
 use fcntl::sys_fcntl;
 
-mod opendir;
-use opendir::sys_opendir;
+mod flock;
+use flock::sys_flock;
+
+mod getdents64;
+use getdents64::sys_getdents64;
+
+mod pread;
+use pread::{sys_pread64, sys_pwrite64};
+
+mod uio;
+use uio::{sys_preadv, sys_pwritev, sys_readv, sys_writev};
+
+mod inotify;
+use inotify::{sys_inotify_add_watch, sys_inotify_init, sys_inotify_init1, sys_inotify_rm_watch};
+
+mod truncate;
+use truncate::{sys_ftruncate64, sys_truncate64};
+
+mod fsync;
+use fsync::{sys_fdatasync, sys_fsync, sys_sync, sys_syncfs};
+
+mod rlimit;
+use rlimit::{sys_getrlimit, sys_setrlimit};
+
+mod getrusage;
+use getrusage::sys_getrusage;
+
+mod personality;
+use personality::sys_personality;
+
+mod sched;
+use sched::{
+    sys_getpriority, sys_nice, sys_sched_get_priority_max, sys_sched_get_priority_min,
+    sys_sched_getparam, sys_sched_getscheduler, sys_sched_setparam, sys_sched_setscheduler,
+    sys_sched_yield, sys_setpriority,
+};
+
+mod setresuid;
+use setresuid::{sys_setresgid, sys_setresuid};
+
+mod getresuid;
+use getresuid::{sys_getresgid, sys_getresuid};
 
 mod stat;
-use stat::{statfn, sys_stat};
+use stat::{statfn, sys_fstatat, sys_stat};
 
 mod lstat;
 use lstat::sys_lstat;
@@ -187,8 +257,23 @@ use statfs::sys_statfs;
 mod fstatfs;
 use fstatfs::sys_fstatfs;
 
+mod getxattr;
+use getxattr::sys_getxattr;
+
+mod setxattr;
+use setxattr::sys_setxattr;
+
+mod listxattr;
+use listxattr::sys_listxattr;
+
+mod removexattr;
+use removexattr::sys_removexattr;
+
 mod chdir;
-use chdir::sys_chdir;
+use chdir::{sys_chdir, sys_fchdir};
+
+mod chroot;
+use chroot::sys_chroot;
 
 mod getcwd;
 use getcwd::sys_getcwd;
@@ -214,11 +299,11 @@ use fchmod::sys_fchmod;
 mod link;
 use link::sys_link;
 mod mkdir;
-use mkdir::sys_mkdir;
+use mkdir::{sys_mkdir, sys_mkdirat};
 mod rmdir;
 use rmdir::sys_rmdir;
 mod rename;
-use rename::sys_rename;
+use rename::{sys_rename, sys_renameat};
 mod symlink;
 use symlink::sys_symlink;
 mod mknod;
@@ -226,7 +311,7 @@ use mknod::sys_mknod;
 mod utime;
 use utime::sys_utime;
 mod readlink;
-use readlink::sys_readlink;
+use readlink::{sys_readlink, sys_readlinkat};
 
 /*
  * These below declarations are IPC related
@@ -234,9 +319,9 @@ use readlink::sys_readlink;
 mod dup;
 use dup::sys_dup;
 mod dup2;
-use dup2::sys_dup2;
+use dup2::{sys_dup2, sys_dup3};
 mod pipe;
-use pipe::sys_pipe;
+use pipe::{sys_pipe, sys_pipe2};
 pub mod socket;
 use socket::{sys_socketcall, SocketArgsPtr};
 mod read;
@@ -244,7 +329,7 @@ use read::sys_read;
 mod write;
 use write::sys_write;
 mod open;
-use open::sys_open;
+use open::{sys_open, sys_openat};
 mod close;
 use close::sys_close;
 mod isatty;
@@ -320,6 +405,7 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
     let result = match eax {
         EXIT => sys_exit(ebx as i32),       // This syscall doesn't return !
         FORK => sys_fork(cpu_state as u32), // CpuState represents kernel_esp
+        VFORK => sys_vfork(cpu_state as u32),
         READ => sys_read(ebx as i32, ecx as *mut u8, edx as usize),
         WRITE => sys_write(ebx as i32, ecx as *const u8, edx as usize),
         OPEN => sys_open(ebx as *const c_char, ecx as u32, edx as mode_t),
@@ -334,6 +420,7 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
             edx as *const *const c_char,
         ),
         CHDIR => sys_chdir(ebx as *const c_char),
+        CHROOT => sys_chroot(ebx as *const c_char),
         CHMOD => sys_chmod(ebx as *const c_char, ecx as mode_t),
         FCHMOD => sys_fchmod(ebx as Fd, ecx as mode_t),
         MKNOD => sys_mknod(ebx as *const c_char, ecx as mode_t, edx as dev_t),
@@ -358,30 +445,44 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         ACCESS => sys_access(ebx as *const c_char, ecx as u32),
         UTIME => sys_utime(ebx as *const c_char, ecx as *const utimbuf),
         KILL => sys_kill(ebx as i32, ecx as u32),
+        SIGQUEUE => sys_sigqueue(ebx as Pid, ecx as u32, edx as u32),
         RENAME => sys_rename(ebx as *const c_char, ecx as *const c_char),
         MKDIR => sys_mkdir(ebx as *const c_char, ecx as mode_t),
         RMDIR => sys_rmdir(ebx as *const c_char),
         PIPE => sys_pipe(core::slice::from_raw_parts_mut(ebx as *mut i32, 2)),
+        PIPE2 => sys_pipe2(
+            core::slice::from_raw_parts_mut(ebx as *mut i32, 2),
+            ecx as u32,
+        ),
         TIMES => sys_times(ebx as *mut tms),
         DUP => sys_dup(ebx as u32),
         SETGID => sys_setgid(ebx as gid_t),
         GETGID => sys_getgid(),
         GETEUID => sys_geteuid(),
-        FCNTL => sys_fcntl(ebx as Fd, ecx as u32, edx as Fd),
+        FCNTL => sys_fcntl(ebx as Fd, ecx as u32, edx as u32),
+        FLOCK => sys_flock(ebx as Fd, ecx as u32),
         GETEGID => sys_getegid(),
         UMOUNT => sys_umount(ebx as *const c_char),
         IOCTL => sys_ioctl(ebx as Fd, ecx as u32, edx as u32),
         SIGNAL => sys_signal(ebx as u32, ecx as usize),
         SETPGID => sys_setpgid(ebx as Pid, ecx as Pid),
         DUP2 => sys_dup2(ebx as u32, ecx as u32),
+        DUP3 => sys_dup3(ebx as u32, ecx as u32, edx as u32),
         GETPPID => sys_getppid(),
         GETPGRP => sys_getpgrp(),
+        SETSID => sys_setsid(),
         SIGACTION => sys_sigaction(
             ebx as u32,
             ecx as *const StructSigaction,
             edx as *mut StructSigaction,
         ),
         SIGSUSPEND => sys_sigsuspend(ebx as *const sigset_t),
+        SIGPENDING => sys_sigpending(ebx as *mut sigset_t),
+        SIGTIMEDWAIT => sys_sigtimedwait(
+            ebx as *const sigset_t,
+            ecx as *mut SigInfo,
+            edx as *const TimeSpec,
+        ),
         GETGROUPS => sys_getgroups(ebx as i32, ecx as *mut gid_t),
         SETGROUPS => sys_setgroups(ebx as i32, ecx as *const gid_t),
         SYMLINK => sys_symlink(ebx as *const c_char, ecx as *const c_char),
@@ -393,6 +494,7 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         UMASK => sys_umask(ebx as mode_t),
         SOCKETCALL => sys_socketcall(ebx as u32, ecx as SocketArgsPtr),
         WAIT4 => sys_wait4(ebx as i32, ecx as *mut i32, edx as u32, esi as *mut rusage),
+        WAITID => sys_waitid(ebx as u32, ecx as i32, edx as *mut SigInfo, esi as u32),
         CLONE => sys_clone(cpu_state as u32, ebx as *const c_void, ecx as u32),
         MPROTECT => sys_mprotect(
             ebx as *mut u8,
@@ -401,15 +503,115 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         ),
         SIGPROCMASK => sys_sigprocmask(ebx as u32, ecx as *const sigset_t, edx as *mut sigset_t),
         GETPGID => sys_getpgid(ebx as Pid),
+        GETSID => sys_getsid(ebx as Pid),
+        FCHDIR => sys_fchdir(ebx as Fd),
         STATFS => sys_statfs(ebx as *const c_char, ecx as *mut libc_binding::statfs),
         FSTATFS => sys_fstatfs(ebx as Fd, ecx as *mut libc_binding::statfs),
+        GETDENTS64 => sys_getdents64(ebx as Fd, ecx as *mut u8, edx as u32),
+        GETXATTR => sys_getxattr(
+            ebx as *const c_char,
+            ecx as *const c_char,
+            edx as *mut u8,
+            esi as usize,
+        ),
+        SETXATTR => sys_setxattr(
+            ebx as *const c_char,
+            ecx as *const c_char,
+            edx as *const u8,
+            esi as usize,
+            edi as u32,
+        ),
+        LISTXATTR => sys_listxattr(ebx as *const c_char, ecx as *mut u8, edx as usize),
+        REMOVEXATTR => sys_removexattr(ebx as *const c_char, ecx as *const c_char),
         NANOSLEEP => sys_nanosleep(ebx as *const TimeSpec, ecx as *mut TimeSpec),
         CHOWN => sys_chown(ebx as *const c_char, ecx as uid_t, edx as gid_t),
         FCHOWN => sys_fchown(ebx as Fd, ecx as uid_t, edx as gid_t),
         GETCWD => sys_getcwd(ebx as *mut c_char, ecx as usize),
         GETTIMEOFDAY => sys_gettimeofday(ebx as *mut timeval, ecx as *mut timezone),
         SIGRETURN => sys_sigreturn(cpu_state),
+        RT_SIGRETURN => sys_rt_sigreturn(cpu_state),
+        SIGALTSTACK => sys_sigaltstack(cpu_state, ebx as *const stack_t, ecx as *mut stack_t),
         SHUTDOWN => sys_shutdown(),
+        OPENAT => sys_openat(ebx as i32, ecx as *const c_char, edx as u32, esi as mode_t),
+        MKDIRAT => sys_mkdirat(ebx as i32, ecx as *const c_char, edx as mode_t),
+        FSTATAT => sys_fstatat(
+            ebx as i32,
+            ecx as *const c_char,
+            edx as *mut libc_binding::stat,
+            esi as u32,
+        ),
+        UNLINKAT => sys_unlinkat(ebx as i32, ecx as *const c_char, edx as u32),
+        RENAMEAT => sys_renameat(
+            ebx as i32,
+            ecx as *const c_char,
+            edx as i32,
+            esi as *const c_char,
+        ),
+        READLINKAT => sys_readlinkat(
+            ebx as i32,
+            ecx as *const c_char,
+            edx as *mut c_char,
+            esi as u32,
+        ),
+        PREAD64 => sys_pread64(
+            ebx as Fd,
+            ecx as *mut u8,
+            edx as usize,
+            esi as off_t + ((edi as off_t) << 32),
+        ),
+        PWRITE64 => sys_pwrite64(
+            ebx as Fd,
+            ecx as *const u8,
+            edx as usize,
+            esi as off_t + ((edi as off_t) << 32),
+        ),
+        READV => sys_readv(ebx as Fd, ecx as *const iovec, edx as i32),
+        WRITEV => sys_writev(ebx as Fd, ecx as *const iovec, edx as i32),
+        PREADV => sys_preadv(
+            ebx as Fd,
+            ecx as *const iovec,
+            edx as i32,
+            esi as off_t + ((edi as off_t) << 32),
+        ),
+        PWRITEV => sys_pwritev(
+            ebx as Fd,
+            ecx as *const iovec,
+            edx as i32,
+            esi as off_t + ((edi as off_t) << 32),
+        ),
+        TRUNCATE64 => sys_truncate64(
+            ebx as *const c_char,
+            ecx as off_t + ((edx as off_t) << 32),
+        ),
+        FTRUNCATE64 => sys_ftruncate64(ebx as Fd, ecx as off_t + ((edx as off_t) << 32)),
+        SYNC => sys_sync(),
+        SYNCFS => sys_syncfs(ebx as Fd),
+        FSYNC => sys_fsync(ebx as Fd),
+        FDATASYNC => sys_fdatasync(ebx as Fd),
+        INOTIFY_INIT => sys_inotify_init(),
+        INOTIFY_INIT1 => sys_inotify_init1(ebx as u32),
+        INOTIFY_ADD_WATCH => sys_inotify_add_watch(ebx as Fd, ecx as *const c_char, edx as u32),
+        INOTIFY_RM_WATCH => sys_inotify_rm_watch(ebx as Fd, ecx as i32),
+        GETRLIMIT => sys_getrlimit(ebx as u32, ecx as *mut rlimit),
+        SETRLIMIT => sys_setrlimit(ebx as u32, ecx as *const rlimit),
+        GETRUSAGE => sys_getrusage(ebx as i32, ecx as *mut rusage),
+        PERSONALITY => sys_personality(ebx as u32),
+        NICE => sys_nice(ebx as i32),
+        GETPRIORITY => sys_getpriority(ebx as u32, ecx as u32),
+        SETPRIORITY => sys_setpriority(ebx as u32, ecx as u32, edx as i32),
+        SCHED_SETPARAM => sys_sched_setparam(ebx as Pid, ecx as *const sched_param),
+        SCHED_GETPARAM => sys_sched_getparam(ebx as Pid, ecx as *mut sched_param),
+        SCHED_SETSCHEDULER => {
+            sys_sched_setscheduler(ebx as Pid, ecx as u32, edx as *const sched_param)
+        }
+        SCHED_GETSCHEDULER => sys_sched_getscheduler(ebx as Pid),
+        SCHED_YIELD => sys_sched_yield(),
+        SCHED_GET_PRIORITY_MAX => sys_sched_get_priority_max(ebx as u32),
+        SCHED_GET_PRIORITY_MIN => sys_sched_get_priority_min(ebx as u32),
+        SETRESUID => sys_setresuid(ebx as uid_t, ecx as uid_t, edx as uid_t),
+        GETRESUID => sys_getresuid(ebx as *mut uid_t, ecx as *mut uid_t, edx as *mut uid_t),
+        SETRESGID => sys_setresgid(ebx as gid_t, ecx as gid_t, edx as gid_t),
+        GETRESGID => sys_getresgid(ebx as *mut gid_t, ecx as *mut gid_t, edx as *mut gid_t),
         TEST => sys_test(),
         STACK_OVERFLOW => sys_stack_overflow(0, 0, 0, 0, 0, 0),
         EXIT_QEMU => crate::tests::helpers::exit_qemu(ebx as u32),
@@ -420,7 +622,6 @@ pub unsafe extern "C" fn syscall_interrupt_handler(cpu_state: *mut CpuState) ->
         SETEGID => sys_setegid(ebx as gid_t),
         SETEUID => sys_seteuid(ebx as uid_t),
         ISATTY => sys_isatty(ebx as u32),
-        OPENDIR => sys_opendir(ebx as *const c_char, ecx as *mut DIR),
         IS_STR_VALID => sys_is_str_valid(ebx as *const c_char),
         GETHOSTNAME => sys_gethostname(ebx as *mut c_char, ecx as usize),
         SETHOSTNAME => sys_sethostname(ebx as *const c_char, ecx as usize),
@@ -2,7 +2,8 @@ use super::SysResult;
 
 use super::scheduler::SCHEDULER;
 use super::vfs::{Path, VFS};
-use libc_binding::{c_char, Errno};
+use super::Fd;
+use libc_binding::{c_char, Errno, AT_FDCWD};
 
 use core::convert::TryFrom;
 
@@ -40,7 +41,31 @@ pub fn sys_chdir(buf: *const c_char) -> SysResult<u32> {
         let posix_path = vfs.dentry_path(direntry_id)?;
         assert!(posix_path.is_absolute());
 
-        tg.cwd = posix_path;
+        vfs.set_cwd_dir(&mut tg.cwd, direntry_id)?;
+        tg.cwd_path = posix_path;
+
+        Ok(0)
+    })
+}
+
+/// The fchdir() function shall be equivalent to chdir() except that
+/// the directory that is to be the new current working directory is
+/// specified by the file descriptor fildes.
+pub fn sys_fchdir(fildes: Fd) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        // AT_FDCWD is not a file descriptor
+        if fildes as i32 == AT_FDCWD {
+            return Err(Errno::EBADF);
+        }
+        let tg = scheduler.current_thread_group_mut();
+        let cwd = tg.get_dirfd_path(fildes as i32)?;
+
+        let mut vfs = VFS.lock();
+        let posix_path = vfs.dentry_path(cwd.dir)?;
+        vfs.set_cwd_dir(&mut tg.cwd, cwd.dir)?;
+        tg.cwd_path = posix_path;
 
         Ok(0)
     })
@@ -0,0 +1,53 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use libc_binding::{c_char, Errno};
+
+use core::convert::TryFrom;
+
+/// chroot() changes the root directory of the calling process to that
+/// specified in path. This directory will be used for pathnames
+/// beginning with /, and `..` never goes above it. The root directory
+/// is inherited by all children of the calling process.
+///
+/// Only a privileged process may call chroot().
+///
+/// This call changes an ingredient in the pathname resolution process
+/// and does nothing else. In particular, the current working directory
+/// is left unchanged: it may be outside the new root, in which case
+/// getcwd() fails with ENOENT.
+pub fn sys_chroot(path: *const c_char) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let safe_path = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_str(path)?
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        if !tg.credentials.is_root() {
+            return Err(Errno::EPERM);
+        }
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        let mut vfs = VFS.lock();
+        let direntry_id = vfs.pathname_resolution(cwd, creds, &path)?;
+        let filetype = vfs.file_type(cwd, creds, &path)?;
+
+        if !filetype.is_directory() {
+            return Err(Errno::ENOTDIR);
+        }
+
+        vfs.set_cwd_root(&mut tg.cwd, direntry_id)?;
+
+        Ok(0)
+    })
+}
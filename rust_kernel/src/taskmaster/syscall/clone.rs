@@ -1,4 +1,5 @@
-use super::scheduler::SCHEDULER;
+use super::scheduler::{auto_preempt, SCHEDULER};
+use super::thread::WaitingState;
 use super::SysResult;
 use bitflags::bitflags;
 use core::ffi::c_void;
@@ -41,12 +42,24 @@ bitflags! {
 // parent_tidptr=0x7ff03ba959d0, tls=0x7ff03ba95700,
 // child_tidptr=0x7ff03ba959d0) = 21807
 /// the clone syscall
+///
+/// With CLONE_VFORK, the calling thread is suspended until the child
+/// calls execve or exits. Only a deadly signal ends the wait earlier.
 pub fn sys_clone(kernel_esp: u32, child_stack: *const c_void, clone_flags: u32) -> SysResult<u32> {
     let flags = CloneFlags::from_bits_truncate(clone_flags);
 
     unpreemptible_context!({
-        Ok(SCHEDULER
-            .lock()
-            .current_thread_clone(kernel_esp, child_stack, flags)? as u32)
+        let mut scheduler = SCHEDULER.lock();
+        let child_pid = scheduler.current_thread_clone(kernel_esp, child_stack, flags)?;
+
+        if flags.contains(CloneFlags::VFORK) {
+            scheduler
+                .current_thread_mut()
+                .set_waiting(WaitingState::Vfork(child_pid));
+            drop(scheduler);
+            // The process is terminated on EINTR
+            let _ignored_result = auto_preempt();
+        }
+        Ok(child_pid as u32)
     })
 }
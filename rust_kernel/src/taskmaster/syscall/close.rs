@@ -1,16 +1,28 @@
 //! Close a file descriptor
 
 use super::scheduler::SCHEDULER;
+use super::vfs::{LockOwner, VFS};
 use super::SysResult;
 
 pub fn sys_close(fd: i32) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
+        let pid = scheduler.current_task_id().0;
         let fd_interface = &mut scheduler
             .current_thread_group_running_mut()
             .file_descriptor_interface;
 
+        let inode_id = fd_interface
+            .get_file_operation(fd as u32)?
+            .get_inode_id()
+            .ok();
         fd_interface.close_fd(fd as u32)?;
+
+        // All the record locks of the process on the file are removed
+        // by the close of any of its file descriptors
+        if let Some(inode_id) = inode_id {
+            VFS.lock().release_locks(inode_id, LockOwner::Process(pid));
+        }
     });
     Ok(0)
 }
@@ -12,6 +12,6 @@ pub fn sys_dup(old_fd: u32) -> SysResult<u32> {
             .current_thread_group_running_mut()
             .file_descriptor_interface;
 
-        fd_interface.dup(old_fd, None)
+        fd_interface.dup(old_fd, None, false)
     })
 }
@@ -1,17 +1,58 @@
-//! This file contains the description of the dup2 syscall
+//! This file contains the description of the dup2 and dup3 syscalls
 
 use super::scheduler::SCHEDULER;
+use super::vfs::{LockOwner, VFS};
 use super::SysResult;
 
-/// Duplicate a file descriptor
+use libc_binding::{Errno, O_CLOEXEC};
+
+/// Duplicate a file descriptor. If old_fd is equal to new_fd, new_fd
+/// is returned without closing it nor changing its close-on-exec flag.
 pub fn sys_dup2(old_fd: u32, new_fd: u32) -> SysResult<u32> {
+    if old_fd == new_fd {
+        return unpreemptible_context!({
+            let scheduler = SCHEDULER.lock();
+
+            scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface
+                .get_file_descriptor(old_fd)?;
+            Ok(new_fd)
+        });
+    }
+    dup(old_fd, new_fd, false)
+}
+
+/// Same as dup2(), but the close-on-exec flag of new_fd is set if
+/// flags contains O_CLOEXEC.
+///
+/// [EINVAL]
+///     old_fd is equal to new_fd, or flags contains an invalid value.
+pub fn sys_dup3(old_fd: u32, new_fd: u32, flags: u32) -> SysResult<u32> {
+    if old_fd == new_fd || flags & !O_CLOEXEC != 0 {
+        return Err(Errno::EINVAL);
+    }
+    dup(old_fd, new_fd, flags & O_CLOEXEC != 0)
+}
+
+fn dup(old_fd: u32, new_fd: u32, cloexec: bool) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
+        let pid = scheduler.current_task_id().0;
 
         let fd_interface = &mut scheduler
             .current_thread_group_running_mut()
             .file_descriptor_interface;
 
-        fd_interface.dup2(old_fd, new_fd)
+        // The file previously open on new_fd is closed
+        let closed_inode_id = fd_interface
+            .get_file_operation(new_fd)
+            .ok()
+            .and_then(|file_operation| file_operation.get_inode_id().ok());
+        let fd = fd_interface.dup2(old_fd, new_fd, cloexec)?;
+        if let Some(inode_id) = closed_inode_id {
+            VFS.lock().release_locks(inode_id, LockOwner::Process(pid));
+        }
+        Ok(fd)
     })
 }
@@ -3,20 +3,175 @@
 use super::SysResult;
 
 use super::process::{
-    get_file_content, CpuState, Process, ProcessArguments, ProcessOrigin, UserProcess,
+    CpuState, ExecutableFile, Process, ProcessArguments, ProcessOrigin, UserProcess,
 };
-use super::safe_ffi::CStringArray;
+use super::safe_ffi::{CString, CStringArray};
 use super::scheduler::SCHEDULER;
 use super::thread::ProcessState;
-use fallible_collections::TryClone;
+use super::thread_group::Credentials;
+use super::IpcResult;
+use alloc::vec::Vec;
+use fallible_collections::{FallibleVec, TryClone};
 use libc_binding::c_char;
 
 use core::convert::TryFrom;
 
-use libc_binding::{Amode, Errno, FileType};
+use libc_binding::{
+    Amode, Errno, FileType, MountFlags, OpenFlags, ADDR_NO_RANDOMIZE, RLIMIT_STACK,
+};
+
+use super::vfs::{Cwd, Path, VFS};
+
+/// The first bytes of a script, followed by its interpreter line
+const SHEBANG: &[u8] = b"#!";
+
+/// The maximum length of the interpreter line of a script, like Linux
+const SHEBANG_LINE_MAX: usize = 256;
+
+/// The maximum depth of scripts interpreted by other scripts, like Linux
+const MAX_INTERPRETER_DEPTH: usize = 4;
+
+/// The environment variables removed from the environment of a
+/// set-user-ID or set-group-ID program, as they change the behavior of
+/// the dynamic linker or of the libc.
+const UNSECURE_ENVVARS: &[&str] = &[
+    "GCONV_PATH",
+    "GETCONF_DIR",
+    "HOSTALIASES",
+    "LD_AUDIT",
+    "LD_DEBUG",
+    "LD_DEBUG_OUTPUT",
+    "LD_DYNAMIC_WEAK",
+    "LD_LIBRARY_PATH",
+    "LD_ORIGIN_PATH",
+    "LD_PRELOAD",
+    "LD_PROFILE",
+    "LD_SHOW_AUXV",
+    "LOCALDOMAIN",
+    "LOCPATH",
+    "MALLOC_TRACE",
+    "NIS_PATH",
+    "NLSPATH",
+    "RESOLV_HOST_CONF",
+    "RES_OPTIONS",
+    "TMPDIR",
+    "TZDIR",
+];
+
+/// Checks if the `name=value` variable `var` is one of the UNSECURE_ENVVARS
+fn is_unsecure_env(var: &CString) -> bool {
+    let var = var.as_bytes();
+    let name = var.split(|c| *c == b'=').next().unwrap_or(var);
+    UNSECURE_ENVVARS
+        .iter()
+        .any(|unsecure| unsecure.as_bytes() == name)
+}
 
-use super::vfs::{Path, VFS};
+/// The interpreter line of a script: `#!interpreter [optional-arg]`
+struct Shebang {
+    /// The interpreter to execute
+    path: Path,
+    /// The interpreter as written in the script, its argv[0]
+    interpreter: CString,
+    arg: Option<CString>,
+}
 
+impl Shebang {
+    /// Parse the beginning `head` of a file, which is a script if it
+    /// starts with `#!`. As on Linux, the interpreter is separated from
+    /// its optional argument by blanks, and everything after them up to
+    /// the end of the line is a single argument.
+    fn parse(head: &[u8]) -> SysResult<Option<Self>> {
+        if !head.starts_with(SHEBANG) {
+            return Ok(None);
+        }
+        let line = &head[SHEBANG.len()..];
+        let line = match line.iter().position(|c| *c == b'\n') {
+            Some(end) => &line[..end],
+            // The line does not fit in the buffer
+            None if head.len() == SHEBANG_LINE_MAX => return Err(Errno::ENOEXEC),
+            None => line,
+        };
+        let is_blank = |c: &u8| *c == b' ' || *c == b'\t';
+        let start = line.iter().position(|c| !is_blank(c)).unwrap_or(line.len());
+        let end = line
+            .iter()
+            .rposition(|c| !is_blank(c))
+            .map_or(0, |end| end + 1);
+        if start >= end {
+            return Err(Errno::ENOEXEC);
+        }
+        let line = core::str::from_utf8(&line[start..end]).map_err(|_| Errno::ENOEXEC)?;
+
+        let (interpreter, arg) = match line.find(|c| c == ' ' || c == '\t') {
+            Some(i) => (
+                &line[..i],
+                Some(line[i..].trim_start_matches(&[' ', '\t'][..])),
+            ),
+            None => (line, None),
+        };
+        if interpreter.as_bytes().contains(&0)
+            || arg.map_or(false, |arg| arg.as_bytes().contains(&0))
+        {
+            return Err(Errno::ENOEXEC);
+        }
+        Ok(Some(Self {
+            path: Path::try_from(interpreter)?,
+            interpreter: CString::try_from(interpreter)?,
+            arg: match arg {
+                Some(arg) => Some(CString::try_from(arg)?),
+                None => None,
+            },
+        }))
+    }
+
+    /// Read the interpreter line of the file `path`, if it is a script
+    fn read(cwd: &Cwd, creds: &Credentials, path: &Path) -> SysResult<Option<Self>> {
+        let mode = FileType::from_bits(0).expect("file permission creation failed");
+        let file = match VFS.lock().open(
+            cwd,
+            creds,
+            path.try_clone()?,
+            OpenFlags::empty(),
+            mode,
+            FileType::empty(),
+        )? {
+            IpcResult::Done(file_operator) => file_operator,
+            IpcResult::Wait(file_operator, _) => file_operator,
+        };
+        let mut head = [0; SHEBANG_LINE_MAX];
+        let mut len = 0;
+        let mut file = file.lock();
+        while len < head.len() {
+            let count = file.pread(&mut head[len..], len as i64)? as usize;
+            if count == 0 {
+                break;
+            }
+            len += count;
+        }
+        Self::parse(&head[..len])
+    }
+}
+
+/// If the set-user-ID mode bit of the new process image file is set,
+/// the effective user ID of the new process image shall be set to the
+/// user ID of the new process image file. Similarly, if the
+/// set-group-ID mode bit of the new process image file is set, the
+/// effective group ID of the new process image shall be set to the
+/// group ID of the new process image file. The real user ID, real
+/// group ID, and supplementary group IDs of the new process image
+/// shall remain the same as those of the calling process image. The
+/// effective user ID and effective group ID of the new process image
+/// shall be saved (as the saved set-user-ID and the saved
+/// set-group-ID) for use by setuid().
+///
+/// The set-ID bits are ignored when the file is on a file system
+/// mounted with MS_NOSUID, when the process is traced, and on the
+/// scripts: only the ones of their interpreter are honored. When the new
+/// image runs with effective IDs different from its real IDs, the
+/// dangerous variables are removed from its environment and AT_SECURE
+/// is set in its auxiliary vector.
+///
 /// File descriptors open in the calling process image shall remain
 /// open in the new process image, except for those whose close-on-
 /// exec flag FD_CLOEXEC is set. For those file descriptors that
@@ -26,6 +181,11 @@ use super::vfs::{Path, VFS};
 /// close(). Locks that are not removed by closing of file descriptors
 /// remain unchanged.
 ///
+/// A script starting with `#!interpreter [optional-arg]` is run by its
+/// interpreter, with the argument list `interpreter [optional-arg]
+/// path argv[1]...`. The interpreter may itself be a script, up to
+/// MAX_INTERPRETER_DEPTH times.
+///
 /// Directory streams open in the calling process image shall be
 /// closed in the new process image.
 ///
@@ -93,7 +253,6 @@ use super::vfs::{Path, VFS};
 ///
 ///    Pending signals (see sigpending())
 ///
-// TODO:
 /// A call to any exec function from a process with more than one
 /// thread shall result in all threads being terminated and the new
 /// executable image being loaded and executed. No destructor
@@ -112,62 +271,132 @@ pub fn sys_execve(
             .get_virtual_allocator();
 
         let path: &str = v.make_checked_str(path)?;
-        let pathname = Path::try_from(path)?;
-        let argv_content: CStringArray = v.make_checked_cstring_array(argv)?;
-
-        // Get the argv len to store the argc value
-        let argv_content_len = argv_content.len();
-        let envp_content: CStringArray = v.make_checked_cstring_array(envp)?;
+        let mut filename = CString::try_from(path)?;
+        let mut pathname = Path::try_from(path)?;
+        let mut argv_content: CStringArray = v.make_checked_cstring_array(argv)?;
+        let mut envp_content: CStringArray = v.make_checked_cstring_array(envp)?;
         drop(v);
 
         let tg = scheduler.current_thread_group_mut();
         let creds = &tg.credentials;
         let cwd = &tg.cwd;
         // This seems unefficient since pathname resolution will be executed a lot of times:
-        // here and in get_file_content.
+        // here and in ExecutableFile::open.
 
-        let filetype;
-        let owner;
-        let group;
-        {
-            let mut vfs = VFS.lock();
-            if !vfs.is_access_granted(cwd, creds, &pathname, Amode::EXECUTE) {
-                return Err(Errno::EACCESS);
+        let mut filetype;
+        let mut owner;
+        let mut group;
+        let mut mount_flags;
+        let mut depth = 0;
+        loop {
+            {
+                let mut vfs = VFS.lock();
+                if !vfs.is_access_granted(cwd, creds, &pathname, Amode::EXECUTE) {
+                    return Err(Errno::EACCESS);
+                }
+                mount_flags = vfs.mount_flags(cwd, creds, &pathname)?;
+                if mount_flags.contains(MountFlags::MS_NOEXEC) {
+                    return Err(Errno::EACCESS);
+                }
+
+                filetype = vfs.file_type(cwd, creds, &pathname)?;
+                // (owner, group) = vfs.get_file_owner(cwd, creds, &pathname)?; this does not compile...
+                let (tmp_owner, tmp_group) = vfs.get_file_owner(cwd, creds, &pathname)?;
+                owner = tmp_owner;
+                group = tmp_group;
+            }
+            let shebang = match Shebang::read(cwd, creds, &pathname)? {
+                Some(shebang) => shebang,
+                None => break,
+            };
+            depth += 1;
+            if depth > MAX_INTERPRETER_DEPTH {
+                return Err(Errno::ELOOP);
             }
 
-            filetype = vfs.file_type(cwd, creds, &pathname)?;
-            // (owner, group) = vfs.get_file_owner(cwd, creds, &pathname)?; this does not compile...
-            let (tmp_owner, tmp_group) = vfs.get_file_owner(cwd, creds, &pathname)?;
-            owner = tmp_owner;
-            group = tmp_group;
+            // The script path takes the place of argv[0], after the
+            // interpreter and its argument
+            let mut front = Vec::new();
+            front.try_push(shebang.interpreter.try_clone()?)?;
+            if let Some(arg) = shebang.arg {
+                front.try_push(arg)?;
+            }
+            front.try_push(filename)?;
+            argv_content.try_replace_front(1, front)?;
+
+            filename = shebang.interpreter;
+            pathname = shebang.path;
         }
-        let content = get_file_content(cwd, creds, pathname.try_clone()?)?;
+        let file = ExecutableFile::open(cwd, creds, pathname.try_clone()?)?;
+
+        // Get the argv len to store the argc value
+        let argv_content_len = argv_content.len();
 
         let tg = scheduler.current_thread_group_mut();
 
-        tg.environ = Some(envp_content.try_clone()?);
-        tg.argv = Some(argv_content.try_clone()?);
-        tg.filename = Some(pathname);
+        // If SUID/GUID, become owner/group, unless the mount ignores
+        // these bits or the process is traced.
+        let mut credentials = tg.credentials.try_clone()?;
+        let ignore_set_id = mount_flags.contains(MountFlags::MS_NOSUID) || tg.is_traced();
+        if filetype.contains(FileType::SET_USER_ID) && !ignore_set_id {
+            credentials.euid = owner;
+        }
+        if filetype.contains(FileType::SET_GROUP_ID) && !ignore_set_id {
+            credentials.egid = group;
+        }
+
+        // Save the euid/egid as POSIX specifies.
+        credentials.suid = credentials.euid;
+        credentials.sgid = credentials.egid;
+
+        // A privileged image must not be influenced by the environment
+        // of its unprivileged caller.
+        if credentials.is_secure() {
+            envp_content.retain(|var| !is_unsecure_env(var));
+        }
 
+        // Nor can its layout be made predictable
+        let personality = if credentials.is_secure() {
+            tg.personality & !ADDR_NO_RANDOMIZE
+        } else {
+            tg.personality
+        };
+
+        let environ = envp_content.try_clone()?;
+        let argv = argv_content.try_clone()?;
         let mut new_process = unsafe {
             UserProcess::new(
-                ProcessOrigin::Elf(content.as_ref()),
-                Some(ProcessArguments::new(argv_content, envp_content)),
+                ProcessOrigin::Elf(&file),
+                Some(ProcessArguments::new(
+                    argv_content,
+                    envp_content,
+                    &credentials,
+                    personality,
+                )?),
             )?
         };
+        // The stack grows up to the RLIMIT_STACK soft limit
+        new_process
+            .get_virtual_allocator()
+            .set_stack_limit(tg.rlimits.get(RLIMIT_STACK)?.rlim_cur as usize);
 
-        // Save the euid/egid as POSIX specifies.
-        tg.credentials.suid = tg.credentials.euid;
-        tg.credentials.sgid = tg.credentials.egid;
+        // Only the calling thread goes on, in the new image
+        scheduler.current_thread_group_exec()?;
 
-        // If SUID/GUID, become owner/group.
-        if filetype.contains(FileType::SET_USER_ID) {
-            tg.credentials.euid = owner;
-        }
+        // The peak resident set size outlives the old image
+        let max_rss = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator()
+            .peak_rss();
 
-        if filetype.contains(FileType::SET_GROUP_ID) {
-            tg.credentials.egid = group;
-        }
+        let tg = scheduler.current_thread_group_mut();
+        tg.usage.update_max_rss(max_rss);
+        tg.environ = Some(environ);
+        tg.argv = Some(argv);
+        tg.filename = Some(pathname);
+        tg.credentials = credentials;
+        tg.personality = personality;
 
         let old_process = scheduler.current_thread_mut().unwrap_process_mut();
         /*
@@ -211,6 +440,16 @@ pub fn sys_execve(
             .signal
             .reset_for_new_process_image();
 
+        // Close the file descriptors with the FD_CLOEXEC flag
+        let pid = scheduler.current_task_id().0;
+        scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface
+            .close_on_exec(pid);
+
+        // A vfork() parent gets its address space back
+        scheduler.release_vfork_parent(pid);
+
         // Set the argc argument: EAX
         argv_content_len as u32
     });
@@ -72,7 +72,6 @@ use libc_binding::EXITED_STATUS_BITS;
 ///  process has set a semadj value (see semop()), that value shall be
 ///  added to the semval of the specified semaphore. [Option End]
 ///
-// TODO:
 ///  If the process is a controlling process, the SIGHUP signal shall
 ///  be sent to each process in the foreground process group of the
 ///  controlling terminal belonging to the calling process.
@@ -1,8 +1,13 @@
-use super::scheduler::SCHEDULER;
+use super::flock::set_lock_or_wait;
+use super::scheduler::{Scheduler, SCHEDULER};
+use super::vfs::{FileLock, InodeId, LockOwner, LockType, OFFSET_MAX, VFS};
 use super::Fd;
 use super::SysResult;
-use core::mem::transmute;
-use libc_binding::{Errno, FcntlCmd};
+use core::convert::TryFrom;
+use libc_binding::{
+    flock, off_t, Errno, FcntlCmd, OpenFlags, Whence, FD_CLOEXEC, F_RDLCK, F_UNLCK, F_WRLCK,
+    SEEK_SET,
+};
 
 /// The fcntl() function shall perform the operations described below
 /// on open files. The fildes argument is a file descriptor.
@@ -198,13 +203,15 @@ use libc_binding::{Errno, FcntlCmd};
 /// [TYM] [Option Start] If fildes refers to a typed memory object,
 /// the result of the fcntl() function is unspecified. [Option End]
 
-pub fn sys_fcntl(fildes: Fd, cmd: u32, arg: Fd) -> SysResult<u32> {
+pub fn sys_fcntl(fildes: Fd, cmd: u32, arg: u32) -> SysResult<u32> {
+    let cmd = FcntlCmd::try_from(cmd)?;
+    match cmd {
+        FcntlCmd::F_GETLK => return get_record_lock(fildes, arg as *mut flock),
+        FcntlCmd::F_SETLK => return set_record_lock(fildes, arg as *const flock, false),
+        FcntlCmd::F_SETLKW => return set_record_lock(fildes, arg as *const flock, true),
+        _ => {}
+    }
     unpreemptible_context!({
-        // dbg!(cmd);
-        let cmd: FcntlCmd = unsafe { transmute(cmd) };
-        // dbg!(fildes);
-        // dbg!(cmd);
-        // dbg!(arg);
         let mut scheduler = SCHEDULER.lock();
 
         match cmd {
@@ -222,8 +229,18 @@ pub fn sys_fcntl(fildes: Fd, cmd: u32, arg: Fd) -> SysResult<u32> {
             FcntlCmd::F_DUPFD => scheduler
                 .current_thread_group_running_mut()
                 .file_descriptor_interface
-                .dup(fildes, Some(arg)),
-            //TODO:
+                .dup(fildes, Some(arg as Fd), false),
+            FcntlCmd::F_DUPFD_CLOEXEC => scheduler
+                .current_thread_group_running_mut()
+                .file_descriptor_interface
+                .dup(fildes, Some(arg as Fd), true),
+            FcntlCmd::F_GETFD => {
+                let cloexec = scheduler
+                    .current_thread_group_running()
+                    .file_descriptor_interface
+                    .get_cloexec(fildes)?;
+                Ok(if cloexec { FD_CLOEXEC } else { 0 })
+            }
             // F_SETFD Set the file descriptor flags defined in
             //     <fcntl.h>, that are associated with fildes, to the
             //     third argument, arg, taken as type int. If the
@@ -232,8 +249,158 @@ pub fn sys_fcntl(fildes: Fd, cmd: u32, arg: Fd) -> SysResult<u32> {
             //     functions; otherwise, the file descriptor shall be
             //     closed upon successful execution of one of the exec
             //     functions.
-            FcntlCmd::F_SETFD => Ok(0),
+            FcntlCmd::F_SETFD => {
+                scheduler
+                    .current_thread_group_running_mut()
+                    .file_descriptor_interface
+                    .set_cloexec(fildes, arg & FD_CLOEXEC != 0)?;
+                Ok(0)
+            }
             _ => Err(Errno::EINVAL),
         }
     })
 }
+
+/// Get the file of `fildes`, its open flags and the bytes from the
+/// first to the last described by `lock`
+fn record_range(
+    scheduler: &Scheduler,
+    fildes: Fd,
+    lock: &flock,
+) -> SysResult<(InodeId, OpenFlags, u64, u64)> {
+    let file_descriptor = scheduler
+        .current_thread_group_running()
+        .file_descriptor_interface
+        .get_file_descriptor(fildes)?;
+    let file_operation = file_descriptor.get_file_operation();
+    let mut file_operation = file_operation.lock();
+    let inode_id = file_operation.get_inode_id().map_err(|_| Errno::EINVAL)?;
+
+    let base = match Whence::try_from(lock.l_whence as u32)? {
+        Whence::SeekSet => 0,
+        Whence::SeekCur => file_operation.lseek(0, Whence::SeekCur)?,
+        Whence::SeekEnd => file_operation.fstat()?.st_size,
+    };
+    let start = base.checked_add(lock.l_start).ok_or(Errno::EOVERFLOW)?;
+    let (start, end) = if lock.l_len > 0 {
+        let end = start.checked_add(lock.l_len - 1).ok_or(Errno::EOVERFLOW)?;
+        (start, end)
+    } else if lock.l_len == 0 {
+        (start, OFFSET_MAX as off_t)
+    } else {
+        // The bytes before l_start are locked
+        (
+            start.checked_add(lock.l_len).ok_or(Errno::EINVAL)?,
+            start - 1,
+        )
+    };
+    if start < 0 || end < start {
+        return Err(Errno::EINVAL);
+    }
+    Ok((
+        inode_id,
+        file_descriptor.get_flags(),
+        start as u64,
+        end as u64,
+    ))
+}
+
+/// F_GETLK: Get the first lock which blocks the lock described by `lock`
+fn get_record_lock(fildes: Fd, lock: *mut flock) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let lock = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_ref_mut(lock)?
+        };
+        let lock_type = match lock.l_type as u32 {
+            F_RDLCK => LockType::Shared,
+            F_WRLCK => LockType::Exclusive,
+            _ => return Err(Errno::EINVAL),
+        };
+        let (inode_id, _, start, end) = record_range(&scheduler, fildes, lock)?;
+        let pid = scheduler.current_task_id().0;
+        let request = FileLock {
+            owner: LockOwner::Process(pid),
+            lock_type,
+            start,
+            end,
+            pid,
+        };
+
+        match VFS.lock().get_lock(inode_id, &request) {
+            None => lock.l_type = F_UNLCK as _,
+            Some(blocker) => {
+                lock.l_type = match blocker.lock_type {
+                    LockType::Shared => F_RDLCK as _,
+                    LockType::Exclusive => F_WRLCK as _,
+                };
+                lock.l_whence = SEEK_SET as _;
+                lock.l_start = blocker.start as off_t;
+                lock.l_len = if blocker.end == OFFSET_MAX {
+                    0
+                } else {
+                    (blocker.end - blocker.start + 1) as off_t
+                };
+                lock.l_pid = blocker.pid;
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// F_SETLK and F_SETLKW: Set or clear the record lock described by
+/// `lock`. The range is determined once, before any sleep.
+fn set_record_lock(fildes: Fd, lock: *const flock, wait: bool) -> SysResult<u32> {
+    let (pid, inode_id, lock_type, start, end) = unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let lock = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_ref(lock)?
+        };
+        let (inode_id, flags, start, end) = record_range(&scheduler, fildes, lock)?;
+        let lock_type = match lock.l_type as u32 {
+            F_RDLCK if !flags.is_open_for_read() => return Err(Errno::EBADF),
+            F_WRLCK if !flags.is_open_for_write() => return Err(Errno::EBADF),
+            F_RDLCK => Some(LockType::Shared),
+            F_WRLCK => Some(LockType::Exclusive),
+            F_UNLCK => None,
+            _ => return Err(Errno::EINVAL),
+        };
+        (
+            scheduler.current_task_id().0,
+            inode_id,
+            lock_type,
+            start,
+            end,
+        )
+    });
+    let owner = LockOwner::Process(pid);
+
+    match lock_type {
+        Some(lock_type) => {
+            let lock = FileLock {
+                owner,
+                lock_type,
+                start,
+                end,
+                pid,
+            };
+            set_lock_or_wait(pid, inode_id, lock, wait)
+        }
+        None => unpreemptible_context!({
+            VFS.lock().unlock(inode_id, owner, start, end)?;
+            Ok(0)
+        }),
+    }
+}
@@ -0,0 +1,117 @@
+//! sys_flock()
+
+use super::SysResult;
+
+use super::scheduler::auto_preempt;
+use super::scheduler::SCHEDULER;
+use super::thread::WaitingState;
+use super::vfs::{FileLock, InodeId, LockOwner, LockResult, LockType, OFFSET_MAX, VFS};
+use super::Fd;
+
+use libc_binding::{Errno, Pid, LOCK_EX, LOCK_NB, LOCK_SH, LOCK_UN};
+
+/// Set `lock` on the file `inode_id` for the process `pid`. If the
+/// lock is blocked by another one, fail with EAGAIN, or sleep until
+/// the lock can be set when `wait` is true.
+///
+/// [EDEADLK]
+///     Sleeping until the lock is released would cause a deadlock.
+/// [EINTR]
+///     A signal was caught during the sleep.
+pub fn set_lock_or_wait(pid: Pid, inode_id: InodeId, lock: FileLock, wait: bool) -> SysResult<u32> {
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+            let mut vfs = VFS.lock();
+
+            vfs.stop_waiting_lock(pid);
+            match vfs.set_lock(inode_id, lock)? {
+                LockResult::Done => return Ok(0),
+                LockResult::Blocked { .. } if !wait => return Err(Errno::EAGAIN),
+                LockResult::Blocked { uid, blocker } => {
+                    vfs.wait_for_lock(pid, &blocker)?;
+                    drop(vfs);
+                    scheduler
+                        .current_thread_mut()
+                        .set_waiting(WaitingState::FileLock(uid));
+                    if let Err(e) = auto_preempt() {
+                        // Interrupted by a signal, the lock is not set
+                        VFS.lock().stop_waiting_lock(pid);
+                        return Err(e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Apply or remove an advisory lock on the whole open file specified
+/// by fd. The lock belongs to the open file description: the file
+/// descriptors duplicated by dup() or fork() share it, and it is
+/// released when the last of them is closed. A call on an already
+/// locked file converts the lock to the new mode.
+///
+/// LOCK_SH
+///     Place a shared lock. More than one process may hold a shared
+///     lock for a given file at a given time.
+/// LOCK_EX
+///     Place an exclusive lock. Only one process may hold an
+///     exclusive lock for a given file at a given time.
+/// LOCK_UN
+///     Remove an existing lock held by this process.
+///
+/// The call blocks while an incompatible lock is held by another
+/// process, unless LOCK_NB is ORed with the operation.
+///
+/// [EWOULDBLOCK]
+///     The file is locked and the LOCK_NB flag was selected.
+/// [EINTR]
+///     While blocked waiting to acquire a lock, the call was
+///     interrupted by delivery of a signal caught by a handler.
+/// [EINVAL]
+///     operation is invalid.
+pub fn sys_flock(fd: Fd, operation: u32) -> SysResult<u32> {
+    let wait = operation & LOCK_NB == 0;
+    let lock_type = match operation & !LOCK_NB {
+        LOCK_SH => Some(LockType::Shared),
+        LOCK_EX => Some(LockType::Exclusive),
+        LOCK_UN => None,
+        _ => return Err(Errno::EINVAL),
+    };
+
+    let (pid, inode_id, owner) = unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let file_operation = scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface
+            .get_file_descriptor(fd)?
+            .get_file_operation();
+        let inode_id = file_operation
+            .lock()
+            .get_inode_id()
+            .map_err(|_| Errno::EINVAL)?;
+        (
+            scheduler.current_task_id().0,
+            inode_id,
+            LockOwner::open_file(&file_operation),
+        )
+    });
+
+    match lock_type {
+        Some(lock_type) => {
+            let lock = FileLock {
+                owner,
+                lock_type,
+                start: 0,
+                end: OFFSET_MAX,
+                pid,
+            };
+            set_lock_or_wait(pid, inode_id, lock, wait)
+        }
+        None => unpreemptible_context!({
+            VFS.lock().unlock(inode_id, owner, 0, OFFSET_MAX)?;
+            Ok(0)
+        }),
+    }
+}
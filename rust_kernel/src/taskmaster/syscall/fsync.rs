@@ -0,0 +1,74 @@
+//! sys_sync(), sys_syncfs(), sys_fsync() and sys_fdatasync()
+
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::VFS;
+use super::Fd;
+
+/// The sync() function shall cause all information in memory that
+/// updates file systems to be scheduled for writing out to all file
+/// systems.
+///
+/// The filesystems write through, so only the write caches of the
+/// devices are flushed.
+pub fn sys_sync() -> SysResult<u32> {
+    unpreemptible_context!({
+        if let Err(e) = VFS.lock().sync() {
+            log::error!("sync: {:?}", e);
+        }
+    });
+    Ok(0)
+}
+
+/// syncfs() is like sync(), but synchronizes just the filesystem
+/// containing the file referred to by the open file descriptor fd.
+pub fn sys_syncfs(fd: Fd) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        // Pipes and sockets have no filesystem to synchronize
+        let inode_id = match fd_interface.get_file_operation(fd)?.get_inode_id() {
+            Ok(inode_id) => inode_id,
+            Err(_) => return Ok(0),
+        };
+        VFS.lock().syncfs(inode_id)?;
+        Ok(0)
+    })
+}
+
+/// The fsync() function shall request that all data for the open file
+/// descriptor named by fildes is to be transferred to the storage
+/// device associated with the file described by fildes.
+///
+/// [EINVAL]
+///     The fildes argument does not refer to a file on which this
+///     operation is possible.
+/// [EIO]
+///     An I/O error occurred while reading from or writing to the file
+///     system.
+pub fn sys_fsync(fd: Fd) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let file_operation = &mut fd_interface.get_file_operation(fd)?;
+        file_operation.fsync()
+    })
+}
+
+/// The fdatasync() function shall force all currently queued I/O
+/// operations associated with the file indicated by file descriptor
+/// fildes to the synchronized I/O completion state.
+///
+/// As the metadata are written through with the data, it is fsync().
+pub fn sys_fdatasync(fd: Fd) -> SysResult<u32> {
+    sys_fsync(fd)
+}
@@ -1,6 +1,7 @@
 use super::SysResult;
 
 use super::scheduler::SCHEDULER;
+use super::vfs::VFS;
 use libc_binding::{c_char, Errno};
 
 /// The getcwd() function shall place an absolute pathname of the
@@ -36,8 +37,11 @@ pub fn sys_getcwd(buf: *mut c_char, size: usize) -> SysResult<u32> {
             v.make_checked_mut_slice(buf, size)?
         };
 
+        // The path is seen from the root directory of the process
         let cwd = &scheduler.current_thread_group().cwd;
-        cwd.write_path_in_buffer(safe_buf)?;
+        VFS.lock()
+            .path_from_root(cwd)?
+            .write_path_in_buffer(safe_buf)?;
         Ok(0)
     })
 }
@@ -0,0 +1,33 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::Fd;
+
+/// The getdents64() system call reads several linux_dirent64
+/// structures from the directory referred to by the open file
+/// descriptor `fd` into the buffer pointed to by `dirp`. The argument
+/// `count` specifies the size of that buffer.
+///
+/// On success, the number of bytes read is returned. On end of
+/// directory, 0 is returned.
+pub fn sys_getdents64(fd: Fd, dirp: *mut u8, count: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        // Check if given pointers are not bullshit
+        let safe_buf = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_mut_slice(dirp, count as usize)?
+        };
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let file_operation = &mut fd_interface.get_file_operation(fd)?;
+        file_operation.getdents(safe_buf)
+    })
+}
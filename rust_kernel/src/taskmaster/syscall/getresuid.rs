@@ -0,0 +1,67 @@
+//! sys_getresuid() and sys_getresgid()
+
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+use libc_binding::{gid_t, uid_t};
+
+/// getresuid() returns the real user ID, the effective user ID, and
+/// the saved set-user-ID of the calling process, in the arguments
+/// ruid, euid, and suid, respectively.
+///
+/// [EFAULT]
+///     One of the arguments specified an address outside the calling
+///     program's address space.
+pub fn sys_getresuid(ruid: *mut uid_t, euid: *mut uid_t, suid: *mut uid_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let (ruid, euid, suid) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            (
+                v.make_checked_ref_mut(ruid)?,
+                v.make_checked_ref_mut(euid)?,
+                v.make_checked_ref_mut(suid)?,
+            )
+        };
+        let cred = &scheduler.current_thread_group().credentials;
+        *ruid = cred.uid;
+        *euid = cred.euid;
+        *suid = cred.suid;
+        Ok(0)
+    })
+}
+
+/// getresgid() returns the real group ID, the effective group ID, and
+/// the saved set-group-ID of the calling process, in the arguments
+/// rgid, egid, and sgid, respectively.
+///
+/// [EFAULT]
+///     One of the arguments specified an address outside the calling
+///     program's address space.
+pub fn sys_getresgid(rgid: *mut gid_t, egid: *mut gid_t, sgid: *mut gid_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let (rgid, egid, sgid) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            (
+                v.make_checked_ref_mut(rgid)?,
+                v.make_checked_ref_mut(egid)?,
+                v.make_checked_ref_mut(sgid)?,
+            )
+        };
+        let cred = &scheduler.current_thread_group().credentials;
+        *rgid = cred.gid;
+        *egid = cred.egid;
+        *sgid = cred.sgid;
+        Ok(0)
+    })
+}
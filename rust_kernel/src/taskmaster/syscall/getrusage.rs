@@ -0,0 +1,45 @@
+//! sys_getrusage()
+
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+
+use libc_binding::{rusage, Errno, RUSAGE_CHILDREN, RUSAGE_SELF, RUSAGE_THREAD};
+
+/// Get the resources used by the calling process if who is
+/// RUSAGE_SELF, by its terminated and waited-for children and their
+/// own waited-for descendants if who is RUSAGE_CHILDREN, or by the
+/// calling thread if who is RUSAGE_THREAD. The maximum resident set
+/// size of a thread is the one of its process.
+///
+/// [EINVAL]
+///     The value of the who argument is not valid.
+pub fn sys_getrusage(who: i32, r_usage: *mut rusage) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let (r_usage, max_rss) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            (v.make_checked_ref_mut(r_usage)?, v.peak_rss())
+        };
+        let thread_group = scheduler.current_thread_group_mut();
+        thread_group.usage.update_max_rss(max_rss);
+
+        let usage = match who {
+            who if who == RUSAGE_SELF as i32 => thread_group.usage,
+            RUSAGE_CHILDREN => thread_group.children_usage,
+            who if who == RUSAGE_THREAD as i32 => {
+                let max_rss = thread_group.usage.max_rss;
+                let mut usage = scheduler.current_thread().usage;
+                usage.update_max_rss(max_rss);
+                usage
+            }
+            _ => return Err(Errno::EINVAL),
+        };
+        *r_usage = usage.into();
+        Ok(0)
+    })
+}
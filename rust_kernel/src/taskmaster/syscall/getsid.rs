@@ -0,0 +1,20 @@
+use super::scheduler::{Pid, SCHEDULER};
+use super::SysResult;
+use libc_binding::Errno;
+
+/// The getsid() function shall obtain the process group ID of the
+/// process that is the session leader of the process specified by
+/// pid. If pid is 0, it specifies the calling process.
+///
+/// [ESRCH]
+///     There is no process with a process ID equal to pid.
+pub fn sys_getsid(pid: Pid) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        if pid == 0 {
+            Ok(scheduler.current_thread_group().sid as u32)
+        } else {
+            Ok(scheduler.get_thread_group(pid).ok_or(Errno::ESRCH)?.sid as u32)
+        }
+    })
+}
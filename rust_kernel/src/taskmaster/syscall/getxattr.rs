@@ -0,0 +1,44 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use libc_binding::c_char;
+
+use core::convert::TryFrom;
+
+/// The getxattr() system call retrieves the value of the extended
+/// attribute `name` of the file `path` in the buffer `value` of
+/// `size` bytes, and returns the size of the value. If `size` is 0,
+/// only the size of the value is returned, so that the caller can
+/// allocate a buffer large enough.
+pub fn sys_getxattr(
+    path: *const c_char,
+    name: *const c_char,
+    value: *mut u8,
+    size: usize,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        // Check if given pointers are not bullshit
+        let (safe_path, safe_name, safe_value) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            let safe_value = match size {
+                0 => &mut [][..],
+                _ => v.make_checked_mut_slice(value, size)?,
+            };
+            (v.make_checked_str(path)?, v.make_checked_str(name)?, safe_value)
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        Ok(VFS.lock().getxattr(cwd, creds, path, safe_name, safe_value)? as u32)
+    })
+}
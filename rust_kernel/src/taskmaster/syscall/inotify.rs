@@ -0,0 +1,94 @@
+//! sys_inotify_init(), sys_inotify_init1(), sys_inotify_add_watch()
+//! and sys_inotify_rm_watch()
+
+use super::scheduler::SCHEDULER;
+use super::vfs::Path;
+use super::Fd;
+use super::SysResult;
+
+use core::convert::TryFrom;
+use libc_binding::{c_char, Errno, InotifyMask, OpenFlags, IN_CLOEXEC, IN_NONBLOCK};
+
+/// Initialize a new inotify instance and return a file descriptor
+/// associated with its event queue. The events are read as struct
+/// inotify_event, a read blocks until an event is available.
+pub fn sys_inotify_init() -> SysResult<u32> {
+    sys_inotify_init1(0)
+}
+
+/// Same as inotify_init(), IN_NONBLOCK sets O_NONBLOCK on the new file
+/// descriptor and IN_CLOEXEC its close-on-exec flag.
+///
+/// [EINVAL]
+///     An invalid value was specified in flags.
+pub fn sys_inotify_init1(flags: u32) -> SysResult<u32> {
+    if flags & !(IN_NONBLOCK | IN_CLOEXEC) != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let flags = OpenFlags::from_bits_truncate(flags);
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let fd_interface = &mut scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface;
+
+        fd_interface.new_inotify(flags)
+    })
+}
+
+/// Watch the events `mask` on the file `pathname` for the inotify
+/// instance `fd`, or change the events of its watch of the file.
+/// Return the watch descriptor, which identifies the file in the
+/// events.
+///
+/// [EINVAL]
+///     mask contains no valid events, or fd is not an inotify file
+///     descriptor.
+/// [ENOSPC]
+///     The limit on the number of watches of the instance was reached.
+/// [ENOTDIR]
+///     mask contains IN_ONLYDIR and pathname is not a directory.
+pub fn sys_inotify_add_watch(fd: Fd, pathname: *const c_char, mask: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let safe_path = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_str(pathname)?
+        };
+        let path = Path::try_from(safe_path)?;
+        let tg = scheduler.current_thread_group();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let mut file_operation = fd_interface.get_file_operation(fd)?;
+        file_operation.add_watch(cwd, creds, path, InotifyMask::from_bits_truncate(mask))
+    })
+}
+
+/// Remove the watch `wd` of the inotify instance `fd`, an IN_IGNORED
+/// event is queued for it.
+///
+/// [EINVAL]
+///     wd is not valid, or fd is not an inotify file descriptor.
+pub fn sys_inotify_rm_watch(fd: Fd, wd: i32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let fd_interface = &scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface;
+
+        let mut file_operation = fd_interface.get_file_operation(fd)?;
+        file_operation.rm_watch(wd)
+    })
+}
@@ -3,12 +3,37 @@ use super::Fd;
 use super::SysResult;
 
 use core::convert::TryFrom;
-use libc_binding::IoctlCmd;
+use libc_binding::{Errno, IoctlCmd};
 
+/// The requests on the controlling terminal of a session are handled
+/// here, the other ones by the file operation of `fildes`:
+///
+/// TIOCSCTTY makes the terminal the controlling terminal of the
+/// session led by the calling process, a privileged process may steal
+/// the one of another session if `arg` is 1.
+///
+/// TIOCNOTTY gives up the controlling terminal, which is disassociated
+/// from the session when the calling process is the session leader.
 pub fn sys_ioctl(fildes: Fd, cmd: u32, arg: u32) -> SysResult<u32> {
     unpreemptible_context!({
         let cmd = IoctlCmd::try_from(cmd)?;
-        let scheduler = SCHEDULER.lock();
+        let mut scheduler = SCHEDULER.lock();
+
+        match cmd {
+            IoctlCmd::TIOCSCTTY => {
+                let tty = scheduler
+                    .current_thread_group_tty(fildes)
+                    .ok_or(Errno::ENOTTY)?;
+                scheduler.current_thread_group_acquire_terminal(tty, arg == 1)?;
+                return Ok(0);
+            }
+            IoctlCmd::TIOCNOTTY => {
+                let tty = scheduler.current_thread_group_controlling_terminal(fildes)?;
+                scheduler.current_thread_group_release_terminal(tty);
+                return Ok(0);
+            }
+            _ => {}
+        }
 
         let fd_interface = &scheduler
             .current_thread_group_running()
@@ -1,12 +1,52 @@
 use super::SysResult;
 
 use super::scheduler::{auto_preempt, Pid, SCHEDULER};
-use super::signal_interface::JobAction;
+use super::signal_interface::{JobAction, SigInfo};
 use super::thread_group::{Credentials, ThreadGroup};
 
 use core::convert::TryInto;
 use libc_binding::Errno;
-use libc_binding::{uid_t, Signum};
+use libc_binding::{uid_t, Signum, SI_QUEUE, SI_USER};
+
+/// Generate `signum` with `info` for the processes of `iter` the
+/// process of user IDs `self_uid` and `self_euid` may signal
+fn generate_signal<'a, T: Iterator<Item = &'a mut ThreadGroup>>(
+    iter: T,
+    signum: Signum,
+    info: SigInfo,
+    self_uid: uid_t,
+    self_euid: uid_t,
+) -> SysResult<u32> {
+    let mut present = false;
+    let mut has_perm = false;
+    for tg in iter {
+        if let Some(_thread) = tg.get_first_thread() {
+            present = true;
+        }
+        // For a process to have permission to send a signal to a process
+        // designated by pid, unless the sending process has appropriate
+        // privileges, the real or effective user ID of the sending process
+        // shall match the real or saved set-user-ID of the receiving
+        // process.
+        if tg.credentials.uid == self_uid
+            || tg.credentials.uid == self_euid
+            || tg.credentials.euid == self_uid
+            || tg.credentials.euid == self_euid
+        {
+            if let Some(thread) = tg.get_first_thread() {
+                thread.signal.generate_signal_info(signum, info)?;
+                has_perm = true;
+            }
+        }
+    }
+    if !present {
+        return Err(Errno::ESRCH);
+    }
+    if !has_perm {
+        return Err(Errno::EPERM);
+    }
+    Ok(0)
+}
 
 /// The kill() function shall send a signal to a process or a group of
 /// processes specified by pid. The signal to be sent is specified by
@@ -59,42 +99,6 @@ use libc_binding::{uid_t, Signum};
 /// send sig to any of the processes specified by pid. If kill()
 /// fails, no signal shall be sent.
 pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
-    fn generate_signal<'a, T: Iterator<Item = &'a mut ThreadGroup>>(
-        iter: T,
-        signum: Signum,
-        self_uid: uid_t,
-        self_euid: uid_t,
-    ) -> SysResult<u32> {
-        let mut present = false;
-        let mut has_perm = false;
-        for tg in iter {
-            if let Some(_thread) = tg.get_first_thread() {
-                present = true;
-            }
-            // For a process to have permission to send a signal to a process
-            // designated by pid, unless the sending process has appropriate
-            // privileges, the real or effective user ID of the sending process
-            // shall match the real or saved set-user-ID of the receiving
-            // process.
-            if tg.credentials.uid == self_uid
-                || tg.credentials.uid == self_euid
-                || tg.credentials.euid == self_uid
-                || tg.credentials.euid == self_euid
-            {
-                if let Some(thread) = tg.get_first_thread() {
-                    thread.signal.generate_signal(signum)?;
-                    has_perm = true;
-                }
-            }
-        }
-        if !present {
-            return Err(Errno::ESRCH);
-        }
-        if !has_perm {
-            return Err(Errno::EPERM);
-        }
-        Ok(0)
-    }
     unpreemptible_context!({
         let signum = signum.try_into().map_err(|_| Errno::EINVAL)?;
         let mut scheduler = SCHEDULER.lock();
@@ -104,6 +108,7 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
             euid: self_euid,
             ..
         } = scheduler.current_thread_group().credentials;
+        let info = SigInfo::sender(SI_USER as i32, scheduler.current_task_id().0, self_uid);
 
         if pid == 0 || pid < -1 {
             if pid == 0 {
@@ -120,6 +125,7 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
                         }
                     }),
                 signum,
+                info,
                 self_uid,
                 self_euid,
             )
@@ -127,6 +133,7 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
             generate_signal(
                 scheduler.iter_thread_groups_mut(),
                 signum,
+                info,
                 self_uid,
                 self_euid,
             )
@@ -134,6 +141,7 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
             generate_signal(
                 scheduler.get_thread_group_mut(pid).into_iter(),
                 signum,
+                info,
                 self_uid,
                 self_euid,
             )
@@ -155,3 +163,67 @@ pub unsafe fn sys_kill(mut pid: i32, signum: u32) -> SysResult<u32> {
         Ok(0)
     })
 }
+
+/// The sigqueue() function shall cause the signal specified by signo
+/// to be sent with the value specified by value to the process
+/// specified by pid. If signo is zero (the null signal), error
+/// checking is performed but no signal is actually sent.
+///
+/// The conditions required for a process to have permission to queue
+/// a signal to another process are the same as for the kill()
+/// function.
+///
+/// The signal is queued to the receiving process with the SI_QUEUE
+/// code and the value in the si_value member of its siginfo_t: the
+/// real-time signals sent several times are delivered as many times.
+///
+/// If the value of pid causes signo to be generated for the sending
+/// process, and if signo is not blocked for the calling thread, either
+/// signo or at least one pending unblocked signal shall be delivered
+/// to the calling thread before the sigqueue() function returns.
+///
+/// [EAGAIN]
+///     No resources are available to queue the signal: SIGQUEUE_MAX
+///     signals are already queued to the receiver.
+/// [EINVAL]
+///     The value of the signo argument is an invalid or unsupported
+///     signal number.
+/// [EPERM]
+///     The process does not have appropriate privileges to send the
+///     signal to the receiving process.
+/// [ESRCH]
+///     The process pid does not exist.
+pub fn sys_sigqueue(pid: Pid, signum: u32, value: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let signum = signum.try_into().map_err(|_| Errno::EINVAL)?;
+        let mut scheduler = SCHEDULER.lock();
+
+        if pid <= 0 {
+            return Err(Errno::ESRCH);
+        }
+        let Credentials {
+            uid: self_uid,
+            euid: self_euid,
+            ..
+        } = scheduler.current_thread_group().credentials;
+        let current_task_pid = scheduler.current_task_id().0;
+        let info = SigInfo::sender(SI_QUEUE, current_task_pid, self_uid).with_value(value);
+
+        generate_signal(
+            scheduler.get_thread_group_mut(pid).into_iter(),
+            signum,
+            info,
+            self_uid,
+            self_euid,
+        )?;
+        if pid == current_task_pid {
+            let action = scheduler.current_thread_get_job_action();
+
+            if action.intersects(JobAction::STOP) && !action.intersects(JobAction::TERMINATE) {
+                // Auto-preempt calling in case of Self stop
+                let _ret = auto_preempt();
+            }
+        }
+        Ok(0)
+    })
+}
@@ -0,0 +1,38 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use libc_binding::c_char;
+
+use core::convert::TryFrom;
+
+/// The listxattr() system call places the null terminated names of
+/// the extended attributes of the file `path` in the buffer `list`
+/// of `size` bytes, and returns the size of the list. If `size` is 0,
+/// only the size of the list is returned.
+pub fn sys_listxattr(path: *const c_char, list: *mut u8, size: usize) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        // Check if given pointers are not bullshit
+        let (safe_path, safe_list) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            let safe_list = match size {
+                0 => &mut [][..],
+                _ => v.make_checked_mut_slice(list, size)?,
+            };
+            (v.make_checked_str(path)?, safe_list)
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        Ok(VFS.lock().listxattr(cwd, creds, path, safe_list)? as u32)
+    })
+}
@@ -2,9 +2,15 @@ use super::scheduler::SCHEDULER;
 use super::vfs::{Path, VFS};
 use super::SysResult;
 use core::convert::TryFrom;
-use libc_binding::{c_char, mode_t, Errno, FileType};
+use libc_binding::{c_char, mode_t, Errno, FileType, AT_FDCWD};
 
-pub fn sys_mkdir(path: *const c_char, mut mode: mode_t) -> SysResult<u32> {
+pub fn sys_mkdir(path: *const c_char, mode: mode_t) -> SysResult<u32> {
+    sys_mkdirat(AT_FDCWD, path, mode)
+}
+
+/// Create a directory, a relative `path` is resolved from the
+/// directory open on `dirfd`
+pub fn sys_mkdirat(dirfd: i32, path: *const c_char, mode: mode_t) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
@@ -18,14 +24,15 @@ pub fn sys_mkdir(path: *const c_char, mut mode: mode_t) -> SysResult<u32> {
         };
         let tg = scheduler.current_thread_group_mut();
 
+        let cwd = &tg.get_dirfd_path(dirfd)?;
         let mask = tg.umask;
         let creds = &tg.credentials;
-        let cwd = &tg.cwd;
         let path = Path::try_from(safe_path)?;
-        // Mask out the bits of mode which are set in umask.
-        mode = mode & !mask;
         let mode = FileType::from_bits(mode as u16).ok_or(Errno::EINVAL)?;
-        VFS.lock().mkdir(cwd, creds, path, mode)?;
+        // The VFS masks out the bits of mode which are set in umask,
+        // unless a default ACL takes precedence.
+        let mask = FileType::from_bits_truncate(mask as u16);
+        VFS.lock().mkdir(cwd, creds, path, mode, mask)?;
         Ok(0)
     })
 }
@@ -4,25 +4,40 @@ use super::SysResult;
 use core::convert::TryFrom;
 use core::ffi::c_void;
 
-use libc_binding::c_char;
+use libc_binding::{c_char, MountFlags};
 
-/// Simply mount source on directory target for the moment
+/// Mount the filesystem `filesystemtype` of source on directory
+/// target. With MS_BIND, source is a directory or a file mounted
+/// again on target, and with MS_REMOUNT only the flags of the mount on
+/// target change: filesystemtype is ignored in both cases
 pub fn sys_mount(
     source: *const c_char,
     target: *const c_char,
-    _filesystemtype: *const c_char,
-    _mountflags: u32,
+    filesystemtype: *const c_char,
+    mountflags: u32,
     _data: *const c_void,
 ) -> SysResult<u32> {
     unpreemptible_context!({
         let scheduler = SCHEDULER.lock();
-        let (safe_source, safe_target) = {
+        let flags = MountFlags::from_bits_truncate(mountflags);
+        let (safe_source, safe_target, fs_type) = {
             let v = scheduler
                 .current_thread()
                 .unwrap_process()
                 .get_virtual_allocator();
 
-            (v.make_checked_str(source)?, v.make_checked_str(target)?)
+            let fs_type = if flags.intersects(MountFlags::MS_BIND | MountFlags::MS_REMOUNT) {
+                ""
+            } else {
+                v.make_checked_str(filesystemtype)?
+            };
+            // The source of a remount does not matter
+            let source = if flags.contains(MountFlags::MS_REMOUNT) {
+                "/"
+            } else {
+                v.make_checked_str(source)?
+            };
+            (source, v.make_checked_str(target)?, fs_type)
         };
 
         let tg = scheduler.current_thread_group();
@@ -31,7 +46,8 @@ pub fn sys_mount(
         let source = Path::try_from(safe_source)?;
         let target = Path::try_from(safe_target)?;
 
-        VFS.lock().mount(cwd, creds, source, target)?;
+        VFS.lock()
+            .mount(cwd, creds, source, target, fs_type, flags)?;
         Ok(0)
     })
 }
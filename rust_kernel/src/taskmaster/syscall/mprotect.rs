@@ -94,7 +94,7 @@ pub unsafe fn sys_mprotect(addr: *mut u8, length: usize, prot: MmapProt) -> SysR
                     vaddr.into(),
                     NbrPages::from(length),
                     AllocFlags::from(prot),
-                );
+                )?;
             }
         }
     });
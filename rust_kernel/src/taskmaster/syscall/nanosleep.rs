@@ -22,6 +22,23 @@ extern "C" {
     fn _get_pit_time() -> u32;
 }
 
+impl TimeSpec {
+    /// The pit time at which this interval will have elapsed from now
+    ///
+    /// [EINVAL]
+    ///     The tv_nsec field is less than zero or greater than or
+    ///     equal to 1000 million.
+    pub fn wake_time(&self) -> SysResult<u32> {
+        if self.tv_nsec < 0 || self.tv_nsec >= 1000000000 {
+            return Err(Errno::EINVAL);
+        }
+        // Set precision as 1/1000
+        let request_time = self.tv_sec as f32 + (self.tv_nsec / 1000000) as f32 / 1000.;
+        let pit_period = 1. / PIT0.lock().get_frequency().expect("PIT0 not initialized");
+        Ok((request_time / pit_period) as u32 + unsafe { _get_pit_time() })
+    }
+}
+
 /// The nanosleep() function shall cause the current thread to be
 /// suspended from execution until either the time interval specified
 /// by the rqtp argument has elapsed or a signal is delivered to the
@@ -68,15 +85,8 @@ fn nanosleep(req: *const TimeSpec, rem: *mut TimeSpec) -> SysResult<u32> {
     // drop the mutex
     drop(v);
 
-    let nsec = req.tv_nsec;
-    if nsec < 0 || nsec >= 1000000000 {
-        return Err(Errno::EINVAL);
-    }
-
-    // Set precision as 1/1000
-    let request_time = req.tv_sec as f32 + (req.tv_nsec / 1000000) as f32 / 1000.;
+    let next_wake = req.wake_time()?;
     let pit_period = 1. / PIT0.lock().get_frequency().expect("PIT0 not initialized");
-    let next_wake = (request_time / pit_period) as u32 + unsafe { _get_pit_time() };
 
     // Set as Sleeping
     scheduler
@@ -2,13 +2,18 @@
 use super::scheduler::auto_preempt;
 use super::scheduler::SCHEDULER;
 use super::thread::WaitingState;
-use super::vfs::VFS;
 use super::IpcResult;
 use super::SysResult;
-use libc_binding::{c_char, mode_t, Errno, FileType, OpenFlags};
+use libc_binding::{c_char, mode_t, Errno, FileType, OpenFlags, AT_FDCWD};
 
 /// Open a new file descriptor
-pub fn sys_open(filename: *const c_char, flags: u32, mut mode: mode_t) -> SysResult<u32> {
+pub fn sys_open(filename: *const c_char, flags: u32, mode: mode_t) -> SysResult<u32> {
+    sys_openat(AT_FDCWD, filename, flags, mode)
+}
+
+/// Open a new file descriptor, a relative `filename` is resolved
+/// from the directory open on `dirfd`
+pub fn sys_openat(dirfd: i32, filename: *const c_char, flags: u32, mode: mode_t) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
@@ -22,17 +27,16 @@ pub fn sys_open(filename: *const c_char, flags: u32, mut mode: mode_t) -> SysRes
         };
 
         let flags = OpenFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+        let cwd = scheduler.current_thread_group().get_dirfd_path(dirfd)?;
 
         let fd = {
             let creds;
-            let cwd;
             let fd_interface;
             let umask;
             {
                 let tg = scheduler.current_thread_group_mut();
 
                 creds = &tg.credentials;
-                cwd = &tg.cwd;
                 fd_interface = &mut tg
                     .thread_group_state
                     .unwrap_running_mut()
@@ -40,12 +44,12 @@ pub fn sys_open(filename: *const c_char, flags: u32, mut mode: mode_t) -> SysRes
                 umask = tg.umask;
             }
 
-            // Mask out the bits of mode which are set in umask.
-            mode &= !umask;
-
             let mode = FileType::from_bits(mode as u16).ok_or(Errno::EINVAL)?;
+            // The VFS masks out the bits of mode which are set in umask,
+            // unless a default ACL takes precedence.
+            let umask = FileType::from_bits_truncate(umask as u16);
 
-            match fd_interface.open(cwd, creds, file, flags, mode)? {
+            match fd_interface.open(&cwd, creds, file, flags, mode, umask)? {
                 IpcResult::Wait(fd, file_op_uid) => {
                     scheduler
                         .current_thread_mut()
@@ -57,27 +61,12 @@ pub fn sys_open(filename: *const c_char, flags: u32, mut mode: mode_t) -> SysRes
             }
         };
 
-        let tg = scheduler.current_thread_group_mut();
-        let fd_interface = &tg
-            .thread_group_state
-            .unwrap_running_mut()
-            .file_descriptor_interface;
-
-        let mut file_operation = fd_interface
-            .get_file_operation(fd)
-            .expect("FileOperation should be there for fd.");
-
-        if file_operation.isatty() == Ok(1) && !flags.contains(OpenFlags::O_NOCTTY) {
-            let inode_id = file_operation
-                .get_inode_id()
-                .expect("Should be possible to get the inode id of a tty");
-            let tty_minor = VFS
-                .lock()
-                .get_inode(inode_id)
-                .expect("Inode should be there")
-                .minor;
-
-            tg.controlling_terminal = Some(tty_minor);
+        // A session leader without controlling terminal acquires the
+        // terminal it opens, if it is not the one of another session
+        if !flags.contains(OpenFlags::O_NOCTTY) {
+            if let Some(tty) = scheduler.current_thread_group_tty(fd) {
+                let _ignored_result = scheduler.current_thread_group_acquire_terminal(tty, false);
+            }
         }
         Ok(fd)
     })
@@ -0,0 +1,33 @@
+use super::SysResult;
+use super::SCHEDULER;
+
+use libc_binding::{Errno, ADDR_NO_RANDOMIZE, PER_LINUX};
+
+/// The persona which only queries the current one
+const PERSONALITY_QUERY: u32 = 0xffff_ffff;
+
+/// Set the execution domain of the process to `persona` and return
+/// the previous one. The value 0xffffffff only returns it.
+///
+/// PER_LINUX is the only execution domain, the only flag is
+/// ADDR_NO_RANDOMIZE, which places the next images loaded by execve
+/// at fixed addresses. The persona is inherited by the children and
+/// kept across execve.
+///
+/// [EINVAL]
+///     The kernel was unable to change the personality.
+pub fn sys_personality(persona: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let personality = &mut scheduler.current_thread_group_mut().personality;
+        let old = *personality;
+
+        if persona != PERSONALITY_QUERY {
+            if persona & !ADDR_NO_RANDOMIZE != PER_LINUX {
+                return Err(Errno::EINVAL);
+            }
+            *personality = persona;
+        }
+        Ok(old)
+    })
+}
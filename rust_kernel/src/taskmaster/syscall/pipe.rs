@@ -1,10 +1,25 @@
-//! This file contains the description of the pipe syscall
+//! This file contains the description of the pipe and pipe2 syscalls
 
 use super::scheduler::SCHEDULER;
 use super::SysResult;
 
+use libc_binding::{Errno, OpenFlags, O_CLOEXEC, O_NONBLOCK};
+
 /// Create pipe
 pub fn sys_pipe(fd: &mut [i32]) -> SysResult<u32> {
+    sys_pipe2(fd, 0)
+}
+
+/// Same as pipe(), O_NONBLOCK in flags sets O_NONBLOCK on both file
+/// descriptors and O_CLOEXEC their close-on-exec flag.
+///
+/// [EINVAL]
+///     An invalid value was specified in flags.
+pub fn sys_pipe2(fd: &mut [i32], flags: u32) -> SysResult<u32> {
+    if flags & !(O_NONBLOCK | O_CLOEXEC) != 0 {
+        return Err(Errno::EINVAL);
+    }
+    let flags = OpenFlags::from_bits_truncate(flags);
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
         {
@@ -20,7 +35,7 @@ pub fn sys_pipe(fd: &mut [i32]) -> SysResult<u32> {
             .current_thread_group_running_mut()
             .file_descriptor_interface;
 
-        let ret = fd_interface.new_pipe()?;
+        let ret = fd_interface.new_pipe(flags)?;
         fd[0] = ret.0 as _;
         fd[1] = ret.1 as _;
     });
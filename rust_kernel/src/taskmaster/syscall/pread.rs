@@ -0,0 +1,68 @@
+//! sys_pread64() and sys_pwrite64()
+
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::Fd;
+
+use libc_binding::off_t;
+
+/// The pread() function shall be equivalent to read(), except that it
+/// shall read from a given position in the file without changing the
+/// file offset. An attempt to perform a pread() on a file that is
+/// incapable of seeking shall result in an error.
+///
+/// [EINVAL]
+///     The offset argument is negative.
+/// [ESPIPE]
+///     The file is a pipe, FIFO, or socket.
+pub fn sys_pread64(fd: Fd, buf: *mut u8, count: usize, offset: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let output = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            v.make_checked_mut_slice(buf, count)?
+        };
+
+        scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface
+            .pread(fd, output, offset)
+    })
+}
+
+/// The pwrite() function shall be equivalent to write(), except that
+/// it writes into a given position and does not change the file
+/// offset. An attempt to perform a pwrite() on a file that is
+/// incapable of seeking shall result in an error.
+///
+/// [EINVAL]
+///     The offset argument is negative.
+/// [ESPIPE]
+///     The file is a pipe, FIFO, or socket.
+pub fn sys_pwrite64(fd: Fd, buf: *const u8, count: usize, offset: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let input = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            // Check if pointer exists in user virtual address space
+            v.make_checked_slice(buf, count)?
+        };
+
+        scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface
+            .pwrite(fd, input, offset)
+    })
+}
@@ -2,7 +2,7 @@ use super::SysResult;
 
 use super::scheduler::SCHEDULER;
 use super::vfs::{Path, VFS};
-use libc_binding::c_char;
+use libc_binding::{c_char, AT_FDCWD};
 
 use core::convert::TryFrom;
 
@@ -24,6 +24,17 @@ use core::convert::TryFrom;
 /// return a value of -1, leave the buffer unchanged, and set errno to
 /// indicate the error.
 pub fn sys_readlink(path: *const c_char, buf: *mut c_char, bufsize: u32) -> SysResult<u32> {
+    sys_readlinkat(AT_FDCWD, path, buf, bufsize)
+}
+
+/// readlink() with a relative `path` resolved from the directory
+/// open on `dirfd`
+pub fn sys_readlinkat(
+    dirfd: i32,
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsize: u32,
+) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
@@ -42,7 +53,7 @@ pub fn sys_readlink(path: *const c_char, buf: *mut c_char, bufsize: u32) -> SysR
 
         let tg = scheduler.current_thread_group_mut();
         let creds = &tg.credentials;
-        let cwd = &tg.cwd;
+        let cwd = &tg.get_dirfd_path(dirfd)?;
         let path = Path::try_from(safe_path)?;
 
         VFS.lock().readlink(cwd, creds, path, safe_buf)
@@ -4,11 +4,15 @@ use super::{SysResult, SCHEDULER};
 
 use libc_binding::Errno;
 
+use super::vfs::VFS;
 use crate::drivers::ACPI;
 
 /// Reboot thw computer
 pub fn sys_reboot() -> SysResult<u32> {
     unpreemptible_context!({
+        if let Err(e) = VFS.lock().sync() {
+            log::error!("Cannot sync the filesystems before reboot: {:?}", e);
+        }
         match *ACPI.lock() {
             Some(mut acpi) => match acpi.reboot_computer() {
                 Ok(_) => {}
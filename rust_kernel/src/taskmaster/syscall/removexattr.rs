@@ -0,0 +1,33 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use libc_binding::c_char;
+
+use core::convert::TryFrom;
+
+/// The removexattr() system call removes the extended attribute
+/// `name` of the file `path`.
+pub fn sys_removexattr(path: *const c_char, name: *const c_char) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        // Check if given pointers are not bullshit
+        let (safe_path, safe_name) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            (v.make_checked_str(path)?, v.make_checked_str(name)?)
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        VFS.lock().removexattr(cwd, creds, path, safe_name)?;
+        Ok(0)
+    })
+}
@@ -1,10 +1,10 @@
 use super::scheduler::SCHEDULER;
-use super::vfs::Path;
 use super::vfs::VFS;
+use super::vfs::{Cwd, Path, VirtualFileSystem};
 use super::SysResult;
 use core::convert::TryFrom;
 
-use libc_binding::c_char;
+use libc_binding::{c_char, AT_FDCWD};
 
 /// The rename() function shall change the name of a file. The old
 /// argument points to the pathname of the file to be renamed. The new
@@ -70,6 +70,18 @@ use libc_binding::c_char;
 /// If the rename() function fails for any reason other than [EIO],
 /// any file named by new shall be unaffected.
 pub fn sys_rename(old: *const c_char, new: *const c_char) -> SysResult<u32> {
+    sys_renameat(AT_FDCWD, old, AT_FDCWD, new)
+}
+
+/// rename() with a relative `old` resolved from the directory open on
+/// `olddirfd` and a relative `new` resolved from the directory open
+/// on `newdirfd`
+pub fn sys_renameat(
+    olddirfd: i32,
+    old: *const c_char,
+    newdirfd: i32,
+    new: *const c_char,
+) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
@@ -81,12 +93,27 @@ pub fn sys_rename(old: *const c_char, new: *const c_char) -> SysResult<u32> {
 
             (v.make_checked_str(old)?, v.make_checked_str(new)?)
         };
-        let old = Path::try_from(safe_old)?;
-        let new = Path::try_from(safe_new)?;
         let tg = scheduler.current_thread_group();
         let creds = &tg.credentials;
-        let cwd = &tg.cwd;
-        VFS.lock().rename(cwd, creds, old, new)?;
+        let old_cwd = tg.get_dirfd_path(olddirfd)?;
+        let new_cwd = tg.get_dirfd_path(newdirfd)?;
+
+        let mut vfs = VFS.lock();
+        // The VFS resolves both paths from the same directory
+        let old = absolute_path(&vfs, &old_cwd, Path::try_from(safe_old)?)?;
+        let new = absolute_path(&vfs, &new_cwd, Path::try_from(safe_new)?)?;
+        vfs.rename(&tg.cwd, creds, old, new)?;
         Ok(0)
     })
 }
+
+/// Prefix the relative `path` with the directory of `cwd`, as seen
+/// from the root directory of the process
+fn absolute_path(vfs: &VirtualFileSystem, cwd: &Cwd, path: Path) -> SysResult<Path> {
+    if path.is_absolute() {
+        return Ok(path);
+    }
+    let mut dir = vfs.path_from_root(cwd)?;
+    dir.chain(path)?;
+    Ok(dir)
+}
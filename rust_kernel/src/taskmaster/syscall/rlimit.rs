@@ -0,0 +1,86 @@
+//! sys_getrlimit() and sys_setrlimit()
+
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+
+use super::fd_interface::FileDescriptorInterface;
+use libc_binding::{rlimit, Errno, RLIMIT_NOFILE, RLIMIT_STACK};
+
+/// Get the soft and hard limits of `resource` for the calling
+/// process. The soft limit is the one the kernel enforces, the hard
+/// limit is the ceiling of the soft limit.
+///
+/// [EINVAL]
+///     resource is invalid.
+pub fn sys_getrlimit(resource: u32, rlp: *mut rlimit) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let rlp = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_ref_mut(rlp)?
+        };
+        *rlp = scheduler.current_thread_group().rlimits.get(resource)?;
+        Ok(0)
+    })
+}
+
+/// Set the soft and hard limits of `resource` for the calling
+/// process. An unprivileged process may only lower its hard limit.
+/// The RLIMIT_NOFILE soft limit is the size the file descriptor table
+/// can grow to, the file descriptors above a lowered limit stay open.
+/// The RLIMIT_STACK soft limit is how far the stack can grow, the
+/// pages already allocated below a lowered limit stay so.
+///
+/// [EINVAL]
+///     resource is invalid, or the soft limit is above the hard limit.
+/// [EPERM]
+///     The process attempted to raise its hard limit without
+///     appropriate privileges, or to raise the RLIMIT_NOFILE hard
+///     limit beyond the maximum number of file descriptors of a
+///     process.
+pub fn sys_setrlimit(resource: u32, rlp: *const rlimit) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let limit = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            *v.make_checked_ref(rlp)?
+        };
+        if limit.rlim_cur > limit.rlim_max {
+            return Err(Errno::EINVAL);
+        }
+        let tg = scheduler.current_thread_group_mut();
+        let old_limit = tg.rlimits.get(resource)?;
+        if limit.rlim_max > old_limit.rlim_max && !tg.credentials.is_root() {
+            return Err(Errno::EPERM);
+        }
+        if resource == RLIMIT_NOFILE && limit.rlim_max > FileDescriptorInterface::NR_OPEN {
+            return Err(Errno::EPERM);
+        }
+        tg.rlimits.set(resource, limit)?;
+
+        if resource == RLIMIT_NOFILE {
+            scheduler
+                .current_thread_group_running_mut()
+                .file_descriptor_interface
+                .set_max_fds(limit.rlim_cur);
+        }
+        if resource == RLIMIT_STACK {
+            scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator()
+                .set_stack_limit(limit.rlim_cur as usize);
+        }
+        Ok(0)
+    })
+}
@@ -1,28 +1,8 @@
-use super::scheduler::SCHEDULER;
-use super::vfs::{Path, VFS};
+use super::unlink::sys_unlinkat;
 use super::SysResult;
-use core::convert::TryFrom;
 
-use libc_binding::c_char;
+use libc_binding::{c_char, AT_FDCWD, AT_REMOVEDIR};
 
 pub fn sys_rmdir(path: *const c_char) -> SysResult<u32> {
-    unpreemptible_context!({
-        let mut scheduler = SCHEDULER.lock();
-
-        let safe_path = {
-            let v = scheduler
-                .current_thread_mut()
-                .unwrap_process_mut()
-                .get_virtual_allocator();
-
-            v.make_checked_str(path)?
-        };
-        let tg = scheduler.current_thread_group_mut();
-
-        let creds = &tg.credentials;
-        let cwd = &tg.cwd;
-        let path = Path::try_from(safe_path)?;
-        VFS.lock().rmdir(cwd, creds, path)?;
-        Ok(0)
-    })
+    sys_unlinkat(AT_FDCWD, path, AT_REMOVEDIR)
 }
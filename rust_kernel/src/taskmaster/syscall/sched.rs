@@ -0,0 +1,349 @@
+//! sys_nice(), sys_getpriority(), sys_setpriority() and the sched_*
+//! syscalls
+
+use super::scheduler::{auto_preempt, Pid, SchedPolicy, Scheduler, NICE_MAX, NICE_MIN, SCHEDULER};
+use super::thread::Thread;
+use super::thread_group::ThreadGroup;
+use super::SysResult;
+
+use core::convert::TryFrom;
+use libc_binding::{
+    sched_param, uid_t, Errno, PRIO_PGRP, PRIO_PROCESS, PRIO_USER, SCHED_RESET_ON_FORK,
+};
+
+/// getpriority() returns 20 - nice, which is never negative
+const PRIORITY_BASE: i32 = 20;
+
+/// The processes designated by the `which` and `who` arguments of
+/// getpriority() and setpriority()
+enum Target {
+    Process(Pid),
+    Group(Pid),
+    User(uid_t),
+}
+
+impl Target {
+    /// A `who` of 0 designates the calling process, its process group
+    /// or its real user ID
+    fn new(scheduler: &Scheduler, which: u32, who: u32) -> SysResult<Self> {
+        let thread_group = scheduler.current_thread_group();
+
+        Ok(match which {
+            PRIO_PROCESS if who == 0 => Target::Process(scheduler.current_task_id().0),
+            PRIO_PROCESS => Target::Process(who as Pid),
+            PRIO_PGRP if who == 0 => Target::Group(thread_group.pgid),
+            PRIO_PGRP => Target::Group(who as Pid),
+            PRIO_USER if who == 0 => Target::User(thread_group.credentials.uid),
+            PRIO_USER => Target::User(who as uid_t),
+            _ => return Err(Errno::EINVAL),
+        })
+    }
+
+    fn designates(&self, pid: Pid, thread_group: &ThreadGroup) -> bool {
+        match *self {
+            Target::Process(target) => pid == target,
+            Target::Group(pgid) => thread_group.pgid == pgid,
+            Target::User(uid) => thread_group.credentials.uid == uid,
+        }
+    }
+}
+
+/// Unless it is privileged, a process may only change the scheduling
+/// of the processes whose real or effective user ID is its effective
+/// user ID
+fn may_change(euid: uid_t, is_root: bool, thread_group: &ThreadGroup) -> bool {
+    is_root || thread_group.credentials.uid == euid || thread_group.credentials.euid == euid
+}
+
+/// The effective user ID of the calling process and whether it is
+/// privileged
+fn current_credentials(scheduler: &Scheduler) -> (uid_t, bool) {
+    let credentials = &scheduler.current_thread_group().credentials;
+
+    (credentials.euid, credentials.is_root())
+}
+
+/// The main thread of a running process, the nice value, policy and
+/// priority of a process are the ones of its main thread
+fn main_thread(thread_group: &ThreadGroup) -> Option<&Thread> {
+    thread_group.get_all_thread()?.get(&0)
+}
+
+/// Add `inc` to the nice value of the calling process, a higher nice
+/// value gives a lower share of CPU. The nice value is clamped to
+/// [-20, 19] and applies to all the threads of the process.
+///
+/// [EPERM]
+///     inc is negative and the calling process does not have
+///     appropriate privileges.
+pub fn sys_nice(inc: i32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let thread_group = scheduler.current_thread_group_mut();
+
+        if inc < 0 && !thread_group.credentials.is_root() {
+            return Err(Errno::EPERM);
+        }
+        let nice = thread_group
+            .get_first_thread()
+            .expect("no main thread")
+            .sched
+            .nice()
+            .saturating_add(inc);
+        for thread in thread_group.iter_thread_mut() {
+            thread.sched.set_nice(nice);
+        }
+        Ok(0)
+    })
+}
+
+/// Return 20 minus the lowest nice value of the processes designated
+/// by `which` and `who`: a process ID for PRIO_PROCESS, a process
+/// group ID for PRIO_PGRP, a real user ID for PRIO_USER. A `who` of 0
+/// designates the calling process, its process group or its user.
+///
+/// [ESRCH]
+///     No process could be located using the which and who values
+///     specified.
+/// [EINVAL]
+///     which is not one of PRIO_PROCESS, PRIO_PGRP, or PRIO_USER.
+pub fn sys_getpriority(which: u32, who: u32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let target = Target::new(&scheduler, which, who)?;
+
+        scheduler
+            .iter_thread_groups_with_pid()
+            .filter(|(&pid, thread_group)| target.designates(pid, thread_group))
+            .filter_map(|(_, thread_group)| main_thread(thread_group))
+            .map(|thread| thread.sched.nice())
+            .min()
+            .map(|nice| (PRIORITY_BASE - nice) as u32)
+            .ok_or(Errno::ESRCH)
+    })
+}
+
+/// Set the nice value of the processes designated by `which` and
+/// `who`, as in getpriority(), to `prio` clamped to [-20, 19].
+///
+/// [ESRCH]
+///     No process could be located using the which and who values
+///     specified.
+/// [EINVAL]
+///     which is not one of PRIO_PROCESS, PRIO_PGRP, or PRIO_USER.
+/// [EPERM]
+///     A process was located, but neither the real nor effective user
+///     ID of the executing process match the effective user ID of the
+///     process whose nice value is being changed.
+/// [EACCES]
+///     A request was made to change the priority to a lower numeric
+///     value and the current process does not have appropriate
+///     privileges.
+pub fn sys_setpriority(which: u32, who: u32, prio: i32) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let target = Target::new(&scheduler, which, who)?;
+        let (euid, is_root) = current_credentials(&scheduler);
+        let nice = prio.max(NICE_MIN).min(NICE_MAX);
+        let mut result = Err(Errno::ESRCH);
+
+        for (_, thread_group) in scheduler
+            .iter_thread_groups_with_pid_mut()
+            .filter(|(&pid, thread_group)| target.designates(pid, thread_group))
+        {
+            let current_nice = match main_thread(thread_group) {
+                Some(thread) => thread.sched.nice(),
+                None => continue,
+            };
+            if !may_change(euid, is_root, thread_group) {
+                result = result.or(Err(Errno::EPERM));
+            } else if nice < current_nice && !is_root {
+                result = result.or(Err(Errno::EACCES));
+            } else {
+                for thread in thread_group.iter_thread_mut() {
+                    thread.sched.set_nice(nice);
+                }
+                result = Ok(0);
+            }
+        }
+        result
+    })
+}
+
+/// The running process `pid`, or the calling one when it is 0
+fn sched_target(scheduler: &mut Scheduler, pid: Pid) -> SysResult<&mut ThreadGroup> {
+    let pid = if pid == 0 {
+        scheduler.current_task_id().0
+    } else {
+        pid
+    };
+    scheduler
+        .get_thread_group_mut(pid)
+        .filter(|thread_group| thread_group.get_all_thread().is_some())
+        .ok_or(Errno::ESRCH)
+}
+
+/// Set the policy and the static priority of all the threads of
+/// `pid`, keep the policy when `policy` is None. The policy may carry
+/// the SCHED_RESET_ON_FORK flag.
+fn set_scheduler(pid: Pid, policy: Option<u32>, param: *const sched_param) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let priority = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_ref(param)?.sched_priority
+        };
+        let priority = u32::try_from(priority).map_err(|_| Errno::EINVAL)?;
+        let reset_on_fork = policy.map(|policy| policy & SCHED_RESET_ON_FORK != 0);
+        let policy = policy
+            .map(|policy| SchedPolicy::try_from(policy & !SCHED_RESET_ON_FORK))
+            .transpose()?;
+        let (euid, is_root) = current_credentials(&scheduler);
+        let thread_group = sched_target(&mut scheduler, pid)?;
+
+        let current = &main_thread(thread_group).expect("no main thread").sched;
+        let policy = policy.unwrap_or(current.policy());
+        let reset_on_fork = reset_on_fork.unwrap_or(current.reset_on_fork());
+        // Only a privileged process may let the children inherit a
+        // real-time policy again
+        let keeps_reset = reset_on_fork || !current.reset_on_fork();
+        if !may_change(euid, is_root, thread_group)
+            || ((policy.is_realtime() || !keeps_reset) && !is_root)
+        {
+            return Err(Errno::EPERM);
+        }
+        // The priority is in the range of the policy for all the threads or for none
+        for thread in thread_group.iter_thread_mut() {
+            thread.sched.set_policy(policy, priority)?;
+            thread.sched.set_reset_on_fork(reset_on_fork);
+        }
+        Ok(0)
+    })
+}
+
+/// Set the scheduling policy and the static priority of the process
+/// `pid`, or of the calling process when it is 0.
+///
+/// SCHED_FIFO and SCHED_RR are the real-time policies, with a static
+/// priority from 1 to 99: a real-time thread runs before all the
+/// SCHED_OTHER ones, and before the real-time ones of lower priority.
+/// A SCHED_FIFO thread runs until it blocks or yields, a SCHED_RR one
+/// shares the CPU in turn with the ones of the same priority.
+/// SCHED_OTHER is the time sharing policy, its static priority is 0.
+///
+/// When SCHED_RESET_ON_FORK is or'ed to the policy, the threads and
+/// processes created by the process start as SCHED_OTHER, with a nice
+/// value of at least 0, and without the flag.
+///
+/// [EINVAL]
+///     The value of the policy parameter is invalid, or the priority
+///     is outside the range of the policy.
+/// [EPERM]
+///     The requesting process does not have permission to set the
+///     scheduling policy of the specified process, only privileged
+///     processes can select a real-time policy.
+/// [ESRCH]
+///     No process can be found corresponding to that specified by
+///     pid.
+pub fn sys_sched_setscheduler(pid: Pid, policy: u32, param: *const sched_param) -> SysResult<u32> {
+    set_scheduler(pid, Some(policy), param)
+}
+
+/// Set the static priority of the process `pid`, or of the calling
+/// process when it is 0, within the range of its policy. The errors
+/// are the ones of sched_setscheduler().
+pub fn sys_sched_setparam(pid: Pid, param: *const sched_param) -> SysResult<u32> {
+    set_scheduler(pid, None, param)
+}
+
+/// Return the scheduling policy of the process `pid`, or of the
+/// calling thread when it is 0, with SCHED_RESET_ON_FORK if it is set.
+///
+/// [ESRCH]
+///     No process can be found corresponding to that specified by
+///     pid.
+pub fn sys_sched_getscheduler(pid: Pid) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let thread = if pid == 0 {
+            scheduler.current_thread()
+        } else {
+            scheduler
+                .get_thread_group(pid)
+                .and_then(main_thread)
+                .ok_or(Errno::ESRCH)?
+        };
+        let policy: u32 = thread.sched.policy().into();
+        if thread.sched.reset_on_fork() {
+            Ok(policy | SCHED_RESET_ON_FORK)
+        } else {
+            Ok(policy)
+        }
+    })
+}
+
+/// Store the static priority of the process `pid`, or of the calling
+/// thread when it is 0, in `param`.
+///
+/// [ESRCH]
+///     No process can be found corresponding to that specified by
+///     pid.
+pub fn sys_sched_getparam(pid: Pid, param: *mut sched_param) -> SysResult<u32> {
+    unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+
+        let param = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_ref_mut(param)?
+        };
+        let thread = if pid == 0 {
+            scheduler.current_thread()
+        } else {
+            scheduler
+                .get_thread_group(pid)
+                .and_then(main_thread)
+                .ok_or(Errno::ESRCH)?
+        };
+        param.sched_priority = thread.sched.rt_priority() as i32;
+        Ok(0)
+    })
+}
+
+/// Return the highest static priority of `policy`.
+///
+/// [EINVAL]
+///     The value of the policy parameter does not represent a defined
+///     scheduling policy.
+pub fn sys_sched_get_priority_max(policy: u32) -> SysResult<u32> {
+    Ok(SchedPolicy::try_from(policy)?.priority_range().1)
+}
+
+/// Return the lowest static priority of `policy`.
+///
+/// [EINVAL]
+///     The value of the policy parameter does not represent a defined
+///     scheduling policy.
+pub fn sys_sched_get_priority_min(policy: u32) -> SysResult<u32> {
+    Ok(SchedPolicy::try_from(policy)?.priority_range().0)
+}
+
+/// Let the other threads run before the calling one: the ones of the
+/// same static priority for a real-time thread, all the runnable
+/// SCHED_OTHER ones otherwise. Always succeeds.
+pub fn sys_sched_yield() -> SysResult<u32> {
+    unpreemptible_context!({
+        SCHEDULER.lock().current_thread_mut().sched.yield_cpu();
+        let _ignored_result = auto_preempt();
+        Ok(0)
+    })
+}
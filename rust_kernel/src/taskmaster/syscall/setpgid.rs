@@ -14,16 +14,61 @@ use libc_binding::Errno;
 /// As a special case, if pid is 0, the process ID of the calling
 /// process shall be used. Also, if pgid is 0, the process ID of the
 /// indicated process shall be used.
+///
+/// [EACCES]
+///     The value of the pid argument matches the process ID of a
+///     child process of the calling process and the child process has
+///     successfully executed one of the exec functions.
+/// [EINVAL]
+///     The value of the pgid argument is less than 0.
+/// [EPERM]
+///     The process indicated by the pid argument is a session leader,
+///     or is a child of the calling process which is not in the same
+///     session as the calling process, or pgid does not match the
+///     process ID of the process indicated by the pid argument and
+///     there is no process with a process group ID that matches pgid
+///     in the same session as the calling process.
+/// [ESRCH]
+///     The value of the pid argument does not match the process ID of
+///     the calling process or of a child process of the calling
+///     process.
 pub fn sys_setpgid(mut pid: Pid, mut pgid: Pid) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
+        let current_pid = scheduler.current_task_id().0;
+        let sid = scheduler.current_thread_group().sid;
 
         if pid == 0 {
-            pid = scheduler.current_task_id().0
+            pid = current_pid
         }
         if pgid == 0 {
             pgid = pid
         }
+        if pgid < 0 {
+            return Err(Errno::EINVAL);
+        }
+        let thread_group = scheduler.get_thread_group(pid).ok_or(Errno::ESRCH)?;
+        if pid != current_pid {
+            if thread_group.parent != current_pid {
+                return Err(Errno::ESRCH);
+            }
+            if thread_group.sid != sid {
+                return Err(Errno::EPERM);
+            }
+            if thread_group.filename.is_some() {
+                return Err(Errno::EACCES);
+            }
+        }
+        if thread_group.sid == pid {
+            return Err(Errno::EPERM);
+        }
+        if pgid != pid
+            && !scheduler
+                .iter_thread_groups()
+                .any(|thread_group| thread_group.pgid == pgid && thread_group.sid == sid)
+        {
+            return Err(Errno::EPERM);
+        }
         scheduler
             .get_thread_group_mut(pid)
             .ok_or(Errno::ESRCH)?
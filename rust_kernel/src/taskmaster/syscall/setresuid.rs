@@ -0,0 +1,76 @@
+//! sys_setresuid() and sys_setresgid()
+
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+use libc_binding::{gid_t, uid_t, Errno};
+
+/// The value of an ID argument which leaves the ID unchanged
+const UNCHANGED: u32 = !0;
+
+/// Compute the new (real, effective, saved) IDs from the current ones,
+/// an unprivileged process may only set each of them to one of the
+/// current IDs.
+fn new_ids(current: [u32; 3], new: [u32; 3], privileged: bool) -> SysResult<[u32; 3]> {
+    let mut ids = current;
+    for (id, new) in ids.iter_mut().zip(new.iter()) {
+        if *new == UNCHANGED {
+            continue;
+        }
+        if !privileged && !current.contains(new) {
+            return Err(Errno::EPERM);
+        }
+        *id = *new;
+    }
+    Ok(ids)
+}
+
+/// setresuid() sets the real user ID, the effective user ID, and the
+/// saved set-user-ID of the calling process. An argument of -1 leaves
+/// the corresponding ID unchanged.
+///
+/// An unprivileged process may change each of these IDs only to the
+/// current real user ID, effective user ID or saved set-user-ID.
+///
+/// [EPERM]
+///     The calling process is not privileged and tried to change an
+///     ID to a value which is not one of its current IDs.
+pub fn sys_setresuid(ruid: uid_t, euid: uid_t, suid: uid_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let cred = &mut scheduler.current_thread_group_mut().credentials;
+
+        let [uid, euid, suid] = new_ids(
+            [cred.uid, cred.euid, cred.suid],
+            [ruid, euid, suid],
+            cred.is_root(),
+        )?;
+        cred.uid = uid;
+        cred.euid = euid;
+        cred.suid = suid;
+        Ok(0)
+    })
+}
+
+/// setresgid() sets the real group ID, the effective group ID, and
+/// the saved set-group-ID of the calling process, with the same rules
+/// as setresuid().
+///
+/// [EPERM]
+///     The calling process is not privileged and tried to change an
+///     ID to a value which is not one of its current IDs.
+pub fn sys_setresgid(rgid: gid_t, egid: gid_t, sgid: gid_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let cred = &mut scheduler.current_thread_group_mut().credentials;
+
+        let [gid, egid, sgid] = new_ids(
+            [cred.gid, cred.egid, cred.sgid],
+            [rgid, egid, sgid],
+            cred.is_root(),
+        )?;
+        cred.gid = gid;
+        cred.egid = egid;
+        cred.sgid = sgid;
+        Ok(0)
+    })
+}
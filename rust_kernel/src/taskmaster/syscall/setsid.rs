@@ -0,0 +1,23 @@
+use super::scheduler::SCHEDULER;
+use super::SysResult;
+
+/// The setsid() function shall create a new session, if the calling
+/// process is not a process group leader. Upon return the calling
+/// process shall be the session leader of this new session, shall be
+/// the process group leader of a new process group, and shall have no
+/// controlling terminal. The process group ID of the calling process
+/// shall be set equal to the process ID of the calling process. The
+/// calling process shall be the only process in the new process group
+/// and the only process in the new session.
+///
+/// [EPERM]
+///     The calling process is already a process group leader, or the
+///     process group ID of a process other than the calling process
+///     matches the process ID of the calling process.
+pub fn sys_setsid() -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        Ok(scheduler.current_thread_group_new_session()? as u32)
+    })
+}
@@ -0,0 +1,50 @@
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use libc_binding::{c_char, Errno, XattrFlags};
+
+use core::convert::TryFrom;
+
+/// The setxattr() system call sets the value of the extended
+/// attribute `name` of the file `path` to the `size` bytes of
+/// `value`. With the XATTR_CREATE flag it fails if the attribute
+/// already exists, with XATTR_REPLACE if it does not exist yet.
+pub fn sys_setxattr(
+    path: *const c_char,
+    name: *const c_char,
+    value: *const u8,
+    size: usize,
+    flags: u32,
+) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let flags = XattrFlags::from_bits(flags).ok_or(Errno::EINVAL)?;
+        if flags.contains(XattrFlags::XATTR_CREATE | XattrFlags::XATTR_REPLACE) {
+            return Err(Errno::EINVAL);
+        }
+
+        // Check if given pointers are not bullshit
+        let (safe_path, safe_name, safe_value) = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            let safe_value = match size {
+                0 => &[][..],
+                _ => v.make_checked_slice(value, size)?,
+            };
+            (v.make_checked_str(path)?, v.make_checked_str(name)?, safe_value)
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        VFS.lock().setxattr(cwd, creds, path, safe_name, safe_value, flags)?;
+        Ok(0)
+    })
+}
@@ -2,12 +2,16 @@ use super::SysResult;
 
 use libc_binding::Errno;
 
+use super::vfs::VFS;
 use crate::drivers::ACPI;
 use crate::system::i8086_payload_apm_shutdown;
 
 /// Shutdown the computer
 pub fn sys_shutdown() -> SysResult<u32> {
     unpreemptible_context!({
+        if let Err(e) = VFS.lock().sync() {
+            log::error!("Cannot sync the filesystems before shutdown: {:?}", e);
+        }
         match *ACPI.lock() {
             Some(mut acpi) => match unsafe { acpi.shutdown() } {
                 Ok(_) => {}
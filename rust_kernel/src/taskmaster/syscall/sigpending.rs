@@ -0,0 +1,21 @@
+use super::scheduler::SCHEDULER;
+use super::signal_interface::sigset_t;
+use super::SysResult;
+
+/// The sigpending() function shall store, in the location referenced
+/// by the set argument, the set of signals that are blocked from
+/// delivery to the calling thread and that are pending on the process
+/// or the calling thread.
+pub fn sys_sigpending(set: *mut sigset_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let checked_set = scheduler
+            .current_thread_mut()
+            .unwrap_process_mut()
+            .get_virtual_allocator()
+            .make_checked_ref_mut(set)?;
+
+        *checked_set = scheduler.current_thread().signal.pending_signals();
+        Ok(0)
+    })
+}
@@ -2,6 +2,7 @@ use super::SysResult;
 
 use super::process::CpuState;
 use super::scheduler::SCHEDULER;
+use super::signal_interface::UContext;
 
 /// Must know who is the last pending signal
 /// Decrease signal frame and POP signal in list
@@ -16,3 +17,22 @@ pub unsafe fn sys_sigreturn(cpu_state: *mut CpuState) -> SysResult<u32> {
         Ok((*cpu_state).registers.eax)
     })
 }
+
+/// Return from a SA_SIGINFO handler: restore the context saved in the
+/// ucontext of its signal frame, which the handler may have changed
+pub unsafe fn sys_rt_sigreturn(cpu_state: *mut CpuState) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+        let ucontext = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator()
+            .make_checked_ref(UContext::from_frame((*cpu_state).esp))?;
+
+        scheduler
+            .current_thread_mut()
+            .signal
+            .terminate_rt_signal(cpu_state, ucontext);
+        Ok((*cpu_state).registers.eax)
+    })
+}
@@ -0,0 +1,112 @@
+use super::SysResult;
+
+use super::nanosleep::TimeSpec;
+use super::scheduler::{auto_preempt, SCHEDULER};
+use super::signal_interface::{sigset_t, SigInfo};
+use super::thread::WaitingState;
+
+use libc_binding::Errno;
+
+extern "C" {
+    fn _get_pit_time() -> u32;
+}
+
+/// The sigtimedwait() function shall select the pending signal from
+/// the set specified by set. Should any of multiple pending signals
+/// in the range SIGRTMIN to SIGRTMAX be selected, it shall be the
+/// lowest numbered one. If no signal in set is pending at the time of
+/// the call, the thread shall be suspended until one or more becomes
+/// pending or until it is interrupted by an unblocked, caught signal.
+///
+/// The signals defined by set should have been blocked at the time of
+/// the call, otherwise the behavior is undefined.
+///
+/// If the info argument is not NULL, the selected signal number shall
+/// be stored in the si_signo member, and the cause of the signal
+/// shall be stored in the si_code member. If any value is queued to
+/// the selected signal, the first such queued value shall be dequeued
+/// and stored in the si_value member of info.
+///
+/// If no signal in set is pending at the time of the call, the
+/// thread shall be suspended until one or more becomes pending or
+/// until the time interval specified by the timespec structure
+/// referenced by timeout has elapsed. If timeout is NULL, the thread
+/// waits without limit, as sigwaitinfo() does.
+///
+/// Upon successful completion, the selected signal number is
+/// returned.
+///
+/// [EAGAIN]
+///     No signal specified by set was generated within the specified
+///     timeout period.
+/// [EINTR]
+///     The wait was interrupted by an unblocked, caught signal.
+/// [EINVAL]
+///     The timeout argument specified a tv_nsec value less than zero
+///     or greater than or equal to 1000 million.
+pub fn sys_sigtimedwait(
+    set: *const sigset_t,
+    info: *mut SigInfo,
+    timeout: *const TimeSpec,
+) -> SysResult<u32> {
+    let (set, wake_time) = unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        let set = *v.make_checked_ref(set)?;
+        let wake_time = if timeout.is_null() {
+            None
+        } else {
+            Some(v.make_checked_ref(timeout)?.wake_time()?)
+        };
+        (set, wake_time)
+    });
+    let mut interrupted = false;
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+
+            let checked_info = if info.is_null() {
+                None
+            } else {
+                Some(
+                    scheduler
+                        .current_thread()
+                        .unwrap_process()
+                        .get_virtual_allocator()
+                        .make_checked_ref_mut(info)?,
+                )
+            };
+
+            let signal = &mut scheduler.current_thread_mut().signal;
+            if let Some((signum, siginfo)) = signal.take_pending_signal(set) {
+                signal.set_waited_signals(0);
+                if let Some(info) = checked_info {
+                    *info = siginfo;
+                }
+                return Ok(signum as u32);
+            }
+            if interrupted {
+                signal.set_waited_signals(0);
+                return Err(Errno::EINTR);
+            }
+            if wake_time.map_or(false, |time| unsafe { _get_pit_time() } >= time) {
+                signal.set_waited_signals(0);
+                return Err(Errno::EAGAIN);
+            }
+            // The awaited signals resume the thread even if they are blocked
+            signal.set_waited_signals(set);
+            scheduler.current_thread_mut().set_waiting(match wake_time {
+                Some(time) => WaitingState::Sleeping(time),
+                None => WaitingState::Pause,
+            });
+            interrupted = match auto_preempt() {
+                Err(Errno::EINTR) => true,
+                _ => false,
+            };
+        })
+    }
+}
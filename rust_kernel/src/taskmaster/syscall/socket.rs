@@ -12,7 +12,7 @@ use super::thread::WaitingState;
 use super::vfs::{posix_consts::PATH_MAX, Path, VFS};
 use core::mem::transmute;
 use fallible_collections::TryClone;
-use libc_binding::{c_char, Errno, ShutDownOption};
+use libc_binding::{c_char, Errno, OpenFlags, ShutDownOption, SOCK_CLOEXEC, SOCK_NONBLOCK};
 
 use sync::DeadMutexGuard;
 
@@ -126,6 +126,8 @@ safe_convertible_enum!(
         SysRecvFrom = 12,
         /// Shut down part of a full-duplex connection. connection-oriented
         SysShutdown = 13,
+        /// Accept a connection on a socket, with flags for the new file descriptor
+        SysAccept4 = 18,
     }
 );
 
@@ -246,11 +248,13 @@ pub fn sys_socketcall(call_type: u32, args: SocketArgsPtr) -> SysResult<u32> {
                     socket_type,
                     protocol,
                 } = unsafe { *(args as *const SocketArgs) };
+                let flags = socket_type & (SOCK_NONBLOCK | SOCK_CLOEXEC);
                 socket(
                     &mut scheduler,
                     domain.try_into()?,
-                    socket_type.try_into()?,
+                    (socket_type & !flags).try_into()?,
                     protocol,
+                    OpenFlags::from_bits_truncate(flags),
                 )
             }
             SysBind => {
@@ -299,7 +303,40 @@ pub fn sys_socketcall(call_type: u32, args: SocketArgsPtr) -> SysResult<u32> {
                 };
                 drop(v);
                 drop(scheduler);
-                accept(socket_fd as i32, addr, addr_len as *mut SockLen)
+                accept(
+                    socket_fd as i32,
+                    addr,
+                    addr_len as *mut SockLen,
+                    OpenFlags::empty(),
+                )
+            }
+            SysAccept4 => {
+                v.check_user_ptr::<Accept4Args>(args as *const Accept4Args)?;
+                let Accept4Args {
+                    socket_fd,
+                    addr,
+                    addr_len,
+                    flags,
+                } = unsafe { *(args as *const Accept4Args) };
+                if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+                    return Err(Errno::EINVAL);
+                }
+                let addr = {
+                    let addr = addr as *mut SockaddrUnix;
+                    if addr.is_null() {
+                        None
+                    } else {
+                        Some(v.make_checked_ref_mut(addr)?)
+                    }
+                };
+                drop(v);
+                drop(scheduler);
+                accept(
+                    socket_fd as i32,
+                    addr,
+                    addr_len as *mut SockLen,
+                    OpenFlags::from_bits_truncate(flags),
+                )
             }
             SysSend => {
                 v.check_user_ptr::<SendArgs>(args as *const SendArgs)?;
@@ -431,6 +468,7 @@ fn socket(
     domain: Domain,
     socket_type: SocketType,
     _protocol: u32,
+    flags: OpenFlags,
 ) -> SysResult<u32> {
     // println!(
     //     "{:?}: {:?} {:?} {:?}",
@@ -444,7 +482,7 @@ fn socket(
         .thread_group_state
         .unwrap_running_mut()
         .file_descriptor_interface;
-    fd_interface.open_socket(domain, socket_type)
+    fd_interface.open_socket(domain, socket_type, flags)
 }
 
 raw_deferencing_struct!(
@@ -559,11 +597,28 @@ raw_deferencing_struct!(
     }
 );
 
+raw_deferencing_struct!(
+    /// Arguments for accept4() function
+    #[derive(Debug, Copy, Clone)]
+    #[repr(C)]
+    struct Accept4Args {
+        /// Associated socket file decriptor
+        socket_fd: i32,
+        /// Sockaddr strucure pointer. Ths syscall must fill this structure if NON-NULL
+        addr: *mut Sockaddr,
+        /// Length of previous structure. The syscall must specify the length of sockaddr
+        addr_len: *mut SockLen,
+        /// SOCK_NONBLOCK and SOCK_CLOEXEC flags of the new file descriptor
+        flags: u32,
+    }
+);
+
 // This function cannot be completely safe by nature of theses functionalities.
 fn accept(
     socket_fd: i32,
     sockaddr: Option<&mut SockaddrUnix>,
     _sockaddr_len: *mut SockLen,
+    flags: OpenFlags,
 ) -> SysResult<u32> {
     // println!(
     //     "{:?}: {:?} {:?} {:?}",
@@ -579,7 +634,7 @@ fn accept(
                 .current_thread_group_mut()
                 .unwrap_running_mut()
                 .file_descriptor_interface;
-            let res = fd_interface.accept_socket(socket_fd as u32)?;
+            let res = fd_interface.accept_socket(socket_fd as u32, flags)?;
             match res {
                 IpcResult::Wait(_res, file_op_uid) => {
                     scheduler
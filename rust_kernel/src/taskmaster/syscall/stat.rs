@@ -3,7 +3,7 @@ use super::SysResult;
 use super::scheduler::{Scheduler, SCHEDULER};
 use super::vfs::{Path, VFS};
 use core::convert::TryFrom;
-use libc_binding::{c_char, stat};
+use libc_binding::{c_char, stat, Errno, AT_EMPTY_PATH, AT_FDCWD, AT_SYMLINK_NOFOLLOW};
 
 pub fn statfn(scheduler: &Scheduler, path: Path) -> SysResult<stat> {
     let tg = scheduler.current_thread_group();
@@ -13,9 +13,25 @@ pub fn statfn(scheduler: &Scheduler, path: Path) -> SysResult<stat> {
 }
 
 pub fn sys_stat(filename: *const c_char, buf: *mut stat) -> SysResult<u32> {
+    sys_fstatat(AT_FDCWD, filename, buf, 0)
+}
+
+/// stat() with a relative `filename` resolved from the directory
+/// open on `dirfd`. With AT_SYMLINK_NOFOLLOW, it acts like lstat(),
+/// and with AT_EMPTY_PATH, an empty `filename` designates `dirfd`
+/// itself, which may be any file
+pub fn sys_fstatat(
+    dirfd: i32,
+    filename: *const c_char,
+    buf: *mut stat,
+    flags: u32,
+) -> SysResult<u32> {
     unpreemptible_context!({
         let scheduler = SCHEDULER.lock();
 
+        if flags & !(AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0 {
+            return Err(Errno::EINVAL);
+        }
         // Check if given pointers are not bullshit
         let (safe_filename, safe_buf) = {
             let v = scheduler
@@ -28,13 +44,27 @@ pub fn sys_stat(filename: *const c_char, buf: *mut stat) -> SysResult<u32> {
                 v.make_checked_ref_mut::<stat>(buf)?,
             )
         };
-        let path = Path::try_from(safe_filename)?;
-        match statfn(&scheduler, path) {
-            Ok(stat) => {
-                *safe_buf = stat;
-                Ok(0)
+
+        let tg = scheduler.current_thread_group();
+        let creds = &tg.credentials;
+        *safe_buf = if safe_filename.is_empty() && flags & AT_EMPTY_PATH != 0 {
+            if dirfd == AT_FDCWD {
+                VFS.lock().stat(&tg.cwd, creds, Path::try_from(".")?)?
+            } else {
+                tg.unwrap_running()
+                    .file_descriptor_interface
+                    .get_file_operation(dirfd as _)?
+                    .fstat()?
             }
-            Err(value) => Err(value),
-        }
+        } else {
+            let cwd = &tg.get_dirfd_path(dirfd)?;
+            let path = Path::try_from(safe_filename)?;
+            if flags & AT_SYMLINK_NOFOLLOW != 0 {
+                VFS.lock().lstat(cwd, creds, path)?
+            } else {
+                VFS.lock().stat(cwd, creds, path)?
+            }
+        };
+        Ok(0)
     })
 }
@@ -17,25 +17,14 @@ use super::SysResult;
 /// foreground process group.
 /// [EBADF]
 ///     The fildes argument is not a valid file descriptor.
-/// [EINVAL]
-///     This implementation does not support the value in the pgid_id
-///     argument.
-/// [EIO]
-///     The process group of the writing process is orphaned, the
-///     calling thread is not blocking SIGTTOU, and the process is not
-///     ignoring SIGTTOU.
 /// [ENOTTY]
 ///     The calling process does not have a controlling terminal, or
-///     the file is not the controlling terminal, or the controlling
-///     terminal is no longer associated with the session of the
-///     calling process.
-/// [EPERM]
-///     The value of pgid_id is a value supported by the
-///     implementation, but does not match the process group ID of a
-///     process in the same session as the calling process.
+///     the file is not the controlling terminal.
 pub fn sys_tcgetpgrp(fildes: Fd) -> SysResult<u32> {
     unpreemptible_context!({
         let scheduler = SCHEDULER.lock();
+        scheduler.current_thread_group_controlling_terminal(fildes)?;
+
         let fd_interface = &scheduler
             .current_thread_group_running()
             .file_descriptor_interface;
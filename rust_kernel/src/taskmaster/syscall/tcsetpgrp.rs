@@ -1,9 +1,12 @@
 //! tcsetpgrp syscall
-use super::scheduler::SCHEDULER;
+use super::scheduler::{auto_preempt, SCHEDULER};
 use super::Fd;
 use super::Pid;
 use super::SysResult;
 
+use libc_binding::Errno;
+use terminal::TtyAccess;
+
 /// If the process has a controlling terminal, tcsetpgrp() shall set
 /// the foreground process group ID associated with the terminal to
 /// pgid_id. The application shall ensure that the file associated
@@ -13,7 +16,6 @@ use super::SysResult;
 /// value of pgid_id matches a process group ID of a process in the
 /// same session as the calling process.
 ///
-//TODO:
 /// Attempts to use tcsetpgrp() from a process which is a member of a
 /// background process group on a fildes associated with its
 /// controlling terminal shall cause the process group to be sent a
@@ -22,17 +24,52 @@ use super::SysResult;
 /// allowed to perform the operation, and no signal is sent.
 /// [EBADF]
 ///     The fildes argument is not a valid file descriptor.
+/// [EINTR]
+///     A signal interrupted tcsetpgrp().
+/// [EINVAL]
+///     This implementation does not support the value in the pgid_id
+///     argument.
+/// [EIO]
+///     The process group of the writing process is orphaned, the
+///     calling thread is not blocking SIGTTOU, and the process is not
+///     ignoring SIGTTOU.
 /// [ENOTTY]
 ///     The calling process does not have a controlling terminal, or
-///     the file is not the controlling terminal.
+///     the file is not the controlling terminal, or the controlling
+///     terminal is no longer associated with the session of the
+///     calling process.
+/// [EPERM]
+///     The value of pgid_id is a value supported by the
+///     implementation, but does not match the process group ID of a
+///     process in the same session as the calling process.
 pub fn sys_tcsetpgrp(fildes: Fd, pgid_id: Pid) -> SysResult<u32> {
-    unpreemptible_context!({
-        let scheduler = SCHEDULER.lock();
-        let fd_interface = &scheduler
-            .current_thread_group_running()
-            .file_descriptor_interface;
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+            let sid = scheduler.current_thread_group().sid;
+
+            scheduler.current_thread_group_controlling_terminal(fildes)?;
+            if pgid_id <= 0 {
+                return Err(Errno::EINVAL);
+            }
+            if !scheduler
+                .iter_thread_groups()
+                .any(|thread_group| thread_group.pgid == pgid_id && thread_group.sid == sid)
+            {
+                return Err(Errno::EPERM);
+            }
+            if scheduler.current_thread_tty_job_control(fildes, TtyAccess::Control)? {
+                // Stopped by SIGTTOU until the process group is continued
+                let _ignored_result = auto_preempt();
+                continue;
+            }
+
+            let fd_interface = &scheduler
+                .current_thread_group_running()
+                .file_descriptor_interface;
 
-        let file_operation = &mut fd_interface.get_file_operation(fildes)?;
-        file_operation.tcsetpgrp(pgid_id)
-    })
+            let file_operation = &mut fd_interface.get_file_operation(fildes)?;
+            return file_operation.tcsetpgrp(pgid_id);
+        })
+    }
 }
@@ -1,7 +1,7 @@
 use super::mmap::MmapArgStruct;
 use super::nanosleep::TimeSpec;
 use super::process::CpuState;
-use super::signal_interface::{sigset_t, StructSigaction};
+use super::signal_interface::{sigset_t, SigInfo, StructSigaction};
 use super::Fd;
 use super::MmapProt;
 use super::SocketArgsPtr;
@@ -10,18 +10,25 @@ use crate::memory::tools::address::Virt;
 use core::ffi::c_void;
 use i386::BaseRegisters;
 use libc_binding::{
-    c_char, dev_t, gid_t, kernel, mode_t, off_t, rusage, stat, termios, timeval, timezone, tms,
-    uid_t, utimbuf, OpenFlags, Pid, DIR,
+    c_char, dev_t, gid_t, iovec, kernel, mode_t, off_t, rlimit, rusage, sched_param, stack_t, stat,
+    termios, timeval, timezone, tms, uid_t, utimbuf, OpenFlags, Pid,
 };
 use libc_binding::{
-    ACCESS, CHDIR, CHMOD, CHOWN, CLONE, CLOSE, DUP, DUP2, EXECVE, EXIT, EXIT_QEMU, FCHMOD, FCHOWN,
-    FCNTL, FORK, FSTAT, GETCWD, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID, GETPGRP,
-    GETPID, GETPPID, GETTIMEOFDAY, GETUID, GET_KERNEL_PROPERTIES, INSMOD, IOCTL, ISATTY, KILL,
-    LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKNOD, MMAP, MOUNT, MPROTECT, MUNMAP, NANOSLEEP, OPEN,
-    OPENDIR, PAUSE, PIPE, READ, READLINK, REBOOT, RENAME, RMDIR, RMMOD, SETEGID, SETEUID, SETGID,
-    SETGROUPS, SETHOSTNAME, SETPGID, SETUID, SHUTDOWN, SIGACTION, SIGNAL, SIGPROCMASK, SIGRETURN,
-    SIGSUSPEND, SOCKETCALL, STACK_OVERFLOW, STAT, SYMLINK, TCGETATTR, TCGETPGRP, TCSETATTR,
-    TCSETPGRP, TEST, TIMES, UMASK, UMOUNT, UNLINK, UTIME, WAIT4, WAITPID, WRITE,
+    ACCESS, CHDIR, CHMOD, CHOWN, CHROOT, CLONE, CLOSE, DUP, DUP2, DUP3, EXECVE, EXIT, EXIT_QEMU,
+    FCHDIR, FCHMOD, FCHOWN, FCNTL, FDATASYNC, FLOCK, FORK, FSTAT, FSTATAT, FSYNC, FTRUNCATE64,
+    GETCWD, GETDENTS64, GETEGID, GETEUID, GETGID, GETGROUPS, GETHOSTNAME, GETPGID, GETPGRP, GETPID,
+    GETPPID, GETPRIORITY, GETRESGID, GETRESUID, GETRLIMIT, GETRUSAGE, GETSID, GETTIMEOFDAY, GETUID,
+    GET_KERNEL_PROPERTIES, INOTIFY_ADD_WATCH, INOTIFY_INIT, INOTIFY_INIT1, INOTIFY_RM_WATCH,
+    INSMOD, IOCTL, ISATTY, KILL, LINK, LSEEK, LSMOD, LSTAT, MKDIR, MKDIRAT, MKNOD, MMAP, MOUNT,
+    MPROTECT, MUNMAP, NANOSLEEP, NICE, OPEN, OPENAT, PAUSE, PERSONALITY, PIPE, PIPE2, PREAD64,
+    PREADV, PWRITE64, PWRITEV, READ, READLINK, READLINKAT, READV, REBOOT, RENAME, RENAMEAT, RMDIR,
+    RMMOD, RT_SIGRETURN, SCHED_GETPARAM, SCHED_GETSCHEDULER, SCHED_GET_PRIORITY_MAX,
+    SCHED_GET_PRIORITY_MIN, SCHED_SETPARAM, SCHED_SETSCHEDULER, SCHED_YIELD, SETEGID, SETEUID,
+    SETGID, SETGROUPS, SETHOSTNAME, SETPGID, SETPRIORITY, SETRESGID, SETRESUID, SETRLIMIT, SETSID,
+    SETUID, SHUTDOWN, SIGACTION, SIGALTSTACK, SIGNAL, SIGPENDING, SIGPROCMASK, SIGQUEUE, SIGRETURN,
+    SIGSUSPEND, SIGTIMEDWAIT, SOCKETCALL, STACK_OVERFLOW, STAT, SYMLINK, SYNC, SYNCFS, TCGETATTR,
+    TCGETPGRP, TCSETATTR, TCSETPGRP, TEST, TIMES, TRUNCATE64, UMASK, UMOUNT, UNLINK, UNLINKAT,
+    UTIME, VFORK, WAIT4, WAITID, WAITPID, WRITE, WRITEV,
 };
 
 #[allow(dead_code)]
@@ -40,6 +47,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
         match eax {
             EXIT => log::info!("exit({:#?})", ebx as i32),
             FORK => log::info!("fork()"),
+            VFORK => log::info!("vfork()"),
             READ => log::info!(
                 "read({:#?}, {:#?}, {:#?})",
                 ebx as i32,
@@ -78,6 +86,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as *const *const c_char,
             ),
             CHDIR => log::info!("chdir({:#?})", ebx as *const c_char),
+            CHROOT => log::info!("chroot({:#?})", ebx as *const c_char),
             CHMOD => log::info!("chmod({:#?}, {:#?})", ebx as *const c_char, ecx as mode_t),
             FCHMOD => log::info!("fchmod({:#?}, {:#?})", ebx as Fd, ecx as mode_t),
             MKNOD => log::info!(
@@ -111,6 +120,12 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             GETUID => log::info!("getuid()"),
             PAUSE => log::info!("pause()"),
             FSTAT => log::info!("fstat(fd: {:?}, buf: {:#X?})", ebx as Fd, ecx as *mut stat),
+            GETDENTS64 => log::info!(
+                "getdents64(fd: {:?}, dirp: {:#X?}, count: {:?})",
+                ebx as Fd,
+                ecx as *mut u8,
+                edx as u32
+            ),
             ACCESS => log::info!("access({:#?}, {:#?})", ebx as *const c_char, ecx as i32),
             UTIME => log::info!(
                 "utime({:#?}, {:#?})",
@@ -118,6 +133,12 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 ecx as *const utimbuf
             ),
             KILL => log::info!("kill({:#?}, {:#?})", ebx as i32, ecx as u32),
+            SIGQUEUE => log::info!(
+                "sigqueue({:#?}, {:#?}, {:#?})",
+                ebx as Pid,
+                ecx as u32,
+                edx as u32
+            ),
             RENAME => log::info!(
                 "rename(
 {:#?}, {:#?})",
@@ -127,6 +148,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             MKDIR => log::info!("mkdir({:#?}, {:#?})", ebx as *const c_char, ecx as mode_t),
             RMDIR => log::info!("rmdir({:#?})", ebx as *const c_char),
             PIPE => log::info!("pipe({:#?})", ebx as *const i32),
+            PIPE2 => log::info!("pipe2({:#?}, {:#?})", ebx as *const i32, ecx as u32),
             TIMES => log::info!("times({:#?})", ebx as *mut tms),
             DUP => log::info!("dup({:#?})", ebx as u32),
             SETGID => log::info!("setgid({:#?})", ebx as gid_t),
@@ -138,6 +160,7 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 ecx as u32,
                 edx as Fd
             ),
+            FLOCK => log::info!("flock({:#?}, {:#?})", ebx as Fd, ecx as u32),
             GETEGID => log::info!("getegid()"),
             UMOUNT => log::info!("umount({:#?})", ebx as *const c_char),
             IOCTL => log::info!(
@@ -150,7 +173,14 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             SETPGID => log::info!("setpgid({:#?}, {:#?})", ebx as Pid, ecx as Pid),
             GETPPID => log::info!("getppid()"),
             DUP2 => log::info!("dup2({:#?}, {:#?})", ebx as u32, ecx as u32),
+            DUP3 => log::info!(
+                "dup3({:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as u32,
+                edx as u32
+            ),
             GETPGRP => log::info!("getpgrp()"),
+            SETSID => log::info!("setsid()"),
             SIGACTION => log::info!(
                 "sigaction({:#?}, {:#?}, {:#?})",
                 ebx as u32,
@@ -158,6 +188,13 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as *mut StructSigaction,
             ),
             SIGSUSPEND => log::info!("sigsuspend({:#?})", ebx as *const sigset_t),
+            SIGPENDING => log::info!("sigpending({:#?})", ebx as *mut sigset_t),
+            SIGTIMEDWAIT => log::info!(
+                "sigtimedwait({:#?}, {:#?}, {:#?})",
+                ebx as *const sigset_t,
+                ecx as *mut SigInfo,
+                edx as *const TimeSpec
+            ),
             GETGROUPS => log::info!("getgroups({:#?}, {:#?})", ebx as i32, ecx as *mut gid_t),
             SETGROUPS => log::info!("setgroups({:#?}, {:#?})", ebx as i32, ecx as *const gid_t),
             SYMLINK => log::info!(
@@ -189,6 +226,13 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as i32,
                 esi as *mut rusage,
             ),
+            WAITID => log::info!(
+                "waitid({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as i32,
+                edx as *mut SigInfo,
+                esi as u32,
+            ),
             CLONE => log::info!(
                 "clone({:#?}, {:#?}, {:#?})",
                 cpu_state as u32,
@@ -208,6 +252,8 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
                 edx as *mut sigset_t
             ),
             GETPGID => log::info!("getpgid({:#?})", ebx as Pid),
+            GETSID => log::info!("getsid({:#?})", ebx as Pid),
+            FCHDIR => log::info!("fchdir({:#?})", ebx as Fd),
             NANOSLEEP => log::info!(
                 "nanosleep({:#?}, {:#?})",
                 ebx as *const TimeSpec,
@@ -229,7 +275,172 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
 
             GETCWD => log::info!("getcwd({:#?}, {:#?})", ebx as *const c_char, ecx as usize),
             SIGRETURN => log::info!("sigreturn({:#?})", cpu_state),
+            RT_SIGRETURN => log::info!("rt_sigreturn({:#?})", cpu_state),
+            SIGALTSTACK => log::info!(
+                "sigaltstack({:#?}, {:#?})",
+                ebx as *const stack_t,
+                ecx as *mut stack_t
+            ),
             SHUTDOWN => log::info!("shutdown()"),
+            OPENAT => log::info!(
+                "openat({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as u32,
+                esi as mode_t
+            ),
+            MKDIRAT => log::info!(
+                "mkdirat({:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as mode_t
+            ),
+            FSTATAT => log::info!(
+                "fstatat({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as *mut stat,
+                esi as u32
+            ),
+            UNLINKAT => log::info!(
+                "unlinkat({:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as u32
+            ),
+            RENAMEAT => log::info!(
+                "renameat({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as i32,
+                esi as *const c_char
+            ),
+            READLINKAT => log::info!(
+                "readlinkat({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as i32,
+                ecx as *const c_char,
+                edx as *mut c_char,
+                esi as u32
+            ),
+            PREAD64 => log::info!(
+                "pread64({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *mut u8,
+                edx as usize,
+                esi as off_t + ((edi as off_t) << 32),
+            ),
+            PWRITE64 => log::info!(
+                "pwrite64({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const u8,
+                edx as usize,
+                esi as off_t + ((edi as off_t) << 32),
+            ),
+            READV => log::info!(
+                "readv({:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const iovec,
+                edx as i32
+            ),
+            WRITEV => log::info!(
+                "writev({:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const iovec,
+                edx as i32
+            ),
+            PREADV => log::info!(
+                "preadv({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const iovec,
+                edx as i32,
+                esi as off_t + ((edi as off_t) << 32),
+            ),
+            PWRITEV => log::info!(
+                "pwritev({:#?}, {:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const iovec,
+                edx as i32,
+                esi as off_t + ((edi as off_t) << 32),
+            ),
+            TRUNCATE64 => log::info!(
+                "truncate64({:#?}, {:#?})",
+                ebx as *const c_char,
+                ecx as off_t + ((edx as off_t) << 32),
+            ),
+            FTRUNCATE64 => log::info!(
+                "ftruncate64({:#?}, {:#?})",
+                ebx as Fd,
+                ecx as off_t + ((edx as off_t) << 32),
+            ),
+            SYNC => log::info!("sync()"),
+            SYNCFS => log::info!("syncfs({:#?})", ebx as Fd),
+            FSYNC => log::info!("fsync({:#?})", ebx as Fd),
+            FDATASYNC => log::info!("fdatasync({:#?})", ebx as Fd),
+            GETRLIMIT => log::info!("getrlimit({:#?}, {:#?})", ebx as u32, ecx as *mut rlimit),
+            SETRLIMIT => log::info!("setrlimit({:#?}, {:#?})", ebx as u32, ecx as *const rlimit),
+            GETRUSAGE => log::info!("getrusage({:#?}, {:#?})", ebx as i32, ecx as *mut rusage),
+            PERSONALITY => log::info!("personality({:#x})", ebx as u32),
+            NICE => log::info!("nice({:#?})", ebx as i32),
+            GETPRIORITY => log::info!("getpriority({:#?}, {:#?})", ebx as u32, ecx as u32),
+            SETPRIORITY => log::info!(
+                "setpriority({:#?}, {:#?}, {:#?})",
+                ebx as u32,
+                ecx as u32,
+                edx as i32
+            ),
+            SCHED_SETPARAM => log::info!(
+                "sched_setparam({:#?}, {:#?})",
+                ebx as Pid,
+                ecx as *const sched_param
+            ),
+            SCHED_GETPARAM => log::info!(
+                "sched_getparam({:#?}, {:#?})",
+                ebx as Pid,
+                ecx as *mut sched_param
+            ),
+            SCHED_SETSCHEDULER => log::info!(
+                "sched_setscheduler({:#?}, {:#?}, {:#?})",
+                ebx as Pid,
+                ecx as u32,
+                edx as *const sched_param
+            ),
+            SCHED_GETSCHEDULER => log::info!("sched_getscheduler({:#?})", ebx as Pid),
+            SCHED_YIELD => log::info!("sched_yield()"),
+            SCHED_GET_PRIORITY_MAX => log::info!("sched_get_priority_max({:#?})", ebx as u32),
+            SCHED_GET_PRIORITY_MIN => log::info!("sched_get_priority_min({:#?})", ebx as u32),
+            SETRESUID => log::info!(
+                "setresuid({:#?}, {:#?}, {:#?})",
+                ebx as uid_t,
+                ecx as uid_t,
+                edx as uid_t
+            ),
+            GETRESUID => log::info!(
+                "getresuid({:#?}, {:#?}, {:#?})",
+                ebx as *mut uid_t,
+                ecx as *mut uid_t,
+                edx as *mut uid_t
+            ),
+            SETRESGID => log::info!(
+                "setresgid({:#?}, {:#?}, {:#?})",
+                ebx as gid_t,
+                ecx as gid_t,
+                edx as gid_t
+            ),
+            GETRESGID => log::info!(
+                "getresgid({:#?}, {:#?}, {:#?})",
+                ebx as *mut gid_t,
+                ecx as *mut gid_t,
+                edx as *mut gid_t
+            ),
+            INOTIFY_INIT => log::info!("inotify_init()"),
+            INOTIFY_INIT1 => log::info!("inotify_init1({:#?})", ebx as u32),
+            INOTIFY_ADD_WATCH => log::info!(
+                "inotify_add_watch({:#?}, {:#?}, {:#?})",
+                ebx as Fd,
+                ecx as *const c_char,
+                edx as u32
+            ),
+            INOTIFY_RM_WATCH => log::info!("inotify_rm_watch({:#?}, {:#?})", ebx as Fd, ecx as i32),
             TEST => log::info!("test()"),
             STACK_OVERFLOW => log::info!("stack_overflow()"),
             EXIT_QEMU => log::info!("exit_qemu({:#?})", ebx as u32),
@@ -245,7 +456,6 @@ pub fn trace_syscall(cpu_state: *mut CpuState) {
             SETEGID => log::info!("setegid({:#?})", ebx as gid_t),
             SETEUID => log::info!("seteuid({:#?})", ebx as uid_t),
             ISATTY => log::info!("isatty({:#?})", ebx as u32),
-            OPENDIR => log::info!("opendir({:#?}, {:#?})", ebx as *const u8, ecx as *mut DIR),
             INSMOD => log::info!("insmod({:#?})", ebx as *const c_char),
             RMMOD => log::info!("rmmod({:#?})", ebx as *const c_char),
             LSMOD => log::info!("lsmod"),
@@ -274,6 +484,7 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
     let sysname = match eax {
         EXIT => "exit",
         FORK => "fork",
+        VFORK => "vfork",
         READ => "read",
         WRITE => "write",
         OPEN => "open",
@@ -283,6 +494,7 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         UNLINK => "unlink",
         EXECVE => "execve",
         CHDIR => "chdir",
+        CHROOT => "chroot",
         CHMOD => "chmod",
         FCHMOD => "fchmod",
         STAT => "stat",
@@ -293,17 +505,21 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         GETUID => "getuid",
         PAUSE => "pause",
         FSTAT => "fstat",
+        GETDENTS64 => "getdents64",
         UTIME => "utime",
         ACCESS => "access",
         KILL => "kill",
+        SIGQUEUE => "sigqueue",
         RENAME => "rename",
         MKDIR => "mkdir",
         RMDIR => "rmdir",
         PIPE => "pipe",
+        PIPE2 => "pipe2",
         TIMES => "times",
         GETGID => "getgid",
         GETEUID => "geteuid",
         FCNTL => "fcntl",
+        FLOCK => "flock",
         GETEGID => "getegid",
         UMOUNT => "umount",
         IOCTL => "ioctl",
@@ -311,8 +527,11 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         SETPGID => "setpgid",
         GETPPID => "getppid",
         GETPGRP => "getpgrp",
+        SETSID => "setsid",
         SIGACTION => "sigaction",
         SIGSUSPEND => "sigsuspend",
+        SIGPENDING => "sigpending",
+        SIGTIMEDWAIT => "sigtimedwait",
         GETGROUPS => "getgroups",
         SETGROUPS => "setgroups",
         LSTAT => "lstat",
@@ -324,16 +543,61 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         GETTIMEOFDAY => "gettimeofday",
         SOCKETCALL => "socketcall",
         WAIT4 => "wait4",
+        WAITID => "waitid",
         CLONE => "clone",
         MPROTECT => "mprotect",
         SIGPROCMASK => "sigprocmask",
         GETPGID => "getpgid",
+        GETSID => "getsid",
+        FCHDIR => "fchdir",
         CHOWN => "chown",
         FCHOWN => "fchown",
         NANOSLEEP => "nanosleep",
         GETCWD => "getcwd",
         SIGRETURN => "sigreturn",
+        RT_SIGRETURN => "rt_sigreturn",
+        SIGALTSTACK => "sigaltstack",
         SHUTDOWN => "shutdown",
+        OPENAT => "openat",
+        MKDIRAT => "mkdirat",
+        FSTATAT => "fstatat",
+        UNLINKAT => "unlinkat",
+        RENAMEAT => "renameat",
+        READLINKAT => "readlinkat",
+        PREAD64 => "pread64",
+        PWRITE64 => "pwrite64",
+        READV => "readv",
+        WRITEV => "writev",
+        PREADV => "preadv",
+        PWRITEV => "pwritev",
+        TRUNCATE64 => "truncate64",
+        FTRUNCATE64 => "ftruncate64",
+        SYNC => "sync",
+        SYNCFS => "syncfs",
+        FSYNC => "fsync",
+        FDATASYNC => "fdatasync",
+        GETRLIMIT => "getrlimit",
+        SETRLIMIT => "setrlimit",
+        GETRUSAGE => "getrusage",
+        PERSONALITY => "personality",
+        NICE => "nice",
+        GETPRIORITY => "getpriority",
+        SETPRIORITY => "setpriority",
+        SCHED_SETPARAM => "sched_setparam",
+        SCHED_GETPARAM => "sched_getparam",
+        SCHED_SETSCHEDULER => "sched_setscheduler",
+        SCHED_GETSCHEDULER => "sched_getscheduler",
+        SCHED_YIELD => "sched_yield",
+        SCHED_GET_PRIORITY_MAX => "sched_get_priority_max",
+        SCHED_GET_PRIORITY_MIN => "sched_get_priority_min",
+        SETRESUID => "setresuid",
+        GETRESUID => "getresuid",
+        SETRESGID => "setresgid",
+        GETRESGID => "getresgid",
+        INOTIFY_INIT => "inotify_init",
+        INOTIFY_INIT1 => "inotify_init1",
+        INOTIFY_ADD_WATCH => "inotify_add_watch",
+        INOTIFY_RM_WATCH => "inotify_rm_watch",
         TEST => "test",
         STACK_OVERFLOW => "stack_overflow",
         EXIT_QEMU => "exit_qemu",
@@ -344,7 +608,6 @@ pub fn trace_syscall_result(cpu_state: *mut CpuState, result: SysResult<u32>) {
         SETEGID => "setegid",
         SETEUID => "seteuid",
         ISATTY => "isatty",
-        OPENDIR => "opendir",
         INSMOD => "insmod",
         RMMOD => "rmmod",
         LSMOD => "lsmod",
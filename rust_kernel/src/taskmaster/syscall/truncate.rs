@@ -0,0 +1,64 @@
+//! sys_truncate64() and sys_ftruncate64()
+
+use super::SysResult;
+
+use super::scheduler::SCHEDULER;
+use super::vfs::{Path, VFS};
+use super::Fd;
+
+use core::convert::TryFrom;
+use libc_binding::{c_char, off_t};
+
+/// The truncate() function shall cause the regular file named by path
+/// to have a size which shall be equal to length bytes. If the file
+/// previously was larger than length, the extra data is discarded. If
+/// the file was previously shorter than length, its size is increased,
+/// and the extended area appears as if it were zero-filled.
+///
+/// [EACCES]
+///     Write permission is denied for the file.
+/// [EINVAL]
+///     The length argument was less than 0.
+/// [EISDIR]
+///     The named file is a directory.
+pub fn sys_truncate64(path: *const c_char, length: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let safe_path = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            v.make_checked_str(path)?
+        };
+
+        let tg = scheduler.current_thread_group_mut();
+        let creds = &tg.credentials;
+        let cwd = &tg.cwd;
+        let path = Path::try_from(safe_path)?;
+
+        VFS.lock().truncate(cwd, creds, path, length)?;
+        Ok(0)
+    })
+}
+
+/// The ftruncate() function shall be equivalent to truncate(), on the
+/// regular file open by the file descriptor `fd`.
+///
+/// [EBADF]
+///     The fildes argument is not a file descriptor open for writing.
+/// [EINVAL]
+///     The fildes argument references a file that was opened without
+///     write permission, or that is not a regular file.
+pub fn sys_ftruncate64(fd: Fd, length: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface
+            .ftruncate(fd, length)
+    })
+}
@@ -0,0 +1,335 @@
+//! sys_readv(), sys_writev(), sys_preadv() and sys_pwritev()
+
+use super::SysResult;
+
+use super::scheduler::auto_preempt;
+use super::scheduler::SCHEDULER;
+use super::thread::WaitingState;
+use super::Fd;
+use super::IpcResult;
+
+use crate::memory::AddressSpace;
+use alloc::vec::Vec;
+use fallible_collections::FallibleVec;
+use libc_binding::{iovec, off_t, Errno, IOV_MAX, PIPE_BUF};
+use terminal::TtyAccess;
+
+/// Check the iovec array and its total length, which must fit in a ssize_t
+fn checked_iovecs<'unbound>(
+    v: &AddressSpace,
+    iov: *const iovec,
+    iovcnt: i32,
+) -> SysResult<&'unbound [iovec]> {
+    if iovcnt < 0 || iovcnt as u32 > IOV_MAX {
+        return Err(Errno::EINVAL);
+    }
+    let iovecs = v.make_checked_slice(iov, iovcnt as usize)?;
+    iovecs
+        .iter()
+        .try_fold(0_usize, |total, elem| {
+            total.checked_add(elem.iov_len as usize)
+        })
+        .filter(|&total| total <= i32::max_value() as usize)
+        .ok_or(Errno::EINVAL)?;
+    Ok(iovecs)
+}
+
+/// Check every buffer of the iovec array for an input operation
+fn checked_mut_buffers<'unbound>(
+    v: &AddressSpace,
+    iov: *const iovec,
+    iovcnt: i32,
+) -> SysResult<Vec<&'unbound mut [u8]>> {
+    let iovecs = checked_iovecs(v, iov, iovcnt)?;
+    let mut buffers = Vec::new();
+    for elem in iovecs {
+        buffers
+            .try_push(v.make_checked_mut_slice(elem.iov_base as *mut u8, elem.iov_len as usize)?)?;
+    }
+    Ok(buffers)
+}
+
+/// Check every buffer of the iovec array for an output operation
+fn checked_buffers<'unbound>(
+    v: &AddressSpace,
+    iov: *const iovec,
+    iovcnt: i32,
+) -> SysResult<Vec<&'unbound [u8]>> {
+    let iovecs = checked_iovecs(v, iov, iovcnt)?;
+    let mut buffers = Vec::new();
+    for elem in iovecs {
+        buffers
+            .try_push(v.make_checked_slice(elem.iov_base as *const u8, elem.iov_len as usize)?)?;
+    }
+    Ok(buffers)
+}
+
+/// The readv() function shall be equivalent to read(), except that it
+/// shall place the input data into the iovcnt buffers specified by
+/// the members of the iov array. The readv() function shall always
+/// fill an area completely before proceeding to the next.
+///
+/// As read(), readv() only blocks while nothing was read.
+///
+/// [EINVAL]
+///     The sum of the iov_len values in the iov array overflowed a
+///     ssize_t, or the iovcnt argument was less than or equal to 0,
+///     or greater than {IOV_MAX}.
+pub fn sys_readv(fd: Fd, iov: *const iovec, iovcnt: i32) -> SysResult<u32> {
+    'retry: loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+
+            // The buffers are checked again after each wake up
+            let buffers = {
+                let v = scheduler
+                    .current_thread()
+                    .unwrap_process()
+                    .get_virtual_allocator();
+
+                checked_mut_buffers(&v, iov, iovcnt)?
+            };
+
+            if scheduler.current_thread_tty_job_control(fd, TtyAccess::Read)? {
+                // Stopped by SIGTTIN until the process group is continued
+                let _ignored_result = auto_preempt();
+                continue;
+            }
+
+            let fd_interface = &mut scheduler
+                .current_thread_group_running_mut()
+                .file_descriptor_interface;
+
+            let mut readen_bytes = 0;
+            for buf in buffers.into_iter().filter(|buf| !buf.is_empty()) {
+                let len = buf.len();
+                match fd_interface.read(fd, buf) {
+                    Err(e) if readen_bytes == 0 => return Err(e),
+                    Err(_) => break,
+                    Ok(IpcResult::Done(res)) => {
+                        readen_bytes += res;
+                        if (res as usize) < len {
+                            break;
+                        }
+                    }
+                    Ok(IpcResult::Wait(res, file_op_uid)) => {
+                        readen_bytes += res;
+                        if readen_bytes != 0 {
+                            break;
+                        }
+                        scheduler
+                            .current_thread_mut()
+                            .set_waiting(WaitingState::Read(file_op_uid));
+                        let _ret = auto_preempt()?;
+                        continue 'retry;
+                    }
+                }
+            }
+            return Ok(readen_bytes);
+        })
+    }
+}
+
+/// The writev() function shall be equivalent to write(), except that
+/// it shall gather output data from the iovcnt buffers specified by
+/// the members of the iov array. The writev() function shall always
+/// write a complete area before proceeding to the next.
+///
+/// A writev() of at most {PIPE_BUF} bytes is gathered before being
+/// written, so it is atomic on pipes and FIFOs as a write() would be.
+///
+/// [EINVAL]
+///     The sum of the iov_len values in the iov array overflowed a
+///     ssize_t, or the iovcnt argument was less than or equal to 0,
+///     or greater than {IOV_MAX}.
+pub fn sys_writev(fd: Fd, iov: *const iovec, iovcnt: i32) -> SysResult<u32> {
+    let gathered = unpreemptible_context!({
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        let buffers = checked_buffers(&v, iov, iovcnt)?;
+        let total: usize = buffers.iter().map(|buf| buf.len()).sum();
+        if total <= PIPE_BUF as usize {
+            let mut gathered = Vec::new();
+            gathered.try_reserve_exact(total)?;
+            for buf in buffers {
+                gathered.extend_from_slice(buf);
+            }
+            Some(gathered)
+        } else {
+            None
+        }
+    });
+    match gathered {
+        Some(gathered) => write_all(fd, &gathered),
+        None => write_user_buffers(fd, iov, iovcnt),
+    }
+}
+
+/// Write a kernel gathered buffer, as sys_write() does
+fn write_all(fd: Fd, buf: &[u8]) -> SysResult<u32> {
+    let mut written_bytes = 0;
+    loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+
+            if scheduler.current_thread_tty_job_control(fd, TtyAccess::Write)? {
+                // Stopped by SIGTTOU until the process group is continued
+                let _ignored_result = auto_preempt();
+                continue;
+            }
+
+            let fd_interface = &mut scheduler
+                .current_thread_group_running_mut()
+                .file_descriptor_interface;
+
+            match fd_interface.write(fd, &buf[written_bytes as usize..])? {
+                IpcResult::Wait(res, file_op_uid) => {
+                    written_bytes += res;
+                    scheduler
+                        .current_thread_mut()
+                        .set_waiting(WaitingState::Write(file_op_uid));
+                    let _ret = auto_preempt()?;
+                }
+                IpcResult::Done(res) => return Ok(written_bytes + res),
+            }
+        })
+    }
+}
+
+/// Write the user buffers one after the other, they are checked again
+/// after each wake up
+fn write_user_buffers(fd: Fd, iov: *const iovec, iovcnt: i32) -> SysResult<u32> {
+    let mut written_bytes = 0;
+    'retry: loop {
+        unpreemptible_context!({
+            let mut scheduler = SCHEDULER.lock();
+
+            let buffers = {
+                let v = scheduler
+                    .current_thread()
+                    .unwrap_process()
+                    .get_virtual_allocator();
+
+                checked_buffers(&v, iov, iovcnt)?
+            };
+
+            if scheduler.current_thread_tty_job_control(fd, TtyAccess::Write)? {
+                // Stopped by SIGTTOU until the process group is continued
+                let _ignored_result = auto_preempt();
+                continue;
+            }
+
+            let fd_interface = &mut scheduler
+                .current_thread_group_running_mut()
+                .file_descriptor_interface;
+
+            let mut skip = written_bytes as usize;
+            for buf in buffers {
+                if skip >= buf.len() {
+                    skip -= buf.len();
+                    continue;
+                }
+                let buf = &buf[skip..];
+                skip = 0;
+                match fd_interface.write(fd, buf) {
+                    Err(e) if written_bytes == 0 => return Err(e),
+                    Err(_) => break,
+                    Ok(IpcResult::Done(res)) => {
+                        written_bytes += res;
+                        if (res as usize) < buf.len() {
+                            break;
+                        }
+                    }
+                    Ok(IpcResult::Wait(res, file_op_uid)) => {
+                        written_bytes += res;
+                        scheduler
+                            .current_thread_mut()
+                            .set_waiting(WaitingState::Write(file_op_uid));
+                        let _ret = auto_preempt()?;
+                        continue 'retry;
+                    }
+                }
+            }
+            return Ok(written_bytes);
+        })
+    }
+}
+
+/// The preadv() function combines the functionality of readv() and
+/// pread(): the buffers are filled from the file at `offset`, and the
+/// file offset is not used nor changed.
+pub fn sys_preadv(fd: Fd, iov: *const iovec, iovcnt: i32, mut offset: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let buffers = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            checked_mut_buffers(&v, iov, iovcnt)?
+        };
+
+        let fd_interface = &mut scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface;
+
+        let mut readen_bytes = 0;
+        for buf in buffers {
+            let len = buf.len();
+            let res = match fd_interface.pread(fd, buf, offset) {
+                Err(e) if readen_bytes == 0 => return Err(e),
+                Err(_) => break,
+                Ok(res) => res,
+            };
+            readen_bytes += res;
+            offset += res as off_t;
+            if (res as usize) < len {
+                break;
+            }
+        }
+        Ok(readen_bytes)
+    })
+}
+
+/// The pwritev() function combines the functionality of writev() and
+/// pwrite(): the buffers are written into the file at `offset`, and
+/// the file offset is not used nor changed.
+pub fn sys_pwritev(fd: Fd, iov: *const iovec, iovcnt: i32, mut offset: off_t) -> SysResult<u32> {
+    unpreemptible_context!({
+        let mut scheduler = SCHEDULER.lock();
+
+        let buffers = {
+            let v = scheduler
+                .current_thread()
+                .unwrap_process()
+                .get_virtual_allocator();
+
+            checked_buffers(&v, iov, iovcnt)?
+        };
+
+        let fd_interface = &mut scheduler
+            .current_thread_group_running_mut()
+            .file_descriptor_interface;
+
+        let mut written_bytes = 0;
+        for buf in buffers {
+            let res = match fd_interface.pwrite(fd, buf, offset) {
+                Err(e) if written_bytes == 0 => return Err(e),
+                Err(_) => break,
+                Ok(res) => res,
+            };
+            written_bytes += res;
+            offset += res as off_t;
+            if (res as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(written_bytes)
+    })
+}
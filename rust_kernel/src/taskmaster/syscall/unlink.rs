@@ -3,12 +3,21 @@ use super::scheduler::SCHEDULER;
 use super::vfs::{Path, VFS};
 use super::SysResult;
 use core::convert::TryFrom;
-use libc_binding::c_char;
+use libc_binding::{c_char, Errno, AT_FDCWD, AT_REMOVEDIR};
 
 pub fn sys_unlink(path: *const c_char) -> SysResult<u32> {
+    sys_unlinkat(AT_FDCWD, path, 0)
+}
+
+/// Delete a name, a relative `path` is resolved from the directory
+/// open on `dirfd`. With AT_REMOVEDIR, it acts like rmdir()
+pub fn sys_unlinkat(dirfd: i32, path: *const c_char, flags: u32) -> SysResult<u32> {
     unpreemptible_context!({
         let mut scheduler = SCHEDULER.lock();
 
+        if flags & !AT_REMOVEDIR != 0 {
+            return Err(Errno::EINVAL);
+        }
         let safe_path = {
             let v = scheduler
                 .current_thread_mut()
@@ -19,10 +28,14 @@ pub fn sys_unlink(path: *const c_char) -> SysResult<u32> {
         };
         let tg = scheduler.current_thread_group();
         let creds = &tg.credentials;
-        let cwd = &tg.cwd;
+        let cwd = &tg.get_dirfd_path(dirfd)?;
 
         let path = Path::try_from(safe_path)?;
-        VFS.lock().unlink(cwd, creds, path)?;
+        if flags & AT_REMOVEDIR != 0 {
+            VFS.lock().rmdir(cwd, creds, path)?;
+        } else {
+            VFS.lock().unlink(cwd, creds, path)?;
+        }
         Ok(0)
     })
 }
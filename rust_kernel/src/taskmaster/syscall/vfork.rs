@@ -0,0 +1,22 @@
+use super::clone::{sys_clone, CloneFlags};
+use super::SysResult;
+use core::ffi::c_void;
+
+/// The vfork() function shall be equivalent to fork(), except that
+/// the behavior is undefined if the process created by vfork() either
+/// modifies any data other than a variable of type pid_t used to store
+/// the return value from vfork(), or returns from the function in
+/// which vfork() was called, or calls any other function before
+/// successfully calling _exit() or one of the exec family of
+/// functions.
+///
+/// The child shares the address space of its parent, which is not
+/// copied. The calling thread is suspended until the child calls
+/// execve or exits.
+pub fn sys_vfork(kernel_esp: u32) -> SysResult<u32> {
+    sys_clone(
+        kernel_esp,
+        0 as *const c_void,
+        (CloneFlags::VM | CloneFlags::VFORK).bits(),
+    )
+}
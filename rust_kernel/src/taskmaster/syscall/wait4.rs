@@ -1,5 +1,6 @@
 //! wait4 (wait, waitpid, wait3) implementations
 
+use super::rusage::ResourceUsage;
 use super::scheduler::{auto_preempt, unpreemptible};
 use super::scheduler::{Scheduler, SCHEDULER};
 use super::thread::{AutoPreemptReturnValue, WaitingState};
@@ -7,7 +8,7 @@ use super::thread_group::{JobState, Status};
 use super::SysResult;
 use bitflags::bitflags;
 
-use libc_binding::{rusage, Errno, Pid};
+use libc_binding::{rusage, uid_t, Errno, Pid};
 
 /// The wait() and waitpid() functions shall obtain status information
 /// (see Status Information) pertaining to one of the caller's child
@@ -196,13 +197,12 @@ use libc_binding::{rusage, Errno, Pid};
 ///     by stat_loc is undefined.  [EINVAL] The options argument is
 ///     not valid.
 fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysResult<u32> {
-    let mut scheduler = SCHEDULER.lock();
-
     // WARNING: In a multithread context. The pointers must be verified just before writing on it !
-    let (wstatus, rusage) = {
+    let (wstatus, rusage, pgid) = {
+        let scheduler = SCHEDULER.lock();
         let v = scheduler
-            .current_thread_mut()
-            .unwrap_process_mut()
+            .current_thread()
+            .unwrap_process()
             .get_virtual_allocator();
 
         // If wstatus is not NULL, wait() and waitpid() store status information in the int to which it points.
@@ -217,168 +217,34 @@ fn wait4(pid: i32, wstatus: *mut i32, options: u32, rusage: *mut rusage) -> SysR
         } else {
             Some(v.make_checked_ref_mut::<rusage>(rusage)?)
         };
-        (safe_wstatus, safe_rusage)
-    };
-
-    // WIFEXITED(wstatus)
-    // returns true if the child terminated normally, that is, by calling exit(3) or _exit(2), or by returning from main().
-
-    // WEXITSTATUS(wstatus)
-    // returns  the exit status of the child. This consists of the least significant 8 bits of the status argument that
-    // the child specified in a call to exit(3) or _exit(2) or as the argument for a
-    // return statement in main().  This macro should be employed only if WIFEXITED returned true.
-
-    // the two next macro are signal dedicated ... WIFSIGNALED(wstatus) && WTERMSIG(wstatus)
-
-    // Return EINVAL for any unknown option
-    let options = WaitOption::from_bits(options).ok_or(Errno::EINVAL)?;
-
-    let thread_group = scheduler.current_thread_group();
-
-    // The pid argument specifies a set of child processes for which
-    // status is requested. The waitpid() function shall only return the
-    // status of a child process from this set:
-    // child_pid is a Option. Some(child_pid) if some child is dead, None
-    // otherwise
-    let child_pid = match pid {
-        // If pid is equal to (pid_t)-1, status is requested for any
-        // child process. In this respect, waitpid() is then
-        // equivalent to wait().
-        -1 => {
-            // Check if at leat one child exists
-            if thread_group.unwrap_running().child.len() == 0 {
-                return Err(Errno::ECHILD);
-            }
-            // Check is the at least one child is a already a zombie -> Return immediatly child PID
-            thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&current_pid| scheduler.has_status_available(*current_pid, options))
-        }
-        // If pid is less than (pid_t)-1, status is requested for any
-        // child process whose process group ID is equal to the
-        // absolute value of pid.
-        mut pid if (pid < 0 || pid == 0) => {
-            // If pid is 0, status is requested for any child process
-            // whose process group ID is equal to that of the calling
-            // process.
-            if pid == 0 {
-                pid = thread_group.pgid;
-            }
-            let candidate_number = thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .map(|&current_pid| {
-                    scheduler
-                        .get_thread_group(current_pid)
-                        .expect("Pid must be here")
-                })
-                .filter(|tg| tg.pgid == -pid)
-                .count();
-
-            if candidate_number == 0 {
-                return Err(Errno::ECHILD);
-            }
-
-            thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&current_pid| scheduler.has_status_available(*current_pid, options))
-        }
-        // If pid is greater than 0, it specifies the process ID of a
-        // single child process for which status is requested.
-        pid if pid > 0 => {
-            // Check if specified child exists
-            if let Some(elem) = thread_group
-                .unwrap_running()
-                .child
-                .iter()
-                .find(|&&current_pid| current_pid == pid)
-            {
-                if scheduler.has_status_available(*elem, options) {
-                    Some(elem)
-                } else {
-                    None
-                }
-            } else {
-                return Err(Errno::ECHILD);
-            }
-        }
-        _ => unreachable!(),
+        (
+            safe_wstatus,
+            safe_rusage,
+            scheduler.current_thread_group().pgid,
+        )
     };
 
-    match child_pid {
-        Some(&dead_pid) => {
-            let tg = scheduler
-                .get_thread_group_mut(dead_pid)
-                .expect("Pid must be here");
+    // Return EINVAL for any unknown option, the options proper to
+    // waitid() included
+    let options = WaitOption::from_bits(options)
+        .filter(|options| !options.intersects(WaitOption::WEXITED | WaitOption::WNOWAIT))
+        .ok_or(Errno::EINVAL)?;
 
-            let status = match tg.get_death_status() {
-                Some(status) => {
-                    if let Some(rusage) = rusage {
-                        *rusage = scheduler
-                            .get_thread_group(dead_pid)
-                            .expect("Woot ?")
-                            .process_duration
-                            .into();
-                    }
-                    scheduler.current_thread_group_mut().remove_child(dead_pid);
-                    scheduler.remove_thread_group(dead_pid);
-                    status
-                }
-                None => Status::from(tg.job.consume_last_event().expect("no status")),
-            };
+    // The terminated children are always reported
+    match wait_child(
+        WaitSet::from_waitpid(pid, pgid),
+        options | WaitOption::WEXITED,
+    )? {
+        Some(event) => {
             if let Some(wstatus) = wstatus {
-                *wstatus = status.into()
+                *wstatus = event.status.into();
             }
-            Ok(dead_pid as u32)
-        }
-        None if options.contains(WaitOption::WNOHANG) => {
-            return Ok(0);
-        }
-        None => {
-            // Set process as Waiting for ChildDeath. set the PID option inside
-            let pgid = thread_group.pgid;
-            scheduler
-                .current_thread_mut()
-                .set_waiting(WaitingState::Waitpid { pid, pgid, options });
-
-            let ret = auto_preempt()?;
-
-            // Re-Lock immediatly critical ressources (auto_preempt unlocked all)
-            unpreemptible();
-            let mut scheduler = SCHEDULER.lock();
-
-            let child_pid = match ret {
-                AutoPreemptReturnValue::Wait {
-                    dead_process_pid,
-                    status,
-                } => {
-                    if let Some(rusage) = rusage {
-                        *rusage = scheduler
-                            .get_thread_group(dead_process_pid)
-                            .expect("Woot ?")
-                            .process_duration
-                            .into();
-                    }
-                    if status.is_terminated() {
-                        let thread_group = scheduler.current_thread_group_mut();
-                        thread_group.remove_child(dead_process_pid);
-                        scheduler.remove_thread_group(dead_process_pid);
-                    }
-                    // Set wstatus pointer is not null by reading y
-                    if let Some(wstatus) = wstatus {
-                        *wstatus = status.into();
-                    }
-                    dead_process_pid
-                }
-                _ => panic!("WTF"),
-            };
-            Ok(child_pid as u32)
+            if let Some(rusage) = rusage {
+                *rusage = event.usage.into();
+            }
+            Ok(event.pid as u32)
         }
+        None => Ok(0),
     }
 }
 
@@ -396,16 +262,164 @@ bitflags! {
         const WUNTRACED = libc_binding::WUNTRACED;
         const WCONTINUED = libc_binding::WCONTINUED;
         const WNOHANG = libc_binding::WNOHANG;
+        const WEXITED = libc_binding::WEXITED;
+        const WNOWAIT = libc_binding::WNOWAIT;
+    }
+}
+
+/// The children a wait is about
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WaitSet {
+    /// Any child process
+    Any,
+    /// The children in a process group
+    Group(Pid),
+    /// A single child process
+    Child(Pid),
+}
+
+impl WaitSet {
+    /// The set described by the pid argument of waitpid(), for a
+    /// caller in the process group `pgid`
+    fn from_waitpid(pid: Pid, pgid: Pid) -> Self {
+        match pid {
+            // If pid is equal to (pid_t)-1, status is requested for any
+            // child process. In this respect, waitpid() is then
+            // equivalent to wait().
+            -1 => Self::Any,
+            // If pid is 0, status is requested for any child process
+            // whose process group ID is equal to that of the calling
+            // process.
+            0 => Self::Group(pgid),
+            // If pid is less than (pid_t)-1, status is requested for any
+            // child process whose process group ID is equal to the
+            // absolute value of pid.
+            pid if pid < 0 => Self::Group(-pid),
+            // If pid is greater than 0, it specifies the process ID of a
+            // single child process for which status is requested.
+            pid => Self::Child(pid),
+        }
+    }
+
+    /// Check if the child `pid` of the process group `pgid` is in the set
+    pub fn contains(&self, pid: Pid, pgid: Pid) -> bool {
+        match *self {
+            Self::Any => true,
+            Self::Group(group) => pgid == group,
+            Self::Child(child) => pid == child,
+        }
+    }
+}
+
+/// A change of state of a child, reported by a wait
+pub struct ChildEvent {
+    pub pid: Pid,
+    pub status: Status,
+    /// The real user ID of the child
+    pub uid: uid_t,
+    /// The resources used by the child and by the children it reaped
+    pub usage: ResourceUsage,
+}
+
+/// Wait for a child of `set` to change state as requested by
+/// `options`. None is returned if WNOHANG is set and no child has
+/// changed state.
+pub fn wait_child(set: WaitSet, options: WaitOption) -> SysResult<Option<ChildEvent>> {
+    let mut scheduler = SCHEDULER.lock();
+
+    let mut children = scheduler
+        .current_thread_group()
+        .unwrap_running()
+        .child
+        .iter()
+        .filter(|&&child| {
+            let thread_group = scheduler.get_thread_group(child).expect("Pid must be here");
+            set.contains(child, thread_group.pgid)
+        })
+        .peekable();
+
+    // The set must contain at least one child
+    if children.peek().is_none() {
+        return Err(Errno::ECHILD);
+    }
+    // Check if a child already changed state -> Return immediatly
+    let ready = children
+        .find(|&&child| scheduler.has_status_available(child, options))
+        .copied();
+
+    if let Some(child) = ready {
+        let thread_group = scheduler
+            .get_thread_group_mut(child)
+            .expect("Pid must be here");
+        let status = match thread_group.get_death_status() {
+            Some(status) => status,
+            None if options.contains(WaitOption::WNOWAIT) => {
+                Status::from(thread_group.job.get_last_event().expect("no status"))
+            }
+            None => Status::from(thread_group.job.consume_last_event().expect("no status")),
+        };
+        return Ok(Some(scheduler.collect_child_event(child, status, options)));
+    }
+    if options.contains(WaitOption::WNOHANG) {
+        return Ok(None);
+    }
+
+    // Set process as Waiting for a child event
+    scheduler
+        .current_thread_mut()
+        .set_waiting(WaitingState::Waitpid { set, options });
+
+    let ret = auto_preempt()?;
+
+    // Re-Lock immediatly critical ressources (auto_preempt unlocked all)
+    unpreemptible();
+    let mut scheduler = SCHEDULER.lock();
+
+    match ret {
+        AutoPreemptReturnValue::Wait {
+            dead_process_pid,
+            status,
+        } => Ok(Some(scheduler.collect_child_event(
+            dead_process_pid,
+            status,
+            options,
+        ))),
+        _ => panic!("WTF"),
     }
 }
 
 impl Scheduler {
     fn has_status_available(&self, pid: Pid, options: WaitOption) -> bool {
         let thread_group = self.get_thread_group(pid).expect("Pid must be here");
-        thread_group.is_zombie()
-            || (options.contains(WaitOption::WUNTRACED)
-                && thread_group.job.get_last_event() == Some(JobState::Stopped))
-            || (options.contains(WaitOption::WUNTRACED)
+        if thread_group.is_zombie() {
+            return options.contains(WaitOption::WEXITED);
+        }
+        (options.contains(WaitOption::WUNTRACED)
+            && thread_group.job.get_last_event() == Some(JobState::Stopped))
+            || (options.contains(WaitOption::WCONTINUED)
                 && thread_group.job.get_last_event() == Some(JobState::Continued))
     }
+
+    /// Describe the change of state of the child `pid`. A terminated
+    /// child is reaped, its usage added to the one of its parent's
+    /// children, unless WNOWAIT is set.
+    fn collect_child_event(&mut self, pid: Pid, status: Status, options: WaitOption) -> ChildEvent {
+        let child = self.get_thread_group(pid).expect("Pid must be here");
+        let mut usage = child.usage;
+        usage += child.children_usage;
+        let event = ChildEvent {
+            pid,
+            status,
+            uid: child.credentials.uid,
+            usage,
+        };
+
+        if status.is_terminated() && !options.contains(WaitOption::WNOWAIT) {
+            let thread_group = self.current_thread_group_mut();
+            thread_group.children_usage += usage;
+            thread_group.remove_child(pid);
+            self.remove_thread_group(pid);
+        }
+        event
+    }
 }
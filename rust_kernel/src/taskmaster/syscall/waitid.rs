@@ -0,0 +1,111 @@
+//! waitid implementation
+
+use super::scheduler::SCHEDULER;
+use super::signal_interface::SigInfo;
+use super::wait4::{wait_child, WaitOption, WaitSet};
+use super::SysResult;
+
+use libc_binding::{idtype_P_ALL, idtype_P_PGID, idtype_P_PID, Errno, Signum};
+
+/// The waitid() function shall obtain status information (see Status
+/// Information) pertaining to termination, stop, and/or continue
+/// events in one of the caller's child processes.
+///
+/// The idtype and id arguments are used to specify which children
+/// waitid() waits for.
+///
+/// If idtype is P_PID, waitid() shall wait for the child with a
+/// process ID equal to (pid_t)id.
+///
+/// If idtype is P_PGID, waitid() shall wait for any child with a
+/// process group ID equal to (pid_t)id. An id of 0 designates the
+/// process group of the caller.
+///
+/// If idtype is P_ALL, waitid() shall wait for any children and id
+/// is ignored.
+///
+/// The options argument is used to specify which state changes
+/// waitid() shall wait for. It is formed by OR'ing together the
+/// following flags:
+///
+/// WCONTINUED Status shall be returned for any child that was
+///     stopped and has been continued.
+/// WEXITED Wait for processes that have exited.
+/// WNOHANG Return immediately if there are no children to wait for.
+/// WNOWAIT Keep the process whose status is returned in infop in a
+///     waitable state. This shall not affect the state of the
+///     process; the process may be waited for again after this call
+///     completes.
+/// WSTOPPED Status shall be returned for any child that has stopped
+///     upon receipt of a signal.
+///
+/// Applications shall specify at least one of the flags WEXITED,
+/// WSTOPPED, or WCONTINUED to be OR'ed in with the options argument.
+///
+/// The infop argument shall point to a siginfo_t structure. If
+/// waitid() returns because a child process was found that satisfied
+/// the conditions indicated by the arguments idtype and options, then
+/// the structure pointed to by infop shall be filled in by the system
+/// with the status of the process; the si_signo member shall be set
+/// equal to SIGCHLD. If waitid() returns because WNOHANG was specified
+/// and status is not available for any process specified by idtype
+/// and id, then the si_signo and si_pid members of the structure
+/// pointed to by infop shall be set to zero.
+///
+/// If waitid() returns due to the change of state of one of its
+/// children, 0 shall be returned. Otherwise, -1 shall be returned and
+/// errno set to indicate the error.
+///
+/// [ECHILD]
+///     The calling process has no existing unwaited-for child
+///     processes.
+/// [EINTR]
+///     The waitid() function was interrupted by a signal.
+/// [EINVAL]
+///     An invalid value was specified for options, or idtype and id
+///     specify an invalid set of processes.
+fn waitid(idtype: u32, id: i32, infop: *mut SigInfo, options: u32) -> SysResult<u32> {
+    // WARNING: In a multithread context. The pointers must be verified just before writing on it !
+    let (infop, pgid) = {
+        let scheduler = SCHEDULER.lock();
+        let v = scheduler
+            .current_thread()
+            .unwrap_process()
+            .get_virtual_allocator();
+
+        let safe_infop = if infop.is_null() {
+            None
+        } else {
+            Some(v.make_checked_ref_mut(infop)?)
+        };
+        (safe_infop, scheduler.current_thread_group().pgid)
+    };
+
+    let options = WaitOption::from_bits(options).ok_or(Errno::EINVAL)?;
+    if !options.intersects(WaitOption::WEXITED | WaitOption::WUNTRACED | WaitOption::WCONTINUED) {
+        return Err(Errno::EINVAL);
+    }
+    let set = match idtype {
+        idtype_P_ALL => WaitSet::Any,
+        idtype_P_PGID if id == 0 => WaitSet::Group(pgid),
+        idtype_P_PGID if id > 0 => WaitSet::Group(id),
+        idtype_P_PID if id > 0 => WaitSet::Child(id),
+        _ => return Err(Errno::EINVAL),
+    };
+
+    let info = match wait_child(set, options)? {
+        Some(event) => SigInfo {
+            si_signo: Signum::SIGCHLD as i32,
+            ..SigInfo::child(event.pid, event.uid, event.status)
+        },
+        None => SigInfo::default(),
+    };
+    if let Some(infop) = infop {
+        *infop = info;
+    }
+    Ok(0)
+}
+
+pub fn sys_waitid(idtype: u32, id: i32, infop: *mut SigInfo, options: u32) -> SysResult<u32> {
+    unpreemptible_context!({ waitid(idtype, id, infop, options) })
+}
@@ -7,6 +7,8 @@ use super::scheduler::SCHEDULER;
 use super::thread::WaitingState;
 use super::IpcResult;
 
+use terminal::TtyAccess;
+
 /// Write something into a file descriptor
 pub fn sys_write(fd: i32, mut buf: *const u8, mut count: usize) -> SysResult<u32> {
     let mut written_bytes = 0;
@@ -24,6 +26,12 @@ pub fn sys_write(fd: i32, mut buf: *const u8, mut count: usize) -> SysResult<u32
                 v.make_checked_slice(buf, count)?
             };
 
+            if scheduler.current_thread_tty_job_control(fd as _, TtyAccess::Write)? {
+                // Stopped by SIGTTOU until the process group is continued
+                let _ignored_result = auto_preempt();
+                continue;
+            }
+
             let fd_interface = &mut scheduler
                 .current_thread_group_running_mut()
                 .file_descriptor_interface;
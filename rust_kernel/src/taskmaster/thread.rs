@@ -1,10 +1,11 @@
 //! This file contains definition of a task
 
 use super::process::{CpuState, UserProcess};
-use super::scheduler::Pid;
+use super::rusage::ResourceUsage;
+use super::scheduler::{Pid, SchedEntity};
 use super::signal_interface::SignalInterface;
 use super::syscall::clone::CloneFlags;
-use super::syscall::WaitOption;
+use super::syscall::{WaitOption, WaitSet};
 use super::thread_group::Status;
 use super::SysResult;
 
@@ -35,6 +36,10 @@ pub struct Thread {
     pub process_state: ProcessState,
     /// Signal Interface
     pub signal: SignalInterface,
+    /// Scheduling policy, priority and CPU time used
+    pub sched: SchedEntity,
+    /// Resources used by the thread, reported by RUSAGE_THREAD
+    pub usage: ResourceUsage,
     /// Return value for auto_preempt
     autopreempt_return_value: Box<SysResult<AutoPreemptReturnValue>>,
 }
@@ -44,6 +49,8 @@ impl Thread {
         Ok(Self {
             process_state,
             signal: SignalInterface::new(),
+            sched: SchedEntity::new(),
+            usage: ResourceUsage::default(),
             autopreempt_return_value: Box::try_new(Ok(Default::default()))?,
         })
     }
@@ -61,14 +68,21 @@ impl Thread {
         child_stack: *const c_void,
         flags: CloneFlags,
     ) -> SysResult<Self> {
+        let mut signal = self.signal.fork();
+        // A thread does not share the alternate stack of its creator
+        if flags.contains(CloneFlags::VM) {
+            signal.drop_alt_stack();
+        }
         Ok(Self {
-            signal: self.signal.fork(),
+            signal,
+            sched: self.sched.fork(),
             process_state: match &self.process_state {
                 ProcessState::Running(Some(p)) => {
                     ProcessState::Running(Some(p.sys_clone(kernel_esp, child_stack, flags)?))
                 }
                 _ => panic!("Non running process should not clone"),
             },
+            usage: ResourceUsage::default(),
             autopreempt_return_value: Box::try_new(Ok(Default::default()))?,
         })
     }
@@ -143,12 +157,8 @@ pub enum WaitingState {
     Sleeping(u32),
     /// The sys_pause command was invoqued, the process is waiting for a signal
     Pause,
-    /// The Process is looking for the death of his child
-    Waitpid {
-        pid: Pid,
-        pgid: Pid,
-        options: WaitOption,
-    },
+    /// The Process is looking for a change of state of a child in `set`
+    Waitpid { set: WaitSet, options: WaitOption },
     /// In Waiting to read
     Read(usize),
     /// In Waiting to write
@@ -159,6 +169,21 @@ pub enum WaitingState {
     Connect(usize),
     /// In waiting for a socket connection
     Accept(usize),
+    /// In waiting for a file lock
+    FileLock(usize),
+    /// The vfork() child with this pid runs in the address space of
+    /// the process until it calls execve or exits
+    Vfork(Pid),
+}
+
+impl WaitingState {
+    /// Can a signal to handle end the wait, the deadly ones always do
+    pub fn is_interruptible(&self) -> bool {
+        match self {
+            WaitingState::Vfork(_) => false,
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug)]
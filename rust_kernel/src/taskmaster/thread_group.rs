@@ -1,12 +1,12 @@
-use super::fd_interface::FileDescriptorInterface;
-use super::global_time::ProcessDuration;
+use super::fd_interface::{Fd, FileDescriptorInterface};
+use super::rusage::ResourceUsage;
 use super::scheduler::{Pid, Tid};
 use super::syscall::clone::CloneFlags;
 use super::thread::Thread;
 use super::SysResult;
 use libc_binding::{Amode, FileType, PermissionClass};
 
-use super::vfs::Path;
+use super::vfs::{Cwd, Path, VFS};
 
 use super::safe_ffi::CStringArray;
 
@@ -14,7 +14,10 @@ use alloc::collections::TryReserveError;
 use alloc::vec::Vec;
 use core::ffi::c_void;
 use fallible_collections::{btree::BTreeMap, TryClone};
-use libc_binding::{dev_t, gid_t, mode_t, uid_t, Signum};
+use libc_binding::{
+    dev_t, gid_t, mode_t, rlim_t, rlimit, uid_t, Errno, Signum, AT_FDCWD, RLIMIT_NOFILE,
+    RLIM_NLIMITS,
+};
 use try_clone_derive::TryClone;
 
 #[derive(Debug)]
@@ -80,24 +83,34 @@ impl ThreadGroupState {
 pub struct ThreadGroup {
     /// the identity(uid, gid, groups...)
     pub credentials: Credentials,
-    /// the current working directory of the process
-    pub cwd: Path,
+    /// the current working directory and the root directory of the
+    /// process, pinned in the dcache
+    pub cwd: Cwd,
+    /// Filled by chdir, used by /proc/[pid]/cwd in the procfs.
+    pub cwd_path: Path,
     /// all the thread in the thread group
     pub thread_group_state: ThreadGroupState,
     /// the process group id
     pub pgid: Pid,
+    /// the session id, the pid of the session leader
+    pub sid: Pid,
     /// Parent
     pub parent: Pid,
     /// the next availabel tid for a new thread
     next_tid: Tid,
     /// Current job status of a process
     pub job: Job,
-    /// Time the process has spend is user space and in kernel space
-    pub process_duration: ProcessDuration,
+    /// Resources used by all the threads of the process, the exited
+    /// ones included
+    pub usage: ResourceUsage,
+    /// Resources used by the reaped children of the process, and by
+    /// the children they reaped
+    pub children_usage: ResourceUsage,
     /// The umask of the process: The actived bits in it are disabled in all file creating operations.
     pub umask: mode_t,
 
-    /// The minor of the controlling terminal of the process.
+    /// The minor of the controlling terminal of the process, the one
+    /// of its session when it has not given it up.
     pub controlling_terminal: Option<dev_t>,
 
     /// Filled by execve, used by /proc/[pid]/environ in the procfs.
@@ -108,6 +121,50 @@ pub struct ThreadGroup {
 
     /// Filled by execve, used by /proc/[pid]/exe in the procfs.
     pub filename: Option<Path>,
+
+    /// The limits on the resources of the process
+    pub rlimits: ResourceLimits,
+
+    /// The execution domain of the process, set by personality(). Its
+    /// ADDR_NO_RANDOMIZE flag disables the address space layout
+    /// randomization of the next images.
+    pub personality: u32,
+}
+
+/// The soft and hard limits of each resource of a process, indexed by
+/// the RLIMIT_* values
+#[derive(Debug, Copy, Clone)]
+pub struct ResourceLimits([rlimit; RLIM_NLIMITS as usize]);
+
+impl ResourceLimits {
+    /// The value of a limit which is not enforced
+    pub const INFINITY: rlim_t = !0;
+
+    /// Get the limits of `resource`
+    pub fn get(&self, resource: u32) -> SysResult<rlimit> {
+        self.0.get(resource as usize).copied().ok_or(Errno::EINVAL)
+    }
+
+    /// Set the limits of `resource`
+    pub fn set(&mut self, resource: u32, limit: rlimit) -> SysResult<()> {
+        *self.0.get_mut(resource as usize).ok_or(Errno::EINVAL)? = limit;
+        Ok(())
+    }
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        let unlimited = rlimit {
+            rlim_cur: Self::INFINITY,
+            rlim_max: Self::INFINITY,
+        };
+        let mut limits = [unlimited; RLIM_NLIMITS as usize];
+        limits[RLIMIT_NOFILE as usize] = rlimit {
+            rlim_cur: FileDescriptorInterface::OPEN_MAX,
+            rlim_max: FileDescriptorInterface::OPEN_MAX_HARD,
+        };
+        Self(limits)
+    }
 }
 
 #[derive(Debug, TryClone)]
@@ -141,6 +198,13 @@ impl Credentials {
         self.euid == Self::ROOT.uid
     }
 
+    /// Checks if the effective IDs differ from the real ones, as after
+    /// the execution of a set-user-ID or set-group-ID program. Such a
+    /// process image should not trust its environment.
+    pub fn is_secure(&self) -> bool {
+        self.euid != self.uid || self.egid != self.gid
+    }
+
     /// Checks with the same semantics of `access(2)` whether access
     /// shall be granted for an file access permission type
     /// `access_type` on a file that has a FileType `filetype` for the
@@ -218,7 +282,8 @@ impl ThreadGroup {
         Ok(ThreadGroup {
             parent: father_pid,
             credentials: Credentials::ROOT,
-            cwd: Path::root(),
+            cwd: Cwd::new(),
+            cwd_path: Path::root(),
             thread_group_state: ThreadGroupState::Running(RunningThreadGroup {
                 all_thread: all_thread,
                 child: Vec::new(),
@@ -226,13 +291,18 @@ impl ThreadGroup {
             }),
             next_tid: 1,
             pgid,
+            // A process created from scratch leads its own session
+            sid: pgid,
             job: Job::new(),
-            process_duration: ProcessDuration::default(),
+            usage: ResourceUsage::default(),
+            children_usage: ResourceUsage::default(),
             umask: 0,
             controlling_terminal: None, // hum...
             environ: None,
             argv: None,
             filename: None,
+            rlimits: ResourceLimits::default(),
+            personality: 0,
         })
     }
 
@@ -250,6 +320,14 @@ impl ThreadGroup {
         self.get_death_status().is_some()
     }
 
+    /// Is the process traced. Process tracing is not supported yet, so
+    /// it never is, but execve already ignores the set-ID bits of a
+    /// traced process: a tracer could otherwise take over a privileged
+    /// image once tracing is added
+    pub fn is_traced(&self) -> bool {
+        false
+    }
+
     /// Clone a thread group
     /// the clone was called from thread father_tid
     pub fn sys_clone(
@@ -273,7 +351,8 @@ impl ThreadGroup {
         let child = Self {
             parent: father_pid,
             credentials: self.credentials.try_clone()?,
-            cwd: self.cwd.try_clone()?,
+            cwd: self.cwd,
+            cwd_path: self.cwd_path.try_clone()?,
             thread_group_state: ThreadGroupState::Running(RunningThreadGroup {
                 all_thread: all_thread,
                 child: Vec::new(),
@@ -283,16 +362,22 @@ impl ThreadGroup {
                     .try_clone()?,
             }),
             pgid: self.pgid,
+            sid: self.sid,
             next_tid: 1,
             job: Job::new(),
-            process_duration: ProcessDuration::default(),
+            usage: ResourceUsage::default(),
+            children_usage: ResourceUsage::default(),
             umask: 0,
             controlling_terminal: self.controlling_terminal,
             environ: None,
             argv: None,
             filename: None,
+            rlimits: self.rlimits,
+            personality: self.personality,
         };
 
+        // The child is complete, it is unpinned when it exits
+        VFS.lock().pin_cwd(&child.cwd)?;
         self.unwrap_running_mut().child.push(child_pid);
         Ok(child)
     }
@@ -343,6 +428,22 @@ impl ThreadGroup {
     pub fn unwrap_running_mut(&mut self) -> &mut RunningThreadGroup {
         self.thread_group_state.unwrap_running_mut()
     }
+
+    /// Get the directory from which the relative paths of the *at()
+    /// syscalls are resolved: the cwd for AT_FDCWD, else the
+    /// directory open on `dirfd`, with the root of the process
+    pub fn get_dirfd_path(&self, dirfd: i32) -> SysResult<Cwd> {
+        if dirfd == AT_FDCWD {
+            return Ok(self.cwd);
+        }
+        let file_operation = self
+            .unwrap_running()
+            .file_descriptor_interface
+            .get_file_descriptor(dirfd as Fd)?
+            .get_file_operation();
+        let mut file_operation = file_operation.lock();
+        Ok(self.cwd.with_dir(file_operation.get_directory_entry()?))
+    }
 }
 
 /// Global design of User Program Status
@@ -466,6 +567,10 @@ impl Job {
             false
         }
     }
+    pub fn is_stopped(&self) -> bool {
+        self.state == JobState::Stopped
+    }
+
     /// Usable method for waitpid for exemple
     pub fn consume_last_event(&mut self) -> Option<JobState> {
         self.last_event.take()
@@ -3,13 +3,14 @@ use super::kmodules::CURRENT_UNIX_TIME;
 use super::sync::SmartMutex;
 use super::thread_group::Credentials;
 use super::{IpcResult, SysResult};
+use crate::memory::file_mapping::PAGE_CACHE;
 
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::convert::TryInto;
 use core::sync::atomic::Ordering;
-use fallible_collections::{btree::BTreeMap, TryCollect};
+use fallible_collections::{btree::BTreeMap, FallibleVec, TryCollect};
 use lazy_static::lazy_static;
 use sync::DeadMutex;
 
@@ -25,6 +26,9 @@ pub use posix_consts::{NAME_MAX, PATH_MAX, SYMLOOP_MAX};
 
 pub use path::{Filename, Path};
 
+mod cwd;
+pub use cwd::Cwd;
+
 mod direntry;
 pub use direntry::{DirectoryEntry, DirectoryEntryBuilder, DirectoryEntryId};
 
@@ -35,14 +39,32 @@ pub use dcache::Dcache;
 mod inode;
 pub use inode::InodeId;
 use inode::{Inode, InodeData};
+
+mod directory;
+pub use directory::DirectoryFileOperation;
+use directory::{d_type, dirent64_len, write_dirent64, DirectoryCursor};
+
+mod acl;
+use acl::{CachedAcl, PosixAcl, ACL_ACCESS, ACL_DEFAULT};
 use libc_binding::OpenFlags;
 
+mod lock;
+use lock::FileLocks;
+pub use lock::{FileLock, LockOwner, LockResult, LockType, OFFSET_MAX};
+
+mod inotify;
+use inotify::Inotify;
+pub use inotify::InotifyFileOperation;
+
 use libc_binding::c_char;
-use libc_binding::dirent;
 use libc_binding::statfs;
 use libc_binding::Errno::*;
 use libc_binding::FileType;
-use libc_binding::{gid_t, stat, time_t, uid_t, utimbuf, Amode, Errno};
+use libc_binding::InotifyMask;
+use libc_binding::MountFlags;
+use libc_binding::DT_UNKNOWN;
+use libc_binding::{gid_t, off_t, stat, time_t, uid_t, utimbuf, Amode, Errno, Pid, XattrFlags};
+use libc_binding::{XATTR_NAME_MAX, XATTR_SIZE_MAX};
 
 pub mod init;
 pub use init::{init, VFS};
@@ -52,19 +74,37 @@ use filesystem::{DeadFileSystem, FileSystem, FileSystemId, FileSystemSource, Fil
 
 pub struct VirtualFileSystem {
     mounted_filesystems: BTreeMap<FileSystemId, MountedFileSystem>,
+    /// The mounts by the directory entry of their root. A filesystem
+    /// is mounted more than once when some of its directories or files
+    /// are bind mounted
+    mounts: BTreeMap<DirectoryEntryId, Mount>,
 
     // superblocks: Vec<Superblock>,
     inodes: BTreeMap<InodeId, Inode>,
     dcache: Dcache,
+    file_locks: FileLocks,
+    inotify: Inotify,
 }
 
 pub struct MountedFileSystem {
     source: FileSystemSource,
     fs_type: FileSystemType,
-    target: Path,
     fs: Arc<DeadMutex<dyn FileSystem>>,
 }
 
+/// A view of a mounted filesystem, its whole tree or, for a bind
+/// mount, one of its directories or files
+pub struct Mount {
+    fs_id: FileSystemId,
+    target: Path,
+    /// Only the per mount flags: MS_RDONLY, MS_NOSUID, MS_NODEV and
+    /// MS_NOEXEC
+    flags: MountFlags,
+    /// The number of files open for writing through the mount, which
+    /// cannot be made read-only while there are some
+    writers: usize,
+}
+
 use core::fmt::{self, Debug};
 
 impl Debug for VirtualFileSystem {
@@ -80,8 +120,11 @@ impl VirtualFileSystem {
     pub fn new() -> SysResult<VirtualFileSystem> {
         let mut new = Self {
             mounted_filesystems: BTreeMap::new(),
+            mounts: BTreeMap::new(),
             inodes: BTreeMap::new(),
             dcache: Dcache::new(),
+            file_locks: FileLocks::new(),
+            inotify: Inotify::new(),
         };
 
         let root_inode = Inode::root_inode()?;
@@ -92,8 +135,9 @@ impl VirtualFileSystem {
     }
     fn add_inode(&mut self, inode: Inode) -> SysResult<()> {
         if self.inodes.contains_key(&inode.get_id()) {
-            // if it is not from an hard link we panic
-            if inode.link_number == 1 {
+            // if it is not from an hard link or another mount of the
+            // filesystem we panic
+            if inode.link_number == 1 && !self.is_shared_filesystem(inode.get_id()) {
                 // panic!("inode already there {:?}", inode);
                 panic!("Inode already there");
             } else {
@@ -164,28 +208,37 @@ impl VirtualFileSystem {
         Ok(direntry)
     }
 
-    fn recursive_remove_dentries(&mut self, direntry_id: DirectoryEntryId) -> SysResult<()> {
+    /// Remove the entries below the directory `direntry_id`, but the
+    /// pinned ones and their parents, return whether one was kept
+    fn recursive_remove_dentries(&mut self, direntry_id: DirectoryEntryId) -> SysResult<bool> {
         let children: Vec<DirectoryEntryId> = self
             .iter_directory_entries(direntry_id)?
             .map(|entry| entry.id)
             .try_collect()?;
 
-        Ok(for child in children {
+        let mut kept = false;
+        for child in children {
             let entry = self
                 .dcache
                 .get_entry(&child)
                 .expect("There should be a child here");
 
             // let inode_id = entry.inode_id;
-            if entry.is_directory() {
-                self.recursive_remove_dentries(child)?;
+            let mut keep = entry.is_pinned();
+            if entry.is_directory() && self.recursive_remove_dentries(child)? {
+                keep = true;
+            }
+            if keep {
+                kept = true;
+                continue;
             }
             // This means that dynamic filesystems shall not support multiple hardlinks for now.
             // self.inodes.remove(&inode_id).ok_or(Errno::ENOENT)?;
             // eprintln!("Callling funlink.");
             self.funlink(child)?;
             // self.dcache.remove_entry(child)?;
-        })
+        }
+        Ok(kept)
     }
 
     /// construct the files in directory `direntry_id` in ram form the filesystem
@@ -207,6 +260,14 @@ impl VirtualFileSystem {
             .lookup_directory(inode_id.inode_number as u32)?;
 
         for (direntry, inode_data, driver) in iter {
+            // The pinned entries were kept
+            if self
+                .dcache
+                .children(direntry_id)?
+                .any(|child| child.filename == direntry.filename)
+            {
+                continue;
+            }
             let fs_entry = (direntry, inode_data, Some(driver));
             self.add_entry_from_filesystem(fs_cloned.clone(), Some(direntry_id), fs_entry)
                 .or_else(|e| {
@@ -221,6 +282,150 @@ impl VirtualFileSystem {
         Ok(())
     }
 
+    /// Bring the entries of the directory `direntry_id` up to date
+    /// with the filesystem, without touching the entries which did not
+    /// change. The other mounts of a filesystem share its inodes but
+    /// not its directory entries
+    fn refresh_directory(&mut self, direntry_id: DirectoryEntryId) -> SysResult<()> {
+        let inode_id = self.dcache.get_entry(&direntry_id)?.inode_id;
+        let fs = self.get_filesystem(inode_id).ok_or(Errno::EINVAL)?.clone();
+        let fs_entries = fs.lock().lookup_directory(inode_id.inode_number as u32)?;
+
+        // The entries which are not in the filesystem anymore, the
+        // ones which do not come from it, the mount points and the
+        // directories of the processes are kept
+        let stale: Vec<DirectoryEntryId> = self
+            .dcache
+            .children(direntry_id)?
+            .filter(|child| {
+                child.inode_id.filesystem_id == inode_id.filesystem_id
+                    && child.get_mountpoint_entry().is_none()
+                    && !fs_entries.iter().any(|(entry, _, _)| {
+                        entry.filename == child.filename && entry.inode_id == child.inode_id
+                    })
+            })
+            .map(|child| child.id)
+            .try_collect()?;
+        for child in stale {
+            if self.mounts.keys().any(|root| self.is_below(*root, child))
+                || self.is_pinned_below(child)
+            {
+                continue;
+            }
+            self.dcache
+                .get_entry_mut(&direntry_id)?
+                .remove_entry(child)?;
+            self.recursive_trash(child)?;
+        }
+
+        for fs_entry in fs_entries {
+            if self
+                .dcache
+                .children(direntry_id)?
+                .any(|child| child.filename == fs_entry.0.filename)
+            {
+                continue;
+            }
+            let (direntry, inode_data, driver) = fs_entry;
+            self.add_entry_from_filesystem(
+                fs.clone(),
+                Some(direntry_id),
+                (direntry, inode_data, Some(driver)),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Is the filesystem of `inode_id` seen through more than one tree
+    /// of directory entries. The root of a file bind mount has no
+    /// entries, so it does not count
+    fn is_shared_filesystem(&self, inode_id: InodeId) -> bool {
+        let fs_id = match inode_id.filesystem_id {
+            Some(fs_id) => fs_id,
+            None => return false,
+        };
+        self.mounts
+            .iter()
+            .filter(|(root, mount)| {
+                mount.fs_id == fs_id
+                    && self
+                        .dcache
+                        .get_entry(root)
+                        .map_or(false, |entry| entry.is_directory())
+            })
+            .count()
+            > 1
+    }
+
+    /// Get the root of the mount which the entry `entry_id` belongs to
+    fn get_mount_root(&self, mut entry_id: DirectoryEntryId) -> Option<DirectoryEntryId> {
+        loop {
+            if self.mounts.contains_key(&entry_id) {
+                return Some(entry_id);
+            }
+            let entry = self.dcache.get_entry(&entry_id).ok()?;
+            if entry.parent_id == entry_id {
+                return None;
+            }
+            entry_id = entry.parent_id;
+        }
+    }
+
+    /// Get the mount which the entry `entry_id` belongs to
+    fn get_mount(&self, entry_id: DirectoryEntryId) -> Option<&Mount> {
+        self.mounts.get(&self.get_mount_root(entry_id)?)
+    }
+
+    /// Is the inode the root of a mount, as the root of a bind mount
+    /// shares the inode of its source
+    fn is_mounted_inode(&self, inode_id: InodeId) -> bool {
+        self.mounts.keys().any(|root| {
+            self.dcache
+                .get_entry(root)
+                .map_or(false, |entry| entry.inode_id == inode_id)
+        })
+    }
+
+    /// Is the entry `entry_id` in the tree of `ancestor_id`
+    fn is_below(&self, mut entry_id: DirectoryEntryId, ancestor_id: DirectoryEntryId) -> bool {
+        loop {
+            if entry_id == ancestor_id {
+                return true;
+            }
+            match self.dcache.get_entry(&entry_id) {
+                Ok(entry) if entry.parent_id != entry_id => entry_id = entry.parent_id,
+                _ => return false,
+            }
+        }
+    }
+
+    /// EROFS if the entry `entry_id` is on a read-only mount
+    fn check_writable(&self, entry_id: DirectoryEntryId) -> SysResult<()> {
+        self.check_mount_writable(self.get_mount_root(entry_id))
+    }
+
+    /// EROFS if the mount of root `mount_root`, which a file was
+    /// opened through, is read-only
+    fn check_mount_writable(&self, mount_root: Option<DirectoryEntryId>) -> SysResult<()> {
+        match mount_root.and_then(|root| self.mounts.get(&root)) {
+            Some(mount) if mount.flags.contains(MountFlags::MS_RDONLY) => Err(Errno::EROFS),
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the flags of the mount where `path` is
+    pub fn mount_flags(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: &Path,
+    ) -> SysResult<MountFlags> {
+        let entry_id = self.pathname_resolution(cwd, creds, path)?;
+        Ok(self
+            .get_mount(entry_id)
+            .map_or(MountFlags::empty(), |mount| mount.flags))
+    }
+
     /// Construct a path from a DirectoryEntryId by follow up its
     /// parent
     pub fn dentry_path(&self, id: DirectoryEntryId) -> SysResult<Path> {
@@ -233,34 +438,102 @@ impl VirtualFileSystem {
     /// directory_entry_id associate with the file, used for lstat
     pub fn pathname_resolution_no_follow_last_symlink(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         pathname: &Path,
     ) -> SysResult<DirectoryEntryId> {
-        let root = if pathname.is_absolute() {
-            self.dcache.root_id
-        } else {
-            debug_assert!(cwd.is_absolute());
-            self._pathname_resolution(self.dcache.root_id, creds, cwd, 0, true)?
-        };
-        self._pathname_resolution(root, creds, pathname, 0, false)
+        let (top, root) = self.resolve_cwd(cwd, pathname)?;
+        self._pathname_resolution(top, root, creds, pathname, 0, false)
     }
 
     /// resolve the path `pathname` from root `root`, return the
     /// directory_entry_id associate with the file
     pub fn pathname_resolution(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         pathname: &Path,
     ) -> SysResult<DirectoryEntryId> {
+        let (top, root) = self.resolve_cwd(cwd, pathname)?;
+        self._pathname_resolution(top, root, creds, pathname, 0, true)
+    }
+
+    /// Get the root directory of the process, which the absolute
+    /// `pathname` starts from, and its working directory, which the
+    /// relative `pathname` starts from
+    fn resolve_cwd(
+        &self,
+        cwd: &Cwd,
+        pathname: &Path,
+    ) -> SysResult<(DirectoryEntryId, DirectoryEntryId)> {
+        let top = self.cwd_top(cwd)?;
         let root = if pathname.is_absolute() {
-            self.dcache.root_id
+            top
         } else {
-            debug_assert!(cwd.is_absolute());
-            self._pathname_resolution(self.dcache.root_id, creds, cwd, 0, true)?
+            self.dcache.get_entry(&cwd.dir)?.id
         };
-        self._pathname_resolution(root, creds, pathname, 0, true)
+        Ok((top, root))
+    }
+
+    /// Get the root directory of `cwd`, or the root of the filesystem
+    /// mounted on it, to which `..` is compared
+    fn cwd_top(&self, cwd: &Cwd) -> SysResult<DirectoryEntryId> {
+        let mut top = cwd.root;
+        let mut top_entry = self.dcache.get_entry(&top)?;
+        self.handle_mount_point(&mut top_entry, &mut top);
+        Ok(top)
+    }
+
+    /// Get the path of the working directory of `cwd` as seen from its
+    /// root directory, ENOENT if it is not below it
+    pub fn path_from_root(&self, cwd: &Cwd) -> SysResult<Path> {
+        let top = self.cwd_top(cwd)?;
+        if !self.is_below(cwd.dir, top) {
+            return Err(ENOENT);
+        }
+        let depth = self.dentry_path(top)?.depth();
+        let mut path = Path::root();
+        path.chain_components(self.dentry_path(cwd.dir)?.components().skip(depth))?;
+        Ok(path)
+    }
+
+    /// Pin the directories of `cwd`, for a new process which inherits
+    /// them
+    pub fn pin_cwd(&mut self, cwd: &Cwd) -> SysResult<()> {
+        self.dcache.pin(cwd.root)?;
+        self.dcache.pin(cwd.dir).map_err(|e| {
+            self.dcache.unpin(cwd.root);
+            e
+        })
+    }
+
+    /// Unpin the directories of `cwd`, when its process exits
+    pub fn unpin_cwd(&mut self, cwd: &Cwd) {
+        self.dcache.unpin(cwd.root);
+        self.dcache.unpin(cwd.dir);
+    }
+
+    /// Make the directory `dir_id` the working directory of `cwd`
+    pub fn set_cwd_dir(&mut self, cwd: &mut Cwd, dir_id: DirectoryEntryId) -> SysResult<()> {
+        self.dcache.pin(dir_id)?;
+        self.dcache.unpin(cwd.dir);
+        cwd.dir = dir_id;
+        Ok(())
+    }
+
+    /// Make the directory `root_id` the root directory of `cwd`
+    pub fn set_cwd_root(&mut self, cwd: &mut Cwd, root_id: DirectoryEntryId) -> SysResult<()> {
+        self.dcache.pin(root_id)?;
+        self.dcache.unpin(cwd.root);
+        cwd.root = root_id;
+        Ok(())
+    }
+
+    /// Is an entry below `entry_id`, or itself, pinned by a process
+    fn is_pinned_below(&self, entry_id: DirectoryEntryId) -> bool {
+        self.dcache
+            .iter()
+            .any(|entry| entry.is_pinned() && self.is_below(entry.id, entry_id))
     }
 
     /// this method follow the mount point
@@ -284,8 +557,12 @@ impl VirtualFileSystem {
         }
     }
 
+    /// Resolve `pathname` from `root`, or from `top` if it is
+    /// absolute. `top` is the root directory of the process, which
+    /// `..` never goes above
     fn _pathname_resolution(
         &mut self,
+        top: DirectoryEntryId,
         mut root: DirectoryEntryId,
         creds: &Credentials,
         pathname: &Path,
@@ -297,7 +574,7 @@ impl VirtualFileSystem {
         }
 
         if pathname.is_absolute() {
-            root = self.dcache.root_id;
+            root = top;
         }
 
         if !self.dcache.contains_entry(&root) {
@@ -319,6 +596,10 @@ impl VirtualFileSystem {
             if component == &"." {
                 continue;
             } else if component == &".." {
+                // The root directory of the process is its own parent
+                if current_dir_id == top {
+                    continue;
+                }
                 current_dir_id = current_entry.parent_id;
                 current_entry = self.dcache.get_entry(&current_dir_id)?;
                 self.handle_mount_point(&mut current_entry, &mut current_dir_id);
@@ -328,9 +609,9 @@ impl VirtualFileSystem {
             if {
                 let inode = self
                     .inodes
-                    .get(&current_entry.inode_id)
+                    .get_mut(&current_entry.inode_id)
                     .expect("No corresponding inode fir direntry");
-                !creds.is_access_granted(inode.access_mode, Amode::SEARCH, (inode.uid, inode.gid))
+                !inode.is_access_granted(creds, Amode::SEARCH)
             } {
                 return Err(Errno::EACCES);
             }
@@ -369,9 +650,16 @@ impl VirtualFileSystem {
                 // symlink, as on a symlink current_dir_id must point
                 // to the directory, not the symlink
                 current_dir_id = *next_entry_id;
+                self.handle_mount_point(&mut current_entry, &mut current_dir_id);
                 continue;
             }
-            let next_entry_id = current_dir
+            if self.is_shared_filesystem(current_entry.inode_id) {
+                // The directory may have changed through another mount
+                self.refresh_directory(current_dir_id)?;
+                current_entry = self.dcache.get_entry(&current_dir_id)?;
+            }
+            let next_entry_id = current_entry
+                .get_directory()?
                 .entries()
                 .find(|x| {
                     let filename = &self
@@ -405,6 +693,7 @@ impl VirtualFileSystem {
             new_path.chain(components.try_into()?)?;
 
             self._pathname_resolution(
+                top,
                 current_dir_id,
                 creds,
                 &new_path,
@@ -431,7 +720,7 @@ impl VirtualFileSystem {
     /// Je te passe l'ownership complet du 'Driver'
     pub fn new_driver(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         mode: FileType,
@@ -514,7 +803,7 @@ impl VirtualFileSystem {
     /// Returns the FileType of the file pointed by the Path `path`.
     pub fn file_type(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: &Path,
     ) -> SysResult<FileType> {
@@ -534,7 +823,7 @@ impl VirtualFileSystem {
     /// Returns the owner (uid) and group (gid) of the file pointed by the Path `path`.
     pub fn get_file_owner(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: &Path,
     ) -> SysResult<(uid_t, gid_t)> {
@@ -597,6 +886,7 @@ impl VirtualFileSystem {
         filesystem: MountedFileSystem,
         fs_id: FileSystemId,
         mount_dir_id: DirectoryEntryId,
+        flags: MountFlags,
     ) -> SysResult<()> {
         let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
         if !mount_dir.is_directory() {
@@ -606,6 +896,7 @@ impl VirtualFileSystem {
         if mount_dir.is_mounted()? {
             return Err(EBUSY);
         }
+        let target = self.dentry_path(mount_dir_id)?;
         let (mut root_dentry, mut root_inode_data, driver) = filesystem.fs.lock().root()?;
 
         root_inode_data.id.filesystem_id = Some(fs_id);
@@ -626,199 +917,384 @@ impl VirtualFileSystem {
             .expect("WTF: and should be a directory");
 
         self.mounted_filesystems.try_insert(fs_id, filesystem)?;
+        self.mounts.try_insert(
+            root_dentry_id,
+            Mount {
+                fs_id,
+                target,
+                flags: flags & MountFlags::PER_MOUNT,
+                writers: 0,
+            },
+        )?;
         //TODO: cleanup root dentry_id
 
         Ok(())
     }
 
-    /// mount the source `source` on the target `target`
+    /// mount the source `source` on the target `target`, `fs_type`
+    /// is the name of the filesystem: "ext2", "vfat", "msdos" or
+    /// "iso9660". With MS_BIND, `source` is a directory or a file
+    /// which is mounted again on `target`, and with MS_REMOUNT only
+    /// the flags of the mount on `target` change
     pub fn mount(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         source: Path,
         target: Path,
+        fs_type: &str,
+        flags: MountFlags,
     ) -> SysResult<()> {
+        if flags.contains(MountFlags::MS_REMOUNT) {
+            return self.remount(cwd, creds, target, flags);
+        } else if flags.contains(MountFlags::MS_BIND) {
+            return self.bind_mount(cwd, creds, source, target, flags);
+        }
         use ext2::Ext2Filesystem;
+        use fat::FatFilesystem;
         use filesystem::devfs::DiskWrapper;
-        use filesystem::Ext2fs;
-
-        let flags = libc_binding::OpenFlags::O_RDWR;
+        use filesystem::{Ext2fs, Fatfs, Iso9660fs};
+        use iso9660::Iso9660Filesystem;
+
+        let fs_type = match fs_type {
+            "ext2" => FileSystemType::Ext2,
+            "vfat" | "msdos" => FileSystemType::Vfat,
+            "iso9660" => FileSystemType::Iso9660,
+            _ => return Err(Errno::ENODEV),
+        };
+        let flags = match fs_type {
+            FileSystemType::Iso9660 => libc_binding::OpenFlags::O_RDONLY,
+            _ => libc_binding::OpenFlags::O_RDWR,
+        };
         let mode = FileType::from_bits(0o777).expect("file permission creation failed");
         let source_path = self.resolve_path(cwd, creds, &source)?;
         let file_operation = self
-            .open(cwd, creds, source, flags, mode)
+            .open(cwd, creds, source, flags, mode, FileType::empty())
             .expect("open sda1 failed")
             .expect("disk driver open failed");
 
-        let ext2_disk = DiskWrapper(file_operation);
+        let disk = DiskWrapper(file_operation);
         VFS.force_unlock();
 
-        let ext2 = Ext2Filesystem::new(Box::try_new(ext2_disk)?).map_err(|_| Errno::EINVAL)?;
         let fs_id: FileSystemId = self.gen();
-
-        // we handle only ext2 fs right now
         // HARDFIX: `mount kernel.elf .` should be solved in a better way.
-        let filesystem = Ext2fs::new(ext2, fs_id);
+        let filesystem: Arc<DeadMutex<dyn FileSystem>> = match fs_type {
+            FileSystemType::Ext2 => {
+                let ext2 = Ext2Filesystem::new(Box::try_new(disk)?).map_err(|_| Errno::EINVAL)?;
+                Arc::try_new(DeadMutex::new(Ext2fs::new(ext2, fs_id)))?
+            }
+            FileSystemType::Iso9660 => {
+                let iso = Iso9660Filesystem::new(Box::try_new(disk)?).map_err(|_| Errno::EINVAL)?;
+                Arc::try_new(DeadMutex::new(Iso9660fs::new(iso, fs_id)))?
+            }
+            _ => {
+                let fat = FatFilesystem::new(Box::try_new(disk)?).map_err(|_| Errno::EINVAL)?;
+                Arc::try_new(DeadMutex::new(Fatfs::new(fat, fs_id)))?
+            }
+        };
         let mount_dir_id = self.pathname_resolution(cwd, creds, &target)?;
-        let target = self.resolve_path(cwd, creds, &target)?;
         self.mount_filesystem(
             MountedFileSystem {
                 source: FileSystemSource::File { source_path },
-                // we only handle ext2
-                fs_type: FileSystemType::Ext2,
-                target,
-                fs: Arc::try_new(DeadMutex::new(filesystem))?,
+                fs_type,
+                fs: filesystem,
             },
             fs_id,
             mount_dir_id,
+            flags,
         )
     }
 
-    fn recursive_trash(&mut self, root_dentry_id: DirectoryEntryId) {
+    /// Mount the directory or the file `source` on `target`, which
+    /// must be of the same kind. Both mounts share the inodes of the
+    /// filesystem
+    fn bind_mount(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        source: Path,
+        target: Path,
+        flags: MountFlags,
+    ) -> SysResult<()> {
+        let source_id = self.pathname_resolution(cwd, creds, &source)?;
+        let target_id = self.pathname_resolution(cwd, creds, &target)?;
+        let source_entry = self.dcache.get_entry(&source_id)?;
+        let target_entry = self.dcache.get_entry(&target_id)?;
+
+        if source_entry.is_directory() != target_entry.is_directory() {
+            return Err(ENOTDIR);
+        }
+        if target_entry.is_mounted()? {
+            return Err(EBUSY);
+        }
+        let fs_id = source_entry.inode_id.filesystem_id.ok_or(EINVAL)?;
+        let mounted = self.mounted_filesystems.get(&fs_id).ok_or(EINVAL)?;
+        // The entries of a dynamic filesystem are remade at each
+        // lookup, and the devfs gives its drivers to the first one,
+        // so they could not be shared
+        let unshareable = match mounted.fs_type {
+            FileSystemType::Devfs => source_entry.is_directory(),
+            _ => mounted.fs.lock().is_dynamic(),
+        };
+        if unshareable {
+            return Err(EINVAL);
+        }
+
+        let mut root_dentry = source_entry.try_clone()?;
+        root_dentry.unset_mounted()?;
+        if root_dentry.is_directory() {
+            // The entries are looked up again from the filesystem
+            root_dentry.set_directory();
+        }
+        let target_path = self.dentry_path(target_id)?;
+        let root_dentry_id = if target_entry.is_directory() {
+            self.dcache.add_entry(Some(target_id), root_dentry)?
+        } else {
+            // A file has no entries, its mount root only refers to it
+            let id = self.dcache.add_entry(None, root_dentry)?;
+            self.dcache.get_entry_mut(&id)?.parent_id = target_id;
+            id
+        };
+        self.dcache
+            .get_entry_mut(&target_id)?
+            .set_mounted(root_dentry_id)?;
+
+        self.mounts.try_insert(
+            root_dentry_id,
+            Mount {
+                fs_id,
+                target: target_path,
+                flags: flags & MountFlags::PER_MOUNT,
+                writers: 0,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Change the flags of the mount on `target`
+    fn remount(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        target: Path,
+        flags: MountFlags,
+    ) -> SysResult<()> {
+        let root_dentry_id = self.pathname_resolution(cwd, creds, &target)?;
+        let mount = self.mounts.get_mut(&root_dentry_id).ok_or(EINVAL)?;
+
+        // The files open for writing could still be written
+        if flags.contains(MountFlags::MS_RDONLY) && mount.writers > 0 {
+            return Err(EBUSY);
+        }
+        mount.flags = flags & MountFlags::PER_MOUNT;
+        Ok(())
+    }
+
+    /// Remove the entries of the tree `root_dentry_id` from the
+    /// dcache, then the inodes which no entry refers to anymore,
+    /// unless they are still open. The inodes of a filesystem which is
+    /// mounted elsewhere may still be used
+    fn recursive_trash(&mut self, root_dentry_id: DirectoryEntryId) -> SysResult<()> {
+        let mut trashed_inodes = Vec::new();
+        self.trash_dentries(root_dentry_id, &mut trashed_inodes)?;
+
+        let mut used_inodes = BTreeMap::new();
+        for entry in self.dcache.iter() {
+            used_inodes.try_insert(entry.inode_id, ())?;
+        }
+        for inode_id in trashed_inodes {
+            let unused = !used_inodes.contains_key(&inode_id)
+                && self
+                    .inodes
+                    .get(&inode_id)
+                    .map_or(false, |inode| !inode.is_opened());
+            if unused {
+                self.inodes.remove(&inode_id);
+                self.inotify.remove_inode(inode_id, InotifyMask::IN_UNMOUNT);
+            }
+        }
+        Ok(())
+    }
+
+    fn trash_dentries(
+        &mut self,
+        root_dentry_id: DirectoryEntryId,
+        trashed_inodes: &mut Vec<InodeId>,
+    ) -> SysResult<()> {
         let direntry = self.dcache.d_entries.remove(&root_dentry_id);
         if let Some(direntry) = direntry {
-            self.inodes.remove(&direntry.inode_id);
+            trashed_inodes.try_push(direntry.inode_id)?;
             if let Ok(directory) = direntry.get_directory() {
                 for child in directory.entries() {
-                    self.recursive_trash(*child);
+                    self.trash_dentries(*child, trashed_inodes)?;
                 }
             }
         }
+        Ok(())
     }
 
-    pub fn umount(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<()> {
-        let mut mount_dir_id = self.pathname_resolution(cwd, creds, &path)?;
-        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
-        // get the parent to have the mount point as pathname
-        // resolution follow mount points
-        mount_dir_id = mount_dir.parent_id;
-        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
-
-        if !mount_dir.is_mounted()? {
+    pub fn umount(&mut self, cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<()> {
+        // pathname resolution follow mount points, so this is the
+        // root of the mount
+        let root_dentry_id = self.pathname_resolution(cwd, creds, &path)?;
+        if !self.mounts.contains_key(&root_dentry_id) {
             return Err(EINVAL);
         }
+        // The mounts below and the directories of the processes would
+        // be lost
+        if self
+            .mounts
+            .keys()
+            .any(|id| *id != root_dentry_id && self.is_below(*id, root_dentry_id))
+            || self.is_pinned_below(root_dentry_id)
+        {
+            return Err(EBUSY);
+        }
 
-        let root_dentry_id = mount_dir.get_mountpoint_entry().ok_or(EINVAL)?;
+        let mount_dir_id = self.dcache.get_entry(&root_dentry_id)?.parent_id;
+        let mount_dir = self.dcache.get_entry_mut(&mount_dir_id)?;
 
         // this set the mount_dir as unmouted
         mount_dir.unset_mounted()?;
-        mount_dir.remove_entry(root_dentry_id)?;
-
-        let mounted_dir = self.dcache.get_entry_mut(&root_dentry_id)?;
-        let fs_id = mounted_dir.inode_id.filesystem_id.ok_or(EINVAL)?;
+        if mount_dir.is_directory() {
+            mount_dir.remove_entry(root_dentry_id)?;
+        }
 
-        self.recursive_trash(root_dentry_id);
+        let fs_id = self
+            .mounts
+            .remove(&root_dentry_id)
+            .expect("mount should be there")
+            .fs_id;
 
-        self.mounted_filesystems.remove(&fs_id);
+        self.recursive_trash(root_dentry_id)?;
 
+        if !self.mounts.values().any(|mount| mount.fs_id == fs_id) {
+            self.mounted_filesystems.remove(&fs_id);
+        }
         Ok(())
     }
 
-    pub fn opendir(
+    /// Get the directory entry of the directory `inode_id` opened at
+    /// `entry_id`, a dynamic filesystem may have recreated it since
+    pub fn open_directory_entry(
         &mut self,
-        cwd: &Path,
-        creds: &Credentials,
-        path: Path,
-    ) -> SysResult<Vec<dirent>> {
-        //TODO check this function
-        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
-        let entry = self.dcache.get_entry(&entry_id)?;
-
-        let inode = self
-            .inodes
-            .get(&entry.inode_id)
-            .expect("No corresponding Inode for direntry");
-        if !creds.is_access_granted(
-            // check for write permission in the parent.
-            inode.access_mode,
-            Amode::READ,
-            (inode.uid, inode.gid),
-        ) {
-            return Err(Errno::EACCES);
+        entry_id: DirectoryEntryId,
+        inode_id: InodeId,
+        path: &Path,
+    ) -> SysResult<DirectoryEntryId> {
+        match self.dcache.get_entry(&entry_id) {
+            Ok(entry) if entry.inode_id == inode_id && entry.is_directory() => Ok(entry_id),
+            _ => {
+                // The directory was already opened, the search
+                // permissions do not matter anymore
+                let entry_id = self.pathname_resolution(&Cwd::new(), &Credentials::ROOT, path)?;
+                if self.dcache.get_entry(&entry_id)?.inode_id != inode_id {
+                    return Err(ENOENT);
+                }
+                Ok(entry_id)
+            }
         }
+    }
 
-        let entry_count = entry.get_directory()?.entries().count();
-
+    /// Fill `buf` with the linux_dirent64 of the entries of the
+    /// directory `entry_id` which follow `cursor`, and advance it.
+    /// The dcache keeps the children sorted by filename, so the reading
+    /// resumes right after the last filename given
+    pub fn getdents(
+        &mut self,
+        entry_id: DirectoryEntryId,
+        cursor: &mut DirectoryCursor,
+        buf: &mut [u8],
+    ) -> SysResult<u32> {
+        let entry = self.dcache.get_entry(&entry_id)?;
         let should_lookup = {
-            entry_count == 0
+            entry.get_directory()?.entries().count() == 0
                 || self
                     .get_filesystem(entry.inode_id)
                     .expect("No corresonding filesystem for direntry")
                     .lock()
                     .is_dynamic()
         };
-
         if should_lookup {
             self.lookup_directory(entry_id)?;
+        } else if self.is_shared_filesystem(entry.inode_id) {
+            self.refresh_directory(entry_id)?;
         }
 
         let direntry = self.dcache.get_entry(&entry_id)?;
-        let dir = direntry.get_directory()?;
-        Ok(dir
-            .entries()
-            .map(|e| {
-                let child = self
-                    .dcache
-                    .get_entry(&e)
-                    .expect("entry not found vfs is bullshit");
-                child.dirent()
-            })
-            // recreate on the fly the . and .. file as it is not stocked
-            // in the vfs
-            .chain(Some(dirent {
-                d_name: {
-                    let mut name = [0; NAME_MAX + 1];
-                    name[0] = '.' as c_char;
-                    name
-                },
-                d_ino: direntry.inode_id.inode_number as u32,
-            }))
-            .chain(Some(dirent {
-                d_name: {
-                    let mut name = [0; NAME_MAX + 1];
-                    name[0] = '.' as c_char;
-                    name[1] = '.' as c_char;
-                    name
-                },
-                d_ino: self
-                    .dcache
-                    .get_entry(&direntry.parent_id)
-                    .unwrap()
-                    .inode_id
-                    .inode_number as u32,
-            }))
-            .try_collect()?)
+        let mut parent = self.dcache.get_entry(&direntry.parent_id)?;
+        // The parent of the root of a filesystem is the parent of its mount point
+        if parent.is_mounted()? && parent.id != direntry.id {
+            parent = self.dcache.get_entry(&parent.parent_id)?;
+        }
+
+        let dot_entries = [(".", direntry.inode_id), ("..", parent.inode_id)];
+        let dots = dot_entries.iter().cloned().skip(cursor.dots_given());
+        let children = self
+            .dcache
+            .children_after(entry_id, cursor.last())?
+            .map(|child| (child.filename.as_str(), child.inode_id));
+
+        let mut written = 0;
+        for (name, inode_id) in dots.chain(children) {
+            if written + dirent64_len(name) > buf.len() {
+                if written == 0 {
+                    // Not even one entry fits
+                    return Err(EINVAL);
+                }
+                break;
+            }
+            let d_type = self
+                .inodes
+                .get(&inode_id)
+                .map_or(DT_UNKNOWN as u8, |inode| d_type(inode.access_mode));
+            let d_off = cursor.advance(name)?;
+            written += write_dirent64(
+                &mut buf[written..],
+                inode_id.inode_number as u64,
+                d_off,
+                d_type,
+                name,
+            );
+        }
+        Ok(written as u32)
     }
 
-    pub fn unlink(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<()> {
+    pub fn unlink(&mut self, cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<()> {
         let entry_id = self.pathname_resolution_no_follow_last_symlink(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
         let parent_id;
 
         {
-            let entry = self.dcache.get_entry_mut(&entry_id)?;
+            let entry = self.dcache.get_entry(&entry_id)?;
             if entry.is_directory() {
                 // unlink on directory not supported
                 return Err(EISDIR);
             }
+            if self.is_mounted_inode(entry.inode_id) {
+                return Err(EBUSY);
+            }
             parent_id = entry.parent_id;
         }
 
         let parent_inode_id = self.dcache.get_entry_mut(&parent_id)?.inode_id;
         let parent_inode = self
             .inodes
-            .get(&parent_inode_id)
+            .get_mut(&parent_inode_id)
             .expect("No corresponding Inode for direntry");
-        if !creds.is_access_granted(
-            // check for write permission in the parent.
-            parent_inode.access_mode,
-            Amode::WRITE,
-            (parent_inode.uid, parent_inode.gid),
-        ) {
+        // check for write permission in the parent.
+        if !parent_inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
-        Ok(self.funlink(entry_id)?)
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        self.notify_parent(entry_id, InotifyMask::IN_DELETE, 0);
+        self.funlink(entry_id)?;
+        // The link count of the remaining links changed
+        if self.inodes.contains_key(&inode_id) {
+            self.notify(inode_id, InotifyMask::IN_ATTRIB);
+        }
+        Ok(())
     }
 
     pub fn funlink(&mut self, entry_id: DirectoryEntryId) -> SysResult<()> {
@@ -850,6 +1326,10 @@ impl VirtualFileSystem {
         // we remove the inode only if we free the inode data
         if free_inode_data {
             self.inodes.remove(&inode_id).ok_or(ENOENT)?;
+            self.inotify
+                .remove_inode(inode_id, InotifyMask::IN_DELETE_SELF);
+            // The inode number may be given to a new file
+            PAGE_CACHE.lock().invalidate(inode_id);
         } // else if corresponding_inode.lazy_unlink {
           //     eprintln!(
           //         "Lazy unlinking entry for {}, hardlinks: {}",
@@ -876,11 +1356,20 @@ impl VirtualFileSystem {
         let inode_id = corresponding_inode.get_id();
         if corresponding_inode.close() {
             self.inodes.remove(&inode_id).expect("no such inode");
+            self.inotify
+                .remove_inode(inode_id, InotifyMask::IN_DELETE_SELF);
+            PAGE_CACHE.lock().invalidate(inode_id);
             if let Some(fs) = self.get_filesystem(inode_id) {
                 fs.lock()
                     .remove_inode(inode_id.inode_number)
                     .expect("remove inode failed");
             }
+        } else if !corresponding_inode.is_opened() {
+            // Let the filesystem release what it kept for the writers
+            let _r = corresponding_inode
+                .filesystem
+                .lock()
+                .close(inode_id.inode_number);
         }
     }
 
@@ -896,43 +1385,28 @@ impl VirtualFileSystem {
         }
     }
 
-    /// Gets the corresponding inode for a directory entry of id `direntry_id`.
+    /// Gets the corresponding inode mutably for a directory entry of id `direntry_id`.
     /// This methods helps removing the currently popular boilerplate.
     ///
     /// Panic:
     /// Panics if there is no corresponding inode for the given direntry_id.
-    fn get_inode_from_direntry_id(&self, direntry_id: DirectoryEntryId) -> SysResult<&Inode> {
+    fn get_inode_from_direntry_id_mut(
+        &mut self,
+        direntry_id: DirectoryEntryId,
+    ) -> SysResult<&mut Inode> {
         let direntry = self.dcache.get_entry(&direntry_id)?;
 
         // should we remove this panic
         Ok(self
             .inodes
-            .get(&direntry.inode_id)
+            .get_mut(&direntry.inode_id)
             .expect("No corresponding Inode for Directory"))
     }
 
-    // /// Gets the corresponding inode mutably for a directory entry of id `direntry_id`.
-    // /// This methods helps removing the currently popular boilerplate.
-    // ///
-    // /// Panic:
-    // /// Panics if there is no corresponding inode for the given direntry_id.
-    // fn get_inode_from_direntry_id_mut(
-    //     &mut self,
-    //     direntry_id: DirectoryEntryId,
-    // ) -> SysResult<&mut Inode> {
-    //     let direntry = self.dcache.get_entry(&direntry_id)?;
-
-    //     // should we remove this panic
-    //     Ok(self
-    //         .inodes
-    //         .get_mut(&direntry.inode_id)
-    //         .expect("No corresponding Inode for Directory"))
-    // }
-
     /// Checks if the given `amode` is permitted for the file pointed by `path`
     pub fn is_access_granted(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: &Path,
         amode: Amode,
@@ -942,14 +1416,42 @@ impl VirtualFileSystem {
             Ok(id) => id,
         };
 
-        let inode = self.get_inode_from_direntry_id(direntry_id).unwrap();
+        let inode = self.get_inode_from_direntry_id_mut(direntry_id).unwrap();
 
         // Ensure that non-regular files are not executables regardless of the file permissions.
         if !inode.access_mode.is_regular() && amode.contains(Amode::EXECUTE) {
             return false;
         }
 
-        creds.is_access_granted(inode.access_mode, amode, (inode.uid, inode.gid))
+        inode.is_access_granted(creds, amode)
+    }
+
+    /// Check that the mount of the entry `entry_id` allows to open it
+    /// with `flags`: no writing on a read-only mount and no device on
+    /// a MS_NODEV one
+    fn check_open_on_mount(&self, entry_id: DirectoryEntryId, flags: OpenFlags) -> SysResult<()> {
+        let mount_flags = self
+            .get_mount(entry_id)
+            .map_or(MountFlags::empty(), |mount| mount.flags);
+        let inode = self
+            .inodes
+            .get(&self.dcache.get_entry(&entry_id)?.inode_id)
+            .ok_or(ENOENT)?;
+        let is_device =
+            inode.access_mode.is_character_device() || inode.access_mode.is_block_device();
+
+        if mount_flags.contains(MountFlags::MS_NODEV) && is_device {
+            return Err(Errno::EACCES);
+        }
+        // The devices, fifos and sockets are not written on the filesystem
+        let is_stored = inode.access_mode.is_regular() || inode.access_mode.is_directory();
+        if mount_flags.contains(MountFlags::MS_RDONLY)
+            && is_stored
+            && (flags.is_open_for_write() || flags.contains(OpenFlags::O_TRUNC))
+        {
+            return Err(Errno::EROFS);
+        }
+        Ok(())
     }
 
     /// La fonction open() du vfs sera appelee par la fonction open()
@@ -962,11 +1464,12 @@ impl VirtualFileSystem {
     /// Ce sont les 'Driver' qui auront l'ownership des 'FileOperation'
     pub fn open(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path, // Could be a ref.
         flags: OpenFlags,
         mode: FileType,
+        umask: FileType,
     ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
         let entry_id;
         match self.pathname_resolution(cwd, creds, &path) {
@@ -974,10 +1477,11 @@ impl VirtualFileSystem {
                 return Err(Errno::EEXIST)
             }
             Ok(id) => {
+                self.check_open_on_mount(id, flags)?;
                 let amode = Amode::from(flags);
-                let inode = self.get_inode_from_direntry_id(id)?;
+                let inode = self.get_inode_from_direntry_id_mut(id)?;
 
-                if !creds.is_access_granted(inode.access_mode, amode, (inode.uid, inode.gid)) {
+                if !inode.is_access_granted(creds, amode) {
                     return Err(Errno::EACCES);
                 }
 
@@ -986,32 +1490,36 @@ impl VirtualFileSystem {
             Err(e) if !flags.contains(OpenFlags::O_CREAT) => return Err(e.into()),
             _ => {
                 let parent_id = self.pathname_resolution(cwd, creds, &path.parent()?)?;
+                self.check_writable(parent_id)?;
                 let parent_entry = self.dcache.get_entry(&parent_id)?;
-                let parent_inode = self.get_inode_from_direntry_id(parent_id)?;
-
                 let inode_id = parent_entry.inode_id;
                 let inode_number = inode_id.inode_number as u32;
 
-                if !creds.is_access_granted(
-                    parent_inode.access_mode,
-                    Amode::WRITE,
-                    (parent_inode.uid, parent_inode.gid),
-                ) {
+                let parent_inode = self.get_inode_from_direntry_id_mut(parent_id)?;
+                if !parent_inode.is_access_granted(creds, Amode::WRITE) {
                     return Err(Errno::EACCES);
                 }
 
+                // Open creates regular files
+                let (mode, acl) =
+                    self.inherited_acl(inode_id, FileType::REGULAR_FILE | mode, umask)?;
+
                 let fs = self.get_filesystem_mut(inode_id).expect("no filesystem");
                 let fs_cloned = fs.clone();
 
                 let (direntry, inode_data, driver) = fs.lock().create(
                     path.filename().expect("no filename").as_str(),
                     inode_number,
-                    // Open creates regular files
-                    FileType::REGULAR_FILE | mode,
+                    mode,
                     (creds.euid, creds.egid),
                 )?;
                 let fs_entry = (direntry, inode_data, Some(driver));
                 entry_id = self.add_entry_from_filesystem(fs_cloned, Some(parent_id), fs_entry)?;
+                if let Some(acl) = acl {
+                    let new_inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+                    self.store_inherited_acl(inode_id, new_inode_id, acl)?;
+                }
+                self.notify_parent(entry_id, InotifyMask::IN_CREATE, 0);
             }
         }
 
@@ -1021,10 +1529,34 @@ impl VirtualFileSystem {
             return Err(Errno::ENOTDIR);
         }
 
-        self.inodes
-            .get_mut(&entry_inode_id)
-            .ok_or(ENOENT)?
-            .open(flags)
+        let res = if entry.is_directory() {
+            if flags.is_open_for_write() {
+                return Err(Errno::EISDIR);
+            }
+            let path = self.dentry_path(entry_id)?;
+            self.inodes
+                .get_mut(&entry_inode_id)
+                .ok_or(ENOENT)?
+                .open_directory(entry_id, path)?
+        } else {
+            self.inodes
+                .get_mut(&entry_inode_id)
+                .ok_or(ENOENT)?
+                .open(flags)?
+        };
+        // The flags of the mount apply to the open file
+        if let (IpcResult::Done(file_operation), Some(mount_root)) =
+            (&res, self.get_mount_root(entry_id))
+        {
+            let kept = file_operation.lock().set_mount_root(mount_root);
+            if kept && flags.is_open_for_write() {
+                self.mounts
+                    .get_mut(&mount_root)
+                    .expect("mount should be there")
+                    .writers += 1;
+            }
+        }
+        Ok(res)
     }
 
     // pub fn creat(
@@ -1063,7 +1595,7 @@ impl VirtualFileSystem {
 
     pub fn chmod(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         mut mode: FileType,
@@ -1075,15 +1607,19 @@ impl VirtualFileSystem {
         let entry = self.dcache.get_entry(&entry_id)?;
 
         let inode_id = entry.inode_id;
-        self.fchmod(creds, inode_id, mode)
+        self.fchmod(creds, self.get_mount_root(entry_id), inode_id, mode)
     }
 
+    /// Change the mode of the file `inode_id`, opened through the
+    /// mount of root `mount_root`
     pub fn fchmod(
         &mut self,
         creds: &Credentials,
+        mount_root: Option<DirectoryEntryId>,
         inode_id: InodeId,
         mut mode: FileType,
     ) -> SysResult<()> {
+        self.check_mount_writable(mount_root)?;
         let mask = FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK;
         mode &= mask;
 
@@ -1096,6 +1632,33 @@ impl VirtualFileSystem {
             return Err(Errno::EPERM);
         }
 
+        self.set_mode(inode_id, mode)?;
+
+        // Keep the access ACL consistent with the new permission bits.
+        let fs = self
+            .get_filesystem(inode_id)
+            .expect("No corresponding filesystem")
+            .clone();
+        let inode_number = inode_id.inode_number as u32;
+        let acl = PosixAcl::load(&*fs.lock(), inode_number, ACL_ACCESS)?;
+        if let Some(mut acl) = acl {
+            acl.chmod(mode);
+            fs.lock().setxattr(
+                inode_number,
+                ACL_ACCESS,
+                &acl.to_xattr()?,
+                XattrFlags::XATTR_REPLACE,
+            )?;
+            self.get_inode(inode_id)?.invalidate_acl();
+        }
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    /// Set the special bits and the file permission bits of an inode
+    fn set_mode(&mut self, inode_id: InodeId, mode: FileType) -> SysResult<()> {
+        let mask = FileType::SPECIAL_BITS | FileType::PERMISSIONS_MASK;
+
         self.get_filesystem(inode_id)
             .expect("No corresponding filesystem")
             .lock()
@@ -1116,7 +1679,7 @@ impl VirtualFileSystem {
 
     pub fn chown(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         owner: uid_t,
@@ -1126,16 +1689,20 @@ impl VirtualFileSystem {
         let entry = self.dcache.get_entry(&entry_id)?;
 
         let inode_id = entry.inode_id;
-        self.fchown(creds, inode_id, owner, group)
+        self.fchown(creds, self.get_mount_root(entry_id), inode_id, owner, group)
     }
 
+    /// Change the owner of the file `inode_id`, opened through the
+    /// mount of root `mount_root`
     pub fn fchown(
         &mut self,
         creds: &Credentials,
+        mount_root: Option<DirectoryEntryId>,
         inode_id: InodeId,
         owner: uid_t,
         group: gid_t,
     ) -> SysResult<()> {
+        self.check_mount_writable(mount_root)?;
         let inode = self
             .inodes
             .get(&inode_id)
@@ -1161,18 +1728,20 @@ impl VirtualFileSystem {
         if group != gid_t::max_value() {
             inode.set_gid(group);
         }
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
         Ok(())
     }
 
     pub fn utime(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         times: Option<&utimbuf>,
     ) -> SysResult<()> {
         // Handle permissions here too.
         let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
         let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
         let fs = self.get_filesystem(inode_id).expect("No filesystem");
 
@@ -1189,36 +1758,37 @@ impl VirtualFileSystem {
             inode.atime = current_time as time_t;
             inode.mtime = current_time as time_t;
         }
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
         Ok(())
     }
 
     pub fn mkdir(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         mut path: Path,
         mode: FileType,
+        umask: FileType,
     ) -> SysResult<()> {
         if let Ok(_) = self.pathname_resolution(cwd, creds, &path) {
             return Err(EEXIST);
         }
         let filename = path.pop().ok_or(EINVAL)?;
         let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         if !entry.is_directory() {
             return Err(ENOTDIR);
         }
 
-        let parent_inode = self.get_inode_from_direntry_id(entry_id)?;
-        if !creds.is_access_granted(
-            parent_inode.access_mode,
-            Amode::WRITE,
-            (parent_inode.uid, parent_inode.gid),
-        ) {
+        let inode_id = entry.inode_id;
+
+        let parent_inode = self.get_inode_from_direntry_id_mut(entry_id)?;
+        if !parent_inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
-        let inode_id = entry.inode_id;
+        let (mode, acl) = self.inherited_acl(inode_id, mode, umask)?;
 
         let fs = self.get_filesystem(inode_id).expect("no filesystem");
         let fs_cloned = fs.clone();
@@ -1230,7 +1800,12 @@ impl VirtualFileSystem {
         )?;
 
         let fs_entry = (direntry, inode_data, Some(driver));
-        self.add_entry_from_filesystem(fs_cloned, Some(entry_id), fs_entry)?;
+        let new_entry_id = self.add_entry_from_filesystem(fs_cloned, Some(entry_id), fs_entry)?;
+        if let Some(acl) = acl {
+            let new_inode_id = self.dcache.get_entry(&new_entry_id)?.inode_id;
+            self.store_inherited_acl(inode_id, new_inode_id, acl)?;
+        }
+        self.notify_parent(new_entry_id, InotifyMask::IN_CREATE, 0);
         Ok(())
     }
 
@@ -1243,7 +1818,7 @@ impl VirtualFileSystem {
             panic!("path is not absolute");
         }
         let entry_id = self
-            .pathname_resolution(&Path::root(), creds, path)
+            .pathname_resolution(&Cwd::new(), creds, path)
             .map_err(|_| ENOENT)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         Ok(entry.inode_id)
@@ -1251,7 +1826,7 @@ impl VirtualFileSystem {
 
     pub fn mknod(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         mut path: Path,
         mode: FileType,
@@ -1270,6 +1845,7 @@ impl VirtualFileSystem {
         }
         let filename = path.pop().ok_or(EINVAL)?;
         let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         if !entry.is_directory() {
             return Err(ENOTDIR);
@@ -1279,7 +1855,7 @@ impl VirtualFileSystem {
         let inode = self
             .get_inode(inode_id)
             .expect("No corresponding Inode for Direntry");
-        if !creds.is_access_granted(inode.access_mode, Amode::WRITE, (inode.uid, inode.gid)) {
+        if !inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
@@ -1294,6 +1870,7 @@ impl VirtualFileSystem {
         )?;
         let fs_entry = (direntry, inode_data, Some(driver));
         let new_entry_id = self.add_entry_from_filesystem(fs_cloned, Some(entry_id), fs_entry)?;
+        self.notify_parent(new_entry_id, InotifyMask::IN_CREATE, 0);
         let new_entry = self.dcache.get_entry(&new_entry_id)?;
         Ok(new_entry.inode_id)
     }
@@ -1314,17 +1891,20 @@ impl VirtualFileSystem {
 
     pub fn remove_orphan_driver(&mut self, inode_id: InodeId) -> SysResult<Box<dyn Driver>> {
         let inode = self.inodes.remove(&inode_id).ok_or(ENOENT)?;
+        self.inotify
+            .remove_inode(inode_id, InotifyMask::IN_DELETE_SELF);
         Ok(inode.driver)
     }
 
     // TODO: Sticky bit (EPERM condition in posix) is not implemented for now.
-    pub fn rmdir(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<()> {
+    pub fn rmdir(&mut self, cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<()> {
         let filename = path.filename().ok_or(EINVAL)?;
         if filename == &"." || filename == &".." {
             return Err(EINVAL);
         }
 
         let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
         let entry = self.dcache.get_entry(&entry_id)?;
 
         if !entry.is_directory() {
@@ -1334,24 +1914,27 @@ impl VirtualFileSystem {
             return Err(ENOTEMPTY);
         }
         let inode_id = entry.inode_id;
+        // The root or the working directory of a process is in use
+        if self.is_mounted_inode(inode_id) || entry.is_pinned() {
+            return Err(EBUSY);
+        }
         let parent_id = entry.parent_id;
 
         let parent_inode = self
-            .get_inode_from_direntry_id(parent_id)
+            .get_inode_from_direntry_id_mut(parent_id)
             .expect("No corresponding inode");
         let parent_inode_id = parent_inode.id;
 
-        if !creds.is_access_granted(
-            // check for write permission in the parent.
-            parent_inode.access_mode,
-            Amode::WRITE,
-            (parent_inode.uid, parent_inode.gid),
-        ) {
+        // check for write permission in the parent.
+        if !parent_inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
+        self.notify_parent(entry_id, InotifyMask::IN_DELETE, 0);
         self.dcache.remove_entry(entry_id)?;
         self.inodes.remove(&inode_id).expect("inode should be here");
+        self.inotify
+            .remove_inode(inode_id, InotifyMask::IN_DELETE_SELF);
 
         let fs = self.get_filesystem(inode_id).expect("no filesystem");
         fs.lock().rmdir(
@@ -1368,7 +1951,7 @@ impl VirtualFileSystem {
     /// this implementation follow symbolic links
     pub fn link(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         oldpath: Path,
         newpath: Path,
@@ -1391,6 +1974,12 @@ impl VirtualFileSystem {
         }
 
         let parent_new_id = self.pathname_resolution(cwd, creds, &newpath.parent()?)?;
+        self.check_writable(parent_new_id)?;
+        // A link cannot cross mount points, even between two mounts
+        // of the same filesystem
+        if self.get_mount_root(oldentry_id) != self.get_mount_root(parent_new_id) {
+            return Err(EXDEV);
+        }
         let parent_inode_id = self.dcache.get_entry_mut(&parent_new_id)?.inode_id;
         let parent_inode_number = parent_inode_id.inode_number;
 
@@ -1398,12 +1987,8 @@ impl VirtualFileSystem {
             .get_inode(parent_inode_id)
             .expect("No corresponding inode");
 
-        if !creds.is_access_granted(
-            // check for write permission in the parent.
-            parent_inode.access_mode,
-            Amode::WRITE,
-            (parent_inode.uid, parent_inode.gid),
-        ) {
+        // check for write permission in the parent.
+        if !parent_inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
@@ -1425,11 +2010,13 @@ impl VirtualFileSystem {
             fs.lock()
                 .link(parent_inode_number, target_inode_number, filename.as_str())?;
         // self.add_entry_from_filesystem(fs_cloned, Some(parent_new_id), fs_entry)?;
-        self.dcache.add_entry(Some(parent_new_id), newentry)?;
+        let newentry_id = self.dcache.add_entry(Some(parent_new_id), newentry)?;
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
+        self.notify_parent(newentry_id, InotifyMask::IN_CREATE, 0);
         Ok(())
     }
 
-    pub fn stat(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<stat> {
+    pub fn stat(&mut self, cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<stat> {
         let entry_id = self.pathname_resolution(cwd, creds, &path)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         let inode_id = entry.inode_id;
@@ -1437,7 +2024,7 @@ impl VirtualFileSystem {
         inode.stat()
     }
 
-    pub fn lstat(&mut self, cwd: &Path, creds: &Credentials, path: Path) -> SysResult<stat> {
+    pub fn lstat(&mut self, cwd: &Cwd, creds: &Credentials, path: Path) -> SysResult<stat> {
         let entry_id = self.pathname_resolution_no_follow_last_symlink(cwd, creds, &path)?;
         let entry = self.dcache.get_entry(&entry_id)?;
         let inode_id = entry.inode_id;
@@ -1447,7 +2034,7 @@ impl VirtualFileSystem {
 
     pub fn readlink(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         buf: &mut [c_char],
@@ -1464,7 +2051,7 @@ impl VirtualFileSystem {
 
     pub fn symlink(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         target: &str,
         mut linkname: Path,
@@ -1474,6 +2061,7 @@ impl VirtualFileSystem {
         }
         let filename = linkname.pop().expect("no filename");
         let direntry_id = self.pathname_resolution(cwd, creds, &linkname)?;
+        self.check_writable(direntry_id)?;
         let direntry = self.dcache.get_entry(&direntry_id)?;
         if !direntry.is_directory() {
             return Err(ENOENT);
@@ -1484,14 +2072,10 @@ impl VirtualFileSystem {
         // let's remove this code duplication with `get_inode_from_direntry_id` or something.
         let parent_inode = self
             .inodes
-            .get(&parent_inode_id)
+            .get_mut(&parent_inode_id)
             .expect("No corresponding Inode for direntry");
-        if !creds.is_access_granted(
-            // check for write permission in the parent.
-            parent_inode.access_mode,
-            Amode::WRITE,
-            (parent_inode.uid, parent_inode.gid),
-        ) {
+        // check for write permission in the parent.
+        if !parent_inode.is_access_granted(creds, Amode::WRITE) {
             return Err(Errno::EACCES);
         }
 
@@ -1507,17 +2091,14 @@ impl VirtualFileSystem {
         )?;
 
         let fs_entry = (direntry, inode_data, Some(driver));
-        self.add_entry_from_filesystem(fs_cloned.clone(), Some(direntry_id), fs_entry)
+        let new_entry_id = self
+            .add_entry_from_filesystem(fs_cloned.clone(), Some(direntry_id), fs_entry)
             .expect("add entry from filesystem failed");
+        self.notify_parent(new_entry_id, InotifyMask::IN_CREATE, 0);
         Ok(())
     }
 
-    pub fn resolve_path(
-        &mut self,
-        cwd: &Path,
-        creds: &Credentials,
-        path: &Path,
-    ) -> SysResult<Path> {
+    pub fn resolve_path(&mut self, cwd: &Cwd, creds: &Credentials, path: &Path) -> SysResult<Path> {
         let direntry_id = self.pathname_resolution(cwd, creds, &path)?;
         self.dentry_path(direntry_id)
     }
@@ -1525,7 +2106,7 @@ impl VirtualFileSystem {
     //TODO: permissions here not currently implemented.
     pub fn rename(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         oldpath: Path,
         newpath: Path,
@@ -1544,6 +2125,17 @@ impl VirtualFileSystem {
         }
 
         let oldentry_id = self.pathname_resolution_no_follow_last_symlink(cwd, creds, &oldpath)?;
+        let new_parent_id = self.pathname_resolution(cwd, creds, &newpath.parent()?)?;
+        self.check_writable(oldentry_id)?;
+        self.check_writable(new_parent_id)?;
+        if self.mounts.contains_key(&oldentry_id) {
+            return Err(EBUSY);
+        }
+        // A file cannot be moved across mount points, even between two
+        // mounts of the same filesystem
+        if self.get_mount_root(oldentry_id) != self.get_mount_root(new_parent_id) {
+            return Err(EXDEV);
+        }
         // The old pathname shall not name an ancestor directory of
         // the new pathname.
         let resolved_old_path = self.resolve_path(cwd, creds, &oldpath)?;
@@ -1599,7 +2191,6 @@ impl VirtualFileSystem {
         let old_parent_inode_id = self.dcache.get_entry_mut(&old_parent_id)?.inode_id;
         let old_parent_inode_nbr = old_parent_inode_id.inode_number;
 
-        let new_parent_id = self.pathname_resolution(cwd, creds, &newpath.parent()?)?;
         let new_parent_inode_id = self.dcache.get_entry_mut(&new_parent_id)?.inode_id;
         let new_parent_inode_nbr = new_parent_inode_id.inode_number;
 
@@ -1614,21 +2205,23 @@ impl VirtualFileSystem {
             new_filename.as_str(),
         )?;
 
-        let oldentry_id = self.dcache.move_dentry(oldentry_id, new_parent_id)?;
+        let cookie = self.inotify.new_cookie();
+        self.notify_parent(oldentry_id, InotifyMask::IN_MOVED_FROM, cookie);
 
-        let entry = self
+        let oldentry_id = self
             .dcache
-            .d_entries
-            .get_mut(&oldentry_id)
-            .expect("oldentry sould be there");
+            .move_dentry(oldentry_id, new_parent_id, *new_filename)?;
 
-        entry.set_filename(*new_filename);
+        let inode_id = self.dcache.get_entry(&oldentry_id)?.inode_id;
+        self.notify_parent(oldentry_id, InotifyMask::IN_MOVED_TO, cookie);
+        self.inotify
+            .notify(inode_id, InotifyMask::IN_MOVE_SELF, 0, None);
         Ok(())
     }
 
     pub fn statfs(
         &mut self,
-        cwd: &Path,
+        cwd: &Cwd,
         creds: &Credentials,
         path: Path,
         buf: &mut statfs,
@@ -1654,6 +2247,508 @@ impl VirtualFileSystem {
 
         fs.lock().statfs(buf)
     }
+
+    /// Set the size of the regular file `path` to `length`
+    pub fn truncate(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        length: off_t,
+    ) -> SysResult<()> {
+        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        let inode = self.get_inode(inode_id)?;
+
+        if inode.is_directory() {
+            return Err(EISDIR);
+        }
+        if !inode.is_access_granted(creds, Amode::WRITE) {
+            return Err(EACCES);
+        }
+        self.ftruncate(inode_id, length)
+    }
+
+    /// Set the size of the regular file `inode_id` to `length`, the
+    /// permissions were checked at the opening of the file
+    pub fn ftruncate(&mut self, inode_id: InodeId, length: off_t) -> SysResult<()> {
+        if length < 0 {
+            return Err(EINVAL);
+        }
+        let inode = self.get_inode(inode_id)?;
+
+        if inode.is_directory() {
+            return Err(EISDIR);
+        }
+        if !inode.is_regular() {
+            return Err(EINVAL);
+        }
+        inode.truncate(length as u64)?;
+        self.notify(inode_id, InotifyMask::IN_MODIFY);
+        Ok(())
+    }
+
+    /// Write `buf` in the file `inode_id` at `offset`, which is
+    /// advanced by the count of written bytes
+    pub fn write(&mut self, inode_id: InodeId, offset: &mut u64, buf: &[u8]) -> SysResult<u32> {
+        let count = self.get_inode(inode_id)?.write(offset, buf)?;
+        if count > 0 {
+            self.notify(inode_id, InotifyMask::IN_MODIFY);
+        }
+        Ok(count)
+    }
+
+    /// The file `inode_id` opened for writing through the mount of
+    /// root `mount_root` is closed
+    pub fn close_write(&mut self, inode_id: InodeId, mount_root: Option<DirectoryEntryId>) {
+        // The mount may be gone
+        if let Some(mount) = mount_root.and_then(|root| self.mounts.get_mut(&root)) {
+            mount.writers = mount.writers.saturating_sub(1);
+        }
+        self.notify(inode_id, InotifyMask::IN_CLOSE_WRITE);
+    }
+
+    /// Write back the file `inode_id` to its device. The filesystems
+    /// write through, so it is the same as writing back its filesystem
+    pub fn fsync(&self, inode_id: InodeId) -> SysResult<()> {
+        self.syncfs(inode_id)
+    }
+
+    /// Write back the filesystem containing `inode_id`
+    pub fn syncfs(&self, inode_id: InodeId) -> SysResult<()> {
+        match self.get_filesystem(inode_id) {
+            Some(fs) => fs.lock().sync(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write back all the mounted filesystems. The last mounted are
+    /// the first written back as they may be stored on a loop device
+    /// of a previous one
+    pub fn sync(&self) -> SysResult<()> {
+        let mut res = Ok(());
+        for mounted in self.mounted_filesystems.values().rev() {
+            if let Err(e) = mounted.fs.lock().sync() {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    /// Get the first lock which would prevent `lock` from being set on
+    /// the file `inode_id`
+    pub fn get_lock(&self, inode_id: InodeId, lock: &FileLock) -> Option<FileLock> {
+        self.file_locks.get_conflict(inode_id, lock)
+    }
+
+    /// Set an advisory lock on the file `inode_id`
+    pub fn set_lock(&mut self, inode_id: InodeId, lock: FileLock) -> SysResult<LockResult> {
+        self.file_locks.set_lock(inode_id, lock)
+    }
+
+    /// Remove the locks of `owner` on the bytes `start` to `end` of the
+    /// file `inode_id`
+    pub fn unlock(
+        &mut self,
+        inode_id: InodeId,
+        owner: LockOwner,
+        start: u64,
+        end: u64,
+    ) -> SysResult<()> {
+        self.file_locks.unlock(inode_id, owner, start, end)
+    }
+
+    /// Remove all the locks of `owner` on the file `inode_id`
+    pub fn release_locks(&mut self, inode_id: InodeId, owner: LockOwner) {
+        self.file_locks.release(inode_id, owner)
+    }
+
+    /// Remove all the record locks of the exiting process `pid`
+    pub fn release_process_locks(&mut self, pid: Pid) {
+        self.file_locks.release_process(pid)
+    }
+
+    /// Check that `pid` can sleep until `blocker` is released without
+    /// deadlock, and register it
+    pub fn wait_for_lock(&mut self, pid: Pid, blocker: &FileLock) -> SysResult<()> {
+        self.file_locks.wait_for(pid, blocker)
+    }
+
+    /// `pid` is not waiting for a lock anymore
+    pub fn stop_waiting_lock(&mut self, pid: Pid) {
+        self.file_locks.stop_waiting(pid)
+    }
+
+    /// Create an inotify instance
+    pub fn inotify_new_instance(&mut self) -> SysResult<usize> {
+        self.inotify.new_instance()
+    }
+
+    /// Remove the inotify instance `instance`, whose last file
+    /// descriptor is closed
+    pub fn inotify_remove_instance(&mut self, instance: usize) {
+        self.inotify.remove_instance(instance)
+    }
+
+    /// Watch the events `mask` on the file `path` for the inotify
+    /// instance `instance`, which must be able to read it
+    pub fn inotify_add_watch(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        instance: usize,
+        path: Path,
+        mask: InotifyMask,
+    ) -> SysResult<i32> {
+        if (mask & InotifyMask::ALL_EVENTS).is_empty() {
+            return Err(EINVAL);
+        }
+        let entry_id = if mask.contains(InotifyMask::IN_DONT_FOLLOW) {
+            self.pathname_resolution_no_follow_last_symlink(cwd, creds, &path)?
+        } else {
+            self.pathname_resolution(cwd, creds, &path)?
+        };
+        let entry = self.dcache.get_entry(&entry_id)?;
+        if mask.contains(InotifyMask::IN_ONLYDIR) && !entry.is_directory() {
+            return Err(ENOTDIR);
+        }
+        let inode_id = entry.inode_id;
+        if !self
+            .get_inode(inode_id)?
+            .is_access_granted(creds, Amode::READ)
+        {
+            return Err(Errno::EACCES);
+        }
+        self.inotify.add_watch(instance, inode_id, mask)
+    }
+
+    /// Remove the watch `wd` of the inotify instance `instance`
+    pub fn inotify_rm_watch(&mut self, instance: usize, wd: i32) -> SysResult<()> {
+        self.inotify.rm_watch(instance, wd)
+    }
+
+    /// Read the events of the inotify instance `instance`
+    pub fn inotify_read(&mut self, instance: usize, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        self.inotify.read(instance, buf)
+    }
+
+    /// The size of the events of the inotify instance `instance`
+    /// waiting to be read
+    pub fn inotify_pending_len(&self, instance: usize) -> SysResult<usize> {
+        self.inotify.pending_len(instance)
+    }
+
+    /// Send the inotify events `mask` of the file `inode_id` to its
+    /// watches, and to the watches of the directories which contain it
+    /// with its filename
+    fn notify(&mut self, inode_id: InodeId, mask: InotifyMask) {
+        if !self.inotify.is_watching() {
+            return;
+        }
+        let mask = match self.inodes.get(&inode_id) {
+            Some(inode) if inode.is_directory() => mask | InotifyMask::IN_ISDIR,
+            _ => mask,
+        };
+        self.inotify.notify(inode_id, mask, 0, None);
+        for entry in self.dcache.iter() {
+            if entry.inode_id != inode_id
+                || entry.id == entry.parent_id
+                || self.mounts.contains_key(&entry.id)
+            {
+                continue;
+            }
+            if let Ok(parent) = self.dcache.get_entry(&entry.parent_id) {
+                self.inotify
+                    .notify(parent.inode_id, mask, 0, Some(&entry.filename));
+            }
+        }
+    }
+
+    /// Send the inotify events `mask` about the entry `entry_id` to the
+    /// watches of its directory: the creations, the deletions and the
+    /// renames, which are linked by `cookie`
+    fn notify_parent(&mut self, entry_id: DirectoryEntryId, mask: InotifyMask, cookie: u32) {
+        let entry = match self.dcache.get_entry(&entry_id) {
+            Ok(entry) => entry,
+            Err(_) => return,
+        };
+        let mask = if entry.is_directory() {
+            mask | InotifyMask::IN_ISDIR
+        } else {
+            mask
+        };
+        if let Ok(parent) = self.dcache.get_entry(&entry.parent_id) {
+            self.inotify
+                .notify(parent.inode_id, mask, cookie, Some(&entry.filename));
+        }
+    }
+
+    /// Get the mode and the access ACL of a new inode created with
+    /// `mode` in the directory `parent_inode_id`, from the default ACL
+    /// of the directory.
+    ///
+    /// As POSIX.1e requires, the `umask` of the creator only applies
+    /// when the directory has no default ACL.
+    fn inherited_acl(
+        &self,
+        parent_inode_id: InodeId,
+        mode: FileType,
+        umask: FileType,
+    ) -> SysResult<(FileType, Option<PosixAcl>)> {
+        let fs = self.get_filesystem(parent_inode_id).expect("no filesystem");
+        let default_acl = PosixAcl::load(
+            &*fs.lock(),
+            parent_inode_id.inode_number as u32,
+            ACL_DEFAULT,
+        )?;
+        Ok(match default_acl {
+            Some(mut acl) => (acl.create(mode), Some(acl)),
+            None => (mode & !(umask & FileType::PERMISSIONS_MASK), None),
+        })
+    }
+
+    /// Store the ACLs inherited by the new inode `inode_id` from the
+    /// directory `parent_inode_id`: its access ACL, and the default ACL
+    /// of the directory if it is a directory too.
+    fn store_inherited_acl(
+        &mut self,
+        parent_inode_id: InodeId,
+        inode_id: InodeId,
+        acl: PosixAcl,
+    ) -> SysResult<()> {
+        let fs = self.get_filesystem(inode_id).expect("no filesystem").clone();
+        let inode_number = inode_id.inode_number as u32;
+
+        if !acl.is_minimal() {
+            fs.lock().setxattr(
+                inode_number,
+                ACL_ACCESS,
+                &acl.to_xattr()?,
+                XattrFlags::empty(),
+            )?;
+            self.get_inode(inode_id)?.invalidate_acl();
+        }
+        if self.get_inode(inode_id)?.access_mode.is_directory() {
+            let default_acl = PosixAcl::load(
+                &*fs.lock(),
+                parent_inode_id.inode_number as u32,
+                ACL_DEFAULT,
+            )?
+            .expect("the default ACL should be there");
+            fs.lock().setxattr(
+                inode_number,
+                ACL_DEFAULT,
+                &default_acl.to_xattr()?,
+                XattrFlags::empty(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks whether `creds` may read (`amode` is READ) or write
+    /// (`amode` is WRITE) the extended attribute `name` of an inode.
+    ///
+    /// The `user.` attributes follow the file permissions, the
+    /// `trusted.` ones are reserved to root, the `security.` ones may
+    /// only be written by root, and the ACLs by the owner of the file.
+    fn xattr_permission(
+        &mut self,
+        creds: &Credentials,
+        inode_id: InodeId,
+        name: &str,
+        amode: Amode,
+    ) -> SysResult<()> {
+        if name.is_empty() || name.len() > XATTR_NAME_MAX as usize {
+            return Err(Errno::ERANGE);
+        }
+        let write = amode.contains(Amode::WRITE);
+        // Reading an attribute we may not access looks like reading a missing one
+        let denied = if write { Errno::EPERM } else { Errno::ENODATA };
+
+        let inode = self.get_inode(inode_id)?;
+        let is_owner = creds.is_root() || creds.euid == inode.uid;
+        if name == ACL_ACCESS || name == ACL_DEFAULT {
+            if write && !is_owner {
+                return Err(Errno::EPERM);
+            }
+            if write && name == ACL_DEFAULT && !inode.access_mode.is_directory() {
+                return Err(Errno::EACCES);
+            }
+            Ok(())
+        } else if name.starts_with("security.") {
+            if write && !creds.is_root() {
+                return Err(Errno::EPERM);
+            }
+            Ok(())
+        } else if name.starts_with("trusted.") {
+            if !creds.is_root() {
+                return Err(denied);
+            }
+            Ok(())
+        } else if name.starts_with("user.") {
+            let mode = inode.access_mode;
+            if !mode.is_regular() && !mode.is_directory() {
+                return Err(denied);
+            }
+            // In a sticky directory, only the owner may write attributes
+            if write && mode.is_directory() && mode.contains(FileType::STICKY_BIT) && !is_owner {
+                return Err(Errno::EPERM);
+            }
+            if !inode.is_access_granted(creds, amode) {
+                return Err(Errno::EACCES);
+            }
+            Ok(())
+        } else {
+            Err(Errno::EOPNOTSUPP)
+        }
+    }
+
+    /// Get the value of the extended attribute `name` of the file
+    /// `path` in `buf`, or only its size if `buf` is empty.
+    pub fn getxattr(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        name: &str,
+        buf: &mut [u8],
+    ) -> SysResult<usize> {
+        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        self.xattr_permission(creds, inode_id, name, Amode::READ)?;
+
+        let len = core::cmp::min(buf.len(), XATTR_SIZE_MAX as usize);
+        let fs = self.get_filesystem(inode_id).expect("no filesystem");
+        fs.lock()
+            .getxattr(inode_id.inode_number as u32, name, &mut buf[..len])
+    }
+
+    /// Set the value of the extended attribute `name` of the file
+    /// `path`. Setting an access ACL updates the permission bits of
+    /// the file, and removes the ACL if the bits can represent it.
+    pub fn setxattr(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        name: &str,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> SysResult<()> {
+        if value.len() > XATTR_SIZE_MAX as usize {
+            return Err(Errno::E2BIG);
+        }
+        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        self.xattr_permission(creds, inode_id, name, Amode::WRITE)?;
+
+        let fs = self.get_filesystem(inode_id).expect("no filesystem").clone();
+        let inode_number = inode_id.inode_number as u32;
+        if name != ACL_ACCESS && name != ACL_DEFAULT {
+            fs.lock().setxattr(inode_number, name, value, flags)?;
+            self.notify(inode_id, InotifyMask::IN_ATTRIB);
+            return Ok(());
+        }
+
+        // An empty value removes the ACL
+        let acl = match value.len() {
+            0 => None,
+            _ => Some(PosixAcl::from_xattr(value)?),
+        };
+        let mut mode = None;
+        let acl = match acl {
+            Some(acl) if name == ACL_ACCESS => {
+                let special_bits = self.get_inode(inode_id)?.access_mode & FileType::SPECIAL_BITS;
+                mode = Some(special_bits | acl.mode());
+                if acl.is_minimal() {
+                    None
+                } else {
+                    Some(acl)
+                }
+            }
+            acl => acl,
+        };
+        match acl {
+            Some(acl) => fs
+                .lock()
+                .setxattr(inode_number, name, &acl.to_xattr()?, flags)?,
+            None => match fs.lock().removexattr(inode_number, name) {
+                Err(Errno::ENODATA) => {}
+                ret => ret?,
+            },
+        }
+        if let Some(mode) = mode {
+            self.set_mode(inode_id, mode)?;
+        }
+        self.get_inode(inode_id)?.invalidate_acl();
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
+
+    /// Get the null terminated names of the extended attributes of
+    /// the file `path` in `buf`, or only the size of the list if `buf`
+    /// is empty. The attributes `creds` may not read are not listed.
+    pub fn listxattr(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        buf: &mut [u8],
+    ) -> SysResult<usize> {
+        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        let inode_number = inode_id.inode_number as u32;
+        let fs = self.get_filesystem(inode_id).expect("no filesystem").clone();
+
+        let size = fs.lock().listxattr(inode_number, &mut [])?;
+        let mut list = Vec::new();
+        list.try_reserve_exact(size)?;
+        list.resize(size, 0);
+        let size = fs.lock().listxattr(inode_number, &mut list)?;
+
+        let mut len = 0;
+        for name in list[..size].split(|c| *c == 0).filter(|name| !name.is_empty()) {
+            let readable = match core::str::from_utf8(name) {
+                Ok(name) => self
+                    .xattr_permission(creds, inode_id, name, Amode::empty())
+                    .is_ok(),
+                Err(_) => false,
+            };
+            if !readable {
+                continue;
+            }
+            if !buf.is_empty() {
+                let dest = buf.get_mut(len..len + name.len() + 1).ok_or(Errno::ERANGE)?;
+                dest[..name.len()].copy_from_slice(name);
+                dest[name.len()] = 0;
+            }
+            len += name.len() + 1;
+        }
+        Ok(len)
+    }
+
+    /// Remove the extended attribute `name` of the file `path`.
+    pub fn removexattr(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        name: &str,
+    ) -> SysResult<()> {
+        let entry_id = self.pathname_resolution(cwd, creds, &path)?;
+        self.check_writable(entry_id)?;
+        let inode_id = self.dcache.get_entry(&entry_id)?.inode_id;
+        self.xattr_permission(creds, inode_id, name, Amode::WRITE)?;
+
+        let fs = self.get_filesystem(inode_id).expect("no filesystem");
+        fs.lock()
+            .removexattr(inode_id.inode_number as u32, name)?;
+        self.get_inode(inode_id)?.invalidate_acl();
+        self.notify(inode_id, InotifyMask::IN_ATTRIB);
+        Ok(())
+    }
 }
 
 // pub type VfsHandler<T> = fn(VfsHandlerParams) -> SysResult<T>;
@@ -0,0 +1,387 @@
+//! POSIX access control lists
+//!
+//! An access ACL extends the owner, group and other classes of the
+//! file permission bits with entries granting permissions to named
+//! users and groups. When an ACL has named entries, the group class
+//! permission bits of the file are its mask entry: the upper bound of
+//! the permissions granted by every entry but the owner and others
+//! ones. A directory may also have a default ACL, which is given to
+//! the files created in it.
+//!
+//! The filesystems store the ACLs as the `system.posix_acl_access`
+//! and `system.posix_acl_default` extended attributes, in the format
+//! used by the xattr syscalls.
+
+use super::{Credentials, FileSystem, SysResult};
+use alloc::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use libc_binding::{gid_t, uid_t, Amode, Errno, FileType};
+
+/// Name of the extended attribute holding the access ACL
+pub const ACL_ACCESS: &str = "system.posix_acl_access";
+
+/// Name of the extended attribute holding the default ACL of a directory
+pub const ACL_DEFAULT: &str = "system.posix_acl_default";
+
+/// Version of the xattr format of the ACLs
+const ACL_XATTR_VERSION: u32 = 2;
+
+/// Size of an entry in the xattr format
+const ACL_XATTR_ENTRY_SIZE: usize = 8;
+
+/// The id of the entries which are not named
+const ACL_UNDEFINED_ID: u32 = 0xffff_ffff;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u16)]
+pub enum AclTag {
+    UserObj = 0x01,
+    User = 0x02,
+    GroupObj = 0x04,
+    Group = 0x08,
+    Mask = 0x10,
+    Other = 0x20,
+}
+
+impl TryFrom<u16> for AclTag {
+    type Error = Errno;
+    fn try_from(n: u16) -> Result<Self, Self::Error> {
+        use AclTag::*;
+        Ok(match n {
+            0x01 => UserObj,
+            0x02 => User,
+            0x04 => GroupObj,
+            0x08 => Group,
+            0x10 => Mask,
+            0x20 => Other,
+            _ => Err(Errno::EINVAL)?,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    pub tag: AclTag,
+    pub perm: Amode,
+    /// The uid or gid of the named entries
+    pub id: u32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct PosixAcl {
+    /// The entries sorted by tag, then by id
+    entries: Vec<AclEntry>,
+}
+
+impl PosixAcl {
+    /// Parse and validate an ACL in the xattr format
+    pub fn from_xattr(value: &[u8]) -> SysResult<Self> {
+        if value.len() < 4 || (value.len() - 4) % ACL_XATTR_ENTRY_SIZE != 0 {
+            return Err(Errno::EINVAL);
+        }
+        if u32::from_le_bytes(value[..4].try_into().unwrap()) != ACL_XATTR_VERSION {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        let mut entries = Vec::new();
+        entries.try_reserve_exact((value.len() - 4) / ACL_XATTR_ENTRY_SIZE)?;
+        for entry in value[4..].chunks(ACL_XATTR_ENTRY_SIZE) {
+            let tag = AclTag::try_from(u16::from_le_bytes(entry[..2].try_into().unwrap()))?;
+            let perm = u16::from_le_bytes(entry[2..4].try_into().unwrap());
+            let perm = Amode::from_bits(perm as u32).ok_or(Errno::EINVAL)?;
+            let id = match tag {
+                AclTag::User | AclTag::Group => {
+                    let id = u32::from_le_bytes(entry[4..].try_into().unwrap());
+                    if id > uid_t::max_value() as u32 {
+                        return Err(Errno::EINVAL);
+                    }
+                    id
+                }
+                _ => ACL_UNDEFINED_ID,
+            };
+            entries.push(AclEntry { tag, perm, id });
+        }
+        entries.sort_unstable_by_key(|entry| (entry.tag, entry.id));
+
+        let acl = Self { entries };
+        acl.validate()?;
+        Ok(acl)
+    }
+
+    /// Serialize the ACL in the xattr format
+    pub fn to_xattr(&self) -> SysResult<Vec<u8>> {
+        let mut value = Vec::new();
+        value.try_reserve_exact(4 + self.entries.len() * ACL_XATTR_ENTRY_SIZE)?;
+        value.extend_from_slice(&ACL_XATTR_VERSION.to_le_bytes());
+        for entry in self.entries.iter() {
+            value.extend_from_slice(&(entry.tag as u16).to_le_bytes());
+            value.extend_from_slice(&(entry.perm.bits() as u16).to_le_bytes());
+            value.extend_from_slice(&entry.id.to_le_bytes());
+        }
+        Ok(value)
+    }
+
+    /// Load the ACL `name` of an inode, None if the inode has none
+    /// or if its filesystem does not support ACLs
+    pub fn load(fs: &dyn FileSystem, inode_nbr: u32, name: &str) -> SysResult<Option<Self>> {
+        let size = match fs.getxattr(inode_nbr, name, &mut []) {
+            Err(Errno::ENODATA) | Err(Errno::EOPNOTSUPP) | Err(Errno::ENOSYS) => return Ok(None),
+            size => size?,
+        };
+        let mut value = Vec::new();
+        value.try_reserve_exact(size)?;
+        value.resize(size, 0);
+        let size = fs.getxattr(inode_nbr, name, &mut value)?;
+        Ok(Some(Self::from_xattr(&value[..size])?))
+    }
+
+    /// Check that there is exactly one owner, owning group and
+    /// others entry, that the named entries are unique, and that
+    /// there is a mask entry if there are named entries
+    fn validate(&self) -> SysResult<()> {
+        let count = |tag| self.entries.iter().filter(|e| e.tag == tag).count();
+        if count(AclTag::UserObj) != 1 || count(AclTag::GroupObj) != 1 || count(AclTag::Other) != 1
+        {
+            return Err(Errno::EINVAL);
+        }
+        let named = count(AclTag::User) + count(AclTag::Group);
+        match count(AclTag::Mask) {
+            0 if named > 0 => return Err(Errno::EINVAL),
+            0 | 1 => {}
+            _ => return Err(Errno::EINVAL),
+        }
+        // The entries are sorted, duplicates are neighbours
+        let duplicated = self.entries.windows(2).any(|pair| {
+            (pair[0].tag == AclTag::User || pair[0].tag == AclTag::Group)
+                && pair[0].tag == pair[1].tag
+                && pair[0].id == pair[1].id
+        });
+        if duplicated {
+            return Err(Errno::EINVAL);
+        }
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, tag: AclTag) -> Option<&mut AclEntry> {
+        self.entries.iter_mut().find(|entry| entry.tag == tag)
+    }
+
+    fn perm(&self, tag: AclTag) -> Option<Amode> {
+        self.entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .map(|entry| entry.perm)
+    }
+
+    /// Get the group class entry: the mask if there is one, the
+    /// owning group entry otherwise
+    fn group_class_tag(&self) -> AclTag {
+        if self.perm(AclTag::Mask).is_some() {
+            AclTag::Mask
+        } else {
+            AclTag::GroupObj
+        }
+    }
+
+    /// Check whether the ACL only has the owner, owning group and
+    /// others entries, which the file permission bits can represent
+    pub fn is_minimal(&self) -> bool {
+        self.entries.len() == 3
+    }
+
+    /// Get the file permission bits matching the ACL
+    pub fn mode(&self) -> FileType {
+        let bits = |tag| self.perm(tag).expect("ACL should be valid").bits() as u16;
+        FileType::from_bits_truncate(
+            bits(AclTag::UserObj) << 6 | bits(self.group_class_tag()) << 3 | bits(AclTag::Other),
+        )
+    }
+
+    /// Update the ACL after a change of the file permission bits
+    pub fn chmod(&mut self, mode: FileType) {
+        let group_class_tag = self.group_class_tag();
+        for (tag, perm) in [
+            (AclTag::UserObj, mode.owner_access()),
+            (group_class_tag, mode.group_access()),
+            (AclTag::Other, mode.other_access()),
+        ]
+        .iter()
+        {
+            self.entry_mut(*tag).expect("ACL should be valid").perm = *perm;
+        }
+    }
+
+    /// Restrict the inherited ACL of a new file to the permission bits
+    /// it is created with, and return the new permission bits
+    pub fn create(&mut self, mode: FileType) -> FileType {
+        let group_class_tag = self.group_class_tag();
+        for (tag, perm) in [
+            (AclTag::UserObj, mode.owner_access()),
+            (group_class_tag, mode.group_access()),
+            (AclTag::Other, mode.other_access()),
+        ]
+        .iter()
+        {
+            self.entry_mut(*tag).expect("ACL should be valid").perm &= *perm;
+        }
+        (mode & !FileType::PERMISSIONS_MASK) | self.mode()
+    }
+
+    /// Checks whether access shall be granted by the ACL for the
+    /// access permission type `access_type` to the non root `creds`,
+    /// on a file owned by `owner` and `group`
+    pub fn is_access_granted(
+        &self,
+        creds: &Credentials,
+        access_type: Amode,
+        (owner, group): (uid_t, gid_t),
+    ) -> bool {
+        let mask = self.perm(AclTag::Mask).unwrap_or(Amode::all());
+        let in_group = |gid: u32| {
+            creds.egid as u32 == gid || creds.groups.iter().any(|&supp| supp as u32 == gid)
+        };
+
+        if creds.euid == owner {
+            return self
+                .perm(AclTag::UserObj)
+                .expect("ACL should be valid")
+                .contains(access_type);
+        }
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| entry.tag == AclTag::User && entry.id == creds.euid as u32)
+        {
+            return (entry.perm & mask).contains(access_type);
+        }
+        let mut group_matched = false;
+        for entry in self.entries.iter() {
+            let matches = match entry.tag {
+                AclTag::GroupObj => in_group(group as u32),
+                AclTag::Group => in_group(entry.id),
+                _ => false,
+            };
+            if matches {
+                if (entry.perm & mask).contains(access_type) {
+                    return true;
+                }
+                group_matched = true;
+            }
+        }
+        !group_matched
+            && self
+                .perm(AclTag::Other)
+                .expect("ACL should be valid")
+                .contains(access_type)
+    }
+}
+
+/// The access ACL of an inode, as cached by the VFS
+#[derive(Debug)]
+pub enum CachedAcl {
+    NotLoaded,
+    Absent,
+    Present(PosixAcl),
+}
+
+#[cfg(test)]
+mod posix_acl_should {
+    use super::*;
+
+    const UNDEFINED: u32 = ACL_UNDEFINED_ID;
+
+    fn xattr(entries: &[(u16, u16, u32)]) -> Vec<u8> {
+        let mut value = ACL_XATTR_VERSION.to_le_bytes().to_vec();
+        for (tag, perm, id) in entries {
+            value.extend_from_slice(&tag.to_le_bytes());
+            value.extend_from_slice(&perm.to_le_bytes());
+            value.extend_from_slice(&id.to_le_bytes());
+        }
+        value
+    }
+
+    fn creds(euid: uid_t, egid: gid_t, groups: &[gid_t]) -> Credentials {
+        Credentials {
+            uid: euid,
+            gid: egid,
+            euid,
+            egid,
+            suid: euid,
+            sgid: egid,
+            groups: groups.to_vec(),
+        }
+    }
+
+    /// user::rw- user:1000:rwx group::r-- group:50:rw- mask::rw- other::---
+    fn sample() -> PosixAcl {
+        PosixAcl::from_xattr(&xattr(&[
+            (0x20, 0, UNDEFINED),
+            (0x01, 6, UNDEFINED),
+            (0x02, 7, 1000),
+            (0x04, 4, UNDEFINED),
+            (0x08, 6, 50),
+            (0x10, 6, UNDEFINED),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn be_sorted_and_serialized_back() {
+        let acl = sample();
+        assert_eq!(PosixAcl::from_xattr(&acl.to_xattr().unwrap()).unwrap(), acl);
+        assert_eq!(acl.entries[0].tag, AclTag::UserObj);
+        assert_eq!(acl.mode().bits(), 0o660);
+    }
+
+    #[test]
+    fn refuse_invalid_acls() {
+        // no mask with named entries
+        assert!(PosixAcl::from_xattr(&xattr(&[
+            (0x01, 6, UNDEFINED),
+            (0x02, 7, 1000),
+            (0x04, 4, UNDEFINED),
+            (0x20, 0, UNDEFINED),
+        ]))
+        .is_err());
+        // missing owner
+        assert!(PosixAcl::from_xattr(&xattr(&[(0x04, 4, UNDEFINED), (0x20, 0, UNDEFINED)])).is_err());
+        // duplicated named user
+        assert!(PosixAcl::from_xattr(&xattr(&[
+            (0x01, 6, UNDEFINED),
+            (0x02, 7, 1000),
+            (0x02, 4, 1000),
+            (0x04, 4, UNDEFINED),
+            (0x10, 7, UNDEFINED),
+            (0x20, 0, UNDEFINED),
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn grant_named_entries_within_the_mask() {
+        let acl = sample();
+        let owner = (10, 20);
+        assert!(acl.is_access_granted(&creds(10, 0, &[]), Amode::WRITE, owner));
+        assert!(!acl.is_access_granted(&creds(10, 0, &[]), Amode::EXECUTE, owner));
+        // named user 1000 has rwx but the mask removes x
+        assert!(acl.is_access_granted(&creds(1000, 0, &[]), Amode::READ | Amode::WRITE, owner));
+        assert!(!acl.is_access_granted(&creds(1000, 0, &[]), Amode::EXECUTE, owner));
+        // the named group grants write even if the owning group does not
+        assert!(acl.is_access_granted(&creds(1, 20, &[50]), Amode::WRITE, owner));
+        assert!(!acl.is_access_granted(&creds(1, 20, &[]), Amode::WRITE, owner));
+        // a matching group denies even when others would be granted
+        assert!(!acl.is_access_granted(&creds(1, 2, &[]), Amode::READ, owner));
+    }
+
+    #[test]
+    fn follow_chmod_and_create() {
+        let mut acl = sample();
+        acl.chmod(FileType::from_bits(0o750).unwrap());
+        assert_eq!(acl.mode().bits(), 0o750);
+        assert_eq!(acl.perm(AclTag::GroupObj), Some(Amode::READ));
+
+        let mut acl = sample();
+        let mode = acl.create(FileType::REGULAR_FILE | FileType::from_bits(0o644).unwrap());
+        assert_eq!(mode, FileType::REGULAR_FILE | FileType::from_bits(0o640).unwrap());
+        assert_eq!(acl.perm(AclTag::Mask), Some(Amode::READ));
+    }
+}
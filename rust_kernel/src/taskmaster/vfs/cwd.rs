@@ -0,0 +1,32 @@
+//! The directories from which a process resolves its pathnames
+
+use super::direntry::DirectoryEntryId;
+
+/// The working directory of a process and its root directory, where
+/// its absolute pathnames start and which `..` never goes above. Both
+/// are directory entries, which follow the renames. The ones of a
+/// process are pinned in the dcache, so that they are never removed
+/// while it uses them
+#[derive(Debug, Copy, Clone)]
+pub struct Cwd {
+    pub root: DirectoryEntryId,
+    pub dir: DirectoryEntryId,
+}
+
+impl Cwd {
+    /// The VFS root as root and working directory
+    pub fn new() -> Self {
+        // The root entry of the dcache
+        let root = DirectoryEntryId::new(2);
+        Self { root, dir: root }
+    }
+
+    /// The same root with `dir` as working directory, used to resolve
+    /// the relative paths of the *at() syscalls
+    pub fn with_dir(&self, dir: DirectoryEntryId) -> Self {
+        Self {
+            root: self.root,
+            dir,
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use super::direntry::{DirectoryEntry, DirectoryEntryId};
-use super::path::Path;
+use super::path::{Filename, Path};
 use super::SysResult;
 use fallible_collections::btree::BTreeMap;
 use itertools::unfold;
@@ -47,22 +47,34 @@ impl Dcache {
 
         entry.id = id;
         entry.parent_id = parent.unwrap_or(self.root_id); //eeeeeh yeah
+        let filename = entry.filename;
         if self.d_entries.contains_key(&id) {
             return Err(EEXIST);
         }
         self.d_entries.try_insert(id, entry)?;
 
         if let Some(parent) = parent {
+            let index = self.child_index_after(parent, filename.as_str())?;
             let parent = match self.d_entries.get_mut(&parent) {
                 None => return Err(ENOENT),
                 Some(parent) => parent,
             };
 
-            parent.add_entry(id)?;
+            parent.insert_entry(index, id)?;
         }
         Ok(id)
     }
 
+    /// Get the index, among the children of the directory `dir_id`,
+    /// of the first one whose filename is greater than `name`
+    fn child_index_after(&self, dir_id: DirectoryEntryId, name: &str) -> SysResult<usize> {
+        let entries = self.get_entry(&dir_id)?.get_directory()?.entries_slice();
+        Ok(entries.partition_point(|id| {
+            self.get_entry(id)
+                .map_or(false, |child| child.filename.as_str() <= name)
+        }))
+    }
+
     pub fn remove_entry(&mut self, id: DirectoryEntryId) -> SysResult<DirectoryEntry> {
         let parent_id;
         {
@@ -71,12 +83,10 @@ impl Dcache {
                 Some(entry) => entry,
             };
 
-            if entry.is_directory() {
-                if !entry.is_directory_empty()? {
-                    return Err(ENOTEMPTY);
-                } else if entry.is_mounted()? {
-                    return Err(EBUSY)?;
-                }
+            if entry.is_directory() && !entry.is_directory_empty()? {
+                return Err(ENOTEMPTY);
+            } else if entry.is_mounted()? || entry.is_pinned() {
+                return Err(EBUSY)?;
             }
             parent_id = entry.parent_id;
         }
@@ -89,6 +99,24 @@ impl Dcache {
         })
     }
 
+    /// Pin the entry `id`, the root or the working directory of a
+    /// process, so that it is not removed. The root entry never is, its
+    /// pins are not counted
+    pub fn pin(&mut self, id: DirectoryEntryId) -> SysResult<()> {
+        if id != self.root_id {
+            self.get_entry_mut(&id)?.pin();
+        }
+        Ok(())
+    }
+
+    pub fn unpin(&mut self, id: DirectoryEntryId) {
+        if id != self.root_id {
+            if let Ok(entry) = self.get_entry_mut(&id) {
+                entry.unpin();
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn dentry_path(&self, id: DirectoryEntryId) -> SysResult<Path> {
         let mut current_id = id;
@@ -152,21 +180,54 @@ impl Dcache {
         }))
     }
 
+    /// Iterate in filename order over the children of the directory
+    /// `dir_id` whose filename is greater than `name`, or all of them
+    pub fn children_after<'a>(
+        &'a self,
+        dir_id: DirectoryEntryId,
+        name: Option<&str>,
+    ) -> SysResult<impl Iterator<Item = &'a DirectoryEntry>> {
+        let index = match name {
+            Some(name) => self.child_index_after(dir_id, name)?,
+            None => 0,
+        };
+        let entries = self.get_entry(&dir_id)?.get_directory()?.entries_slice();
+        Ok(entries[index..]
+            .iter()
+            .filter_map(move |id| self.get_entry(id).ok()))
+    }
+
+    /// Move the entry `id` in the directory `new_parent` under the
+    /// name `filename`
     pub fn move_dentry(
         &mut self,
         id: DirectoryEntryId,
         new_parent: DirectoryEntryId,
+        filename: Filename,
     ) -> SysResult<DirectoryEntryId> {
-        let parent_id;
-        {
-            let entry = self.d_entries.get(&id).ok_or(ENOENT)?;
-            parent_id = entry.parent_id;
-        }
+        // The id is kept, as the children and the open directories refer to it
+        let parent_id = self.d_entries.get(&id).ok_or(ENOENT)?.parent_id;
+        // Nothing can fail once the entry left its parent
+        self.d_entries
+            .get_mut(&new_parent)
+            .ok_or(ENOENT)?
+            .get_directory_mut()?
+            .reserve_entry()?;
         if let Some(parent_dir) = self.d_entries.get_mut(&parent_id) {
             parent_dir.remove_entry(id)?;
         }
-        let entry = self.d_entries.remove(&id).ok_or(ENOENT)?;
-        self.add_entry(Some(new_parent), entry)
+        let index = self.child_index_after(new_parent, filename.as_str())?;
+        self.d_entries
+            .get_mut(&new_parent)
+            .ok_or(ENOENT)?
+            .insert_entry(index, id)?;
+        let entry = self
+            .d_entries
+            .get_mut(&id)
+            .expect("moved entry should be there");
+        entry.parent_id = new_parent;
+        entry.set_filename(filename);
+        Ok(id)
     }
 
     fn get_available_id(&self) -> DirectoryEntryId {
@@ -0,0 +1,192 @@
+//! The file operation of the open directories, which are read with getdents64
+
+use super::{Credentials, FileOperation, IpcResult, SysResult};
+use super::{DirectoryEntryId, Filename, InodeId, Path, VFS};
+
+use core::convert::TryFrom;
+use libc_binding::{
+    gid_t, off_t, statfs, uid_t, Errno, FileType, Whence, DT_BLK, DT_CHR, DT_DIR, DT_FIFO, DT_LNK,
+    DT_REG, DT_SOCK, DT_UNKNOWN,
+};
+
+/// Offset of d_name in a linux_dirent64, after d_ino, d_off,
+/// d_reclen and d_type
+const DIRENT64_NAME_OFFSET: usize = 19;
+
+/// The linux_dirent64 are aligned on their d_ino
+const DIRENT64_ALIGN: usize = 8;
+
+/// Return the size of the linux_dirent64 of the filename `name`
+pub fn dirent64_len(name: &str) -> usize {
+    let len = DIRENT64_NAME_OFFSET + name.len() + 1;
+    (len + DIRENT64_ALIGN - 1) & !(DIRENT64_ALIGN - 1)
+}
+
+/// Write the linux_dirent64 of `name` at the start of `buf`, which
+/// must hold dirent64_len(name) bytes, return its size
+pub fn write_dirent64(buf: &mut [u8], ino: u64, off: i64, d_type: u8, name: &str) -> usize {
+    let reclen = dirent64_len(name);
+    let record = &mut buf[..reclen];
+    record[0..8].copy_from_slice(&ino.to_ne_bytes());
+    record[8..16].copy_from_slice(&off.to_ne_bytes());
+    record[16..18].copy_from_slice(&(reclen as u16).to_ne_bytes());
+    record[18] = d_type;
+    let name_end = DIRENT64_NAME_OFFSET + name.len();
+    record[DIRENT64_NAME_OFFSET..name_end].copy_from_slice(name.as_bytes());
+    // The \0 and the padding
+    for b in record[name_end..].iter_mut() {
+        *b = 0;
+    }
+    reclen
+}
+
+/// Return the d_type of a file of mode `mode`
+pub fn d_type(mode: FileType) -> u8 {
+    (match mode & FileType::S_IFMT {
+        FileType::REGULAR_FILE => DT_REG,
+        FileType::DIRECTORY => DT_DIR,
+        FileType::SYMBOLIC_LINK => DT_LNK,
+        FileType::FIFO => DT_FIFO,
+        FileType::UNIX_SOCKET => DT_SOCK,
+        FileType::CHARACTER_DEVICE => DT_CHR,
+        FileType::BLOCK_DEVICE => DT_BLK,
+        _ => DT_UNKNOWN,
+    }) as u8
+}
+
+/// The number of "." and ".." entries, given before the others
+const DOT_ENTRIES: u64 = 2;
+
+/// The position of an open directory. The entries are given in
+/// filename order, so that the creations and the removals between
+/// two reads neither skip nor repeat the others
+#[derive(Debug, Default)]
+pub struct DirectoryCursor {
+    /// The last filename given, after "." and ".."
+    last: Option<Filename>,
+    /// The number of entries given, the d_off of the last one
+    position: u64,
+}
+
+impl DirectoryCursor {
+    /// The number of "." and ".." entries already given
+    pub fn dots_given(&self) -> usize {
+        core::cmp::min(self.position, DOT_ENTRIES) as usize
+    }
+
+    /// The last filename given, after "." and ".."
+    pub fn last(&self) -> Option<&str> {
+        self.last.as_ref().map(|last| last.as_str())
+    }
+
+    /// Give the entry `name`, return its d_off
+    pub fn advance(&mut self, name: &str) -> SysResult<i64> {
+        if self.position >= DOT_ENTRIES {
+            self.last = Some(Filename::try_from(name)?);
+        }
+        self.position += 1;
+        Ok(self.position as i64)
+    }
+}
+
+/// An open directory, of any filesystem
+#[derive(Debug)]
+pub struct DirectoryFileOperation {
+    inode_id: InodeId,
+    /// The directory entry at the opening, a dynamic filesystem may
+    /// recreate it, then it is found again with its path
+    entry_id: DirectoryEntryId,
+    path: Path,
+    cursor: DirectoryCursor,
+    /// The root of the mount which the directory was opened through
+    mount_root: Option<DirectoryEntryId>,
+}
+
+impl DirectoryFileOperation {
+    pub fn new(inode_id: InodeId, entry_id: DirectoryEntryId, path: Path) -> Self {
+        Self {
+            inode_id,
+            entry_id,
+            path,
+            cursor: Default::default(),
+            mount_root: None,
+        }
+    }
+}
+
+impl FileOperation for DirectoryFileOperation {
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn set_mount_root(&mut self, mount_root: DirectoryEntryId) -> bool {
+        self.mount_root = Some(mount_root);
+        true
+    }
+
+    fn read(&mut self, _buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        Err(Errno::EISDIR)
+    }
+
+    fn write(&mut self, _buf: &[u8]) -> SysResult<IpcResult<u32>> {
+        Err(Errno::EISDIR)
+    }
+
+    /// Only the rewind is supported, the d_off are not seekable
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        match (whence, offset) {
+            (Whence::SeekSet, 0) => {
+                self.cursor = Default::default();
+                Ok(0)
+            }
+            (Whence::SeekCur, 0) => Ok(self.cursor.position as off_t),
+            _ => Err(Errno::EINVAL),
+        }
+    }
+
+    fn getdents(&mut self, buf: &mut [u8]) -> SysResult<u32> {
+        let mut vfs = VFS.lock();
+        self.entry_id = vfs.open_directory_entry(self.entry_id, self.inode_id, &self.path)?;
+        vfs.getdents(self.entry_id, &mut self.cursor, buf)
+    }
+
+    /// The directory entry follows the renames of the directory
+    fn get_directory_entry(&mut self) -> SysResult<DirectoryEntryId> {
+        let mut vfs = VFS.lock();
+        self.entry_id = vfs.open_directory_entry(self.entry_id, self.inode_id, &self.path)?;
+        self.path = vfs.dentry_path(self.entry_id)?;
+        Ok(self.entry_id)
+    }
+
+    fn fstatfs(&mut self, buf: &mut statfs) -> SysResult<u32> {
+        VFS.lock().fstatfs(self.inode_id, buf)?;
+        Ok(0)
+    }
+
+    fn fsync(&mut self) -> SysResult<u32> {
+        VFS.lock().fsync(self.inode_id)?;
+        Ok(0)
+    }
+
+    fn fchmod(&mut self, creds: &Credentials, mode: FileType) -> SysResult<u32> {
+        VFS.lock()
+            .fchmod(creds, self.mount_root, self.inode_id, mode)?;
+        Ok(0)
+    }
+
+    fn fchown(&mut self, creds: &Credentials, owner: uid_t, group: gid_t) -> SysResult<u32> {
+        VFS.lock()
+            .fchown(creds, self.mount_root, self.inode_id, owner, group)?;
+        Ok(0)
+    }
+}
+
+impl Drop for DirectoryFileOperation {
+    fn drop(&mut self) {
+        let mut vfs = VFS.lock();
+        // rmdir does not wait for the last close
+        if vfs.get_inode(self.inode_id).is_ok() {
+            vfs.close_file_operation(self.inode_id);
+        }
+    }
+}
@@ -3,8 +3,7 @@ use super::path::{Filename, Path};
 use super::Incrementor;
 use super::SysResult;
 use alloc::vec::Vec;
-use fallible_collections::FallibleVec;
-use libc_binding::{c_char, dirent, Errno::*};
+use libc_binding::Errno::*;
 use try_clone_derive::TryClone;
 
 #[derive(Debug, Clone, TryClone)]
@@ -14,6 +13,12 @@ pub struct DirectoryEntry {
     pub id: DirectoryEntryId,
     pub parent_id: DirectoryEntryId,
     pub inode_id: InodeId,
+    /// The root of the filesystem mounted on the entry, a file may be
+    /// the mount point of a bind mount
+    mounted: Option<DirectoryEntryId>,
+    /// The number of processes which have the entry as root or working
+    /// directory, it is not removed while there are some
+    pins: usize,
 }
 
 pub struct DirectoryEntryBuilder {
@@ -92,21 +97,13 @@ impl DirectoryEntryBuilder {
             id: self.id.unwrap_or(DirectoryEntryId::new(0)),
             parent_id: self.parent_id.unwrap_or(DirectoryEntryId::new(0)),
             inode_id: self.inode_id.expect("no inode_id given"),
+            mounted: None,
+            pins: 0,
         }
     }
 }
 
 impl DirectoryEntry {
-    pub fn dirent(&self) -> dirent {
-        let mut d = dirent {
-            d_name: self.filename.0,
-            d_ino: self.inode_id.inode_number as u32,
-        };
-        // assure the \0 at end of filename
-        d.d_name[self.filename.len()] = '\0' as c_char;
-        d
-    }
-
     // ---------- BUILDER PATTERN ------------
     pub fn set_filename(&mut self, filename: Filename) -> &mut Self {
         self.filename = filename;
@@ -149,11 +146,13 @@ impl DirectoryEntry {
     }
 
     pub fn set_mounted(&mut self, on: DirectoryEntryId) -> SysResult<()> {
-        self.inner.set_mounted(on)
+        self.mounted = Some(on);
+        Ok(())
     }
 
     pub fn unset_mounted(&mut self) -> SysResult<()> {
-        self.inner.unset_mounted()
+        self.mounted = None;
+        Ok(())
     }
 
     pub fn root_entry() -> Self {
@@ -197,17 +196,33 @@ impl DirectoryEntry {
     }
 
     pub fn is_mounted(&self) -> SysResult<bool> {
-        self.inner.is_mounted()
+        Ok(self.mounted.is_some())
     }
 
     pub fn get_mountpoint_entry(&self) -> Option<DirectoryEntryId> {
-        self.inner.get_mountpoint_entry()
+        self.mounted
     }
 
-    pub fn add_entry(&mut self, entry: DirectoryEntryId) -> SysResult<()> {
+    pub fn pin(&mut self) {
+        self.pins += 1;
+    }
+
+    pub fn unpin(&mut self) {
+        debug_assert!(self.pins > 0, "unpin of an entry which is not pinned");
+        self.pins = self.pins.saturating_sub(1);
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pins != 0
+    }
+
+    /// Insert the child `entry` at `index`, which keeps the children
+    /// sorted by filename
+    pub fn insert_entry(&mut self, index: usize, entry: DirectoryEntryId) -> SysResult<()> {
         let directory = self.inner.get_directory_mut()?;
 
-        directory.entries.try_push(entry)?;
+        directory.entries.try_reserve(1)?;
+        directory.entries.insert(index, entry);
         Ok(())
     }
 
@@ -218,7 +233,8 @@ impl DirectoryEntry {
             Some(index) => index,
             None => return Err(ENOENT),
         };
-        directory.entries.swap_remove(index);
+        // The order of the children is kept
+        directory.entries.remove(index);
         Ok(())
     }
 }
@@ -231,6 +247,8 @@ impl Default for DirectoryEntry {
             id: DirectoryEntryId::new(0),
             parent_id: DirectoryEntryId::new(0),
             inode_id: InodeId::new(0, None),
+            mounted: None,
+            pins: 0,
         }
     }
 }
@@ -262,10 +280,11 @@ impl Display for DirectoryEntryId {
     }
 }
 
+/// The children of a directory, sorted by filename so that a reader
+/// can resume after the last filename it got
 #[derive(Debug, Clone, TryClone)]
 pub struct EntryDirectory {
     entries: Vec<DirectoryEntryId>,
-    mounted: Option<DirectoryEntryId>,
 }
 
 impl EntryDirectory {
@@ -277,24 +296,18 @@ impl EntryDirectory {
         self.entries.iter()
     }
 
-    pub fn is_mounted(&self) -> bool {
-        self.mounted.is_some()
+    /// The children, in filename order
+    pub fn entries_slice(&self) -> &[DirectoryEntryId] {
+        &self.entries
     }
 
-    pub fn clear_entries(&mut self) {
-        self.entries.truncate(0);
+    /// Make room for one more child
+    pub fn reserve_entry(&mut self) -> SysResult<()> {
+        Ok(self.entries.try_reserve(1)?)
     }
 
-    pub fn get_mountpoint_entry(&self) -> Option<DirectoryEntryId> {
-        self.mounted
-    }
-
-    pub fn unset_mounted(&mut self) {
-        self.mounted = None
-    }
-
-    pub fn set_mounted(&mut self, on: DirectoryEntryId) {
-        self.mounted = Some(on)
+    pub fn clear_entries(&mut self) {
+        self.entries.truncate(0);
     }
 }
 
@@ -302,7 +315,6 @@ impl Default for EntryDirectory {
     fn default() -> Self {
         Self {
             entries: Vec::new(),
-            mounted: None,
         }
     }
 }
@@ -368,26 +380,6 @@ impl DirectoryEntryInner {
             _ => return None,
         }
     }
-
-    pub fn is_mounted(&self) -> SysResult<bool> {
-        Ok(self.get_directory()?.is_mounted())
-    }
-
-    pub fn get_mountpoint_entry(&self) -> Option<DirectoryEntryId> {
-        self.get_directory()
-            .ok()
-            .and_then(|d| d.get_mountpoint_entry())
-    }
-
-    pub fn set_mounted(&mut self, on: DirectoryEntryId) -> SysResult<()> {
-        self.get_directory_mut()?.set_mounted(on);
-        Ok(())
-    }
-
-    pub fn unset_mounted(&mut self) -> SysResult<()> {
-        self.get_directory_mut()?.unset_mounted();
-        Ok(())
-    }
 }
 
 #[cfg(test)]
@@ -9,7 +9,7 @@ use super::{Driver, FileOperation, Inode, InodeData, IpcResult, PATH_MAX, VFS};
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Display};
-use libc_binding::{gid_t, statfs, uid_t, utimbuf, Errno, FileType};
+use libc_binding::{gid_t, statfs, uid_t, utimbuf, Errno, FileType, XattrFlags};
 use try_clone_derive::TryClone;
 
 pub mod dead;
@@ -18,6 +18,12 @@ pub use dead::DeadFileSystem;
 pub mod ext2fs;
 pub use ext2fs::Ext2fs;
 
+pub mod fatfs;
+pub use fatfs::Fatfs;
+
+pub mod iso9660fs;
+pub use iso9660fs::Iso9660fs;
+
 pub mod devfs;
 pub use devfs::Devfs;
 
@@ -73,6 +79,17 @@ pub trait FileSystem: Send + Debug {
         Err(Errno::ENOSYS)
     }
 
+    /// Write back everything the filesystem keeps in memory and flush
+    /// its device
+    fn sync(&mut self) -> SysResult<()> {
+        Ok(())
+    }
+
+    /// Called when the last file operation on the inode is closed
+    fn close(&mut self, _inode_nbr: u32) -> SysResult<()> {
+        Ok(())
+    }
+
     fn create(
         &mut self,
         _filename: &str,
@@ -145,6 +162,32 @@ pub trait FileSystem: Send + Debug {
     fn utime(&mut self, _inode_number: u32, _times: Option<&utimbuf>) -> SysResult<()> {
         Err(Errno::ENOSYS)
     }
+
+    /// Get the value of the extended attribute `name` in `buf`, or
+    /// only its size if `buf` is empty
+    fn getxattr(&self, _inode_nbr: u32, _name: &str, _buf: &mut [u8]) -> SysResult<usize> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    fn setxattr(
+        &mut self,
+        _inode_nbr: u32,
+        _name: &str,
+        _value: &[u8],
+        _flags: XattrFlags,
+    ) -> SysResult<()> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Get the null terminated names of the extended attributes in
+    /// `buf`, or only the size of the list if `buf` is empty
+    fn listxattr(&self, _inode_nbr: u32, _buf: &mut [u8]) -> SysResult<usize> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    fn removexattr(&mut self, _inode_nbr: u32, _name: &str) -> SysResult<()> {
+        Err(Errno::EOPNOTSUPP)
+    }
     // fn lookup: Option<fn(&mut Superblock)>,
     // fn create: Option<fn(&mut Superblock)>,
     // fn unlink: Option<fn(&mut Superblock)>,
@@ -179,6 +222,8 @@ impl Display for FileSystemSource {
 #[derive(Debug, Copy, Clone)]
 pub enum FileSystemType {
     Ext2,
+    Vfat,
+    Iso9660,
     Procfs,
     Devfs,
 }
@@ -187,6 +232,8 @@ impl Display for FileSystemType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::Ext2 => write!(f, "ext2"),
+            Self::Vfat => write!(f, "vfat"),
+            Self::Iso9660 => write!(f, "iso9660"),
             Self::Procfs => write!(f, "proc"),
             Self::Devfs => write!(f, "dev"),
         }
@@ -35,6 +35,9 @@ pub use fb::{DevFb, FbDevice};
 pub mod sda;
 pub use sda::{BiosInt13hInstance, DiskDriver, DiskFileOperation, DiskWrapper, IdeAtaInstance};
 
+pub mod loop_device;
+pub use loop_device::{LoopDevice, LoopDriver, NBR_LOOP_DEVICES, NBR_LOOP_PARTITIONS};
+
 #[derive(Debug)]
 pub struct Devfs {
     fs_id: FileSystemId,
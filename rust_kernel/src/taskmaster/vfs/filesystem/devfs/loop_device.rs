@@ -0,0 +1,370 @@
+//! This file contains the loop devices, which expose a regular file as a disk
+
+use super::InodeId;
+use super::SysResult;
+use super::{Driver, FileOperation, FileSystem, IpcResult};
+
+use crate::taskmaster::scheduler::Scheduler;
+use crate::taskmaster::vfs::VFS;
+
+use alloc::sync::Arc;
+use core::cmp::min;
+use libc_binding::{
+    c_char, dev_t, loop_info, off_t, Errno, IoctlCmd, OpenFlags, Whence, LO_FLAGS_PARTSCAN,
+    LO_FLAGS_READ_ONLY, LO_NAME_SIZE,
+};
+use mbr::Mbr;
+use sync::dead_mutex::DeadMutex;
+
+/// Number of /dev/loopN devices
+pub const NBR_LOOP_DEVICES: usize = 4;
+
+/// Number of primary partitions of a MBR, /dev/loopNp1 to /dev/loopNp4
+pub const NBR_LOOP_PARTITIONS: usize = 4;
+
+const MBR_SIZE: usize = 512;
+
+/// The regular file attached to a loop device
+#[derive(Debug)]
+struct Backing {
+    /// Keep the file open while it is attached, like a file descriptor
+    file_operation: Arc<DeadMutex<dyn FileOperation>>,
+    /// The data goes directly through the filesystem of the file, as
+    /// the VFS is already locked when a filesystem mounted on the
+    /// loop device reads it
+    filesystem: Arc<DeadMutex<dyn FileSystem>>,
+    inode_id: InodeId,
+    /// The size of the file when it was attached, the loop device
+    /// never grows it
+    size: u64,
+    read_only: bool,
+    /// The device containing the file
+    device: dev_t,
+    name: [c_char; LO_NAME_SIZE as usize],
+}
+
+impl Backing {
+    /// Get the file of the file descriptor `fd` of the current process
+    fn from_fd(scheduler: &Scheduler, fd: u32) -> SysResult<Self> {
+        let file_descriptor = scheduler
+            .current_thread_group_running()
+            .file_descriptor_interface
+            .get_file_descriptor(fd)?;
+        let file_operation = file_descriptor.get_file_operation();
+
+        // The file operation of the loop device itself is locked by the ioctl
+        let inode_id = file_operation
+            .try_lock()
+            .ok_or(Errno::EINVAL)?
+            .get_inode_id()
+            .map_err(|_| Errno::EINVAL)?;
+
+        let mut vfs = VFS.lock();
+        let inode = vfs.get_inode(inode_id)?;
+        if !inode.is_regular() {
+            return Err(Errno::EINVAL);
+        }
+
+        let mut name = [0; LO_NAME_SIZE as usize];
+        // A too long name is truncated
+        if file_descriptor
+            .get_open_path()
+            .write_path_in_buffer(&mut name)
+            .is_err()
+        {
+            name[LO_NAME_SIZE as usize - 1] = 0;
+        }
+        Ok(Self {
+            filesystem: inode.filesystem.clone(),
+            inode_id,
+            size: inode.size,
+            read_only: !file_descriptor.get_flags().is_open_for_write(),
+            device: inode.stat()?.st_dev,
+            name,
+            file_operation,
+        })
+    }
+}
+
+/// A loop device, shared by the drivers of /dev/loopN and of its partitions
+#[derive(Debug)]
+pub struct LoopDevice {
+    number: usize,
+    backing: Option<Backing>,
+    /// The start and the size of the MBR partitions found when the
+    /// file was attached
+    partitions: [Option<(u64, u64)>; NBR_LOOP_PARTITIONS],
+    /// Number of open file operations on the device and its partitions
+    nbr_open: usize,
+}
+
+impl LoopDevice {
+    pub fn new(number: usize) -> Self {
+        Self {
+            number,
+            backing: None,
+            partitions: [None; NBR_LOOP_PARTITIONS],
+            nbr_open: 0,
+        }
+    }
+
+    /// Return the attached file and the start and size of the whole
+    /// device or of one of its partitions
+    fn window(&self, partition: Option<usize>) -> SysResult<(&Backing, u64, u64)> {
+        let backing = self.backing.as_ref().ok_or(Errno::ENXIO)?;
+        let (start, size) = match partition {
+            None => (0, backing.size),
+            Some(i) => self.partitions[i].ok_or(Errno::ENXIO)?,
+        };
+        Ok((backing, start, size))
+    }
+
+    fn attach(&mut self, backing: Backing) -> SysResult<()> {
+        if self.backing.is_some() {
+            return Err(Errno::EBUSY);
+        }
+        self.backing = Some(backing);
+        if let Err(e) = self.scan_partitions() {
+            self.backing = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Detach the file, the caller keeps the only open file operation
+    fn detach(&mut self) -> SysResult<Backing> {
+        if self.backing.is_none() {
+            return Err(Errno::ENXIO);
+        }
+        if self.nbr_open > 1 {
+            return Err(Errno::EBUSY);
+        }
+        self.partitions = [None; NBR_LOOP_PARTITIONS];
+        Ok(self.backing.take().expect("no backing file"))
+    }
+
+    /// Read the MBR of the file, a file formatted as a whole has
+    /// garbage instead of the partition entries
+    fn scan_partitions(&mut self) -> SysResult<()> {
+        let (backing, _, size) = self.window(None)?;
+        let mut partitions = [None; NBR_LOOP_PARTITIONS];
+        if size >= MBR_SIZE as u64 {
+            let mut sector = [0; MBR_SIZE];
+            let mut offset = 0;
+            backing.filesystem.lock().read(
+                backing.inode_id.inode_number,
+                &mut offset,
+                &mut sector,
+            )?;
+            let mbr = unsafe { Mbr::new(&sector) };
+
+            for (part, partition) in mbr.parts.iter().zip(partitions.iter_mut()) {
+                let start = part.start as u64 * MBR_SIZE as u64;
+                let part_size = part.size as u64 * MBR_SIZE as u64;
+                if part.is_used() && part_size != 0 && start + part_size <= size {
+                    *partition = Some((start, part_size));
+                }
+            }
+        }
+        self.partitions = partitions;
+        Ok(())
+    }
+}
+
+/// The driver of /dev/loopN or of its partition /dev/loopNpM
+#[derive(Debug)]
+pub struct LoopDriver {
+    device: Arc<DeadMutex<LoopDevice>>,
+    /// The index of the partition, None for the whole device
+    partition: Option<usize>,
+    inode_id: InodeId,
+}
+
+impl LoopDriver {
+    pub fn new(device: Arc<DeadMutex<LoopDevice>>, partition: Option<usize>) -> Self {
+        Self {
+            device,
+            partition,
+            inode_id: Default::default(),
+        }
+    }
+}
+
+impl Driver for LoopDriver {
+    fn open(
+        &mut self,
+        _flags: OpenFlags,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        {
+            let mut device = self.device.lock();
+            // The whole device can be opened without file, to attach one
+            if self.partition.is_some() {
+                device.window(self.partition)?;
+            }
+            // The file operation gives it back when dropped
+            device.nbr_open += 1;
+        }
+        Ok(IpcResult::Done(Arc::try_new(DeadMutex::new(
+            LoopFileOperation {
+                device: self.device.clone(),
+                partition: self.partition,
+                offset: 0,
+                inode_id: self.inode_id,
+            },
+        ))?))
+    }
+
+    fn set_inode_id(&mut self, inode_id: InodeId) {
+        self.inode_id = inode_id;
+    }
+}
+
+/// An open loop device or partition
+#[derive(Debug)]
+pub struct LoopFileOperation {
+    device: Arc<DeadMutex<LoopDevice>>,
+    partition: Option<usize>,
+    offset: u64,
+    inode_id: InodeId,
+}
+
+impl FileOperation for LoopFileOperation {
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        let device = self.device.lock();
+        let (backing, start, size) = device.window(self.partition)?;
+        let len = min(buf.len() as u64, size.saturating_sub(self.offset)) as usize;
+
+        let mut offset = start + self.offset;
+        let count = backing.filesystem.lock().read(
+            backing.inode_id.inode_number,
+            &mut offset,
+            &mut buf[..len],
+        )?;
+        self.offset += count as u64;
+        Ok(IpcResult::Done(count))
+    }
+
+    fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
+        let device = self.device.lock();
+        let (backing, start, size) = device.window(self.partition)?;
+        if backing.read_only {
+            return Err(Errno::EROFS);
+        }
+        let len = min(buf.len() as u64, size.saturating_sub(self.offset)) as usize;
+        if len == 0 && !buf.is_empty() {
+            return Err(Errno::ENOSPC);
+        }
+
+        let mut offset = start + self.offset;
+        let (count, _) = backing.filesystem.lock().write(
+            backing.inode_id.inode_number,
+            &mut offset,
+            &buf[..len],
+        )?;
+        self.offset += count as u64;
+        Ok(IpcResult::Done(count))
+    }
+
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        if offset == core::i64::MIN {
+            // volontary trash i64 min value to avoid -offset ==
+            // offset
+            return Err(Errno::EINVAL);
+        }
+        let (_, _, size) = self.device.lock().window(self.partition)?;
+        let new_offset = match whence {
+            Whence::SeekCur => {
+                if offset < 0 {
+                    self.offset
+                        .checked_sub((-offset) as u64)
+                        .ok_or(Errno::EINVAL)?
+                } else {
+                    self.offset
+                        .checked_add(offset as u64)
+                        .ok_or(Errno::EINVAL)?
+                }
+            }
+            Whence::SeekSet => {
+                if offset < 0 {
+                    return Err(Errno::EINVAL);
+                }
+                offset as u64
+            }
+            Whence::SeekEnd => {
+                if offset > 0 {
+                    return Err(Errno::EINVAL);
+                }
+                size.checked_sub((-offset) as u64).ok_or(Errno::EINVAL)?
+            }
+        };
+        if new_offset > size {
+            return Err(Errno::EINVAL);
+        }
+        self.offset = new_offset;
+        Ok(self.offset as off_t)
+    }
+
+    fn fsync(&mut self) -> SysResult<u32> {
+        let device = self.device.lock();
+        let (backing, _, _) = device.window(self.partition)?;
+        backing.filesystem.lock().sync()?;
+        Ok(0)
+    }
+
+    fn ioctl(&mut self, scheduler: &Scheduler, cmd: IoctlCmd, arg: u32) -> SysResult<u32> {
+        match cmd {
+            IoctlCmd::LOOP_SET_FD => {
+                let backing = Backing::from_fd(scheduler, arg)?;
+                self.device.lock().attach(backing)?;
+                Ok(0)
+            }
+            IoctlCmd::LOOP_CLR_FD => {
+                let backing = self.device.lock().detach()?;
+                // Closing the file may need the VFS, which does not
+                // need the device
+                drop(backing);
+                self.offset = 0;
+                Ok(0)
+            }
+            IoctlCmd::LOOP_GET_STATUS => {
+                let rdevice = VFS.lock().get_inode(self.inode_id)?.stat()?.st_rdev;
+                let info = {
+                    let v = scheduler
+                        .current_thread()
+                        .unwrap_process()
+                        .get_virtual_allocator();
+
+                    v.make_checked_ref_mut(arg as *mut loop_info)
+                }?;
+
+                let device = self.device.lock();
+                let (backing, _, _) = device.window(None)?;
+                let mut flags = LO_FLAGS_PARTSCAN;
+                if backing.read_only {
+                    flags |= LO_FLAGS_READ_ONLY;
+                }
+                *info = loop_info {
+                    lo_number: device.number as i32,
+                    lo_device: backing.device,
+                    lo_inode: backing.inode_id.inode_number,
+                    lo_rdevice: rdevice,
+                    lo_offset: 0,
+                    lo_flags: flags as i32,
+                    lo_name: backing.name,
+                };
+                Ok(0)
+            }
+            _ => Err(Errno::EINVAL),
+        }
+    }
+}
+
+impl Drop for LoopFileOperation {
+    fn drop(&mut self) {
+        self.device.lock().nbr_open -= 1;
+    }
+}
@@ -4,6 +4,7 @@ use super::FileOperation;
 use super::InodeId;
 use super::IpcResult;
 use super::SysResult;
+use crate::drivers::storage::ide_ata_controller::Rank;
 use crate::drivers::storage::{
     BlockIo, DiskResult, NbrSectors, Sector, BIOS_INT13H, IDE_ATA_CONTROLLER, SECTOR_MASK,
     SECTOR_SHIFT, SECTOR_SIZE,
@@ -94,8 +95,10 @@ impl BlockIo for BiosInt13hInstance {
     }
 }
 
+/// An IDE drive, selected on the controller before each access as
+/// several drives may be in use
 #[derive(Debug, Copy, Clone)]
-pub struct IdeAtaInstance;
+pub struct IdeAtaInstance(pub Rank);
 
 impl BlockIo for IdeAtaInstance {
     fn read(
@@ -105,10 +108,9 @@ impl BlockIo for IdeAtaInstance {
         buf: *mut u8,
     ) -> DiskResult<NbrSectors> {
         unsafe {
-            IDE_ATA_CONTROLLER
-                .as_mut()
-                .unwrap()
-                .read(start_sector, nbr_sectors, buf)
+            let controller = IDE_ATA_CONTROLLER.as_mut().unwrap();
+            controller.select_drive(self.0)?;
+            controller.read(start_sector, nbr_sectors, buf)
         }
     }
 
@@ -119,16 +121,30 @@ impl BlockIo for IdeAtaInstance {
         buf: *const u8,
     ) -> DiskResult<NbrSectors> {
         unsafe {
-            IDE_ATA_CONTROLLER
-                .as_mut()
-                .unwrap()
-                .write(start_sector, nbr_sectors, buf)
+            let controller = IDE_ATA_CONTROLLER.as_mut().unwrap();
+            controller.select_drive(self.0)?;
+            controller.write(start_sector, nbr_sectors, buf)
+        }
+    }
+
+    fn flush(&mut self) -> DiskResult<()> {
+        unsafe {
+            let controller = IDE_ATA_CONTROLLER.as_mut().unwrap();
+            controller.select_drive(self.0)?;
+            controller.flush()
         }
     }
 
     /// return the size of the disk
     fn disk_size(&self) -> u64 {
-        unimplemented!()
+        unsafe {
+            IDE_ATA_CONTROLLER
+                .as_ref()
+                .unwrap()
+                .drive_capacity(self.0)
+                .map(|capacity| capacity.0 as u64 * SECTOR_SIZE as u64)
+                .unwrap_or(0)
+        }
     }
 }
 
@@ -296,6 +312,11 @@ impl<D: BlockIo + Send> FileOperation for DiskFileOperation<D> {
         self.offset = new_offset;
         Ok(self.offset as off_t)
     }
+
+    fn fsync(&mut self) -> SysResult<u32> {
+        self.disk.flush().map_err(|_| Errno::EIO)?;
+        Ok(0)
+    }
     // fn get_inode_id(&self) -> InodeId {
     //     self.inode_id
     // }
@@ -312,8 +333,9 @@ use libc_binding::Whence;
 use sync::DeadMutex;
 
 impl DiskIo for DiskWrapper {
-    /// flush
+    /// flush the underlying file
     fn flush(&mut self) -> IoResult<()> {
+        self.0.lock().fsync()?;
         Ok(())
     }
     /// write at offset
@@ -339,3 +361,27 @@ impl DiskIo for DiskWrapper {
         }
     }
 }
+
+impl fat::DiskIo for DiskWrapper {
+    fn flush(&mut self) -> IoResult<()> {
+        DiskIo::flush(self)
+    }
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        DiskIo::write_buffer(self, offset, buf)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        DiskIo::read_buffer(self, offset, buf)
+    }
+}
+
+impl iso9660::DiskIo for DiskWrapper {
+    fn flush(&mut self) -> IoResult<()> {
+        DiskIo::flush(self)
+    }
+    fn write_buffer(&mut self, offset: u64, buf: &[u8]) -> IoResult<u64> {
+        DiskIo::write_buffer(self, offset, buf)
+    }
+    fn read_buffer(&mut self, offset: u64, buf: &mut [u8]) -> IoResult<u64> {
+        DiskIo::read_buffer(self, offset, buf)
+    }
+}
@@ -15,7 +15,9 @@ use core::str;
 use core::sync::atomic::Ordering;
 use ext2::{DirectoryEntryType, Ext2Filesystem};
 use fallible_collections::TryCollect;
-use libc_binding::{gid_t, statfs, uid_t, utimbuf, FileType, EXT2_SUPER_MAGIC, NAME_MAX};
+use libc_binding::{
+    gid_t, statfs, uid_t, utimbuf, FileType, XattrFlags, EXT2_SUPER_MAGIC, NAME_MAX,
+};
 
 pub mod file;
 pub use file::{Ext2DriverFile, Ext2FileOperation};
@@ -161,6 +163,14 @@ impl FileSystem for Ext2fs {
         Ok(self.ext2.lock().truncate(inode_nbr, new_size)?)
     }
 
+    fn sync(&mut self) -> SysResult<()> {
+        Ok(self.ext2.lock().sync()?)
+    }
+
+    fn close(&mut self, inode_nbr: u32) -> SysResult<()> {
+        Ok(self.ext2.lock().discard_preallocation(inode_nbr)?)
+    }
+
     fn chmod(&self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
         Ok(self.ext2.lock().chmod(inode_nbr, mode)?)
     }
@@ -312,4 +322,26 @@ impl FileSystem for Ext2fs {
         self.ext2.lock().utime(inode_number, times, current_time)?;
         Ok(())
     }
+
+    fn getxattr(&self, inode_nbr: u32, name: &str, buf: &mut [u8]) -> SysResult<usize> {
+        Ok(self.ext2.lock().getxattr(inode_nbr, name, buf)?)
+    }
+
+    fn setxattr(
+        &mut self,
+        inode_nbr: u32,
+        name: &str,
+        value: &[u8],
+        flags: XattrFlags,
+    ) -> SysResult<()> {
+        Ok(self.ext2.lock().setxattr(inode_nbr, name, value, flags)?)
+    }
+
+    fn listxattr(&self, inode_nbr: u32, buf: &mut [u8]) -> SysResult<usize> {
+        Ok(self.ext2.lock().listxattr(inode_nbr, buf)?)
+    }
+
+    fn removexattr(&mut self, inode_nbr: u32, name: &str) -> SysResult<()> {
+        Ok(self.ext2.lock().removexattr(inode_nbr, name)?)
+    }
 }
@@ -1,5 +1,5 @@
 use super::{Credentials, Driver, FileOperation, IpcResult, SysResult};
-use super::{InodeId, VFS};
+use super::{DirectoryEntryId, InodeId, VFS};
 use alloc::sync::Arc;
 use libc_binding::{gid_t, off_t, statfs, uid_t, Errno, FileType, OpenFlags, Whence};
 use sync::DeadMutex;
@@ -19,10 +19,10 @@ impl Ext2DriverFile {
 impl Driver for Ext2DriverFile {
     fn open(
         &mut self,
-        _flags: OpenFlags,
+        flags: OpenFlags,
     ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
         Ok(IpcResult::Done(Arc::new(DeadMutex::new(
-            Ext2FileOperation::new(self.inode_id),
+            Ext2FileOperation::new(self.inode_id, flags),
         ))))
     }
 }
@@ -32,13 +32,18 @@ impl Driver for Ext2DriverFile {
 pub struct Ext2FileOperation {
     inode_id: InodeId,
     offset: u64,
+    flags: OpenFlags,
+    /// The root of the mount which the file was opened through
+    mount_root: Option<DirectoryEntryId>,
 }
 
 impl Ext2FileOperation {
-    fn new(inode_id: InodeId) -> Self {
+    fn new(inode_id: InodeId, flags: OpenFlags) -> Self {
         Self {
             inode_id,
             offset: 0,
+            flags,
+            mount_root: None,
         }
     }
 }
@@ -48,6 +53,11 @@ impl FileOperation for Ext2FileOperation {
         self.offset = offset;
     }
 
+    fn set_mount_root(&mut self, mount_root: DirectoryEntryId) -> bool {
+        self.mount_root = Some(mount_root);
+        true
+    }
+
     fn get_inode_id(&self) -> SysResult<InodeId> {
         Ok(self.inode_id)
     }
@@ -66,12 +76,18 @@ impl FileOperation for Ext2FileOperation {
         Ok(0)
     }
 
+    fn fsync(&mut self) -> SysResult<u32> {
+        VFS.lock().fsync(self.inode_id)?;
+        Ok(0)
+    }
+
+    fn ftruncate(&mut self, length: off_t) -> SysResult<u32> {
+        VFS.lock().ftruncate(self.inode_id, length)?;
+        Ok(0)
+    }
+
     fn write(&mut self, buf: &[u8]) -> SysResult<IpcResult<u32>> {
-        let res = VFS
-            .lock()
-            .get_inode(self.inode_id)
-            .expect("no such inode")
-            .write(&mut self.offset, buf)? as u32;
+        let res = VFS.lock().write(self.inode_id, &mut self.offset, buf)?;
         Ok(IpcResult::Done(res))
     }
 
@@ -109,18 +125,24 @@ impl FileOperation for Ext2FileOperation {
     }
 
     fn fchmod(&mut self, creds: &Credentials, mode: FileType) -> SysResult<u32> {
-        VFS.lock().fchmod(creds, self.inode_id, mode)?;
+        VFS.lock()
+            .fchmod(creds, self.mount_root, self.inode_id, mode)?;
         Ok(0)
     }
 
     fn fchown(&mut self, creds: &Credentials, owner: uid_t, group: gid_t) -> SysResult<u32> {
-        VFS.lock().fchown(creds, self.inode_id, owner, group)?;
+        VFS.lock()
+            .fchown(creds, self.mount_root, self.inode_id, owner, group)?;
         Ok(0)
     }
 }
 
 impl Drop for Ext2FileOperation {
     fn drop(&mut self) {
-        VFS.lock().close_file_operation(self.inode_id);
+        let mut vfs = VFS.lock();
+        if self.flags.is_open_for_write() {
+            vfs.close_write(self.inode_id, self.mount_root);
+        }
+        vfs.close_file_operation(self.inode_id);
     }
 }
@@ -0,0 +1,286 @@
+use super::FileSystem;
+use super::{DirectoryEntry, Driver, FileSystemId, InodeData};
+use super::{DirectoryEntryBuilder, Filename, InodeId, SysResult};
+use crate::taskmaster::kmodules::CURRENT_UNIX_TIME;
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::sync::atomic::Ordering;
+use fallible_collections::vec::FallibleVec;
+use fat::FatFilesystem;
+use libc_binding::{gid_t, statfs, uid_t, utimbuf, Errno, FileType, MSDOS_SUPER_MAGIC};
+
+use super::ext2fs::Ext2DriverFile;
+
+use sync::DeadMutex;
+
+/// FAT has no owner nor permissions: everything belongs to root,
+/// and the read only attribute only removes the write permissions
+const DIRECTORY_PERMISSIONS: u16 = 0o755;
+const FILE_PERMISSIONS: u16 = 0o644;
+const WRITE_PERMISSIONS: u16 = 0o222;
+
+/// Maximum length of a long name, in UTF-16 code units
+const FAT_NAME_MAX: u32 = 255;
+
+#[derive(Debug)]
+pub struct Fatfs {
+    fat: Arc<DeadMutex<FatFilesystem>>,
+    fs_id: FileSystemId,
+}
+
+/// the FAT12/16/32 wrapper which implement filesystem
+impl Fatfs {
+    pub fn new(fat: FatFilesystem, fs_id: FileSystemId) -> Self {
+        Self {
+            fat: Arc::new(DeadMutex::new(fat)),
+            fs_id,
+        }
+    }
+
+    /// Get the mode FAT can represent for a file
+    fn access_mode(is_directory: bool, read_only: bool) -> FileType {
+        let (file_type, mut permissions) = match is_directory {
+            true => (FileType::DIRECTORY, DIRECTORY_PERMISSIONS),
+            false => (FileType::REGULAR_FILE, FILE_PERMISSIONS),
+        };
+        if read_only {
+            permissions &= !WRITE_PERMISSIONS;
+        }
+        file_type | FileType::from_bits_truncate(permissions)
+    }
+
+    fn inode_data(&self, stat: &fat::Stat) -> InodeData {
+        InodeData {
+            id: InodeId::new(stat.inode_nbr, Some(self.fs_id)),
+            major: 0,
+            minor: 0,
+            link_number: 1,
+            access_mode: Self::access_mode(stat.is_directory, stat.read_only),
+            uid: 0,
+            gid: 0,
+            atime: stat.access_time as i32,
+            mtime: stat.modification_time as i32,
+            ctime: stat.modification_time as i32,
+            size: stat.size,
+            nbr_disk_sectors: stat.nbr_disk_sectors,
+        }
+    }
+
+    fn convert_entry_fat_to_vfs(
+        &self,
+        filename: &str,
+        stat: &fat::Stat,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let inode_data = self.inode_data(stat);
+        let direntry = {
+            let mut builder = DirectoryEntryBuilder::new();
+            builder
+                .set_filename(Filename::try_from(filename)?)
+                .set_inode_id(inode_data.id);
+            if stat.is_directory {
+                builder.set_directory();
+            } else {
+                builder.set_regular();
+            }
+            builder.build()
+        };
+        Ok((
+            direntry,
+            inode_data,
+            Box::try_new(Ext2DriverFile::new(inode_data.id))?,
+        ))
+    }
+}
+
+impl FileSystem for Fatfs {
+    fn root(&self) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let stat = self.fat.lock().root_inode()?;
+        self.convert_entry_fat_to_vfs("fatRoot", &stat)
+    }
+
+    fn lookup_directory(
+        &mut self,
+        inode_nbr: u32,
+    ) -> SysResult<Vec<(DirectoryEntry, InodeData, Box<dyn Driver>)>> {
+        let entries = self.fat.lock().lookup_directory(inode_nbr)?;
+        let mut res = Vec::new();
+        for (filename, stat) in entries.iter() {
+            match self.convert_entry_fat_to_vfs(filename, stat) {
+                // 255 UTF-16 code units may not fit in NAME_MAX bytes
+                Err(Errno::ENAMETOOLONG) => continue,
+                entry => res.try_push(entry?)?,
+            }
+        }
+        Ok(res)
+    }
+
+    fn truncate(&mut self, inode_nbr: u32, new_size: u64) -> SysResult<()> {
+        let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+        Ok(self.fat.lock().truncate(inode_nbr, new_size, timestamp)?)
+    }
+
+    fn sync(&mut self) -> SysResult<()> {
+        Ok(self.fat.lock().sync()?)
+    }
+
+    /// Only the write permissions can change, through the read only attribute
+    fn chmod(&self, inode_nbr: u32, mode: FileType) -> SysResult<()> {
+        let mut fat = self.fat.lock();
+        let stat = fat.stat(inode_nbr)?;
+        let read_only = !mode.contains(FileType::USER_WRITE_PERMISSION);
+        if mode.bits() != Self::access_mode(stat.is_directory, read_only).bits() & 0o7777 {
+            return Err(Errno::EPERM);
+        }
+        Ok(fat.set_read_only(inode_nbr, read_only)?)
+    }
+
+    fn chown(&self, _inode_nbr: u32, owner: uid_t, group: gid_t) -> SysResult<()> {
+        match (owner, group) {
+            (0, 0) => Ok(()),
+            _ => Err(Errno::EPERM),
+        }
+    }
+
+    fn unlink(
+        &mut self,
+        dir_inode_nbr: u32,
+        name: &str,
+        free_inode_data: bool,
+        _inode_nbr: u32,
+    ) -> SysResult<()> {
+        Ok(self
+            .fat
+            .lock()
+            .unlink(dir_inode_nbr, name, free_inode_data)?)
+    }
+
+    fn remove_inode(&mut self, inode_nbr: u32) -> SysResult<()> {
+        Ok(self.fat.lock().remove_inode(inode_nbr)?)
+    }
+
+    fn create(
+        &mut self,
+        filename: &str,
+        parent_inode_nbr: u32,
+        mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        if !mode.is_typed() || mode.extract_type() != FileType::REGULAR_FILE {
+            return Err(Errno::EPERM);
+        }
+        let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+        let read_only = !mode.contains(FileType::USER_WRITE_PERMISSION);
+        let stat = self
+            .fat
+            .lock()
+            .create(parent_inode_nbr, filename, timestamp, read_only)?;
+        self.convert_entry_fat_to_vfs(filename, &stat)
+    }
+
+    fn write(
+        &mut self,
+        inode_number: u32,
+        offset: &mut u64,
+        buf: &[u8],
+    ) -> SysResult<(u32, InodeData)> {
+        let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+        let (count, stat) = self
+            .fat
+            .lock()
+            .write(inode_number, offset, buf, timestamp)?;
+        Ok((count as u32, self.inode_data(&stat)))
+    }
+
+    fn read(&mut self, inode_number: u32, offset: &mut u64, buf: &mut [u8]) -> SysResult<u32> {
+        Ok(self.fat.lock().read(inode_number, offset, buf)? as u32)
+    }
+
+    fn create_dir(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        _mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let timestamp = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+        let stat = self
+            .fat
+            .lock()
+            .create_dir(parent_inode_nbr, filename, timestamp)?;
+        self.convert_entry_fat_to_vfs(filename, &stat)
+    }
+
+    fn rmdir(&mut self, parent_inode_nbr: u32, filename: &str) -> SysResult<()> {
+        Ok(self.fat.lock().rmdir(parent_inode_nbr, filename)?)
+    }
+
+    /// FAT has neither symbolic nor hard links
+    fn symlink(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target: &str,
+        _filename: &str,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        Err(Errno::EPERM)
+    }
+
+    fn link(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target_inode_nbr: u32,
+        _filename: &str,
+    ) -> SysResult<DirectoryEntry> {
+        Err(Errno::EPERM)
+    }
+
+    fn rename(
+        &mut self,
+        parent_inode_nbr: u32,
+        filename: &str,
+        new_parent_inode_nbr: u32,
+        new_filename: &str,
+    ) -> SysResult<()> {
+        Ok(self.fat.lock().rename(
+            parent_inode_nbr,
+            filename,
+            new_parent_inode_nbr,
+            new_filename,
+        )?)
+    }
+
+    fn statfs(&self, buf: &mut statfs) -> SysResult<()> {
+        let fat = self.fat.lock();
+
+        Ok(*buf = statfs {
+            f_type: MSDOS_SUPER_MAGIC,
+            f_bsize: fat.get_cluster_size(),
+            f_blocks: fat.get_nbr_clusters(),
+            f_bfree: fat.get_free_clusters(),
+            f_bavail: fat.get_free_clusters(),
+            // There is no inode table
+            f_files: 0,
+            f_ffree: 0,
+            f_fsid: fat.get_volume_id(),
+            f_namelen: FAT_NAME_MAX,
+            f_frsize: fat.get_cluster_size(),
+            f_flags: 0,
+        })
+    }
+
+    fn utime(&mut self, inode_number: u32, times: Option<&utimbuf>) -> SysResult<()> {
+        let (access_time, modification_time) = match times {
+            Some(times) => (times.actime as u32, times.modtime as u32),
+            None => {
+                let current_time = unsafe { CURRENT_UNIX_TIME.load(Ordering::Relaxed) };
+                (current_time, current_time)
+            }
+        };
+        Ok(self
+            .fat
+            .lock()
+            .utime(inode_number, access_time, modification_time)?)
+    }
+}
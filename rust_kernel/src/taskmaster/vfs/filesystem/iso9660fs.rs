@@ -0,0 +1,229 @@
+use super::FileSystem;
+use super::{DirectoryEntry, Driver, FileSystemId, InodeData, Path};
+use super::{DirectoryEntryBuilder, Filename, InodeId, SysResult};
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use fallible_collections::vec::FallibleVec;
+use iso9660::{FileKind, Iso9660Filesystem};
+use libc_binding::{
+    dev_t, gid_t, nlink_t, statfs, uid_t, utimbuf, Errno, FileType, ISOFS_SUPER_MAGIC,
+};
+
+use super::ext2fs::Ext2DriverFile;
+
+use sync::DeadMutex;
+
+/// Maximum length of a Rock Ridge name, the other names are shorter
+const ISO9660_NAME_MAX: u32 = 255;
+
+/// The read only flag of statfs
+const ST_RDONLY: u32 = 1;
+
+#[derive(Debug)]
+pub struct Iso9660fs {
+    iso: Arc<DeadMutex<Iso9660Filesystem>>,
+    fs_id: FileSystemId,
+}
+
+/// the read only ISO9660 wrapper which implement filesystem
+impl Iso9660fs {
+    pub fn new(iso: Iso9660Filesystem, fs_id: FileSystemId) -> Self {
+        Self {
+            iso: Arc::new(DeadMutex::new(iso)),
+            fs_id,
+        }
+    }
+
+    fn inode_data(&self, stat: &iso9660::Stat) -> InodeData {
+        let file_type = match stat.kind {
+            FileKind::Regular => FileType::REGULAR_FILE,
+            FileKind::Directory => FileType::DIRECTORY,
+            FileKind::Symlink => FileType::SYMBOLIC_LINK,
+            FileKind::Fifo => FileType::FIFO,
+            FileKind::Socket => FileType::UNIX_SOCKET,
+            FileKind::CharDevice => FileType::CHARACTER_DEVICE,
+            FileKind::BlockDevice => FileType::BLOCK_DEVICE,
+        };
+        InodeData {
+            id: InodeId::new(stat.inode_nbr, Some(self.fs_id)),
+            major: stat.device.0 as dev_t,
+            minor: stat.device.1 as dev_t,
+            link_number: stat.nlink as nlink_t,
+            access_mode: file_type | FileType::from_bits_truncate(stat.mode),
+            uid: stat.uid as uid_t,
+            gid: stat.gid as gid_t,
+            atime: stat.access_time as i32,
+            mtime: stat.modification_time as i32,
+            ctime: stat.change_time as i32,
+            size: stat.size,
+            nbr_disk_sectors: stat.nbr_disk_sectors,
+        }
+    }
+
+    fn convert_entry_iso_to_vfs(
+        &self,
+        filename: &str,
+        stat: &iso9660::Stat,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let inode_data = self.inode_data(stat);
+        let direntry = {
+            let mut builder = DirectoryEntryBuilder::new();
+            builder
+                .set_filename(Filename::try_from(filename)?)
+                .set_inode_id(inode_data.id);
+            match stat.kind {
+                FileKind::Directory => builder.set_directory(),
+                FileKind::Symlink => {
+                    let target = self.iso.lock().read_link(stat.inode_nbr)?;
+                    builder.set_symlink(Path::try_from(target.as_str())?)
+                }
+                FileKind::Fifo => builder.set_fifo(),
+                FileKind::Socket => builder.set_socket(),
+                FileKind::CharDevice | FileKind::BlockDevice => builder.set_chardevice(),
+                FileKind::Regular => builder.set_regular(),
+            };
+            builder.build()
+        };
+        Ok((
+            direntry,
+            inode_data,
+            Box::try_new(Ext2DriverFile::new(inode_data.id))?,
+        ))
+    }
+}
+
+impl FileSystem for Iso9660fs {
+    fn root(&self) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        let stat = self.iso.lock().root_inode()?;
+        self.convert_entry_iso_to_vfs("iso9660Root", &stat)
+    }
+
+    fn lookup_directory(
+        &mut self,
+        inode_nbr: u32,
+    ) -> SysResult<Vec<(DirectoryEntry, InodeData, Box<dyn Driver>)>> {
+        let entries = self.iso.lock().lookup_directory(inode_nbr)?;
+        let mut res = Vec::new();
+        for (filename, stat) in entries.iter() {
+            match self.convert_entry_iso_to_vfs(filename, stat) {
+                // 255 UCS-2 characters may not fit in NAME_MAX bytes
+                Err(Errno::ENAMETOOLONG) => continue,
+                entry => res.try_push(entry?)?,
+            }
+        }
+        Ok(res)
+    }
+
+    fn chmod(&self, _inode_nbr: u32, _mode: FileType) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn chown(&self, _inode_nbr: u32, _owner: uid_t, _group: gid_t) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn unlink(
+        &mut self,
+        _dir_inode_nbr: u32,
+        _name: &str,
+        _free_inode_data: bool,
+        _inode_nbr: u32,
+    ) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn truncate(&mut self, _inode_nbr: u32, _new_size: u64) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn create(
+        &mut self,
+        _filename: &str,
+        _parent_inode_nbr: u32,
+        _mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        Err(Errno::EROFS)
+    }
+
+    fn write(
+        &mut self,
+        _inode_number: u32,
+        _offset: &mut u64,
+        _buf: &[u8],
+    ) -> SysResult<(u32, InodeData)> {
+        Err(Errno::EROFS)
+    }
+
+    fn read(&mut self, inode_number: u32, offset: &mut u64, buf: &mut [u8]) -> SysResult<u32> {
+        Ok(self.iso.lock().read(inode_number, offset, buf)? as u32)
+    }
+
+    fn create_dir(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _filename: &str,
+        _mode: FileType,
+        _owner: (uid_t, gid_t),
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        Err(Errno::EROFS)
+    }
+
+    fn rmdir(&mut self, _parent_inode_nbr: u32, _filename: &str) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn symlink(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target: &str,
+        _filename: &str,
+    ) -> SysResult<(DirectoryEntry, InodeData, Box<dyn Driver>)> {
+        Err(Errno::EROFS)
+    }
+
+    fn link(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _target_inode_nbr: u32,
+        _filename: &str,
+    ) -> SysResult<DirectoryEntry> {
+        Err(Errno::EROFS)
+    }
+
+    fn rename(
+        &mut self,
+        _parent_inode_nbr: u32,
+        _filename: &str,
+        _new_parent_inode_nbr: u32,
+        _new_filename: &str,
+    ) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+
+    fn statfs(&self, buf: &mut statfs) -> SysResult<()> {
+        let iso = self.iso.lock();
+
+        Ok(*buf = statfs {
+            f_type: ISOFS_SUPER_MAGIC,
+            f_bsize: iso.get_block_size(),
+            f_blocks: iso.get_nbr_blocks(),
+            f_bfree: 0,
+            f_bavail: 0,
+            // There is no inode table
+            f_files: 0,
+            f_ffree: 0,
+            f_fsid: iso.get_volume_id(),
+            f_namelen: ISO9660_NAME_MAX,
+            f_frsize: iso.get_block_size(),
+            f_flags: ST_RDONLY,
+        })
+    }
+
+    fn utime(&mut self, _inode_number: u32, _times: Option<&utimbuf>) -> SysResult<()> {
+        Err(Errno::EROFS)
+    }
+}
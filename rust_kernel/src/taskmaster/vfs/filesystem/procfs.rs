@@ -67,6 +67,9 @@ pub use vmstat::VmstatDriver;
 mod mounts;
 pub use mounts::MountsDriver;
 
+mod locks;
+pub use locks::LocksDriver;
+
 mod comm;
 pub use comm::CommDriver;
 
@@ -222,6 +225,7 @@ impl ProcFs {
         let meminfo_filename = Filename::from_str_unwrap("meminfo");
         let vmstat_filename = Filename::from_str_unwrap("vmstat");
         let mounts_filename = Filename::from_str_unwrap("mounts");
+        let locks_filename = Filename::from_str_unwrap("locks");
         let owning = (0, 0);
 
         self.register_file(
@@ -295,6 +299,15 @@ impl ProcFs {
             owning,
         )?;
 
+        self.register_file(
+            root_dir_id,
+            locks_filename,
+            Box::try_new(|inode_id| -> Result<Box<dyn Driver>, AllocError> {
+                Ok(Box::try_new(locks::LocksDriver::new(inode_id))? as Box<dyn Driver>)
+            })?,
+            owning,
+        )?;
+
         // Inserting divers basic procfs files.
         Ok(())
     }
@@ -476,7 +489,7 @@ impl ProcFs {
             owning,
         )?;
 
-        let cwd = thread_group.cwd.try_clone()?;
+        let cwd = thread_group.cwd_path.try_clone()?;
 
         self.symlink(dir_id, cwd_filename, cwd, Some(owning))?;
 
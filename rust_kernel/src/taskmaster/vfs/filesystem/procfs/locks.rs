@@ -0,0 +1,124 @@
+use super::{Driver, FileOperation, InodeId, IpcResult, ProcFsOperations, SysResult, VFS};
+use crate::taskmaster::vfs::{LockOwner, LockType, OFFSET_MAX};
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use fallible_collections::TryCollect;
+
+use libc_binding::{Errno, OpenFlags};
+use sync::DeadMutex;
+
+type Mutex<T> = DeadMutex<T>;
+
+use libc_binding::{off_t, Whence};
+
+#[derive(Debug, Clone)]
+pub struct LocksDriver {
+    inode_id: InodeId,
+}
+
+impl LocksDriver {
+    pub fn new(inode_id: InodeId) -> Self {
+        Self { inode_id }
+    }
+}
+
+unsafe impl Send for LocksDriver {}
+
+#[derive(Debug, Default)]
+pub struct LocksOperations {
+    inode_id: InodeId,
+    offset: usize,
+}
+
+impl Driver for LocksDriver {
+    fn open(&mut self, _flags: OpenFlags) -> SysResult<IpcResult<Arc<Mutex<dyn FileOperation>>>> {
+        let res = Arc::try_new(Mutex::new(LocksOperations {
+            inode_id: self.inode_id,
+            offset: 0,
+        }))?;
+        Ok(IpcResult::Done(res))
+    }
+}
+
+impl FileOperation for LocksOperations {
+    fn get_inode_id(&self) -> SysResult<InodeId> {
+        Ok(self.inode_id)
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        self.seq_read(buf)
+    }
+
+    fn lseek(&mut self, offset: off_t, whence: Whence) -> SysResult<off_t> {
+        self.proc_lseek(offset, whence)
+    }
+}
+
+impl ProcFsOperations for LocksOperations {
+    fn get_seq_string(&self) -> SysResult<Cow<str>> {
+        VFS.force_unlock();
+        let vfs = VFS.lock();
+        let locks_bytes: Vec<u8> = vfs
+            .file_locks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (inode_id, lock))| {
+                let kind = match lock.owner {
+                    LockOwner::Process(_) => "POSIX",
+                    LockOwner::OpenFile(_) => "FLOCK",
+                };
+                let lock_type = match lock.lock_type {
+                    LockType::Shared => "READ ",
+                    LockType::Exclusive => "WRITE",
+                };
+                let filesystem_id = inode_id.filesystem_id.map(|id| id.0).unwrap_or(0);
+                let line = if lock.end == OFFSET_MAX {
+                    tryformat!(
+                        128,
+                        "{}: {}  ADVISORY  {} {} 00:{:02x}:{} {} EOF\n",
+                        i + 1,
+                        kind,
+                        lock_type,
+                        lock.pid,
+                        filesystem_id,
+                        inode_id.inode_number,
+                        lock.start
+                    )
+                } else {
+                    tryformat!(
+                        128,
+                        "{}: {}  ADVISORY  {} {} 00:{:02x}:{} {} {}\n",
+                        i + 1,
+                        kind,
+                        lock_type,
+                        lock.pid,
+                        filesystem_id,
+                        inode_id.inode_number,
+                        lock.start,
+                        lock.end
+                    )
+                };
+                Some(line.ok()?.into_bytes())
+            })
+            .flatten()
+            .try_collect()?;
+
+        Ok(Cow::from(String::from_utf8(locks_bytes).map_err(|_| {
+            log::error!("invalid utf8 in locks operation");
+            Errno::EINVAL
+        })?))
+    }
+    fn get_offset(&mut self) -> &mut usize {
+        &mut self.offset
+    }
+}
+
+impl Drop for LocksOperations {
+    fn drop(&mut self) {
+        VFS.lock().close_file_operation(self.inode_id);
+    }
+}
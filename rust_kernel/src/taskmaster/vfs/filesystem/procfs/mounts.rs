@@ -9,13 +9,38 @@ use alloc::vec::Vec;
 
 use fallible_collections::TryCollect;
 
-use libc_binding::{Errno, OpenFlags};
+use core::fmt;
+use libc_binding::{Errno, MountFlags, OpenFlags};
 use sync::DeadMutex;
 
 type Mutex<T> = DeadMutex<T>;
 
 use libc_binding::{off_t, Whence};
 
+/// The options field of a line of /proc/mounts
+struct MountOptions(MountFlags);
+
+impl fmt::Display for MountOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = self.0;
+        if flags.contains(MountFlags::MS_RDONLY) {
+            write!(f, "ro")?;
+        } else {
+            write!(f, "rw")?;
+        }
+        if flags.contains(MountFlags::MS_NOSUID) {
+            write!(f, ",nosuid")?;
+        }
+        if flags.contains(MountFlags::MS_NODEV) {
+            write!(f, ",nodev")?;
+        }
+        if flags.contains(MountFlags::MS_NOEXEC) {
+            write!(f, ",noexec")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MountsDriver {
     inode_id: InodeId,
@@ -64,22 +89,27 @@ impl ProcFsOperations for MountsOperations {
         VFS.force_unlock();
         let vfs = VFS.lock();
         let mounts_bytes: Vec<u8> = vfs
-            .mounted_filesystems
+            .mounts
             .values()
-            .filter_map(
-                |MountedFileSystem {
-                     ref source,
-                     ref target,
-                     ref fs_type,
-                     ..
-                 }| {
-                    Some(
-                        tryformat!(128, "{} {} {} rw 0 0\n", source, target, fs_type)
-                            .ok()?
-                            .into_bytes(),
+            .filter_map(|mount| {
+                let MountedFileSystem {
+                    ref source,
+                    ref fs_type,
+                    ..
+                } = vfs.mounted_filesystems.get(&mount.fs_id)?;
+                Some(
+                    tryformat!(
+                        128,
+                        "{} {} {} {} 0 0\n",
+                        source,
+                        mount.target,
+                        fs_type,
+                        MountOptions(mount.flags)
                     )
-                },
-            )
+                    .ok()?
+                    .into_bytes(),
+                )
+            })
             .flatten()
             .try_collect()?;
 
@@ -12,6 +12,7 @@ use core::convert::TryFrom;
 
 use libc_binding::OpenFlags;
 use sync::DeadMutex;
+use terminal::TERMINAL;
 
 type Mutex<T> = DeadMutex<T>;
 
@@ -83,10 +84,37 @@ impl ProcFsOperations for StatOperations {
             ThreadGroupState::Zombie(_status) => "Z",
         };
 
-        let utime = thread_group.process_duration.user_time().as_secs(); // convert to clock tick count.
-        let stime = thread_group.process_duration.system_time().as_secs();
+        let usage = &thread_group.usage;
+        let children_usage = &thread_group.children_usage;
+        let utime = usage.duration.user_time().as_secs(); // convert to clock tick count.
+        let stime = usage.duration.system_time().as_secs();
+        let cutime = children_usage.duration.user_time().as_secs();
+        let cstime = children_usage.duration.system_time().as_secs();
 
         let ctty = thread_group.controlling_terminal.unwrap_or(0 as dev_t);
+        // The foreground process group of the controlling terminal
+        let tpgid = thread_group.controlling_terminal.map_or(-1, |tty| unsafe {
+            TERMINAL
+                .as_mut()
+                .unwrap()
+                .get_line_discipline(tty as usize)
+                .tcgetpgrp()
+        });
+
+        // The scheduling of a process is the one of its main thread
+        let threads = thread_group.get_all_thread();
+        let num_threads = threads.map_or(0, |threads| threads.len());
+        let (priority, nice, rt_priority, policy) = threads
+            .and_then(|threads| threads.get(&0))
+            .map_or((0, 0, 0, 0), |thread| {
+                let sched = &thread.sched;
+                (
+                    sched.priority(),
+                    sched.nice(),
+                    sched.rt_priority(),
+                    u32::from(sched.policy()),
+                )
+            });
 
         let stat_string = tryformat!(4096, "{} ({}) {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n", self.pid,
                                   // comm
@@ -98,35 +126,35 @@ impl ProcFsOperations for StatOperations {
                                   // pgrp
                                   thread_group.pgid,
                                   // session
-                                  1,
+                                  thread_group.sid,
                                   // tty_nr
                                   4 << 8 | ctty, // TODO: make the major not hardcoded.
                                   // tpgid
-                                  1,
+                                  tpgid,
                                   // flags
                                   1,
                                   // minflt
-                                  1,
+                                  usage.minor_faults,
                                   // cminflt
-                                  1,
+                                  children_usage.minor_faults,
                                   // majflt
-                                  1,
+                                  usage.major_faults,
                                   // cmajflt
-                                  1,
+                                  children_usage.major_faults,
                                   // utime
                                   utime,
                                   // stime
                                   stime,
                                   // cutime
-                                  42,
+                                  cutime,
                                   // cstime
-                                  42,
+                                  cstime,
                                   // priority
-                                  1,
+                                  priority,
                                   // nice
-                                  1,
+                                  nice,
                                   // num_threads
-                                  1,
+                                  num_threads,
                                   // itrealvalue
                                   1,
                                   // starttime
@@ -166,9 +194,9 @@ impl ProcFsOperations for StatOperations {
                                   // processor
                                   1,
                                   // rt_priority
-                                  1,
+                                  rt_priority,
                                   // policy
-                                  1,
+                                  policy,
                                   // delayacct_blkio_ticks
                                   1,
                                   // guest_time
@@ -1,6 +1,6 @@
 use super::filesystem::devfs::{
-    BiosInt13hInstance, DiskDriver, DiskWrapper, FbDevice, IdeAtaInstance, NullDevice,
-    RandomDevice, ZeroDevice,
+    BiosInt13hInstance, DiskDriver, DiskWrapper, FbDevice, IdeAtaInstance, LoopDevice, LoopDriver,
+    NullDevice, RandomDevice, ZeroDevice, NBR_LOOP_DEVICES, NBR_LOOP_PARTITIONS,
 };
 use super::filesystem::{Devfs, Ext2fs, FileSystemSource, FileSystemType};
 use super::SmartMutex;
@@ -14,7 +14,8 @@ use sync::DeadMutex;
 
 use super::filesystem::procfs::ProcFs;
 use super::*;
-use crate::drivers::storage::{BlockIo, DiskDriverType, NbrSectors, Sector};
+use crate::drivers::storage::ide_ata_controller::{Hierarchy, Rank};
+use crate::drivers::storage::{BlockIo, DiskDriverType, NbrSectors, Sector, IDE_ATA_CONTROLLER};
 use alloc::boxed::Box;
 use ext2::Ext2Filesystem;
 use mbr::Mbr;
@@ -34,6 +35,7 @@ pub fn init() -> Vfs {
     init_procfs(&mut vfs).expect("Failed to init /proc (procfs)");
     // then init tty on /dev/tty
     init_tty(&mut devfs);
+    init_loop_devices(&mut devfs);
     mount_devfs(&mut vfs, devfs, fs_id);
     vfs
 }
@@ -86,17 +88,17 @@ fn mount_devfs(vfs: &mut Vfs, mut devfs: Devfs, fs_id: FileSystemId) {
         .expect("failed to add new driver sda to devfs");
 
     let dev_id = vfs
-        .pathname_resolution(&Path::root(), &root_creds, &Path::try_from("/dev").unwrap())
+        .pathname_resolution(&Cwd::new(), &root_creds, &Path::try_from("/dev").unwrap())
         .unwrap();
     vfs.mount_filesystem(
         MountedFileSystem {
             source: FileSystemSource::Devfs,
             fs_type: FileSystemType::Devfs,
-            target: Path::try_from("/dev").expect("/dev path creation failed"),
             fs: Arc::try_new(DeadMutex::new(devfs)).expect("arc new devfs failed"),
         },
         fs_id,
         dev_id,
+        MountFlags::empty(),
     )
     .expect("mounting /dev failed");
 }
@@ -122,47 +124,118 @@ fn init_ext2(vfs: &mut Vfs, devfs: &mut Devfs, driver_type: DiskDriverType) {
                 source_path: Path::try_from("/dev/sda1").expect("enomem to create path /dev/sda1"),
             },
             fs_type: FileSystemType::Ext2,
-            target: Path::try_from("/").expect("enomem to create path /"),
             fs: Arc::try_new(DeadMutex::new(ext2fs)).expect("arc new ext2fs failed"),
         },
         fs_id,
         DirectoryEntryId::new(2),
+        MountFlags::empty(),
     )
     .expect("mount filesystem failed");
 
-    init_sda(devfs, sda_driver, partition_drivers);
+    init_disk(devfs, "sda", sda_driver, partition_drivers);
+    if let DiskDriverType::Ide = driver_type {
+        init_second_disk(devfs);
+        init_cdrom_drives(devfs);
+    }
+}
+
+/// Tell if the IDE drive at `rank` is a packet device, like a CD-ROM drive
+fn is_atapi(rank: Rank) -> bool {
+    unsafe {
+        IDE_ATA_CONTROLLER
+            .as_ref()
+            .map_or(false, |controller| controller.is_atapi(rank))
+    }
+}
+
+/// construct /dev/sdb from the IDE primary slave if any, for example
+/// a second QEMU drive to mount
+fn init_second_disk(devfs: &mut Devfs) {
+    let rank = Rank::Primary(Hierarchy::Slave);
+    let disk = IdeAtaInstance(rank);
+    let disk_size = disk.disk_size();
+    if disk_size == 0 || is_atapi(rank) {
+        return;
+    }
+    let (sdb_driver, partition_drivers) =
+        disk_drivers(disk, disk_size).expect("initialisation of sdb drivers failed");
+    init_disk(devfs, "sdb", sdb_driver, partition_drivers);
+}
+
+/// construct /dev/sr0,1,.. from the IDE CD-ROM drives, the QEMU
+/// `-cdrom` is the secondary master. The medium is read as a whole,
+/// without partition table
+fn init_cdrom_drives(devfs: &mut Devfs) {
+    let ranks = [
+        Rank::Primary(Hierarchy::Slave),
+        Rank::Secondary(Hierarchy::Master),
+        Rank::Secondary(Hierarchy::Slave),
+    ];
+    for (i, rank) in ranks.iter().filter(|rank| is_atapi(**rank)).enumerate() {
+        let disk = IdeAtaInstance(*rank);
+        let driver =
+            Box::try_new(DiskDriver::new(disk, 0, disk.disk_size())).expect("cdrom driver failed");
+        init_disk(devfs, &format!("sr{}", i), driver, Vec::new());
+    }
+}
+
+/// construct /dev/loop0,1,.. and their partitions /dev/loopNp1,2,..,
+/// which are empty until a file is attached with LOOP_SET_FD
+fn init_loop_devices(devfs: &mut Devfs) {
+    for n in 0..NBR_LOOP_DEVICES {
+        let device =
+            Arc::try_new(DeadMutex::new(LoopDevice::new(n))).expect("loop device creation failed");
+        let mut partition_drivers: Vec<Box<dyn Driver>> = Vec::new();
+        for i in 0..NBR_LOOP_PARTITIONS {
+            partition_drivers
+                .try_push(
+                    Box::try_new(LoopDriver::new(device.clone(), Some(i)))
+                        .expect("loop partition driver failed"),
+                )
+                .expect("loop partition driver failed");
+        }
+        let driver = Box::try_new(LoopDriver::new(device, None)).expect("loop driver failed");
+        init_disk(devfs, &format!("loop{}", n), driver, partition_drivers);
+    }
 }
 
-/// mount /dev/sda1 on the vfs, WARNING: must be call after ext2 is
-/// mounted on root
-fn init_sda(
+/// add /dev/`name` and its partitions /dev/`name`1,2,.. on the devfs,
+/// or /dev/`name`p1,p2,.. when `name` ends with a digit, like Linux
+/// WARNING: must be call after ext2 is mounted on root
+fn init_disk(
     devfs: &mut Devfs,
-    mut sda_driver: Box<dyn Driver>,
+    name: &str,
+    mut disk_driver: Box<dyn Driver>,
     partition_drivers: Vec<Box<dyn Driver>>,
 ) {
     let mode = FileType::from_bits(0o660).expect("file permission creation failed")
         | FileType::CHARACTER_DEVICE;
 
     let inode_id = devfs.gen_inode_id();
-    sda_driver.set_inode_id(inode_id);
+    disk_driver.set_inode_id(inode_id);
     devfs
         .add_driver(
-            Filename::try_from("sda").expect("path sda creation failed"),
+            Filename::try_from(name).expect("disk path creation failed"),
             mode,
-            sda_driver,
+            disk_driver,
             inode_id,
         )
-        .expect("failed to add new driver sda to devfs");
+        .expect("failed to add new disk driver to devfs");
+    let separator = if name.ends_with(|c: char| c.is_ascii_digit()) {
+        "p"
+    } else {
+        ""
+    };
     for (i, mut d) in partition_drivers.into_iter().enumerate() {
-        let filename = Filename::try_from(format!("sda{}", i + 1).as_ref())
-            .expect("filename sda_i creation failed");
+        let filename = Filename::try_from(format!("{}{}{}", name, separator, i + 1).as_ref())
+            .expect("partition filename creation failed");
         let inode_id = devfs.gen_inode_id();
         d.set_inode_id(inode_id);
         devfs
             .add_driver(filename, mode, d, inode_id)
-            .expect("failed to add new driver sda1 to devfs");
+            .expect("failed to add new partition driver to devfs");
     }
-    log::info!("/dev/sda initialized");
+    log::info!("/dev/{} initialized", name);
 }
 
 // TODO: make a Initer abstraction that takes a &mut of Vfs.
@@ -177,7 +250,7 @@ fn init_procfs(vfs: &mut Vfs) -> Result<(), Errno> {
     let procfs = ProcFs::new(fs_id)?;
 
     let root_creds = Credentials::ROOT;
-    let cwd = Path::try_from("/")?;
+    let cwd = Cwd::new();
 
     let ret = vfs.pathname_resolution(&cwd, &root_creds, &procfs_root);
     let procfs_dir_perms = FileType::from_bits(0555).ok_or(Errno::EINVAL)?;
@@ -190,6 +263,7 @@ fn init_procfs(vfs: &mut Vfs) -> Result<(), Errno> {
                 // Well, we need ownership here, we should decide what to do about this.
                 procfs_root.try_clone()?,
                 procfs_dir_perms,
+                FileType::empty(),
             )?;
             vfs.pathname_resolution(&cwd, &root_creds, &procfs_root)?
         }
@@ -197,21 +271,27 @@ fn init_procfs(vfs: &mut Vfs) -> Result<(), Errno> {
         Ok(id) => id,
     };
 
-    assert_eq!(
-        2,
-        vfs.opendir(&cwd, &root_creds, procfs_root.try_clone()?)?
-            .len()
-    );
+    // The mount point only contains . and ..
+    let mut cursor = DirectoryCursor::default();
+    let mut buf = [0; 512];
+    let mut size = 0;
+    loop {
+        match vfs.getdents(proc_dir_directory_id, &mut cursor, &mut buf)? {
+            0 => break,
+            len => size += len as usize,
+        }
+    }
+    assert_eq!(dirent64_len(".") + dirent64_len(".."), size);
 
     vfs.mount_filesystem(
         MountedFileSystem {
             source: FileSystemSource::Procfs,
             fs_type: FileSystemType::Procfs,
-            target: Path::try_from("/proc")?,
             fs: Arc::try_new(DeadMutex::new(procfs))?,
         },
         fs_id,
         proc_dir_directory_id,
+        MountFlags::empty(),
     )
 }
 
@@ -242,43 +322,43 @@ fn read_mbr(disk: &mut dyn BlockIo) -> Mbr {
     unsafe { Mbr::new(&v1) }
 }
 
-/// returns the sda driver and sda1,2,.. drivers
-fn new_disk_drivers(
-    driver_type: DiskDriverType,
+/// returns the disk driver and the drivers of its partitions
+fn disk_drivers<D: BlockIo + Copy + Clone + Debug + 'static>(
+    mut disk: D,
+    disk_size: u64,
 ) -> SysResult<(Box<dyn Driver>, Vec<Box<dyn Driver>>)> {
-    fn _new_disk_drivers<D: BlockIo + Copy + Clone + Debug + 'static>(
-        mut disk: D,
-        disk_size: u64,
-    ) -> SysResult<(Box<dyn Driver>, Vec<Box<dyn Driver>>)> {
-        let mbr = read_mbr(&mut disk);
-        let sda = Box::try_new(DiskDriver::new(disk, 0, disk_size))?;
-        let mut drivers: Vec<Box<dyn Driver>> = Vec::new();
-        for part in &mbr.parts {
-            if part.is_used() {
-                drivers.try_push(Box::try_new(DiskDriver::new(
-                    disk,
-                    part.start as u64 * 512,
-                    part.size as u64 * 512,
-                ))?)?
-            }
+    let mbr = read_mbr(&mut disk);
+    let sda = Box::try_new(DiskDriver::new(disk, 0, disk_size))?;
+    let mut drivers: Vec<Box<dyn Driver>> = Vec::new();
+    for part in &mbr.parts {
+        // A disk without partition table, formatted as a whole,
+        // has garbage instead of the partition entries
+        let end = part.start as u64 * 512 + part.size as u64 * 512;
+        if part.is_used() && end <= disk_size {
+            drivers.try_push(Box::try_new(DiskDriver::new(
+                disk,
+                part.start as u64 * 512,
+                part.size as u64 * 512,
+            ))?)?
         }
-        Ok((sda, drivers))
     }
+    Ok((sda, drivers))
+}
 
+/// returns the sda driver and sda1,2,.. drivers
+fn new_disk_drivers(
+    driver_type: DiskDriverType,
+) -> SysResult<(Box<dyn Driver>, Vec<Box<dyn Driver>>)> {
     match driver_type {
         DiskDriverType::Bios => {
             let disk = BiosInt13hInstance;
             let disk_size = disk.disk_size();
-            _new_disk_drivers(disk, disk_size)
+            disk_drivers(disk, disk_size)
         }
         DiskDriverType::Ide => {
-            let disk_size = {
-                // TODO: how to know the size with an IdeAta
-                let disk = BiosInt13hInstance;
-                disk.disk_size()
-            };
-            let disk = IdeAtaInstance;
-            _new_disk_drivers(disk, disk_size)
+            let disk = IdeAtaInstance(Rank::Primary(Hierarchy::Master));
+            let disk_size = disk.disk_size();
+            disk_drivers(disk, disk_size)
         }
         _ => unimplemented!(),
     }
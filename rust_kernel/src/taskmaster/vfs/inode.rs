@@ -5,14 +5,17 @@ use super::Driver;
 use super::FileSystem;
 use super::Incrementor;
 use super::{FileOperation, IpcResult, OpenFlags};
+use crate::memory::file_mapping::PAGE_CACHE;
 use crate::taskmaster::SysResult;
 // use super::{FileSystemId, VfsError, VfsHandler, VfsHandlerKind, VfsHandlerParams, VfsResult};
 use super::FileSystemId;
+use super::{CachedAcl, Credentials, PosixAcl, ACL_ACCESS};
+use super::{DirectoryEntryId, DirectoryFileOperation, Path};
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use libc_binding::{
-    blkcnt_t, dev_t, gid_t, ino_t, mode_t, nlink_t, off_t, stat, time_t, timespec, uid_t, Errno,
-    FileType,
+    blkcnt_t, dev_t, gid_t, ino_t, mode_t, nlink_t, off_t, stat, time_t, timespec, uid_t, Amode,
+    Errno, FileType,
 };
 use sync::DeadMutex;
 use try_clone_derive::TryClone;
@@ -26,7 +29,12 @@ pub struct Inode {
     /// if true, the inode need to be unlink when
     /// nbr_open_file_operation reach to 0
     pub lazy_unlink: bool,
+    /// The number of process images mapping the inode, which cannot
+    /// be written meanwhile
+    write_denials: usize,
     pub filesystem: Arc<DeadMutex<dyn FileSystem>>,
+    /// the access ACL of the inode, loaded on the first permission check
+    acl: CachedAcl,
 }
 
 use core::ops::{Deref, DerefMut};
@@ -49,6 +57,11 @@ impl Inode {
         &mut self,
         flags: OpenFlags,
     ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        if self.write_denials > 0
+            && (flags.is_open_for_write() || flags.contains(OpenFlags::O_TRUNC))
+        {
+            return Err(Errno::ETXTBSY);
+        }
         if flags.contains(OpenFlags::O_TRUNC) {
             if self
                 .filesystem
@@ -57,6 +70,7 @@ impl Inode {
                 .is_ok()
             {
                 self.inode_data.set_size(0);
+                PAGE_CACHE.lock().invalidate(self.id);
             }
         }
         let mut res = self.driver.open(flags)?;
@@ -68,6 +82,19 @@ impl Inode {
         self.nbr_open_file_operation += 1;
         Ok(res)
     }
+
+    /// Open the directory, whatever its filesystem, to read it with getdents
+    pub fn open_directory(
+        &mut self,
+        entry_id: DirectoryEntryId,
+        path: Path,
+    ) -> SysResult<IpcResult<Arc<DeadMutex<dyn FileOperation>>>> {
+        let file_operation = Arc::try_new(DeadMutex::new(DirectoryFileOperation::new(
+            self.id, entry_id, path,
+        )))?;
+        self.nbr_open_file_operation += 1;
+        Ok(IpcResult::Done(file_operation))
+    }
     pub fn new(
         filesystem: Arc<DeadMutex<dyn FileSystem>>,
         driver: Box<dyn Driver>,
@@ -79,6 +106,8 @@ impl Inode {
             driver,
             nbr_open_file_operation: 0,
             lazy_unlink: false,
+            write_denials: 0,
+            acl: CachedAcl::NotLoaded,
         }
     }
     pub fn root_inode() -> SysResult<Self> {
@@ -88,6 +117,8 @@ impl Inode {
             filesystem: Arc::try_new(DeadMutex::new(DeadFileSystem))?,
             nbr_open_file_operation: 0,
             lazy_unlink: false,
+            write_denials: 0,
+            acl: CachedAcl::NotLoaded,
         })
     }
     pub fn stat(&self) -> SysResult<stat> {
@@ -101,8 +132,23 @@ impl Inode {
                 .write(self.id.inode_number, offset, buf)?;
         self.inode_data.size = inode_data.size;
         self.inode_data.nbr_disk_sectors = inode_data.nbr_disk_sectors;
+        if count > 0 {
+            PAGE_CACHE.lock().invalidate(self.id);
+        }
         Ok(count as u32)
     }
+    /// Set the size of the file to `new_size`, an extension reads as zeros
+    pub fn truncate(&mut self, new_size: u64) -> SysResult<()> {
+        if self.write_denials > 0 {
+            return Err(Errno::ETXTBSY);
+        }
+        self.filesystem
+            .lock()
+            .truncate(self.id.inode_number, new_size)?;
+        self.inode_data.set_size(new_size);
+        PAGE_CACHE.lock().invalidate(self.id);
+        Ok(())
+    }
     pub fn read(&mut self, offset: &mut u64, buf: &mut [u8]) -> SysResult<u32> {
         if self.inode_data.is_directory() {
             return Err(Errno::EISDIR);
@@ -120,6 +166,22 @@ impl Inode {
         self.link_number == 0 && self.nbr_open_file_operation == 0
     }
 
+    /// return if some file operations are still opened on the inode
+    pub fn is_opened(&self) -> bool {
+        self.nbr_open_file_operation > 0
+    }
+
+    /// Forbid the writes to the inode while a process image maps it
+    pub fn deny_write(&mut self) {
+        self.write_denials += 1;
+    }
+
+    /// Allow the writes again, once per deny_write()
+    pub fn allow_write(&mut self) {
+        debug_assert!(self.write_denials > 0);
+        self.write_denials = self.write_denials.saturating_sub(1);
+    }
+
     /// increment artificialy open file operation field. Used for
     /// binding a socket
     pub unsafe fn incr_nbr_open_file_operation(&mut self) {
@@ -136,6 +198,45 @@ impl Inode {
         self.link_number == 0 && !self.lazy_unlink
     }
 
+    /// Get the access ACL of the inode, loading it from the filesystem if necessary
+    pub fn access_acl(&mut self) -> SysResult<Option<&PosixAcl>> {
+        if let CachedAcl::NotLoaded = self.acl {
+            let acl = PosixAcl::load(
+                &*self.filesystem.lock(),
+                self.id.inode_number as u32,
+                ACL_ACCESS,
+            )?;
+            self.acl = match acl {
+                Some(acl) => CachedAcl::Present(acl),
+                None => CachedAcl::Absent,
+            };
+        }
+        Ok(match &self.acl {
+            CachedAcl::Present(acl) => Some(acl),
+            _ => None,
+        })
+    }
+
+    /// Forget the cached access ACL, must be called when the ACL changes
+    pub fn invalidate_acl(&mut self) {
+        self.acl = CachedAcl::NotLoaded;
+    }
+
+    /// Checks whether access shall be granted for an file access
+    /// permission type `access_type` on the inode for the `creds`
+    /// credentials, evaluating its access ACL if it has one.
+    pub fn is_access_granted(&mut self, creds: &Credentials, access_type: Amode) -> bool {
+        let owner = (self.uid, self.gid);
+        if !creds.is_root() {
+            match self.access_acl() {
+                Ok(Some(acl)) => return acl.is_access_granted(creds, access_type, owner),
+                Ok(None) => {}
+                Err(_) => return false,
+            }
+        }
+        creds.is_access_granted(self.access_mode, access_type, owner)
+    }
+
     pub fn get_id(&self) -> InodeId {
         self.inode_data.get_id()
     }
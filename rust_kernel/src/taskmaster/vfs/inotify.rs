@@ -0,0 +1,419 @@
+//! inotify: the watches on the inodes and the event queues of the
+//! inotify instances, which the VFS feeds at each change of a file
+
+use super::{Credentials, Cwd, FileOperation, IpcResult, SysResult};
+use super::{Filename, InodeId, Path, VFS};
+
+use crate::taskmaster::drivers::get_file_op_uid;
+use crate::taskmaster::scheduler::Scheduler;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use fallible_collections::btree::BTreeMap;
+use fallible_collections::FallibleVec;
+use libc_binding::{Errno, InotifyMask, IoctlCmd};
+use messaging::MessageTo;
+
+/// Size of a struct inotify_event without its name. The names are
+/// padded to a multiple of it, so that the next event is aligned
+const EVENT_SIZE: usize = 16;
+
+/// The events an instance keeps until they are read, the next ones are
+/// lost and replaced by an IN_Q_OVERFLOW event
+const MAX_QUEUED_EVENTS: usize = 1024;
+
+/// The watches an instance may have
+const MAX_WATCHES: usize = 8192;
+
+#[derive(Debug)]
+struct Event {
+    /// The watch descriptor, -1 for IN_Q_OVERFLOW
+    wd: i32,
+    mask: InotifyMask,
+    /// Shared by the IN_MOVED_FROM and the IN_MOVED_TO of a rename
+    cookie: u32,
+    /// The name of the file in a watched directory
+    name: Option<Filename>,
+}
+
+impl Event {
+    /// The size of the struct inotify_event and its padded name
+    fn len(&self) -> usize {
+        EVENT_SIZE + self.name_len()
+    }
+
+    fn name_len(&self) -> usize {
+        match &self.name {
+            Some(name) => (name.len() + 1 + EVENT_SIZE - 1) & !(EVENT_SIZE - 1),
+            None => 0,
+        }
+    }
+
+    /// Write the struct inotify_event at the start of `buf`, which
+    /// must hold self.len() bytes
+    fn write(&self, buf: &mut [u8]) {
+        let record = &mut buf[..self.len()];
+        record[0..4].copy_from_slice(&self.wd.to_ne_bytes());
+        record[4..8].copy_from_slice(&self.mask.bits().to_ne_bytes());
+        record[8..12].copy_from_slice(&self.cookie.to_ne_bytes());
+        record[12..16].copy_from_slice(&(self.name_len() as u32).to_ne_bytes());
+        // The \0 and the padding
+        for b in record[EVENT_SIZE..].iter_mut() {
+            *b = 0;
+        }
+        if let Some(name) = &self.name {
+            record[EVENT_SIZE..EVENT_SIZE + name.len()].copy_from_slice(name.as_str().as_bytes());
+        }
+    }
+
+    /// Two identical events in a row are merged
+    fn same_as(&self, other: &Event) -> bool {
+        self.wd == other.wd
+            && self.mask == other.mask
+            && self.cookie == other.cookie
+            && self.name == other.name
+    }
+}
+
+#[derive(Debug)]
+struct Watch {
+    instance: usize,
+    wd: i32,
+    mask: InotifyMask,
+}
+
+#[derive(Debug)]
+struct Instance {
+    /// Announced to the readers when an event is queued
+    uid: usize,
+    events: VecDeque<Event>,
+    next_wd: i32,
+    nbr_watches: usize,
+}
+
+impl Instance {
+    fn new(uid: usize) -> Self {
+        Self {
+            uid,
+            events: VecDeque::new(),
+            next_wd: 1,
+            nbr_watches: 0,
+        }
+    }
+
+    /// Queue `event` for the readers. When the queue is full, the
+    /// event is lost and the readers get an IN_Q_OVERFLOW event instead
+    fn queue(&mut self, event: Event) {
+        if let Some(last) = self.events.back() {
+            if last.same_as(&event) || last.mask == InotifyMask::IN_Q_OVERFLOW {
+                return;
+            }
+        }
+        let event = if self.events.len() < MAX_QUEUED_EVENTS {
+            event
+        } else {
+            Event {
+                wd: -1,
+                mask: InotifyMask::IN_Q_OVERFLOW,
+                cookie: 0,
+                name: None,
+            }
+        };
+        if self.events.try_reserve(1).is_err() {
+            return;
+        }
+        self.events.push_back(event);
+        unsafe {
+            messaging::send_message(MessageTo::Reader {
+                uid_file_op: self.uid,
+            });
+        }
+    }
+
+    /// Tell the readers that the watch `wd` is removed
+    fn queue_ignored(&mut self, wd: i32) {
+        self.queue(Event {
+            wd,
+            mask: InotifyMask::IN_IGNORED,
+            cookie: 0,
+            name: None,
+        });
+    }
+}
+
+/// All the inotify instances and their watches
+#[derive(Debug)]
+pub struct Inotify {
+    instances: BTreeMap<usize, Instance>,
+    /// The watches of the watched inodes, at most one per instance
+    watches: BTreeMap<InodeId, Vec<Watch>>,
+    next_cookie: u32,
+}
+
+impl Inotify {
+    pub fn new() -> Self {
+        Self {
+            instances: BTreeMap::new(),
+            watches: BTreeMap::new(),
+            next_cookie: 0,
+        }
+    }
+
+    /// Create an inotify instance, return its identifier, which is
+    /// also the uid its readers wait on
+    pub fn new_instance(&mut self) -> SysResult<usize> {
+        let uid = get_file_op_uid();
+        self.instances.try_insert(uid, Instance::new(uid))?;
+        Ok(uid)
+    }
+
+    /// Remove the instance `instance` with all its watches
+    pub fn remove_instance(&mut self, instance: usize) {
+        self.instances.remove(&instance);
+        for watches in self.watches.values_mut() {
+            watches.retain(|w| w.instance != instance);
+        }
+        self.remove_unwatched();
+    }
+
+    /// Is any inode watched, the VFS spares the search of the parent
+    /// directories of a changed file when there are no watches
+    pub fn is_watching(&self) -> bool {
+        !self.watches.is_empty()
+    }
+
+    /// Watch the events `mask` on `inode_id` for `instance`, return the
+    /// watch descriptor. The watch of the inode by the instance has its
+    /// mask replaced, or extended with IN_MASK_ADD
+    pub fn add_watch(
+        &mut self,
+        instance: usize,
+        inode_id: InodeId,
+        mask: InotifyMask,
+    ) -> SysResult<i32> {
+        let events = mask & (InotifyMask::ALL_EVENTS | InotifyMask::IN_ONESHOT);
+        let inst = self.instances.get_mut(&instance).ok_or(Errno::EINVAL)?;
+
+        if let Some(watches) = self.watches.get_mut(&inode_id) {
+            if let Some(watch) = watches.iter_mut().find(|w| w.instance == instance) {
+                if mask.contains(InotifyMask::IN_MASK_ADD) {
+                    watch.mask |= events;
+                } else {
+                    watch.mask = events;
+                }
+                return Ok(watch.wd);
+            }
+        }
+        if inst.nbr_watches >= MAX_WATCHES {
+            return Err(Errno::ENOSPC);
+        }
+        if !self.watches.contains_key(&inode_id) {
+            self.watches.try_insert(inode_id, Vec::new())?;
+        }
+        let watches = self.watches.get_mut(&inode_id).expect("no watches");
+        let wd = inst.next_wd;
+        watches.try_push(Watch {
+            instance,
+            wd,
+            mask: events,
+        })?;
+        inst.next_wd += 1;
+        inst.nbr_watches += 1;
+        Ok(wd)
+    }
+
+    /// Remove the watch `wd` of `instance`, which gets an IN_IGNORED
+    /// event
+    pub fn rm_watch(&mut self, instance: usize, wd: i32) -> SysResult<()> {
+        let inst = self.instances.get_mut(&instance).ok_or(Errno::EINVAL)?;
+        let watches = self
+            .watches
+            .values_mut()
+            .find(|watches| watches.iter().any(|w| w.instance == instance && w.wd == wd))
+            .ok_or(Errno::EINVAL)?;
+
+        watches.retain(|w| w.instance != instance || w.wd != wd);
+        inst.nbr_watches -= 1;
+        inst.queue_ignored(wd);
+        self.remove_unwatched();
+        Ok(())
+    }
+
+    /// Get a cookie to link the two events of a rename
+    pub fn new_cookie(&mut self) -> u32 {
+        self.next_cookie = self.next_cookie.wrapping_add(1);
+        self.next_cookie
+    }
+
+    /// Queue the events `mask` of the watched inode `inode_id`, or of
+    /// its file `name` if it is a directory, for the instances which
+    /// watch them. The IN_ONESHOT watches are removed after that
+    pub fn notify(
+        &mut self,
+        inode_id: InodeId,
+        mask: InotifyMask,
+        cookie: u32,
+        name: Option<&Filename>,
+    ) {
+        let watches = match self.watches.get_mut(&inode_id) {
+            Some(watches) => watches,
+            None => return,
+        };
+        let instances = &mut self.instances;
+        watches.retain(|watch| {
+            let events = mask & watch.mask & InotifyMask::ALL_EVENTS;
+            if events.is_empty() {
+                return true;
+            }
+            let inst = instances.get_mut(&watch.instance).expect("no instance");
+            inst.queue(Event {
+                wd: watch.wd,
+                mask: events | (mask & InotifyMask::IN_ISDIR),
+                cookie,
+                name: name.copied(),
+            });
+            if watch.mask.contains(InotifyMask::IN_ONESHOT) {
+                inst.nbr_watches -= 1;
+                inst.queue_ignored(watch.wd);
+                false
+            } else {
+                true
+            }
+        });
+        if watches.is_empty() {
+            self.watches.remove(&inode_id);
+        }
+    }
+
+    /// Remove the watches of `inode_id`, which is destroyed or whose
+    /// filesystem is unmounted: `reason` is IN_DELETE_SELF or
+    /// IN_UNMOUNT, and is followed by IN_IGNORED
+    pub fn remove_inode(&mut self, inode_id: InodeId, reason: InotifyMask) {
+        let watches = match self.watches.remove(&inode_id) {
+            Some(watches) => watches,
+            None => return,
+        };
+        for watch in watches {
+            let inst = self
+                .instances
+                .get_mut(&watch.instance)
+                .expect("no instance");
+            if reason == InotifyMask::IN_UNMOUNT || watch.mask.intersects(reason) {
+                inst.queue(Event {
+                    wd: watch.wd,
+                    mask: reason,
+                    cookie: 0,
+                    name: None,
+                });
+            }
+            inst.nbr_watches -= 1;
+            inst.queue_ignored(watch.wd);
+        }
+    }
+
+    /// Read as many whole events of `instance` as `buf` can hold. The
+    /// reader waits for an event when there is none
+    pub fn read(&mut self, instance: usize, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        let inst = self.instances.get_mut(&instance).ok_or(Errno::EINVAL)?;
+        let first_len = match inst.events.front() {
+            Some(event) => event.len(),
+            None => return Ok(IpcResult::Wait(0, inst.uid)),
+        };
+        if first_len > buf.len() {
+            return Err(Errno::EINVAL);
+        }
+        let mut written = 0;
+        while let Some(event) = inst.events.front() {
+            let len = event.len();
+            if written + len > buf.len() {
+                break;
+            }
+            event.write(&mut buf[written..]);
+            written += len;
+            inst.events.pop_front();
+        }
+        Ok(IpcResult::Done(written as u32))
+    }
+
+    /// The size of the events of `instance` waiting to be read
+    pub fn pending_len(&self, instance: usize) -> SysResult<usize> {
+        let inst = self.instances.get(&instance).ok_or(Errno::EINVAL)?;
+        Ok(inst.events.iter().map(|event| event.len()).sum())
+    }
+
+    /// Forget the inodes left without watches
+    fn remove_unwatched(&mut self) {
+        while let Some(inode_id) = self
+            .watches
+            .iter()
+            .find(|(_, watches)| watches.is_empty())
+            .map(|(inode_id, _)| *inode_id)
+        {
+            self.watches.remove(&inode_id);
+        }
+    }
+}
+
+/// The file operation of an inotify instance, whose events are read
+/// as struct inotify_event
+#[derive(Debug)]
+pub struct InotifyFileOperation {
+    instance: usize,
+}
+
+impl InotifyFileOperation {
+    pub fn new() -> SysResult<Self> {
+        Ok(Self {
+            instance: VFS.lock().inotify_new_instance()?,
+        })
+    }
+}
+
+impl FileOperation for InotifyFileOperation {
+    fn read(&mut self, buf: &mut [u8]) -> SysResult<IpcResult<u32>> {
+        VFS.lock().inotify_read(self.instance, buf)
+    }
+
+    fn ioctl(&mut self, scheduler: &Scheduler, cmd: IoctlCmd, arg: u32) -> SysResult<u32> {
+        match cmd {
+            // Get the number of bytes which can be read
+            IoctlCmd::FIONREAD => {
+                let len = VFS.lock().inotify_pending_len(self.instance)?;
+                let count = {
+                    let v = scheduler
+                        .current_thread()
+                        .unwrap_process()
+                        .get_virtual_allocator();
+
+                    v.make_checked_ref_mut(arg as *mut i32)
+                }?;
+                *count = len as i32;
+                Ok(0)
+            }
+            _ => Err(Errno::EINVAL),
+        }
+    }
+
+    fn add_watch(
+        &mut self,
+        cwd: &Cwd,
+        creds: &Credentials,
+        path: Path,
+        mask: InotifyMask,
+    ) -> SysResult<u32> {
+        Ok(VFS
+            .lock()
+            .inotify_add_watch(cwd, creds, self.instance, path, mask)? as u32)
+    }
+
+    fn rm_watch(&mut self, wd: i32) -> SysResult<u32> {
+        VFS.lock().inotify_rm_watch(self.instance, wd)?;
+        Ok(0)
+    }
+}
+
+impl Drop for InotifyFileOperation {
+    fn drop(&mut self) {
+        VFS.lock().inotify_remove_instance(self.instance);
+    }
+}
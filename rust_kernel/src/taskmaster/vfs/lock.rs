@@ -0,0 +1,319 @@
+//! Advisory file locks: the whole file locks of flock() and the
+//! record locks of fcntl(), kept in a lock table per inode
+
+use super::InodeId;
+use super::SysResult;
+
+use crate::taskmaster::drivers::{get_file_op_uid, FileOperation};
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::{max, min};
+use fallible_collections::btree::BTreeMap;
+use libc_binding::{Errno, Pid};
+use messaging::MessageTo;
+use sync::DeadMutex;
+
+/// The last byte of a lock which extends to the end of the file,
+/// whatever its size
+pub const OFFSET_MAX: u64 = core::i64::MAX as u64;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LockType {
+    /// Shared or read lock
+    Shared,
+    /// Exclusive or write lock
+    Exclusive,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LockOwner {
+    /// A record lock belongs to a process, and is lost when the
+    /// process closes any file descriptor of the file
+    Process(Pid),
+    /// A flock() lock belongs to an open file description, and is
+    /// lost when its last file descriptor is closed
+    OpenFile(usize),
+}
+
+impl LockOwner {
+    /// The owner of the flock() locks of an open file description,
+    /// which is identified by its address
+    pub fn open_file(file_operation: &Arc<DeadMutex<dyn FileOperation>>) -> Self {
+        LockOwner::OpenFile(Arc::as_ptr(file_operation) as *const u8 as usize)
+    }
+
+    /// The flock() locks and the record locks ignore each other
+    fn same_kind(&self, other: &LockOwner) -> bool {
+        match (self, other) {
+            (LockOwner::Process(_), LockOwner::Process(_)) => true,
+            (LockOwner::OpenFile(_), LockOwner::OpenFile(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct FileLock {
+    pub owner: LockOwner,
+    pub lock_type: LockType,
+    /// The first locked byte
+    pub start: u64,
+    /// The last locked byte, OFFSET_MAX until the end of the file
+    pub end: u64,
+    /// The process which set the lock
+    pub pid: Pid,
+}
+
+impl FileLock {
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start <= end && start <= self.end
+    }
+
+    /// Two locks of different owners conflict on their common bytes
+    /// if one of them is exclusive
+    fn conflicts_with(&self, other: &FileLock) -> bool {
+        self.owner.same_kind(&other.owner)
+            && self.owner != other.owner
+            && self.overlaps(other.start, other.end)
+            && (self.lock_type == LockType::Exclusive || other.lock_type == LockType::Exclusive)
+    }
+
+    /// Is the lock adjacent to `other` and mergeable with it
+    fn extends(&self, other: &FileLock) -> bool {
+        self.owner == other.owner
+            && self.lock_type == other.lock_type
+            && (self.end.checked_add(1) == Some(other.start)
+                || other.end.checked_add(1) == Some(self.start))
+    }
+}
+
+/// The result of a lock request
+#[derive(Debug, Copy, Clone)]
+pub enum LockResult {
+    /// The lock is set
+    Done,
+    /// The lock conflicts with `blocker`. The thread may wait on the
+    /// `uid` of the lock table, which is announced at each unlock
+    Blocked { uid: usize, blocker: FileLock },
+}
+
+/// The locks of an inode
+#[derive(Debug)]
+struct LockTable {
+    uid: usize,
+    locks: Vec<FileLock>,
+}
+
+impl LockTable {
+    fn new() -> Self {
+        Self {
+            uid: get_file_op_uid(),
+            locks: Vec::new(),
+        }
+    }
+
+    fn get_conflict(&self, lock: &FileLock) -> Option<&FileLock> {
+        self.locks.iter().find(|l| l.conflicts_with(lock))
+    }
+
+    /// Remove the bytes from `start` to `end` of the locks of
+    /// `owner`. One lock may be split in two, so the table must have
+    /// room for one more lock
+    fn remove_range(&mut self, owner: LockOwner, start: u64, end: u64) -> bool {
+        let mut removed = false;
+        let mut i = 0;
+        while i < self.locks.len() {
+            let lock = self.locks[i];
+            if lock.owner != owner || !lock.overlaps(start, end) {
+                i += 1;
+                continue;
+            }
+            removed = true;
+            if lock.start < start && lock.end > end {
+                self.locks[i].end = start - 1;
+                self.locks.push(FileLock {
+                    start: end + 1,
+                    ..lock
+                });
+                i += 1;
+            } else if lock.start < start {
+                self.locks[i].end = start - 1;
+                i += 1;
+            } else if lock.end > end {
+                self.locks[i].start = end + 1;
+                i += 1;
+            } else {
+                self.locks.remove(i);
+            }
+        }
+        removed
+    }
+
+    /// Announce to the waiting threads that some locks were released
+    fn wake_waiters(&self) {
+        unsafe {
+            messaging::send_message(MessageTo::Locker { uid: self.uid });
+        }
+    }
+}
+
+/// All the advisory locks of the system
+#[derive(Debug)]
+pub struct FileLocks {
+    tables: BTreeMap<InodeId, LockTable>,
+    /// The process owning the record lock each sleeping process is
+    /// waiting for, to detect the deadlocks
+    blocked_on: BTreeMap<Pid, Pid>,
+}
+
+impl FileLocks {
+    pub fn new() -> Self {
+        Self {
+            tables: BTreeMap::new(),
+            blocked_on: BTreeMap::new(),
+        }
+    }
+
+    /// Get the first lock which would prevent `lock` from being set
+    pub fn get_conflict(&self, inode_id: InodeId, lock: &FileLock) -> Option<FileLock> {
+        self.tables
+            .get(&inode_id)?
+            .get_conflict(lock)
+            .map(|blocker| *blocker)
+    }
+
+    /// Set `lock` on the file, replacing the previous locks of its
+    /// owner on the same bytes
+    pub fn set_lock(&mut self, inode_id: InodeId, lock: FileLock) -> SysResult<LockResult> {
+        if !self.tables.contains_key(&inode_id) {
+            self.tables.try_insert(inode_id, LockTable::new())?;
+        }
+        let table = self.tables.get_mut(&inode_id).expect("no lock table");
+
+        if let Some(blocker) = table.get_conflict(&lock) {
+            return Ok(LockResult::Blocked {
+                uid: table.uid,
+                blocker: *blocker,
+            });
+        }
+        // Room for a split lock and the new one
+        table.locks.try_reserve(2)?;
+        let converted = table.remove_range(lock.owner, lock.start, lock.end);
+
+        let mut lock = lock;
+        table.locks.retain(|l| {
+            if l.extends(&lock) {
+                lock.start = min(lock.start, l.start);
+                lock.end = max(lock.end, l.end);
+                false
+            } else {
+                true
+            }
+        });
+        table.locks.push(lock);
+
+        // An exclusive lock may have become shared
+        if converted {
+            table.wake_waiters();
+        }
+        Ok(LockResult::Done)
+    }
+
+    /// Remove the locks of `owner` from `start` to `end`
+    pub fn unlock(
+        &mut self,
+        inode_id: InodeId,
+        owner: LockOwner,
+        start: u64,
+        end: u64,
+    ) -> SysResult<()> {
+        let table = match self.tables.get_mut(&inode_id) {
+            Some(table) => table,
+            None => return Ok(()),
+        };
+        table.locks.try_reserve(1)?;
+        if table.remove_range(owner, start, end) {
+            table.wake_waiters();
+        }
+        if table.locks.is_empty() {
+            self.tables.remove(&inode_id);
+        }
+        Ok(())
+    }
+
+    /// Remove all the locks of `owner` on the file. Unlocking a whole
+    /// lock never splits it
+    pub fn release(&mut self, inode_id: InodeId, owner: LockOwner) {
+        let table = match self.tables.get_mut(&inode_id) {
+            Some(table) => table,
+            None => return,
+        };
+        let len = table.locks.len();
+        table.locks.retain(|l| l.owner != owner);
+        if table.locks.len() != len {
+            table.wake_waiters();
+        }
+        if table.locks.is_empty() {
+            self.tables.remove(&inode_id);
+        }
+    }
+
+    /// Remove all the record locks of the exiting process `pid`
+    pub fn release_process(&mut self, pid: Pid) {
+        let owner = LockOwner::Process(pid);
+        for table in self.tables.values_mut() {
+            let len = table.locks.len();
+            table.locks.retain(|l| l.owner != owner);
+            if table.locks.len() != len {
+                table.wake_waiters();
+            }
+        }
+        while let Some(inode_id) = self
+            .tables
+            .iter()
+            .find(|(_, table)| table.locks.is_empty())
+            .map(|(inode_id, _)| *inode_id)
+        {
+            self.tables.remove(&inode_id);
+        }
+        self.blocked_on.remove(&pid);
+    }
+
+    /// Register that `pid` is going to sleep until `blocker` is
+    /// released, unless the owner of `blocker` is itself waiting,
+    /// directly or not, for a lock of `pid`
+    pub fn wait_for(&mut self, pid: Pid, blocker: &FileLock) -> SysResult<()> {
+        let blocker_pid = match blocker.owner {
+            LockOwner::Process(blocker_pid) => blocker_pid,
+            // The flock() locks are not checked for deadlocks
+            LockOwner::OpenFile(_) => return Ok(()),
+        };
+        let mut owner = blocker_pid;
+        // Each sleeping process waits for one process only, so the
+        // chain is at most as long as the number of sleeping processes
+        for _ in 0..=self.blocked_on.len() {
+            if owner == pid {
+                return Err(Errno::EDEADLK);
+            }
+            match self.blocked_on.get(&owner) {
+                Some(&next) => owner = next,
+                None => break,
+            }
+        }
+        self.blocked_on.try_insert(pid, blocker_pid)?;
+        Ok(())
+    }
+
+    /// `pid` is not waiting for a lock anymore
+    pub fn stop_waiting(&mut self, pid: Pid) {
+        self.blocked_on.remove(&pid);
+    }
+
+    /// Iter on all the locks with their inode
+    pub fn iter(&self) -> impl Iterator<Item = (&InodeId, &FileLock)> {
+        self.tables
+            .iter()
+            .flat_map(|(inode_id, table)| table.locks.iter().map(move |lock| (inode_id, lock)))
+    }
+}